@@ -0,0 +1,79 @@
+//! Traversal and deletion throughput on generated caches
+//!
+//! Run with `cargo bench`; `CLEARMODEL_BENCH_DIR` puts the generated caches on the
+//! storage to measure instead of the temp directory.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tempfile::TempDir;
+
+use clearmodel::bench;
+use clearmodel::config::ClearModelConfig;
+use clearmodel::policy::CleanupPolicy;
+use clearmodel::resource_manager::ResourceManager;
+use clearmodel::walk::{ParallelWalker, WalkOptions};
+
+const WALK_FILES: u64 = 20_000;
+const DELETE_FILES: u64 = 2_000;
+const AGE: Duration = Duration::from_secs(60 * 86_400);
+
+fn bench_dir() -> TempDir {
+    match std::env::var_os("CLEARMODEL_BENCH_DIR") {
+        Some(dir) => TempDir::new_in(dir).unwrap(),
+        None => TempDir::new().unwrap(),
+    }
+}
+
+fn walk(c: &mut Criterion) {
+    let temp_dir = bench_dir();
+    bench::generate(temp_dir.path(), WALK_FILES, AGE).unwrap();
+    let options = WalkOptions { max_depth: 16, follow_links: false, root_device: None };
+    
+    let mut group = c.benchmark_group("walk");
+    group.throughput(Throughput::Elements(WALK_FILES));
+    for threads in [1, 4, 16] {
+        let walker = ParallelWalker::new(threads).unwrap();
+        group.bench_function(format!("{}_threads", threads), |b| b.iter(|| {
+            let files = AtomicU64::new(0);
+            walker.walk(temp_dir.path(), options, false, |_, _| true, |_| {
+                files.fetch_add(1, Ordering::Relaxed);
+                true
+            });
+            assert_eq!(files.into_inner(), WALK_FILES);
+        }));
+    }
+    group.finish();
+}
+
+fn delete(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let temp_dir = bench_dir();
+    let mut config = ClearModelConfig::default();
+    config.security.allowed_roots = vec![temp_dir.path().to_path_buf()];
+    let policy = CleanupPolicy::from_config(&config);
+    let manager = runtime.block_on(ResourceManager::new(config)).unwrap();
+    let generated = AtomicU64::new(0);
+    let fresh_cache = || -> PathBuf {
+        let root = temp_dir.path().join(format!("cache-{}", generated.fetch_add(1, Ordering::Relaxed)));
+        bench::generate(&root, DELETE_FILES, AGE).unwrap();
+        root
+    };
+    let plan_and_apply = |root: PathBuf| {
+        runtime.block_on(async {
+            let plan = manager.plan_paths(&[root], &policy).await.unwrap();
+            assert_eq!(plan.file_count(), DELETE_FILES);
+            manager.apply(plan).await.unwrap();
+        })
+    };
+    
+    let mut group = c.benchmark_group("delete");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(DELETE_FILES));
+    group.bench_function("plan_and_apply", |b| b.iter_batched(fresh_cache, plan_and_apply, BatchSize::PerIteration));
+    group.finish();
+}
+
+criterion_group!(benches, walk, delete);
+criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,257 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::Metadata;
+use std::path::Path;
+
+use crate::config::ClearModelConfig;
+
+/// How file sizes are counted in scan reports, health scores, model listings and cache budgets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeBasis {
+    /// Space the files take up on disk: compressed and sparse files count for the blocks
+    /// they have allocated, and hardlinks and blocks shared by reflinked clones count once
+    #[default]
+    Allocated,
+    
+    /// Logical file sizes, as `ls -l` and `du --apparent-size` show them
+    Apparent,
+}
+
+/// Size of one file on its own; links and clones it shares blocks with are not looked at
+pub fn size_of(metadata: &Metadata, basis: SizeBasis) -> u64 {
+    match basis {
+        SizeBasis::Apparent => metadata.len(),
+        SizeBasis::Allocated => allocated(metadata),
+    }
+}
+
+/// Bytes of the blocks allocated to a file (`st_blocks` is in 512-byte units)
+#[cfg(unix)]
+fn allocated(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
+
+/// Adds up the disk usage of many files, counting blocks they share once
+///
+/// Under the allocated basis a file with several links counts once, and on filesystems
+/// with reflinks (btrfs, XFS, bcachefs) extents the kernel reports as shared count once
+/// across every file added. Finding shared extents opens each file, so it is skipped when
+/// `usage.track` is on: the tracker would record those opens as uses. On ZFS and APFS,
+/// where the kernel does not report sharing, clones count for every copy.
+#[derive(Debug)]
+pub struct UsageCounter {
+    basis: SizeBasis,
+    clones: bool,
+    total: u64,
+    
+    /// `(device, inode)` of files with more than one link, counted already
+    inodes: HashSet<(u64, u64)>,
+    
+    /// `(device, physical offset)` of shared extents, counted already
+    extents: HashSet<(u64, u64)>,
+    
+    /// Whether the filesystem on a device has reflinks, by device
+    reflinks: HashMap<u64, bool>,
+}
+
+impl UsageCounter {
+    /// A counter under `basis`, looking for blocks shared by clones when `clones` is set
+    pub fn new(basis: SizeBasis, clones: bool) -> Self {
+        Self {
+            basis,
+            clones,
+            total: 0,
+            inodes: HashSet::new(),
+            extents: HashSet::new(),
+            reflinks: HashMap::new(),
+        }
+    }
+    
+    /// A counter with the configured `size_basis`
+    pub fn for_config(config: &ClearModelConfig) -> Self {
+        Self::new(config.size_basis, !config.usage.track)
+    }
+    
+    /// Count a regular file, returning the bytes it added
+    pub fn add(&mut self, path: &Path, metadata: &Metadata) -> u64 {
+        let size = match self.basis {
+            SizeBasis::Apparent => metadata.len(),
+            SizeBasis::Allocated => self.unshared(path, metadata),
+        };
+        self.total += size;
+        size
+    }
+    
+    /// Bytes counted so far
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+    
+    #[cfg(unix)]
+    fn unshared(&mut self, path: &Path, metadata: &Metadata) -> u64 {
+        use std::os::unix::fs::MetadataExt;
+        
+        if metadata.nlink() > 1 && !self.inodes.insert((metadata.dev(), metadata.ino())) {
+            return 0;
+        }
+        let mut size = allocated(metadata);
+        if self.clones && *self.reflinks.entry(metadata.dev()).or_insert_with(|| imp::has_reflinks(path)) {
+            for (physical, length) in imp::shared_extents(path) {
+                if !self.extents.insert((metadata.dev(), physical)) {
+                    size = size.saturating_sub(length);
+                }
+            }
+        }
+        size
+    }
+    
+    #[cfg(not(unix))]
+    fn unshared(&mut self, _path: &Path, metadata: &Metadata) -> u64 {
+        allocated(metadata)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use tracing::debug;
+    
+    /// `_IOWR('f', 11, struct fiemap)`
+    const FS_IOC_FIEMAP: u32 = 0xC020_660B;
+    const FIEMAP_EXTENT_LAST: u32 = 0x1;
+    const FIEMAP_EXTENT_SHARED: u32 = 0x2000;
+    
+    /// Extents asked for per call
+    const EXTENTS: usize = 64;
+    
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct Extent {
+        logical: u64,
+        physical: u64,
+        length: u64,
+        reserved64: [u64; 2],
+        flags: u32,
+        reserved: [u32; 3],
+    }
+    
+    /// `struct fiemap` with room for `EXTENTS` extents
+    #[repr(C)]
+    struct Request {
+        start: u64,
+        length: u64,
+        flags: u32,
+        mapped_extents: u32,
+        extent_count: u32,
+        reserved: u32,
+        extents: [Extent; EXTENTS],
+    }
+    
+    /// Whether files on the filesystem holding `path` can share extents
+    pub fn has_reflinks(path: &Path) -> bool {
+        let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is NUL-terminated and `stat` is a valid buffer for the call to fill
+        if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+            return false;
+        }
+        // btrfs, XFS and bcachefs; every magic number fits in 32 bits
+        matches!(stat.f_type as u32, 0x9123_683E | 0x5846_5342 | 0xCA45_1A4E)
+    }
+    
+    /// `(physical offset, length)` of the extents of `path` that other files share
+    pub fn shared_extents(path: &Path) -> Vec<(u64, u64)> {
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) => {
+                debug!("Cannot map the extents of {:?}: {}", path, e);
+                return Vec::new();
+            }
+        };
+        
+        let mut shared = Vec::new();
+        let mut start = 0;
+        loop {
+            let mut request = Request {
+                start,
+                length: u64::MAX,
+                flags: 0,
+                mapped_extents: 0,
+                extent_count: EXTENTS as u32,
+                reserved: 0,
+                extents: [Extent::default(); EXTENTS],
+            };
+            // SAFETY: `request` is a `struct fiemap` followed by room for `extent_count` extents
+            if unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as _, &mut request) } != 0 {
+                debug!("Cannot map the extents of {:?}: {}", path, std::io::Error::last_os_error());
+                return shared;
+            }
+            
+            let mapped = &request.extents[..(request.mapped_extents as usize).min(EXTENTS)];
+            shared.extend(mapped.iter()
+                .filter(|extent| extent.flags & FIEMAP_EXTENT_SHARED != 0)
+                .map(|extent| (extent.physical, extent.length)));
+            match mapped.last() {
+                Some(last) if last.flags & FIEMAP_EXTENT_LAST == 0 => start = last.logical + last.length,
+                _ => return shared,
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+mod imp {
+    use std::path::Path;
+    
+    /// Sharing is only reported by Linux filesystems
+    pub fn has_reflinks(_path: &Path) -> bool {
+        false
+    }
+    
+    pub fn shared_extents(_path: &Path) -> Vec<(u64, u64)> {
+        Vec::new()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_hardlinks_and_sparse_files_count_for_their_blocks() {
+        let temp_dir = TempDir::new().unwrap();
+        let blob = temp_dir.path().join("blob");
+        fs::write(&blob, vec![1u8; 64 * 1024]).unwrap();
+        fs::hard_link(&blob, temp_dir.path().join("link")).unwrap();
+        let sparse = temp_dir.path().join("sparse");
+        fs::File::create(&sparse).unwrap().set_len(1 << 30).unwrap();
+        
+        let count = |basis| {
+            let mut counter = UsageCounter::new(basis, true);
+            for name in ["blob", "link", "sparse"] {
+                let path = temp_dir.path().join(name);
+                counter.add(&path, &fs::metadata(&path).unwrap());
+            }
+            counter.total()
+        };
+        
+        assert_eq!(count(SizeBasis::Apparent), 2 * 64 * 1024 + (1 << 30));
+        let allocated = count(SizeBasis::Allocated);
+        // The link counts once and the sparse file for the little it has allocated
+        assert!(allocated > 0 && allocated < 1 << 20, "allocated {}", allocated);
+    }
+}
\ No newline at end of file
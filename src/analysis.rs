@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+use crate::allocation::{self, SizeBasis, UsageCounter};
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::policy::CleanupPolicy;
+use crate::resource_manager::ResourceManager;
+use crate::targets;
+
+/// Usage of one managed cache on the analysed filesystem
+#[derive(Debug, Clone)]
+pub struct CacheUsage {
+    pub path: PathBuf,
+    pub bytes: u64,
+    
+    /// Bytes the current policy would remove
+    pub reclaimable: u64,
+}
+
+/// Where the space on a filesystem went
+#[derive(Debug, Clone)]
+pub struct VolumeReport {
+    pub path: PathBuf,
+    pub mount_point: PathBuf,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    
+    /// Managed caches living on this filesystem
+    pub caches: Vec<CacheUsage>,
+    
+    /// Largest directories outside the caches, at the requested depth below `path`
+    pub top_dirs: Vec<(PathBuf, u64)>,
+    
+    /// Bytes found under `path` outside the caches
+    pub other_bytes: u64,
+    
+    /// Entries that could not be read during the scan
+    pub unreadable: u64,
+}
+
+impl VolumeReport {
+    pub fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.available_bytes)
+    }
+    
+    pub fn cache_bytes(&self) -> u64 {
+        self.caches.iter().map(|cache| cache.bytes).sum()
+    }
+    
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.caches.iter().map(|cache| cache.reclaimable).sum()
+    }
+}
+
+/// Explain why the filesystem holding `path` is full, without changing anything
+///
+/// `depth` is how many levels below `path` directories are grouped at, and `top`
+/// how many of the largest are reported.
+pub async fn why_full(path: &Path, config: &ClearModelConfig, depth: usize, top: usize) -> Result<VolumeReport> {
+    let path = path.canonicalize()
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Cannot analyse path: {}", e),
+            Some(path.to_path_buf())
+        ))?;
+    
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks.list().iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+        .ok_or_else(|| ClearModelError::environment(format!(
+            "No mounted filesystem found for {:?}",
+            path
+        )))?;
+    
+    // Only caches on the same filesystem contribute to its usage
+    let cache_roots: Vec<PathBuf> = managed_cache_roots(config)
+        .into_iter()
+        .filter(|root| same_filesystem(root, &path))
+        .collect();
+    
+    info!("Analysing {:?} on {:?} ({} managed caches)", path, disk.mount_point(), cache_roots.len());
+    let resource_manager = ResourceManager::new(config.clone()).await?;
+    let mut caches = Vec::new();
+    for root in &cache_roots {
+        let policy = if is_package_cache(root) {
+            CleanupPolicy::for_package_caches(config)
+        } else {
+            CleanupPolicy::from_config(config)
+        };
+        let reclaimable = resource_manager.clean_paths(std::slice::from_ref(root), &policy, true).await?
+            .iter()
+            .map(|result| result.bytes_freed)
+            .sum();
+        
+        caches.push(CacheUsage {
+            path: root.clone(),
+            bytes: tree_size(root, UsageCounter::for_config(config)),
+            reclaimable,
+        });
+    }
+    caches.sort_by_key(|cache| std::cmp::Reverse(cache.bytes));
+    
+    let scan_root = path.clone();
+    let basis = config.size_basis;
+    let (top_dirs, other_bytes, unreadable) = tokio::task::spawn_blocking(move || {
+        largest_directories(&scan_root, &cache_roots, depth, top, basis)
+    })
+    .await
+    .map_err(|e| ClearModelError::cache(format!("Scan task failed: {}", e)))?;
+    
+    Ok(VolumeReport {
+        path,
+        mount_point: disk.mount_point().to_path_buf(),
+        total_bytes: disk.total_space(),
+        available_bytes: disk.available_space(),
+        caches,
+        top_dirs,
+        other_bytes,
+        unreadable,
+    })
+}
+
+/// Configured cache directories plus the package manager caches
+fn managed_cache_roots(config: &ClearModelConfig) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = config.existing_cache_paths().into_iter().cloned().collect();
+    if config.package_caches.enabled {
+        roots.extend(
+            targets::package_cache_locations()
+                .into_iter()
+                .map(|(_, path)| path)
+                .filter(|path| path.is_dir())
+        );
+    }
+    
+    let mut roots: Vec<PathBuf> = roots.into_iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+fn is_package_cache(root: &Path) -> bool {
+    targets::package_cache_locations()
+        .into_iter()
+        .any(|(_, path)| path.canonicalize().map(|path| path == root).unwrap_or(false))
+}
+
+#[cfg(unix)]
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_filesystem(a: &Path, b: &Path) -> bool {
+    a.components().next() == b.components().next()
+}
+
+fn tree_size(root: &Path, mut usage: UsageCounter) -> u64 {
+    for entry in WalkDir::new(root).same_file_system(true).into_iter().flatten() {
+        if entry.file_type().is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                usage.add(entry.path(), &metadata);
+            }
+        }
+    }
+    usage.total()
+}
+
+/// Read-only walk of `root` outside the caches, grouping file sizes by their ancestor `depth` levels down
+///
+/// Files are sized on their own, without opening them to find shared extents.
+fn largest_directories(root: &Path, cache_roots: &[PathBuf], depth: usize, top: usize, basis: SizeBasis) -> (Vec<(PathBuf, u64)>, u64, u64) {
+    let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut total = 0;
+    let mut unreadable = 0;
+    
+    let walker = WalkDir::new(root)
+        .same_file_system(true)
+        .into_iter()
+        .filter_entry(|entry| !cache_roots.iter().any(|cache| entry.path() == cache));
+    
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                debug!("Skipping unreadable entry: {}", e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        
+        let Ok(metadata) = entry.metadata() else {
+            unreadable += 1;
+            continue;
+        };
+        
+        // Files above the grouping depth are attributed to their own directory
+        let group_depth = depth.min(entry.depth().saturating_sub(1));
+        let group: PathBuf = entry.path()
+            .ancestors()
+            .nth(entry.depth() - group_depth)
+            .unwrap_or(root)
+            .to_path_buf();
+        
+        let size = allocation::size_of(&metadata, basis);
+        *sizes.entry(group).or_default() += size;
+        total += size;
+    }
+    
+    let mut sizes: Vec<(PathBuf, u64)> = sizes.into_iter().collect();
+    sizes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sizes.truncate(top);
+    (sizes, total, unreadable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_largest_directories_skips_caches() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for (file, size) in [
+            ("datasets/imagenet/train.tar", 500),
+            ("datasets/imagenet/val.tar", 100),
+            ("datasets/coco/images.zip", 300),
+            ("projects/notes.txt", 10),
+            ("readme.md", 5),
+            (".cache/huggingface/blob", 1000),
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, vec![0u8; size]).unwrap();
+        }
+        
+        let caches = vec![root.join(".cache/huggingface")];
+        let (top, total, unreadable) = largest_directories(root, &caches, 2, 2, SizeBasis::Apparent);
+        
+        assert_eq!(total, 915);
+        assert_eq!(unreadable, 0);
+        assert_eq!(top, vec![
+            (root.join("datasets/imagenet"), 600),
+            (root.join("datasets/coco"), 300),
+        ]);
+    }
+}
\ No newline at end of file
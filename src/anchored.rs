@@ -0,0 +1,287 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// A validated cache root held open, so files below it are removed through directory handles
+///
+/// Each removal opens the file's directory relative to the root without following any
+/// symlink (`openat2` with `RESOLVE_BENEATH | RESOLVE_NO_SYMLINKS` on Linux, one
+/// `O_NOFOLLOW` component at a time elsewhere), checks that the entry is still the
+/// file that was inspected, and unlinks it through that directory. A directory swapped
+/// for a symlink after the walk then makes the removal fail instead of landing outside
+/// the cache. Outside unix, removals go by path after a best-effort check, with no such
+/// guarantee.
+pub struct AnchoredRoot {
+    root: PathBuf,
+    #[cfg(unix)]
+    dir: std::os::fd::OwnedFd,
+}
+
+impl AnchoredRoot {
+    /// Open a cache root; paths handed to it later must start with `root`
+    pub fn open(root: &Path) -> io::Result<Self> {
+        let dir = std::fs::File::open(root)?;
+        if !dir.metadata()?.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} is not a directory", root)));
+        }
+        
+        Ok(Self {
+            root: root.to_path_buf(),
+            #[cfg(unix)]
+            dir: dir.into(),
+        })
+    }
+    
+    /// Path components between the root and `path`, which must all be plain names
+    fn relative<'a>(&self, path: &'a Path) -> io::Result<Vec<&'a std::ffi::OsStr>> {
+        let relative = path.strip_prefix(&self.root)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} is not below {:?}", path, self.root)))?;
+        
+        let mut names = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => names.push(name),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unexpected component in {:?}", path))),
+            }
+        }
+        if names.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Refusing to remove the cache root {:?}", path)));
+        }
+        Ok(names)
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::ffi::{CString, OsStr};
+    use std::fs::{File, Metadata};
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+    
+    use super::AnchoredRoot;
+    
+    impl AnchoredRoot {
+        /// Unlink `path` if it is still the file `expected` was read from
+        pub fn remove_file(&self, path: &Path, expected: &Metadata) -> io::Result<()> {
+            let (dir, name) = self.parent(path)?;
+            check_unchanged(&dir, &name, expected, path)?;
+            // SAFETY: `dir` is an open directory and `name` a NUL-terminated file name
+            if unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), 0) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+        
+        /// Remove the empty directory `path`
+        pub fn remove_dir(&self, path: &Path) -> io::Result<()> {
+            let (dir, name) = self.parent(path)?;
+            // SAFETY: as in `remove_file`
+            if unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), libc::AT_REMOVEDIR) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+        
+        /// Rename `path` to `destination` (e.g. into quarantine) if it is still the file `expected` was read from
+        pub fn rename_out(&self, path: &Path, expected: &Metadata, destination: &Path) -> io::Result<()> {
+            let (dir, name) = self.parent(path)?;
+            check_unchanged(&dir, &name, expected, path)?;
+            let destination = CString::new(destination.as_os_str().as_bytes())?;
+            // SAFETY: both names are NUL-terminated; AT_FDCWD resolves `destination` as a normal path
+            if unsafe { libc::renameat(dir.as_raw_fd(), name.as_ptr(), libc::AT_FDCWD, destination.as_ptr()) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+        
+        /// Open `path` for writing if it is still the file `expected` was read from (for shredding)
+        pub fn open_for_overwrite(&self, path: &Path, expected: &Metadata) -> io::Result<File> {
+            let (dir, name) = self.parent(path)?;
+            // O_NONBLOCK keeps a FIFO swapped in for the file from blocking the open
+            // SAFETY: `dir` is open and `name` is NUL-terminated
+            let fd = unsafe {
+                libc::openat(dir.as_raw_fd(), name.as_ptr(), libc::O_WRONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK | libc::O_CLOEXEC)
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: the kernel just returned this descriptor to us
+            let file = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+            
+            let opened = file.metadata()?;
+            if !opened.is_file() || opened.dev() != expected.dev() || opened.ino() != expected.ino() {
+                return Err(io::Error::other(format!("{:?} was replaced after it was checked", path)));
+            }
+            Ok(file)
+        }
+        
+        /// Directory holding `path`, opened beneath the root, and the file's name in it
+        pub fn parent(&self, path: &Path) -> io::Result<(OwnedFd, CString)> {
+            let mut names = self.relative(path)?;
+            let name = CString::new(names.pop().unwrap_or_default().as_bytes())?;
+            let dir = if names.is_empty() {
+                self.dir.try_clone()?
+            } else {
+                open_beneath(&self.dir, &names)?
+            };
+            Ok((dir, name))
+        }
+    }
+    
+    /// Refuse to touch an entry that is no longer the inspected file (e.g. now a symlink)
+    // `st_dev` and `st_ino` are narrower than u64 on some platforms
+    #[allow(clippy::unnecessary_cast)]
+    fn check_unchanged(dir: &OwnedFd, name: &CString, expected: &Metadata, path: &Path) -> io::Result<()> {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: `stat` is written by a successful call before it is read
+        if unsafe { libc::fstatat(dir.as_raw_fd(), name.as_ptr(), stat.as_mut_ptr(), libc::AT_SYMLINK_NOFOLLOW) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let stat = unsafe { stat.assume_init() };
+        
+        if stat.st_dev as u64 != expected.dev() || stat.st_ino as u64 != expected.ino() {
+            return Err(io::Error::other(format!("{:?} was replaced after it was checked", path)));
+        }
+        Ok(())
+    }
+    
+    /// Open a directory below `root` without following symlinks or leaving it
+    #[cfg(target_os = "linux")]
+    fn open_beneath(root: &OwnedFd, names: &[&OsStr]) -> io::Result<OwnedFd> {
+        let relative: std::path::PathBuf = names.iter().collect();
+        let relative = CString::new(relative.as_os_str().as_bytes())?;
+        
+        // SAFETY: open_how is plain data; zeroed is its documented default
+        let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+        how.flags = (libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC) as u64;
+        how.resolve = libc::RESOLVE_BENEATH | libc::RESOLVE_NO_SYMLINKS;
+        
+        // SAFETY: `how` and `relative` outlive the call, which returns a new descriptor or -1
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_openat2,
+                root.as_raw_fd(),
+                relative.as_ptr(),
+                &how as *const libc::open_how,
+                std::mem::size_of::<libc::open_how>(),
+            )
+        };
+        if fd >= 0 {
+            // SAFETY: the kernel just returned this descriptor to us
+            return Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) });
+        }
+        
+        let error = io::Error::last_os_error();
+        match error.raw_os_error() {
+            // Kernels before 5.6, or container seccomp profiles that block openat2
+            Some(libc::ENOSYS) | Some(libc::EPERM) => open_each(root, names),
+            _ => Err(error),
+        }
+    }
+    
+    #[cfg(not(target_os = "linux"))]
+    fn open_beneath(root: &OwnedFd, names: &[&OsStr]) -> io::Result<OwnedFd> {
+        open_each(root, names)
+    }
+    
+    /// Walk down one plain name at a time, refusing symlinks at every step
+    fn open_each(root: &OwnedFd, names: &[&OsStr]) -> io::Result<OwnedFd> {
+        let mut dir = root.try_clone()?;
+        for name in names {
+            let name = CString::new(name.as_bytes())?;
+            // SAFETY: `dir` is open and `name` is NUL-terminated
+            let fd = unsafe {
+                libc::openat(dir.as_raw_fd(), name.as_ptr(), libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC)
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // SAFETY: the kernel just returned this descriptor to us
+            dir = unsafe { OwnedFd::from_raw_fd(fd) };
+        }
+        Ok(dir)
+    }
+}
+
+#[cfg(not(unix))]
+impl AnchoredRoot {
+    /// Remove `path` if it is still the file `expected` was read from
+    pub fn remove_file(&self, path: &Path, expected: &std::fs::Metadata) -> io::Result<()> {
+        self.check_unchanged(path, expected)?;
+        std::fs::remove_file(path)
+    }
+    
+    /// Remove the empty directory `path`
+    pub fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.relative(path)?;
+        std::fs::remove_dir(path)
+    }
+    
+    /// Rename `path` to `destination` if it is still the file `expected` was read from
+    pub fn rename_out(&self, path: &Path, expected: &std::fs::Metadata, destination: &Path) -> io::Result<()> {
+        self.check_unchanged(path, expected)?;
+        std::fs::rename(path, destination)
+    }
+    
+    /// Open `path` for writing if it is still the file `expected` was read from
+    pub fn open_for_overwrite(&self, path: &Path, expected: &std::fs::Metadata) -> io::Result<std::fs::File> {
+        self.check_unchanged(path, expected)?;
+        std::fs::OpenOptions::new().write(true).open(path)
+    }
+    
+    /// Compare the entry with `expected` by type, size and timestamps
+    ///
+    /// Without directory handles or a stable file index this only catches most
+    /// replacements: a file swapped in with the same size and times, or a directory
+    /// swapped for a link between this check and the removal, goes unnoticed.
+    fn check_unchanged(&self, path: &Path, expected: &std::fs::Metadata) -> io::Result<()> {
+        self.relative(path)?;
+        let current = std::fs::symlink_metadata(path)?;
+        if current.file_type().is_symlink() != expected.file_type().is_symlink()
+            || current.len() != expected.len()
+            || current.modified().ok() != expected.modified().ok()
+            || current.created().ok() != expected.created().ok()
+        {
+            return Err(io::Error::other(format!("{:?} was replaced after it was checked", path)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_removal_refuses_directories_swapped_for_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(cache.join("models")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(cache.join("models/weights.bin"), b"x").unwrap();
+        fs::write(outside.join("weights.bin"), b"x").unwrap();
+        
+        let anchor = AnchoredRoot::open(&cache).unwrap();
+        let checked = fs::metadata(cache.join("models/weights.bin")).unwrap();
+        
+        // The directory is swapped for a link elsewhere between the check and the removal
+        fs::rename(cache.join("models"), temp_dir.path().join("moved")).unwrap();
+        std::os::unix::fs::symlink(&outside, cache.join("models")).unwrap();
+        assert!(anchor.remove_file(&cache.join("models/weights.bin"), &checked).is_err());
+        assert!(outside.join("weights.bin").exists());
+        
+        fs::remove_file(cache.join("models")).unwrap();
+        fs::rename(temp_dir.path().join("moved"), cache.join("models")).unwrap();
+        anchor.remove_file(&cache.join("models/weights.bin"), &checked).unwrap();
+        assert!(!cache.join("models/weights.bin").exists());
+        
+        assert!(anchor.remove_file(&outside.join("weights.bin"), &checked).is_err());
+        anchor.remove_dir(&cache.join("models")).unwrap();
+        assert!(!cache.join("models").exists());
+    }
+}
\ No newline at end of file
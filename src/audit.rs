@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+use crate::config::AuditConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::journal;
+use crate::state;
+
+/// Environment variable holding the MAC key as 64 hex characters, overriding `audit.key_file`
+pub const KEY_ENV: &str = "CLEARMODEL_AUDIT_KEY";
+
+/// `prev` of the first entry in a log
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Bytes read back from the end of the log to find the last entry (entries are far smaller)
+const TAIL_BYTES: u64 = 16 * 1024;
+
+/// One removal recorded to the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 1
+    pub seq: u64,
+    
+    /// Seconds since the Unix epoch when the file was removed
+    pub timestamp: u64,
+    
+    pub run_id: String,
+    
+    /// Account the cleanup ran as
+    pub user: String,
+    
+    /// Account that invoked it through sudo, if any
+    pub sudo_user: Option<String>,
+    
+    pub path: PathBuf,
+    
+    /// Size in bytes at the time of removal
+    pub size: u64,
+    
+    /// BLAKE3 hash of the contents (omitted for files above the journal's hashing limit)
+    pub hash: Option<String>,
+    
+    /// Policy rule that selected the file (`expired`, `incomplete`, `budget`, ...)
+    pub rule: String,
+    
+    /// `deleted`, `shredded` or `quarantined`
+    pub action: String,
+    
+    /// MAC of the previous entry, which chains every entry to all those before it
+    pub prev: String,
+    
+    /// Keyed BLAKE3 hash of this entry serialized with an empty `mac`
+    pub mac: String,
+}
+
+impl AuditEntry {
+    fn compute_mac(&self, key: &[u8; 32]) -> Result<String> {
+        let unsigned = AuditEntry { mac: String::new(), ..self.clone() };
+        let bytes = serde_json::to_vec(&unsigned)?;
+        Ok(blake3::keyed_hash(key, &bytes).to_hex().to_string())
+    }
+}
+
+/// Append-only, MAC-chained record of every file removed, across runs
+///
+/// Each entry carries the MAC of the one before it, so editing, removing or reordering
+/// entries breaks the chain from that point on (see [`verify`]). Several processes may
+/// append at once: the file is locked while the last entry is read back and the next
+/// one written.
+pub struct AuditLog {
+    path: PathBuf,
+    key: [u8; 32],
+    run_id: String,
+    user: String,
+    sudo_user: Option<String>,
+    hash_limit: u64,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open (creating if needed) the configured log for a run, along with its key
+    pub fn open(run_id: &str, config: &AuditConfig, hash_max_mb: u64) -> Result<Self> {
+        let path = log_path(config)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create audit log directory: {}", e),
+                    Some(dir.to_path_buf())
+                ))?;
+        }
+        let key = load_key(config, &path, true)?;
+        
+        let mut options = OpenOptions::new();
+        options.create(true).read(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(&path)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to open audit log: {}", e),
+                Some(path.clone())
+            ))?;
+        info!("Recording deletions to the audit log {:?}", path);
+        
+        let (user, sudo_user) = current_user();
+        Ok(Self {
+            path,
+            key,
+            run_id: run_id.to_string(),
+            user,
+            sudo_user,
+            hash_limit: hash_max_mb.saturating_mul(1024 * 1024),
+            file: Mutex::new(file),
+        })
+    }
+    
+    /// Hash a file that is about to be removed, if it is small enough
+    pub fn hash(&self, path: &Path, size: u64) -> Option<String> {
+        if size <= self.hash_limit {
+            journal::hash_file(path).ok()
+        } else {
+            None
+        }
+    }
+    
+    /// Append an entry for a removed file, chained to the last entry in the log
+    pub fn record(&self, path: &Path, size: u64, hash: Option<String>, rule: &str, action: &str) -> Result<()> {
+        let file = self.file.lock()
+            .map_err(|_| ClearModelError::cache("Audit log lock poisoned".to_string()))?;
+        let _lock = FileLock::exclusive(&file)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to lock audit log: {}", e),
+                Some(self.path.clone())
+            ))?;
+        
+        let last = last_entry(&file)
+            .map_err(|e| ClearModelError::security(format!(
+                "Cannot continue the audit log {:?} ({}); run `clearmodel audit verify`", self.path, e
+            )))?;
+        let (seq, prev) = match last {
+            Some(last) => (last.seq + 1, last.mac),
+            None => (1, GENESIS.to_string()),
+        };
+        
+        let mut entry = AuditEntry {
+            seq,
+            timestamp: unix_now(),
+            run_id: self.run_id.clone(),
+            user: self.user.clone(),
+            sudo_user: self.sudo_user.clone(),
+            path: path.to_path_buf(),
+            size,
+            hash,
+            rule: rule.to_string(),
+            action: action.to_string(),
+            prev,
+            mac: String::new(),
+        };
+        entry.mac = entry.compute_mac(&self.key)?;
+        
+        // One write per entry, so a concurrent reader never sees half a line
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        (&*file).write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Check every entry's MAC and its link to the previous one, returning the number of entries
+///
+/// Fails at the first entry that was altered, inserted, removed or reordered. Entries cut
+/// off the end of the log cannot be detected from the log alone; compare the count with a
+/// copy kept elsewhere (or make the file append-only with `chattr +a`).
+pub fn verify(path: &Path, key: &[u8; 32]) -> Result<u64> {
+    let file = File::open(path)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to open audit log: {}", e),
+            Some(path.to_path_buf())
+        ))?;
+    
+    let tampered = |line: usize, reason: String| ClearModelError::security(
+        format!("Audit log {:?} fails verification at line {}: {}", path, line, reason)
+    );
+    
+    let mut prev = GENESIS.to_string();
+    let mut count = 0;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let number = index + 1;
+        let entry: AuditEntry = serde_json::from_str(&line)
+            .map_err(|e| tampered(number, format!("unreadable entry ({})", e)))?;
+        
+        if entry.seq != count + 1 {
+            return Err(tampered(number, format!("expected entry {}, found {}", count + 1, entry.seq)));
+        }
+        if entry.prev != prev {
+            return Err(tampered(number, "does not follow the previous entry".to_string()));
+        }
+        if entry.compute_mac(key)? != entry.mac {
+            return Err(tampered(number, "MAC does not match its contents".to_string()));
+        }
+        
+        prev = entry.mac;
+        count += 1;
+    }
+    
+    Ok(count)
+}
+
+/// Location of the log (`audit.path`, or `audit.log` in the state directory)
+pub fn log_path(config: &AuditConfig) -> Result<PathBuf> {
+    if let Some(path) = &config.path {
+        return Ok(path.clone());
+    }
+    state::require(state::AUDIT_LOG, "the audit log")
+}
+
+/// MAC key from `CLEARMODEL_AUDIT_KEY` or the key file, generating the file if `create` is set
+pub fn load_key(config: &AuditConfig, log: &Path, create: bool) -> Result<[u8; 32]> {
+    if let Ok(hex) = std::env::var(KEY_ENV) {
+        return parse_key(&hex)
+            .ok_or_else(|| ClearModelError::configuration(format!("{} must be 64 hex characters", KEY_ENV)));
+    }
+    
+    let key_file = config.key_file.clone().unwrap_or_else(|| log.with_extension("key"));
+    match std::fs::read_to_string(&key_file) {
+        Ok(text) => parse_key(text.trim())
+            .ok_or_else(|| ClearModelError::configuration(format!("{:?} does not hold a 64 character hex key", key_file))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && create => create_key(&key_file),
+        Err(e) => Err(ClearModelError::file_operation(
+            format!("Failed to read audit key: {}", e),
+            Some(key_file)
+        )),
+    }
+}
+
+fn parse_key(hex: &str) -> Option<[u8; 32]> {
+    blake3::Hash::from_hex(hex.trim()).ok().map(|hash| *hash.as_bytes())
+}
+
+fn create_key(key_file: &Path) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    getrandom::getrandom(&mut key)
+        .map_err(|e| ClearModelError::environment(format!("Failed to generate an audit key: {}", e)))?;
+    
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(key_file)
+        .and_then(|mut file| writeln!(file, "{}", blake3::Hash::from(key).to_hex()))
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to write audit key: {}", e),
+            Some(key_file.to_path_buf())
+        ))?;
+    
+    info!("Generated an audit log key at {:?}; keep a copy where the log's writers cannot change it", key_file);
+    Ok(key)
+}
+
+/// Last entry of the log, read back from its end
+fn last_entry(mut file: &File) -> std::io::Result<Option<AuditEntry>> {
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    
+    let Some(line) = tail.split(|byte| *byte == b'\n').rev().find(|line| !line.is_empty()) else {
+        return Ok(None);
+    };
+    serde_json::from_slice(line)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("its last entry is unreadable: {}", e)))
+}
+
+/// The effective user's name, and the invoking user's under sudo
+pub(crate) fn current_user() -> (String, Option<String>) {
+    let sudo_user = std::env::var("SUDO_USER").ok().filter(|user| !user.is_empty());
+    
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid cannot fail
+        let uid = unsafe { libc::geteuid() };
+        let user = user_name(uid).unwrap_or_else(|| uid.to_string());
+        (user, sudo_user)
+    }
+    #[cfg(not(unix))]
+    {
+        let user = std::env::var("USERNAME").unwrap_or_else(|_| "unknown".to_string());
+        (user, sudo_user)
+    }
+}
+
+#[cfg(unix)]
+fn user_name(uid: libc::uid_t) -> Option<String> {
+    let mut passwd = std::mem::MaybeUninit::<libc::passwd>::uninit();
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer is valid for the call; `result` is only set to `passwd` on success
+    let status = unsafe {
+        libc::getpwuid_r(uid, passwd.as_mut_ptr(), buffer.as_mut_ptr(), buffer.len(), &mut result)
+    };
+    if status != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success `pw_name` points into `buffer`, which is still alive
+    let name = unsafe { std::ffi::CStr::from_ptr((*result).pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Exclusive `flock` on the log, held while an entry is appended
+struct FileLock<'a> {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    file: &'a File,
+}
+
+impl<'a> FileLock<'a> {
+    fn exclusive(file: &'a File) -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+            // SAFETY: the descriptor is open for as long as `file` is borrowed
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(Self { file })
+    }
+}
+
+impl Drop for FileLock<'_> {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            use std::os::fd::AsRawFd;
+            // SAFETY: as in `exclusive`
+            unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) };
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_verify_detects_edited_and_removed_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = AuditConfig {
+            enabled: true,
+            path: Some(temp_dir.path().join("audit.log")),
+            key_file: Some(temp_dir.path().join("audit.key")),
+        };
+        let log = AuditLog::open("run", &config, 1).unwrap();
+        for name in ["a.bin", "b.bin", "c.bin"] {
+            log.record(&temp_dir.path().join(name), 10, None, "expired", "deleted").unwrap();
+        }
+        
+        let path = config.path.clone().unwrap();
+        let key = load_key(&config, &path, false).unwrap();
+        assert_eq!(verify(&path, &key).unwrap(), 3);
+        assert!(verify(&path, &[7u8; 32]).is_err());
+        
+        let original = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, original.replacen("\"size\":10", "\"size\":11", 1)).unwrap();
+        assert!(verify(&path, &key).unwrap_err().to_string().contains("line 1"));
+        
+        let lines: Vec<&str> = original.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(verify(&path, &key).unwrap_err().to_string().contains("line 2"));
+    }
+}
\ No newline at end of file
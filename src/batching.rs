@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+/// Batch size before anything has been measured
+const INITIAL_BATCH: usize = 100;
+
+const MIN_BATCH: usize = 8;
+const MAX_BATCH: usize = 2_000;
+
+/// Wall time a batch should take, so progress and cancellation stay responsive
+const TARGET_BATCH_TIME: Duration = Duration::from_millis(50);
+
+/// Bytes a batch should remove at most, since moving or hashing big files dominates
+const TARGET_BATCH_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Picks how many files the executor processes per parallel batch
+///
+/// Each finished batch reports its wall time and the bytes it removed. The next
+/// size aims at [`TARGET_BATCH_TIME`] and [`TARGET_BATCH_BYTES`], so directories of
+/// tiny `.pyc` files get large batches and multi-GB checkpoints get small ones.
+/// Sizes move halfway towards the target to avoid oscillating.
+#[derive(Debug, Clone)]
+pub struct BatchSizer {
+    size: usize,
+}
+
+impl Default for BatchSizer {
+    fn default() -> Self {
+        Self { size: INITIAL_BATCH }
+    }
+}
+
+impl BatchSizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Files to put in the next batch
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    
+    /// Feed back how the last batch went
+    pub fn observe(&mut self, files: usize, bytes_removed: u64, elapsed: Duration) {
+        if files == 0 {
+            return;
+        }
+        
+        let per_file = elapsed.as_secs_f64() / files as f64;
+        let by_time = if per_file > 0.0 {
+            TARGET_BATCH_TIME.as_secs_f64() / per_file
+        } else {
+            MAX_BATCH as f64
+        };
+        
+        let per_file_bytes = bytes_removed as f64 / files as f64;
+        let by_bytes = if per_file_bytes > 0.0 {
+            TARGET_BATCH_BYTES as f64 / per_file_bytes
+        } else {
+            MAX_BATCH as f64
+        };
+        
+        let target = by_time.min(by_bytes).clamp(MIN_BATCH as f64, MAX_BATCH as f64) as usize;
+        self.size = if target > self.size {
+            self.size + (target - self.size).div_ceil(2)
+        } else {
+            self.size - (self.size - target).div_ceil(2)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_batches_grow_for_tiny_files_and_shrink_for_huge_ones() {
+        let mut sizer = BatchSizer::new();
+        for _ in 0..20 {
+            let files = sizer.size();
+            sizer.observe(files, files as u64 * 512, Duration::from_micros(5 * files as u64));
+        }
+        assert_eq!(sizer.size(), MAX_BATCH);
+        
+        for _ in 0..20 {
+            let files = sizer.size();
+            sizer.observe(files, files as u64 * 4 * 1024 * 1024 * 1024, Duration::from_millis(files as u64));
+        }
+        assert_eq!(sizer.size(), MIN_BATCH);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,220 @@
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use sysinfo::Disks;
+use tracing::{debug, info, warn};
+
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::netfs::NetworkFsMode;
+use crate::policy::CleanupPolicy;
+use crate::resource_manager::ResourceManager;
+use crate::shred::DeletionMode;
+use crate::uring::BatchUnlinker;
+
+/// Files in each synthetic directory, about what a package's bytecode or a blob shard holds
+const FILES_PER_DIR: u64 = 100;
+
+/// Synthetic directories grouped under each top-level shard
+const DIRS_PER_SHARD: u64 = 100;
+
+/// Every fifth file is a small blob, the rest are bytecode
+const BLOB_EVERY: u64 = 5;
+const BLOB_BYTES: usize = 1024;
+const BYTECODE_BYTES: usize = 128;
+
+/// A generated cache tree
+#[derive(Debug, Clone, Serialize)]
+pub struct SyntheticCache {
+    pub root: PathBuf,
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+}
+
+/// Time taken by one phase of the benchmark
+#[derive(Debug, Clone, Serialize)]
+pub struct Phase {
+    pub files: u64,
+    pub seconds: f64,
+    pub files_per_sec: f64,
+}
+
+impl Phase {
+    fn new(files: u64, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64();
+        Self { files, seconds, files_per_sec: files as f64 / seconds.max(f64::EPSILON) }
+    }
+}
+
+/// The settings a benchmark ran with, resolved to the values actually used
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchSettings {
+    pub max_parallel_operations: usize,
+    pub scan_workers: usize,
+    pub delete_workers: usize,
+    pub batched_unlinks: bool,
+}
+
+/// Scan and delete throughput of the current configuration on a synthetic cache
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub cache: SyntheticCache,
+    pub generate: Phase,
+    pub scan: Phase,
+    pub delete: Phase,
+    pub settings: BenchSettings,
+}
+
+/// Create `files` small files under `root`, all last modified `age` ago
+///
+/// Directories hold a hundred files each and are grouped a hundred to a shard, so the
+/// tree is wide like a real cache; files are written in parallel.
+pub fn generate(root: &Path, files: u64, age: Duration) -> Result<SyntheticCache> {
+    let directories = files.div_ceil(FILES_PER_DIR);
+    let modified = filetime::FileTime::from_system_time(SystemTime::now() - age);
+    let failed = |path: &Path, e: std::io::Error| ClearModelError::file_operation(
+        format!("Failed to generate the synthetic cache: {}", e),
+        Some(path.to_path_buf())
+    );
+    
+    (0..directories).into_par_iter().try_for_each(|dir| -> Result<()> {
+        let path = root.join(format!("shard-{:03}", dir / DIRS_PER_SHARD)).join(format!("pkg-{:06}", dir));
+        std::fs::create_dir_all(&path).map_err(|e| failed(&path, e))?;
+        for file in dir * FILES_PER_DIR..files.min((dir + 1) * FILES_PER_DIR) {
+            let (name, size) = if file % BLOB_EVERY == 0 {
+                (format!("blob-{:016x}.bin", file), BLOB_BYTES)
+            } else {
+                (format!("mod_{}.cpython-311.pyc", file), BYTECODE_BYTES)
+            };
+            let file_path = path.join(name);
+            std::fs::write(&file_path, vec![0u8; size]).map_err(|e| failed(&file_path, e))?;
+            filetime::set_file_mtime(&file_path, modified).map_err(|e| failed(&file_path, e))?;
+        }
+        Ok(())
+    })?;
+    
+    let blobs = files.div_ceil(BLOB_EVERY);
+    Ok(SyntheticCache {
+        root: root.to_path_buf(),
+        files,
+        directories,
+        bytes: blobs * BLOB_BYTES as u64 + (files - blobs) * BYTECODE_BYTES as u64,
+    })
+}
+
+/// Generate `files` files in a temporary directory under `parent`, then time how long the
+/// current configuration takes to scan and to delete them
+///
+/// The run is not journaled, audited or quarantined, since the files are thrown away;
+/// everything else (workers, rate limits, deletion mode, sandbox) is as configured. The
+/// directory is removed afterwards, also when the benchmark fails.
+pub async fn run(config: &ClearModelConfig, files: u64, parent: &Path) -> Result<BenchReport> {
+    let root = parent.join(format!("clearmodel-bench-cache-{}", std::process::id()));
+    check_free_space(parent, files)?;
+    let _cleanup = RemoveOnDrop(root.clone());
+    
+    let age = Duration::from_secs((u64::from(config.max_cache_age_days) + 1) * 86_400);
+    info!("Generating {} files under {:?}", files, root);
+    let started = Instant::now();
+    let cache = tokio::task::spawn_blocking({
+        let root = root.clone();
+        move || generate(&root, files, age)
+    })
+    .await
+    .map_err(|e| ClearModelError::cache(format!("Generating the synthetic cache failed: {}", e)))??;
+    let generate = Phase::new(cache.files, started.elapsed());
+    
+    let mut bench_config = config.clone();
+    bench_config.cache_paths = vec![root.clone()];
+    bench_config.security.allowed_roots = vec![root.clone()];
+    if bench_config.network_filesystems == NetworkFsMode::Skip {
+        bench_config.network_filesystems = NetworkFsMode::Allow;
+    }
+    let settings = BenchSettings {
+        max_parallel_operations: bench_config.max_parallel_operations,
+        scan_workers: bench_config.concurrency.scan_workers.unwrap_or(bench_config.max_parallel_operations),
+        delete_workers: bench_config.concurrency.delete_workers.unwrap_or_else(rayon::current_num_threads),
+        batched_unlinks: !bench_config.follow_symlinks
+            && !bench_config.security.sandbox
+            && bench_config.deletion_mode == DeletionMode::Delete
+            && BatchUnlinker::detect().is_some(),
+    };
+    let policy = CleanupPolicy::from_config(&bench_config);
+    let manager = ResourceManager::new(bench_config).await?;
+    
+    let started = Instant::now();
+    let plan = manager.plan_paths(std::slice::from_ref(&root), &policy).await?;
+    let scan = Phase::new(plan.file_count(), started.elapsed());
+    if plan.file_count() < cache.files {
+        warn!(
+            "Only {} of {} synthetic files were selected; exclude_patterns or other settings keep the rest",
+            plan.file_count(),
+            cache.files
+        );
+    }
+    
+    let started = Instant::now();
+    let results = manager.apply(plan).await?;
+    let removed = results.iter().map(|result| result.files_removed).sum();
+    let delete = Phase::new(removed, started.elapsed());
+    
+    Ok(BenchReport { cache, generate, scan, delete, settings })
+}
+
+/// Refuse to start when the files would not fit, counting a block per file
+fn check_free_space(parent: &Path, files: u64) -> Result<()> {
+    let parent = parent.canonicalize()
+        .map_err(|e| ClearModelError::file_operation(format!("Cannot use the benchmark directory: {}", e), Some(parent.to_path_buf())))?;
+    let disks = Disks::new_with_refreshed_list();
+    let Some(disk) = disks.list().iter()
+        .filter(|disk| parent.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+    else {
+        return Ok(());
+    };
+    
+    let needed = files.saturating_mul(4096 + BLOB_BYTES as u64 / BLOB_EVERY);
+    if disk.available_space() < needed {
+        return Err(ClearModelError::environment(format!(
+            "{} files need about {:.1} GB but {:?} has {:.1} GB free; use fewer files or another --dir",
+            files,
+            needed as f64 / 1_073_741_824.0,
+            disk.mount_point(),
+            disk.available_space() as f64 / 1_073_741_824.0
+        )));
+    }
+    Ok(())
+}
+
+/// Removes the synthetic cache, whatever the benchmark left of it
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.0) {
+            Ok(()) => debug!("Removed the synthetic cache {:?}", self.0),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove the synthetic cache {:?}: {}", self.0, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[tokio::test]
+    async fn test_bench_scans_and_deletes_every_synthetic_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = run(&ClearModelConfig::default(), 250, temp_dir.path()).await.unwrap();
+        
+        assert_eq!(report.cache.directories, 3);
+        assert_eq!(report.scan.files, 250);
+        assert_eq!(report.delete.files, 250);
+        // The synthetic cache is gone afterwards
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,35 @@
+//! Privileged removal helper, run as root through `pkexec` by `privilege_backend = "pkexec"`
+//!
+//! Installed as `/usr/libexec/clearmodel-remove`; it only removes files below the
+//! directories listed in `/etc/clearmodel/privileged-roots`.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clearmodel::privilege::{self, HELPER_ROOTS};
+
+fn main() -> ExitCode {
+    let mut args = std::env::args_os().skip(1).peekable();
+    // Only the first `--` is a separator; a later one is a path
+    args.next_if_eq(&OsString::from("--"));
+    let paths: Vec<PathBuf> = args.map(PathBuf::from).collect();
+    
+    let roots = match privilege::read_helper_roots(std::path::Path::new(HELPER_ROOTS)) {
+        Ok(roots) => roots,
+        Err(e) => {
+            eprintln!("clearmodel-remove: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    
+    let refused = privilege::helper_remove(&paths, &roots);
+    for (path, reason) in &refused {
+        eprintln!("clearmodel-remove: {}: {}", path.display(), reason);
+    }
+    if refused.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tracing::debug;
+use walkdir::WalkDir;
+
+use crate::allocation::{SizeBasis, UsageCounter};
+use crate::models::{self, CachedModel};
+use crate::policy::CleanupPolicy;
+
+/// When a model was last used: its latest access or write, or a later recorded open
+pub fn last_used(cached: &CachedModel, policy: &CleanupPolicy) -> Option<SystemTime> {
+    let opened = policy.last_used.as_ref()
+        .and_then(|opens| cached.files.iter().filter_map(|file| opens.get(file)).max().copied());
+    cached.last_access.max(opened)
+}
+
+/// Models to evict so `used` bytes fit in `budget`, least recently used first
+///
+/// Pinned models are never chosen; when only pinned models are left the cache stays over budget.
+pub fn select(mut cached: Vec<CachedModel>, used: u64, budget: u64, policy: &CleanupPolicy) -> Vec<CachedModel> {
+    cached.retain(|cached| {
+        let pinned = policy.pinned.matches(&cached.model.name);
+        if pinned {
+            debug!("Keeping pinned model {} over budget", cached.model);
+        }
+        !pinned
+    });
+    cached.sort_by_cached_key(|cached| last_used(cached, policy));
+    
+    let mut excess = used.saturating_sub(budget);
+    cached.into_iter()
+        .take_while(|cached| {
+            let needed = excess > 0;
+            excess = excess.saturating_sub(cached.bytes);
+            needed
+        })
+        .collect()
+}
+
+/// Models stored under `roots`, including Ollama's when its store is inside one of them
+pub fn models_under(roots: &[PathBuf], basis: SizeBasis) -> Vec<CachedModel> {
+    let ollama = models::ollama_models_dir().filter(|dir| roots.iter().any(|root| dir.starts_with(root)));
+    models::inventory(roots, ollama.as_deref(), basis)
+}
+
+/// Bytes of regular files under `roots`, counted by `usage`; links are not followed
+pub fn disk_usage(roots: &[PathBuf], mut usage: UsageCounter) -> u64 {
+    for entry in roots.iter().flat_map(|root| WalkDir::new(root).into_iter().flatten()) {
+        if entry.file_type().is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                usage.add(entry.path(), &metadata);
+            }
+        }
+    }
+    usage.total()
+}
+
+/// Budget in bytes from a size in GB
+pub fn bytes(gb: f64) -> u64 {
+    (gb * 1_073_741_824.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClearModelConfig;
+    use crate::models::{ModelRef, ModelSource};
+    use std::time::{Duration, UNIX_EPOCH};
+    
+    #[test]
+    fn test_least_recently_used_models_are_evicted_until_within_budget() {
+        let model = |name: &str, bytes: u64, used: u64| CachedModel {
+            model: ModelRef { source: ModelSource::HuggingFace, name: name.to_string() },
+            bytes,
+            last_access: Some(UNIX_EPOCH + Duration::from_secs(used)),
+            files: vec![PathBuf::from(format!("/hf/{}/model.bin", name))],
+            dirs: Vec::new(),
+        };
+        let config = ClearModelConfig {
+            pinned_models: vec!["org/pinned".to_string()],
+            ..ClearModelConfig::default()
+        };
+        let mut policy = CleanupPolicy::from_config(&config);
+        let cached = vec![
+            model("org/recent", 40, 300),
+            model("org/pinned", 40, 50),
+            model("org/old", 30, 100),
+            model("org/older", 20, 80),
+        ];
+        
+        let names = |selected: Vec<CachedModel>| selected.into_iter().map(|cached| cached.model.name).collect::<Vec<_>>();
+        assert_eq!(names(select(cached.clone(), 130, 100, &policy)), vec!["org/older", "org/old"]);
+        assert_eq!(names(select(cached.clone(), 110, 100, &policy)), vec!["org/older"]);
+        assert!(select(cached.clone(), 90, 100, &policy).is_empty());
+        
+        // A recorded open makes the oldest download the most recently used
+        policy.last_used = Some(std::sync::Arc::new(std::collections::HashMap::from([
+            (PathBuf::from("/hf/org/older/model.bin"), UNIX_EPOCH + Duration::from_secs(400)),
+        ])));
+        assert_eq!(names(select(cached, 110, 100, &policy)), vec!["org/old"]);
+    }
+}
\ No newline at end of file
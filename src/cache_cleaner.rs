@@ -1,20 +1,90 @@
-use secrecy::ExposeSecret;
 
-use std::time::Duration;
-use tokio::process::Command as AsyncCommand;
-use tokio::time::timeout;
+use std::collections::HashSet;
+#[cfg(feature = "daemon")]
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(feature = "daemon")]
+use std::sync::Mutex;
+#[cfg(feature = "daemon")]
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::allocation::UsageCounter;
+use crate::budget;
+#[cfg(feature = "daemon")]
+use crate::cancel;
+use crate::cache_target::{self, CacheTarget, TargetContext};
+use crate::capabilities::Capabilities;
+use crate::checkpoint::{self, Checkpoint, CheckpointWriter};
+use crate::command_runner::{CommandRecord, CommandRunner};
 use crate::config::ClearModelConfig;
+#[cfg(feature = "daemon")]
+use crate::daemon::{self, DaemonStatus, DiskMonitor, SharedStatus};
+use crate::disk_space::{self, FilesystemSpace, SpaceChange};
 use crate::environment::EnvironmentManager;
 use crate::errors::{ClearModelError, Result};
-use crate::resource_manager::{ResourceManager, CleanupResult};
+use crate::events::{self, Event, EventSink};
+use crate::export::RemovalLog;
+use crate::audit::AuditLog;
+use crate::journal::Journal;
+#[cfg(feature = "daemon")]
+use crate::run_lock::{self, PassLock, RunLock};
+use crate::shared_cache::SharedCache;
+use crate::lifecycle::{self, LifecycleSummary};
+#[cfg(all(feature = "daemon", feature = "metrics"))]
+use crate::metrics::{Metrics, MetricsServer};
+use crate::quarantine::{self, Quarantine};
+use crate::log_dedup::ErrorDeduplicator;
+use crate::policy::CleanupPolicy;
+use crate::hf_repair;
+use crate::models::{self, CachedModel, ModelRef, PinnedModels};
+use crate::netfs;
+use crate::prescan;
+use crate::privilege::{self, PrivilegeEscalator};
+use crate::progress::ProgressReporter;
+use crate::prompt;
+use crate::categories::{self, CategoryBreakdown};
+use crate::resource_manager::{ResourceManager, CleanupResult, FailedFile};
+use crate::security::SecurityManager;
+use crate::stats::StatsSnapshot;
+use crate::shred::{self, DeletionMode};
+use crate::plan::{CleanupPlan, PlannedDirectory, PlannedFile};
+use crate::targets::TargetFilter;
+#[cfg(feature = "daemon")]
+use crate::watch::DownloadWatcher;
+
+/// Removals smaller than this are not compared against the space they freed
+const MIN_UNFREED_BYTES: u64 = 256 * 1_048_576;
+
+/// Files removed by one privileged `rm`, keeping its command line well short of the limit
+const PRIVILEGED_BATCH: usize = 256;
 
 /// Main cache cleaner that orchestrates all cleaning operations
 pub struct CacheCleaner {
     config: ClearModelConfig,
     env_manager: EnvironmentManager,
     resource_manager: ResourceManager,
+    commands: CommandRunner,
+    capabilities: Capabilities,
+    targets: TargetFilter,
+    
+    /// Everything `--only` and `--exclude-target` choose from
+    registry: Vec<Box<dyn CacheTarget>>,
+    events: Option<EventSink>,
+    checkpoint: Option<Arc<CheckpointWriter>>,
+    
+    /// Plans left by an interrupted run, cleaned instead of scanning
+    resume: Option<Checkpoint>,
+    
+    /// Free space on each filesystem before and after the last cleanup
+    disk_space: std::sync::Mutex<Vec<SpaceChange>>,
+    
+    /// Runs privileged removals instead of the configured `privilege_backend`
+    escalator: Option<Arc<dyn PrivilegeEscalator>>,
 }
 
 impl CacheCleaner {
@@ -24,246 +94,961 @@ impl CacheCleaner {
         env_manager: EnvironmentManager,
     ) -> Result<Self> {
         let resource_manager = ResourceManager::new(config.clone()).await?;
+        let registry = cache_target::builtin_targets(&config);
+        let commands = CommandRunner::new(&config.commands);
         
         Ok(Self {
             config,
             env_manager,
             resource_manager,
+            commands,
+            capabilities: Capabilities::full(),
+            targets: TargetFilter::default(),
+            registry,
+            events: None,
+            checkpoint: None,
+            resume: None,
+            disk_space: std::sync::Mutex::new(Vec::new()),
+            escalator: None,
         })
     }
     
-    /// Clean all caches (main entry point)
-    pub async fn clean_all_caches(&self, dry_run: bool) -> Result<()> {
+    /// Show scan and deletion progress through the given reporter
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.resource_manager = self.resource_manager.with_progress(progress);
+        self
+    }
+    
+    /// Move removed files into quarantine instead of deleting them
+    pub fn with_quarantine(mut self, quarantine: Arc<Quarantine>) -> Self {
+        self.resource_manager = self.resource_manager.with_quarantine(quarantine);
+        self
+    }
+    
+    /// Coordinate with other users' instances on shared caches
+    pub fn with_shared(mut self, shared: Arc<SharedCache>) -> Self {
+        self.resource_manager = self.resource_manager.with_shared(shared);
+        self
+    }
+    
+    /// Append removed files to the tamper-evident audit log
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.resource_manager = self.resource_manager.with_audit(audit);
+        self
+    }
+    
+    /// Plan every target up front and save progress, so an interrupted run can be resumed
+    pub fn with_checkpoint(mut self, checkpoint: Arc<CheckpointWriter>) -> Self {
+        self.resource_manager = self.resource_manager.with_checkpoint(Arc::clone(&checkpoint));
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+    
+    /// Clean what an interrupted run left, from its checkpoint, instead of scanning
+    pub fn resuming(mut self, checkpoint: Checkpoint) -> Self {
+        self.resume = Some(checkpoint);
+        self
+    }
+    
+    /// Record removed files to a deletion journal so the run can be undone
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.resource_manager = self.resource_manager.with_journal(journal);
+        self
+    }
+    
+    /// Set up a dry run, which on shared caches only looks at other instances' locks
+    pub fn previewing(mut self) -> Result<Self> {
+        if self.config.shared.enabled {
+            let shared = SharedCache::open(&self.config, true)?;
+            self = self.with_shared(Arc::new(shared));
+        }
+        Ok(self)
+    }
+    
+    /// Journal (and, when configured, audit, quarantine and shared cache locks) removals under a new run id
+    ///
+    /// With a read-only home directory the journal and checkpoints, which only make a run
+    /// recoverable, are left off; quarantine and the audit log were asked for explicitly, so
+    /// the run is refused rather than removing files without them.
+    pub fn for_run(mut self, run_id: &str) -> Result<Self> {
+        let writable = self.capabilities.writable_home;
+        if !writable {
+            if self.config.quarantine || self.config.audit.enabled {
+                return Err(ClearModelError::environment(
+                    "The home directory is read-only, so quarantine and the audit log cannot be written; turn them off to clean here".to_string()
+                ));
+            }
+            warn!("The home directory is read-only; this run is not journaled and cannot be resumed or undone");
+        }
+        
+        if self.config.journal.enabled && writable {
+            let journal = Journal::create(run_id, &self.config.journal)?;
+            self = self.with_journal(Arc::new(journal));
+        }
+        if self.config.audit.enabled {
+            let audit = AuditLog::open(run_id, &self.config.audit, self.config.journal.hash_max_mb)?;
+            self = self.with_audit(Arc::new(audit));
+        }
+        if self.config.quarantine {
+            self = self.with_quarantine(Arc::new(Quarantine::for_run(run_id)?));
+        }
+        if self.config.shared.enabled {
+            let shared = SharedCache::open(&self.config, false)?;
+            self = self.with_shared(Arc::new(shared));
+        }
+        if self.config.checkpoint && writable {
+            self = self.with_checkpoint(Arc::new(CheckpointWriter::new(checkpoint::checkpoint_path()?)));
+        }
+        if self.config.deletion_mode == DeletionMode::Shred {
+            warn!("{}", shred::CAVEAT);
+        }
+        Ok(self)
+    }
+    
+    /// Disable features that the detected environment cannot support
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.resource_manager = self.resource_manager.with_capabilities(capabilities.clone());
+        self.capabilities = capabilities;
+        self
+    }
+    
+    /// Stop starting new removals once `cancel` is cancelled (Ctrl-C or SIGTERM)
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.resource_manager = self.resource_manager.with_cancellation(cancel);
+        self
+    }
+    
+    /// Restrict cleaning to the selected targets
+    pub fn with_target_filter(mut self, targets: TargetFilter) -> Self {
+        self.targets = targets;
+        self
+    }
+    
+    /// Remove files that need administrator rights through `escalator` rather than `privilege_backend`
+    pub fn with_escalator(mut self, escalator: Arc<dyn PrivilegeEscalator>) -> Self {
+        self.escalator = Some(escalator);
+        self
+    }
+    
+    /// Register another target, cleaned after the built-in ones when selected
+    pub fn with_target(mut self, target: Box<dyn CacheTarget>) -> Self {
+        self.registry.push(target);
+        self
+    }
+    
+    /// Record every file removed, for exporting what the run did
+    pub fn with_removal_log(mut self, removals: Arc<RemovalLog>) -> Self {
+        self.resource_manager = self.resource_manager.with_removal_log(removals);
+        self
+    }
+    
+    /// Stream lifecycle events for wrappers that build their own UI
+    pub fn with_events(mut self, events: EventSink) -> Self {
+        self.resource_manager = self.resource_manager.with_events(events.clone());
+        self.events = Some(events);
+        self
+    }
+    
+    /// Clean all caches (main entry point), returning the result for every cleaned directory
+    pub async fn clean_all_caches(&self, dry_run: bool) -> Result<Vec<CleanupResult>> {
+        let started = std::time::Instant::now();
+        let roots = prescan::cache_roots(&self.config);
+        let before = self.resource_manager.check_system_resources(&roots).await?;
+        let outcome = self.clean_selected_caches(dry_run).await;
+        // Nothing was removed in a dry run; after a failure the partial results are not returned
+        if let (Ok(results), false) = (outcome.as_deref(), dry_run) {
+            self.report_disk_space(&before, &disk_space::measure(&roots), results);
+        }
+        
+        if let Some(events) = &self.events {
+            if let Err(e) = &outcome {
+                events.emit(&Event::error(None, e));
+            }
+            let results = outcome.as_deref().unwrap_or_default();
+            events.emit(&Event::RunFinished {
+                succeeded: outcome.is_ok(),
+                files_removed: results.iter().map(|result| result.files_removed).sum(),
+                bytes_freed: results.iter().map(|result| result.bytes_freed).sum(),
+                errors: results.iter().map(|result| result.errors.len() as u64).sum::<u64>() + u64::from(outcome.is_err()),
+                duration_ms: events::millis(started.elapsed()),
+            });
+        }
+        
+        outcome
+    }
+    
+    async fn clean_selected_caches(&self, dry_run: bool) -> Result<Vec<CleanupResult>> {
         info!("Starting comprehensive cache cleanup");
         
-        // Clean ML model caches
-        let ml_results = self.clean_ml_model_caches(dry_run).await?;
-        self.log_cleanup_results("ML Model Caches", &ml_results);
+        // Inside a container the home directory may be the host's, mounted in
+        if let Some(layout) = self.env_manager.container_layout() {
+            SecurityManager::check_container_mounts(
+                layout,
+                &prescan::cache_roots(&self.config),
+                self.config.security.allow_container_host_mounts,
+                |paths| dry_run || prompt::confirm(&format!(
+                    "{} cache(s) may belong to the host rather than this container. Clean them anyway?",
+                    paths.len()
+                ))
+            )?;
+        }
         
-        // Only clean Python cache files if we have cache directories or if current dir looks like a project
-        if !ml_results.is_empty() || self.current_dir_looks_like_project().await? {
-            let python_result = self.clean_python_cache_files(dry_run).await?;
-            self.log_cleanup_results("Python Caches", &[python_result]);
-        } else {
-            info!("Skipping Python cache cleanup - no cache directories found and current directory doesn't appear to be a Python project");
+        // Runs quarantined longer than the grace period are deleted for good
+        if !dry_run {
+            self.purge_expired_quarantine();
+        }
+        
+        let cx = TargetContext {
+            config: &self.config,
+            resource_manager: &self.resource_manager,
+            commands: &self.commands,
+            dry_run,
+        };
+        let selected: Vec<&dyn CacheTarget> = self.selected_targets()?
+            .into_iter()
+            .filter(|target| self.resume.as_ref().is_none_or(|resume| resume.has_target(target.name())))
+            .filter(|target| {
+                let found = target.detect();
+                if !found {
+                    debug!("Nothing found for target {}, skipping", target.name());
+                }
+                found
+            })
+            .filter(|target| target.roots().iter().all(|root| netfs::admitted(root, &self.config)))
+            .collect();
+        
+        // Large cleanups are planned first so the total can be confirmed before anything goes
+        let mut plans: Vec<Option<CleanupPlan>> = selected.iter().map(|_| None).collect();
+        if let Some(resume) = &self.resume {
+            info!("Resuming an interrupted run: {} files left to remove", resume.file_count());
+            plans = selected.iter().map(|target| Some(resume.plan(target.name()))).collect();
+        } else if let Some(threshold_gb) = self.config.security.require_confirmation_threshold_gb {
+            if !dry_run && prompt::interactive() {
+                match self.confirm_cleanup(&cx, &selected, threshold_gb).await? {
+                    Some(confirmed) => plans = confirmed.into_iter().map(Some).collect(),
+                    None => {
+                        info!("Cleanup declined");
+                        return Ok(Vec::new());
+                    }
+                }
+            }
+        }
+        
+        let deadline = self.config.timeouts.run_secs.map(|secs| Instant::now() + Duration::from_secs(secs));
+        let mut results = Vec::new();
+        // Targets the watchdog abandoned while planning are not cleaned
+        let mut abandoned = HashSet::new();
+        
+        // Planning everything first lets an interrupted run resume without scanning again
+        let checkpoint = self.checkpoint.as_ref().filter(|_| !dry_run);
+        if let Some(checkpoint) = checkpoint {
+            for (target, plan) in selected.iter().zip(plans.iter_mut()) {
+                if plan.is_none() {
+                    self.resource_manager.start_target(target.name());
+                    match self.watched(target.name(), deadline, target.scan(&cx)).await {
+                        Ok(scanned) => *plan = Some(scanned),
+                        Err(e @ ClearModelError::TimedOut { .. }) => {
+                            results.extend(Self::abandoned(*target, &e));
+                            abandoned.insert(target.name());
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            checkpoint.start(selected.iter()
+                .zip(&plans)
+                .filter_map(|(target, plan)| plan.as_ref().map(|plan| (target.name().to_string(), plan)))
+                .collect());
+        }
+        
+        let mut evicted = HashSet::new();
+        for (target, plan) in selected.iter().zip(plans) {
+            if abandoned.contains(target.name()) {
+                continue;
+            }
+            info!("Cleaning {} ({})", target.name(), target.group());
+            self.resource_manager.start_target(target.name());
+            let mut target_results = match self.watched(target.name(), deadline, target.clean(&cx, plan)).await {
+                Ok(target_results) => target_results,
+                Err(e @ ClearModelError::TimedOut { .. }) => Self::abandoned(*target, &e),
+                Err(e) => return Err(e),
+            };
+            let cancelled = self.resource_manager.is_cancelled();
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.finish_target(target.name(), cancelled);
+            }
+            if let Some(&budget_gb) = self.config.target_budgets_gb.get(target.name()).filter(|_| !cancelled) {
+                let freed = target_results.iter().map(|result| result.bytes_freed).sum();
+                target_results.extend(self.enforce_budget(target.name(), &target.roots(), budget_gb, freed, &mut evicted, dry_run).await?);
+            }
+            self.log_cleanup_results(target.name(), &target_results);
+            results.extend(target_results);
+            if cancelled {
+                info!("Cleanup interrupted after {}", target.name());
+                return Ok(results);
+            }
+        }
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.complete();
         }
         
+        let freed = results.iter().map(|result| result.bytes_freed).sum();
+        let total_results = self.enforce_total_budget(&selected, freed, &mut evicted, dry_run).await?;
+        if self.config.max_total_cache_gb.is_some() {
+            self.log_cleanup_results("max_total_cache_gb", &total_results);
+        }
+        results.extend(total_results);
+        
         info!("All cache cleaning operations completed successfully");
-        Ok(())
+        Ok(results)
+    }
+    
+    /// Evict least recently used models across every selected target until they fit in
+    /// `max_total_cache_gb`, when it is set
+    async fn enforce_total_budget(
+        &self,
+        selected: &[&dyn CacheTarget],
+        freed: u64,
+        evicted: &mut HashSet<ModelRef>,
+        dry_run: bool,
+    ) -> Result<Vec<CleanupResult>> {
+        let Some(budget_gb) = self.config.max_total_cache_gb else {
+            return Ok(Vec::new());
+        };
+        let roots: Vec<PathBuf> = selected.iter().flat_map(|target| target.roots()).collect();
+        self.resource_manager.start_target("max_total_cache_gb");
+        self.enforce_budget("max_total_cache_gb", &roots, budget_gb, freed, evicted, dry_run).await
     }
     
-    /// Clean machine learning model caches
-    async fn clean_ml_model_caches(&self, dry_run: bool) -> Result<Vec<CleanupResult>> {
-        info!("Cleaning ML model caches");
+    /// Evict least recently used models under `roots` until they hold no more than `budget_gb`
+    ///
+    /// `freed` is what the run has removed from `roots` already; a dry run removed nothing, so
+    /// it is discounted from the measured usage, and models `evicted` by earlier budgets are
+    /// not chosen again.
+    async fn enforce_budget(
+        &self,
+        name: &str,
+        roots: &[PathBuf],
+        budget_gb: f64,
+        freed: u64,
+        evicted: &mut HashSet<ModelRef>,
+        dry_run: bool,
+    ) -> Result<Vec<CleanupResult>> {
+        let cached: Vec<CachedModel> = budget::models_under(roots, self.config.size_basis)
+            .into_iter()
+            .filter(|cached| !evicted.contains(&cached.model))
+            .collect();
+        let mut used = budget::disk_usage(roots, UsageCounter::for_config(&self.config));
+        if dry_run {
+            used = used.saturating_sub(freed);
+        }
+        
+        let budget = budget::bytes(budget_gb);
+        if used <= budget {
+            debug!("{}: {:.2} GB used of the {:.2} GB budget", name, used as f64 / 1_073_741_824.0, budget_gb);
+            return Ok(Vec::new());
+        }
         
-        // Use the resource manager to clean all configured cache paths
-        let results = self.resource_manager.clean_all_caches(dry_run).await?;
+        let policy = CleanupPolicy::from_config(&self.config);
+        let selected = budget::select(cached, used, budget, &policy);
+        info!(
+            "{}: {:.2} GB over the {:.2} GB budget, evicting {} least recently used models",
+            name,
+            (used - budget) as f64 / 1_073_741_824.0,
+            budget_gb,
+            selected.len()
+        );
+        for cached in &selected {
+            info!("Evicting {} ({:.2} GB)", cached.model, cached.bytes as f64 / 1_073_741_824.0);
+        }
+        evicted.extend(selected.iter().map(|cached| cached.model.clone()));
         
-        // Additional cleanup for specific ML frameworks
-        self.clean_framework_specific_caches(dry_run).await?;
+        if dry_run {
+            return Ok(selected.iter()
+                .map(|cached| CleanupResult::preview(planned_model(cached, "budget"), Duration::ZERO))
+                .collect());
+        }
+        self.evict(&selected, "budget").await
+    }
+    
+    /// Remove every file of the given models, then what is left of their directories
+    async fn evict(&self, cached: &[CachedModel], rule: &'static str) -> Result<Vec<CleanupResult>> {
+        let plan = CleanupPlan { directories: cached.iter().map(|cached| planned_model(cached, rule)).collect() };
+        let mut results = self.resource_manager.apply(plan).await?;
         
+        // What is left of a HuggingFace repository are snapshot links into the removed blobs
+        for (cached, result) in cached.iter().zip(results.iter_mut()) {
+            for dir in cached.dirs.iter().filter(|dir| dir.exists()) {
+                match self.resource_manager.check_deletable(dir).and_then(|()| hf_repair::remove_link_tree(dir)) {
+                    Ok(()) => result.dirs_pruned += 1,
+                    Err(e) => {
+                        warn!("{}", e);
+                        result.errors.push(e.to_string());
+                    }
+                }
+            }
+        }
         Ok(results)
     }
     
-    /// Clean framework-specific caches that might not be in standard locations
-    async fn clean_framework_specific_caches(&self, dry_run: bool) -> Result<()> {
-        // Clean HuggingFace cache with their CLI if available
-        if let Err(e) = self.clean_huggingface_cache(dry_run).await {
-            warn!("Failed to clean HuggingFace cache: {}", e);
+    /// Names and groups of every registered target, whether or not the filter selects it
+    pub fn registry(&self) -> Vec<(String, &str)> {
+        self.registry.iter()
+            .map(|target| (target.name().to_string(), target.group()))
+            .collect()
+    }
+    
+    /// Registered targets the filter selects; naming one that does not exist is an error
+    fn selected_targets(&self) -> Result<Vec<&dyn CacheTarget>> {
+        let known = self.registry();
+        let unknown = self.targets.unknown(&known);
+        if !unknown.is_empty() {
+            let mut names: Vec<&str> = known.iter()
+                .flat_map(|(name, group)| [name.as_str(), *group])
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            return Err(ClearModelError::configuration(format!(
+                "Unknown target(s): {} (known targets: {})",
+                unknown.join(", "),
+                names.join(", ")
+            )));
         }
         
-        // Clean PyTorch cache
-        if let Err(e) = self.clean_pytorch_cache(dry_run).await {
-            warn!("Failed to clean PyTorch cache: {}", e);
+        Ok(self.registry.iter()
+            .map(|target| target.as_ref())
+            .filter(|target| if target.on_by_default() {
+                self.targets.allows(target.name(), target.group())
+            } else {
+                self.targets.names(target.name(), target.group())
+            })
+            .collect())
+    }
+    
+    /// Scan every target and ask before removing more than `threshold_gb`; `None` when declined
+    async fn confirm_cleanup(
+        &self,
+        cx: &TargetContext<'_>,
+        selected: &[&dyn CacheTarget],
+        threshold_gb: u64,
+    ) -> Result<Option<Vec<CleanupPlan>>> {
+        let mut plans = Vec::new();
+        for target in selected {
+            plans.push(target.scan(cx).await?);
         }
         
-        // Clean TensorFlow cache
-        if let Err(e) = self.clean_tensorflow_cache(dry_run).await {
-            warn!("Failed to clean TensorFlow cache: {}", e);
+        let bytes: u64 = plans.iter().map(CleanupPlan::total_bytes).sum();
+        if bytes > threshold_gb * 1_073_741_824 {
+            let question = format!(
+                "Remove {} files ({:.2} GB) from {} cache directories?",
+                plans.iter().map(CleanupPlan::file_count).sum::<u64>(),
+                bytes as f64 / 1_073_741_824.0,
+                plans.iter().map(|plan| plan.directories.len()).sum::<usize>()
+            );
+            if !prompt::confirm(&question) {
+                return Ok(None);
+            }
+        }
+        Ok(Some(plans))
+    }
+    
+    /// Remove every file of the cached models matching a name or glob, leaving the rest of the caches alone
+    ///
+    /// Pinned models are kept, and Ollama blobs another model still uses stay in place.
+    /// Removal goes through the journal and quarantine like a cleanup, so it can be undone.
+    /// Results come back in the order of the returned models.
+    pub async fn remove_models(&self, pattern: &str, dry_run: bool) -> Result<Vec<(CachedModel, CleanupResult)>> {
+        let matcher = models::model_matcher(pattern)
+            .map_err(|e| ClearModelError::configuration(format!("Invalid model pattern {:?}: {}", pattern, e)))?;
+        let pinned = PinnedModels::new(&self.config.pinned_models);
+        let (matched, kept): (Vec<CachedModel>, Vec<CachedModel>) =
+            models::inventory(&prescan::cache_roots(&self.config), models::ollama_models_dir().as_deref(), self.config.size_basis)
+                .into_iter()
+                .filter(|cached| matcher.is_match(&cached.model.name))
+                .partition(|cached| !pinned.matches(&cached.model.name));
+        for cached in &kept {
+            warn!("Keeping pinned model {}", cached.model);
+        }
+        if matched.is_empty() {
+            return Err(ClearModelError::cache(if kept.is_empty() {
+                format!("No cached model matches {:?} (see `clearmodel models list`)", pattern)
+            } else {
+                format!("Every model matching {:?} is pinned; unpin it first", pattern)
+            }));
         }
         
-        Ok(())
+        let plan = CleanupPlan { directories: matched.iter().map(|cached| planned_model(cached, "requested")).collect() };
+        if dry_run {
+            let results = plan.directories.into_iter().map(|directory| CleanupResult::preview(directory, Duration::ZERO));
+            return Ok(matched.into_iter().zip(results).collect());
+        }
+        
+        if let Some(threshold_gb) = self.config.security.require_confirmation_threshold_gb {
+            let question = format!(
+                "Remove {} models ({:.2} GB)?",
+                matched.len(),
+                plan.total_bytes() as f64 / 1_073_741_824.0
+            );
+            if plan.total_bytes() > threshold_gb * 1_073_741_824 && prompt::interactive() && !prompt::confirm(&question) {
+                info!("Removal declined");
+                return Ok(Vec::new());
+            }
+        }
+        
+        let results = self.evict(&matched, "requested").await?;
+        Ok(matched.into_iter().zip(results).collect())
     }
     
-    /// Clean HuggingFace cache using their CLI
-    async fn clean_huggingface_cache(&self, dry_run: bool) -> Result<()> {
-        debug!("Attempting to clean HuggingFace cache");
+    /// Watch cache directories and re-apply the cleanup policy whenever a download completes
+    #[cfg(feature = "daemon")]
+    pub async fn watch_and_enforce(&self, dry_run: bool) -> Result<()> {
+        if !self.capabilities.file_watching {
+            return Err(ClearModelError::environment(
+                "File watching is not available in this environment (see `clearmodel doctor`)".to_string()
+            ));
+        }
+        
+        let roots: Vec<_> = self.config.existing_cache_paths().into_iter().cloned().collect();
+        if roots.is_empty() {
+            return Err(ClearModelError::configuration(
+                "No existing cache directories to watch".to_string()
+            ));
+        }
+        
+        let (tx, mut rx) = mpsc::channel(64);
+        let watcher = DownloadWatcher::new(roots, &self.config.watch);
+        let watch_task = tokio::spawn(watcher.run(tx));
         
-        // Check if huggingface-hub CLI is available
-        let check_cmd = AsyncCommand::new("huggingface-cli")
-            .arg("--help")
-            .output()
-            .await;
+        info!("Watching for completed downloads (dry_run: {})", dry_run);
+        
+        while let Some(first) = rx.recv().await {
+            let mut downloads = vec![first];
+            while let Ok(download) = rx.try_recv() {
+                downloads.push(download);
+            }
             
-        if check_cmd.is_err() {
-            debug!("huggingface-cli not available, skipping");
-            return Ok(());
+            // Collapse a burst of completed files into one pass per cache root
+            let mut roots = BTreeSet::new();
+            for download in downloads {
+                info!(
+                    "Download completed: {:?} ({:.2} MB)",
+                    download.path,
+                    download.size as f64 / 1_048_576.0
+                );
+                roots.insert(download.root);
+            }
+            
+            let Some(_lock) = pass_lock(dry_run) else {
+                continue;
+            };
+            let _pass = PassLock::acquire();
+            for root in roots {
+                if let Err(e) = self.enforce_cache_root(&root, dry_run).await {
+                    warn!("Failed to enforce policy on {:?}: {}", root, e);
+                }
+            }
         }
         
-        let mut cmd = AsyncCommand::new("huggingface-cli");
-        cmd.arg("delete-cache");
+        match watch_task.await {
+            Ok(result) => result,
+            Err(e) => Err(ClearModelError::cache(format!("Watch task failed: {}", e))),
+        }
+    }
+    
+    /// Stay resident, cleaning whenever free space on a cache filesystem drops below
+    /// `min_free_space_gb` or a cleanup is requested over the control socket
+    #[cfg(feature = "daemon")]
+    pub async fn run_daemon(mut self, dry_run: bool) -> Result<()> {
+        let roots: Vec<_> = self.config.existing_cache_paths().into_iter().cloned().collect();
+        if roots.is_empty() {
+            return Err(ClearModelError::configuration(
+                "No existing cache directories to monitor".to_string()
+            ));
+        }
         
-        if dry_run {
-            // HuggingFace CLI doesn't have a dry-run flag, so we'll just report
-            info!("Would run: huggingface-cli delete-cache");
-            return Ok(());
+        let mut monitor = DiskMonitor::new(roots, self.config.min_free_space_gb);
+        let status: SharedStatus = Arc::new(Mutex::new(DaemonStatus::new(monitor.threshold_bytes(), dry_run)));
+        let (trigger_tx, mut trigger_rx) = mpsc::channel(1);
+        
+        #[cfg(unix)]
+        let server = {
+            let socket = self.config.daemon.socket_path.clone()
+                .or_else(daemon::default_socket_path)
+                .ok_or_else(|| ClearModelError::environment(
+                    "Cannot determine the daemon control socket path".to_string()
+                ))?;
+            let server = daemon::ControlServer::bind(&socket).await?;
+            info!(socket = %socket.display(), "Control socket listening");
+            tokio::spawn(server.serve(status.clone(), trigger_tx.clone()))
+        };
+        #[cfg(not(unix))]
+        warn!("The control socket is not available on this platform; `daemon status` and `daemon trigger-now` will not work");
+        
+        #[cfg(feature = "metrics")]
+        let metrics = Arc::new(Metrics::default());
+        #[cfg(feature = "metrics")]
+        let metrics_server = match self.config.daemon.metrics_port {
+            Some(port) => {
+                let address: std::net::IpAddr = self.config.daemon.metrics_address.parse()
+                    .map_err(|e| ClearModelError::configuration(format!(
+                        "Invalid daemon.metrics_address {:?}: {}",
+                        self.config.daemon.metrics_address, e
+                    )))?;
+                let server = MetricsServer::bind((address, port).into()).await?;
+                info!(address = %server.local_addr()?, "Serving Prometheus metrics at /metrics");
+                Some(tokio::spawn(server.serve(metrics.clone())))
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "metrics"))]
+        if self.config.daemon.metrics_port.is_some() {
+            warn!("daemon.metrics_port is set but this build has no metrics support");
         }
         
-        // Add flag to disable TUI and avoid interactive prompts
-        cmd.arg("--disable-tui");
+        #[cfg(feature = "usage")]
+        let tracker = if self.config.usage.track {
+            let tracker = crate::usage::UsageTracker::new(crate::prescan::cache_roots(&self.config), &self.config.usage)?;
+            Some(tokio::spawn(async move {
+                if let Err(e) = tracker.run(cancel::shutdown_signal()).await {
+                    warn!(error = %e, "Usage tracking stopped");
+                }
+            }))
+        } else {
+            None
+        };
+        #[cfg(not(feature = "usage"))]
+        if self.config.usage.track {
+            warn!("usage.track is set but this build has no usage tracking support");
+        }
         
-        let timeout_duration = Duration::from_secs(300); // 5 minutes timeout
+        let interval = Duration::from_secs(self.config.daemon.check_interval_secs.max(1));
+        let cooldown = Duration::from_secs(self.config.daemon.cooldown_secs);
+        let mut ticker = tokio::time::interval(interval);
+        let lifecycle_enabled = self.config.lifecycle.enabled;
+        let mut lifecycle_ticker = tokio::time::interval(
+            Duration::from_secs(self.config.lifecycle.interval_hours.max(1) * 3600)
+        );
+        let mut last_pressure_cleanup: Option<Instant> = None;
         
-        match timeout(timeout_duration, cmd.output()).await {
-            Ok(Ok(output)) => {
-                if output.status.success() {
-                    info!("Successfully cleaned HuggingFace cache");
-                    debug!("HuggingFace cleanup output: {}", String::from_utf8_lossy(&output.stdout));
-                } else {
-                    warn!(
-                        "HuggingFace cache cleanup failed: {}",
-                        String::from_utf8_lossy(&output.stderr)
+        info!(
+            interval_secs = interval.as_secs(),
+            threshold_gb = self.config.min_free_space_gb,
+            dry_run,
+            "Daemon started"
+        );
+        
+        loop {
+            let reason = tokio::select! {
+                _ = ticker.tick() => {
+                    let disks = monitor.check();
+                    let under_pressure = monitor.under_pressure(&disks);
+                    for disk in &disks {
+                        debug!(
+                            mount_point = %disk.mount_point.display(),
+                            available_gb = disk.available_bytes as f64 / 1_073_741_824.0,
+                            "Free space check"
+                        );
+                    }
+                    #[cfg(feature = "metrics")]
+                    metrics.record_available(
+                        disks.iter().map(|disk| (disk.mount_point.clone(), disk.available_bytes)).collect()
                     );
+                    if let Ok(mut status) = status.lock() {
+                        status.record_check(disks, under_pressure);
+                    }
+                    
+                    if !under_pressure {
+                        continue;
+                    }
+                    if last_pressure_cleanup.map(|at| at.elapsed() < cooldown).unwrap_or(false) {
+                        debug!("Free space is low but the last cleanup was too recent");
+                        continue;
+                    }
+                    last_pressure_cleanup = Some(Instant::now());
+                    "low_free_space"
+                }
+                _ = lifecycle_ticker.tick(), if lifecycle_enabled => "lifecycle",
+                Some(()) = trigger_rx.recv() => "trigger_now",
+                _ = cancel::shutdown_signal() => break,
+            };
+            
+            let Some(_lock) = pass_lock(dry_run) else {
+                continue;
+            };
+            let _pass = PassLock::acquire();
+            if reason == "lifecycle" {
+                if !dry_run {
+                    self = self.for_run(&crate::journal::new_run_id())?;
                 }
+                if let Err(e) = self.run_lifecycle(dry_run).await {
+                    warn!(error = %e, "Lifecycle pass failed");
+                }
+                continue;
             }
-            Ok(Err(e)) => {
-                warn!("Failed to execute HuggingFace cache cleanup: {}", e);
+            
+            info!(reason, "Starting cleanup");
+            if !dry_run {
+                self = self.for_run(&crate::journal::new_run_id())?;
             }
-            Err(_) => {
-                warn!("HuggingFace cache cleanup timed out");
+            if let Ok(mut status) = status.lock() {
+                status.record_cleanup(true);
+            }
+            
+            let started = Instant::now();
+            let result = self.clean_all_caches(dry_run).await;
+            
+            if let Ok(mut status) = status.lock() {
+                status.record_cleanup(false);
+            }
+            #[cfg(feature = "metrics")]
+            match &result {
+                Ok(results) => metrics.record_run(results, started.elapsed(), true),
+                Err(_) => metrics.record_run(&[], started.elapsed(), false),
+            }
+            let min_available_gb = monitor.check().iter()
+                .map(|disk| disk.available_bytes)
+                .min()
+                .unwrap_or(0) as f64 / 1_073_741_824.0;
+            match result {
+                Ok(_) => info!(
+                    reason,
+                    min_available_gb,
+                    duration_secs = started.elapsed().as_secs_f64(),
+                    "Cleanup finished"
+                ),
+                Err(e) => warn!(reason, error = %e, "Cleanup failed"),
             }
         }
         
+        #[cfg(unix)]
+        server.abort();
+        #[cfg(feature = "metrics")]
+        if let Some(metrics_server) = metrics_server {
+            metrics_server.abort();
+        }
+        // The tracker saw the same signal and is writing out its pending opens
+        #[cfg(feature = "usage")]
+        if let Some(tracker) = tracker {
+            let _ = tokio::time::timeout(Duration::from_secs(5), tracker).await;
+        }
+        info!("Daemon stopped");
         Ok(())
     }
     
-    /// Clean PyTorch cache
-    async fn clean_pytorch_cache(&self, _dry_run: bool) -> Result<()> {
-        debug!("Cleaning PyTorch cache");
-        
-        // PyTorch doesn't have a built-in cache cleanup command,
-        // so we rely on the resource manager to clean the cache directories
-        // This is already handled in clean_ml_model_caches
+    /// Advance cache files through the lifecycle tiers: report, archive, delete
+    pub async fn run_lifecycle(&self, dry_run: bool) -> Result<LifecycleSummary> {
+        lifecycle::run_pass(&self.config, &self.resource_manager, dry_run).await
+    }
+    
+    /// Permanently delete quarantined runs older than `quarantine_ttl_days`
+    fn purge_expired_quarantine(&self) {
+        let Some(root) = quarantine::quarantine_dir() else {
+            return;
+        };
         
-        Ok(())
+        let ttl = Duration::from_secs(self.config.quarantine_ttl_days as u64 * 24 * 3600);
+        match quarantine::purge(&root, Some(ttl), false) {
+            Ok(summary) if summary.runs > 0 => info!(
+                "Purged {} expired quarantined runs ({:.2} MB)",
+                summary.runs,
+                summary.bytes as f64 / 1_048_576.0
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to purge expired quarantine: {}", e),
+        }
     }
     
-    /// Clean TensorFlow cache
-    async fn clean_tensorflow_cache(&self, _dry_run: bool) -> Result<()> {
-        debug!("Cleaning TensorFlow cache");
+    /// Re-apply the cleanup policy to a single cache root, then the size budgets covering it
+    ///
+    /// A download can push a target over its `target_budgets_gb` long before anything in it
+    /// ages out, so the budgets of the targets stored in or under `root` are checked next,
+    /// and `max_total_cache_gb` last.
+    pub async fn enforce_cache_root(&self, root: &Path, dry_run: bool) -> Result<Vec<CleanupResult>> {
+        info!("Re-evaluating cache policy for {:?}", root);
         
-        // TensorFlow doesn't have a built-in cache cleanup command,
-        // so we rely on the resource manager to clean the cache directories
-        // This is already handled in clean_ml_model_caches
+        let policy = CleanupPolicy::from_config(&self.config);
+        let mut results = self.resource_manager.clean_paths(&[root.to_path_buf()], &policy, dry_run).await?;
         
-        Ok(())
+        let selected = self.selected_targets()?;
+        let mut evicted = HashSet::new();
+        for target in &selected {
+            let roots = target.roots();
+            if !roots.iter().any(|target_root| target_root.starts_with(root) || root.starts_with(target_root)) {
+                continue;
+            }
+            if let Some(&budget_gb) = self.config.target_budgets_gb.get(target.name()) {
+                let freed = results.iter().map(|result| result.bytes_freed).sum();
+                self.resource_manager.start_target(target.name());
+                results.extend(self.enforce_budget(target.name(), &roots, budget_gb, freed, &mut evicted, dry_run).await?);
+            }
+        }
+        let freed = results.iter().map(|result| result.bytes_freed).sum();
+        results.extend(self.enforce_total_budget(&selected, freed, &mut evicted, dry_run).await?);
+        self.log_cleanup_results("Watch Enforcement", &results);
+        
+        Ok(results)
     }
     
-    /// Check if current directory looks like a Python project
-    async fn current_dir_looks_like_project(&self) -> Result<bool> {
-        let current_dir = std::env::current_dir()
-            .map_err(|e| ClearModelError::file_operation(
-                format!("Failed to get current directory: {}", e),
-                None
-            ))?;
-        
-        // Look for common Python project indicators
-        let project_indicators = [
-            "setup.py",
-            "pyproject.toml",
-            "requirements.txt",
-            "Pipfile",
-            "poetry.lock",
-            "__pycache__",
-            ".py",  // Any .py files
-        ];
-        
-        for indicator in &project_indicators {
-            if *indicator == ".py" {
-                // Check for any .py files
-                if let Ok(entries) = std::fs::read_dir(&current_dir) {
-                    for entry in entries.flatten() {
-                        if let Some(ext) = entry.path().extension() {
-                            if ext == "py" {
-                                debug!("Found Python files in current directory");
-                                return Ok(true);
-                            }
-                        }
+    /// Remove the files a cleanup left for needing administrator rights, when `security.privileged` is set
+    ///
+    /// The files go to `rm` (or with pkexec, the removal helper) through `privilege_backend` a
+    /// batch at a time. Since the removal runs as root, each file is checked against the denied
+    /// and allowed roots again and must still be below its cache root once symlinks are
+    /// resolved. Each file is journaled and audited like any other removal; quarantine and
+    /// shredding are refused with `privileged` (see [`ClearModelConfig::check_privileged`]).
+    /// Files refused or still there afterwards are recorded as failed.
+    pub async fn remove_privileged(&mut self, results: &mut [CleanupResult], dry_run: bool) -> Result<()> {
+        let pending: usize = results.iter().map(|result| result.needs_privileges.len()).sum();
+        if !self.config.security.privileged || pending == 0 {
+            return Ok(());
+        }
+        self.config.check_privileged()?;
+        info!("Removing {} files that need administrator rights", pending);
+        
+        for result in results.iter_mut() {
+            let mut removable = Vec::new();
+            for path in std::mem::take(&mut result.needs_privileges) {
+                match self.privileged_candidate(&result.path, &path) {
+                    Ok(metadata) => removable.push((path, metadata)),
+                    Err(e) => result.failed.push(FailedFile { path, error: e.to_string() }),
+                }
+            }
+            
+            for batch in removable.chunks(PRIVILEGED_BATCH) {
+                if dry_run {
+                    let paths: Vec<&str> = batch.iter().filter_map(|(path, _)| path.to_str()).collect();
+                    self.remove_with_privileges(&paths, true).await?;
+                    result.files_removed += batch.len() as u64;
+                    result.bytes_freed += batch.iter().map(|(_, metadata)| metadata.len()).sum::<u64>();
+                    continue;
+                }
+                
+                // Hashed for the journal and audit log while this user can still see the files
+                let mut prepared = Vec::new();
+                for (path, metadata) in batch {
+                    match self.resource_manager.prepare_privileged(path, metadata.clone()) {
+                        Ok(removal) => prepared.push(removal),
+                        Err(e) => result.failed.push(FailedFile { path: path.clone(), error: e.to_string() }),
                     }
                 }
-            } else {
-                let indicator_path = current_dir.join(indicator);
-                if indicator_path.exists() {
-                    debug!("Found project indicator: {:?}", indicator);
-                    return Ok(true);
+                let paths: Vec<&str> = prepared.iter().filter_map(|removal| removal.path.to_str()).collect();
+                if paths.is_empty() {
+                    continue;
+                }
+                if let Err(e) = self.remove_with_privileges(&paths, false).await {
+                    result.failed.extend(prepared.into_iter().map(|removal| FailedFile { path: removal.path, error: e.to_string() }));
+                    continue;
+                }
+                
+                for removal in prepared {
+                    if removal.path.symlink_metadata().is_ok() {
+                        result.failed.push(FailedFile { path: removal.path, error: "Still present after the privileged removal".to_string() });
+                        continue;
+                    }
+                    let (path, size) = (removal.path.clone(), removal.size());
+                    match self.resource_manager.finish_privileged(removal) {
+                        Ok(()) => {
+                            result.files_removed += 1;
+                            result.bytes_freed += size;
+                        }
+                        Err(e) => result.failed.push(FailedFile { path, error: e.to_string() }),
+                    }
                 }
             }
         }
-        
-        debug!("Current directory doesn't appear to be a Python project");
-        Ok(false)
+        Ok(())
     }
-
-    /// Clean Python cache files in the current directory and subdirectories
-    async fn clean_python_cache_files(&self, dry_run: bool) -> Result<CleanupResult> {
-        info!("Cleaning Python cache files in current directory");
+    
+    /// Metadata of a file that may be removed with administrator rights, or why it may not
+    fn privileged_candidate(&self, root: &Path, path: &Path) -> Result<std::fs::Metadata> {
+        if path.to_str().is_none() {
+            return Err(ClearModelError::file_operation("Path is not valid UTF-8", Some(path.to_path_buf())));
+        }
+        SecurityManager::validate_deletion_safety(path, &self.config.security)?;
+        SecurityManager::check_allowed_roots(path, &self.config.security.allowed_roots)?;
         
-        let result = self.resource_manager.clean_python_caches(dry_run).await?;
+        // A directory swapped for a symlink since the scan would point `rm` elsewhere
+        let parent = path.parent().and_then(|parent| parent.canonicalize().ok());
+        if !parent.zip(root.canonicalize().ok()).is_some_and(|(parent, root)| parent.starts_with(root)) {
+            return Err(ClearModelError::path_traversal(path));
+        }
+        let metadata = path.symlink_metadata()
+            .map_err(|e| ClearModelError::file_operation(format!("Failed to get file metadata: {}", e), Some(path.to_path_buf())))?;
+        if metadata.is_dir() {
+            return Err(ClearModelError::file_operation("Not a file", Some(path.to_path_buf())));
+        }
+        SecurityManager::check_owner(path, &metadata, &self.config.security)?;
+        Ok(metadata)
+    }
+    
+    /// Scan or clean one target under the watchdog, abandoning it once its own limit or the
+    /// run's is reached
+    ///
+    /// Abandoning the work drops it, which aborts its cleanup tasks; a removal already under
+    /// way on a worker thread still finishes.
+    async fn watched<T>(&self, target: &str, deadline: Option<Instant>, work: impl Future<Output = Result<T>>) -> Result<T> {
+        let timeouts = &self.config.timeouts;
+        let own = timeouts.targets.get(target).copied().or(timeouts.target_secs);
+        let left = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let (limit, timed_out) = match (own, left) {
+            (_, Some(left)) if own.is_none_or(|secs| left < Duration::from_secs(secs)) => {
+                (left, ClearModelError::TimedOut { what: "The run".to_string(), secs: timeouts.run_secs.unwrap_or_default() })
+            }
+            (Some(secs), _) => {
+                (Duration::from_secs(secs), ClearModelError::TimedOut { what: format!("Target {}", target), secs })
+            }
+            (None, _) => return work.await,
+        };
         
-        Ok(result)
+        match tokio::time::timeout(limit, work).await {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                warn!("{}; moving on to the remaining targets", timed_out);
+                Err(timed_out)
+            }
+        }
     }
     
-    /// Execute a command with sudo if needed
-    async fn execute_sudo_command(&mut self, command: &str, args: &[&str], dry_run: bool) -> Result<()> {
+    /// Results recording that the watchdog abandoned a target
+    fn abandoned(target: &dyn CacheTarget, timed_out: &ClearModelError) -> Vec<CleanupResult> {
+        target.roots().into_iter()
+            .map(|root| CleanupResult::aborted(root, timed_out.to_string()))
+            .collect()
+    }
+    
+    /// Remove files with administrator rights through the configured privilege backend
+    async fn remove_with_privileges(&mut self, paths: &[&str], dry_run: bool) -> Result<()> {
+        let escalator: Arc<dyn PrivilegeEscalator> = match &self.escalator {
+            Some(escalator) => Arc::clone(escalator),
+            None => Arc::from(privilege::escalator(
+                self.config.security.privilege_backend,
+                self.config.security.askpass_helper.as_deref(),
+                &self.commands
+            )?),
+        };
+        let (command, args) = escalator.removal(paths);
         if dry_run {
-            info!("Would execute: sudo {} {}", command, args.join(" "));
+            info!("Would execute through {}: {} {}", escalator.name(), command, args.join(" "));
             return Ok(());
         }
         
-        let sudo_password = self.env_manager.get_sudo_password()?;
-        
-        let mut cmd = AsyncCommand::new("sudo");
-        cmd.arg("-S") // Read password from stdin
-            .arg(command)
-            .args(args)
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped());
-        
-        let mut child = cmd.spawn()
-            .map_err(|e| ClearModelError::file_operation(
-                format!("Failed to spawn sudo command: {}", e),
-                None
-            ))?;
-        
-        // Send password to sudo
-        if let Some(stdin) = child.stdin.as_mut() {
-            use tokio::io::AsyncWriteExt;
-            let password_with_newline = format!("{}\n", sudo_password.expose_secret());
-            stdin.write_all(password_with_newline.as_bytes()).await
-                .map_err(|e| ClearModelError::file_operation(
-                    format!("Failed to write password to sudo: {}", e),
-                    None
-                ))?;
-        }
-        
-        let output = child.wait_with_output().await
-            .map_err(|e| ClearModelError::file_operation(
-                format!("Failed to wait for sudo command: {}", e),
-                None
-            ))?;
+        let password = if escalator.needs_password() {
+            Some(self.env_manager.get_sudo_password(&self.commands)?.clone())
+        } else {
+            None
+        };
         
-        if !output.status.success() {
-            return Err(ClearModelError::file_operation(
-                format!(
-                    "Sudo command failed: {}",
-                    String::from_utf8_lossy(&output.stderr)
-                ),
-                None
-            ));
-        }
+        // Backends wait on a prompt or the child, so keep them off the async workers
+        let command = command.to_string();
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        tokio::task::spawn_blocking(move || {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            escalator.run(&command, &args, password.as_ref())
+        })
+        .await
+        .map_err(|e| ClearModelError::environment(format!("Privileged command did not finish: {}", e)))??;
         
-        debug!("Sudo command executed successfully");
+        debug!("Privileged command executed successfully");
         Ok(())
     }
     
@@ -281,10 +1066,50 @@ impl CacheCleaner {
             total_errors
         );
         
+        let mut categories = CategoryBreakdown::new();
+        for result in results {
+            categories::merge(&mut categories, &result.categories);
+        }
+        for (kind, stats) in &categories {
+            info!(
+                "  {:<12} {:.2} MB scanned, {:.2} MB removed",
+                kind.label(),
+                stats.scanned_bytes as f64 / 1_048_576.0,
+                stats.deleted_bytes as f64 / 1_048_576.0
+            );
+        }
+        
+        let needs_privileges: Vec<&PathBuf> = results.iter().flat_map(|r| &r.needs_privileges).collect();
+        if !needs_privileges.is_empty() {
+            warn!(
+                "{}: skipped {} files owned by other users or in directories you cannot write to; run with --privileged to clean these (and --include-other-users for other users' files)",
+                category,
+                needs_privileges.len()
+            );
+            for path in needs_privileges.iter().take(10) {
+                info!("  needs privileges: {}", path.display());
+            }
+            for path in needs_privileges.iter().skip(10) {
+                debug!("  needs privileges: {}", path.display());
+            }
+        }
+        
+        let failed: Vec<&FailedFile> = results.iter().flat_map(|r| &r.failed).collect();
+        if !failed.is_empty() {
+            warn!("{}: {} files could not be removed and were left in place", category, failed.len());
+            for file in failed.iter().take(10) {
+                warn!("  {}: {}", file.path.display(), file.error);
+            }
+            for file in failed.iter().skip(10) {
+                debug!("  {}: {}", file.path.display(), file.error);
+            }
+        }
+        
         if total_errors > 0 {
             warn!("Errors encountered during {} cleanup:", category);
+            let threshold = self.config.logging.dedup_threshold;
             for result in results {
-                for error in &result.errors {
+                for error in ErrorDeduplicator::collapse(&result.errors, threshold) {
                     warn!("  {}: {}", result.path.display(), error);
                 }
             }
@@ -302,17 +1127,55 @@ impl CacheCleaner {
         }
     }
     
-    /// Get current operation statistics
-    pub fn get_operation_stats(&self) -> Vec<(String, crate::resource_manager::OperationStats)> {
-        self.resource_manager.get_operation_stats()
+    /// Log how much space each filesystem gained, next to the size of what was removed from it
+    fn report_disk_space(&self, before: &[FilesystemSpace], after: &[FilesystemSpace], results: &[CleanupResult]) {
+        let gb = |bytes: f64| bytes / 1_073_741_824.0;
+        let changes = disk_space::compare(before, after, results);
+        for change in &changes {
+            info!(
+                "{}: {:.2} GB free before, {:.2} GB after ({:+.2} GB; {:.2} GB of files removed)",
+                change.mount_point.display(),
+                gb(change.available_before as f64),
+                gb(change.available_after as f64),
+                gb(change.freed() as f64),
+                gb(change.bytes_removed as f64)
+            );
+            // Small differences are other processes writing or filesystem metadata
+            let missing = change.bytes_removed as i64 - change.freed();
+            if change.bytes_removed >= MIN_UNFREED_BYTES && missing > change.bytes_removed as i64 / 2 {
+                warn!(
+                    "{}: {:.2} GB of files were removed but only {:+.2} GB became free; hardlinked or reflinked copies, {}files still open or filesystem snapshots keep the rest in use",
+                    change.mount_point.display(),
+                    gb(change.bytes_removed as f64),
+                    gb(change.freed() as f64),
+                    if self.config.quarantine { "quarantined files, " } else { "" }
+                );
+            }
+        }
+        *self.disk_space.lock().unwrap_or_else(|e| e.into_inner()) = changes;
+    }
+    
+    /// Free space on each filesystem holding a cache before and after the last cleanup
+    /// (empty after a dry run)
+    pub fn disk_space(&self) -> Vec<SpaceChange> {
+        self.disk_space.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+    
+    /// External commands the targets ran or were refused, with what they printed
+    pub fn commands(&self) -> Vec<CommandRecord> {
+        self.commands.records()
+    }
+    
+    /// Per-directory, per-target and run-wide statistics of what was cleaned so far
+    pub async fn stats(&self) -> StatsSnapshot {
+        self.resource_manager.stats().await
     }
     
     /// Estimate space that would be freed without actually cleaning
     pub async fn estimate_cleanup_space(&self) -> Result<u64> {
         info!("Estimating cleanup space");
         
-        let results = self.resource_manager.clean_all_caches(true).await?;
-        let total_bytes: u64 = results.iter().map(|r| r.bytes_freed).sum();
+        let total_bytes = self.resource_manager.plan().await?.total_bytes();
         
         info!(
             "Estimated cleanup space: {:.2} MB",
@@ -332,11 +1195,77 @@ impl CacheCleaner {
     }
 }
 
+/// Run lock for one watch or daemon pass, with `Some(None)` for dry runs, which need none
+///
+/// `None` means another instance is cleaning and this pass is skipped; the next trigger
+/// picks up whatever it leaves.
+#[cfg(feature = "daemon")]
+fn pass_lock(dry_run: bool) -> Option<Option<RunLock>> {
+    if dry_run {
+        return Some(None);
+    }
+    match run_lock::lock_path().and_then(|path| RunLock::try_acquire_at(&path)) {
+        Ok(Some(lock)) => Some(Some(lock)),
+        Ok(None) => {
+            info!("Skipping this pass: another clearmodel instance is cleaning");
+            None
+        }
+        Err(e) => {
+            warn!("Skipping this pass: failed to take the run lock: {}", e);
+            None
+        }
+    }
+}
+
+/// A model's removable files as a planned directory, rooted at its own directory where it has one
+fn planned_model(cached: &CachedModel, rule: &'static str) -> PlannedDirectory {
+    let root = cached.dirs.first()
+        .cloned()
+        .or_else(|| cached.files.first().and_then(|file| file.parent()).map(Path::to_path_buf))
+        .unwrap_or_default();
+    let mut planned = PlannedDirectory { root, ..PlannedDirectory::default() };
+    
+    for path in &cached.files {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            continue;
+        };
+        categories::record(&mut planned.categories, path, metadata.len(), false);
+        planned.files.push(PlannedFile {
+            path: path.clone(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            rule,
+        });
+    }
+    planned
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::AuditConfig;
+    use crate::targets::PACKAGE_CACHE_GROUP;
+    use filetime::FileTime;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::time::SystemTime;
     use tempfile::TempDir;
     
+    /// A HuggingFace model in `root` with one 4 KiB blob, last used `age` ago
+    fn write_model(root: &Path, name: &str, age: Duration) -> PathBuf {
+        let blobs = root.join(format!("models--org--{}/blobs", name));
+        fs::create_dir_all(&blobs).unwrap();
+        let blob = blobs.join("weights");
+        fs::write(&blob, vec![1u8; 4096]).unwrap();
+        let used = FileTime::from_system_time(SystemTime::now() - age);
+        filetime::set_file_times(&blob, used, used).unwrap();
+        blob
+    }
+    
+    async fn cleaner(config: ClearModelConfig) -> CacheCleaner {
+        CacheCleaner::new(config, EnvironmentManager::detached()).await.unwrap()
+    }
+    
     #[tokio::test]
     async fn test_cache_cleaner_creation() {
         // This test requires environment setup, so we'll skip it in CI
@@ -361,6 +1290,7 @@ mod tests {
     }
     
     #[tokio::test]
+    #[allow(clippy::field_reassign_with_default)]
     async fn test_cleanup_estimation() {
         // Create a temporary directory structure for testing
         let temp_dir = TempDir::new().unwrap();
@@ -376,4 +1306,179 @@ mod tests {
         // Note: Full test would require proper environment setup
         // This demonstrates the structure
     }
-} 
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_download_over_a_target_budget_evicts_least_recently_used_models() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("hub");
+        let old = write_model(&root, "old", Duration::from_secs(3600));
+        let new = write_model(&root, "new", Duration::ZERO);
+        
+        // Both models are well within max_cache_age_days, but together over the budget
+        let config = ClearModelConfig {
+            cache_paths: vec![root.clone()],
+            target_budgets_gb: BTreeMap::from([("hub".to_string(), 6000.0 / 1_073_741_824.0)]),
+            ..ClearModelConfig::default()
+        };
+        let results = cleaner(config).await.enforce_cache_root(&root, false).await.unwrap();
+        
+        assert_eq!(results.iter().map(|result| result.files_removed).sum::<u64>(), 1);
+        assert!(!old.exists());
+        assert!(new.exists());
+    }
+    
+    #[tokio::test]
+    async fn test_read_only_home_leaves_recovery_off_and_refuses_quarantine() {
+        let read_only = Capabilities { writable_home: false, ..Capabilities::full() };
+        let config = ClearModelConfig { checkpoint: true, ..ClearModelConfig::default() };
+        let run = cleaner(config.clone()).await.with_capabilities(read_only.clone()).for_run("read-only").unwrap();
+        assert!(run.checkpoint.is_none());
+        
+        let quarantined = ClearModelConfig { quarantine: true, ..config };
+        let refused = cleaner(quarantined).await.with_capabilities(read_only).for_run("read-only");
+        assert!(matches!(refused, Err(ClearModelError::Environment { .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_target_selection_rejects_unknown_names_and_lets_excludes_win() {
+        let typo = cleaner(ClearModelConfig::default()).await
+            .with_target_filter(TargetFilter::only(vec!["pip".to_string(), "ppi".to_string()]));
+        let error = typo.selected_targets().err().unwrap().to_string();
+        assert!(error.contains("Unknown target(s): ppi") && error.contains("known targets"));
+        
+        let excluded_typo = cleaner(ClearModelConfig::default()).await
+            .with_target_filter(TargetFilter::default().excluding(vec!["ppi".to_string()]));
+        assert!(excluded_typo.selected_targets().is_err());
+        
+        // Naming pip both ways leaves it out, even though its group is selected too
+        let filter = TargetFilter::only(vec![PACKAGE_CACHE_GROUP.to_string(), "pip".to_string()])
+            .excluding(vec!["pip".to_string()]);
+        let cleaner = cleaner(ClearModelConfig::default()).await.with_target_filter(filter);
+        let selected: Vec<&str> = cleaner.selected_targets().unwrap().iter().map(|target| target.name()).collect();
+        assert_eq!(selected, ["uv", "poetry"]);
+    }
+    
+    /// Runs privileged commands as the current user, in place of sudo
+    struct Unprivileged;
+    
+    impl PrivilegeEscalator for Unprivileged {
+        fn name(&self) -> &'static str {
+            "unprivileged"
+        }
+        
+        fn run(&self, program: &str, args: &[&str], _password: Option<&secrecy::Secret<String>>) -> Result<()> {
+            match std::process::Command::new(program).args(args).status()?.success() {
+                true => Ok(()),
+                false => Err(ClearModelError::cache(format!("{} failed", program))),
+            }
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_privileged_removals_are_journaled_and_audited() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        let file = cache.join("root-owned.bin");
+        fs::write(&file, b"weights").unwrap();
+        let state = temp_dir.path().join("state");
+        
+        let mut config = ClearModelConfig::default();
+        config.security.privileged = true;
+        config.audit = AuditConfig { enabled: true, path: Some(state.join("audit.log")), key_file: Some(state.join("audit.key")) };
+        let journal = Journal::in_dir(&state.join("journal"), "privileged", &config.journal).unwrap();
+        let audit = AuditLog::open("privileged", &config.audit, config.journal.hash_max_mb).unwrap();
+        let mut cleaner = cleaner(config.clone()).await
+            .with_journal(Arc::new(journal))
+            .with_audit(Arc::new(audit))
+            .with_escalator(Arc::new(Unprivileged));
+        
+        let mut results = [CleanupResult { needs_privileges: vec![file.clone()], errors: Vec::new(), ..CleanupResult::aborted(cache, String::new()) }];
+        cleaner.remove_privileged(&mut results, false).await.unwrap();
+        assert!(!file.exists());
+        assert_eq!((results[0].files_removed, results[0].bytes_freed), (1, 7));
+        assert!(results[0].failed.is_empty());
+        
+        let (_, journal_path) = crate::journal::latest_run(&state.join("journal")).unwrap().unwrap();
+        let entries = crate::journal::read_entries(&journal_path).unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.path.as_path()).collect::<Vec<_>>(), [file.as_path()]);
+        let audit_path = config.audit.path.clone().unwrap();
+        let key = crate::audit::load_key(&config.audit, &audit_path, false).unwrap();
+        assert_eq!(crate::audit::verify(&audit_path, &key).unwrap(), 1);
+        assert!(fs::read_to_string(&audit_path).unwrap().contains("root-owned.bin"));
+        
+        // Neither can be honored for a file root removes
+        let quarantined = ClearModelConfig { quarantine: true, ..config };
+        assert!(matches!(quarantined.check_privileged(), Err(ClearModelError::Configuration { .. })));
+    }
+    
+    /// A target that hangs in `scan` or `clean` where told to, and otherwise removes one file
+    struct Hanging {
+        name: &'static str,
+        root: PathBuf,
+        in_scan: bool,
+        in_clean: bool,
+    }
+    
+    impl CacheTarget for Hanging {
+        fn name(&self) -> &str {
+            self.name
+        }
+        
+        fn group(&self) -> &str {
+            "hanging"
+        }
+        
+        fn detect(&self) -> bool {
+            true
+        }
+        
+        fn roots(&self) -> Vec<PathBuf> {
+            vec![self.root.clone()]
+        }
+        
+        fn scan<'a>(&'a self, _cx: &'a TargetContext<'a>) -> cache_target::TargetFuture<'a, CleanupPlan> {
+            Box::pin(async move {
+                if self.in_scan {
+                    std::future::pending::<()>().await;
+                }
+                Ok(CleanupPlan::default())
+            })
+        }
+        
+        fn clean<'a>(&'a self, _cx: &'a TargetContext<'a>, _plan: Option<CleanupPlan>) -> cache_target::TargetFuture<'a, Vec<CleanupResult>> {
+            Box::pin(async move {
+                if self.in_clean {
+                    std::future::pending::<()>().await;
+                }
+                Ok(vec![CleanupResult { files_removed: 1, errors: Vec::new(), ..CleanupResult::aborted(self.root.clone(), String::new()) }])
+            })
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_watchdog_abandons_hanging_targets_and_cleans_the_rest() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = ClearModelConfig::default();
+        config.timeouts.targets = BTreeMap::from([("stuck-scan".to_string(), 1), ("stuck-clean".to_string(), 1)]);
+        let target = |name, in_scan, in_clean| Box::new(Hanging { name, root: temp_dir.path().join(name), in_scan, in_clean });
+        
+        // The checkpoint makes every target scan first, so both phases are watched
+        let cleaner = cleaner(config).await
+            .with_target(target("stuck-scan", true, false))
+            .with_target(target("stuck-clean", false, true))
+            .with_target(target("quick", false, false))
+            .with_target_filter(TargetFilter::only(vec!["stuck-scan".to_string(), "stuck-clean".to_string(), "quick".to_string()]))
+            .with_checkpoint(Arc::new(CheckpointWriter::new(temp_dir.path().join("checkpoint.json"))));
+        let results = cleaner.clean_selected_caches(false).await.unwrap();
+        
+        let outcome: Vec<(PathBuf, u64, Vec<String>)> = results.into_iter()
+            .map(|result| (result.path, result.files_removed, result.errors))
+            .collect();
+        assert_eq!(outcome, [
+            (temp_dir.path().join("stuck-scan"), 0, vec!["Target stuck-scan did not finish within 1s".to_string()]),
+            (temp_dir.path().join("stuck-clean"), 0, vec!["Target stuck-clean did not finish within 1s".to_string()]),
+            (temp_dir.path().join("quick"), 1, Vec::new()),
+        ]);
+    }
+}
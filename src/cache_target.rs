@@ -0,0 +1,990 @@
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::command_runner::CommandRunner;
+use crate::config::ClearModelConfig;
+use crate::containers::{self, ContainerEngine};
+use crate::data_caches::{self, DataCacheKind};
+use crate::engine_caches;
+use crate::experiments::{self, WandbProject};
+use crate::errors::{ClearModelError, Result};
+use crate::hf_caches::{self, HfCacheInstance};
+use crate::hf_revisions;
+use crate::image_models::{self, ImageStore};
+use crate::notebooks::NotebookKind;
+use crate::plan::CleanupPlan;
+use crate::policy::CleanupPolicy;
+use crate::resource_manager::{CleanupResult, ResourceManager};
+use crate::targets::{self, COMPILE_CACHE_GROUP, CONTAINER_TARGET, DATA_CACHE_GROUP, ENGINE_CACHE_GROUP, EXPERIMENT_GROUP, GPU_CACHE_GROUP, NOTEBOOK_GROUP, GGUF_GROUP, HF_GROUP, IMAGE_MODEL_GROUP, ML_TARGET, PACKAGE_CACHE_GROUP, PYTHON_TARGET};
+
+/// Future returned by the asynchronous steps of a [`CacheTarget`]
+pub type TargetFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// What a target works with during a run
+pub struct TargetContext<'a> {
+    pub config: &'a ClearModelConfig,
+    
+    /// Walks, plans and removes files with the run's journal, quarantine and progress
+    pub resource_manager: &'a ResourceManager,
+    
+    /// Runs external tools on the allow-list and records their output for the report
+    pub commands: &'a CommandRunner,
+    pub dry_run: bool,
+}
+
+/// Something `clearmodel clean` can clean, selectable by name with `--only` and `--exclude-target`
+///
+/// The built-in frameworks are implemented here; library users can add their own with
+/// `CacheCleaner::with_target`.
+pub trait CacheTarget: Send + Sync {
+    /// Name given on the command line
+    fn name(&self) -> &str;
+    
+    /// Group the target belongs to, which selects all its members at once
+    fn group(&self) -> &str;
+    
+    /// Whether the target runs without being named explicitly
+    fn on_by_default(&self) -> bool {
+        true
+    }
+    
+    /// Whether there is anything of the target's on this machine
+    fn detect(&self) -> bool;
+    
+    /// Directories holding the target's models, which size budgets apply to
+    fn roots(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+    
+    /// What cleaning would remove, without removing anything
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan>;
+    
+    /// Remove the files of a plan from `scan`, or walk and clean in one streaming pass without one
+    ///
+    /// Dry runs never come with a plan.
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>>;
+}
+
+/// The built-in targets for a configuration
+pub fn builtin_targets(config: &ClearModelConfig) -> Vec<Box<dyn CacheTarget>> {
+    let mut builtin: Vec<Box<dyn CacheTarget>> = Vec::new();
+    for path in config.cache_paths.iter().filter(|path| !config.is_hf_cache_instance(path)) {
+        builtin.push(Box::new(ModelCacheTarget::new(path.clone())));
+    }
+    for instance in hf_caches::instances(config) {
+        builtin.push(Box::new(HfCacheTarget { instance }));
+    }
+    for store in image_models::stores(config) {
+        builtin.push(Box::new(ImageStoreTarget { store }));
+    }
+    builtin.push(Box::new(PythonBytecodeTarget));
+    for (name, path) in targets::package_cache_locations() {
+        builtin.push(Box::new(PackageCacheTarget { name, path, enabled: config.package_caches.enabled }));
+    }
+    for (name, path) in targets::compile_cache_locations() {
+        builtin.push(Box::new(CompileCacheTarget { name, path, enabled: config.compile_caches.enabled }));
+    }
+    for (name, path) in targets::gguf_store_locations() {
+        builtin.push(Box::new(GgufStoreTarget { name, path, enabled: config.gguf_stores.enabled }));
+    }
+    for (name, roots) in engine_caches::locations() {
+        builtin.push(Box::new(EngineCacheTarget { name, roots, enabled: config.engine_caches.enabled }));
+    }
+    for (name, roots) in targets::gpu_cache_locations(config) {
+        builtin.push(Box::new(GpuCacheTarget { name, roots, enabled: config.gpu_caches.enabled }));
+    }
+    for kind in DataCacheKind::ALL {
+        builtin.push(Box::new(DataCacheTarget { kind, roots: kind.locations(), enabled: kind.settings(config).enabled }));
+    }
+    builtin.push(Box::new(DvcCacheTarget {
+        caches: experiments::dvc_caches(config),
+        workspaces: config.dvc.workspaces.clone(),
+        enabled: config.dvc.enabled,
+    }));
+    builtin.push(Box::new(MlflowTarget { stores: experiments::mlflow_stores(config), enabled: config.mlflow.enabled }));
+    builtin.push(Box::new(WandbTarget {
+        caches: experiments::wandb_caches(),
+        projects: experiments::wandb_projects(config),
+        enabled: config.wandb.enabled,
+    }));
+    for kind in NotebookKind::ALL {
+        builtin.push(Box::new(NotebookTarget { kind, roots: kind.locations(config), enabled: config.notebooks.enabled }));
+    }
+    builtin.push(Box::new(ContainerTarget { engines: containers::engines(config), enabled: config.containers.enabled }));
+    #[cfg(feature = "remote")]
+    for target in crate::remote_store::targets(config) {
+        builtin.push(Box::new(target));
+    }
+    builtin
+}
+
+/// A configured ML cache directory, cleaned under the main policy
+pub struct ModelCacheTarget {
+    name: String,
+    path: PathBuf,
+}
+
+impl ModelCacheTarget {
+    pub fn new(path: PathBuf) -> Self {
+        Self { name: targets::cache_dir_target(&path), path }
+    }
+}
+
+impl CacheTarget for ModelCacheTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn group(&self) -> &str {
+        ML_TARGET
+    }
+    
+    fn detect(&self) -> bool {
+        self.path.exists()
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::from_config(cx.config);
+            cx.resource_manager.plan_paths(std::slice::from_ref(&self.path), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            let mut results = match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await?,
+                None => cx.resource_manager.clean_cache_paths(std::slice::from_ref(&self.path), cx.dry_run).await?,
+            };
+            let policy = CleanupPolicy::from_config(cx.config);
+            results.extend(hf_revisions::prune(cx.resource_manager, &self.path, &policy, cx.dry_run).await?);
+            
+            if self.name == "huggingface" {
+                if let Err(e) = clean_with_huggingface_cli(cx).await {
+                    warn!("Failed to clean HuggingFace cache: {}", e);
+                }
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// Run `huggingface-cli delete-cache` when it is allowed and installed
+async fn clean_with_huggingface_cli(cx: &TargetContext<'_>) -> Result<()> {
+    debug!("Attempting to clean HuggingFace cache");
+    
+    // huggingface-cli knows nothing about the keep-list, so never hand it the cache
+    if !cx.config.pinned_models.is_empty() {
+        debug!("Models are pinned, skipping huggingface-cli delete-cache");
+        return Ok(());
+    }
+    
+    if !cx.commands.installed("huggingface-cli") {
+        debug!("huggingface-cli not available or not in commands.allowed, skipping");
+        return Ok(());
+    }
+    
+    if cx.dry_run {
+        // HuggingFace CLI doesn't have a dry-run flag, so we'll just report
+        info!("Would run: huggingface-cli delete-cache");
+        return Ok(());
+    }
+    
+    // --disable-tui avoids interactive prompts
+    let args = ["delete-cache".to_string(), "--disable-tui".to_string()];
+    let record = cx.commands.run("huggingface-cli", &args).await?;
+    if record.success() {
+        info!("Successfully cleaned HuggingFace cache");
+        debug!("HuggingFace cleanup output: {}", record.stdout);
+    } else {
+        warn!("HuggingFace cache cleanup failed: {}", record.stderr.trim());
+    }
+    
+    Ok(())
+}
+
+/// A HuggingFace cache instance with its own policy and budget
+pub struct HfCacheTarget {
+    instance: HfCacheInstance,
+}
+
+impl CacheTarget for HfCacheTarget {
+    fn name(&self) -> &str {
+        &self.instance.name
+    }
+    
+    fn group(&self) -> &str {
+        HF_GROUP
+    }
+    
+    fn detect(&self) -> bool {
+        self.instance.path.exists()
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![self.instance.path.clone()]
+    }
+    
+    /// Only the age policy is planned; eviction to fit the budget follows when cleaning
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(cx.resource_manager.plan_paths(std::slice::from_ref(&self.instance.path), &self.instance.policy))
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(self.instance.clean(cx.resource_manager, plan, cx.dry_run))
+    }
+}
+
+/// The `models` folder of a ComfyUI, AUTOMATIC1111 or InvokeAI installation
+///
+/// Only model weights are ever removed; previews, configs and the rest of the
+/// installation are left alone.
+pub struct ImageStoreTarget {
+    store: ImageStore,
+}
+
+impl CacheTarget for ImageStoreTarget {
+    fn name(&self) -> &str {
+        &self.store.name
+    }
+    
+    fn group(&self) -> &str {
+        IMAGE_MODEL_GROUP
+    }
+    
+    fn detect(&self) -> bool {
+        self.store.models_dir().is_dir()
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![self.store.models_dir()]
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            cx.resource_manager.plan_paths(&[self.store.models_dir()], &self.store.policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => cx.resource_manager.clean_paths(&[self.store.models_dir()], &self.store.policy, cx.dry_run).await,
+            }
+        })
+    }
+}
+
+/// Python bytecode under the current directory, when it looks like a Python project
+pub struct PythonBytecodeTarget;
+
+impl PythonBytecodeTarget {
+    fn current_dir() -> Result<PathBuf> {
+        std::env::current_dir().map_err(|e| ClearModelError::file_operation(
+            format!("Failed to get current directory: {}", e),
+            None
+        ))
+    }
+}
+
+impl CacheTarget for PythonBytecodeTarget {
+    fn name(&self) -> &str {
+        PYTHON_TARGET
+    }
+    
+    fn group(&self) -> &str {
+        PYTHON_TARGET
+    }
+    
+    fn detect(&self) -> bool {
+        let Ok(current_dir) = Self::current_dir() else {
+            return false;
+        };
+        
+        // Look for common Python project indicators, or any .py file
+        let project_indicators = ["setup.py", "pyproject.toml", "requirements.txt", "Pipfile", "poetry.lock", "__pycache__"];
+        if let Some(indicator) = project_indicators.iter().find(|indicator| current_dir.join(indicator).exists()) {
+            debug!("Found project indicator: {:?}", indicator);
+            return true;
+        }
+        let has_python_files = std::fs::read_dir(&current_dir)
+            .map(|entries| entries.flatten().any(|entry| entry.path().extension().is_some_and(|ext| ext == "py")))
+            .unwrap_or(false);
+        if has_python_files {
+            debug!("Found Python files in current directory");
+        }
+        has_python_files
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::from_config(cx.config);
+            cx.resource_manager.plan_paths(&[Self::current_dir()?], &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => Ok(vec![cx.resource_manager.clean_python_caches(cx.dry_run).await?]),
+            }
+        })
+    }
+}
+
+/// A pip, uv or poetry cache, cleaned with wheel-aware retention
+pub struct PackageCacheTarget {
+    name: &'static str,
+    path: PathBuf,
+    
+    /// `package_caches.enabled`; a disabled cache still runs when named with `--only`
+    enabled: bool,
+}
+
+impl CacheTarget for PackageCacheTarget {
+    fn name(&self) -> &str {
+        self.name
+    }
+    
+    fn group(&self) -> &str {
+        PACKAGE_CACHE_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.path.exists()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::for_package_caches(cx.config);
+            cx.resource_manager.plan_paths(std::slice::from_ref(&self.path), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let policy = CleanupPolicy::for_package_caches(cx.config);
+                    cx.resource_manager.clean_paths(std::slice::from_ref(&self.path), &policy, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// A JAX, Triton or TorchInductor compilation cache, cleaned under its shorter age limit
+pub struct CompileCacheTarget {
+    name: &'static str,
+    path: PathBuf,
+    
+    /// `compile_caches.enabled`; a disabled cache still runs when named with `--only`
+    enabled: bool,
+}
+
+impl CacheTarget for CompileCacheTarget {
+    fn name(&self) -> &str {
+        self.name
+    }
+    
+    fn group(&self) -> &str {
+        COMPILE_CACHE_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.path.exists()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::for_compile_caches(cx.config);
+            cx.resource_manager.plan_paths(std::slice::from_ref(&self.path), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let policy = CleanupPolicy::for_compile_caches(cx.config);
+                    cx.resource_manager.clean_paths(std::slice::from_ref(&self.path), &policy, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// A llama.cpp, LM Studio or GPT4All model store; only GGUF (and legacy GGML `.bin`) files are removed
+pub struct GgufStoreTarget {
+    name: &'static str,
+    path: PathBuf,
+    
+    /// `gguf_stores.enabled`; a disabled store still runs when named with `--only`
+    enabled: bool,
+}
+
+impl CacheTarget for GgufStoreTarget {
+    fn name(&self) -> &str {
+        self.name
+    }
+    
+    fn group(&self) -> &str {
+        GGUF_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.path.exists()
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![self.path.clone()]
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::for_gguf_stores(cx.config);
+            cx.resource_manager.plan_paths(std::slice::from_ref(&self.path), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let policy = CleanupPolicy::for_gguf_stores(cx.config);
+                    cx.resource_manager.clean_paths(std::slice::from_ref(&self.path), &policy, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// An ONNX Runtime or TensorRT engine cache, holding engines built per GPU and driver
+pub struct EngineCacheTarget {
+    name: &'static str,
+    roots: Vec<PathBuf>,
+    
+    /// `engine_caches.enabled`; a disabled cache still runs when named with `--only`
+    enabled: bool,
+}
+
+impl EngineCacheTarget {
+    /// Only one of the usual locations tends to exist
+    fn existing_roots(&self) -> Vec<PathBuf> {
+        self.roots.iter().filter(|root| root.is_dir()).cloned().collect()
+    }
+}
+
+impl CacheTarget for EngineCacheTarget {
+    fn name(&self) -> &str {
+        self.name
+    }
+    
+    fn group(&self) -> &str {
+        ENGINE_CACHE_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.roots.iter().any(|root| root.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.roots.clone()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::for_engine_caches(cx.config);
+            cx.resource_manager.plan_paths(&self.existing_roots(), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let policy = CleanupPolicy::for_engine_caches(cx.config);
+                    cx.resource_manager.clean_paths(&self.existing_roots(), &policy, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// DVC caches, where only objects no workspace's `dvc.lock` or `.dvc` file references are removed
+pub struct DvcCacheTarget {
+    caches: Vec<PathBuf>,
+    workspaces: Vec<PathBuf>,
+    
+    /// `dvc.enabled`; a disabled cache still runs when named with `--only`
+    enabled: bool,
+}
+
+impl DvcCacheTarget {
+    fn unreferenced(&self) -> Vec<PathBuf> {
+        experiments::dvc_unreferenced(&self.caches, &self.workspaces)
+    }
+}
+
+impl CacheTarget for DvcCacheTarget {
+    fn name(&self) -> &str {
+        "dvc"
+    }
+    
+    fn group(&self) -> &str {
+        EXPERIMENT_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.caches.iter().any(|cache| cache.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.caches.clone()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::for_dvc_cache(cx.config);
+            cx.resource_manager.plan_paths(&self.unreferenced(), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let policy = CleanupPolicy::for_dvc_cache(cx.config);
+                    cx.resource_manager.clean_paths(&self.unreferenced(), &policy, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// MLflow file stores, where the artifacts of runs past the retention window are removed
+pub struct MlflowTarget {
+    stores: Vec<PathBuf>,
+    
+    /// `mlflow.enabled`; a disabled store still runs when named with `--only`
+    enabled: bool,
+}
+
+impl MlflowTarget {
+    /// Artifact directories of expired runs, and the policy that removes them whole
+    fn expired(&self, config: &ClearModelConfig) -> (Vec<PathBuf>, CleanupPolicy) {
+        let retention = config.older_than.unwrap_or(Duration::from_secs(config.mlflow.retention_days as u64 * 24 * 3600));
+        let paths = self.stores.iter().flat_map(|store| experiments::mlflow_expired(store, retention)).collect();
+        (paths, data_caches::eviction(&CleanupPolicy::for_compile_caches(config)))
+    }
+}
+
+impl CacheTarget for MlflowTarget {
+    fn name(&self) -> &str {
+        "mlflow"
+    }
+    
+    fn group(&self) -> &str {
+        EXPERIMENT_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.stores.iter().any(|store| store.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.stores.clone()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let (paths, eviction) = self.expired(cx.config);
+            cx.resource_manager.plan_paths(&paths, &eviction).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let (paths, eviction) = self.expired(cx.config);
+                    cx.resource_manager.clean_paths(&paths, &eviction, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// The W&B artifact cache and upload staging, and the media and staging of finished runs,
+/// each project under its own retention
+pub struct WandbTarget {
+    caches: Vec<PathBuf>,
+    projects: Vec<WandbProject>,
+    
+    /// `wandb.enabled`; W&B data is still cleaned when named with `--only`
+    enabled: bool,
+}
+
+impl WandbTarget {
+    /// Directories to clean, grouped by the policy that applies to them
+    fn groups(&self, config: &ClearModelConfig) -> Vec<(Vec<PathBuf>, CleanupPolicy)> {
+        let cache_age = config.older_than.unwrap_or(Duration::from_secs(config.wandb.max_age_days as u64 * 24 * 3600));
+        let caches: Vec<PathBuf> = self.caches.iter().filter(|cache| cache.is_dir()).cloned().collect();
+        let mut groups = vec![(caches, CleanupPolicy::for_wandb(config, cache_age))];
+        for project in &self.projects {
+            groups.push((experiments::wandb_run_staging(&project.runs), CleanupPolicy::for_wandb(config, project.retention)));
+        }
+        groups
+    }
+}
+
+impl CacheTarget for WandbTarget {
+    fn name(&self) -> &str {
+        "wandb"
+    }
+    
+    fn group(&self) -> &str {
+        EXPERIMENT_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.caches.iter().chain(self.projects.iter().map(|project| &project.runs)).any(|path| path.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.caches.iter().cloned().chain(self.projects.iter().map(|project| project.runs.clone())).collect()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let mut plan = CleanupPlan::default();
+            for (paths, policy) in self.groups(cx.config) {
+                plan.directories.extend(cx.resource_manager.plan_paths(&paths, &policy).await?.directories);
+            }
+            Ok(plan)
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            if let Some(plan) = plan {
+                return cx.resource_manager.apply(plan).await;
+            }
+            let mut results = Vec::new();
+            for (paths, policy) in self.groups(cx.config) {
+                results.extend(cx.resource_manager.clean_paths(&paths, &policy, cx.dry_run).await?);
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// Jupyter runtime files, orphaned kernel specs, oversized IPython history or execution caches
+pub struct NotebookTarget {
+    kind: NotebookKind,
+    roots: Vec<PathBuf>,
+    
+    /// `notebooks.enabled`; the group still runs when named with `--only`
+    enabled: bool,
+}
+
+impl CacheTarget for NotebookTarget {
+    fn name(&self) -> &str {
+        self.kind.name()
+    }
+    
+    fn group(&self) -> &str {
+        NOTEBOOK_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.roots.iter().any(|root| root.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.roots.clone()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let (paths, policy) = self.kind.candidates(cx.config, &self.roots);
+            cx.resource_manager.plan_paths(&paths, &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            let (results, paths) = match plan {
+                Some(plan) => {
+                    let paths: Vec<PathBuf> = plan.directories.iter().map(|directory| directory.root.clone()).collect();
+                    (cx.resource_manager.apply(plan).await?, paths)
+                }
+                None => {
+                    let (paths, policy) = self.kind.candidates(cx.config, &self.roots);
+                    (cx.resource_manager.clean_paths(&paths, &policy, cx.dry_run).await?, paths)
+                }
+            };
+            // A kernel spec directory left empty would still be listed by `jupyter kernelspec list`
+            if self.kind == NotebookKind::Kernelspecs && !cx.dry_run {
+                data_caches::remove_emptied(&paths);
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// Docker/Podman images of ML frameworks, pruned through the engine's API rather than the filesystem
+///
+/// Images hold no files clearmodel can plan, so `scan` returns an empty plan and `clean` asks
+/// the engine directly, reporting one result per engine.
+pub struct ContainerTarget {
+    engines: Vec<ContainerEngine>,
+    
+    /// `containers.enabled`; pruning still runs when named with `--only`
+    enabled: bool,
+}
+
+impl CacheTarget for ContainerTarget {
+    fn name(&self) -> &str {
+        CONTAINER_TARGET
+    }
+    
+    fn group(&self) -> &str {
+        CONTAINER_TARGET
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        !self.engines.is_empty()
+    }
+    
+    fn scan<'a>(&'a self, _cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move { Ok(CleanupPlan::default()) })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, _plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            let mut results = Vec::new();
+            for engine in &self.engines {
+                results.push(containers::prune(engine, cx.config, cx.dry_run).await?);
+            }
+            Ok(results)
+        })
+    }
+}
+
+/// A CUDA compute, cuDNN autotune or GL shader cache, all regenerated on demand by the driver or library
+pub struct GpuCacheTarget {
+    name: &'static str,
+    roots: Vec<PathBuf>,
+    
+    /// `gpu_caches.enabled`; a disabled cache still runs when named with `--only`
+    enabled: bool,
+}
+
+impl GpuCacheTarget {
+    fn existing_roots(&self) -> Vec<PathBuf> {
+        self.roots.iter().filter(|root| root.is_dir()).cloned().collect()
+    }
+}
+
+impl CacheTarget for GpuCacheTarget {
+    fn name(&self) -> &str {
+        self.name
+    }
+    
+    fn group(&self) -> &str {
+        GPU_CACHE_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.roots.iter().any(|root| root.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.roots.clone()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let policy = CleanupPolicy::for_gpu_caches(cx.config);
+            cx.resource_manager.plan_paths(&self.existing_roots(), &policy).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            match plan {
+                Some(plan) => cx.resource_manager.apply(plan).await,
+                None => {
+                    let policy = CleanupPolicy::for_gpu_caches(cx.config);
+                    cx.resource_manager.clean_paths(&self.existing_roots(), &policy, cx.dry_run).await
+                }
+            }
+        })
+    }
+}
+
+/// A Whisper, spaCy, NLTK or gensim data cache, where each download is removed as a whole
+/// once none of it was used for the library's age limit
+pub struct DataCacheTarget {
+    kind: DataCacheKind,
+    roots: Vec<PathBuf>,
+    
+    /// `data_caches.<name>.enabled`; a disabled cache still runs when named with `--only`
+    enabled: bool,
+}
+
+impl DataCacheTarget {
+    /// Paths of the expired downloads, and the policy that removes them
+    fn expired(&self, config: &ClearModelConfig) -> (Vec<PathBuf>, CleanupPolicy) {
+        let policy = CleanupPolicy::for_data_cache(config, self.kind.settings(config));
+        (data_caches::expired(self.kind, &self.roots, &policy), data_caches::eviction(&policy))
+    }
+}
+
+impl CacheTarget for DataCacheTarget {
+    fn name(&self) -> &str {
+        self.kind.name()
+    }
+    
+    fn group(&self) -> &str {
+        DATA_CACHE_GROUP
+    }
+    
+    fn on_by_default(&self) -> bool {
+        self.enabled
+    }
+    
+    fn detect(&self) -> bool {
+        self.roots.iter().any(|root| root.exists())
+    }
+    
+    fn roots(&self) -> Vec<PathBuf> {
+        self.roots.clone()
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let (paths, eviction) = self.expired(cx.config);
+            cx.resource_manager.plan_paths(&paths, &eviction).await
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            let (results, paths) = match plan {
+                Some(plan) => {
+                    let paths: Vec<PathBuf> = plan.directories.iter().map(|directory| directory.root.clone()).collect();
+                    (cx.resource_manager.apply(plan).await?, paths)
+                }
+                None => {
+                    let (paths, eviction) = self.expired(cx.config);
+                    (cx.resource_manager.clean_paths(&paths, &eviction, cx.dry_run).await?, paths)
+                }
+            };
+            if !cx.dry_run {
+                data_caches::remove_emptied(&paths);
+            }
+            Ok(results)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[tokio::test]
+    async fn test_builtin_target_scans_and_cleans_its_plan() {
+        let temp_dir = TempDir::new().unwrap();
+        let torch = temp_dir.path().join(".cache/torch");
+        fs::create_dir_all(&torch).unwrap();
+        fs::write(torch.join("hub.pyc"), b"bytecode").unwrap();
+        fs::write(torch.join("model.pt"), b"weights").unwrap();
+        
+        let config = ClearModelConfig { cache_paths: vec![torch.clone()], ..ClearModelConfig::default() };
+        let names: Vec<(String, String)> = builtin_targets(&config).iter()
+            .map(|target| (target.name().to_string(), target.group().to_string()))
+            .collect();
+        assert_eq!(names[0], ("torch".to_string(), ML_TARGET.to_string()));
+        assert!(names.contains(&(PYTHON_TARGET.to_string(), PYTHON_TARGET.to_string())));
+        assert!(names.contains(&("triton".to_string(), COMPILE_CACHE_GROUP.to_string())));
+        assert!(names.contains(&("lm-studio".to_string(), GGUF_GROUP.to_string())));
+        assert!(names.contains(&("nltk".to_string(), DATA_CACHE_GROUP.to_string())));
+        assert!(names.contains(&("tensorrt".to_string(), ENGINE_CACHE_GROUP.to_string())));
+        assert!(names.contains(&("shader-cache".to_string(), GPU_CACHE_GROUP.to_string())));
+        assert!(names.contains(&(CONTAINER_TARGET.to_string(), CONTAINER_TARGET.to_string())));
+        assert!(names.contains(&("mlflow".to_string(), EXPERIMENT_GROUP.to_string())));
+        assert!(names.contains(&("ipython".to_string(), NOTEBOOK_GROUP.to_string())));
+        
+        let resource_manager = ResourceManager::new(config.clone()).await.unwrap();
+        let commands = CommandRunner::new(&config.commands);
+        let cx = TargetContext { config: &config, resource_manager: &resource_manager, commands: &commands, dry_run: false };
+        let target = ModelCacheTarget::new(torch.clone());
+        assert!(target.detect());
+        
+        let plan = target.scan(&cx).await.unwrap();
+        assert_eq!(plan.file_count(), 1);
+        let results = target.clean(&cx, Some(plan)).await.unwrap();
+        assert_eq!(results[0].files_removed, 1);
+        assert!(!torch.join("hub.pyc").exists());
+        assert!(torch.join("model.pt").exists());
+    }
+}
\ No newline at end of file
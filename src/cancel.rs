@@ -0,0 +1,36 @@
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::errors::EXIT_CANCELLED;
+
+/// Resolve once the process is asked to stop (Ctrl-C, or SIGTERM on Unix)
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Cancel `token` on the first Ctrl-C or SIGTERM, and exit at once on the second
+///
+/// Cancelling stops new deletions while those already under way finish, so no file is
+/// left half-written and the journal and audit log match what was removed.
+pub fn cancel_on_signal(token: CancellationToken) {
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        warn!("Interrupted: finishing the removals in progress, then stopping (interrupt again to quit now)");
+        token.cancel();
+        
+        shutdown_signal().await;
+        std::process::exit(EXIT_CANCELLED);
+    });
+}
\ No newline at end of file
@@ -0,0 +1,352 @@
+use serde::Serialize;
+use std::path::Path;
+use sysinfo::System;
+use tracing::{debug, warn};
+
+/// Platform capabilities detected at startup
+///
+/// Restricted environments (seccomp'd containers, sandboxes without /proc,
+/// read-only home directories) break features in ways that would otherwise
+/// surface as scattered errors mid-run. Detecting them once up front lets the
+/// affected features be switched off with a single clear report.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    /// /proc is mounted and readable (Linux only; always true elsewhere)
+    pub proc_fs: bool,
+    
+    /// The process runs under a seccomp filter
+    pub seccomp_filtered: bool,
+    
+    /// sysinfo can read memory statistics
+    pub system_info: bool,
+    
+    /// The home directory accepts writes
+    pub writable_home: bool,
+    
+    /// The platform file watcher (inotify, FSEvents, ...) can be created
+    pub file_watching: bool,
+    
+    /// Extended attributes can be read (Linux only; always false elsewhere)
+    pub xattrs: bool,
+}
+
+/// A single line of the capability report
+#[derive(Debug, Clone, Serialize)]
+pub struct CapabilityStatus {
+    pub name: &'static str,
+    pub available: bool,
+    pub impact: &'static str,
+}
+
+/// A backend compiled into this binary
+#[derive(Debug, Clone, Serialize)]
+pub struct Backend {
+    pub subsystem: &'static str,
+    pub implementation: &'static str,
+}
+
+/// Cargo features this binary was built with
+pub fn compiled_features() -> Vec<&'static str> {
+    [
+        ("daemon", cfg!(feature = "daemon")),
+        ("tui", cfg!(feature = "tui")),
+        ("remote", cfg!(feature = "remote")),
+        ("metrics", cfg!(feature = "metrics")),
+        ("history", cfg!(feature = "history")),
+        ("keyring", cfg!(feature = "keyring")),
+        ("usage", cfg!(feature = "usage")),
+        ("portable", cfg!(feature = "portable")),
+        ("io-uring", cfg!(feature = "io-uring")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+    .collect()
+}
+
+/// Backends compiled into this binary, so static and cross builds can be checked
+pub fn compiled_backends() -> Vec<Backend> {
+    vec![
+        Backend {
+            subsystem: "state_store",
+            implementation: "json-lines files (pure Rust)",
+        },
+        Backend {
+            subsystem: "hashing",
+            implementation: if cfg!(feature = "portable") {
+                "blake3 (portable Rust)"
+            } else {
+                "blake3 (SIMD)"
+            },
+        },
+        Backend {
+            subsystem: "file_watching",
+            implementation: if !cfg!(feature = "daemon") {
+                "not compiled in"
+            } else if cfg!(target_os = "linux") {
+                "inotify"
+            } else if cfg!(target_os = "macos") {
+                "fsevents"
+            } else if cfg!(windows) {
+                "ReadDirectoryChangesW"
+            } else {
+                "polling"
+            },
+        },
+        Backend {
+            subsystem: "progress",
+            implementation: if cfg!(feature = "tui") { "indicatif" } else { "silent" },
+        },
+        Backend {
+            subsystem: "credentials",
+            implementation: if !cfg!(feature = "keyring") {
+                "not compiled in"
+            } else if cfg!(target_os = "macos") {
+                "macOS Keychain"
+            } else if cfg!(windows) {
+                "Windows Credential Manager"
+            } else {
+                "Secret Service (secret-tool)"
+            },
+        },
+        Backend {
+            subsystem: "usage_tracking",
+            implementation: if !cfg!(feature = "usage") {
+                "not compiled in"
+            } else if cfg!(target_os = "linux") {
+                "inotify open events"
+            } else {
+                "unavailable (no open events on this platform)"
+            },
+        },
+        Backend {
+            subsystem: "unlinking",
+            implementation: if cfg!(all(target_os = "linux", feature = "io-uring")) {
+                "io_uring batches when the kernel allows, else one syscall per file"
+            } else {
+                "one syscall per file"
+            },
+        },
+        Backend {
+            subsystem: "system_info",
+            implementation: "sysinfo",
+        },
+        Backend {
+            subsystem: "libc",
+            implementation: if cfg!(target_env = "musl") {
+                "musl"
+            } else if cfg!(target_env = "gnu") {
+                "glibc"
+            } else {
+                "system"
+            },
+        },
+    ]
+}
+
+impl Capabilities {
+    /// Probe the current environment
+    pub fn detect() -> Self {
+        let proc_fs = Self::detect_proc_fs();
+        
+        let capabilities = Self {
+            proc_fs,
+            seccomp_filtered: Self::detect_seccomp(),
+            system_info: proc_fs && Self::detect_system_info(),
+            writable_home: home::home_dir()
+                .map(|home| Self::is_writable(&home))
+                .unwrap_or(false),
+            file_watching: Self::detect_file_watching(),
+            xattrs: home::home_dir().is_some_and(|home| Self::detect_xattrs(&home)),
+        };
+        
+        debug!("Detected capabilities: {:?}", capabilities);
+        capabilities
+    }
+    
+    /// Assume every capability is present (used when detection is not wanted)
+    pub fn full() -> Self {
+        Self {
+            proc_fs: true,
+            seccomp_filtered: false,
+            system_info: true,
+            writable_home: true,
+            file_watching: cfg!(feature = "daemon"),
+            xattrs: cfg!(target_os = "linux"),
+        }
+    }
+    
+    /// Describe each capability and what is disabled without it
+    pub fn report(&self) -> Vec<CapabilityStatus> {
+        let mut report = vec![
+            CapabilityStatus {
+                name: "proc_fs",
+                available: self.proc_fs,
+                impact: "files open in running processes are not kept, and system resource checks are skipped",
+            },
+            CapabilityStatus {
+                name: "seccomp_unrestricted",
+                available: !self.seccomp_filtered,
+                impact: "io_uring batched unlinks are off; other denied system calls degrade their features",
+            },
+            CapabilityStatus {
+                name: "system_info",
+                available: self.system_info,
+                impact: "memory pressure checks are skipped",
+            },
+            CapabilityStatus {
+                name: "writable_home",
+                available: self.writable_home,
+                impact: "the deletion journal and checkpoints are off; quarantine and the audit log refuse to run",
+            },
+        ];
+        
+        // Only Linux reads keep markers, so elsewhere there is nothing to lose
+        if cfg!(target_os = "linux") {
+            report.push(CapabilityStatus {
+                name: "xattrs",
+                available: self.xattrs,
+                impact: "`user.clearmodel.keep` markers are not read",
+            });
+        }
+        
+        // Builds without the daemon feature have no watcher to report on
+        if cfg!(feature = "daemon") {
+            report.push(CapabilityStatus {
+                name: "file_watching",
+                available: self.file_watching,
+                impact: "watch mode is unavailable",
+            });
+        }
+        
+        report
+    }
+    
+    /// Whether any capability is missing
+    pub fn is_degraded(&self) -> bool {
+        self.report().iter().any(|status| !status.available)
+    }
+    
+    /// Log a single summary of the features disabled in this environment
+    pub fn log_degraded(&self) {
+        if !self.is_degraded() {
+            return;
+        }
+        
+        warn!("Running in a restricted environment; some features are disabled:");
+        for status in self.report().iter().filter(|status| !status.available) {
+            warn!("  {} unavailable: {}", status.name, status.impact);
+        }
+    }
+    
+    fn detect_proc_fs() -> bool {
+        if cfg!(target_os = "linux") {
+            std::fs::read_to_string("/proc/self/stat").is_ok()
+        } else {
+            true
+        }
+    }
+    
+    fn detect_seccomp() -> bool {
+        // "Seccomp: 2" means a filter is installed, 1 is strict mode
+        std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                status.lines()
+                    .find_map(|line| line.strip_prefix("Seccomp:"))
+                    .map(|mode| mode.trim() != "0")
+            })
+            .unwrap_or(false)
+    }
+    
+    fn detect_system_info() -> bool {
+        let mut system = System::new();
+        system.refresh_memory();
+        system.total_memory() > 0
+    }
+    
+    #[cfg(feature = "daemon")]
+    fn detect_file_watching() -> bool {
+        notify::recommended_watcher(|_: notify::Result<notify::Event>| {}).is_ok()
+    }
+    
+    #[cfg(not(feature = "daemon"))]
+    fn detect_file_watching() -> bool {
+        false
+    }
+    
+    /// Whether reading an attribute that may not exist fails for any other reason than its absence
+    #[cfg(target_os = "linux")]
+    fn detect_xattrs(path: &Path) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+        
+        let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        let name = std::ffi::CString::new(crate::exclude::KEEP_XATTR).unwrap_or_default();
+        // SAFETY: both strings are NUL-terminated and a zero-sized read writes nothing
+        let found = unsafe { libc::getxattr(c_path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) } >= 0;
+        found || std::io::Error::last_os_error().raw_os_error() == Some(libc::ENODATA)
+    }
+    
+    #[cfg(not(target_os = "linux"))]
+    fn detect_xattrs(_path: &Path) -> bool {
+        false
+    }
+    
+    /// Check write access without creating files (catches read-only mounts too)
+    #[cfg(unix)]
+    fn is_writable(path: &Path) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+        
+        let Ok(c_path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        
+        // SAFETY: c_path is a valid NUL-terminated string for the duration of the call
+        unsafe { libc::access(c_path.as_ptr(), libc::W_OK) == 0 }
+    }
+    
+    #[cfg(not(unix))]
+    fn is_writable(path: &Path) -> bool {
+        std::fs::metadata(path)
+            .map(|metadata| !metadata.permissions().readonly())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_full_capabilities_are_not_degraded() {
+        let capabilities = Capabilities::full();
+        assert!(!capabilities.is_degraded());
+        assert!(capabilities.report().iter().all(|status| status.available));
+    }
+    
+    #[test]
+    fn test_missing_capability_is_reported() {
+        let capabilities = Capabilities {
+            writable_home: false,
+            ..Capabilities::full()
+        };
+        
+        assert!(capabilities.is_degraded());
+        let missing: Vec<_> = capabilities.report()
+            .into_iter()
+            .filter(|status| !status.available)
+            .map(|status| status.name)
+            .collect();
+        assert_eq!(missing, vec!["writable_home"]);
+    }
+    
+    #[test]
+    fn test_writable_probe() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(Capabilities::is_writable(temp_dir.path()));
+        assert!(!Capabilities::is_writable(&temp_dir.path().join("missing")));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,149 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Coarse kind of a cache file, for telling weights from junk at a glance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Safetensors,
+    /// Pickled weights: `.bin`, `.pt`, `.pth`, `.ckpt`
+    Bin,
+    /// Dataset shards: `.arrow`, `.parquet`
+    Arrow,
+    Pyc,
+    /// Compiled kernels, extensions and TensorRT engines: `.so`, `.o`, `.ptx`, `.cubin`, `.engine`,
+    /// and the torch kernel cache
+    Compiled,
+    /// Logs and TensorBoard event files
+    Logs,
+    Other,
+}
+
+impl FileCategory {
+    pub fn of(path: &Path) -> Self {
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        
+        match extension.as_deref() {
+            Some("safetensors") => FileCategory::Safetensors,
+            Some("bin" | "pt" | "pth" | "ckpt") => FileCategory::Bin,
+            Some("arrow" | "parquet") => FileCategory::Arrow,
+            Some("pyc" | "pyo") => FileCategory::Pyc,
+            Some("so" | "o" | "pyd" | "ptx" | "cubin" | "fatbin" | "engine" | "trt" | "timing") => FileCategory::Compiled,
+            Some("log") => FileCategory::Logs,
+            _ if is_event_file(path) => FileCategory::Logs,
+            _ if is_kernel_cache(path) => FileCategory::Compiled,
+            _ => FileCategory::Other,
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileCategory::Safetensors => "safetensors",
+            FileCategory::Bin => "bin",
+            FileCategory::Arrow => "arrow",
+            FileCategory::Pyc => "pyc",
+            FileCategory::Compiled => "compiled",
+            FileCategory::Logs => "logs",
+            FileCategory::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for FileCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// TensorBoard writes `events.out.tfevents.<timestamp>.<host>`, with no useful extension
+fn is_event_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with("events.out.tfevents."))
+        .unwrap_or(false)
+}
+
+/// The torch kernel cache (`torch/kernels/...`) names its files by hash alone
+fn is_kernel_cache(path: &Path) -> bool {
+    path.ancestors().skip(1).any(|ancestor| ancestor.ends_with("torch/kernels"))
+}
+
+/// Files seen and removed in one category
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CategoryStats {
+    pub scanned_files: u64,
+    pub scanned_bytes: u64,
+    pub deleted_files: u64,
+    pub deleted_bytes: u64,
+}
+
+impl CategoryStats {
+    pub fn merge(&mut self, other: &CategoryStats) {
+        self.scanned_files += other.scanned_files;
+        self.scanned_bytes += other.scanned_bytes;
+        self.deleted_files += other.deleted_files;
+        self.deleted_bytes += other.deleted_bytes;
+    }
+}
+
+/// Per-category totals, in a stable order
+pub type CategoryBreakdown = BTreeMap<FileCategory, CategoryStats>;
+
+/// Count one file into a breakdown
+pub fn record(breakdown: &mut CategoryBreakdown, path: &Path, size: u64, removed: bool) {
+    let stats = breakdown.entry(FileCategory::of(path)).or_default();
+    stats.scanned_files += 1;
+    stats.scanned_bytes += size;
+    if removed {
+        stats.deleted_files += 1;
+        stats.deleted_bytes += size;
+    }
+}
+
+/// Count a removal of a file that was already recorded as scanned
+pub fn record_deleted(breakdown: &mut CategoryBreakdown, path: &Path, size: u64) {
+    let stats = breakdown.entry(FileCategory::of(path)).or_default();
+    stats.deleted_files += 1;
+    stats.deleted_bytes += size;
+}
+
+/// Add every category of `from` into `into`
+pub fn merge(into: &mut CategoryBreakdown, from: &CategoryBreakdown) {
+    for (category, stats) in from {
+        into.entry(*category).or_default().merge(stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_files_are_categorised_and_counted() {
+        let mut breakdown = CategoryBreakdown::new();
+        record(&mut breakdown, Path::new("blobs/model.safetensors"), 100, false);
+        record(&mut breakdown, Path::new("checkpoints/resnet50.PTH"), 40, true);
+        record(&mut breakdown, Path::new("runs/events.out.tfevents.1712.host"), 5, true);
+        record(&mut breakdown, Path::new("__pycache__/mod.cpython-311.pyc"), 2, true);
+        record(&mut breakdown, Path::new("README"), 1, false);
+        record(&mut breakdown, Path::new("torch_extensions/py311_cu121/fused_adam/fused_adam.so"), 7, true);
+        record(&mut breakdown, Path::new(".cache/torch/kernels/7a/1f2e3d"), 3, false);
+        
+        assert_eq!(breakdown[&FileCategory::Safetensors].scanned_bytes, 100);
+        assert_eq!(breakdown[&FileCategory::Safetensors].deleted_bytes, 0);
+        assert_eq!(breakdown[&FileCategory::Bin].deleted_bytes, 40);
+        assert_eq!(breakdown[&FileCategory::Logs].deleted_files, 1);
+        assert_eq!(breakdown[&FileCategory::Pyc].deleted_files, 1);
+        assert_eq!(breakdown[&FileCategory::Other].scanned_files, 1);
+        assert_eq!(breakdown[&FileCategory::Compiled].scanned_bytes, 10);
+        
+        let mut total = CategoryBreakdown::new();
+        merge(&mut total, &breakdown);
+        merge(&mut total, &breakdown);
+        assert_eq!(total[&FileCategory::Bin].scanned_bytes, 80);
+    }
+}
\ No newline at end of file
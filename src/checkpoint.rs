@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+use crate::errors::{ClearModelError, Result};
+use crate::plan::{CleanupPlan, PlannedDirectory, PlannedFile};
+use crate::state;
+
+/// Format of the checkpoint file, bumped on incompatible changes
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// How often progress is written while files are being removed
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What an unfinished cleanup still has to remove, target by target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    
+    /// Seconds since the Unix epoch when the run was planned
+    pub planned_at: u64,
+    
+    /// Targets not yet cleaned, in the order they run
+    pub targets: Vec<TargetCheckpoint>,
+}
+
+/// The files one target still has to remove
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetCheckpoint {
+    pub name: String,
+    pub directories: Vec<DirectoryCheckpoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryCheckpoint {
+    pub root: PathBuf,
+    pub files: Vec<PlannedFile>,
+}
+
+impl Checkpoint {
+    /// Load the checkpoint left by an interrupted run, if there is one
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(ClearModelError::file_operation(
+                format!("Failed to read the checkpoint: {}", e),
+                Some(path.to_path_buf())
+            )),
+        };
+        let checkpoint: Self = serde_json::from_slice(&content)
+            .map_err(|e| ClearModelError::cache(format!("Checkpoint {:?} is damaged ({}); run a full `clearmodel clean` instead", path, e)))?;
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(ClearModelError::cache(format!(
+                "Checkpoint {:?} was written by another version of clearmodel; run a full `clearmodel clean` instead",
+                path
+            )));
+        }
+        Ok(Some(checkpoint))
+    }
+    
+    /// Whether `target` still has work left
+    pub fn has_target(&self, name: &str) -> bool {
+        self.targets.iter().any(|target| target.name == name)
+    }
+    
+    /// The remaining plan of `target`, as the target cleans it
+    pub fn plan(&self, name: &str) -> CleanupPlan {
+        let directories = self.targets.iter()
+            .filter(|target| target.name == name)
+            .flat_map(|target| &target.directories)
+            .map(|directory| PlannedDirectory {
+                root: directory.root.clone(),
+                files: directory.files.clone(),
+                ..PlannedDirectory::default()
+            })
+            .collect();
+        CleanupPlan { directories }
+    }
+    
+    /// Files still to be removed
+    pub fn file_count(&self) -> u64 {
+        self.targets.iter()
+            .flat_map(|target| &target.directories)
+            .map(|directory| directory.files.len() as u64)
+            .sum()
+    }
+}
+
+/// Keeps the checkpoint of the current run up to date as files are removed
+///
+/// The checkpoint is written when the plan is made, again at most every few seconds
+/// while files are removed, and deleted once the run finishes. A run killed between
+/// two saves repeats at most a few seconds of work on resume, and files it removed
+/// already are skipped because they are gone.
+#[derive(Debug)]
+pub struct CheckpointWriter {
+    path: PathBuf,
+    state: Mutex<Option<(Checkpoint, Instant)>>,
+}
+
+impl CheckpointWriter {
+    /// Write checkpoints to `path` once a plan is started
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, state: Mutex::new(None) }
+    }
+    
+    /// Record the plans of the targets about to be cleaned
+    pub fn start(&self, targets: Vec<(String, &CleanupPlan)>) {
+        let checkpoint = Checkpoint {
+            version: CHECKPOINT_VERSION,
+            planned_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            targets: targets.into_iter()
+                .map(|(name, plan)| TargetCheckpoint {
+                    name,
+                    directories: plan.directories.iter()
+                        .map(|directory| DirectoryCheckpoint {
+                            root: directory.root.clone(),
+                            files: directory.files.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        };
+        self.save(&checkpoint);
+        *self.lock() = Some((checkpoint, Instant::now()));
+    }
+    
+    /// Record that `batch`, the next files planned under `root`, has been handled
+    ///
+    /// Only the target being cleaned (the first one left) is advanced, and only when the
+    /// batch is where that directory's plan continues, so removals outside the checkpointed
+    /// plan (budget evictions, revision pruning) leave it alone.
+    pub fn advance(&self, root: &Path, batch: &[PlannedFile]) {
+        let mut state = self.lock();
+        let Some((checkpoint, saved)) = state.as_mut() else {
+            return;
+        };
+        let Some(directory) = checkpoint.targets.first_mut()
+            .and_then(|target| target.directories.iter_mut().find(|directory| {
+                directory.root == root && directory.files.first().map(|file| &file.path) == batch.first().map(|file| &file.path)
+            }))
+        else {
+            return;
+        };
+        
+        directory.files.drain(..batch.len().min(directory.files.len()));
+        if saved.elapsed() >= SAVE_INTERVAL {
+            self.save(checkpoint);
+            *saved = Instant::now();
+        }
+    }
+    
+    /// Record that `name` has been cleaned, or stopped before the end with `cancelled`
+    pub fn finish_target(&self, name: &str, cancelled: bool) {
+        let mut state = self.lock();
+        let Some((checkpoint, saved)) = state.as_mut() else {
+            return;
+        };
+        if !cancelled {
+            checkpoint.targets.retain(|target| target.name != name);
+        }
+        self.save(checkpoint);
+        *saved = Instant::now();
+    }
+    
+    /// Remove the checkpoint once the run has finished
+    pub fn complete(&self) {
+        if self.lock().take().is_none() {
+            return;
+        }
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => debug!("Removed checkpoint {:?}", self.path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove checkpoint {:?}: {}", self.path, e),
+        }
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<(Checkpoint, Instant)>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Save failures only cost the ability to resume, so they are logged and the run goes on
+    fn save(&self, checkpoint: &Checkpoint) {
+        match write_atomically(&self.path, checkpoint) {
+            Ok(()) => debug!("Checkpoint saved: {} files left", checkpoint.file_count()),
+            Err(e) => warn!("Failed to save checkpoint {:?}: {}", self.path, e),
+        }
+    }
+}
+
+fn write_atomically(path: &Path, checkpoint: &Checkpoint) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    let mut file = File::create(&temp)?;
+    serde_json::to_writer(&mut file, checkpoint)?;
+    file.write_all(b"\n")?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)
+}
+
+/// Location of the checkpoint (`checkpoint.json` in the state directory)
+pub fn checkpoint_path() -> Result<PathBuf> {
+    state::require(state::CHECKPOINT, "the checkpoint")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    fn planned(path: &str) -> PlannedFile {
+        PlannedFile { path: PathBuf::from(path), size: 4, modified: Some(UNIX_EPOCH), rule: "expired" }
+    }
+    
+    #[test]
+    fn test_interrupted_progress_survives_a_reload() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("checkpoint.json");
+        let plan = CleanupPlan {
+            directories: vec![PlannedDirectory {
+                root: PathBuf::from("/cache"),
+                files: vec![planned("/cache/a"), planned("/cache/b"), planned("/cache/c")],
+                ..PlannedDirectory::default()
+            }],
+        };
+        
+        let writer = CheckpointWriter::new(path.clone());
+        writer.start(vec![("models".to_string(), &plan), ("pip".to_string(), &CleanupPlan::default())]);
+        writer.advance(Path::new("/cache"), &plan.directories[0].files[..1]);
+        // Not where the plan continues, so not counted
+        writer.advance(Path::new("/cache"), &plan.directories[0].files[2..]);
+        writer.finish_target("models", true);
+        
+        let checkpoint = Checkpoint::load(&path).unwrap().unwrap();
+        assert_eq!(checkpoint.file_count(), 2);
+        assert!(checkpoint.has_target("pip"));
+        assert_eq!(checkpoint.plan("models").directories[0].files[0].path, PathBuf::from("/cache/b"));
+        
+        writer.complete();
+        assert!(Checkpoint::load(&path).unwrap().is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,287 @@
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use crate::config::CommandsConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::events;
+
+/// Bytes of each output stream kept in the run report; the end is kept, where errors are
+const MAX_CAPTURED: usize = 16 * 1024;
+
+/// One external command clearmodel ran, or refused to
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandRecord {
+    pub program: String,
+    pub args: Vec<String>,
+    
+    /// Exit status; none when the command did not run to the end or was killed by a signal
+    pub status: Option<i32>,
+    pub duration_ms: u64,
+    pub stdout: String,
+    pub stderr: String,
+    
+    /// Why the command was refused, could not start or was killed
+    pub error: Option<String>,
+}
+
+impl CommandRecord {
+    pub fn success(&self) -> bool {
+        self.error.is_none() && self.status == Some(0)
+    }
+}
+
+/// Runs the external tools in `commands.allowed`, with a scrubbed environment and a time
+/// limit, and keeps what they printed for the run report
+#[derive(Clone)]
+pub struct CommandRunner {
+    config: Arc<CommandsConfig>,
+    records: Arc<Mutex<Vec<CommandRecord>>>,
+}
+
+impl CommandRunner {
+    pub fn new(config: &CommandsConfig) -> Self {
+        Self { config: Arc::new(config.clone()), records: Arc::default() }
+    }
+    
+    /// Runner holding the default `commands` settings, for tools run before the configuration
+    /// is loaded: verifying and downloading a remote configuration
+    pub fn builtin() -> Self {
+        Self::new(&CommandsConfig::default())
+    }
+    
+    /// What to run for `program`: the allow-list entry naming it, which is an absolute path
+    /// when the configuration pins the binary
+    fn resolve(&self, program: &str) -> Option<&str> {
+        self.config.allowed.iter().map(String::as_str).find(|allowed| {
+            *allowed == program || Path::new(allowed).is_absolute() && Path::new(allowed).file_name() == Some(OsStr::new(program))
+        })
+    }
+    
+    /// Fail unless `program` is on the allow-list
+    pub fn check(&self, program: &str) -> Result<()> {
+        match self.resolve(program) {
+            Some(_) => Ok(()),
+            None => Err(ClearModelError::security(format!("Refusing to run {}: it is not in commands.allowed", program))
+                .with_hint(format!("Add {:?} to commands.allowed to let clearmodel run it", program))),
+        }
+    }
+    
+    /// Whether `program` is allowed and can be found, without running it
+    pub fn installed(&self, program: &str) -> bool {
+        let Some(resolved) = self.resolve(program) else {
+            return false;
+        };
+        if Path::new(resolved).is_absolute() {
+            return Path::new(resolved).is_file();
+        }
+        std::env::var_os("PATH").is_some_and(|path| {
+            std::env::split_paths(&path).any(|dir| {
+                dir.join(resolved).is_file() || dir.join(format!("{}{}", resolved, std::env::consts::EXE_SUFFIX)).is_file()
+            })
+        })
+    }
+    
+    /// Seconds `program args` may take: its subcommand's limit, the program's, or `timeout_secs`
+    fn timeout_secs(&self, program: &str, args: &[String]) -> u64 {
+        let timeouts = &self.config.timeouts;
+        args.first()
+            .and_then(|subcommand| timeouts.get(&format!("{} {}", program, subcommand)))
+            .or_else(|| timeouts.get(program))
+            .copied()
+            .unwrap_or(self.config.timeout_secs)
+    }
+    
+    /// Run `program` with `args` and wait for it
+    ///
+    /// Refused programs, programs that fail to start and ones killed at their time limit
+    /// are errors; a command exiting unsuccessfully is not, and callers check
+    /// [`CommandRecord::success`]. Every command is recorded either way.
+    pub async fn run(&self, program: &str, args: &[String]) -> Result<CommandRecord> {
+        let mut record = CommandRecord {
+            program: program.to_string(),
+            args: args.to_vec(),
+            status: None,
+            duration_ms: 0,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+        };
+        let Some(resolved) = self.resolve(program) else {
+            let e = self.check(program).unwrap_err();
+            warn!("{}", e);
+            record.error = Some(e.to_string());
+            self.record(record);
+            return Err(e);
+        };
+        
+        let secs = self.timeout_secs(program, args);
+        let mut command = Command::new(resolved);
+        command.args(args)
+            .env_clear()
+            .envs(self.config.pass_env.iter().filter_map(|name| std::env::var_os(name).map(|value| (name, value))))
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        debug!("Running {} {} (limit {}s)", resolved, args.join(" "), secs);
+        
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(Duration::from_secs(secs), command.output()).await;
+        record.duration_ms = events::millis(started.elapsed());
+        let outcome = match outcome {
+            Ok(Ok(output)) => {
+                record.status = output.status.code();
+                record.stdout = captured(&output.stdout);
+                record.stderr = captured(&output.stderr);
+                Ok(record.clone())
+            }
+            Ok(Err(e)) => Err(ClearModelError::environment(format!("Failed to run {}: {}", program, e))),
+            Err(_) => Err(ClearModelError::TimedOut { what: format!("{} {}", program, args.join(" ")), secs }),
+        };
+        if let Err(e) = &outcome {
+            record.error = Some(e.to_string());
+        }
+        self.record(record);
+        outcome
+    }
+    
+    /// Run `program` with `args` on this thread, feeding it `input` on stdin, and return
+    /// what it printed
+    ///
+    /// For callers outside the async runtime. The allow-list, environment and time limit
+    /// are those of [`run`](Self::run), but nothing is recorded, since what these commands
+    /// read and print may be a secret; callers check the exit status.
+    pub fn output(&self, program: &str, args: &[&str], input: Option<&[u8]>) -> Result<Output> {
+        self.output_with_env(program, args, &[], input)
+    }
+    
+    /// [`output`](Self::output), with `env` set on top of the variables `pass_env` lets through
+    pub fn output_with_env(&self, program: &str, args: &[&str], env: &[(&str, &OsStr)], input: Option<&[u8]>) -> Result<Output> {
+        let resolved = self.resolve(program).ok_or_else(|| self.check(program).unwrap_err())?;
+        let secs = self.timeout_secs(program, &args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>());
+        debug!("Running {} {} (limit {}s)", resolved, args.join(" "), secs);
+        
+        let mut child = std::process::Command::new(resolved)
+            .args(args)
+            .env_clear()
+            .envs(self.config.pass_env.iter().filter_map(|name| std::env::var_os(name).map(|value| (name, value))))
+            .envs(env.iter().copied())
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| ClearModelError::environment(format!("Failed to run {}: {}", program, e)))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input)?;
+        }
+        
+        // Read on other threads so a chatty command never blocks on a full pipe
+        let drain = |stream: Option<Box<dyn Read + Send>>| std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut stream) = stream {
+                let _ = stream.read_to_end(&mut bytes);
+            }
+            bytes
+        });
+        let stdout = drain(child.stdout.take().map(|stream| Box::new(stream) as Box<dyn Read + Send>));
+        let stderr = drain(child.stderr.take().map(|stream| Box::new(stream) as Box<dyn Read + Send>));
+        
+        let deadline = Instant::now() + Duration::from_secs(secs);
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ClearModelError::TimedOut { what: format!("{} {}", program, args.join(" ")), secs });
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+    
+    fn record(&self, record: CommandRecord) {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).push(record);
+    }
+    
+    /// Every command run or refused so far, in order
+    pub fn records(&self) -> Vec<CommandRecord> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// The last `MAX_CAPTURED` bytes of an output stream, as text
+fn captured(output: &[u8]) -> String {
+    let start = output.len().saturating_sub(MAX_CAPTURED);
+    String::from_utf8_lossy(&output[start..]).into_owned()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    
+    #[tokio::test]
+    async fn test_only_allowed_commands_run_with_a_scrubbed_environment() {
+        let runner = CommandRunner::new(&CommandsConfig {
+            allowed: vec!["sh".to_string(), "/bin/sleep".to_string()],
+            timeout_secs: 30,
+            timeouts: BTreeMap::from([("sleep".to_string(), 1)]),
+            pass_env: vec!["PATH".to_string()],
+        });
+        
+        std::env::set_var("CLEARMODEL_TEST_SECRET", "hunter2");
+        let args = ["-c".to_string(), "echo \"[$CLEARMODEL_TEST_SECRET]\"".to_string()];
+        let record = runner.run("sh", &args).await.unwrap();
+        assert!(record.success());
+        assert_eq!(record.stdout, "[]\n");
+        
+        assert!(runner.run("rm", &["-rf".to_string(), "/tmp/nothing".to_string()]).await.is_err());
+        assert!(!runner.installed("rm"));
+        
+        // Allowed by its absolute path, and killed at its limit
+        let e = runner.run("sleep", &["5".to_string()]).await.unwrap_err();
+        assert!(matches!(e, ClearModelError::TimedOut { secs: 1, .. }));
+        
+        let records = runner.records();
+        assert_eq!(records.len(), 3);
+        assert!(records[1].error.as_deref().unwrap().contains("not in commands.allowed"));
+        assert!(!records[2].success());
+    }
+    
+    #[test]
+    fn test_blocking_commands_are_checked_and_fed_their_input() {
+        let runner = CommandRunner::new(&CommandsConfig {
+            allowed: vec!["cat".to_string(), "sleep".to_string()],
+            timeout_secs: 1,
+            ..CommandsConfig::default()
+        });
+        let output = runner.output("cat", &[], Some(b"hunter2")).unwrap();
+        assert_eq!(output.stdout, b"hunter2");
+        let sh = CommandRunner::new(&CommandsConfig { allowed: vec!["/bin/sh".to_string()], ..CommandsConfig::default() });
+        let output = sh.output_with_env("sh", &["-c", "echo $SUDO_ASKPASS"], &[("SUDO_ASKPASS", OsStr::new("/bin/askpass"))], None).unwrap();
+        assert_eq!(output.stdout, b"/bin/askpass\n");
+        
+        let refused = runner.output("curl", &["https://example.com"], None).unwrap_err();
+        assert!(refused.to_string().contains("not in commands.allowed"));
+        assert!(matches!(runner.output("sleep", &["5"], None), Err(ClearModelError::TimedOut { secs: 1, .. })));
+        assert!(runner.records().is_empty());
+        
+        // The tools clearmodel itself runs are allowed out of the box, and nothing else
+        let builtin = CommandRunner::builtin();
+        for program in ["curl", "ssh-keygen", "secret-tool", "systemctl", "launchctl", "schtasks", "/usr/bin/sudo", "/usr/bin/pkexec"] {
+            assert!(builtin.check(program).is_ok(), "{} is refused", program);
+        }
+        assert!(builtin.check("rm").is_err());
+    }
+}
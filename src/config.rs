@@ -1,13 +1,38 @@
 use config::{Config, Environment, File};
 use home::home_dir;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use std::time::Duration;
+use tracing::{debug, info, warn};
 
+use crate::allocation::SizeBasis;
+use crate::notifications::WebhookFormat;
+use crate::containers;
 use crate::errors::{ClearModelError, Result};
+use crate::exclude;
+use crate::expand;
+use crate::hf_datasets;
+use crate::log_file::RotationPeriod;
+use crate::log_format::LogFormat;
+use crate::image_models::ImageStoreKind;
+use crate::models::PinnedModels;
+use crate::netfs::NetworkFsMode;
+use crate::policy::{AgeBasis, ConflictMode, Decision, EvictionPolicy, DEFAULT_PRECEDENCE};
+use crate::privilege::PrivilegeBackend;
+use crate::rate_limit::IoPriority;
+use crate::security::SecurityManager;
+use crate::shred::DeletionMode;
+use crate::targets::RESERVED_TARGET_NAMES;
+use crate::torch_caches;
 
 /// Configuration for the clearmodel application
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Missing fields fall back to their defaults; this also covers empty lists, which
+/// the `config` crate drops from the defaults layer.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct ClearModelConfig {
     /// Cache directories to clean
     pub cache_paths: Vec<PathBuf>,
@@ -15,18 +40,47 @@ pub struct ClearModelConfig {
     /// Maximum age of cache files in days
     pub max_cache_age_days: u32,
     
-    /// Maximum number of parallel operations
+    /// Timestamp a file's age is measured from: `mtime`, `atime` or `btime`
+    pub age_basis: AgeBasis,
+    
+    /// Only files at least this large (in MB) are cleaned; smaller ones are always kept
+    pub min_file_size_mb: Option<f64>,
+    
+    /// How sizes are reported and compared against budgets: `allocated` (default) counts
+    /// the blocks files take up, with clones and hardlinks counted once; `apparent` their
+    /// logical sizes (`--apparent-size` for one run)
+    pub size_basis: SizeBasis,
+    
+    /// What counts as a file's last use: `age` (the `age_basis` timestamp) or `lru`
+    /// (opens recorded by the usage tracker as well)
+    pub policy: EvictionPolicy,
+    
+    /// Maximum number of parallel operations: cache roots processed at once (and the
+    /// threads listing their directories, unless `concurrency.scan_workers` is set)
     pub max_parallel_operations: usize,
     
     /// Whether to follow symbolic links
     pub follow_symlinks: bool,
     
+    /// Never leave the filesystem a cache root is on (bind mounts, network shares, linked drives)
+    pub stay_on_filesystem: bool,
+    
+    /// Cache roots on NFS, SMB or FUSE mounts: `skip` (default), `allow` or `throttle`
+    /// (cleaned one file at a time)
+    pub network_filesystems: NetworkFsMode,
+    
+    /// Remove directories left empty and dangling symlinks inside cache roots after cleaning
+    pub prune_empty_dirs: bool,
+    
     /// File extensions to target for Python cache cleanup
     pub python_cache_extensions: Vec<String>,
     
     /// Directories to skip during cleanup
     pub skip_directories: Vec<String>,
     
+    /// Gitignore-style patterns, relative to each cache root, for files that are never cleaned
+    pub exclude_patterns: Vec<String>,
+    
     /// Minimum free space threshold (in GB) before cleanup
     pub min_free_space_gb: u64,
     
@@ -36,12 +90,145 @@ pub struct ClearModelConfig {
     /// Logging configuration
     pub log_level: String,
     
+    /// Model name patterns that are never deleted (e.g. `meta-llama/Llama-3-8B`, `llama3:*`)
+    pub pinned_models: Vec<String>,
+    
+    /// Snapshot revisions kept per HuggingFace repository, newest first (all when unset)
+    pub keep_revisions: Option<usize>,
+    
+    /// Least recently used models are evicted until the model caches together fit (disabled when unset)
+    pub max_total_cache_gb: Option<f64>,
+    
+    /// The same budget for single targets, by name (`huggingface = 100`)
+    pub target_budgets_gb: BTreeMap<String, f64>,
+    
+    /// Move removed files into quarantine instead of deleting them immediately
+    pub quarantine: bool,
+    
+    /// Days quarantined files are kept before they are purged permanently
+    pub quarantine_ttl_days: u32,
+    
+    /// `delete` (default) or `shred`, which overwrites file contents before unlinking them
+    pub deletion_mode: DeletionMode,
+    
+    /// Limits on removals per second and hashing or shredding bandwidth, and the priority
+    /// cleanups run at, for shared machines
+    pub io_rate_limit: IoRateLimitConfig,
+    
+    /// How removals that fail because a file is busy or locked are tried again
+    pub retry: RetryConfig,
+    
+    /// Threads scanning and deleting, overall and for single targets
+    pub concurrency: ConcurrencyConfig,
+    
+    /// How long targets and whole cleanups may run before the watchdog abandons them
+    pub timeouts: TimeoutConfig,
+    
+    /// External tools clearmodel may run, how long each may take and what environment it sees
+    pub commands: CommandsConfig,
+    
+    /// Caches on group-writable volumes that several users clean
+    pub shared: SharedCacheConfig,
+    
+    /// Plan every target before removing anything and save progress as files go, so
+    /// `clean --resume` can finish an interrupted run without scanning again
+    pub checkpoint: bool,
+    
+    /// Remove leftovers of interrupted downloads (`*.incomplete`, `*.part`, empty blobs, ...) regardless of age
+    pub clean_incomplete_downloads: bool,
+    
+    /// Minutes a partial download must be untouched before it counts as abandoned
+    pub incomplete_grace_minutes: u64,
+    
+    /// Order in which rules win when several match a file (`pinned`, `python_cache`, `incomplete`, `expired`)
+    pub rule_precedence: Vec<String>,
+    
+    /// How files matched by disagreeing rules are settled
+    pub on_conflict: ConflictMode,
+    
     /// Security settings
     pub security: SecurityConfig,
+    
+    /// Watch mode settings
+    pub watch: WatchConfig,
+    
+    /// Resident daemon settings
+    pub daemon: DaemonConfig,
+    
+    /// Recording of cache file opens for `policy = "lru"` and `clearmodel usage`
+    pub usage: UsageConfig,
+    
+    /// Background size index refresh (`clearmodel prescan`)
+    pub prescan: PrescanConfig,
+    
+    /// Python package manager caches (pip, uv, poetry)
+    pub package_caches: PackageCacheConfig,
+    
+    /// JAX, Triton and TorchInductor compilation caches
+    pub compile_caches: CompileCacheConfig,
+    
+    /// llama.cpp, LM Studio and GPT4All model stores
+    pub gguf_stores: GgufStoreConfig,
+    
+    /// Whisper, spaCy, NLTK and gensim downloads
+    pub data_caches: DataCacheConfig,
+    
+    /// ONNX Runtime and TensorRT engine caches
+    pub engine_caches: EngineCacheConfig,
+    
+    /// CUDA compute, cuDNN autotune and GL shader caches
+    pub gpu_caches: GpuCacheConfig,
+    
+    /// Docker/Podman images of ML frameworks, pruned through the engine's API socket
+    pub containers: ContainerConfig,
+    
+    /// DVC caches of the listed workspaces
+    pub dvc: DvcConfig,
+    
+    /// MLflow run artifacts in local file stores
+    pub mlflow: MlflowConfig,
+    
+    /// Weights & Biases artifact cache and run staging
+    pub wandb: WandbConfig,
+    
+    /// Jupyter runtime files and kernel specs, IPython history and notebook execution caches
+    pub notebooks: NotebookConfig,
+    
+    /// HuggingFace caches cleaned as separate instances, each with its own policy and budget
+    pub hf_caches: Vec<HfCacheConfig>,
+    
+    /// ComfyUI, AUTOMATIC1111 and InvokeAI installations whose model folders are cleaned
+    pub image_stores: Vec<ImageStoreConfig>,
+    
+    /// S3, GCS and MinIO prefixes holding shared model caches, cleaned when named with `--only`
+    pub remote_buckets: Vec<RemoteBucketConfig>,
+    
+    /// Gradual warn, archive and delete tiers applied by the daemon
+    pub lifecycle: LifecycleConfig,
+    
+    /// Log output settings
+    pub logging: LoggingConfig,
+    
+    /// Deletion journal used by `clearmodel undo`
+    pub journal: JournalConfig,
+    
+    /// Tamper-evident log of every removal, for regulated environments
+    pub audit: AuditConfig,
+    
+    /// Run summaries posted to a webhook (Slack, Discord or any JSON receiver)
+    pub notifications: NotificationConfig,
+    
+    /// `--older-than`: replaces every age limit for this run
+    #[serde(skip)]
+    pub older_than: Option<Duration>,
+    
+    /// `--newer-than`: files at least this old are left alone for this run
+    #[serde(skip)]
+    pub newer_than: Option<Duration>,
 }
 
 /// Security-related configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SecurityConfig {
     /// Whether to validate cache paths
     pub validate_cache_paths: bool,
@@ -54,6 +241,433 @@ pub struct SecurityConfig {
     
     /// Whether to require confirmation for large deletions
     pub require_confirmation_threshold_gb: Option<u64>,
+    
+    /// Clean caches on host directories bind-mounted into a container without asking
+    #[serde(default)]
+    pub allow_container_host_mounts: bool,
+    
+    /// How commands needing administrator rights are run (`password`, `askpass`, `authorization`, `pkexec`)
+    #[serde(default)]
+    pub privilege_backend: PrivilegeBackend,
+    
+    /// `SUDO_ASKPASS` program for the `askpass` backend (a built-in dialog on macOS when unset)
+    #[serde(default)]
+    pub askpass_helper: Option<PathBuf>,
+    
+    /// Paths that are never cleaned, as globs: `/usr` protects the directory itself, `/System/**`
+    /// everything below it
+    #[serde(default = "default_denied_roots")]
+    pub denied_roots: Vec<String>,
+    
+    /// When set, nothing outside these directories is ever deleted (expanded like `cache_paths`)
+    #[serde(default)]
+    pub allowed_roots: Vec<PathBuf>,
+    
+    /// Other users whose files may be removed, besides this user's own
+    #[serde(default)]
+    pub allowed_uids: Vec<u32>,
+    
+    /// Remove files whatever user owns them (`--include-other-users`), for administrators
+    #[serde(default)]
+    pub include_other_users: bool,
+    
+    /// Remove selected files that need administrator rights through `privilege_backend` after
+    /// the rest of a cleanup (`--privileged`), instead of only listing them
+    #[serde(default)]
+    pub privileged: bool,
+    
+    /// Delete from Landlock-sandboxed worker threads that can only write below the cache roots (Linux)
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// Critical system paths `security.denied_roots` starts with
+fn default_denied_roots() -> Vec<String> {
+    let mut roots: Vec<String> = [
+        "/", "/bin", "/boot", "/dev", "/etc", "/lib", "/proc", "/root", "/sbin", "/sys", "/usr",
+        "/var/log", "/var/lib",
+    ].iter().map(|root| root.to_string()).collect();
+    if cfg!(target_os = "macos") {
+        for tree in ["/System", "/Library/System", "/Applications/Utilities"] {
+            roots.push(tree.to_string());
+            roots.push(format!("{}/**", tree));
+        }
+    }
+    roots
+}
+
+/// Watch mode configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WatchConfig {
+    /// Seconds without writes before a file is considered fully downloaded
+    pub quiesce_secs: u64,
+    
+    /// Minimum size (in MB) of a completed download that triggers enforcement
+    pub min_download_mb: u64,
+}
+
+/// Daemon configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DaemonConfig {
+    /// Seconds between free space checks
+    pub check_interval_secs: u64,
+    
+    /// Minimum seconds between cleanups triggered by low free space
+    pub cooldown_secs: u64,
+    
+    /// Control socket location (defaults to `$XDG_RUNTIME_DIR/clearmodel/daemon.sock`)
+    pub socket_path: Option<PathBuf>,
+    
+    /// Port for the Prometheus `/metrics` endpoint (disabled when unset)
+    pub metrics_port: Option<u16>,
+    
+    /// Address the metrics endpoint listens on
+    pub metrics_address: String,
+}
+
+/// Usage tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsageConfig {
+    /// Whether the daemon records opens of files under the cache roots
+    pub track: bool,
+    
+    /// Seconds between writes of recorded opens to the state database
+    pub flush_secs: u64,
+}
+
+/// Prescan configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PrescanConfig {
+    /// Directories listed between pauses
+    pub batch_dirs: u64,
+    
+    /// Milliseconds to pause after each batch
+    pub pause_ms: u64,
+}
+
+/// Python package manager cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PackageCacheConfig {
+    /// Whether pip, uv and poetry caches are cleaned on every run
+    pub enabled: bool,
+    
+    /// Maximum age of cached wheels in days
+    pub wheel_max_age_days: u32,
+    
+    /// Maximum age of other package cache files (HTTP responses, metadata) in days
+    pub max_age_days: u32,
+}
+
+/// Compilation cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CompileCacheConfig {
+    /// Whether the JAX, Triton and TorchInductor caches are cleaned on every run
+    pub enabled: bool,
+    
+    /// Maximum age of compiled artifacts in days; they are cheap to rebuild
+    pub max_age_days: u32,
+}
+
+/// Local LLM model store configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GgufStoreConfig {
+    /// Whether the llama.cpp, LM Studio and GPT4All stores are cleaned on every run
+    pub enabled: bool,
+    
+    /// Maximum age of unused models in days; they were downloaded by hand, so they are kept longer
+    pub max_age_days: u32,
+}
+
+/// Engine cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EngineCacheConfig {
+    /// Whether the ONNX Runtime and TensorRT engine caches are cleaned on every run
+    pub enabled: bool,
+    
+    /// Maximum age of built engines in days; rebuilding one takes minutes, so they outlive compile caches
+    pub max_age_days: u32,
+}
+
+/// Container image pruning configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ContainerConfig {
+    /// Whether images are pruned on every run; off by default since images live outside any cache
+    pub enabled: bool,
+    
+    /// Docker or Podman API socket; found automatically when unset
+    #[serde(default)]
+    pub socket: Option<PathBuf>,
+    
+    /// Repositories whose images may be pruned once old, matched without their tag
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    
+    /// Maximum age of matching images in days, counted from when the image was built
+    pub max_age_days: u32,
+    
+    /// Whether untagged images left by rebuilds and newer pulls are pruned too
+    pub prune_dangling: bool,
+}
+
+/// DVC cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DvcConfig {
+    /// Whether the DVC caches are cleaned on every run
+    pub enabled: bool,
+    
+    /// Workspaces whose `dvc.lock` and `.dvc` files decide what is still referenced; their
+    /// caches and `~/.dvc/cache` are only cleaned when at least one is listed
+    #[serde(default)]
+    pub workspaces: Vec<PathBuf>,
+    
+    /// Unreferenced objects are kept this many days, covering outputs not committed yet
+    pub max_age_days: u32,
+}
+
+/// MLflow artifact configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MlflowConfig {
+    /// Whether MLflow artifacts are cleaned on every run
+    pub enabled: bool,
+    
+    /// Local `mlruns` directories; `$MLFLOW_TRACKING_URI` is added when it is a local path
+    #[serde(default)]
+    pub tracking_dirs: Vec<PathBuf>,
+    
+    /// Artifacts of runs that ended this many days ago are removed; params and metrics stay
+    pub retention_days: u32,
+}
+
+/// Weights & Biases configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WandbConfig {
+    /// Whether W&B data is cleaned on every run
+    pub enabled: bool,
+    
+    /// Maximum age of files in the artifact cache and upload staging, in days
+    pub max_age_days: u32,
+    
+    /// Maximum age of run media and staging in projects without a retention of their own
+    pub run_retention_days: u32,
+    
+    /// Projects whose `wandb/` run directories are cleaned
+    #[serde(default)]
+    pub projects: Vec<WandbProjectConfig>,
+}
+
+/// A project with W&B run directories
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WandbProjectConfig {
+    /// The project, or its `wandb` directory
+    pub path: PathBuf,
+    
+    /// Overrides `run_retention_days` for this project
+    #[serde(default)]
+    pub retention_days: Option<u32>,
+}
+
+/// Jupyter and IPython configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NotebookConfig {
+    /// Whether the `notebooks` group runs on every run; off by default as it touches user state
+    pub enabled: bool,
+    
+    /// Kernel connection files and files of exited servers older than this are removed
+    pub runtime_max_age_days: u32,
+    
+    /// IPython history databases larger than this are removed, losing their history
+    pub ipython_history_max_mb: u64,
+    
+    /// jupyter-cache directories (e.g. a jupyter-book's `_build/.jupyter_cache`)
+    #[serde(default)]
+    pub execution_caches: Vec<PathBuf>,
+    
+    /// Maximum age of files in the execution caches, in days
+    pub execution_cache_max_age_days: u32,
+}
+
+/// GPU driver and library cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GpuCacheConfig {
+    /// Whether the CUDA compute, cuDNN autotune and shader caches are cleaned on every run
+    pub enabled: bool,
+    
+    /// Maximum age of cache entries in days; rebuilding them stalls the first launch, so this is generous
+    pub max_age_days: u32,
+    
+    /// Further autotune cache directories, e.g. where cudnn-frontend plans are serialized
+    #[serde(default)]
+    pub autotune_dirs: Vec<PathBuf>,
+}
+
+/// Library data cache configuration, one entry per library
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DataCacheConfig {
+    /// Whisper checkpoints in `~/.cache/whisper`
+    pub whisper: DataCacheEntry,
+    
+    /// spaCy model packages installed by `spacy download`
+    pub spacy: DataCacheEntry,
+    
+    /// NLTK corpora, tokenizers and taggers in `~/nltk_data`
+    pub nltk: DataCacheEntry,
+    
+    /// gensim-data models and corpora
+    pub gensim: DataCacheEntry,
+}
+
+/// Settings of one library data cache
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DataCacheEntry {
+    /// Whether the cache is cleaned on every run (it can still be selected with `--only`)
+    pub enabled: bool,
+    
+    /// Downloads unused for this many days are removed, each as a whole
+    pub max_age_days: u32,
+}
+
+/// One HuggingFace cache instance (an `HF_HOME` or hub directory)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HfCacheConfig {
+    /// Name used with `--only` (e.g. `scratch`)
+    pub name: String,
+    
+    pub path: PathBuf,
+    
+    /// Overrides `max_cache_age_days` for this cache
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    
+    /// Least recently used repositories are removed until the cache fits (disabled when unset)
+    #[serde(default)]
+    pub max_size_gb: Option<f64>,
+    
+    /// Overrides `keep_revisions` for this cache
+    #[serde(default)]
+    pub keep_revisions: Option<usize>,
+}
+
+/// An image-generation app whose `models` folder is cleaned
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ImageStoreConfig {
+    /// Name used with `--only` (e.g. `comfy`)
+    pub name: String,
+    
+    /// The app's installation or data directory, the one holding `models`
+    pub path: PathBuf,
+    
+    /// Which app it is; detected from the folder layout when unset
+    #[serde(default)]
+    pub kind: Option<ImageStoreKind>,
+    
+    /// Overrides `max_cache_age_days` for this store
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+}
+
+/// An object storage prefix cleaned like a cache directory (needs the `remote` feature)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RemoteBucketConfig {
+    /// Name used with `--only` (e.g. `team-cache`)
+    pub name: String,
+    
+    /// `s3://bucket/prefix` or `gs://bucket/prefix`
+    pub url: String,
+    
+    /// S3-compatible endpoint, such as a MinIO server (`https://minio.internal:9000`)
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+    
+    /// AWS CLI profile, or gcloud configuration for `gs://` URLs
+    #[serde(default)]
+    pub profile: Option<String>,
+    
+    /// Overrides `max_cache_age_days` for this prefix
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    
+    /// Size the prefix may grow to; the least recently written models are deleted beyond it
+    #[serde(default)]
+    pub budget_gb: Option<f64>,
+}
+
+/// Age-based lifecycle configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LifecycleConfig {
+    /// Whether the tiers are applied; their delete ages then replace the age limits above
+    pub enabled: bool,
+    
+    /// Where archived files are moved (defaults to `$XDG_CACHE_HOME/clearmodel/archive`)
+    pub archive_dir: Option<PathBuf>,
+    
+    /// Hours between lifecycle passes in the daemon
+    pub interval_hours: u64,
+    
+    /// Tiers for the ML model caches
+    pub ml: LifecycleTiers,
+    
+    /// Tiers for pip, uv and poetry caches
+    pub packages: LifecycleTiers,
+}
+
+/// Ages (in days) at which a cache file moves to the next lifecycle stage
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LifecycleTiers {
+    /// Report files at least this old
+    pub warn_days: Option<u32>,
+    
+    /// Move files at least this old into the archive
+    pub archive_days: Option<u32>,
+    
+    /// Delete files (including archived ones) at least this old
+    pub delete_days: u32,
+}
+
+/// Log output configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Identical warnings shown before the rest are collapsed into one summary line (0 disables)
+    pub dedup_threshold: usize,
+    
+    /// `text` (default) or `json` lines, on the console and in log files once the config is loaded
+    pub format: LogFormat,
+    
+    /// Also write logs to `clearmodel.log` in `directory`, for daemon and scheduled runs
+    pub file: bool,
+    
+    /// Where log files go (defaults to `~/.local/state/clearmodel/logs`)
+    pub directory: Option<PathBuf>,
+    
+    /// Most verbose level written to the log files: `error`, `warn`, `info` (default), `debug` or `trace`
+    pub file_level: String,
+    
+    /// When log files are rotated and how many old ones are kept
+    pub rotation: LogRotation,
+}
+
+/// Log file rotation
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct LogRotation {
+    /// Size in MB a log file is rotated at (0 for no limit)
+    pub max_size_mb: u64,
+    
+    /// Also start a new file every `hourly` or `daily` (default) period, or `never`
+    pub period: RotationPeriod,
+    
+    /// Rotated files kept (`clearmodel.log.1` is the newest)
+    pub keep: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_size_mb: 10,
+            period: RotationPeriod::default(),
+            keep: 7,
+        }
+    }
 }
 
 impl Default for ClearModelConfig {
@@ -61,8 +675,15 @@ impl Default for ClearModelConfig {
         Self {
             cache_paths: Self::default_cache_paths(),
             max_cache_age_days: 7,
+            age_basis: AgeBasis::default(),
+            min_file_size_mb: None,
+            size_basis: SizeBasis::default(),
+            policy: EvictionPolicy::default(),
             max_parallel_operations: 10,
             follow_symlinks: false,
+            stay_on_filesystem: false,
+            network_filesystems: NetworkFsMode::default(),
+            prune_empty_dirs: false,
             python_cache_extensions: vec![
                 ".pyc".to_string(),
                 ".pyo".to_string(),
@@ -76,10 +697,54 @@ impl Default for ClearModelConfig {
                 "venv".to_string(),
                 "__pycache__".to_string(),
             ],
+            exclude_patterns: Vec::new(),
             min_free_space_gb: 1,
             default_dry_run: false,
             log_level: "info".to_string(),
+            pinned_models: Vec::new(),
+            keep_revisions: None,
+            max_total_cache_gb: None,
+            target_budgets_gb: BTreeMap::new(),
+            quarantine: false,
+            quarantine_ttl_days: 7,
+            deletion_mode: DeletionMode::default(),
+            io_rate_limit: IoRateLimitConfig::default(),
+            retry: RetryConfig::default(),
+            concurrency: ConcurrencyConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            commands: CommandsConfig::default(),
+            shared: SharedCacheConfig::default(),
+            checkpoint: true,
+            clean_incomplete_downloads: false,
+            incomplete_grace_minutes: 60,
+            rule_precedence: DEFAULT_PRECEDENCE.iter().map(|rule| rule.label().to_string()).collect(),
+            on_conflict: ConflictMode::Precedence,
             security: SecurityConfig::default(),
+            watch: WatchConfig::default(),
+            daemon: DaemonConfig::default(),
+            usage: UsageConfig::default(),
+            prescan: PrescanConfig::default(),
+            package_caches: PackageCacheConfig::default(),
+            compile_caches: CompileCacheConfig::default(),
+            gguf_stores: GgufStoreConfig::default(),
+            data_caches: DataCacheConfig::default(),
+            engine_caches: EngineCacheConfig::default(),
+            gpu_caches: GpuCacheConfig::default(),
+            containers: ContainerConfig::default(),
+            dvc: DvcConfig::default(),
+            mlflow: MlflowConfig::default(),
+            wandb: WandbConfig::default(),
+            notebooks: NotebookConfig::default(),
+            hf_caches: Vec::new(),
+            image_stores: Vec::new(),
+            remote_buckets: Vec::new(),
+            lifecycle: LifecycleConfig::default(),
+            logging: LoggingConfig::default(),
+            journal: JournalConfig::default(),
+            audit: AuditConfig::default(),
+            notifications: NotificationConfig::default(),
+            older_than: None,
+            newer_than: None,
         }
     }
 }
@@ -91,79 +756,728 @@ impl Default for SecurityConfig {
             check_path_traversal: true,
             max_path_depth: 20,
             require_confirmation_threshold_gb: Some(10),
+            allow_container_host_mounts: false,
+            privilege_backend: PrivilegeBackend::default(),
+            askpass_helper: None,
+            denied_roots: default_denied_roots(),
+            allowed_roots: Vec::new(),
+            allowed_uids: Vec::new(),
+            include_other_users: false,
+            privileged: false,
+            sandbox: false,
+        }
+    }
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            quiesce_secs: 10,
+            min_download_mb: 100,
+        }
+    }
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: 60,
+            cooldown_secs: 900,
+            socket_path: None,
+            metrics_port: None,
+            metrics_address: "127.0.0.1".to_string(),
+        }
+    }
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            track: false,
+            flush_secs: 60,
+        }
+    }
+}
+
+impl Default for PrescanConfig {
+    fn default() -> Self {
+        Self {
+            batch_dirs: 64,
+            pause_ms: 10,
+        }
+    }
+}
+
+impl Default for PackageCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            wheel_max_age_days: 30,
+            max_age_days: 14,
+        }
+    }
+}
+
+impl Default for CompileCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 3,
+        }
+    }
+}
+
+impl Default for EngineCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 14,
         }
     }
 }
 
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            socket: None,
+            patterns: ["pytorch/*", "nvidia/cuda*", "nvcr.io/nvidia/*", "huggingface/*"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            max_age_days: 30,
+            prune_dangling: true,
+        }
+    }
+}
+
+impl Default for DvcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            workspaces: Vec::new(),
+            max_age_days: 14,
+        }
+    }
+}
+
+impl Default for MlflowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tracking_dirs: Vec::new(),
+            retention_days: 90,
+        }
+    }
+}
+
+impl Default for WandbConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 14,
+            run_retention_days: 30,
+            projects: Vec::new(),
+        }
+    }
+}
+
+impl Default for NotebookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            runtime_max_age_days: 7,
+            ipython_history_max_mb: 100,
+            execution_caches: Vec::new(),
+            execution_cache_max_age_days: 30,
+        }
+    }
+}
+
+impl Default for GpuCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 30,
+            autotune_dirs: Vec::new(),
+        }
+    }
+}
+
+impl Default for DataCacheConfig {
+    fn default() -> Self {
+        let entry = |max_age_days| DataCacheEntry { enabled: true, max_age_days };
+        Self {
+            whisper: entry(60),
+            spacy: entry(90),
+            // Corpora are small and slow to fetch again
+            nltk: entry(180),
+            gensim: entry(60),
+        }
+    }
+}
+
+impl Default for GgufStoreConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 90,
+        }
+    }
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            archive_dir: None,
+            interval_hours: 24,
+            ml: LifecycleTiers {
+                warn_days: Some(30),
+                archive_days: Some(60),
+                delete_days: 120,
+            },
+            packages: LifecycleTiers {
+                warn_days: Some(7),
+                archive_days: None,
+                delete_days: 30,
+            },
+        }
+    }
+}
+
+/// Deletion journal configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JournalConfig {
+    /// Whether every removed file is recorded under `~/.local/state/clearmodel`
+    pub enabled: bool,
+    
+    /// Quarantined (and audited) files larger than this (in MB) are journaled without a content hash
+    pub hash_max_mb: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hash_max_mb: 1024,
+        }
+    }
+}
+
+/// Worker thread configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    /// Threads listing directories, shared by every cache root (`max_parallel_operations` when unset)
+    pub scan_workers: Option<usize>,
+    
+    /// Threads inspecting and removing files (one per CPU when unset)
+    pub delete_workers: Option<usize>,
+    
+    /// Deletion workers for single targets, by name (`nfs-models = 1` removes its files one at a time)
+    pub targets: BTreeMap<String, usize>,
+}
+
+/// Watchdog limits on cleanups, so a hung NFS mount or external command cannot stall a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    /// Seconds one target may take to scan and clean before it is abandoned and the run moves
+    /// on (no limit when unset)
+    pub target_secs: Option<u64>,
+    
+    /// Seconds a whole cleanup may take; targets not finished by then are abandoned
+    pub run_secs: Option<u64>,
+    
+    /// Limits for single targets, by name, overriding `target_secs`
+    pub targets: BTreeMap<String, u64>,
+}
+
+/// External tools run while cleaning and repairing caches (`huggingface-cli`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct CommandsConfig {
+    /// Programs that may be run, by name or absolute path; anything else is refused
+    pub allowed: Vec<String>,
+    
+    /// Seconds a command may take before it is killed
+    pub timeout_secs: u64,
+    
+    /// Limits for single commands, keyed by program or by program and subcommand
+    /// (`"huggingface-cli download"`), overriding `timeout_secs`
+    pub timeouts: BTreeMap<String, u64>,
+    
+    /// Environment variables passed through; commands see no others
+    pub pass_env: Vec<String>,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            allowed: [
+                "huggingface-cli",
+                // Notifications and remote configuration
+                "curl", "ssh-keygen",
+                // `clearmodel schedule`
+                "systemctl", "launchctl", "schtasks",
+                // The keyring
+                "secret-tool", "/usr/bin/security", "powershell.exe",
+                // `security.privilege_backend`, by absolute path so `PATH` cannot substitute another
+                "/usr/bin/sudo", "/usr/bin/osascript", "/usr/bin/pkexec",
+            ].into_iter().map(String::from).collect(),
+            timeout_secs: 300,
+            timeouts: BTreeMap::from([("huggingface-cli download".to_string(), 3600)]),
+            pass_env: [
+                "PATH", "HOME", "USER", "LANG", "LC_ALL", "TMPDIR", "SYSTEMROOT",
+                "HF_HOME", "HF_HUB_CACHE", "HF_TOKEN", "HF_ENDPOINT",
+                "HTTP_PROXY", "HTTPS_PROXY", "NO_PROXY", "http_proxy", "https_proxy", "no_proxy",
+                "SSL_CERT_FILE", "SSL_CERT_DIR", "CURL_CA_BUNDLE",
+                // The user's service manager and keyring are reached through these
+                "XDG_RUNTIME_DIR", "DBUS_SESSION_BUS_ADDRESS",
+                "USERPROFILE", "APPDATA", "LOCALAPPDATA", "USERNAME",
+            ].into_iter().map(String::from).collect(),
+        }
+    }
+}
+
+/// Shared cache mode (`--shared`): cooperate with other users' clearmodel instances
+/// cleaning the same caches
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SharedCacheConfig {
+    /// Lock each model directory before removing from it and leave other users' files alone
+    pub enabled: bool,
+    
+    /// Shared cache roots (all of `cache_paths` when empty)
+    pub paths: Vec<PathBuf>,
+    
+    /// Remove other users' files in the shared caches too (`--all-users`); needs root
+    pub all_users: bool,
+}
+
+/// IO rate limit configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct IoRateLimitConfig {
+    /// Most files removed per second (unlimited when unset)
+    pub files_per_sec: Option<u32>,
+    
+    /// Most MB per second read for hashing or written for shredding (unlimited when unset)
+    pub bandwidth_mb_per_sec: Option<f64>,
+    
+    /// `normal` (default), `low` (nice 10, lowest best-effort IO) or `idle` (nice 19, idle IO class)
+    pub priority: IoPriority,
+}
+
+/// Retries of file operations that fail for a passing reason
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Tries per removal, the first included, while it fails with EBUSY, EINTR or a Windows
+    /// sharing violation (1 disables retries)
+    pub max_attempts: u32,
+    
+    /// Pause before the first retry, doubled for each one after
+    pub initial_backoff_ms: u64,
+    
+    /// Longest pause between retries
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 2_000,
+        }
+    }
+}
+
+/// Audit log configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AuditConfig {
+    /// Whether every removed file is appended to the audit log
+    pub enabled: bool,
+    
+    /// Location of the log (defaults to `~/.local/state/clearmodel/audit.log`)
+    pub path: Option<PathBuf>,
+    
+    /// File holding the MAC key as hex, created on first use (defaults to the log path with a `.key`
+    /// extension); `CLEARMODEL_AUDIT_KEY` takes precedence
+    pub key_file: Option<PathBuf>,
+}
+
+/// Webhook notification configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// https URL the summary of each `clean` and `models remove` run is posted to, with its errors
+    pub webhook_url: Option<String>,
+    
+    /// Payload layout: `json` (default), `slack` or `discord`
+    pub format: WebhookFormat,
+    
+    /// Only post when a run fails
+    pub on_failure_only: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            dedup_threshold: 5,
+            format: LogFormat::default(),
+            file: false,
+            directory: None,
+            file_level: "info".to_string(),
+            rotation: LogRotation::default(),
+        }
+    }
+}
+
+/// A problem `clearmodel config validate` found in a configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// JSON pointer to the offending value (`/compile_caches/max_age_days`), empty for the whole file
+    pub pointer: String,
+    
+    /// Line of the value in the file, when it can be located
+    pub line: Option<usize>,
+    
+    pub message: String,
+}
+
 impl ClearModelConfig {
     /// Load configuration from file or create default
     pub async fn load(config_path: Option<&str>) -> Result<Self> {
+        Self::load_profile(config_path, None).await
+    }
+    
+    /// Load configuration with the files' `[profiles.<name>]` tables applied over their base settings
+    ///
+    /// Without `config_path`, the system, user and project files are merged in that order, so
+    /// each only needs the settings it changes (see [`Self::config_layers`]); `config_path`
+    /// replaces all three. The profile is merged key by key over the files; environment
+    /// variables still override everything.
+    pub async fn load_profile(config_path: Option<&str>, profile: Option<&str>) -> Result<Self> {
+        let config_files = match config_path {
+            Some(path) => vec![PathBuf::from(path)].into_iter().filter(|path| path.exists()).collect(),
+            None => Self::config_layers(),
+        };
+        Self::load_files(&config_files, profile).await
+    }
+    
+    async fn load_files(config_files: &[PathBuf], profile: Option<&str>) -> Result<Self> {
         let mut config_builder = Config::builder();
         
         // Start with defaults
         config_builder = config_builder.add_source(Config::try_from(&ClearModelConfig::default())?);
         
-        // Try to load from various configuration file locations
-        let config_paths = if let Some(path) = config_path {
-            vec![PathBuf::from(path)]
-        } else {
-            Self::default_config_paths()
-        };
+        // Later files override earlier ones, table by table
+        for path in config_files {
+            info!("Loading configuration from: {:?}", path);
+            config_builder = config_builder.add_source(
+                File::from(path.clone())
+                    .required(false)
+                    .format(Self::detect_config_format(path))
+            );
+        }
         
-        for path in config_paths {
-            if path.exists() {
-                info!("Loading configuration from: {:?}", path);
-                config_builder = config_builder.add_source(
-                    File::from(path.clone())
-                        .required(false)
-                        .format(Self::detect_config_format(&path))
-                );
-                break;
+        if let Some(name) = profile {
+            if config_files.is_empty() {
+                return Err(ClearModelError::configuration(
+                    format!("Profile {:?} was selected, but no configuration file was found", name)
+                ));
+            }
+            info!("Applying profile {:?}", name);
+            for source in Self::profile_sources(config_files, name)? {
+                config_builder = config_builder.add_source(source);
+            }
+        }
+        
+        // Override with environment variables
+        config_builder = config_builder.add_source(
+            Environment::with_prefix("CLEARMODEL")
+                .prefix_separator("_")
+                .separator("__")
+        );
+        
+        let config = config_builder.build()
+            .map_err(|e| ClearModelError::configuration(
+                format!("Failed to build configuration: {}", e)
+            ))?;
+            
+        let mut clearmodel_config: ClearModelConfig = config.try_deserialize()
+            .map_err(|e| ClearModelError::configuration(
+                format!("Failed to deserialize configuration: {}", e)
+            ))?;
+        clearmodel_config.expand_cache_paths()?;
+        
+        debug!("Loaded configuration: {:#?}", clearmodel_config);
+        clearmodel_config.validate()?;
+        
+        Ok(clearmodel_config)
+    }
+    
+    /// Expand `~`, `$VAR` and globs in `cache_paths`, `security.allowed_roots` and `shared.paths`,
+    /// logging what each pattern matched
+    fn expand_cache_paths(&mut self) -> Result<()> {
+        self.cache_paths = Self::expand_paths("cache_paths", &self.cache_paths)?;
+        self.security.allowed_roots = Self::expand_paths("security.allowed_roots", &self.security.allowed_roots)?;
+        self.shared.paths = Self::expand_paths("shared.paths", &self.shared.paths)?;
+        Ok(())
+    }
+    
+    fn expand_paths(key: &str, entries: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut expanded: Vec<PathBuf> = Vec::new();
+        for entry in entries {
+            let paths = expand::expand(entry).map_err(|e| ClearModelError::configuration(
+                format!("Invalid {} entry {:?}: {}", key, entry, e)
+            ))?;
+            if paths.is_empty() {
+                warn!("{} entry {:?} matched no directories", key, entry);
+            } else if paths.as_slice() != std::slice::from_ref(entry) {
+                info!("{} entry {:?} expanded to {:?}", key, entry, paths);
             }
+            for path in paths {
+                if !expanded.contains(&path) {
+                    expanded.push(path);
+                }
+            }
+        }
+        Ok(expanded)
+    }
+    
+    /// Validate the configuration
+    /// Refuse `security.privileged` (or `--privileged`) with quarantine or shredding: root
+    /// removes those files outside the pipeline, so they could be neither moved into this
+    /// user's quarantine nor overwritten first
+    pub fn check_privileged(&self) -> Result<()> {
+        if self.security.privileged && (self.quarantine || self.deletion_mode == DeletionMode::Shred) {
+            return Err(ClearModelError::configuration(
+                "security.privileged cannot be combined with quarantine or deletion_mode = \"shred\"; files removed with administrator rights are deleted outright".to_string()
+            ));
+        }
+        Ok(())
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if self.cache_paths.is_empty() {
+            return Err(ClearModelError::configuration(
+                "No cache paths configured".to_string()
+            ));
+        }
+        
+        if self.max_parallel_operations == 0 {
+            return Err(ClearModelError::configuration(
+                "max_parallel_operations must be greater than 0".to_string()
+            ));
+        }
+        
+        if self.security.max_path_depth == 0 {
+            return Err(ClearModelError::configuration(
+                "max_path_depth must be greater than 0".to_string()
+            ));
+        }
+        
+        SecurityManager::denied_roots(&self.security.denied_roots)?;
+        if let Some(relative) = self.security.allowed_roots.iter().find(|root| !root.is_absolute()) {
+            return Err(ClearModelError::configuration(
+                format!("security.allowed_roots entries must be absolute: {:?}", relative)
+            ));
+        }
+        if self.security.sandbox && !cfg!(target_os = "linux") {
+            return Err(ClearModelError::configuration(
+                "security.sandbox uses Landlock and is only available on Linux".to_string()
+            ));
+        }
+        
+        for pattern in &self.pinned_models {
+            PinnedModels::validate_pattern(pattern)
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Invalid pinned model pattern {:?}: {}", pattern, e)
+                ))?;
+        }
+        
+        if self.keep_revisions == Some(0) || self.hf_caches.iter().any(|cache| cache.keep_revisions == Some(0)) {
+            return Err(ClearModelError::configuration(
+                "keep_revisions must be at least 1; leave it unset to keep every revision".to_string()
+            ));
+        }
+        
+        if self.min_file_size_mb.is_some_and(|mb| !mb.is_finite() || mb < 0.0) {
+            return Err(ClearModelError::configuration(
+                "min_file_size_mb must not be negative".to_string()
+            ));
+        }
+        
+        let budgets = self.max_total_cache_gb.iter().chain(self.target_budgets_gb.values());
+        if budgets.into_iter().any(|gb| !gb.is_finite() || *gb <= 0.0) {
+            return Err(ClearModelError::configuration(
+                "max_total_cache_gb and target_budgets_gb must be positive".to_string()
+            ));
+        }
+        
+        if self.policy == EvictionPolicy::Lru && !cfg!(feature = "usage") {
+            return Err(ClearModelError::configuration(
+                "policy = \"lru\" needs clearmodel built with the `usage` feature".to_string()
+            ));
+        }
+        if self.usage.flush_secs == 0 {
+            return Err(ClearModelError::configuration(
+                "usage.flush_secs must be greater than 0".to_string()
+            ));
+        }
+        
+        if let Some(rate) = self.io_rate_limit.bandwidth_mb_per_sec {
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(ClearModelError::configuration(format!(
+                    "io_rate_limit.bandwidth_mb_per_sec must be a positive number of MB, got {}",
+                    rate
+                )));
+            }
+        }
+        if self.logging.file_level.parse::<tracing::level_filters::LevelFilter>().is_err() {
+            return Err(ClearModelError::configuration(format!(
+                "logging.file_level must be error, warn, info, debug or trace, got {:?}",
+                self.logging.file_level
+            )));
+        }
+        
+        if let Some(url) = self.notifications.webhook_url.as_deref().filter(|url| !url.starts_with("https://")) {
+            return Err(ClearModelError::configuration(format!(
+                "notifications.webhook_url must be an https:// URL, got {:?}",
+                url.split('/').take(3).collect::<Vec<_>>().join("/")
+            )));
+        }
+        
+        let timeouts = &self.timeouts;
+        if timeouts.target_secs.iter().chain(&timeouts.run_secs).chain(timeouts.targets.values()).any(|secs| *secs == 0) {
+            return Err(ClearModelError::configuration(
+                "timeouts must be at least 1 second; leave them unset for no limit".to_string()
+            ));
+        }
+        
+        let commands = &self.commands;
+        if commands.timeout_secs == 0 || commands.timeouts.values().any(|secs| *secs == 0) {
+            return Err(ClearModelError::configuration(
+                "commands timeouts must be at least 1 second".to_string()
+            ));
         }
         
-        // Override with environment variables
-        config_builder = config_builder.add_source(
-            Environment::with_prefix("CLEARMODEL")
-                .prefix_separator("_")
-                .separator("__")
-        );
+        if self.retry.max_attempts == 0 {
+            return Err(ClearModelError::configuration(
+                "retry.max_attempts must be at least 1; set it to 1 to disable retries".to_string()
+            ));
+        }
         
-        let config = config_builder.build()
-            .map_err(|e| ClearModelError::configuration(
-                format!("Failed to build configuration: {}", e)
-            ))?;
-            
-        let clearmodel_config: ClearModelConfig = config.try_deserialize()
-            .map_err(|e| ClearModelError::configuration(
-                format!("Failed to deserialize configuration: {}", e)
-            ))?;
+        if self.io_rate_limit.files_per_sec == Some(0) {
+            return Err(ClearModelError::configuration(
+                "io_rate_limit.files_per_sec must be greater than 0; leave it unset for no limit".to_string()
+            ));
+        }
         
-        debug!("Loaded configuration: {:#?}", clearmodel_config);
-        clearmodel_config.validate()?;
+        let workers = [self.concurrency.scan_workers, self.concurrency.delete_workers];
+        if workers.iter().flatten().chain(self.concurrency.targets.values()).any(|threads| *threads == 0) {
+            return Err(ClearModelError::configuration(
+                "concurrency.scan_workers, delete_workers and targets must be greater than 0".to_string()
+            ));
+        }
         
-        Ok(clearmodel_config)
-    }
-    
-    /// Validate the configuration
-    fn validate(&self) -> Result<()> {
-        if self.cache_paths.is_empty() {
+        if self.deletion_mode == DeletionMode::Shred && self.quarantine {
             return Err(ClearModelError::configuration(
-                "No cache paths configured".to_string()
+                "deletion_mode = \"shred\" cannot be combined with quarantine, which keeps the files".to_string()
             ));
         }
+        self.check_privileged()?;
         
-        if self.max_parallel_operations == 0 {
+        if self.security.privilege_backend == PrivilegeBackend::Authorization && !cfg!(target_os = "macos") {
             return Err(ClearModelError::configuration(
-                "max_parallel_operations must be greater than 0".to_string()
+                "privilege_backend = \"authorization\" is only available on macOS".to_string()
+            ));
+        }
+        if self.security.privilege_backend == PrivilegeBackend::Pkexec && !cfg!(target_os = "linux") {
+            return Err(ClearModelError::configuration(
+                "privilege_backend = \"pkexec\" is only available on Linux".to_string()
             ));
         }
         
-        if self.security.max_path_depth == 0 {
+        for pattern in &self.exclude_patterns {
+            exclude::validate_pattern(pattern)?;
+        }
+        
+        for (index, label) in self.rule_precedence.iter().enumerate() {
+            if Decision::from_label(label).is_none() {
+                return Err(ClearModelError::configuration(
+                    format!("Unknown rule in rule_precedence: {:?}", label)
+                ));
+            }
+            if self.rule_precedence[..index].contains(label) {
+                return Err(ClearModelError::configuration(
+                    format!("Rule listed twice in rule_precedence: {:?}", label)
+                ));
+            }
+        }
+        
+        containers::matcher(&self.containers.patterns).map_err(|e| ClearModelError::configuration(
+            format!("Invalid containers pattern: {}", e)
+        ))?;
+        
+        for (index, cache) in self.hf_caches.iter().enumerate() {
+            if cache.name.is_empty() || RESERVED_TARGET_NAMES.contains(&cache.name.as_str()) {
+                return Err(ClearModelError::configuration(
+                    format!("Invalid hf_caches name {:?}: it must be non-empty and not a built-in target", cache.name)
+                ));
+            }
+            if self.hf_caches[..index].iter().any(|other| other.name == cache.name) {
+                return Err(ClearModelError::configuration(
+                    format!("HuggingFace cache name used twice: {:?}", cache.name)
+                ));
+            }
+        }
+        for (index, store) in self.image_stores.iter().enumerate() {
+            if store.name.is_empty() || RESERVED_TARGET_NAMES.contains(&store.name.as_str()) {
+                return Err(ClearModelError::configuration(
+                    format!("Invalid image_stores name {:?}: it must be non-empty and not a built-in target", store.name)
+                ));
+            }
+            let taken = self.image_stores[..index].iter().any(|other| other.name == store.name)
+                || self.hf_caches.iter().any(|cache| cache.name == store.name);
+            if taken {
+                return Err(ClearModelError::configuration(
+                    format!("Image model store name used twice: {:?}", store.name)
+                ));
+            }
+        }
+        
+        if !self.remote_buckets.is_empty() && !cfg!(feature = "remote") {
             return Err(ClearModelError::configuration(
-                "max_path_depth must be greater than 0".to_string()
+                "remote_buckets needs clearmodel built with the `remote` feature".to_string()
             ));
         }
+        for (index, bucket) in self.remote_buckets.iter().enumerate() {
+            if bucket.name.is_empty() || RESERVED_TARGET_NAMES.contains(&bucket.name.as_str()) {
+                return Err(ClearModelError::configuration(
+                    format!("Invalid remote_buckets name {:?}: it must be non-empty and not a built-in target", bucket.name)
+                ));
+            }
+            let taken = self.remote_buckets[..index].iter().any(|other| other.name == bucket.name)
+                || self.hf_caches.iter().any(|cache| cache.name == bucket.name)
+                || self.image_stores.iter().any(|store| store.name == bucket.name);
+            if taken {
+                return Err(ClearModelError::configuration(
+                    format!("Remote bucket name used twice: {:?}", bucket.name)
+                ));
+            }
+            #[cfg(feature = "remote")]
+            crate::remote_store::Location::parse(&bucket.url)?;
+            if bucket.endpoint_url.is_some() && !bucket.url.starts_with("s3://") {
+                return Err(ClearModelError::configuration(
+                    format!("remote_buckets {:?}: endpoint_url only applies to s3:// URLs", bucket.name)
+                ));
+            }
+        }
         
         // Validate cache paths exist or can be created
         for path in &self.cache_paths {
@@ -217,26 +1531,82 @@ impl ClearModelConfig {
             }
         }
         
+        // HuggingFace and torch use %USERPROFILE%\.cache like everywhere else, but
+        // some installers and older releases put caches under %LOCALAPPDATA%
+        if cfg!(windows) {
+            if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA").map(PathBuf::from) {
+                let windows_cache_dirs = [
+                    "huggingface",
+                    "torch",
+                    "Temp\\tfhub_modules",
+                ];
+                
+                for dir in &windows_cache_dirs {
+                    paths.push(local_app_data.join(dir));
+                }
+            }
+        }
+        
+        // Torch hub, its kernel cache and extension builds, wherever the environment moved them
+        let torch_paths = torch_caches::cache_paths(&paths);
+        paths.extend(torch_paths);
+        if let Some(datasets) = hf_datasets::datasets_dir().filter(|dir| !paths.iter().any(|path| dir.starts_with(path))) {
+            paths.push(datasets);
+        }
+        
         paths
     }
     
-    /// Get default configuration file paths
+    /// Configuration files merged when none is given, lowest precedence first
+    ///
+    /// The first existing file of each layer is used: the system one (`/etc/clearmodel/config.toml`,
+    /// `%ProgramData%\clearmodel\config.toml` on Windows), the user one (`~/.clearmodel.toml` or the
+    /// XDG config directory) and the project one (`clearmodel.toml` in the working directory).
+    pub fn config_layers() -> Vec<PathBuf> {
+        [Self::system_config_paths(), Self::user_config_paths(), Self::project_config_paths()]
+            .into_iter()
+            .filter_map(|layer| layer.into_iter().find(|path| path.exists()))
+            .collect()
+    }
+    
+    fn system_config_paths() -> Vec<PathBuf> {
+        let dir = if cfg!(windows) {
+            std::env::var_os("ProgramData").map(|data| PathBuf::from(data).join("clearmodel"))
+        } else {
+            Some(PathBuf::from("/etc/clearmodel"))
+        };
+        dir.into_iter()
+            .flat_map(|dir| ["config.toml", "config.yaml", "config.json"].map(|name| dir.join(name)))
+            .collect()
+    }
+    
+    fn project_config_paths() -> Vec<PathBuf> {
+        vec![
+            PathBuf::from("clearmodel.toml"),
+            PathBuf::from("clearmodel.yaml"),
+            PathBuf::from("clearmodel.json"),
+        ]
+    }
+    
+    /// Configuration files a user writes to, project ones first
     fn default_config_paths() -> Vec<PathBuf> {
+        let mut paths = Self::project_config_paths();
+        paths.extend(Self::user_config_paths());
+        paths
+    }
+    
+    fn user_config_paths() -> Vec<PathBuf> {
         let mut paths = Vec::new();
         
-        // Current directory
-        paths.push(PathBuf::from("clearmodel.toml"));
-        paths.push(PathBuf::from("clearmodel.yaml"));
-        paths.push(PathBuf::from("clearmodel.json"));
-        
         // Home directory
         if let Some(home) = home_dir() {
             paths.push(home.join(".clearmodel.toml"));
             paths.push(home.join(".clearmodel.yaml"));
             paths.push(home.join(".clearmodel.json"));
-            
-            // XDG config directory
-            let config_dir = home.join(".config").join("clearmodel");
+        }
+        
+        // XDG config directory
+        if let Some(config_dir) = Self::user_config_dir() {
             paths.push(config_dir.join("config.toml"));
             paths.push(config_dir.join("config.yaml"));
             paths.push(config_dir.join("config.json"));
@@ -245,6 +1615,290 @@ impl ClearModelConfig {
         paths
     }
     
+    /// `$XDG_CONFIG_HOME/clearmodel`, else `~/.config/clearmodel`
+    pub fn user_config_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home_dir().map(|home| home.join(".config")))
+            .map(|dir| dir.join("clearmodel"))
+    }
+    
+    /// Configuration file that commands such as `pin` write to
+    pub fn writable_config_path(config_path: Option<&str>) -> PathBuf {
+        if let Some(path) = config_path {
+            return PathBuf::from(path);
+        }
+        
+        Self::default_config_paths()
+            .into_iter()
+            .find(|path| path.exists())
+            .or_else(|| Self::user_config_dir().map(|dir| dir.join("config.toml")))
+            .unwrap_or_else(|| PathBuf::from("clearmodel.toml"))
+    }
+    
+    /// JSON Schema of the configuration file, for editor completion and `clearmodel config validate`
+    ///
+    /// Every key is optional, since a file only overrides the defaults, and unknown keys are
+    /// rejected so that typos are caught. `profiles` holds tables of the same keys.
+    pub fn json_schema() -> serde_json::Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(ClearModelConfig))
+            .expect("the configuration schema serializes");
+        relax_schema(&mut schema);
+        let profile = serde_json::json!({
+            "type": "object",
+            "properties": schema["properties"].clone(),
+            "additionalProperties": false,
+        });
+        schema["properties"]["profiles"] = serde_json::json!({
+            "description": "Named overrides selected with `--profile`",
+            "type": "object",
+            "additionalProperties": profile,
+        });
+        schema
+    }
+    
+    /// Check a configuration file against the schema, then the way `load` would, profiles included
+    ///
+    /// Returns every problem found; an empty list means the file is valid.
+    pub async fn validate_file(path: &Path) -> Result<Vec<ValidationIssue>> {
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to read {:?}: {}", path, e),
+                Some(path.to_path_buf())
+            ))?;
+        let format = Self::detect_config_format(path);
+        let document = match parse_document(&content, format) {
+            Ok(document) => document,
+            Err(issue) => return Ok(vec![issue]),
+        };
+        
+        let schema = jsonschema::JSONSchema::compile(&Self::json_schema())
+            .map_err(|e| ClearModelError::configuration(
+                format!("The configuration schema does not compile: {}", e)
+            ))?;
+        let issues: Vec<ValidationIssue> = match schema.validate(&document) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.map(|error| {
+                let pointer = error.instance_path.to_string();
+                ValidationIssue {
+                    line: (format == config::FileFormat::Toml).then(|| toml_line(&content, &pointer)).flatten(),
+                    pointer,
+                    message: error.to_string(),
+                }
+            }).collect(),
+        };
+        if !issues.is_empty() {
+            return Ok(issues);
+        }
+        
+        // Rules the schema can't express: patterns, reserved names, paths that must expand
+        let path_text = path.to_str().ok_or_else(|| ClearModelError::configuration(
+            format!("{:?} is not valid UTF-8", path)
+        ))?;
+        let mut profiles: Vec<Option<&str>> = vec![None];
+        if let Some(names) = document.get("profiles").and_then(|profiles| profiles.as_object()) {
+            profiles.extend(names.keys().map(|name| Some(name.as_str())));
+        }
+        let mut issues = Vec::new();
+        for profile in profiles {
+            if let Err(e) = Self::load_profile(Some(path_text), profile).await {
+                let pointer = profile.map(|name| format!("/profiles/{}", name.replace('~', "~0").replace('/', "~1"))).unwrap_or_default();
+                issues.push(ValidationIssue {
+                    line: (format == config::FileFormat::Toml).then(|| toml_line(&content, &pointer)).flatten(),
+                    pointer,
+                    message: e.to_string(),
+                });
+            }
+        }
+        Ok(issues)
+    }
+    
+    /// Add or remove a pinned model pattern in a configuration file, keeping the rest intact
+    ///
+    /// Returns whether the file changed.
+    pub async fn update_pinned_models(path: &Path, pattern: &str, pin: bool) -> Result<bool> {
+        if pin {
+            PinnedModels::validate_pattern(pattern)
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Invalid pinned model pattern {:?}: {}", pattern, e)
+                ))?;
+        }
+        
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(ClearModelError::file_operation(
+                    format!("Failed to read config file: {}", e),
+                    Some(path.to_path_buf())
+                ));
+            }
+        };
+        
+        let updated = match Self::detect_config_format(path) {
+            config::FileFormat::Yaml => Self::update_pinned_yaml(&content, pattern, pin)?,
+            config::FileFormat::Json => Self::update_pinned_json(&content, pattern, pin)?,
+            _ => Self::update_pinned_toml(&content, pattern, pin)?,
+        };
+        
+        let Some(updated) = updated else {
+            return Ok(false);
+        };
+        
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create config directory: {}", e),
+                    Some(parent.to_path_buf())
+                ))?;
+        }
+        
+        tokio::fs::write(path, updated).await
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to write config file: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        
+        info!("Updated pinned models in: {:?}", path);
+        Ok(true)
+    }
+    
+    /// Edit `pinned_models` in a TOML document without disturbing comments or layout
+    fn update_pinned_toml(content: &str, pattern: &str, pin: bool) -> Result<Option<String>> {
+        let mut doc = content.parse::<toml_edit::DocumentMut>()
+            .map_err(|e| ClearModelError::configuration(
+                format!("Failed to parse TOML configuration: {}", e)
+            ))?;
+        
+        let pinned = doc.entry("pinned_models")
+            .or_insert_with(|| toml_edit::value(toml_edit::Array::new()))
+            .as_array_mut()
+            .ok_or_else(|| ClearModelError::configuration(
+                "pinned_models must be an array".to_string()
+            ))?;
+        
+        let position = pinned.iter().position(|value| value.as_str() == Some(pattern));
+        match (pin, position) {
+            (true, None) => pinned.push(pattern),
+            (false, Some(index)) => {
+                pinned.remove(index);
+            }
+            _ => return Ok(None),
+        }
+        
+        Ok(Some(doc.to_string()))
+    }
+    
+    fn update_pinned_json(content: &str, pattern: &str, pin: bool) -> Result<Option<String>> {
+        let mut doc: serde_json::Value = if content.trim().is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_str(content)
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Failed to parse JSON configuration: {}", e)
+                ))?
+        };
+        
+        let mut pinned: Vec<String> = doc.get("pinned_models")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| ClearModelError::configuration(
+                format!("pinned_models must be a list of strings: {}", e)
+            ))?
+            .unwrap_or_default();
+        
+        if !edit_pin_list(&mut pinned, pattern, pin) {
+            return Ok(None);
+        }
+        
+        let object = doc.as_object_mut()
+            .ok_or_else(|| ClearModelError::configuration(
+                "JSON configuration must be an object".to_string()
+            ))?;
+        object.insert("pinned_models".to_string(), serde_json::json!(pinned));
+        
+        serde_json::to_string_pretty(&doc)
+            .map(Some)
+            .map_err(|e| ClearModelError::configuration(
+                format!("Failed to serialize to JSON: {}", e)
+            ))
+    }
+    
+    fn update_pinned_yaml(content: &str, pattern: &str, pin: bool) -> Result<Option<String>> {
+        let mut doc: serde_yaml::Value = if content.trim().is_empty() {
+            serde_yaml::Value::Mapping(serde_yaml::Mapping::new())
+        } else {
+            serde_yaml::from_str(content)
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Failed to parse YAML configuration: {}", e)
+                ))?
+        };
+        
+        let mut pinned: Vec<String> = doc.get("pinned_models")
+            .cloned()
+            .map(serde_yaml::from_value)
+            .transpose()
+            .map_err(|e| ClearModelError::configuration(
+                format!("pinned_models must be a list of strings: {}", e)
+            ))?
+            .unwrap_or_default();
+        
+        if !edit_pin_list(&mut pinned, pattern, pin) {
+            return Ok(None);
+        }
+        
+        let mapping = doc.as_mapping_mut()
+            .ok_or_else(|| ClearModelError::configuration(
+                "YAML configuration must be a mapping".to_string()
+            ))?;
+        mapping.insert("pinned_models".into(), serde_yaml::to_value(&pinned).unwrap_or_default());
+        
+        serde_yaml::to_string(&doc)
+            .map(Some)
+            .map_err(|e| ClearModelError::configuration(
+                format!("Failed to serialize to YAML: {}", e)
+            ))
+    }
+    
+    /// The `[profiles.<name>]` tables of the configuration files, as sources layered over the files
+    ///
+    /// The profile only has to be defined in one of them.
+    fn profile_sources(paths: &[PathBuf], name: &str) -> Result<Vec<Config>> {
+        let mut sources = Vec::new();
+        let mut known = Vec::new();
+        for path in paths {
+            let file = Config::builder()
+                .add_source(File::from(path.as_path()).format(Self::detect_config_format(path)))
+                .build()
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Failed to read profiles from {:?}: {}", path, e)
+                ))?;
+            let profiles = file.get_table("profiles").unwrap_or_default();
+            known.extend(profiles.keys().cloned());
+            let Some(table) = profiles.get(name) else {
+                continue;
+            };
+            let table: serde_json::Value = table.clone().try_deserialize()
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Invalid profile {:?} in {:?}: {}", name, path, e)
+                ))?;
+            sources.push(Config::try_from(&table).map_err(|e| ClearModelError::configuration(
+                format!("Invalid profile {:?} in {:?}: {}", name, path, e)
+            ))?);
+        }
+        
+        if sources.is_empty() {
+            known.sort();
+            known.dedup();
+            return Err(ClearModelError::configuration(
+                format!("Unknown profile {:?} (defined in {:?}: {})", name, paths, if known.is_empty() { "none".to_string() } else { known.join(", ") })
+            ));
+        }
+        Ok(sources)
+    }
+    
     /// Detect configuration file format based on extension
     fn detect_config_format(path: &Path) -> config::FileFormat {
         match path.extension().and_then(|s| s.to_str()) {
@@ -312,6 +1966,11 @@ impl ClearModelConfig {
             .collect()
     }
     
+    /// Whether a path is cleaned as a HuggingFace cache instance rather than a plain cache path
+    pub fn is_hf_cache_instance(&self, path: &Path) -> bool {
+        self.hf_caches.iter().any(|cache| cache.path == path)
+    }
+    
     /// Get cache paths with their sizes
     pub async fn cache_paths_with_sizes(&self) -> Result<Vec<(PathBuf, u64)>> {
         let mut results = Vec::new();
@@ -361,6 +2020,80 @@ impl ClearModelConfig {
     }
 }
 
+/// Add or remove a pattern, returning whether the list changed
+fn edit_pin_list(pinned: &mut Vec<String>, pattern: &str, pin: bool) -> bool {
+    let position = pinned.iter().position(|existing| existing == pattern);
+    match (pin, position) {
+        (true, None) => pinned.push(pattern.to_string()),
+        (false, Some(index)) => {
+            pinned.remove(index);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Make every key of a generated schema optional and reject unknown keys
+///
+/// Objects with `additionalProperties` of their own (maps such as `target_budgets_gb`) keep it.
+fn relax_schema(schema: &mut serde_json::Value) {
+    match schema {
+        serde_json::Value::Object(object) => {
+            if object.get("required").is_some_and(|required| required.is_array()) {
+                object.remove("required");
+            }
+            if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                object.insert("additionalProperties".to_string(), serde_json::Value::Bool(false));
+            }
+            object.values_mut().for_each(relax_schema);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(relax_schema),
+        _ => {}
+    }
+}
+
+/// Parse a configuration file into JSON for schema validation, locating syntax errors
+fn parse_document(content: &str, format: config::FileFormat) -> std::result::Result<serde_json::Value, ValidationIssue> {
+    let syntax_error = |line: Option<usize>, message: String| ValidationIssue { pointer: String::new(), line, message };
+    match format {
+        config::FileFormat::Yaml => serde_yaml::from_str(content)
+            .map_err(|e| syntax_error(e.location().map(|location| location.line()), e.to_string())),
+        config::FileFormat::Json => serde_json::from_str(content)
+            .map_err(|e| syntax_error(Some(e.line()), e.to_string())),
+        _ => toml::from_str(content)
+            .map_err(|e| syntax_error(e.span().map(|span| line_of(content, span.start)), e.message().trim().replace('\n', "; "))),
+    }
+}
+
+/// Line of the TOML key or value a JSON pointer refers to
+fn toml_line(content: &str, pointer: &str) -> Option<usize> {
+    let document = toml_edit::ImDocument::parse(content).ok()?;
+    let mut item = document.as_item();
+    let mut span = None;
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if let Some(table) = item.as_table_like() {
+            let (key, value) = table.get_key_value(&segment)?;
+            span = key.span().or_else(|| value.span()).or(span);
+            item = value;
+        } else {
+            // Entries of arrays are located, but not looked into
+            let index: usize = segment.parse().ok()?;
+            span = match item {
+                toml_edit::Item::Value(toml_edit::Value::Array(array)) => array.get(index).and_then(|value| value.span()),
+                toml_edit::Item::ArrayOfTables(tables) => tables.get(index).and_then(|table| table.span()),
+                _ => None,
+            }.or(span);
+            break;
+        }
+    }
+    span.map(|span| line_of(content, span.start))
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -385,4 +2118,109 @@ mod tests {
         let loaded_config = ClearModelConfig::load(Some(config_path.to_str().unwrap())).await.unwrap();
         assert_eq!(original_config.max_cache_age_days, loaded_config.max_cache_age_days);
     }
+    
+    #[tokio::test]
+    async fn test_minimal_config_file_loads() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("minimal.toml");
+        tokio::fs::write(&config_path, "max_cache_age_days = 3\n").await.unwrap();
+        
+        let loaded = ClearModelConfig::load(Some(config_path.to_str().unwrap())).await.unwrap();
+        assert_eq!(loaded.max_cache_age_days, 3);
+        assert!(loaded.pinned_models.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_profile_overrides_only_its_own_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("clearmodel.toml");
+        let content = "max_cache_age_days = 30\n\n[compile_caches]\nenabled = false\nmax_age_days = 7\n\n[profiles.ci.compile_caches]\nmax_age_days = 0\n\n[profiles.ci]\nmax_cache_age_days = 1\n";
+        tokio::fs::write(&config_path, content).await.unwrap();
+        let path = config_path.to_str().unwrap();
+        
+        let base = ClearModelConfig::load(Some(path)).await.unwrap();
+        assert_eq!((base.max_cache_age_days, base.compile_caches.max_age_days), (30, 7));
+        let ci = ClearModelConfig::load_profile(Some(path), Some("ci")).await.unwrap();
+        assert_eq!((ci.max_cache_age_days, ci.compile_caches.max_age_days), (1, 0));
+        assert!(!ci.compile_caches.enabled);
+        
+        let unknown = ClearModelConfig::load_profile(Some(path), Some("aggressive")).await.unwrap_err();
+        assert!(unknown.to_string().contains("ci"));
+    }
+    
+    #[tokio::test]
+    async fn test_config_layers_merge_table_by_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let layers = [
+            ("system.toml", "max_cache_age_days = 30\nquarantine = true\n\n[compile_caches]\nenabled = false\nmax_age_days = 14\n\n[profiles.ci]\nquarantine = false\n"),
+            ("user.yaml", "max_cache_age_days: 10\ncompile_caches:\n  max_age_days: 3\n"),
+            ("project.toml", "max_cache_age_days = 2\n"),
+        ];
+        let mut files = Vec::new();
+        for (name, content) in layers {
+            tokio::fs::write(temp_dir.path().join(name), content).await.unwrap();
+            files.push(temp_dir.path().join(name));
+        }
+        
+        let merged = ClearModelConfig::load_files(&files, None).await.unwrap();
+        assert_eq!(merged.max_cache_age_days, 2);
+        assert_eq!((merged.compile_caches.enabled, merged.compile_caches.max_age_days), (false, 3));
+        assert!(merged.quarantine);
+        
+        let ci = ClearModelConfig::load_files(&files, Some("ci")).await.unwrap();
+        assert!(!ci.quarantine);
+        assert_eq!(ci.max_cache_age_days, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_cache_paths_are_expanded_at_load() {
+        let temp_dir = TempDir::new().unwrap();
+        for user in ["alice", "bob"] {
+            std::fs::create_dir_all(temp_dir.path().join(user).join("hf-cache")).unwrap();
+        }
+        let config_path = temp_dir.path().join("clearmodel.toml");
+        let pattern = temp_dir.path().join("*").join("hf-cache");
+        tokio::fs::write(&config_path, format!("cache_paths = [{:?}, \"~/.cache/torch\"]\n", pattern)).await.unwrap();
+        
+        let loaded = ClearModelConfig::load(Some(config_path.to_str().unwrap())).await.unwrap();
+        assert_eq!(loaded.cache_paths, vec![
+            temp_dir.path().join("alice/hf-cache"),
+            temp_dir.path().join("bob/hf-cache"),
+            home_dir().unwrap().join(".cache/torch"),
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_validate_file_locates_schema_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("clearmodel.toml");
+        tokio::fs::write(&config_path, "max_cache_age_days = 3\n\n[compile_caches]\nmax_age_days = \"ten\"\n\n[profiles.ci]\nmax_cache_age_dayz = 1\n").await.unwrap();
+        
+        let issues = ClearModelConfig::validate_file(&config_path).await.unwrap();
+        let located: Vec<(&str, Option<usize>)> = issues.iter().map(|issue| (issue.pointer.as_str(), issue.line)).collect();
+        assert_eq!(located, vec![("/compile_caches/max_age_days", Some(4)), ("/profiles/ci", Some(6))]);
+        assert!(issues[1].message.contains("max_cache_age_dayz"));
+        
+        tokio::fs::write(&config_path, "max_cache_age_days = 3\n[compile_caches]\nmax_age_days = 2\n").await.unwrap();
+        assert!(ClearModelConfig::validate_file(&config_path).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_pin_and_unpin_preserve_toml_comments() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("clearmodel.toml");
+        tokio::fs::write(&config_path, "# keep me\nmax_cache_age_days = 3\n").await.unwrap();
+        
+        assert!(ClearModelConfig::update_pinned_models(&config_path, "meta-llama/*", true).await.unwrap());
+        assert!(!ClearModelConfig::update_pinned_models(&config_path, "meta-llama/*", true).await.unwrap());
+        
+        let content = tokio::fs::read_to_string(&config_path).await.unwrap();
+        assert!(content.contains("# keep me"));
+        let loaded = ClearModelConfig::load(Some(config_path.to_str().unwrap())).await.unwrap();
+        assert_eq!(loaded.pinned_models, vec!["meta-llama/*".to_string()]);
+        
+        assert!(ClearModelConfig::update_pinned_models(&config_path, "meta-llama/*", false).await.unwrap());
+        let loaded = ClearModelConfig::load(Some(config_path.to_str().unwrap())).await.unwrap();
+        assert!(loaded.pinned_models.is_empty());
+    }
 } 
\ No newline at end of file
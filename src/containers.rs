@@ -0,0 +1,287 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use crate::categories::CategoryBreakdown;
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::resource_manager::CleanupResult;
+
+/// How long one request to the engine may take; removing a large image can be slow
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A Docker or Podman engine reachable through its API socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerEngine {
+    pub name: &'static str,
+    pub socket: PathBuf,
+}
+
+/// An image as listed by `GET /images/json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Image {
+    #[serde(rename = "Id")]
+    pub id: String,
+    
+    #[serde(rename = "RepoTags", default)]
+    pub repo_tags: Option<Vec<String>>,
+    
+    /// When the image was built, in seconds since the epoch
+    #[serde(rename = "Created")]
+    pub created: i64,
+    
+    /// Size including layers shared with other images
+    #[serde(rename = "Size")]
+    pub size: i64,
+}
+
+impl Image {
+    /// Untagged images left behind by rebuilds and pulls of newer tags
+    pub fn dangling(&self) -> bool {
+        self.tags().next().is_none()
+    }
+    
+    /// Tags other than the `<none>:<none>` placeholder
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.repo_tags.iter().flatten().map(String::as_str).filter(|tag| *tag != "<none>:<none>")
+    }
+    
+    /// Time since the image was built; the API reports no last use
+    pub fn age(&self, now: SystemTime) -> Duration {
+        let created = UNIX_EPOCH + Duration::from_secs(self.created.max(0) as u64);
+        now.duration_since(created).unwrap_or_default()
+    }
+    
+    fn label(&self) -> String {
+        self.tags().next().map(str::to_string).unwrap_or_else(|| short_id(&self.id).to_string())
+    }
+}
+
+/// Engines whose socket exists: `containers.socket` when set, otherwise `$DOCKER_HOST`
+/// (`unix://` only), `/var/run/docker.sock`, and the rootless and system Podman sockets
+pub fn engines(config: &ClearModelConfig) -> Vec<ContainerEngine> {
+    if let Some(socket) = &config.containers.socket {
+        let name = if socket.to_string_lossy().contains("podman") { "podman" } else { "docker" };
+        return vec![ContainerEngine { name, socket: socket.clone() }];
+    }
+    
+    let mut candidates: Vec<ContainerEngine> = Vec::new();
+    let docker_host = std::env::var("DOCKER_HOST").ok()
+        .and_then(|host| host.strip_prefix("unix://").map(PathBuf::from));
+    for socket in docker_host.into_iter().chain([PathBuf::from("/var/run/docker.sock")]) {
+        candidates.push(ContainerEngine { name: "docker", socket });
+    }
+    if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR").filter(|value| !value.is_empty()) {
+        candidates.push(ContainerEngine { name: "podman", socket: PathBuf::from(runtime).join("podman").join("podman.sock") });
+    }
+    candidates.push(ContainerEngine { name: "podman", socket: PathBuf::from("/run/podman/podman.sock") });
+    
+    let mut engines: Vec<ContainerEngine> = Vec::new();
+    for engine in candidates {
+        // Docker's socket is often a symlink to Podman's, which would list every image twice
+        let resolved = engine.socket.canonicalize().unwrap_or_else(|_| engine.socket.clone());
+        let seen = engines.iter().any(|known| known.socket.canonicalize().ok().as_ref() == Some(&resolved));
+        if engine.socket.exists() && !seen {
+            engines.push(engine);
+        }
+    }
+    engines
+}
+
+/// Matcher for `containers.patterns`, applied to repositories without their tag
+pub fn matcher(patterns: &[String]) -> std::result::Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern).case_insensitive(true).build().map_err(|e| e.to_string())?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Repository of a tag, without the tag and the default registry (`pytorch/pytorch`)
+pub fn repository(tag: &str) -> &str {
+    // A colon followed by a slash belongs to a registry port, not a tag
+    let repository = match tag.rsplit_once(':') {
+        Some((repository, version)) if !version.contains('/') => repository,
+        _ => tag,
+    };
+    repository.strip_prefix("docker.io/library/")
+        .or_else(|| repository.strip_prefix("docker.io/"))
+        .unwrap_or(repository)
+}
+
+/// Images to remove: dangling ones, and those matching a pattern that are older than the age limit
+pub fn prunable<'a>(images: &'a [Image], config: &ClearModelConfig, patterns: &GlobSet, now: SystemTime) -> Vec<&'a Image> {
+    let settings = &config.containers;
+    let max_age = config.older_than.unwrap_or(Duration::from_secs(settings.max_age_days as u64 * 24 * 3600));
+    images.iter()
+        .filter(|image| {
+            if image.dangling() {
+                return settings.prune_dangling;
+            }
+            // Every tag has to match, so an image also tagged for a project is kept
+            image.tags().all(|tag| patterns.is_match(repository(tag))) && image.age(now) > max_age
+        })
+        .collect()
+}
+
+/// List the engine's images and remove the prunable ones, or report them in a dry run
+///
+/// Images used by a container, or tagged more than once, are refused by the engine and
+/// reported as errors; nothing is forced.
+pub async fn prune(engine: &ContainerEngine, config: &ClearModelConfig, dry_run: bool) -> Result<CleanupResult> {
+    let started = Instant::now();
+    let patterns = matcher(&config.containers.patterns).map_err(|e| ClearModelError::configuration(
+        format!("Invalid containers pattern: {}", e)
+    ))?;
+    
+    let (status, body) = request(&engine.socket, "GET", "/images/json").await?;
+    if status != 200 {
+        return Err(ClearModelError::environment(
+            format!("{} refused to list images (HTTP {}): {}", engine.name, status, String::from_utf8_lossy(&body).trim())
+        ));
+    }
+    let images: Vec<Image> = serde_json::from_slice(&body).map_err(|e| ClearModelError::environment(
+        format!("Unexpected image list from {}: {}", engine.name, e)
+    ))?;
+    
+    let mut result = CleanupResult {
+        path: engine.socket.clone(),
+        files_removed: 0,
+        bytes_freed: 0,
+        errors: Vec::new(),
+        dirs_pruned: 0,
+        symlinks_pruned: 0,
+        categories: CategoryBreakdown::new(),
+        needs_privileges: Vec::new(),
+        failed: Vec::new(),
+        duration: Duration::ZERO,
+    };
+    for image in prunable(&images, config, &patterns, SystemTime::now()) {
+        let size = image.size.max(0) as u64;
+        if dry_run {
+            info!("Would remove {} image {} ({:.2} GB)", engine.name, image.label(), size as f64 / 1_073_741_824.0);
+        } else {
+            let (status, body) = request(&engine.socket, "DELETE", &format!("/images/{}", image.id)).await?;
+            if status != 200 {
+                let reason = String::from_utf8_lossy(&body).trim().to_string();
+                warn!("{} kept image {} (HTTP {}): {}", engine.name, image.label(), status, reason);
+                result.errors.push(format!("{}: {}", image.label(), reason));
+                continue;
+            }
+            info!("Removed {} image {} ({:.2} GB)", engine.name, image.label(), size as f64 / 1_073_741_824.0);
+        }
+        result.files_removed += 1;
+        result.bytes_freed += size;
+    }
+    
+    result.duration = started.elapsed();
+    Ok(result)
+}
+
+/// Send one request to the engine API and return the status and body
+#[cfg(unix)]
+async fn request(socket: &Path, method: &str, path: &str) -> Result<(u16, Vec<u8>)> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    let exchange = async {
+        let mut stream = tokio::net::UnixStream::connect(socket).await?;
+        // HTTP/1.0 makes the engine close the connection after the response, so the body
+        // ends at EOF rather than being chunked
+        let request = format!("{} {} HTTP/1.0\r\nHost: localhost\r\n\r\n", method, path);
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok::<_, std::io::Error>(response)
+    };
+    let response = tokio::time::timeout(REQUEST_TIMEOUT, exchange).await
+        .map_err(|_| ClearModelError::environment(format!("{} {} timed out on {:?}", method, path, socket)))?
+        .map_err(|e| ClearModelError::file_operation(format!("Failed to talk to the container engine: {}", e), Some(socket.to_path_buf())))?;
+    parse_response(&response).ok_or_else(|| ClearModelError::environment(
+        format!("Malformed response to {} {} from {:?}", method, path, socket)
+    ))
+}
+
+#[cfg(not(unix))]
+async fn request(socket: &Path, _method: &str, _path: &str) -> Result<(u16, Vec<u8>)> {
+    Err(ClearModelError::environment(format!("Container engine sockets are only supported on Unix: {:?}", socket)))
+}
+
+/// Status and body of an HTTP response
+fn parse_response(response: &[u8]) -> Option<(u16, Vec<u8>)> {
+    let split = response.windows(4).position(|window| window == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&response[..split]).ok()?;
+    let body = &response[split + 4..];
+    let status = head.split_whitespace().nth(1)?.parse().ok()?;
+    let chunked = head.lines().any(|line| {
+        let line = line.to_ascii_lowercase();
+        line.starts_with("transfer-encoding:") && line.contains("chunked")
+    });
+    if !chunked {
+        return Some((status, body.to_vec()));
+    }
+    
+    let mut decoded = Vec::new();
+    let mut rest = body;
+    loop {
+        let line_end = rest.windows(2).position(|window| window == b"\r\n")?;
+        let size = usize::from_str_radix(std::str::from_utf8(&rest[..line_end]).ok()?.split(';').next()?.trim(), 16).ok()?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            break;
+        }
+        decoded.extend_from_slice(rest.get(..size)?);
+        rest = rest.get(size + 2..)?;
+    }
+    debug!("Decoded a chunked response of {} bytes", decoded.len());
+    Some((status, decoded))
+}
+
+fn short_id(id: &str) -> &str {
+    let id = id.strip_prefix("sha256:").unwrap_or(id);
+    &id[..id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_only_old_ml_images_and_dangling_ones_are_pruned() {
+        let image = |tags: &[&str], days_old: u64| Image {
+            id: format!("sha256:{}", tags.join(",")),
+            repo_tags: Some(tags.iter().map(|tag| tag.to_string()).collect()),
+            created: (SystemTime::now() - Duration::from_secs(days_old * 86_400)).duration_since(UNIX_EPOCH).unwrap().as_secs() as i64,
+            size: 1,
+        };
+        let images = vec![
+            image(&["pytorch/pytorch:2.1.0-cuda12.1-cudnn8-runtime"], 90),
+            image(&["docker.io/nvidia/cuda:12.1.0-base-ubuntu22.04"], 60),
+            image(&["pytorch/pytorch:2.4.0-cuda12.4-cudnn9-runtime"], 2),
+            image(&["pytorch/pytorch:2.0.0", "myproject:latest"], 90),
+            image(&["postgres:16"], 400),
+            image(&["<none>:<none>"], 5),
+        ];
+        let config = ClearModelConfig::default();
+        let patterns = matcher(&config.containers.patterns).unwrap();
+        let pruned: Vec<String> = prunable(&images, &config, &patterns, SystemTime::now()).iter().map(|image| image.label()).collect();
+        assert_eq!(pruned, vec![
+            "pytorch/pytorch:2.1.0-cuda12.1-cudnn8-runtime".to_string(),
+            "docker.io/nvidia/cuda:12.1.0-base-ubuntu22.04".to_string(),
+            short_id(&images[5].id).to_string(),
+        ]);
+        
+        assert_eq!(repository("localhost:5000/huggingface/tgi:2.0"), "localhost:5000/huggingface/tgi");
+        assert_eq!(repository("docker.io/library/python:3.11"), "python");
+    }
+    
+    #[test]
+    fn test_chunked_responses_are_decoded() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n[{}]\r\n0\r\n\r\n";
+        assert_eq!(parse_response(response), Some((200, b"[{}]".to_vec())));
+        let response = b"HTTP/1.0 409 Conflict\r\nContent-Type: application/json\r\n\r\n{\"message\":\"in use\"}";
+        assert_eq!(parse_response(response).map(|(status, _)| status), Some(409));
+    }
+}
\ No newline at end of file
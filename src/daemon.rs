@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysinfo::Disks;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Free space on the filesystem holding one or more cache roots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskSpace {
+    pub mount_point: PathBuf,
+    pub available_bytes: u64,
+}
+
+/// What the daemon reports over its control socket
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub started_at: u64,
+    pub dry_run: bool,
+    
+    /// Free space below which a cleanup is triggered
+    pub threshold_bytes: u64,
+    
+    /// Free space at the last check
+    pub disks: Vec<DiskSpace>,
+    
+    pub under_pressure: bool,
+    
+    /// A cleanup is running right now
+    pub cleaning: bool,
+    
+    pub last_check: Option<u64>,
+    pub last_cleanup: Option<u64>,
+    pub cleanups: u64,
+}
+
+impl DaemonStatus {
+    pub fn new(threshold_bytes: u64, dry_run: bool) -> Self {
+        Self {
+            pid: std::process::id(),
+            started_at: unix_now(),
+            dry_run,
+            threshold_bytes,
+            ..Self::default()
+        }
+    }
+    
+    pub fn record_check(&mut self, disks: Vec<DiskSpace>, under_pressure: bool) {
+        self.disks = disks;
+        self.under_pressure = under_pressure;
+        self.last_check = Some(unix_now());
+    }
+    
+    pub fn record_cleanup(&mut self, running: bool) {
+        self.cleaning = running;
+        if !running {
+            self.cleanups += 1;
+            self.last_cleanup = Some(unix_now());
+        }
+    }
+}
+
+/// Status shared between the daemon loop and the control socket
+pub type SharedStatus = Arc<Mutex<DaemonStatus>>;
+
+/// Command sent to a running daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ControlRequest {
+    Status,
+    TriggerNow,
+}
+
+/// Reply from a running daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlResponse {
+    Status(DaemonStatus),
+    
+    /// Whether a cleanup was queued (false if one was already pending)
+    Triggered { queued: bool },
+    
+    Error { message: String },
+}
+
+/// Watches free space on the filesystems that hold the cache roots
+pub struct DiskMonitor {
+    roots: Vec<PathBuf>,
+    threshold_bytes: u64,
+    disks: Disks,
+}
+
+impl DiskMonitor {
+    pub fn new(roots: Vec<PathBuf>, min_free_space_gb: u64) -> Self {
+        Self {
+            roots,
+            threshold_bytes: min_free_space_gb.saturating_mul(1_073_741_824),
+            disks: Disks::new_with_refreshed_list(),
+        }
+    }
+    
+    pub fn threshold_bytes(&self) -> u64 {
+        self.threshold_bytes
+    }
+    
+    /// Refresh and return free space for every filesystem holding a cache root
+    pub fn check(&mut self) -> Vec<DiskSpace> {
+        // Re-list rather than refresh so mounts that come and go are picked up
+        self.disks.refresh_list();
+        let mounts: Vec<DiskSpace> = self.disks.list().iter()
+            .map(|disk| DiskSpace {
+                mount_point: disk.mount_point().to_path_buf(),
+                available_bytes: disk.available_space(),
+            })
+            .collect();
+        
+        let mut spaces: Vec<DiskSpace> = Vec::new();
+        for root in &self.roots {
+            match disk_for(root, &mounts) {
+                Some(disk) if !spaces.contains(disk) => spaces.push(disk.clone()),
+                Some(_) => {}
+                None => debug!("No mounted filesystem found for {:?}", root),
+            }
+        }
+        spaces
+    }
+    
+    /// Whether any of the filesystems is below the free space threshold
+    pub fn under_pressure(&self, spaces: &[DiskSpace]) -> bool {
+        spaces.iter().any(|space| space.available_bytes < self.threshold_bytes)
+    }
+}
+
+/// Filesystem a path lives on: the mount point that is its longest prefix
+fn disk_for<'a>(path: &Path, mounts: &'a [DiskSpace]) -> Option<&'a DiskSpace> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    mounts.iter()
+        .filter(|disk| path.starts_with(&disk.mount_point))
+        .max_by_key(|disk| disk.mount_point.components().count())
+}
+
+/// Default control socket location (`$XDG_RUNTIME_DIR/clearmodel/daemon.sock`, else the state directory)
+pub fn default_socket_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|value| !value.is_empty())
+        .map(|runtime| PathBuf::from(runtime).join("clearmodel"))
+        .map(|dir| dir.join(state::DAEMON_SOCKET))
+        .or_else(|| state::path(state::DAEMON_SOCKET))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Unix socket accepting one JSON request per line
+#[cfg(unix)]
+pub struct ControlServer {
+    path: PathBuf,
+    listener: tokio::net::UnixListener,
+}
+
+#[cfg(unix)]
+impl ControlServer {
+    /// Bind the socket, replacing a stale one but refusing to displace a live daemon
+    pub async fn bind(path: &Path) -> Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+        
+        if path.exists() {
+            if tokio::net::UnixStream::connect(path).await.is_ok() {
+                return Err(ClearModelError::environment(format!(
+                    "Another daemon is already listening on {:?}",
+                    path
+                )));
+            }
+            std::fs::remove_file(path)?;
+        }
+        
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create socket directory: {}", e),
+                    Some(parent.to_path_buf())
+                ))?;
+        }
+        
+        let listener = tokio::net::UnixListener::bind(path)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to bind control socket: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        
+        // Anyone who can reach the socket can trigger deletions
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+    
+    /// Answer requests until the daemon exits
+    pub async fn serve(self, status: SharedStatus, trigger: mpsc::Sender<()>) {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Control socket accept failed: {}", e);
+                    continue;
+                }
+            };
+            
+            let status = status.clone();
+            let trigger = trigger.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, status, trigger).await {
+                    debug!("Control connection failed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+async fn handle_connection(
+    stream: tokio::net::UnixStream,
+    status: SharedStatus,
+    trigger: mpsc::Sender<()>,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    
+    let (reader, mut writer) = stream.into_split();
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    
+    let response = match serde_json::from_str::<ControlRequest>(line.trim()) {
+        Ok(ControlRequest::Status) => {
+            let status = status.lock()
+                .map(|status| status.clone())
+                .unwrap_or_default();
+            ControlResponse::Status(status)
+        }
+        Ok(ControlRequest::TriggerNow) => ControlResponse::Triggered {
+            queued: trigger.try_send(()).is_ok(),
+        },
+        Err(e) => ControlResponse::Error {
+            message: format!("Invalid request: {}", e),
+        },
+    };
+    
+    let mut reply = serde_json::to_vec(&response)?;
+    reply.push(b'\n');
+    writer.write_all(&reply).await?;
+    Ok(())
+}
+
+/// Send a request to a running daemon
+#[cfg(unix)]
+pub async fn send_request(path: &Path, request: ControlRequest) -> Result<ControlResponse> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    
+    let stream = tokio::net::UnixStream::connect(path).await
+        .map_err(|e| ClearModelError::environment(format!(
+            "Cannot reach the daemon at {:?} (is `clearmodel daemon` running?): {}",
+            path, e
+        )))?;
+    
+    let (reader, mut writer) = stream.into_split();
+    let mut request = serde_json::to_vec(&request)?;
+    request.push(b'\n');
+    writer.write_all(&request).await?;
+    
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(not(unix))]
+pub async fn send_request(_path: &Path, _request: ControlRequest) -> Result<ControlResponse> {
+    Err(ClearModelError::environment(
+        "The daemon control socket is only available on Unix".to_string()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_disk_for_picks_longest_mount_point() {
+        let mounts = vec![
+            DiskSpace { mount_point: PathBuf::from("/"), available_bytes: 1 },
+            DiskSpace { mount_point: PathBuf::from("/data"), available_bytes: 2 },
+        ];
+        
+        assert_eq!(disk_for(Path::new("/data/hf-cache"), &mounts).unwrap().available_bytes, 2);
+        assert_eq!(disk_for(Path::new("/database"), &mounts).unwrap().available_bytes, 1);
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_control_socket_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let socket = temp_dir.path().join("daemon.sock");
+        
+        let status = SharedStatus::default();
+        status.lock().unwrap().cleanups = 3;
+        let (trigger, mut triggered) = mpsc::channel(1);
+        
+        let server = ControlServer::bind(&socket).await.unwrap();
+        assert!(ControlServer::bind(&socket).await.is_err());
+        tokio::spawn(server.serve(status, trigger));
+        
+        match send_request(&socket, ControlRequest::Status).await.unwrap() {
+            ControlResponse::Status(status) => assert_eq!(status.cleanups, 3),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        
+        let queued = |response| matches!(response, ControlResponse::Triggered { queued: true });
+        assert!(queued(send_request(&socket, ControlRequest::TriggerNow).await.unwrap()));
+        assert!(!queued(send_request(&socket, ControlRequest::TriggerNow).await.unwrap()));
+        assert!(triggered.recv().await.is_some());
+    }
+}
\ No newline at end of file
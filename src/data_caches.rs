@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+use crate::config::{ClearModelConfig, DataCacheEntry};
+use crate::policy::CleanupPolicy;
+
+/// A library that downloads models and corpora to a data directory of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataCacheKind {
+    Whisper,
+    Spacy,
+    Nltk,
+    Gensim,
+}
+
+/// One download, removed as a whole: a Whisper checkpoint, a spaCy model package with its
+/// `dist-info`, an NLTK resource with its zip, or a gensim-data model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataResource {
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DataCacheKind {
+    pub const ALL: [DataCacheKind; 4] = [DataCacheKind::Whisper, DataCacheKind::Spacy, DataCacheKind::Nltk, DataCacheKind::Gensim];
+    
+    /// Target name
+    pub fn name(self) -> &'static str {
+        match self {
+            DataCacheKind::Whisper => "whisper",
+            DataCacheKind::Spacy => "spacy",
+            DataCacheKind::Nltk => "nltk",
+            DataCacheKind::Gensim => "gensim",
+        }
+    }
+    
+    /// The library's settings in `data_caches`
+    pub fn settings(self, config: &ClearModelConfig) -> &DataCacheEntry {
+        match self {
+            DataCacheKind::Whisper => &config.data_caches.whisper,
+            DataCacheKind::Spacy => &config.data_caches.spacy,
+            DataCacheKind::Nltk => &config.data_caches.nltk,
+            DataCacheKind::Gensim => &config.data_caches.gensim,
+        }
+    }
+    
+    /// Directories the library downloads to
+    ///
+    /// - Whisper: `$XDG_CACHE_HOME/whisper`, else `~/.cache/whisper`
+    /// - spaCy: the site-packages of the active virtualenv or conda env and the user site,
+    ///   where `spacy download` installs model packages
+    /// - NLTK: every directory in `$NLTK_DATA`, else `~/nltk_data`
+    /// - gensim: `$GENSIM_DATA_DIR`, else `~/gensim-data`
+    pub fn locations(self) -> Vec<PathBuf> {
+        let home = home::home_dir();
+        match self {
+            DataCacheKind::Whisper => env_dir("XDG_CACHE_HOME")
+                .or_else(|| home.map(|home| home.join(".cache")))
+                .map(|cache| cache.join("whisper"))
+                .into_iter()
+                .collect(),
+            DataCacheKind::Spacy => site_packages(),
+            DataCacheKind::Nltk => match std::env::var_os("NLTK_DATA").filter(|value| !value.is_empty()) {
+                Some(dirs) => std::env::split_paths(&dirs).collect(),
+                None if cfg!(windows) => env_dir("APPDATA").map(|data| data.join("nltk_data")).into_iter().collect(),
+                None => home.map(|home| home.join("nltk_data")).into_iter().collect(),
+            },
+            DataCacheKind::Gensim => env_dir("GENSIM_DATA_DIR")
+                .or_else(|| home.map(|home| home.join("gensim-data")))
+                .into_iter()
+                .collect(),
+        }
+    }
+    
+    /// The downloads under one of the library's directories
+    pub fn resources(self, root: &Path) -> Vec<DataResource> {
+        let mut resources: Vec<DataResource> = Vec::new();
+        let mut add = |name: String, path: PathBuf| match resources.iter_mut().find(|resource| resource.name == name) {
+            Some(resource) => resource.paths.push(path),
+            None => resources.push(DataResource { name, paths: vec![path] }),
+        };
+        
+        match self {
+            DataCacheKind::Whisper => {
+                for path in entries(root).into_iter().filter(|path| path.is_file()) {
+                    if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                        add(stem.to_string(), path.clone());
+                    }
+                }
+            }
+            DataCacheKind::Spacy => {
+                let entries = entries(root);
+                for package in entries.iter().filter(|path| is_spacy_package(path)) {
+                    let Some(name) = package.file_name().and_then(|name| name.to_str()) else {
+                        continue;
+                    };
+                    add(name.to_string(), package.clone());
+                    let dist_info = entries.iter().filter(|path| {
+                        path.file_name()
+                            .and_then(|file_name| file_name.to_str())
+                            .and_then(|file_name| file_name.strip_suffix(".dist-info"))
+                            .and_then(|file_name| file_name.rsplit_once('-'))
+                            .is_some_and(|(project, _)| project == name)
+                    });
+                    for path in dist_info {
+                        add(name.to_string(), path.clone());
+                    }
+                }
+            }
+            // `<category>/<resource>`, where a resource is extracted next to its zip
+            DataCacheKind::Nltk => {
+                for category in entries(root).into_iter().filter(|path| path.is_dir()) {
+                    let Some(category_name) = category.file_name().and_then(|name| name.to_str()).map(str::to_string) else {
+                        continue;
+                    };
+                    for path in entries(&category) {
+                        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                            continue;
+                        };
+                        let resource = file_name.strip_suffix(".zip").unwrap_or(file_name);
+                        add(format!("{}/{}", category_name, resource), path.clone());
+                    }
+                }
+            }
+            // `information.json` at the top is gensim's catalogue, not a model
+            DataCacheKind::Gensim => {
+                for path in entries(root).into_iter().filter(|path| path.is_dir()) {
+                    if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                        add(name.to_string(), path.clone());
+                    }
+                }
+            }
+        }
+        resources
+    }
+}
+
+impl DataResource {
+    /// Time since any file of the resource was last used
+    pub fn idle(&self, policy: &CleanupPolicy) -> Option<Duration> {
+        let now = SystemTime::now();
+        self.paths.iter()
+            .flat_map(|path| WalkDir::new(path).into_iter().flatten())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| policy.age_of(entry.path(), &entry.metadata().ok()?, now))
+            .min()
+    }
+}
+
+/// Paths of every resource of `kind` unused for longer than the policy allows, pinned ones aside
+pub fn expired(kind: DataCacheKind, roots: &[PathBuf], policy: &CleanupPolicy) -> Vec<PathBuf> {
+    roots.iter()
+        .flat_map(|root| kind.resources(root))
+        .filter(|resource| !policy.pinned.matches(&resource.name))
+        .filter(|resource| resource.idle(policy).is_some_and(|idle| idle > policy.max_age))
+        .flat_map(|resource| resource.paths)
+        .collect()
+}
+
+/// Policy that removes every file of the resources picked by `expired`; the decision was made
+/// for each resource as a whole, so no per-file rule may keep part of one
+pub fn eviction(policy: &CleanupPolicy) -> CleanupPolicy {
+    CleanupPolicy {
+        max_age: Duration::ZERO,
+        extension_max_age: Default::default(),
+        min_size: 0,
+        newer_than: None,
+        ..policy.clone()
+    }
+}
+
+/// Remove the directories of removed resources once nothing is left in them
+///
+/// An empty `corpora/wordnet` or package directory would still look installed to the library.
+pub fn remove_emptied(paths: &[PathBuf]) {
+    for path in paths.iter().filter(|path| path.is_dir()) {
+        for entry in WalkDir::new(path).contents_first(true).into_iter().flatten() {
+            if entry.file_type().is_dir() {
+                // remove_dir refuses non-empty directories, so nothing else can be lost here
+                let _ = std::fs::remove_dir(entry.path());
+            }
+        }
+    }
+}
+
+/// A spaCy model package: `meta.json` naming the spaCy version it was trained for
+fn is_spacy_package(path: &Path) -> bool {
+    std::fs::read_to_string(path.join("meta.json")).is_ok_and(|meta| meta.contains("\"spacy_version\""))
+}
+
+/// site-packages directories that `spacy download` may have installed to
+fn site_packages() -> Vec<PathBuf> {
+    let home = home::home_dir();
+    let mut prefixes: Vec<PathBuf> = ["VIRTUAL_ENV", "CONDA_PREFIX"].into_iter().filter_map(env_dir).collect();
+    if cfg!(windows) {
+        prefixes.extend(env_dir("APPDATA").map(|data| data.join("Python")));
+    } else if cfg!(target_os = "macos") {
+        prefixes.extend(home.map(|home| home.join("Library").join("Python")));
+    } else {
+        prefixes.extend(home.map(|home| home.join(".local")));
+    }
+    
+    let mut dirs = Vec::new();
+    for prefix in prefixes {
+        // <prefix>/lib/python3.X/site-packages, <prefix>/Lib/site-packages, or the per-version
+        // user sites (~/Library/Python/3.X/lib/python/site-packages, %APPDATA%\Python\Python3X\site-packages)
+        dirs.push(prefix.join("Lib").join("site-packages"));
+        for version in entries(&prefix.join("lib")).into_iter().chain(entries(&prefix)) {
+            dirs.push(version.join("site-packages"));
+            dirs.push(version.join("lib").join("python").join("site-packages"));
+        }
+    }
+    dirs.retain(|dir| dir.is_dir());
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    paths
+}
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_resources_group_their_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let nltk = temp_dir.path().join("nltk_data");
+        fs::create_dir_all(nltk.join("corpora/wordnet")).unwrap();
+        fs::write(nltk.join("corpora/wordnet/lexnames"), b"noun.tops").unwrap();
+        fs::write(nltk.join("corpora/wordnet.zip"), b"zip").unwrap();
+        fs::create_dir_all(nltk.join("tokenizers")).unwrap();
+        fs::write(nltk.join("tokenizers/punkt.zip"), b"zip").unwrap();
+        let resources = DataCacheKind::Nltk.resources(&nltk);
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[0].name, "corpora/wordnet");
+        assert_eq!(resources[0].paths.len(), 2);
+        
+        let site = temp_dir.path().join("site-packages");
+        fs::create_dir_all(site.join("en_core_web_sm")).unwrap();
+        fs::write(site.join("en_core_web_sm/meta.json"), br#"{"lang": "en", "spacy_version": ">=3.7.2"}"#).unwrap();
+        fs::create_dir_all(site.join("en_core_web_sm-3.7.1.dist-info")).unwrap();
+        fs::create_dir_all(site.join("requests")).unwrap();
+        fs::create_dir_all(site.join("requests-2.31.0.dist-info")).unwrap();
+        let packages = DataCacheKind::Spacy.resources(&site);
+        assert_eq!(packages, vec![DataResource {
+            name: "en_core_web_sm".to_string(),
+            paths: vec![site.join("en_core_web_sm"), site.join("en_core_web_sm-3.7.1.dist-info")],
+        }]);
+        
+        // Nothing was used for a day, so everything is expired under a zero age limit
+        let policy = CleanupPolicy { max_age: Duration::ZERO, ..CleanupPolicy::from_config(&ClearModelConfig::default()) };
+        let fresh = CleanupPolicy::from_config(&ClearModelConfig::default());
+        assert_eq!(expired(DataCacheKind::Nltk, std::slice::from_ref(&nltk), &fresh), Vec::<PathBuf>::new());
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(expired(DataCacheKind::Nltk, std::slice::from_ref(&nltk), &policy).len(), 3);
+    }
+}
\ No newline at end of file
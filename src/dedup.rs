@@ -0,0 +1,262 @@
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+use crate::errors::{ClearModelError, Result};
+use crate::journal;
+
+/// Identical files found under the cache roots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// BLAKE3 hash of the contents
+    pub hash: String,
+    pub size: u64,
+    
+    /// Sorted; the first path is the copy that is kept when consolidating
+    pub paths: Vec<PathBuf>,
+}
+
+/// How duplicates are replaced when consolidating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkMode {
+    /// Hard link to the kept copy (same filesystem only; the files then share one inode)
+    Hardlink,
+    
+    /// Copy-on-write clone of the kept copy (btrfs, XFS, APFS)
+    Reflink,
+}
+
+impl FromStr for LinkMode {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "hardlink" => Ok(LinkMode::Hardlink),
+            "reflink" => Ok(LinkMode::Reflink),
+            other => Err(format!("unknown link mode {:?} (expected hardlink or reflink)", other)),
+        }
+    }
+}
+
+impl fmt::Display for LinkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LinkMode::Hardlink => "hardlink",
+            LinkMode::Reflink => "reflink",
+        })
+    }
+}
+
+impl DuplicateGroup {
+    /// Bytes freed by keeping a single copy
+    pub fn reclaimable(&self) -> u64 {
+        self.size * (self.paths.len() as u64).saturating_sub(1)
+    }
+    
+    /// Replace every copy but the first with a link to it; returns the bytes reclaimed
+    ///
+    /// Nothing is deleted: each duplicate is swapped for the link with an atomic rename.
+    pub fn consolidate(&self, mode: LinkMode, dry_run: bool) -> Result<u64> {
+        let Some((keep, duplicates)) = self.paths.split_first() else {
+            return Ok(0);
+        };
+        
+        let mut reclaimed = 0;
+        for duplicate in duplicates {
+            if dry_run {
+                info!("Would {} {:?} to {:?}", mode, duplicate, keep);
+            } else {
+                self.link_over(keep, duplicate, mode)?;
+                debug!("Replaced {:?} with a {} to {:?}", duplicate, mode, keep);
+            }
+            reclaimed += self.size;
+        }
+        Ok(reclaimed)
+    }
+    
+    fn link_over(&self, keep: &Path, duplicate: &Path, mode: LinkMode) -> Result<()> {
+        // Cache files are written once, but refuse if either side changed since hashing
+        for path in [keep, duplicate] {
+            let size = std::fs::metadata(path)?.len();
+            if size != self.size {
+                return Err(ClearModelError::file_operation(
+                    "File changed since it was hashed".to_string(),
+                    Some(path.to_path_buf())
+                ));
+            }
+        }
+        
+        let file_name = duplicate.file_name().unwrap_or_default().to_string_lossy();
+        let temp = duplicate.with_file_name(format!(".{}.clearmodel-dedup", file_name));
+        let linked = match mode {
+            LinkMode::Hardlink => std::fs::hard_link(keep, &temp),
+            LinkMode::Reflink => reflink(keep, &temp),
+        };
+        linked.map_err(|e| ClearModelError::file_operation(
+            format!("Failed to {} to {:?}: {}", mode, keep, e),
+            Some(duplicate.to_path_buf())
+        ))?;
+        
+        std::fs::rename(&temp, duplicate).map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            ClearModelError::file_operation(
+                format!("Failed to replace duplicate: {}", e),
+                Some(duplicate.to_path_buf())
+            )
+        })
+    }
+}
+
+/// Find files of at least `min_size` bytes with identical contents under `roots`
+///
+/// Only files sharing a size are hashed. Symlinks are not followed, and hard links
+/// to the same inode count once, so already consolidated files are not reported.
+pub fn find_duplicates(roots: &[PathBuf], min_size: u64) -> Vec<DuplicateGroup> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut seen = HashSet::new();
+    
+    for root in roots {
+        for entry in WalkDir::new(root).into_iter().flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() < min_size || !seen.insert(file_key(entry.path(), &metadata)) {
+                continue;
+            }
+            by_size.entry(metadata.len()).or_default().push(entry.into_path());
+        }
+    }
+    
+    let candidates: Vec<(u64, PathBuf)> = by_size.into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .flat_map(|(size, paths)| paths.into_iter().map(move |path| (size, path)))
+        .collect();
+    info!("Hashing {} files that share a size with another", candidates.len());
+    
+    let hashed: Vec<(u64, String, PathBuf)> = candidates.into_par_iter()
+        .filter_map(|(size, path)| match journal::hash_file(&path) {
+            Ok(hash) => Some((size, hash, path)),
+            Err(e) => {
+                debug!("Skipping unreadable file {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    
+    let mut by_hash: HashMap<(u64, String), Vec<PathBuf>> = HashMap::new();
+    for (size, hash, path) in hashed {
+        by_hash.entry((size, hash)).or_default().push(path);
+    }
+    
+    let mut groups: Vec<DuplicateGroup> = by_hash.into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|((size, hash), mut paths)| {
+            paths.sort();
+            DuplicateGroup { hash, size, paths }
+        })
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.reclaimable()));
+    groups
+}
+
+/// Identity of a file's contents on disk: its inode where there are inodes
+#[cfg(unix)]
+type FileKey = (u64, u64);
+
+#[cfg(not(unix))]
+type FileKey = PathBuf;
+
+#[cfg(unix)]
+fn file_key(_path: &Path, metadata: &std::fs::Metadata) -> FileKey {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_key(path: &Path, _metadata: &std::fs::Metadata) -> FileKey {
+    path.to_path_buf()
+}
+
+/// Clone `source` to the new file `destination` sharing its extents
+#[cfg(target_os = "linux")]
+fn reflink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    
+    let source = std::fs::File::open(source)?;
+    let target = std::fs::OpenOptions::new().write(true).create_new(true).open(destination)?;
+    // SAFETY: both descriptors are open for the duration of the call
+    if unsafe { libc::ioctl(target.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) } != 0 {
+        let error = std::io::Error::last_os_error();
+        drop(target);
+        let _ = std::fs::remove_file(destination);
+        return Err(error);
+    }
+    target.set_permissions(source.metadata()?.permissions())
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    
+    let source = CString::new(source.as_os_str().as_bytes())?;
+    let destination = CString::new(destination.as_os_str().as_bytes())?;
+    // SAFETY: both are valid NUL-terminated paths
+    if unsafe { libc::clonefile(source.as_ptr(), destination.as_ptr(), 0) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_source: &Path, _destination: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reflinks are only supported on Linux and macOS"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_duplicates_are_found_and_hardlinked() {
+        use std::os::unix::fs::MetadataExt;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let (hf, torch) = (temp_dir.path().join("hf"), temp_dir.path().join("torch"));
+        fs::create_dir_all(&hf).unwrap();
+        fs::create_dir_all(&torch).unwrap();
+        fs::write(hf.join("blob"), b"same weights").unwrap();
+        fs::write(torch.join("model.pt"), b"same weights").unwrap();
+        fs::write(torch.join("other.pt"), b"diff weights").unwrap();
+        fs::write(torch.join("small.pt"), b"tiny").unwrap();
+        
+        let roots = vec![hf.clone(), torch.clone()];
+        let groups = find_duplicates(&roots, 8);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].paths, vec![hf.join("blob"), torch.join("model.pt")]);
+        assert_eq!(groups[0].reclaimable(), 12);
+        
+        assert_eq!(groups[0].consolidate(LinkMode::Hardlink, true).unwrap(), 12);
+        assert_eq!(fs::metadata(torch.join("model.pt")).unwrap().nlink(), 1);
+        
+        assert_eq!(groups[0].consolidate(LinkMode::Hardlink, false).unwrap(), 12);
+        assert_eq!(fs::metadata(torch.join("model.pt")).unwrap().ino(), fs::metadata(hf.join("blob")).unwrap().ino());
+        assert_eq!(fs::read(torch.join("model.pt")).unwrap(), b"same weights");
+        
+        // Consolidated files are one inode now, so they are not reported again
+        assert!(find_duplicates(&roots, 8).is_empty());
+    }
+}
\ No newline at end of file
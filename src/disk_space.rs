@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+use tracing::debug;
+
+use crate::resource_manager::CleanupResult;
+
+/// Size and free space of one filesystem at one point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilesystemSpace {
+    pub mount_point: PathBuf,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+/// Free space on one filesystem before and after a cleanup
+///
+/// `bytes_removed` is the size of the files removed from it, which is more than the
+/// space actually freed when blobs are hardlinked or reflinked elsewhere, files were
+/// quarantined on the same filesystem or are still held open, or snapshots keep them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpaceChange {
+    pub mount_point: PathBuf,
+    pub total_bytes: u64,
+    pub available_before: u64,
+    pub available_after: u64,
+    pub bytes_removed: u64,
+}
+
+impl SpaceChange {
+    /// Space that became available, negative when something else wrote more meanwhile
+    pub fn freed(&self) -> i64 {
+        self.available_after as i64 - self.available_before as i64
+    }
+}
+
+/// Free space of every filesystem holding one of `paths`, each listed once
+pub fn measure(paths: &[PathBuf]) -> Vec<FilesystemSpace> {
+    let disks = Disks::new_with_refreshed_list();
+    let mounts: Vec<FilesystemSpace> = disks.list().iter()
+        .map(|disk| FilesystemSpace {
+            mount_point: disk.mount_point().to_path_buf(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+        })
+        .collect();
+    
+    let mut spaces: Vec<FilesystemSpace> = Vec::new();
+    for path in paths {
+        match filesystem_of(path, &mounts) {
+            Some(space) if !spaces.iter().any(|known| known.mount_point == space.mount_point) => spaces.push(space.clone()),
+            Some(_) => {}
+            None => debug!("No mounted filesystem found for {:?}", path),
+        }
+    }
+    spaces
+}
+
+/// Filesystem a path lives on: the mount point that is its longest prefix
+pub fn filesystem_of<'a>(path: &Path, spaces: &'a [FilesystemSpace]) -> Option<&'a FilesystemSpace> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    spaces.iter()
+        .filter(|space| path.starts_with(&space.mount_point))
+        .max_by_key(|space| space.mount_point.components().count())
+}
+
+/// Pair the free space measured `before` and `after` a cleanup, and attribute the bytes
+/// each result removed to the filesystem of its directory
+pub fn compare(before: &[FilesystemSpace], after: &[FilesystemSpace], results: &[CleanupResult]) -> Vec<SpaceChange> {
+    let mut changes: Vec<SpaceChange> = before.iter()
+        .filter_map(|space| {
+            let later = after.iter().find(|later| later.mount_point == space.mount_point)?;
+            Some(SpaceChange {
+                mount_point: space.mount_point.clone(),
+                total_bytes: space.total_bytes,
+                available_before: space.available_bytes,
+                available_after: later.available_bytes,
+                bytes_removed: 0,
+            })
+        })
+        .collect();
+    
+    for result in results {
+        if let Some(space) = filesystem_of(&result.path, before) {
+            if let Some(change) = changes.iter_mut().find(|change| change.mount_point == space.mount_point) {
+                change.bytes_removed += result.bytes_freed;
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn space(mount_point: &str, available_bytes: u64) -> FilesystemSpace {
+        FilesystemSpace { mount_point: PathBuf::from(mount_point), total_bytes: 1000, available_bytes }
+    }
+    
+    #[test]
+    fn test_removed_bytes_count_towards_the_longest_mount_point() {
+        let before = [space("/", 100), space("/data", 200)];
+        let after = [space("/", 150), space("/data", 210)];
+        let result = |path: &str, bytes_freed| CleanupResult {
+            path: PathBuf::from(path),
+            files_removed: 1,
+            bytes_freed,
+            errors: Vec::new(),
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: Default::default(),
+            needs_privileges: Vec::new(),
+            failed: Vec::new(),
+            duration: std::time::Duration::ZERO,
+        };
+        let results = [result("/home/me/.cache/pip", 50), result("/data/hf/hub", 90), result("/data/torch", 10)];
+        
+        let changes = compare(&before, &after, &results);
+        
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[0].bytes_removed, changes[0].freed()), (50, 50));
+        // Hardlinked blobs: 100 bytes of files removed, 10 bytes freed
+        assert_eq!(changes[1].mount_point, PathBuf::from("/data"));
+        assert_eq!((changes[1].bytes_removed, changes[1].freed()), (100, 10));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::ClearModelConfig;
+
+/// Architecture reported for engine files whose name does not say which GPU they were built for
+pub const UNKNOWN_ARCH: &str = "unknown";
+
+/// Bytes of built engines and timing caches for one GPU architecture
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchUsage {
+    /// Compute capability, e.g. `sm_86`
+    pub arch: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// An engine cache with its usage per GPU architecture, largest first
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineCache {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub archs: Vec<ArchUsage>,
+}
+
+/// ONNX Runtime and TensorRT engine caches: (target name, directories)
+///
+/// - onnxruntime: the TensorRT execution provider's engine and timing cache
+///   (`$ORT_TENSORRT_ENGINE_CACHE_PATH`, `$ORT_TENSORRT_CACHE_PATH`), and `~/.cache/onnxruntime`
+///   where optimized models are usually written
+/// - tensorrt: `~/.cache/tensorrt` and Torch-TensorRT's `torch_tensorrt_engine_cache` in the
+///   temporary directory
+pub fn locations() -> Vec<(&'static str, Vec<PathBuf>)> {
+    let env_dir = |var: &str| std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let cache = env_dir("XDG_CACHE_HOME").or_else(|| home::home_dir().map(|home| home.join(".cache")));
+    
+    let mut onnxruntime: Vec<PathBuf> = ["ORT_TENSORRT_ENGINE_CACHE_PATH", "ORT_TENSORRT_CACHE_PATH"]
+        .into_iter()
+        .filter_map(env_dir)
+        .collect();
+    onnxruntime.extend(cache.as_ref().map(|cache| cache.join("onnxruntime")));
+    onnxruntime.dedup();
+    
+    let mut tensorrt: Vec<PathBuf> = cache.map(|cache| cache.join("tensorrt")).into_iter().collect();
+    tensorrt.push(std::env::temp_dir().join("torch_tensorrt_engine_cache"));
+    vec![("onnxruntime", onnxruntime), ("tensorrt", tensorrt)]
+}
+
+/// Existing engine caches, with their usage per GPU architecture
+pub fn caches(config: &ClearModelConfig) -> Vec<EngineCache> {
+    if !config.engine_caches.enabled {
+        return Vec::new();
+    }
+    locations()
+        .into_iter()
+        .flat_map(|(name, paths)| paths.into_iter().map(move |path| (name, path)))
+        .filter(|(_, path)| path.is_dir())
+        .map(|(name, path)| EngineCache { name, archs: by_arch(&path), path })
+        .collect()
+}
+
+/// GPU architecture an engine or timing cache file was built for, from its name
+///
+/// The TensorRT execution provider ends engine and timing cache names with the compute
+/// capability (`..._sm86.engine`, `TensorrtExecutionProvider_cache_sm80.timing`); other
+/// tools write `sm_89` or `sm89` somewhere in the name.
+pub fn gpu_arch(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.split(['_', '-', '.']).peekable();
+    while let Some(part) = parts.next() {
+        let digits = match part.strip_prefix("sm") {
+            Some("") => parts.peek().copied().filter(|next| is_capability(next)),
+            Some(digits) if is_capability(digits) => Some(digits),
+            _ => None,
+        };
+        if let Some(digits) = digits {
+            return Some(format!("sm_{}", digits));
+        }
+    }
+    None
+}
+
+/// Bytes under an engine cache per GPU architecture, largest first
+pub fn by_arch(root: &Path) -> Vec<ArchUsage> {
+    let mut archs: BTreeMap<String, ArchUsage> = BTreeMap::new();
+    for entry in WalkDir::new(root).into_iter().flatten().filter(|entry| entry.file_type().is_file()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let arch = gpu_arch(entry.path()).unwrap_or_else(|| UNKNOWN_ARCH.to_string());
+        let usage = archs.entry(arch.clone()).or_insert(ArchUsage { arch, files: 0, bytes: 0 });
+        usage.files += 1;
+        usage.bytes += metadata.len();
+    }
+    let mut archs: Vec<ArchUsage> = archs.into_values().collect();
+    archs.sort_by_key(|usage| std::cmp::Reverse(usage.bytes));
+    archs
+}
+
+/// Compute capabilities are two or three digits (`75`, `86`, `100`)
+fn is_capability(digits: &str) -> bool {
+    (2..=3).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_engines_are_grouped_by_gpu_architecture() {
+        let arch = |name: &str| gpu_arch(Path::new(name));
+        assert_eq!(arch("TensorrtExecutionProvider_TRTKernel_graph_main_4870623_0_0_sm86.engine").as_deref(), Some("sm_86"));
+        assert_eq!(arch("TensorrtExecutionProvider_cache_sm80.timing").as_deref(), Some("sm_80"));
+        assert_eq!(arch("unet-fp16-sm_89.plan").as_deref(), Some("sm_89"));
+        assert_eq!(arch("model_ctx.onnx"), None);
+        assert_eq!(arch("small.engine"), None);
+        
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a_sm86.engine"), vec![0u8; 300]).unwrap();
+        fs::write(temp_dir.path().join("b_sm86.profile"), vec![0u8; 20]).unwrap();
+        fs::write(temp_dir.path().join("c_sm75.engine"), vec![0u8; 100]).unwrap();
+        fs::write(temp_dir.path().join("model.onnx"), vec![0u8; 10]).unwrap();
+        let archs = by_arch(temp_dir.path());
+        let summary: Vec<(&str, u64, u64)> = archs.iter().map(|usage| (usage.arch.as_str(), usage.files, usage.bytes)).collect();
+        assert_eq!(summary, vec![("sm_86", 2, 320), ("sm_75", 1, 100), (UNKNOWN_ARCH, 1, 10)]);
+    }
+}
\ No newline at end of file
@@ -6,6 +6,7 @@ use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
+use crate::command_runner::CommandRunner;
 use crate::errors::{ClearModelError, Result};
 
 /// Environment variable registry with validation rules
@@ -16,10 +17,27 @@ pub struct EnvVarConfig {
     pub default: String,
 }
 
+/// Root filesystems that container runtimes build their images on
+const CONTAINER_ROOT_FS: [&str; 3] = ["overlay", "aufs", "fuse-overlayfs"];
+
+/// Mounts a runtime creates itself, which never carry host data
+const VIRTUAL_FS: [&str; 9] = ["overlay", "tmpfs", "proc", "sysfs", "devpts", "mqueue", "cgroup", "cgroup2", "devtmpfs"];
+
+/// Filesystem layout of a container whose home directories may belong to the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerLayout {
+    /// Filesystem type of `/` (e.g. `overlay`)
+    pub root_fs_type: String,
+    
+    /// Mount points over home directories that come from another device, most likely bind mounts
+    pub host_mounts: Vec<PathBuf>,
+}
+
 /// Secure environment manager with automatic .env loading and validation
 pub struct EnvironmentManager {
     env_registry: HashMap<String, EnvVarConfig>,
     sudo_password: Option<Secret<String>>,
+    container: Option<ContainerLayout>,
 }
 
 impl EnvironmentManager {
@@ -28,12 +46,30 @@ impl EnvironmentManager {
         let mut manager = Self {
             env_registry: Self::create_env_registry(),
             sudo_password: None,
+            container: Self::detect_container(),
         };
         
+        if let Some(layout) = &manager.container {
+            info!(
+                "Running in a container ({} root) with home directories mounted from elsewhere: {:?}",
+                layout.root_fs_type,
+                layout.host_mounts
+            );
+        }
         manager.load_environment().await?;
         Ok(manager)
     }
     
+    /// Environment manager that reads no .env file and holds no password
+    #[cfg(test)]
+    pub(crate) fn detached() -> Self {
+        Self {
+            env_registry: Self::create_env_registry(),
+            sudo_password: None,
+            container: None,
+        }
+    }
+    
     /// Load environment variables from .env file and validate
     async fn load_environment(&mut self) -> Result<()> {
         // Try to load .env file from internal directory
@@ -182,8 +218,8 @@ impl EnvironmentManager {
             }
         }
         
-        // If not in environment, we'll prompt for it when needed
-        debug!("Sudo password not found in environment - will prompt when needed");
+        // If not in environment, the keyring or a prompt supplies it when needed
+        debug!("Sudo password not found in environment - will look it up when needed");
         
         Ok(())
     }
@@ -226,8 +262,19 @@ impl EnvironmentManager {
         registry
     }
     
-    /// Get sudo password securely - prompts if not available
-    pub fn get_sudo_password(&mut self) -> Result<&Secret<String>> {
+    /// Get sudo password securely: from `SUDO_PASSWORD`, then the OS keyring (its tool run
+    /// through `commands`), then a prompt
+    pub fn get_sudo_password(&mut self, commands: &CommandRunner) -> Result<&Secret<String>> {
+        #[cfg(not(feature = "keyring"))]
+        let _ = commands;
+        #[cfg(feature = "keyring")]
+        if self.sudo_password.is_none() {
+            self.sudo_password = crate::keyring::load(commands, crate::keyring::SUDO_ACCOUNT);
+            if self.sudo_password.is_some() {
+                debug!("Sudo password loaded from the {}", crate::keyring::backend());
+            }
+        }
+        
         if self.sudo_password.is_none() {
             self.prompt_for_sudo_password()?;
         }
@@ -240,6 +287,14 @@ impl EnvironmentManager {
     
     /// Prompt for sudo password securely
     fn prompt_for_sudo_password(&mut self) -> Result<()> {
+        self.sudo_password = Some(Self::read_sudo_password()?);
+        debug!("Sudo password obtained from user input");
+        
+        Ok(())
+    }
+    
+    /// Ask for the sudo password on the terminal
+    pub fn read_sudo_password() -> Result<Secret<String>> {
         print!("Enter sudo password: ");
         io::stdout().flush()
             .map_err(|e| ClearModelError::environment(
@@ -257,10 +312,7 @@ impl EnvironmentManager {
             ));
         }
         
-        self.sudo_password = Some(Secret::new(password));
-        debug!("Sudo password obtained from user input");
-        
-        Ok(())
+        Ok(Secret::new(password))
     }
     
     /// Get an environment variable with default fallback
@@ -290,6 +342,58 @@ impl EnvironmentManager {
     pub fn get_registry(&self) -> &HashMap<String, EnvVarConfig> {
         &self.env_registry
     }
+    
+    /// The container layout detected at startup, if home directories look host-mounted
+    pub fn container_layout(&self) -> Option<&ContainerLayout> {
+        self.container.as_ref()
+    }
+    
+    /// Detect a container root with the home directory bind-mounted from the host
+    pub fn detect_container() -> Option<ContainerLayout> {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+        let mut homes = vec![PathBuf::from("/home"), PathBuf::from("/root")];
+        homes.extend(home::home_dir());
+        Self::container_layout_from_mountinfo(&mountinfo, &homes)
+    }
+    
+    /// Read the layout from `/proc/self/mountinfo` contents
+    ///
+    /// Only a container-style root (overlayfs and friends) counts, and only real
+    /// filesystems mounted at, above or below one of `homes`.
+    pub fn container_layout_from_mountinfo(mountinfo: &str, homes: &[PathBuf]) -> Option<ContainerLayout> {
+        // <id> <parent> <major:minor> <root> <mount point> <options> [optional...] - <fs type> <source> ...
+        let mounts: Vec<(&str, PathBuf, &str)> = mountinfo.lines()
+            .filter_map(|line| {
+                let (fields, rest) = line.split_once(" - ")?;
+                let fields: Vec<&str> = fields.split_whitespace().collect();
+                let fs_type = rest.split_whitespace().next()?;
+                Some((*fields.get(2)?, PathBuf::from(fields.get(4)?.replace("\\040", " ")), fs_type))
+            })
+            .collect();
+        
+        let (root_device, _, root_fs_type) = mounts.iter().rev().find(|(_, point, _)| point == Path::new("/"))?;
+        if !CONTAINER_ROOT_FS.contains(root_fs_type) {
+            return None;
+        }
+        
+        let mut host_mounts: Vec<PathBuf> = mounts.iter()
+            .filter(|(device, point, fs_type)| {
+                device != root_device
+                    && point != Path::new("/")
+                    && !VIRTUAL_FS.contains(fs_type)
+                    && homes.iter().any(|home| home.starts_with(point) || point.starts_with(home))
+            })
+            .map(|(_, point, _)| point.clone())
+            .collect();
+        host_mounts.sort();
+        host_mounts.dedup();
+        
+        if host_mounts.is_empty() {
+            None
+        } else {
+            Some(ContainerLayout { root_fs_type: root_fs_type.to_string(), host_mounts })
+        }
+    }
 }
 
 impl Drop for EnvironmentManager {
@@ -306,16 +410,17 @@ impl Drop for EnvironmentManager {
 mod tests {
     use super::*;
     use std::env;
-    use tempfile::TempDir;
     
     #[tokio::test]
     async fn test_env_registry_creation() {
         let registry = EnvironmentManager::create_env_registry();
         assert!(registry.contains_key("SUDO_PASSWORD"));
-        assert!(registry.get("SUDO_PASSWORD").unwrap().required);
+        // SUDO_PASSWORD is optional - it is prompted for when needed
+        assert!(!registry.get("SUDO_PASSWORD").unwrap().required);
     }
     
     #[tokio::test]
+    #[allow(clippy::bool_assert_comparison)]
     async fn test_env_var_parsing() {
         env::set_var("TEST_INT", "42");
         env::set_var("TEST_BOOL", "true");
@@ -323,6 +428,7 @@ mod tests {
         let manager = EnvironmentManager {
             env_registry: HashMap::new(),
             sudo_password: None,
+            container: None,
         };
         
         assert_eq!(manager.get_env_var_as_int("TEST_INT", 0), 42);
@@ -332,4 +438,26 @@ mod tests {
         env::remove_var("TEST_INT");
         env::remove_var("TEST_BOOL");
     }
+    
+    #[test]
+    fn test_container_with_bind_mounted_home_is_detected() {
+        let mountinfo = "\
+1200 1100 0:140 / / rw,relatime master:1 - overlay overlay rw,lowerdir=/var/lib/docker/a
+1201 1200 0:143 / /proc rw,nosuid - proc proc rw
+1202 1200 0:144 / /dev rw,nosuid - tmpfs tmpfs rw
+1210 1200 259:2 /home/me /home/me rw,relatime - ext4 /dev/nvme0n1p2 rw
+1211 1200 259:2 /var/lib/docker/containers/x/hosts /etc/hosts rw - ext4 /dev/nvme0n1p2 rw
+";
+        let homes = [PathBuf::from("/home"), PathBuf::from("/root")];
+        let layout = EnvironmentManager::container_layout_from_mountinfo(mountinfo, &homes).unwrap();
+        assert_eq!(layout.root_fs_type, "overlay");
+        assert_eq!(layout.host_mounts, vec![PathBuf::from("/home/me")]);
+        
+        // A host whose / and /home are plain filesystems is not a container
+        let host = "\
+22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+23 22 259:3 / /home rw,relatime shared:2 - ext4 /dev/nvme0n1p3 rw
+";
+        assert!(EnvironmentManager::container_layout_from_mountinfo(host, &homes).is_none());
+    }
 } 
\ No newline at end of file
@@ -1,6 +1,24 @@
 use thiserror::Error;
 use std::path::PathBuf;
 
+/// Exit status of a run that failed in a way no other status describes
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit status of a run that finished but could not remove some files
+pub const EXIT_PARTIAL: i32 = 2;
+
+/// Exit status when the configuration could not be read or is invalid
+pub const EXIT_CONFIGURATION: i32 = 3;
+
+/// Exit status when a path or operation was refused by the security checks
+pub const EXIT_SECURITY: i32 = 4;
+
+/// Exit status when another clearmodel instance holds the run lock
+pub const EXIT_LOCKED: i32 = 5;
+
+/// Exit status of a run stopped by Ctrl-C or SIGTERM
+pub const EXIT_CANCELLED: i32 = 6;
+
 /// Custom error types for clearmodel operations
 #[derive(Error, Debug)]
 pub enum ClearModelError {
@@ -28,6 +46,15 @@ pub enum ClearModelError {
     #[error("Security validation failed: {message}")]
     Security { message: String },
     
+    #[error("Another clearmodel instance is running{}; pass --wait to wait for it", .pid.map(|pid| format!(" (pid {})", pid)).unwrap_or_default())]
+    AlreadyRunning { pid: Option<u32> },
+    
+    #[error("Interrupted before the run finished")]
+    Cancelled,
+    
+    #[error("{what} did not finish within {secs}s")]
+    TimedOut { what: String, secs: u64 },
+    
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     
@@ -36,6 +63,10 @@ pub enum ClearModelError {
     
     #[error("Configuration parsing error: {0}")]
     ConfigParsing(#[from] config::ConfigError),
+    
+    /// Another error, with advice on fixing it that is specific to where it happened
+    #[error("{error}")]
+    Hinted { error: Box<ClearModelError>, hint: String },
 }
 
 impl ClearModelError {
@@ -87,6 +118,112 @@ impl ClearModelError {
             message: message.into(),
         }
     }
+    
+    /// Attach advice on how to fix the error, replacing its class's default hint
+    pub fn with_hint(self, hint: impl Into<String>) -> Self {
+        let error = match self {
+            Self::Hinted { error, .. } => error,
+            error => Box::new(error),
+        };
+        Self::Hinted { error, hint: hint.into() }
+    }
+    
+    /// Stable identifier of the failure class, for scripts and support tooling to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Configuration { .. } => "CM-CFG-001",
+            Self::ConfigParsing(_) => "CM-CFG-002",
+            Self::Environment { .. } => "CM-ENV-001",
+            Self::PathTraversal { .. } => "CM-SEC-001",
+            Self::Security { .. } => "CM-SEC-002",
+            Self::Permission { .. } => "CM-PERM-001",
+            Self::FileOperation { .. } => "CM-FS-001",
+            Self::Io(_) => "CM-FS-002",
+            Self::Serialization(_) => "CM-FS-003",
+            Self::Cache { .. } => "CM-CACHE-001",
+            Self::ResourceManager { .. } => "CM-RES-001",
+            Self::AlreadyRunning { .. } => "CM-RUN-001",
+            Self::Cancelled => "CM-RUN-002",
+            Self::TimedOut { .. } => "CM-RUN-003",
+            Self::Hinted { error, .. } => error.code(),
+        }
+    }
+    
+    /// What to do about the error, when there is general advice for its class
+    pub fn hint(&self) -> Option<&str> {
+        match self {
+            Self::Hinted { hint, .. } => Some(hint),
+            Self::Configuration { .. } | Self::ConfigParsing(_) => {
+                Some("Check the configuration with `clearmodel config validate`")
+            }
+            Self::Environment { .. } => Some("Check the environment variables and the .env file clearmodel loads"),
+            Self::PathTraversal { .. } => Some("Only paths inside the configured cache directories are touched; check cache_paths"),
+            Self::Permission { .. } => Some("Run as a user who can write to the cache directory"),
+            Self::TimedOut { .. } => Some("Check for a hung network mount, or raise the limits in [timeouts]"),
+            _ => None,
+        }
+    }
+    
+    /// The error as rustc prints its own: `error[CODE]: message`, then the hint
+    pub fn render(&self) -> String {
+        match self.hint() {
+            Some(hint) => format!("error[{}]: {}\n  hint: {}", self.code(), self, hint),
+            None => format!("error[{}]: {}", self.code(), self),
+        }
+    }
+    
+    /// `code`, `message` and `hint` as a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "hint": self.hint(),
+        })
+    }
+    
+    /// Status the process exits with when a run fails with this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Hinted { error, .. } => error.exit_code(),
+            Self::Configuration { .. } | Self::ConfigParsing(_) => EXIT_CONFIGURATION,
+            Self::Security { .. } | Self::PathTraversal { .. } => EXIT_SECURITY,
+            Self::AlreadyRunning { .. } => EXIT_LOCKED,
+            Self::Cancelled => EXIT_CANCELLED,
+            _ => EXIT_FAILURE,
+        }
+    }
 }
 
-pub type Result<T> = std::result::Result<T, ClearModelError>; 
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, ClearModelError>; 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_failure_classes_have_distinct_exit_codes() {
+        let codes = [
+            ClearModelError::configuration("bad").exit_code(),
+            ClearModelError::path_traversal("/etc").exit_code(),
+            ClearModelError::AlreadyRunning { pid: Some(1) }.exit_code(),
+            ClearModelError::Cancelled.exit_code(),
+            ClearModelError::cache("full").exit_code(),
+        ];
+        assert_eq!(codes, [EXIT_CONFIGURATION, EXIT_SECURITY, EXIT_LOCKED, EXIT_CANCELLED, EXIT_FAILURE]);
+    }
+    
+    #[test]
+    fn test_hints_keep_the_code_and_message_of_the_error() {
+        let error = ClearModelError::cache("No interrupted run to resume");
+        assert_eq!((error.code(), error.hint()), ("CM-CACHE-001", None));
+        
+        let hinted = error.with_hint("Run `clearmodel clean` instead");
+        assert_eq!(hinted.to_string(), "Cache operation error: No interrupted run to resume");
+        assert_eq!(
+            hinted.render(),
+            "error[CM-CACHE-001]: Cache operation error: No interrupted run to resume\n  hint: Run `clearmodel clean` instead"
+        );
+        assert_eq!(hinted.to_json()["code"], "CM-CACHE-001");
+        assert_eq!(ClearModelError::Cancelled.to_json()["hint"], serde_json::Value::Null);
+    }
+}
\ No newline at end of file
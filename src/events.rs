@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+use crate::errors::{ClearModelError, Result};
+
+/// Machine-readable event stream formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventFormat {
+    /// One JSON object per line
+    Ndjson,
+}
+
+impl FromStr for EventFormat {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "ndjson" => Ok(EventFormat::Ndjson),
+            other => Err(format!("unknown event format {:?} (expected ndjson)", other)),
+        }
+    }
+}
+
+impl fmt::Display for EventFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventFormat::Ndjson => f.write_str("ndjson"),
+        }
+    }
+}
+
+/// A cleanup lifecycle event
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A cache directory is about to be scanned
+    ScanStarted { path: PathBuf },
+    
+    /// A file was removed (or would be, in a dry run)
+    FileDeleted { path: PathBuf, bytes: u64, dry_run: bool },
+    
+    /// A cache directory is done
+    DirCompleted {
+        path: PathBuf,
+        files_removed: u64,
+        bytes_freed: u64,
+        errors: u64,
+        duration_ms: u64,
+    },
+    
+    /// The whole run is done
+    RunFinished {
+        succeeded: bool,
+        files_removed: u64,
+        bytes_freed: u64,
+        errors: u64,
+        duration_ms: u64,
+    },
+    
+    Error { path: Option<PathBuf>, message: String },
+}
+
+impl Event {
+    pub fn error(path: Option<&Path>, message: impl fmt::Display) -> Self {
+        Event::Error {
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    /// Milliseconds since the Unix epoch
+    ts: u64,
+    
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Shared writer for the event stream; cheap to clone into cleanup tasks
+#[derive(Clone)]
+pub struct EventSink {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl EventSink {
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self { writer: Arc::new(Mutex::new(writer)) }
+    }
+    
+    pub fn stdout() -> Self {
+        Self::new(Box::new(std::io::stdout()))
+    }
+    
+    /// Write events to a file or named pipe (opening a pipe waits for its reader)
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to open event stream: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        Ok(Self::new(Box::new(file)))
+    }
+    
+    /// Write one event as a line; a reader that went away does not fail the cleanup
+    pub fn emit(&self, event: &Event) {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        let Ok(mut line) = serde_json::to_vec(&Envelope { ts, event }) else {
+            return;
+        };
+        line.push(b'\n');
+        
+        let Ok(mut writer) = self.writer.lock() else {
+            return;
+        };
+        if let Err(e) = writer.write_all(&line).and_then(|_| writer.flush()) {
+            debug!("Failed to write event: {}", e);
+        }
+    }
+}
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSink").finish_non_exhaustive()
+    }
+}
+
+pub fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+    
+    impl Write for Buffer {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(data)
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_events_are_written_as_tagged_lines() {
+        let buffer = Buffer::default();
+        let sink = EventSink::new(Box::new(buffer.clone()));
+        sink.emit(&Event::FileDeleted { path: PathBuf::from("/cache/a.bin"), bytes: 42, dry_run: true });
+        sink.emit(&Event::error(None, "boom"));
+        
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "file_deleted");
+        assert_eq!(lines[0]["path"], "/cache/a.bin");
+        assert_eq!(lines[0]["bytes"], 42);
+        assert!(lines[0]["ts"].as_u64().unwrap() > 0);
+        assert_eq!(lines[1]["event"], "error");
+        assert_eq!(lines[1]["message"], "boom");
+        assert!(lines[1]["path"].is_null());
+    }
+}
\ No newline at end of file
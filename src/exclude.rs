@@ -0,0 +1,150 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::prescan;
+
+/// File in a cache root listing gitignore-style patterns that are never cleaned
+pub const IGNORE_FILE: &str = ".clearmodelignore";
+
+/// Extended attribute marking a file that is never cleaned, whatever its value
+pub const KEEP_XATTR: &str = "user.clearmodel.keep";
+
+/// Files and directories a walk never considers
+///
+/// Each cache root contributes `exclude_patterns` from the configuration and its own
+/// `.clearmodelignore`. Patterns follow gitignore rules relative to the cache root: `*.lock`
+/// matches at any depth, `/models/keep` only at the top, `checkpoints/` only directories,
+/// and `!` re-includes a path (but not inside an excluded directory, which is never entered).
+#[derive(Debug, Clone)]
+pub struct Excludes {
+    matchers: Vec<Gitignore>,
+}
+
+impl Excludes {
+    /// Patterns for a walk starting at `root`
+    ///
+    /// Cache roots enclosing `root` or inside it all apply, so a walk of one repository or
+    /// of the working directory honours the same ignore files as a walk of the whole cache.
+    /// A walk outside every cache root applies the configured patterns relative to `root`.
+    pub fn for_walk(root: &Path, config: &ClearModelConfig) -> Self {
+        let mut roots: Vec<PathBuf> = prescan::cache_roots(config)
+            .into_iter()
+            .filter(|cache_root| root.starts_with(cache_root) || cache_root.starts_with(root))
+            .collect();
+        if !roots.iter().any(|cache_root| root.starts_with(cache_root)) {
+            roots.push(root.to_path_buf());
+        }
+        
+        Self {
+            matchers: roots.iter().map(|cache_root| matcher(cache_root, &config.exclude_patterns)).collect(),
+        }
+    }
+    
+    /// Whether the walk should leave `path` (and everything under it) alone
+    ///
+    /// The ignore file itself is always excluded so cleaning never removes it.
+    pub fn excludes(&self, path: &Path, is_dir: bool) -> bool {
+        if !is_dir && path.file_name().is_some_and(|name| name == IGNORE_FILE) {
+            return true;
+        }
+        self.matchers.iter().any(|matcher| {
+            path != matcher.path()
+                && path.starts_with(matcher.path())
+                && matcher.matched(path, is_dir).is_ignore()
+        })
+    }
+}
+
+/// Configured patterns plus the ignore file of one root; bad lines are logged and skipped
+fn matcher(root: &Path, patterns: &[String]) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        if let Err(e) = builder.add_line(None, pattern) {
+            warn!("Ignoring invalid exclude pattern {:?}: {}", pattern, e);
+        }
+    }
+    
+    let ignore_file = root.join(IGNORE_FILE);
+    if ignore_file.is_file() {
+        debug!("Loading exclude patterns from {:?}", ignore_file);
+        if let Some(e) = builder.add(&ignore_file) {
+            warn!("Problem reading {:?}: {}", ignore_file, e);
+        }
+    }
+    
+    builder.build().unwrap_or_else(|e| {
+        warn!("Exclude patterns for {:?} could not be compiled: {}", root, e);
+        Gitignore::empty()
+    })
+}
+
+/// Whether a file carries the `user.clearmodel.keep` extended attribute
+///
+/// Set it with `setfattr -n user.clearmodel.keep <file>`. Only read on Linux, and only when
+/// the environment allows extended attributes (see `clearmodel doctor`).
+#[cfg(target_os = "linux")]
+pub fn keep_marker(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let (Ok(c_path), Ok(name)) = (std::ffi::CString::new(path.as_os_str().as_bytes()), std::ffi::CString::new(KEEP_XATTR)) else {
+        return false;
+    };
+    // SAFETY: both strings are NUL-terminated and a zero-sized read writes nothing
+    unsafe { libc::getxattr(c_path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) >= 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn keep_marker(_path: &Path) -> bool {
+    false
+}
+
+/// Check that a configured pattern parses
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    GitignoreBuilder::new("/")
+        .add_line(None, pattern)
+        .map(|_| ())
+        .map_err(|e| ClearModelError::configuration(
+            format!("Invalid exclude pattern {:?}: {}", pattern, e)
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_config_patterns_and_ignore_file_are_combined() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("cache");
+        fs::create_dir_all(root.join("hub")).unwrap();
+        fs::write(root.join(IGNORE_FILE), "# experiments in progress\n/runs/\n*.keep\n!important.keep\n").unwrap();
+        
+        let config = ClearModelConfig {
+            cache_paths: vec![root.clone()],
+            exclude_patterns: vec!["*.lock".to_string()],
+            ..ClearModelConfig::default()
+        };
+        let excludes = Excludes::for_walk(&root, &config);
+        assert!(excludes.excludes(&root.join("hub/models--org--name/blobs/abc.lock"), false));
+        assert!(excludes.excludes(&root.join("runs"), true));
+        assert!(!excludes.excludes(&root.join("hub/runs"), true));
+        assert!(excludes.excludes(&root.join("weights.keep"), false));
+        assert!(!excludes.excludes(&root.join("important.keep"), false));
+        assert!(excludes.excludes(&root.join(IGNORE_FILE), false));
+        assert!(!excludes.excludes(&root.join("model.bin"), false));
+        
+        // Walks starting above or below the cache root use its ignore file too
+        let above = Excludes::for_walk(temp_dir.path(), &config);
+        assert!(above.excludes(&root.join("runs"), true));
+        let below = Excludes::for_walk(&root.join("hub"), &config);
+        assert!(below.excludes(&root.join("hub/weights.keep"), false));
+        
+        assert!(validate_pattern("checkpoints/**").is_ok());
+        assert!(validate_pattern("{llama,mistral").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,110 @@
+use globset::GlobBuilder;
+use std::path::{Component, Path, PathBuf};
+
+/// Characters that make a path component a glob pattern
+const GLOB_CHARS: [char; 4] = ['*', '?', '[', '{'];
+
+/// Expand a configured path: a leading `~`, `$VAR` and `${VAR}`, then glob components
+///
+/// A path without glob characters comes back as-is, whether it exists or not. A glob comes
+/// back as the existing directories it matches, sorted; matching nothing is not an error.
+pub fn expand(path: &Path) -> Result<Vec<PathBuf>, String> {
+    let text = path.to_str().ok_or_else(|| format!("{:?} is not valid UTF-8", path))?;
+    let expanded = PathBuf::from(expand_vars(&expand_tilde(text)?)?);
+    if !expanded.to_string_lossy().contains(GLOB_CHARS) {
+        return Ok(vec![expanded]);
+    }
+    
+    let mut matches = vec![PathBuf::new()];
+    for component in expanded.components() {
+        let Component::Normal(name) = component else {
+            matches.iter_mut().for_each(|path| path.push(component));
+            continue;
+        };
+        let name = name.to_string_lossy();
+        if !name.contains(GLOB_CHARS) {
+            matches.iter_mut().for_each(|path| path.push(name.as_ref()));
+            continue;
+        }
+        let glob = GlobBuilder::new(&name).literal_separator(true).build()
+            .map_err(|e| format!("invalid pattern {:?}: {}", name, e))?
+            .compile_matcher();
+        matches = matches.iter()
+            .flat_map(|dir| std::fs::read_dir(dir).into_iter().flatten().flatten())
+            .filter(|entry| glob.is_match(entry.file_name()))
+            .map(|entry| entry.path())
+            .collect();
+    }
+    matches.retain(|path| path.is_dir());
+    matches.sort();
+    Ok(matches)
+}
+
+fn expand_tilde(text: &str) -> Result<String, String> {
+    match text.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            let home = home::home_dir().ok_or("the home directory is unknown")?;
+            Ok(format!("{}{}", home.display(), rest))
+        }
+        _ => Ok(text.to_string()),
+    }
+}
+
+/// Replace `$VAR` and `${VAR}`; an unset variable is an error rather than an empty string,
+/// which would turn `$SCRATCH/models` into `/models`
+fn expand_vars(text: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced.find('}').ok_or_else(|| format!("unclosed ${{ in {:?}", text))?;
+                (&braced[..end], &braced[end + 1..])
+            }
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            let value = std::env::var(name).map_err(|_| format!("${} is not set", name))?;
+            expanded.push_str(&value);
+        }
+        rest = remainder;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_paths_are_expanded_and_globbed() {
+        let home = home::home_dir().unwrap();
+        assert_eq!(expand(Path::new("~/.cache/huggingface")).unwrap(), vec![home.join(".cache/huggingface")]);
+        assert_eq!(expand(Path::new("/srv/~user")).unwrap(), vec![PathBuf::from("/srv/~user")]);
+        assert!(expand(Path::new("$CLEARMODEL_TEST_SURELY_UNSET/models")).unwrap_err().contains("CLEARMODEL_TEST_SURELY_UNSET"));
+        
+        let temp_dir = TempDir::new().unwrap();
+        for dir in ["alice/hf-cache", "bob/hf-cache", "carol/other"] {
+            fs::create_dir_all(temp_dir.path().join(dir)).unwrap();
+        }
+        fs::write(temp_dir.path().join("notes-hf-cache"), "").unwrap();
+        let pattern = temp_dir.path().join("*").join("hf-cache");
+        assert_eq!(expand(&pattern).unwrap(), vec![
+            temp_dir.path().join("alice/hf-cache"),
+            temp_dir.path().join("bob/hf-cache"),
+        ]);
+        assert_eq!(expand(&temp_dir.path().join("*-cache")).unwrap(), Vec::<PathBuf>::new());
+        
+        assert_eq!(expand_vars("${HOME}/x/$HOME").unwrap(), format!("{}/x/{}", std::env::var("HOME").unwrap(), std::env::var("HOME").unwrap()));
+    }
+}
\ No newline at end of file
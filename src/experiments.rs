@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+use crate::config::ClearModelConfig;
+
+/// Parts of a W&B run directory that are only staging for uploads
+const WANDB_RUN_STAGING: [&str; 3] = ["files/media", "files/artifacts", "tmp"];
+
+/// Directories never searched for `.dvc` files inside a workspace
+const SKIPPED_DIRS: [&str; 5] = [".git", ".dvc", "node_modules", ".venv", "__pycache__"];
+
+/// DVC caches of the configured workspaces: each workspace's `cache.dir` (`.dvc/cache` unless
+/// set in `.dvc/config`), and the shared `~/.dvc/cache`
+///
+/// Without workspaces nothing is known to be referenced, so no cache is returned.
+pub fn dvc_caches(config: &ClearModelConfig) -> Vec<PathBuf> {
+    let workspaces = &config.dvc.workspaces;
+    if workspaces.is_empty() {
+        return Vec::new();
+    }
+    let mut caches: Vec<PathBuf> = workspaces.iter().map(|workspace| dvc_cache_dir(workspace)).collect();
+    caches.extend(home::home_dir().map(|home| home.join(".dvc").join("cache")));
+    caches.sort();
+    caches.dedup();
+    caches
+}
+
+/// A workspace's cache directory, from `[cache] dir` in `.dvc/config.local` or `.dvc/config`
+fn dvc_cache_dir(workspace: &Path) -> PathBuf {
+    let dvc = workspace.join(".dvc");
+    for file in ["config.local", "config"] {
+        let Ok(content) = std::fs::read_to_string(dvc.join(file)) else {
+            continue;
+        };
+        let mut section = "";
+        for line in content.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name.trim();
+            } else if let Some((key, value)) = line.split_once('=') {
+                if section == "cache" && key.trim() == "dir" {
+                    // Relative directories are relative to `.dvc`, where the config lives
+                    return dvc.join(value.trim());
+                }
+            }
+        }
+    }
+    dvc.join("cache")
+}
+
+/// Hashes referenced by the `dvc.lock` and `.dvc` files of a workspace
+pub fn dvc_references(workspace: &Path) -> HashSet<String> {
+    let mut hashes = HashSet::new();
+    let walker = WalkDir::new(workspace).into_iter().filter_entry(|entry| {
+        !(entry.file_type().is_dir() && entry.file_name().to_str().is_some_and(|name| SKIPPED_DIRS.contains(&name)))
+    });
+    for entry in walker.flatten().filter(|entry| entry.file_type().is_file()) {
+        let name = entry.file_name().to_string_lossy();
+        if name != "dvc.lock" && !name.ends_with(".dvc") {
+            continue;
+        }
+        let Some(doc) = std::fs::read_to_string(entry.path()).ok().and_then(|content| serde_yaml::from_str(&content).ok()) else {
+            continue;
+        };
+        collect_md5(&doc, &mut hashes);
+    }
+    hashes
+}
+
+fn collect_md5(value: &serde_yaml::Value, hashes: &mut HashSet<String>) {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                match (key.as_str(), value.as_str()) {
+                    (Some("md5"), Some(hash)) => {
+                        hashes.insert(hash.to_string());
+                    }
+                    _ => collect_md5(value, hashes),
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(values) => values.iter().for_each(|value| collect_md5(value, hashes)),
+        _ => {}
+    }
+}
+
+/// Hash of a cache object from its path: `files/md5/ab/cdef…` in DVC 3, `ab/cdef…` in DVC 2,
+/// with `.dir` kept for directory manifests
+pub fn dvc_object_hash(cache: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(cache).ok()?;
+    let parts: Vec<&str> = relative.iter().map(|part| part.to_str()).collect::<Option<_>>()?;
+    let (prefix, rest) = match parts.as_slice() {
+        ["files", "md5", prefix, rest] | [prefix, rest] => (*prefix, *rest),
+        _ => return None,
+    };
+    let is_hex = |s: &str| s.chars().all(|c| c.is_ascii_hexdigit());
+    let hash = rest.strip_suffix(".dir").unwrap_or(rest);
+    (prefix.len() == 2 && is_hex(prefix) && is_hex(hash) && !hash.is_empty()).then(|| format!("{}{}", prefix, rest))
+}
+
+/// Objects in the caches that no workspace references, directly or through a referenced
+/// directory manifest; the run cache and temporary files are left alone
+pub fn dvc_unreferenced(caches: &[PathBuf], workspaces: &[PathBuf]) -> Vec<PathBuf> {
+    let mut referenced: HashSet<String> = workspaces.iter().flat_map(|workspace| dvc_references(workspace)).collect();
+    let objects: Vec<(PathBuf, String)> = caches.iter()
+        .flat_map(|cache| {
+            WalkDir::new(cache).into_iter().flatten()
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(move |entry| Some((entry.path().to_path_buf(), dvc_object_hash(cache, entry.path())?)))
+        })
+        .collect();
+    
+    // A directory output is referenced by its manifest, which lists the hashes of its files
+    for (path, hash) in &objects {
+        if hash.ends_with(".dir") && referenced.contains(hash) {
+            let entries: Vec<serde_json::Value> = std::fs::read(path).ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or_default();
+            referenced.extend(entries.iter().filter_map(|entry| entry.get("md5")?.as_str().map(str::to_string)));
+        }
+    }
+    objects.into_iter()
+        .filter(|(_, hash)| !referenced.contains(hash))
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// MLflow file stores: `mlflow.tracking_dirs`, and `$MLFLOW_TRACKING_URI` when it is a local path
+pub fn mlflow_stores(config: &ClearModelConfig) -> Vec<PathBuf> {
+    let mut stores = config.mlflow.tracking_dirs.clone();
+    let tracking_uri = std::env::var("MLFLOW_TRACKING_URI").ok().filter(|uri| !uri.is_empty());
+    if let Some(uri) = tracking_uri {
+        match uri.strip_prefix("file://").or_else(|| uri.strip_prefix("file:")) {
+            Some(path) => stores.push(PathBuf::from(path)),
+            None if !uri.contains("://") => stores.push(PathBuf::from(uri)),
+            None => {}
+        }
+    }
+    stores.sort();
+    stores.dedup();
+    stores
+}
+
+/// `artifacts` directories of runs that ended longer ago than the retention window
+///
+/// Runs are `<store>/<experiment id>/<run id>/meta.yaml`; their params and metrics stay, so the
+/// run is still listed. Runs without an end time are still running and are kept.
+pub fn mlflow_expired(store: &Path, retention: Duration) -> Vec<PathBuf> {
+    let now = SystemTime::now();
+    let mut expired = Vec::new();
+    for experiment in subdirs(store) {
+        for run in subdirs(&experiment) {
+            let artifacts = run.join("artifacts");
+            let ended = run_end_time(&run.join("meta.yaml"));
+            if ended.is_some_and(|ended| now.duration_since(ended).unwrap_or_default() > retention) && artifacts.is_dir() {
+                expired.push(artifacts);
+            }
+        }
+    }
+    expired
+}
+
+/// `end_time` of a run's `meta.yaml`, in milliseconds since the epoch
+fn run_end_time(meta: &Path) -> Option<SystemTime> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(meta).ok()?).ok()?;
+    doc.get("run_id")?;
+    let millis = doc.get("end_time")?.as_u64()?;
+    Some(UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+/// A directory of W&B runs with its retention
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WandbProject {
+    /// The `wandb` directory holding `run-*` and `offline-run-*` directories
+    pub runs: PathBuf,
+    pub retention: Duration,
+}
+
+/// W&B's artifact cache (`$WANDB_CACHE_DIR`, else `~/.cache/wandb`) and upload staging
+/// (`$WANDB_DATA_DIR/artifacts/staging`, else `~/.local/share/wandb/artifacts/staging`)
+pub fn wandb_caches() -> Vec<PathBuf> {
+    let home = home::home_dir();
+    let cache = env_dir("WANDB_CACHE_DIR").or_else(|| {
+        env_dir("XDG_CACHE_HOME").or_else(|| home.as_ref().map(|home| home.join(".cache"))).map(|cache| cache.join("wandb"))
+    });
+    let data = env_dir("WANDB_DATA_DIR").or_else(|| {
+        env_dir("XDG_DATA_HOME")
+            .or_else(|| home.as_ref().map(|home| home.join(".local").join("share")))
+            .map(|data| data.join("wandb"))
+    });
+    cache.into_iter().chain(data.map(|data| data.join("artifacts").join("staging"))).collect()
+}
+
+/// Run directories of `wandb.projects`, and of `$WANDB_DIR` under the default retention
+///
+/// A project path is either a project (holding `wandb/`) or the `wandb` directory itself.
+pub fn wandb_projects(config: &ClearModelConfig) -> Vec<WandbProject> {
+    let days = |days: u32| Duration::from_secs(days as u64 * 24 * 3600);
+    let default_retention = config.older_than.unwrap_or(days(config.wandb.run_retention_days));
+    let runs_dir = |path: &Path| if path.join("wandb").is_dir() { path.join("wandb") } else { path.to_path_buf() };
+    
+    let mut projects: Vec<WandbProject> = config.wandb.projects.iter()
+        .map(|project| WandbProject {
+            runs: runs_dir(&project.path),
+            retention: config.older_than.or(project.retention_days.map(days)).unwrap_or(default_retention),
+        })
+        .collect();
+    if let Some(dir) = env_dir("WANDB_DIR") {
+        let runs = runs_dir(&dir);
+        if !projects.iter().any(|project| project.runs == runs) {
+            projects.push(WandbProject { runs, retention: default_retention });
+        }
+    }
+    projects
+}
+
+/// Upload staging of the runs in a `wandb` directory: logged media, artifact copies and `tmp`
+///
+/// Offline runs are skipped until `wandb sync` has marked them synced, since their media has
+/// not reached the server yet; `latest-run` is a link to a run listed anyway.
+pub fn wandb_run_staging(runs: &Path) -> Vec<PathBuf> {
+    let mut staging = Vec::new();
+    for run in subdirs(runs) {
+        let Some(name) = run.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let synced = match name.strip_prefix("offline-run-") {
+            Some(rest) => {
+                let id = rest.rsplit('-').next().unwrap_or(rest);
+                run.join(format!("run-{}.wandb.synced", id)).exists()
+            }
+            None => name.starts_with("run-"),
+        };
+        if synced {
+            staging.extend(WANDB_RUN_STAGING.iter().map(|part| run.join(part)).filter(|path| path.is_dir()));
+        }
+    }
+    staging
+}
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+    dirs.sort();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_only_unreferenced_dvc_objects_are_returned() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("project");
+        let cache = workspace.join(".dvc/cache");
+        fs::create_dir_all(workspace.join("data")).unwrap();
+        fs::write(workspace.join("dvc.lock"), "schema: '2.0'\nstages:\n  train:\n    cmd: python train.py\n    outs:\n    - path: model.pt\n      hash: md5\n      md5: aa11\n      size: 7\n    - path: features\n      hash: md5\n      md5: bb22.dir\n").unwrap();
+        fs::write(workspace.join("data/raw.dvc"), "outs:\n- md5: cc33\n  path: raw\n").unwrap();
+        
+        let object = |hash: &str, content: &str| {
+            let path = cache.join("files/md5").join(&hash[..2]).join(&hash[2..]);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            path
+        };
+        object("aa11", "weights");
+        object("bb22.dir", r#"[{"md5": "dd44", "relpath": "part-0.parquet"}]"#);
+        object("cc33", "raw");
+        object("dd44", "features");
+        let stale = object("ee55", "old model");
+        fs::create_dir_all(cache.join("runs/ab")).unwrap();
+        fs::write(cache.join("runs/ab/run"), "run cache").unwrap();
+        
+        assert_eq!(dvc_cache_dir(&workspace), cache);
+        assert_eq!(dvc_object_hash(&cache, &cache.join("ab/cdef")).as_deref(), Some("abcdef"));
+        assert_eq!(dvc_unreferenced(std::slice::from_ref(&cache), &[workspace]), vec![stale]);
+    }
+    
+    #[test]
+    fn test_wandb_staging_skips_unsynced_offline_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let runs = temp_dir.path().join("wandb");
+        for dir in ["run-20240101_120000-abc123/files/media/images", "run-20240101_120000-abc123/tmp", "offline-run-20240102_090000-def456/files/media", "offline-run-20240103_090000-ghi789/files/media"] {
+            fs::create_dir_all(runs.join(dir)).unwrap();
+        }
+        fs::write(runs.join("offline-run-20240103_090000-ghi789/run-ghi789.wandb.synced"), "").unwrap();
+        
+        assert_eq!(wandb_run_staging(&runs), vec![
+            runs.join("offline-run-20240103_090000-ghi789/files/media"),
+            runs.join("run-20240101_120000-abc123/files/media"),
+            runs.join("run-20240101_120000-abc123/tmp"),
+        ]);
+    }
+    
+    #[test]
+    fn test_artifacts_of_old_finished_runs_expire() {
+        let temp_dir = TempDir::new().unwrap();
+        let run = |id: &str, end_time: &str| {
+            let run = temp_dir.path().join("1").join(id);
+            fs::create_dir_all(run.join("artifacts")).unwrap();
+            fs::write(run.join("meta.yaml"), format!("run_id: {}\nexperiment_id: '1'\nend_time: {}\n", id, end_time)).unwrap();
+            run.join("artifacts")
+        };
+        let old = run("old", "1600000000000");
+        run("recent", &(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis()).to_string());
+        run("running", "null");
+        fs::write(temp_dir.path().join("1/meta.yaml"), "experiment_id: '1'\nname: baseline\n").unwrap();
+        
+        assert_eq!(mlflow_expired(temp_dir.path(), Duration::from_secs(90 * 86_400)), vec![old]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Tabular output formats for `scan`, `top` and `clean`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("unknown output format {:?} (expected csv)", other)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Csv => f.write_str("csv"),
+        }
+    }
+}
+
+/// One line of an export; columns a command does not know are left empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportRow {
+    pub path: PathBuf,
+    pub target: String,
+    pub bytes: u64,
+    
+    /// Days since the file was last modified
+    pub age_days: Option<u64>,
+    pub last_access: Option<SystemTime>,
+    
+    /// What the run did with it (`deleted`, `quarantined`, `would delete`, ...)
+    pub action: Option<String>,
+}
+
+const HEADER: [&str; 6] = ["path", "target", "size_bytes", "age_days", "last_access", "action"];
+
+/// Write `rows` as CSV with a header, quoting fields as RFC 4180 asks
+pub fn write_csv(rows: &[ExportRow], mut out: impl Write) -> std::io::Result<()> {
+    writeln!(out, "{}", HEADER.join(","))?;
+    for row in rows {
+        let fields = [
+            row.path.display().to_string(),
+            row.target.clone(),
+            row.bytes.to_string(),
+            row.age_days.map(|days| days.to_string()).unwrap_or_default(),
+            row.last_access.map(utc_timestamp).unwrap_or_default(),
+            row.action.clone().unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| quote(field)).collect();
+        writeln!(out, "{}", fields.join(","))?;
+    }
+    out.flush()
+}
+
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ`, which spreadsheets read as a date
+fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    
+    // Days since the epoch to a proleptic Gregorian date (Howard Hinnant's civil_from_days)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rest / 3600, rest / 60 % 60, rest % 60)
+}
+
+/// Every file a cleanup removed (or would remove), with the target it was cleaned for
+#[derive(Debug, Default)]
+pub struct RemovalLog {
+    target: Mutex<String>,
+    rows: Mutex<Vec<ExportRow>>,
+}
+
+impl RemovalLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Attribute the files recorded from now on to `target`
+    pub fn start_target(&self, target: &str) {
+        *self.target.lock().unwrap_or_else(|e| e.into_inner()) = target.to_string();
+    }
+    
+    /// Record a file `action` was taken on, with its metadata from before the removal
+    pub fn record(&self, path: &Path, metadata: &std::fs::Metadata, action: &str) {
+        let target = self.target.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let age_days = metadata.modified().ok()
+            .map(|modified| SystemTime::now().duration_since(modified).map(|age| age.as_secs() / 86_400).unwrap_or(0));
+        let row = ExportRow {
+            path: path.to_path_buf(),
+            target,
+            bytes: metadata.len(),
+            age_days,
+            last_access: metadata.accessed().ok(),
+            action: Some(action.to_string()),
+        };
+        self.rows.lock().unwrap_or_else(|e| e.into_inner()).push(row);
+    }
+    
+    /// The files recorded so far, in path order
+    pub fn rows(&self) -> Vec<ExportRow> {
+        let mut rows = self.rows.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        rows.sort_by(|a, b| a.path.cmp(&b.path));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    
+    #[test]
+    fn test_csv_quotes_fields_and_formats_access_times() {
+        let rows = [
+            ExportRow {
+                path: PathBuf::from("/cache/models--org--a,b/blobs/\"x\""),
+                target: "huggingface".to_string(),
+                bytes: 1024,
+                age_days: Some(40),
+                last_access: Some(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+                action: Some("would delete".to_string()),
+            },
+            ExportRow {
+                path: PathBuf::from("/cache/pip"),
+                target: "pip".to_string(),
+                bytes: 7,
+                age_days: None,
+                last_access: None,
+                action: None,
+            },
+        ];
+        
+        let mut out = Vec::new();
+        write_csv(&rows, &mut out).unwrap();
+        
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "path,target,size_bytes,age_days,last_access,action\n\
+             \"/cache/models--org--a,b/blobs/\"\"x\"\"\",huggingface,1024,40,2024-02-29T12:34:56Z,would delete\n\
+             /cache/pip,pip,7,,,\n"
+        );
+    }
+}
\ No newline at end of file
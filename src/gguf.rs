@@ -0,0 +1,224 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+
+/// Magic bytes every GGUF file starts with
+const MAGIC: &[u8; 4] = b"GGUF";
+
+/// Key/value entries read before giving up on finding the general metadata
+const MAX_ENTRIES: u64 = 4096;
+
+/// Longest string read from a header; anything longer is not a GGUF file we understand
+const MAX_STRING: u64 = 1 << 20;
+
+/// File types the local LLM apps load models from; nothing else in their stores is touched
+pub const MODEL_EXTENSIONS: [&str; 2] = ["gguf", "bin"];
+
+/// What a GGUF file says about the model inside it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GgufInfo {
+    /// `general.name`, e.g. `Meta Llama 3 8B Instruct`
+    pub name: Option<String>,
+    
+    /// `general.architecture`, e.g. `llama`
+    pub architecture: Option<String>,
+    
+    /// Quantization from `general.file_type`, e.g. `Q4_K_M`
+    pub quantization: Option<String>,
+}
+
+/// Model name of a GGUF file: its stem, without the `-00001-of-00003` of split models
+pub fn model_name(file_name: &str) -> Option<&str> {
+    let stem = file_name.strip_suffix(".gguf")?;
+    let split = stem.rsplit_once("-of-").and_then(|(head, total)| {
+        let (name, part) = head.rsplit_once('-')?;
+        let digits = |s: &str| s.len() == 5 && s.chars().all(|c| c.is_ascii_digit());
+        (digits(part) && digits(total)).then_some(name)
+    });
+    Some(split.unwrap_or(stem)).filter(|name| !name.is_empty())
+}
+
+/// Read the general metadata from a GGUF file's header
+///
+/// Only versions 2 and 3 are understood; version 1 files predate `general.file_type`.
+/// Returns `None` for anything that is not a readable GGUF file.
+pub fn read_info(path: &Path) -> Option<GgufInfo> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).ok()?;
+    if &magic != MAGIC || !matches!(read_u32(&mut reader)?, 2 | 3) {
+        return None;
+    }
+    let _tensors = read_u64(&mut reader)?;
+    let entries = read_u64(&mut reader)?;
+    
+    let mut info = GgufInfo::default();
+    for _ in 0..entries.min(MAX_ENTRIES) {
+        let key = read_string(&mut reader)?;
+        let value_type = read_u32(&mut reader)?;
+        match (key.as_str(), value_type) {
+            ("general.name", STRING) => info.name = Some(read_string(&mut reader)?),
+            ("general.architecture", STRING) => info.architecture = Some(read_string(&mut reader)?),
+            ("general.file_type", UINT32) => info.quantization = Some(file_type_name(read_u32(&mut reader)?)),
+            _ => skip_value(&mut reader, value_type)?,
+        }
+        if info.name.is_some() && info.architecture.is_some() && info.quantization.is_some() {
+            break;
+        }
+    }
+    Some(info)
+}
+
+const UINT32: u32 = 4;
+const STRING: u32 = 8;
+const ARRAY: u32 = 9;
+
+/// Size of a fixed-size value type; strings and arrays have none
+fn fixed_size(value_type: u32) -> Option<i64> {
+    match value_type {
+        0 | 1 | 7 => Some(1),
+        2 | 3 => Some(2),
+        4..=6 => Some(4),
+        10..=12 => Some(8),
+        _ => None,
+    }
+}
+
+fn skip_value<R: Read + Seek>(reader: &mut BufReader<R>, value_type: u32) -> Option<()> {
+    if let Some(size) = fixed_size(value_type) {
+        return reader.seek_relative(size).ok();
+    }
+    match value_type {
+        STRING => {
+            let len = read_u64(reader)?;
+            (len <= MAX_STRING).then_some(())?;
+            reader.seek_relative(len as i64).ok()
+        }
+        ARRAY => {
+            let element_type = read_u32(reader)?;
+            let count = read_u64(reader)?;
+            if let Some(size) = fixed_size(element_type) {
+                return reader.seek_relative(size.checked_mul(i64::try_from(count).ok()?)?).ok();
+            }
+            // Tokenizer vocabularies are arrays of strings, so each one is skipped in turn
+            for _ in 0..count {
+                skip_value(reader, element_type)?;
+            }
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Option<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes).ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> Option<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes).ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
+
+fn read_string(reader: &mut impl Read) -> Option<String> {
+    let len = read_u64(reader)?;
+    if len > MAX_STRING {
+        return None;
+    }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// llama.cpp's name for a `general.file_type` value
+fn file_type_name(file_type: u32) -> String {
+    let name = match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        36 => "TQ1_0",
+        37 => "TQ2_0",
+        other => return format!("type {}", other),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_header_metadata_is_read() {
+        let string = |s: &str| [(s.len() as u64).to_le_bytes().as_slice(), s.as_bytes()].concat();
+        let mut header = Vec::new();
+        header.extend(MAGIC);
+        header.extend(3u32.to_le_bytes());
+        header.extend(291u64.to_le_bytes());
+        header.extend(4u64.to_le_bytes());
+        header.extend(string("general.architecture"));
+        header.extend(STRING.to_le_bytes());
+        header.extend(string("llama"));
+        // An array of strings before the wanted keys has to be skipped
+        header.extend(string("tokenizer.ggml.tokens"));
+        header.extend(ARRAY.to_le_bytes());
+        header.extend(STRING.to_le_bytes());
+        header.extend(2u64.to_le_bytes());
+        header.extend(string("<s>"));
+        header.extend(string("</s>"));
+        header.extend(string("general.name"));
+        header.extend(STRING.to_le_bytes());
+        header.extend(string("Meta Llama 3 8B Instruct"));
+        header.extend(string("general.file_type"));
+        header.extend(UINT32.to_le_bytes());
+        header.extend(15u32.to_le_bytes());
+        
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Meta-Llama-3-8B-Instruct-Q4_K_M.gguf");
+        std::fs::write(&path, &header).unwrap();
+        let info = read_info(&path).unwrap();
+        assert_eq!(info.name.as_deref(), Some("Meta Llama 3 8B Instruct"));
+        assert_eq!(info.architecture.as_deref(), Some("llama"));
+        assert_eq!(info.quantization.as_deref(), Some("Q4_K_M"));
+        
+        std::fs::write(&path, b"not a model").unwrap();
+        assert_eq!(read_info(&path), None);
+    }
+    
+    #[test]
+    fn test_split_models_share_a_name() {
+        assert_eq!(model_name("qwen2.5-72b-instruct-q4_k_m-00001-of-00012.gguf"), Some("qwen2.5-72b-instruct-q4_k_m"));
+        assert_eq!(model_name("mistral-7b-instruct-v0.2.Q5_K_M.gguf"), Some("mistral-7b-instruct-v0.2.Q5_K_M"));
+        assert_eq!(model_name("ggml-model.bin"), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,233 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+use walkdir::WalkDir;
+
+use crate::allocation::UsageCounter;
+use crate::config::ClearModelConfig;
+use crate::exclude::Excludes;
+use crate::policy::{CleanupPolicy, Decision, FileFacts};
+use crate::prescan;
+use crate::size_index::{self, RootSummary, SizeIndex};
+use crate::targets;
+
+/// Files smaller than this are too cheap to be worth counting as duplicates
+const DUPLICATE_MIN_BYTES: u64 = 1024 * 1024;
+
+/// Growth is only measured against an index entry at least this old
+const MIN_GROWTH_WINDOW: Duration = Duration::from_secs(3600);
+
+/// How much attention a cache deserves, from 100 (nothing to do) down to 0
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheHealth {
+    pub path: PathBuf,
+    pub score: u8,
+    pub bytes: u64,
+    pub files: u64,
+    
+    /// Bytes the policy considers expired
+    pub stale_bytes: u64,
+    
+    /// Leftovers of interrupted downloads
+    pub incomplete_files: u64,
+    
+    /// Bytes in extra copies of large files (same name and size, different inode)
+    pub duplicate_bytes: u64,
+    
+    /// Change since the last size index refresh, when there is one to compare with
+    pub growth_bytes_per_day: Option<f64>,
+}
+
+impl CacheHealth {
+    pub fn stale_ratio(&self) -> f64 {
+        ratio(self.stale_bytes, self.bytes)
+    }
+    
+    pub fn incomplete_ratio(&self) -> f64 {
+        ratio(self.incomplete_files, self.files)
+    }
+    
+    pub fn duplicate_ratio(&self) -> f64 {
+        ratio(self.duplicate_bytes, self.bytes)
+    }
+    
+    /// Score out of 100: stale data weighs 40 points, duplicates 25, interrupted
+    /// downloads 20 (all lost at 10% of files) and growth 15 (all lost at 5% a day)
+    fn compute_score(&mut self) {
+        let growth = self.growth_bytes_per_day
+            .map(|growth| (growth / (self.bytes.max(1) as f64 * 0.05)).clamp(0.0, 1.0))
+            .unwrap_or(0.0);
+        let penalty = 40.0 * self.stale_ratio()
+            + 25.0 * (self.duplicate_ratio() * 2.0).min(1.0)
+            + 20.0 * (self.incomplete_ratio() * 10.0).min(1.0)
+            + 15.0 * growth;
+        
+        self.score = (100.0 - penalty).round().clamp(0.0, 100.0) as u8;
+    }
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+/// Health of every managed cache root, worst first
+pub fn assess_all(config: &ClearModelConfig) -> Vec<CacheHealth> {
+    let index = size_index::index_path().map(|path| SizeIndex::load(&path).sized_for(config));
+    let package_roots: Vec<PathBuf> = targets::package_cache_locations()
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    
+    let mut caches: Vec<CacheHealth> = prescan::cache_roots(config)
+        .into_iter()
+        .map(|root| {
+            let policy = if package_roots.contains(&root) {
+                CleanupPolicy::for_package_caches(config)
+            } else {
+                CleanupPolicy::from_config(config)
+            };
+            let previous = index.as_ref().and_then(|index| index.summary(&root));
+            assess(&root, config, &policy, previous)
+        })
+        .collect();
+    
+    caches.sort_by_key(|cache| (cache.score, std::cmp::Reverse(cache.bytes)));
+    caches
+}
+
+/// Walk one cache root and score it
+pub fn assess(root: &Path, config: &ClearModelConfig, policy: &CleanupPolicy, previous: Option<&RootSummary>) -> CacheHealth {
+    let mut health = CacheHealth {
+        path: root.to_path_buf(),
+        score: 100,
+        bytes: 0,
+        files: 0,
+        stale_bytes: 0,
+        incomplete_files: 0,
+        duplicate_bytes: 0,
+        growth_bytes_per_day: None,
+    };
+    let mut copies: HashMap<(String, u64), Vec<FileId>> = HashMap::new();
+    let mut usage = UsageCounter::for_config(config);
+    let now = SystemTime::now();
+    let excludes = Excludes::for_walk(root, config);
+    
+    let walker = WalkDir::new(root)
+        .max_depth(config.security.max_path_depth)
+        .into_iter()
+        .filter_entry(|entry| {
+            if excludes.excludes(entry.path(), entry.file_type().is_dir()) {
+                return false;
+            }
+            entry.file_name().to_str()
+                .map(|name| !config.skip_directories.iter().any(|skip| skip == name))
+                .unwrap_or(true)
+        });
+    
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) if entry.file_type().is_file() => entry,
+            Ok(_) => continue,
+            Err(e) => {
+                debug!("Skipping unreadable entry: {}", e);
+                continue;
+            }
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        
+        let size = metadata.len();
+        let age = policy.age_of(entry.path(), &metadata, now);
+        let facts = FileFacts::from_path(entry.path(), policy, age, size);
+        let used = usage.add(entry.path(), &metadata);
+        
+        health.files += 1;
+        health.bytes += used;
+        if facts.incomplete {
+            health.incomplete_files += 1;
+        }
+        if policy.decide(&facts, &config.python_cache_extensions) == Decision::Expired {
+            health.stale_bytes += used;
+        }
+        
+        if size >= DUPLICATE_MIN_BYTES {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let ids = copies.entry((name, size)).or_default();
+            let id = file_id(&metadata);
+            // Hardlinks share their blocks, so they are not wasted space
+            if id.is_none() || !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    
+    health.duplicate_bytes = copies.iter()
+        .map(|((_, size), ids)| size * (ids.len() as u64 - 1))
+        .sum();
+    health.growth_bytes_per_day = previous.and_then(|previous| growth_per_day(previous.size(config.size_basis), previous.refreshed_at, health.bytes, now));
+    health.compute_score();
+    health
+}
+
+type FileId = Option<(u64, u64)>;
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> FileId {
+    None
+}
+
+fn growth_per_day(previous: u64, refreshed_at: u64, bytes: u64, now: SystemTime) -> Option<f64> {
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let elapsed = now.checked_sub(refreshed_at)?;
+    if elapsed < MIN_GROWTH_WINDOW.as_secs() {
+        return None;
+    }
+    
+    let days = elapsed as f64 / 86_400.0;
+    Some((bytes as f64 - previous as f64) / days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_duplicates_and_incomplete_downloads_lower_the_score() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let weights = vec![0u8; 2 * 1024 * 1024];
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("a/model.safetensors"), &weights).unwrap();
+        fs::write(root.join("b/model.safetensors"), &weights).unwrap();
+        fs::write(root.join("b/model.safetensors.incomplete"), b"partial").unwrap();
+        
+        let config = ClearModelConfig::default();
+        let policy = CleanupPolicy::from_config(&config);
+        let previous = RootSummary {
+            bytes: 1024 * 1024,
+            refreshed_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() - 86_400,
+            ..RootSummary::default()
+        };
+        let health = assess(root, &config, &policy, Some(&previous));
+        
+        assert_eq!(health.files, 3);
+        assert_eq!(health.incomplete_files, 1);
+        assert_eq!(health.duplicate_bytes, weights.len() as u64);
+        assert_eq!(health.stale_bytes, 0);
+        assert!(health.growth_bytes_per_day.unwrap() > 0.0);
+        // Duplicates and growth cost their full 25 + 15 points, the incomplete file 20
+        assert_eq!(health.score, 40);
+    }
+}
\ No newline at end of file
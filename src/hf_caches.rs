@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+use crate::config::{ClearModelConfig, HfCacheConfig};
+use crate::errors::Result;
+use crate::hf_repair;
+use crate::hf_revisions;
+use crate::models::ModelRef;
+use crate::plan::CleanupPlan;
+use crate::policy::CleanupPolicy;
+use crate::resource_manager::{CleanupResult, ResourceManager};
+
+/// Instance name given to a cache found through `HF_HUB_CACHE` / `HF_HOME`
+pub const ENV_INSTANCE: &str = "env";
+
+/// One HuggingFace cache, cleaned on its own with its own policy and budget
+#[derive(Debug, Clone)]
+pub struct HfCacheInstance {
+    pub name: String,
+    pub path: PathBuf,
+    pub policy: CleanupPolicy,
+    
+    /// Bytes the cache may hold after cleaning
+    pub budget: Option<u64>,
+}
+
+/// A repository directory (`models--org--name`) inside a hub cache
+#[derive(Debug, Clone)]
+pub struct CachedRepo {
+    pub name: String,
+    pub dir: PathBuf,
+    pub bytes: u64,
+    
+    /// Newest modification time of anything in the repository
+    pub last_modified: SystemTime,
+}
+
+/// A model present in more than one instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedModel {
+    pub name: String,
+    
+    /// Instances holding a copy, with the size of each copy
+    pub copies: Vec<(String, u64)>,
+}
+
+impl SharedModel {
+    /// Bytes that one copy would save
+    pub fn redundant_bytes(&self) -> u64 {
+        let total: u64 = self.copies.iter().map(|(_, bytes)| bytes).sum();
+        let largest = self.copies.iter().map(|(_, bytes)| *bytes).max().unwrap_or(0);
+        total - largest
+    }
+}
+
+/// Configured instances, plus the cache `HF_HUB_CACHE` / `HF_HOME` points at if it is not one of them
+pub fn instances(config: &ClearModelConfig) -> Vec<HfCacheInstance> {
+    let mut instances: Vec<HfCacheInstance> = config.hf_caches.iter()
+        .map(|cache| instance(config, cache))
+        .collect();
+    
+    let from_env = std::env::var_os("HF_HUB_CACHE")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HF_HOME").filter(|value| !value.is_empty()).map(PathBuf::from));
+    if let Some(path) = from_env {
+        let known = instances.iter().any(|instance| same_dir(&instance.path, &path))
+            || config.cache_paths.iter().any(|cache_path| same_dir(cache_path, &path));
+        if !known && path.is_dir() {
+            debug!("Using HuggingFace cache from the environment: {:?}", path);
+            instances.push(HfCacheInstance {
+                name: ENV_INSTANCE.to_string(),
+                path,
+                policy: CleanupPolicy::from_config(config),
+                budget: None,
+            });
+        }
+    }
+    
+    instances
+}
+
+fn instance(config: &ClearModelConfig, cache: &HfCacheConfig) -> HfCacheInstance {
+    let mut policy = CleanupPolicy::from_config(config);
+    if let Some(days) = cache.max_age_days.filter(|_| config.older_than.is_none()) {
+        policy.max_age = std::time::Duration::from_secs(days as u64 * 24 * 3600);
+        policy.extension_max_age.clear();
+    }
+    if cache.keep_revisions.is_some() {
+        policy.keep_revisions = cache.keep_revisions;
+    }
+    
+    HfCacheInstance {
+        name: cache.name.clone(),
+        path: cache.path.clone(),
+        policy,
+        budget: cache.max_size_gb.map(|gb| (gb * 1_073_741_824.0) as u64),
+    }
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+impl HfCacheInstance {
+    /// The hub directory holding the repositories (`<path>/hub` for an `HF_HOME`)
+    pub fn hub_dir(&self) -> PathBuf {
+        let hub = self.path.join("hub");
+        if hf_repair::is_hub_dir(&hub) { hub } else { self.path.clone() }
+    }
+    
+    /// Repositories in the cache, least recently modified first
+    pub fn repos(&self) -> Vec<CachedRepo> {
+        let Ok(entries) = std::fs::read_dir(self.hub_dir()) else {
+            return Vec::new();
+        };
+        
+        let mut repos: Vec<CachedRepo> = entries.flatten()
+            .filter(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+            .filter_map(|entry| {
+                let dir = entry.path();
+                let model = ModelRef::from_path(Path::new(&entry.file_name()))?;
+                let (bytes, last_modified) = repo_usage(&dir);
+                Some(CachedRepo { name: model.name, dir, bytes, last_modified })
+            })
+            .collect();
+        repos.sort_by_key(|repo| repo.last_modified);
+        repos
+    }
+    
+    /// Apply the instance's age policy, prune old revisions, then evict whole repositories until it fits its budget
+    ///
+    /// A plan of the age policy made beforehand is applied instead of walking the cache again.
+    pub async fn clean(&self, resource_manager: &ResourceManager, plan: Option<CleanupPlan>, dry_run: bool) -> Result<Vec<CleanupResult>> {
+        info!("Cleaning HuggingFace cache {:?} at {:?}", self.name, self.path);
+        let mut results = match plan {
+            Some(plan) => resource_manager.apply(plan).await?,
+            None => resource_manager.clean_paths(std::slice::from_ref(&self.path), &self.policy, dry_run).await?,
+        };
+        results.extend(hf_revisions::prune(resource_manager, &self.path, &self.policy, dry_run).await?);
+        
+        let Some(budget) = self.budget else {
+            return Ok(results);
+        };
+        
+        let repos = self.repos();
+        let mut total: u64 = repos.iter().map(|repo| repo.bytes).sum();
+        if dry_run {
+            // Nothing was removed, so discount what the age policy would have freed
+            total = total.saturating_sub(results.iter().map(|result| result.bytes_freed).sum());
+        }
+        
+        // Removing everything in a repository goes through the usual pipeline, so pins,
+        // the journal and quarantine still apply
+        let evict = CleanupPolicy {
+            max_age: std::time::Duration::ZERO,
+            extension_max_age: Default::default(),
+            ..self.policy.clone()
+        };
+        for repo in repos {
+            if total <= budget {
+                break;
+            }
+            if self.policy.pinned.protects(&repo.dir) {
+                debug!("Keeping pinned repository {} over budget", repo.name);
+                continue;
+            }
+            
+            info!(
+                "HuggingFace cache {:?} is {:.2} GB over its budget, evicting {} ({:.2} GB)",
+                self.name,
+                (total - budget) as f64 / 1_073_741_824.0,
+                repo.name,
+                repo.bytes as f64 / 1_073_741_824.0
+            );
+            let evicted = resource_manager.clean_paths(std::slice::from_ref(&repo.dir), &evict, dry_run).await?;
+            total = total.saturating_sub(evicted.iter().map(|result| result.bytes_freed).sum());
+            results.extend(evicted);
+        }
+        
+        Ok(results)
+    }
+}
+
+/// Bytes held by a repository and its newest modification time; links are not followed
+fn repo_usage(dir: &Path) -> (u64, SystemTime) {
+    let mut bytes = 0;
+    let mut last_modified = SystemTime::UNIX_EPOCH;
+    
+    for entry in WalkDir::new(dir).into_iter().flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            bytes += metadata.len();
+        }
+        if let Ok(modified) = metadata.modified() {
+            last_modified = last_modified.max(modified);
+        }
+    }
+    
+    (bytes, last_modified)
+}
+
+/// Models cached in more than one instance, largest waste first
+pub fn shared_models(instances: &[HfCacheInstance]) -> Vec<SharedModel> {
+    let mut copies: BTreeMap<String, Vec<(String, u64)>> = BTreeMap::new();
+    for instance in instances {
+        for repo in instance.repos() {
+            copies.entry(repo.name).or_default().push((instance.name.clone(), repo.bytes));
+        }
+    }
+    
+    let mut shared: Vec<SharedModel> = copies.into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .map(|(name, copies)| SharedModel { name, copies })
+        .collect();
+    shared.sort_by_key(|model| std::cmp::Reverse(model.redundant_bytes()));
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    fn cache_with(root: &Path, repos: &[(&str, usize)]) {
+        for (repo, size) in repos {
+            let blobs = root.join("hub").join(repo).join("blobs");
+            fs::create_dir_all(&blobs).unwrap();
+            fs::write(blobs.join("abc"), vec![0u8; *size]).unwrap();
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_budget_evicts_oldest_repos_and_shared_models_are_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let (home, scratch) = (temp_dir.path().join("huggingface"), temp_dir.path().join("scratch-hf"));
+        cache_with(&home, &[("models--org--old", 3000), ("models--org--new", 2000)]);
+        cache_with(&scratch, &[("models--org--new", 2000)]);
+        
+        let old_blob = home.join("hub/models--org--old/blobs/abc");
+        let long_ago = filetime::FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_mtime(&old_blob, long_ago).unwrap();
+        filetime::set_file_mtime(home.join("hub/models--org--old/blobs"), long_ago).unwrap();
+        filetime::set_file_mtime(home.join("hub/models--org--old"), long_ago).unwrap();
+        
+        let config = ClearModelConfig {
+            cache_paths: Vec::new(),
+            max_cache_age_days: 100_000,
+            hf_caches: vec![
+                HfCacheConfig { name: "home".to_string(), path: home.clone(), max_age_days: None, max_size_gb: Some(2500.0 / 1_073_741_824.0), keep_revisions: None },
+                HfCacheConfig { name: "scratch".to_string(), path: scratch.clone(), max_age_days: None, max_size_gb: None, keep_revisions: None },
+            ],
+            ..ClearModelConfig::default()
+        };
+        let instances = instances(&config);
+        assert_eq!(instances[0].hub_dir(), home.join("hub"));
+        
+        let shared = shared_models(&instances);
+        assert_eq!(shared, vec![SharedModel {
+            name: "org/new".to_string(),
+            copies: vec![("home".to_string(), 2000), ("scratch".to_string(), 2000)],
+        }]);
+        assert_eq!(shared[0].redundant_bytes(), 2000);
+        
+        let resource_manager = ResourceManager::new(config).await.unwrap();
+        let results = instances[0].clean(&resource_manager, None, false).await.unwrap();
+        assert_eq!(results.iter().map(|result| result.bytes_freed).sum::<u64>(), 3000);
+        assert!(!old_blob.exists());
+        assert!(home.join("hub/models--org--new/blobs/abc").exists());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,169 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use crate::config::ClearModelConfig;
+
+/// The `datasets` library's cache: `$HF_DATASETS_CACHE`, else `datasets` in `$HF_HOME`,
+/// else `~/.cache/huggingface/datasets`
+pub fn datasets_dir() -> Option<PathBuf> {
+    let env_dir = |var: &str| std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from);
+    env_dir("HF_DATASETS_CACHE")
+        .or_else(|| env_dir("HF_HOME").map(|home| home.join("datasets")))
+        .or_else(|| {
+            let cache = env_dir("XDG_CACHE_HOME").or_else(|| home::home_dir().map(|home| home.join(".cache")))?;
+            Some(cache.join("huggingface").join("datasets"))
+        })
+}
+
+/// Datasets caches among the configured cache paths (directly, or `datasets` inside an
+/// `HF_HOME`), plus the one the environment points at
+pub fn caches(config: &ClearModelConfig) -> Vec<PathBuf> {
+    let mut caches: Vec<PathBuf> = Vec::new();
+    let configured = config.existing_cache_paths().into_iter()
+        .map(|path| if path.ends_with("datasets") { path.clone() } else { path.join("datasets") });
+    for dir in configured.chain(datasets_dir()) {
+        let Ok(dir) = dir.canonicalize() else {
+            continue;
+        };
+        if dir.is_dir() && !caches.contains(&dir) {
+            caches.push(dir);
+        }
+    }
+    caches
+}
+
+/// One prepared copy of a dataset: its Arrow shards and metadata for a config and version
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetBuild {
+    pub config: String,
+    pub version: String,
+    pub fingerprint: String,
+    pub bytes: u64,
+    pub shards: usize,
+    pub dir: PathBuf,
+}
+
+/// A dataset in the cache with all of its builds
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedDataset {
+    pub name: String,
+    pub bytes: u64,
+    pub builds: Vec<DatasetBuild>,
+}
+
+/// Dataset name and build directory of a file inside a datasets cache
+///
+/// Prepared datasets are stored as `datasets/<name>/<config>/<version>/<fingerprint>/...`,
+/// with `___` standing for the `/` of namespaced names (`rajpurkar___squad`). Raw
+/// downloads (`datasets/downloads`) belong to no dataset.
+pub fn locate(path: &Path) -> Option<(String, PathBuf)> {
+    let components: Vec<Component> = path.components().collect();
+    let index = components.iter().rposition(|component| *component == Component::Normal("datasets".as_ref()))?;
+    let [name, _config, version, _fingerprint, _file, ..] = components.get(index + 1..)? else {
+        return None;
+    };
+    let (Component::Normal(name), Component::Normal(version)) = (name, version) else {
+        return None;
+    };
+    let (name, version) = (name.to_str()?, version.to_str()?);
+    if name == "downloads" || !is_version(version) {
+        return None;
+    }
+    
+    let build: PathBuf = components[..index + 5].iter().collect();
+    Some((name.replace("___", "/"), build))
+}
+
+/// The directory holding every build of the dataset a file belongs to (`datasets/<name>`)
+pub fn dataset_dir(path: &Path) -> Option<PathBuf> {
+    let (_, build) = locate(path)?;
+    build.ancestors().nth(3).map(Path::to_path_buf)
+}
+
+/// Every dataset under a datasets cache, largest first
+pub fn datasets(root: &Path) -> Vec<CachedDataset> {
+    let mut builds: BTreeMap<(String, PathBuf), DatasetBuild> = BTreeMap::new();
+    for entry in WalkDir::new(root).into_iter().flatten().filter(|entry| entry.file_type().is_file()) {
+        let Some((name, dir)) = locate(entry.path()) else {
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let build = builds.entry((name, dir.clone())).or_insert_with(|| {
+            let part = |up: usize| dir.ancestors().nth(up)
+                .and_then(|ancestor| ancestor.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            DatasetBuild { config: part(2), version: part(1), fingerprint: part(0), bytes: 0, shards: 0, dir: dir.clone() }
+        });
+        build.bytes += metadata.len();
+        if entry.path().extension().is_some_and(|ext| ext == "arrow") {
+            build.shards += 1;
+        }
+    }
+    
+    let mut datasets: BTreeMap<String, CachedDataset> = BTreeMap::new();
+    for ((name, _), build) in builds {
+        let dataset = datasets.entry(name.clone())
+            .or_insert_with(|| CachedDataset { name, bytes: 0, builds: Vec::new() });
+        dataset.bytes += build.bytes;
+        dataset.builds.push(build);
+    }
+    let mut datasets: Vec<CachedDataset> = datasets.into_values().collect();
+    datasets.sort_by_key(|dataset| std::cmp::Reverse(dataset.bytes));
+    datasets
+}
+
+/// Latest of `timestamp` over the files of a dataset build
+pub fn build_last_used(build: &Path, timestamp: impl Fn(&Path, &std::fs::Metadata) -> Option<SystemTime>) -> Option<SystemTime> {
+    WalkDir::new(build)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| timestamp(entry.path(), &entry.metadata().ok()?))
+        .max()
+}
+
+/// Dataset versions are `major.minor.patch`
+fn is_version(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('.').collect();
+    parts.len() == 3 && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_dataset_builds_are_found_with_their_shards() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("datasets");
+        let squad = root.join("rajpurkar___squad/plain_text/0.0.0/7b6d24c4");
+        fs::create_dir_all(&squad).unwrap();
+        fs::write(squad.join("squad-train.arrow"), vec![0u8; 300]).unwrap();
+        fs::write(squad.join("squad-validation.arrow"), vec![0u8; 100]).unwrap();
+        fs::write(squad.join("dataset_info.json"), b"{}").unwrap();
+        let glue = root.join("glue/mrpc/1.0.0/dacbe312");
+        fs::create_dir_all(&glue).unwrap();
+        fs::write(glue.join("glue-train.arrow"), vec![0u8; 50]).unwrap();
+        fs::create_dir_all(root.join("downloads/extracted")).unwrap();
+        fs::write(root.join("downloads/3c1f2a"), vec![0u8; 1000]).unwrap();
+        
+        assert_eq!(locate(&squad.join("squad-train.arrow")), Some(("rajpurkar/squad".to_string(), squad.clone())));
+        assert_eq!(dataset_dir(&squad.join("squad-train.arrow")), Some(root.join("rajpurkar___squad")));
+        assert_eq!(locate(&root.join("downloads/3c1f2a")), None);
+        assert_eq!(locate(Path::new("/home/u/.keras/datasets/cifar-10-batches-py/data_batch_1")), None);
+        
+        let found = datasets(&root);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].name.as_str(), found[0].bytes), ("rajpurkar/squad", 402));
+        let build = &found[0].builds[0];
+        assert_eq!((build.config.as_str(), build.version.as_str(), build.shards), ("plain_text", "0.0.0", 2));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,310 @@
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::command_runner::CommandRunner;
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+
+/// Repository kinds in the HuggingFace hub cache, by directory prefix
+const REPO_TYPES: [(&str, &str); 3] = [("models", "model"), ("datasets", "dataset"), ("spaces", "space")];
+
+/// A snapshot whose file links point at blobs that no longer exist
+#[derive(Debug, Clone)]
+pub struct BrokenSnapshot {
+    /// Hub cache the repository lives in
+    pub hub_dir: PathBuf,
+    
+    /// `model`, `dataset` or `space`
+    pub repo_type: &'static str,
+    
+    /// Repository id, e.g. `meta-llama/Llama-3-8B`
+    pub repo_id: String,
+    
+    /// Commit hash the snapshot was checked out at
+    pub commit: String,
+    
+    pub snapshot_dir: PathBuf,
+    
+    /// Links whose blob is missing, relative to the snapshot
+    pub missing: Vec<PathBuf>,
+    
+    /// Every file link in the snapshot
+    pub links: usize,
+}
+
+/// What to do about broken snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairAction {
+    /// Delete the snapshot's links and any refs pointing at it; blobs are untouched
+    RemoveSnapshot,
+    
+    /// Download the snapshot's revision again with `huggingface-cli`
+    Refetch,
+}
+
+/// HuggingFace hub caches among the configured cache paths (plus `HF_HUB_CACHE` / `HF_HOME`)
+pub fn hub_dirs(config: &ClearModelConfig) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    for path in config.existing_cache_paths() {
+        candidates.push(path.clone());
+        candidates.push(path.join("hub"));
+    }
+    if let Some(hub) = std::env::var_os("HF_HUB_CACHE").filter(|value| !value.is_empty()) {
+        candidates.push(PathBuf::from(hub));
+    }
+    if let Some(home) = std::env::var_os("HF_HOME").filter(|value| !value.is_empty()) {
+        candidates.push(PathBuf::from(home).join("hub"));
+    }
+    
+    let mut hubs: Vec<PathBuf> = candidates.into_iter()
+        .filter(|candidate| is_hub_dir(candidate))
+        .filter_map(|candidate| candidate.canonicalize().ok())
+        .collect();
+    hubs.sort();
+    hubs.dedup();
+    hubs
+}
+
+pub(crate) fn is_hub_dir(path: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return false;
+    };
+    
+    entries.flatten().any(|entry| {
+        entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false)
+            && entry.file_name().to_str().and_then(parse_repo_dir).is_some()
+    })
+}
+
+/// `models--org--name` -> (`model`, `org/name`)
+fn parse_repo_dir(name: &str) -> Option<(&'static str, String)> {
+    let (prefix, repo) = name.split_once("--")?;
+    let (_, repo_type) = REPO_TYPES.iter().find(|(dir_prefix, _)| *dir_prefix == prefix)?;
+    Some((repo_type, repo.replace("--", "/")))
+}
+
+/// Find snapshots in a hub cache whose links point at missing blobs
+pub fn find_broken_snapshots(hub_dir: &Path) -> Result<Vec<BrokenSnapshot>> {
+    let entries = std::fs::read_dir(hub_dir)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to read HuggingFace cache: {}", e),
+            Some(hub_dir.to_path_buf())
+        ))?;
+    
+    let mut broken = Vec::new();
+    for entry in entries.flatten() {
+        let Some((repo_type, repo_id)) = entry.file_name().to_str().and_then(parse_repo_dir) else {
+            continue;
+        };
+        
+        let Ok(snapshots) = std::fs::read_dir(entry.path().join("snapshots")) else {
+            continue;
+        };
+        for snapshot in snapshots.flatten() {
+            let snapshot_dir = snapshot.path();
+            if !snapshot.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false) {
+                continue;
+            }
+            
+            let (links, missing) = check_snapshot(&snapshot_dir);
+            if missing.is_empty() {
+                continue;
+            }
+            
+            debug!("{} of {} links broken in {:?}", missing.len(), links, snapshot_dir);
+            broken.push(BrokenSnapshot {
+                hub_dir: hub_dir.to_path_buf(),
+                repo_type,
+                repo_id: repo_id.clone(),
+                commit: snapshot.file_name().to_string_lossy().into_owned(),
+                snapshot_dir,
+                missing,
+                links,
+            });
+        }
+    }
+    
+    broken.sort_by(|a, b| a.repo_id.cmp(&b.repo_id).then_with(|| a.commit.cmp(&b.commit)));
+    Ok(broken)
+}
+
+/// Count a snapshot's file links and collect those whose target is gone
+fn check_snapshot(snapshot_dir: &Path) -> (usize, Vec<PathBuf>) {
+    let mut links = 0;
+    let mut missing = Vec::new();
+    
+    for entry in WalkDir::new(snapshot_dir).into_iter().flatten() {
+        if !entry.path_is_symlink() {
+            continue;
+        }
+        
+        links += 1;
+        // metadata() follows the link, so it fails exactly when the blob is gone
+        if std::fs::metadata(entry.path()).is_err() {
+            let relative = entry.path().strip_prefix(snapshot_dir).unwrap_or(entry.path());
+            missing.push(relative.to_path_buf());
+        }
+    }
+    
+    (links, missing)
+}
+
+/// Remove a directory that holds nothing but links (a snapshot, or a repository whose blobs are gone)
+///
+/// Regular files mean this is not the layout we expect, so nothing is removed then.
+pub(crate) fn remove_link_tree(dir: &Path) -> Result<()> {
+    let has_files = WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .any(|entry| entry.file_type().is_file());
+    if has_files {
+        return Err(ClearModelError::security(format!(
+            "Refusing to remove {:?}: it contains regular files, not just links",
+            dir
+        )));
+    }
+    
+    std::fs::remove_dir_all(dir)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to remove directory: {}", e),
+            Some(dir.to_path_buf())
+        ))
+}
+
+impl BrokenSnapshot {
+    /// Repository directory, e.g. `<hub>/models--org--name`
+    fn repo_dir(&self) -> &Path {
+        self.snapshot_dir.parent()
+            .and_then(Path::parent)
+            .unwrap_or(&self.hub_dir)
+    }
+    
+    /// Apply a repair, or only describe it in a dry run
+    pub async fn repair(&self, action: RepairAction, commands: &CommandRunner, dry_run: bool) -> Result<()> {
+        match action {
+            RepairAction::RemoveSnapshot => self.remove(dry_run),
+            RepairAction::Refetch => self.refetch(commands, dry_run).await,
+        }
+    }
+    
+    fn remove(&self, dry_run: bool) -> Result<()> {
+        let refs = self.refs_to_commit();
+        if dry_run {
+            info!("Would remove snapshot {:?} and {} refs", self.snapshot_dir, refs.len());
+            return Ok(());
+        }
+        
+        remove_link_tree(&self.snapshot_dir)?;
+        for reference in refs {
+            if let Err(e) = std::fs::remove_file(&reference) {
+                warn!("Failed to remove ref {:?}: {}", reference, e);
+            }
+        }
+        
+        info!("Removed broken snapshot {} of {}", self.commit, self.repo_id);
+        Ok(())
+    }
+    
+    /// Ref files (e.g. `refs/main`) that point at this snapshot's commit
+    fn refs_to_commit(&self) -> Vec<PathBuf> {
+        WalkDir::new(self.repo_dir().join("refs"))
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                std::fs::read_to_string(entry.path())
+                    .map(|content| content.trim() == self.commit)
+                    .unwrap_or(false)
+            })
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+    
+    async fn refetch(&self, commands: &CommandRunner, dry_run: bool) -> Result<()> {
+        let args = [
+            "download".to_string(),
+            self.repo_id.clone(),
+            "--revision".to_string(),
+            self.commit.clone(),
+            "--repo-type".to_string(),
+            self.repo_type.to_string(),
+            "--cache-dir".to_string(),
+            self.hub_dir.display().to_string(),
+        ];
+        
+        commands.check("huggingface-cli")?;
+        if dry_run {
+            info!("Would run: huggingface-cli {}", args.join(" "));
+            return Ok(());
+        }
+        
+        // Missing blobs are downloaded again and the dangling links repointed at them
+        let record = commands.run("huggingface-cli", &args).await
+            .map_err(|e| match e {
+                ClearModelError::Environment { .. } => e.with_hint("Install huggingface_hub to re-fetch"),
+                e => e,
+            })?;
+        
+        if !record.success() {
+            return Err(ClearModelError::environment(format!(
+                "huggingface-cli download failed for {}: {}",
+                self.repo_id,
+                record.stderr.trim()
+            )));
+        }
+        
+        let (_, missing) = check_snapshot(&self.snapshot_dir);
+        if !missing.is_empty() {
+            return Err(ClearModelError::cache(format!(
+                "{} links in {:?} are still broken after re-fetching",
+                missing.len(),
+                self.snapshot_dir
+            )));
+        }
+        
+        info!("Re-fetched snapshot {} of {}", self.commit, self.repo_id);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+    
+    #[tokio::test]
+    async fn test_broken_snapshot_is_found_and_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        let hub = temp_dir.path();
+        let repo = hub.join("models--org--model");
+        let good = repo.join("snapshots/aaa");
+        let bad = repo.join("snapshots/bbb");
+        fs::create_dir_all(repo.join("blobs")).unwrap();
+        fs::create_dir_all(repo.join("refs")).unwrap();
+        fs::create_dir_all(&good).unwrap();
+        fs::create_dir_all(bad.join("sub")).unwrap();
+        fs::write(repo.join("blobs/1111"), b"config").unwrap();
+        fs::write(repo.join("refs/main"), "bbb").unwrap();
+        
+        symlink("../../blobs/1111", good.join("config.json")).unwrap();
+        symlink("../../blobs/1111", bad.join("config.json")).unwrap();
+        symlink("../../../blobs/2222", bad.join("sub/model.safetensors")).unwrap();
+        
+        let broken = find_broken_snapshots(hub).unwrap();
+        assert_eq!(broken.len(), 1);
+        let snapshot = &broken[0];
+        assert_eq!((snapshot.repo_type, snapshot.repo_id.as_str(), snapshot.commit.as_str()), ("model", "org/model", "bbb"));
+        assert_eq!(snapshot.missing, vec![PathBuf::from("sub/model.safetensors")]);
+        assert_eq!(snapshot.links, 2);
+        
+        let commands = CommandRunner::new(&Default::default());
+        snapshot.repair(RepairAction::RemoveSnapshot, &commands, false).await.unwrap();
+        assert!(!bad.exists());
+        assert!(!repo.join("refs/main").exists());
+        assert!(good.join("config.json").exists());
+        assert!(repo.join("blobs/1111").exists());
+    }
+}
\ No newline at end of file
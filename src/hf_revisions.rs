@@ -0,0 +1,358 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::categories;
+use crate::config::ClearModelConfig;
+use crate::errors::Result;
+use crate::hf_caches;
+use crate::hf_repair;
+use crate::models::ModelRef;
+use crate::plan::{CleanupPlan, PlannedDirectory, PlannedFile};
+use crate::policy::CleanupPolicy;
+use crate::resource_manager::{CleanupResult, ResourceManager};
+
+/// One snapshot revision of a repository in a hub cache
+#[derive(Debug, Clone, Serialize)]
+pub struct Revision {
+    /// Commit hash the snapshot was checked out at
+    pub commit: String,
+    
+    /// Refs (e.g. `main`) pointing at the commit
+    pub refs: Vec<String>,
+    
+    /// Bytes of every blob the snapshot links to
+    pub bytes: u64,
+    
+    /// Bytes of the blobs no other revision links to, freed when this one is pruned
+    pub exclusive_bytes: u64,
+    
+    /// Days since the snapshot was last modified
+    pub age_days: u64,
+    
+    #[serde(skip)]
+    pub snapshot_dir: PathBuf,
+    
+    #[serde(skip)]
+    pub modified: SystemTime,
+    
+    #[serde(skip)]
+    blobs: Vec<PathBuf>,
+}
+
+/// A repository's revisions, those a ref points at first, then most recently modified first
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoRevisions {
+    pub name: String,
+    pub dir: PathBuf,
+    pub revisions: Vec<Revision>,
+}
+
+impl RepoRevisions {
+    /// How many leading revisions keeping `keep` of them retains; referenced ones always stay
+    pub fn kept(&self, keep: usize) -> usize {
+        let referenced = self.revisions.iter().filter(|revision| !revision.refs.is_empty()).count();
+        keep.max(referenced).min(self.revisions.len())
+    }
+}
+
+/// What keeping only the newest revisions of the repositories in a hub cache removes
+#[derive(Debug, Default)]
+pub struct RevisionPrune {
+    /// Snapshot directories of the pruned revisions; they only hold links
+    pub snapshots: Vec<PathBuf>,
+    
+    /// Blobs no kept revision links to, removed through the usual pipeline
+    pub blobs: PlannedDirectory,
+}
+
+/// Repositories with snapshots in a hub cache, by name
+pub fn hub_repos(hub_dir: &Path) -> Vec<RepoRevisions> {
+    let Ok(entries) = std::fs::read_dir(hub_dir) else {
+        return Vec::new();
+    };
+    
+    let mut repos: Vec<RepoRevisions> = entries.flatten()
+        .filter(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+        .filter_map(|entry| {
+            let model = ModelRef::from_path(Path::new(&entry.file_name()))?;
+            let revisions = repo_revisions(&entry.path());
+            (!revisions.is_empty()).then(|| RepoRevisions { name: model.name, dir: entry.path(), revisions })
+        })
+        .collect();
+    repos.sort_by(|a, b| a.name.cmp(&b.name));
+    repos
+}
+
+/// Snapshots of one repository with the blobs they link to, referenced ones first, then newest first
+fn repo_revisions(repo_dir: &Path) -> Vec<Revision> {
+    let Ok(snapshots) = std::fs::read_dir(repo_dir.join("snapshots")) else {
+        return Vec::new();
+    };
+    let refs = refs_by_commit(repo_dir);
+    let blobs_dir = repo_dir.join("blobs").canonicalize().unwrap_or_else(|_| repo_dir.join("blobs"));
+    let now = SystemTime::now();
+    
+    let mut revisions: Vec<Revision> = snapshots.flatten()
+        .filter(|snapshot| snapshot.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+        .map(|snapshot| {
+            let snapshot_dir = snapshot.path();
+            let commit = snapshot.file_name().to_string_lossy().into_owned();
+            let modified = snapshot.metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            
+            // Links resolve into blobs/; dangling ones and anything outside are not counted
+            let mut blobs: Vec<PathBuf> = WalkDir::new(&snapshot_dir)
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.path_is_symlink())
+                .filter_map(|entry| entry.path().canonicalize().ok())
+                .filter(|blob| blob.starts_with(&blobs_dir))
+                .collect();
+            blobs.sort();
+            blobs.dedup();
+            
+            Revision {
+                refs: refs.get(&commit).cloned().unwrap_or_default(),
+                commit,
+                bytes: 0,
+                exclusive_bytes: 0,
+                age_days: now.duration_since(modified).map(|age| age.as_secs() / 86_400).unwrap_or(0),
+                snapshot_dir,
+                modified,
+                blobs,
+            }
+        })
+        .collect();
+    
+    let mut links: HashMap<&Path, usize> = HashMap::new();
+    for blob in revisions.iter().flat_map(|revision| &revision.blobs) {
+        *links.entry(blob.as_path()).or_default() += 1;
+    }
+    let sizes: HashMap<PathBuf, (u64, bool)> = links.into_iter()
+        .map(|(blob, count)| {
+            let size = std::fs::metadata(blob).map(|metadata| metadata.len()).unwrap_or(0);
+            (blob.to_path_buf(), (size, count == 1))
+        })
+        .collect();
+    for revision in &mut revisions {
+        for (size, exclusive) in revision.blobs.iter().filter_map(|blob| sizes.get(blob)) {
+            revision.bytes += size;
+            if *exclusive {
+                revision.exclusive_bytes += size;
+            }
+        }
+    }
+    
+    // A snapshot's mtime changes when it is touched, so refs decide what is current
+    revisions.sort_by(|a, b| {
+        a.refs.is_empty().cmp(&b.refs.is_empty())
+            .then_with(|| b.modified.cmp(&a.modified))
+            .then_with(|| a.commit.cmp(&b.commit))
+    });
+    revisions
+}
+
+/// Ref names (`main`, `refs/pr/1`) by the commit they point at
+fn refs_by_commit(repo_dir: &Path) -> BTreeMap<String, Vec<String>> {
+    let refs_dir = repo_dir.join("refs");
+    let mut refs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    
+    for entry in WalkDir::new(&refs_dir).into_iter().flatten().filter(|entry| entry.file_type().is_file()) {
+        let Ok(commit) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let name = entry.path().strip_prefix(&refs_dir).unwrap_or(entry.path()).to_string_lossy().into_owned();
+        refs.entry(commit.trim().to_string()).or_default().push(name);
+    }
+    refs
+}
+
+/// The hub directory inside a cache root, if it holds one
+pub fn hub_dir(root: &Path) -> Option<PathBuf> {
+    let hub = root.join("hub");
+    if hf_repair::is_hub_dir(&hub) {
+        Some(hub)
+    } else if hf_repair::is_hub_dir(root) {
+        Some(root.to_path_buf())
+    } else {
+        None
+    }
+}
+
+/// Hub caches to show in `scan --revisions`, with the number of revisions each keeps
+pub fn hubs(config: &ClearModelConfig) -> Vec<(PathBuf, Option<usize>)> {
+    let mut hubs: Vec<(PathBuf, Option<usize>)> = hf_caches::instances(config)
+        .iter()
+        .filter_map(|instance| {
+            let hub = hub_dir(&instance.path)?.canonicalize().ok()?;
+            Some((hub, instance.policy.keep_revisions))
+        })
+        .collect();
+    for hub in hf_repair::hub_dirs(config) {
+        if !hubs.iter().any(|(known, _)| *known == hub) {
+            hubs.push((hub, config.keep_revisions));
+        }
+    }
+    hubs
+}
+
+/// Everything beyond the newest `keep` revisions of each unpinned repository in a hub cache
+///
+/// Revisions a ref points at are always kept and fill the `keep` slots first. Blobs a kept
+/// revision still links to are never planned, so shared weights survive.
+pub fn plan(hub_dir: &Path, keep: usize, policy: &CleanupPolicy) -> RevisionPrune {
+    let mut prune = RevisionPrune {
+        blobs: PlannedDirectory { root: hub_dir.to_path_buf(), ..PlannedDirectory::default() },
+        ..RevisionPrune::default()
+    };
+    
+    for repo in hub_repos(hub_dir) {
+        let kept = repo.kept(keep);
+        if repo.revisions.len() <= kept {
+            continue;
+        }
+        if policy.pinned.matches(&repo.name) {
+            debug!("Keeping every revision of pinned repository {}", repo.name);
+            continue;
+        }
+        
+        let (kept, pruned) = repo.revisions.split_at(kept);
+        let kept_blobs: Vec<&PathBuf> = kept.iter().flat_map(|revision| &revision.blobs).collect();
+        let mut blobs: Vec<&PathBuf> = pruned.iter()
+            .flat_map(|revision| &revision.blobs)
+            .filter(|blob| !kept_blobs.contains(blob))
+            .collect();
+        blobs.sort();
+        blobs.dedup();
+        
+        for blob in blobs {
+            let Ok(metadata) = std::fs::metadata(blob) else {
+                continue;
+            };
+            categories::record(&mut prune.blobs.categories, blob, metadata.len(), false);
+            prune.blobs.files.push(PlannedFile {
+                path: blob.clone(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+                rule: "unreferenced_revision",
+            });
+        }
+        for revision in pruned {
+            debug!("Pruning revision {} of {} ({} days old)", revision.commit, repo.name, revision.age_days);
+            prune.snapshots.push(revision.snapshot_dir.clone());
+        }
+    }
+    
+    prune
+}
+
+/// Keep only the newest `policy.keep_revisions` revisions in the hub cache under `root`
+///
+/// Runs after the age policy; nothing happens when the policy keeps every revision or
+/// `root` is not a HuggingFace cache.
+pub async fn prune(resource_manager: &ResourceManager, root: &Path, policy: &CleanupPolicy, dry_run: bool) -> Result<Vec<CleanupResult>> {
+    let (Some(keep), Some(hub)) = (policy.keep_revisions, hub_dir(root)) else {
+        return Ok(Vec::new());
+    };
+    
+    let started = Instant::now();
+    let prune = plan(&hub, keep, policy);
+    if prune.snapshots.is_empty() {
+        return Ok(Vec::new());
+    }
+    info!(
+        "Keeping the newest {} revisions in {:?}: pruning {} snapshots, {:.2} MB of blobs",
+        keep,
+        hub,
+        prune.snapshots.len(),
+        prune.blobs.bytes() as f64 / 1_048_576.0
+    );
+    
+    if dry_run {
+        for snapshot in &prune.snapshots {
+            info!("Would remove snapshot {:?}", snapshot);
+        }
+        return Ok(vec![CleanupResult::preview(prune.blobs, started.elapsed())]);
+    }
+    
+    // Links go first, so no kept snapshot is ever left pointing at a removed blob
+    let mut errors = Vec::new();
+    for snapshot in &prune.snapshots {
+        if let Err(e) = resource_manager.check_deletable(snapshot).and_then(|()| hf_repair::remove_link_tree(snapshot)) {
+            warn!("{}", e);
+            errors.push(e.to_string());
+        }
+    }
+    
+    let mut results = resource_manager.apply(CleanupPlan { directories: vec![prune.blobs] }).await?;
+    if let Some(result) = results.first_mut() {
+        result.errors.extend(errors);
+    }
+    Ok(results)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+    
+    #[tokio::test]
+    async fn test_unreferenced_revisions_and_their_own_blobs_are_pruned() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("huggingface");
+        let repo = cache.join("hub/models--org--model");
+        fs::create_dir_all(repo.join("blobs")).unwrap();
+        fs::create_dir_all(repo.join("refs")).unwrap();
+        fs::write(repo.join("blobs/config"), b"shared").unwrap();
+        fs::write(repo.join("blobs/old-weights"), vec![0u8; 3000]).unwrap();
+        fs::write(repo.join("blobs/new-weights"), vec![0u8; 2000]).unwrap();
+        fs::write(repo.join("blobs/tagged-weights"), vec![0u8; 1000]).unwrap();
+        fs::write(repo.join("refs/main"), "bbb").unwrap();
+        fs::write(repo.join("refs/v1"), "ccc\n").unwrap();
+        
+        // The unreferenced snapshot was touched last, which must not save it over `main`
+        let snapshots = [
+            ("aaa", "old-weights", 1_200_000_000),
+            ("bbb", "new-weights", 1_100_000_000),
+            ("ccc", "tagged-weights", 1_000_000_000),
+        ];
+        for (commit, weights, unix_time) in snapshots {
+            let snapshot = repo.join("snapshots").join(commit);
+            fs::create_dir_all(&snapshot).unwrap();
+            symlink("../../blobs/config", snapshot.join("config.json")).unwrap();
+            symlink(format!("../../blobs/{}", weights), snapshot.join("model.safetensors")).unwrap();
+            filetime::set_file_mtime(&snapshot, filetime::FileTime::from_unix_time(unix_time, 0)).unwrap();
+        }
+        
+        let repos = hub_repos(&cache.join("hub"));
+        assert_eq!(repos[0].name, "org/model");
+        let revisions = &repos[0].revisions;
+        assert_eq!(revisions.iter().map(|revision| revision.commit.as_str()).collect::<Vec<_>>(), ["bbb", "ccc", "aaa"]);
+        assert_eq!(revisions[1].refs, ["v1"]);
+        assert_eq!((revisions[2].bytes, revisions[2].exclusive_bytes), (3006, 3000));
+        assert_eq!(repos[0].kept(1), 2);
+        
+        let config = ClearModelConfig {
+            cache_paths: vec![cache.clone()],
+            keep_revisions: Some(1),
+            ..ClearModelConfig::default()
+        };
+        let policy = CleanupPolicy::from_config(&config);
+        let resource_manager = ResourceManager::new(config).await.unwrap();
+        let results = prune(&resource_manager, &cache, &policy, false).await.unwrap();
+        assert_eq!(results[0].bytes_freed, 3000);
+        assert!(!repo.join("snapshots/aaa").exists());
+        assert!(!repo.join("blobs/old-weights").exists());
+        assert!(repo.join("blobs/config").exists());
+        assert!(repo.join("snapshots/bbb/model.safetensors").exists());
+        assert!(repo.join("snapshots/ccc/model.safetensors").exists());
+        assert!(repo.join("refs/v1").exists());
+    }
+}
@@ -0,0 +1,419 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::errors::{ClearModelError, Result};
+use crate::report::RunReport;
+use crate::state;
+
+/// Bumped whenever the schema changes
+const SCHEMA_VERSION: i64 = 2;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        command TEXT NOT NULL,
+        dry_run INTEGER NOT NULL,
+        succeeded INTEGER NOT NULL,
+        error TEXT,
+        started_at INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        files_removed INTEGER NOT NULL,
+        bytes_freed INTEGER NOT NULL,
+        errors INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS results (
+        run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        files_removed INTEGER NOT NULL,
+        bytes_freed INTEGER NOT NULL,
+        errors INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS usage (
+        run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        bytes INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS opens (
+        path TEXT PRIMARY KEY,
+        opens INTEGER NOT NULL,
+        last_open INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS results_run ON results(run_id);
+    CREATE INDEX IF NOT EXISTS usage_run ON usage(run_id);
+";
+
+/// One past run, as listed by `clearmodel history`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunSummary {
+    pub id: i64,
+    pub command: String,
+    pub dry_run: bool,
+    pub succeeded: bool,
+    pub error: Option<String>,
+    
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub files_removed: u64,
+    pub bytes_freed: u64,
+    pub errors: u64,
+}
+
+/// Outcome for one directory of a past run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirectoryRecord {
+    pub path: PathBuf,
+    pub files_removed: u64,
+    pub bytes_freed: u64,
+    pub errors: u64,
+    pub duration_ms: u64,
+}
+
+/// Everything recorded about a run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunDetails {
+    pub run: RunSummary,
+    pub directories: Vec<DirectoryRecord>,
+    
+    /// Size of every cache root after the run
+    pub usage: Vec<(PathBuf, u64)>,
+}
+
+/// How a cache root's size changed between two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UsageChange {
+    pub path: PathBuf,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl UsageChange {
+    pub fn delta(&self) -> i64 {
+        self.after.unwrap_or(0) as i64 - self.before.unwrap_or(0) as i64
+    }
+}
+
+/// Local SQLite database of past runs (`history.sqlite3` in the state directory)
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Open the database, creating it and its tables when missing
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create state directory: {}", e),
+                    Some(parent.to_path_buf())
+                ))?;
+        }
+        
+        let conn = Connection::open(path).map_err(db_error)?;
+        // The usage tracker writes while other commands read
+        conn.busy_timeout(Duration::from_secs(5)).map_err(db_error)?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(db_error)?;
+        if version > SCHEMA_VERSION {
+            return Err(ClearModelError::cache(format!(
+                "History database {:?} was written by a newer clearmodel (schema {})",
+                path, version
+            )));
+        }
+        
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(db_error)?;
+        conn.pragma_update(None, "foreign_keys", true).map_err(db_error)?;
+        Ok(Self { conn })
+    }
+    
+    /// Store a finished run and the cache sizes measured after it; returns its id
+    pub fn record(&mut self, report: &RunReport, usage: &[(PathBuf, u64)]) -> Result<i64> {
+        let tx = self.conn.transaction().map_err(db_error)?;
+        tx.execute(
+            "INSERT INTO runs (command, dry_run, succeeded, error, started_at, duration_ms, files_removed, bytes_freed, errors)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                report.command,
+                report.dry_run,
+                report.succeeded,
+                report.error,
+                report.started_at as i64,
+                report.duration_ms as i64,
+                report.totals.files_removed as i64,
+                report.totals.bytes_freed as i64,
+                report.totals.errors as i64,
+            ],
+        ).map_err(db_error)?;
+        let id = tx.last_insert_rowid();
+        
+        for result in &report.results {
+            tx.execute(
+                "INSERT INTO results (run_id, path, files_removed, bytes_freed, errors, duration_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    id,
+                    result.path.to_string_lossy(),
+                    result.files_removed as i64,
+                    result.bytes_freed as i64,
+                    result.errors.len() as i64,
+                    result.duration_ms as i64,
+                ],
+            ).map_err(db_error)?;
+        }
+        for (path, bytes) in usage {
+            tx.execute(
+                "INSERT INTO usage (run_id, path, bytes) VALUES (?1, ?2, ?3)",
+                params![id, path.to_string_lossy(), *bytes as i64],
+            ).map_err(db_error)?;
+        }
+        
+        tx.commit().map_err(db_error)?;
+        Ok(id)
+    }
+    
+    /// The most recent runs, newest first
+    pub fn runs(&self, limit: usize) -> Result<Vec<RunSummary>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, command, dry_run, succeeded, error, started_at, duration_ms, files_removed, bytes_freed, errors
+             FROM runs ORDER BY id DESC LIMIT ?1"
+        ).map_err(db_error)?;
+        let runs = statement.query_map([limit as i64], run_summary)
+            .map_err(db_error)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+        Ok(runs)
+    }
+    
+    /// A run with its per-directory results and the cache sizes after it
+    pub fn show(&self, id: i64) -> Result<Option<RunDetails>> {
+        let run = self.conn.query_row(
+            "SELECT id, command, dry_run, succeeded, error, started_at, duration_ms, files_removed, bytes_freed, errors
+             FROM runs WHERE id = ?1",
+            [id],
+            run_summary,
+        ).optional().map_err(db_error)?;
+        let Some(run) = run else {
+            return Ok(None);
+        };
+        
+        let mut statement = self.conn.prepare(
+            "SELECT path, files_removed, bytes_freed, errors, duration_ms FROM results WHERE run_id = ?1 ORDER BY bytes_freed DESC"
+        ).map_err(db_error)?;
+        let directories = statement.query_map([id], |row| {
+            Ok(DirectoryRecord {
+                path: PathBuf::from(row.get::<_, String>(0)?),
+                files_removed: row.get::<_, i64>(1)? as u64,
+                bytes_freed: row.get::<_, i64>(2)? as u64,
+                errors: row.get::<_, i64>(3)? as u64,
+                duration_ms: row.get::<_, i64>(4)? as u64,
+            })
+        })
+        .map_err(db_error)?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+        
+        Ok(Some(RunDetails { run, directories, usage: self.usage(id)? }))
+    }
+    
+    /// Compare cache sizes after run `from` with those after run `to`, biggest change first
+    ///
+    /// Without ids the oldest and newest runs that measured usage are compared.
+    pub fn diff(&self, from: Option<i64>, to: Option<i64>) -> Result<Option<(i64, i64, Vec<UsageChange>)>> {
+        let bound = |order: &str| -> Result<Option<i64>> {
+            self.conn.query_row(
+                &format!("SELECT run_id FROM usage ORDER BY run_id {} LIMIT 1", order),
+                [],
+                |row| row.get(0),
+            ).optional().map_err(db_error)
+        };
+        let from = match from {
+            Some(id) => Some(id),
+            None => bound("ASC")?,
+        };
+        let to = match to {
+            Some(id) => Some(id),
+            None => bound("DESC")?,
+        };
+        let (Some(from), Some(to)) = (from, to) else {
+            return Ok(None);
+        };
+        
+        let mut changes: BTreeMap<PathBuf, UsageChange> = BTreeMap::new();
+        for (path, bytes) in self.usage(from)? {
+            changes.insert(path.clone(), UsageChange { path, before: Some(bytes), after: None });
+        }
+        for (path, bytes) in self.usage(to)? {
+            changes.entry(path.clone())
+                .or_insert(UsageChange { path, before: None, after: None })
+                .after = Some(bytes);
+        }
+        
+        let mut changes: Vec<UsageChange> = changes.into_values().collect();
+        changes.sort_by_key(|change| std::cmp::Reverse(change.delta().unsigned_abs()));
+        Ok(Some((from, to, changes)))
+    }
+    
+    /// Add file opens seen by the usage tracker: a count and the latest open per path
+    pub fn record_opens(&mut self, opens: &HashMap<PathBuf, (u64, SystemTime)>) -> Result<()> {
+        let tx = self.conn.transaction().map_err(db_error)?;
+        for (path, (count, last_open)) in opens {
+            tx.execute(
+                "INSERT INTO opens (path, opens, last_open) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(path) DO UPDATE SET opens = opens + excluded.opens, last_open = MAX(last_open, excluded.last_open)",
+                params![path.to_string_lossy(), *count as i64, unix_secs(*last_open)],
+            ).map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)
+    }
+    
+    /// Every recorded path with its open count and last open
+    pub fn opens(&self) -> Result<HashMap<PathBuf, (u64, SystemTime)>> {
+        let mut statement = self.conn.prepare("SELECT path, opens, last_open FROM opens").map_err(db_error)?;
+        let opens = statement.query_map([], |row| {
+            let last_open = UNIX_EPOCH + Duration::from_secs(row.get::<_, i64>(2)?.max(0) as u64);
+            Ok((PathBuf::from(row.get::<_, String>(0)?), (row.get::<_, i64>(1)? as u64, last_open)))
+        })
+        .map_err(db_error)?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()
+        .map_err(db_error)?;
+        Ok(opens)
+    }
+    
+    /// Drop recorded opens of files that no longer exist; returns how many were dropped
+    pub fn forget_missing_opens(&mut self) -> Result<usize> {
+        let missing: Vec<PathBuf> = self.opens()?
+            .into_keys()
+            .filter(|path| !path.exists())
+            .collect();
+        
+        let tx = self.conn.transaction().map_err(db_error)?;
+        for path in &missing {
+            tx.execute("DELETE FROM opens WHERE path = ?1", [path.to_string_lossy()]).map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)?;
+        Ok(missing.len())
+    }
+    
+    fn usage(&self, id: i64) -> Result<Vec<(PathBuf, u64)>> {
+        let mut statement = self.conn.prepare("SELECT path, bytes FROM usage WHERE run_id = ?1 ORDER BY bytes DESC")
+            .map_err(db_error)?;
+        let usage = statement.query_map([id], |row| {
+            Ok((PathBuf::from(row.get::<_, String>(0)?), row.get::<_, i64>(1)? as u64))
+        })
+        .map_err(db_error)?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+        Ok(usage)
+    }
+}
+
+fn run_summary(row: &rusqlite::Row<'_>) -> rusqlite::Result<RunSummary> {
+    Ok(RunSummary {
+        id: row.get(0)?,
+        command: row.get(1)?,
+        dry_run: row.get(2)?,
+        succeeded: row.get(3)?,
+        error: row.get(4)?,
+        started_at: row.get::<_, i64>(5)? as u64,
+        duration_ms: row.get::<_, i64>(6)? as u64,
+        files_removed: row.get::<_, i64>(7)? as u64,
+        bytes_freed: row.get::<_, i64>(8)? as u64,
+        errors: row.get::<_, i64>(9)? as u64,
+    })
+}
+
+fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map(|since| since.as_secs() as i64).unwrap_or(0)
+}
+
+fn db_error(e: rusqlite::Error) -> ClearModelError {
+    ClearModelError::cache(format!("History database error: {}", e))
+}
+
+/// Location of the history database (`history.sqlite3` in the state directory)
+pub fn history_path() -> Option<PathBuf> {
+    state::path(state::HISTORY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::categories::CategoryBreakdown;
+    use crate::resource_manager::CleanupResult;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    
+    fn report(bytes_freed: u64) -> RunReport {
+        let mut report = RunReport::start("clean", false);
+        report.add_results(&[CleanupResult {
+            path: PathBuf::from("/cache/torch"),
+            files_removed: 2,
+            bytes_freed,
+            errors: Vec::new(),
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: CategoryBreakdown::new(),
+            needs_privileges: Vec::new(),
+            failed: Vec::new(),
+            duration: Duration::from_millis(5),
+        }]);
+        report.finish(None);
+        report
+    }
+    
+    #[test]
+    fn test_runs_are_recorded_listed_and_compared() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state/history.sqlite3");
+        let mut history = History::open(&path).unwrap();
+        
+        let first = history.record(&report(100), &[
+            (PathBuf::from("/cache/torch"), 1000),
+            (PathBuf::from("/cache/hf"), 500),
+        ]).unwrap();
+        let second = history.record(&report(40), &[
+            (PathBuf::from("/cache/torch"), 900),
+            (PathBuf::from("/cache/pip"), 50),
+        ]).unwrap();
+        
+        // Reopening keeps what was stored
+        let history = History::open(&path).unwrap();
+        let runs = history.runs(10).unwrap();
+        assert_eq!(runs.iter().map(|run| run.id).collect::<Vec<_>>(), vec![second, first]);
+        assert_eq!(runs[1].bytes_freed, 100);
+        
+        let details = history.show(first).unwrap().unwrap();
+        assert_eq!(details.directories[0].files_removed, 2);
+        assert_eq!(details.usage[0], (PathBuf::from("/cache/torch"), 1000));
+        assert!(history.show(second + 1).unwrap().is_none());
+        
+        let (from, to, changes) = history.diff(None, None).unwrap().unwrap();
+        assert_eq!((from, to), (first, second));
+        assert_eq!(changes[0], UsageChange { path: PathBuf::from("/cache/hf"), before: Some(500), after: None });
+        assert_eq!(changes[1].delta(), -100);
+        assert_eq!(changes[2].delta(), 50);
+    }
+    
+    #[test]
+    fn test_opens_accumulate() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut history = History::open(&temp_dir.path().join("history.sqlite3")).unwrap();
+        let weights = temp_dir.path().join("model.safetensors");
+        std::fs::write(&weights, b"weights").unwrap();
+        let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(secs);
+        
+        history.record_opens(&HashMap::from([(weights.clone(), (2, at(200)))])).unwrap();
+        history.record_opens(&HashMap::from([
+            (weights.clone(), (1, at(100))),
+            (temp_dir.path().join("gone.bin"), (1, at(300))),
+        ])).unwrap();
+        
+        let opens = history.opens().unwrap();
+        assert_eq!(opens[&weights], (3, at(200)));
+        assert_eq!(history.forget_missing_opens().unwrap(), 1);
+        assert_eq!(history.opens().unwrap().len(), 1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,209 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{ClearModelConfig, ImageStoreConfig};
+use crate::policy::CleanupPolicy;
+
+/// File types the image-generation apps load models from; nothing else in a store is touched
+pub const MODEL_EXTENSIONS: [&str; 7] = ["safetensors", "ckpt", "pt", "pth", "bin", "sft", "gguf"];
+
+/// ComfyUI's model folders (`models/<folder>`)
+const COMFYUI_FOLDERS: [&str; 15] = [
+    "checkpoints", "loras", "vae", "controlnet", "upscale_models", "embeddings", "clip",
+    "clip_vision", "unet", "diffusion_models", "text_encoders", "style_models",
+    "hypernetworks", "gligen", "photomaker",
+];
+
+/// AUTOMATIC1111's model folders (`models/<folder>`)
+const AUTOMATIC1111_FOLDERS: [&str; 9] = [
+    "Stable-diffusion", "Lora", "LyCORIS", "VAE", "ESRGAN", "RealESRGAN", "ControlNet",
+    "hypernetworks", "VAE-approx",
+];
+
+/// InvokeAI's base model folders (`models/<base>/<type>/<model>`)
+const INVOKEAI_BASES: [&str; 7] = ["sd-1", "sd-2", "sdxl", "sdxl-refiner", "sd-3", "flux", "any"];
+
+/// An image-generation app whose model folders are cleaned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageStoreKind {
+    Comfyui,
+    Automatic1111,
+    Invokeai,
+}
+
+impl ImageStoreKind {
+    /// Recognise an app installation (or data directory) from its model folders
+    pub fn detect(path: &Path) -> Option<Self> {
+        let models = path.join("models");
+        if models.join("checkpoints").is_dir() {
+            Some(ImageStoreKind::Comfyui)
+        } else if models.join("Stable-diffusion").is_dir() {
+            Some(ImageStoreKind::Automatic1111)
+        } else if path.join("invokeai.yaml").is_file() || INVOKEAI_BASES.iter().any(|base| models.join(base).is_dir()) {
+            Some(ImageStoreKind::Invokeai)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ImageStoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageStoreKind::Comfyui => write!(f, "ComfyUI"),
+            ImageStoreKind::Automatic1111 => write!(f, "AUTOMATIC1111"),
+            ImageStoreKind::Invokeai => write!(f, "InvokeAI"),
+        }
+    }
+}
+
+/// A configured image model store with its own policy
+#[derive(Debug, Clone)]
+pub struct ImageStore {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: Option<ImageStoreKind>,
+    pub policy: CleanupPolicy,
+}
+
+impl ImageStore {
+    /// The folder holding the models; the rest of the installation is never walked
+    pub fn models_dir(&self) -> PathBuf {
+        self.path.join("models")
+    }
+}
+
+/// Configured image model stores
+pub fn stores(config: &ClearModelConfig) -> Vec<ImageStore> {
+    config.image_stores.iter().map(|store| store_for(config, store)).collect()
+}
+
+fn store_for(config: &ClearModelConfig, store: &ImageStoreConfig) -> ImageStore {
+    let mut policy = CleanupPolicy::from_config(config);
+    if let Some(days) = store.max_age_days.filter(|_| config.older_than.is_none()) {
+        policy.max_age = std::time::Duration::from_secs(days as u64 * 24 * 3600);
+        policy.extension_max_age.clear();
+    }
+    policy.only_extensions = MODEL_EXTENSIONS.iter().map(|ext| ext.to_string()).collect();
+    
+    ImageStore {
+        name: store.name.clone(),
+        kind: store.kind.or_else(|| ImageStoreKind::detect(&store.path)),
+        path: store.path.clone(),
+        policy,
+    }
+}
+
+/// Name of the image model a file inside a store's `models` folder belongs to
+///
+/// ComfyUI and AUTOMATIC1111 models are single files, named `<folder>/<file stem>`
+/// (`checkpoints/sd_xl_base_1.0`, `Lora/detail-tweaker`) with any subfolders kept; previews
+/// and configs next to them are not models. InvokeAI ones are `<base>/<type>/<model>`, where
+/// the model can be a file or a whole diffusers directory.
+pub fn model_name(components: &[&str]) -> Option<String> {
+    let index = components.iter().rposition(|component| *component == "models")?;
+    let folder = *components.get(index + 1)?;
+    let rest = components.get(index + 2..).filter(|rest| !rest.is_empty())?;
+    
+    if COMFYUI_FOLDERS.contains(&folder) || AUTOMATIC1111_FOLDERS.contains(&folder) {
+        let (file, subfolders) = rest.split_last()?;
+        let stem = model_stem(file)?;
+        let mut parts = vec![folder];
+        parts.extend(subfolders);
+        parts.push(stem);
+        return Some(parts.join("/"));
+    }
+    if INVOKEAI_BASES.contains(&folder) {
+        let [model_type, model, ..] = rest else {
+            return None;
+        };
+        return Some(format!("{}/{}/{}", folder, model_type, model_stem(model).unwrap_or(model)));
+    }
+    None
+}
+
+/// The directory of an InvokeAI diffusers model a file belongs to (`models/<base>/<type>/<model>`)
+pub fn model_dir(path: &Path) -> Option<PathBuf> {
+    let components: Vec<Component> = path.components().collect();
+    let index = components.iter().rposition(|component| *component == Component::Normal("models".as_ref()))?;
+    let Component::Normal(base) = components.get(index + 1)? else {
+        return None;
+    };
+    if !INVOKEAI_BASES.iter().any(|known| base.to_str() == Some(known)) || components.len() <= index + 4 {
+        return None;
+    }
+    Some(components[..index + 4].iter().collect())
+}
+
+/// Whether a file is one of the model types the apps load
+pub fn is_model_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MODEL_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn model_stem(file: &str) -> Option<&str> {
+    let (stem, _) = file.rsplit_once('.')?;
+    (!stem.is_empty() && is_model_file(Path::new(file))).then_some(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Decision, FileFacts};
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_stores_are_detected_and_their_models_named() {
+        let temp_dir = TempDir::new().unwrap();
+        let comfy = temp_dir.path().join("ComfyUI");
+        fs::create_dir_all(comfy.join("models/checkpoints")).unwrap();
+        let a1111 = temp_dir.path().join("stable-diffusion-webui");
+        fs::create_dir_all(a1111.join("models/Stable-diffusion")).unwrap();
+        assert_eq!(ImageStoreKind::detect(&comfy), Some(ImageStoreKind::Comfyui));
+        assert_eq!(ImageStoreKind::detect(&a1111), Some(ImageStoreKind::Automatic1111));
+        assert_eq!(ImageStoreKind::detect(temp_dir.path()), None);
+        
+        assert_eq!(model_name(&["ComfyUI", "models", "checkpoints", "sd_xl_base_1.0.safetensors"]).as_deref(), Some("checkpoints/sd_xl_base_1.0"));
+        assert_eq!(model_name(&["ComfyUI", "models", "loras", "SDXL", "detail.safetensors"]).as_deref(), Some("loras/SDXL/detail"));
+        assert_eq!(model_name(&["webui", "models", "Stable-diffusion", "v1-5.ckpt"]).as_deref(), Some("Stable-diffusion/v1-5"));
+        assert_eq!(model_name(&["invokeai", "models", "sdxl", "main", "juggernaut", "unet", "model.safetensors"]).as_deref(), Some("sdxl/main/juggernaut"));
+        assert_eq!(model_name(&["ComfyUI", "models", "checkpoints", "sd_xl_base_1.0.preview.png"]), None);
+        assert_eq!(model_name(&["ComfyUI", "models", "checkpoints"]), None);
+        assert_eq!(model_name(&["ComfyUI", "custom_nodes", "node.py"]), None);
+        
+        let unet = Path::new("/invokeai/models/sdxl/main/juggernaut/unet/model.safetensors");
+        assert_eq!(model_dir(unet), Some(PathBuf::from("/invokeai/models/sdxl/main/juggernaut")));
+        assert_eq!(model_dir(Path::new("/invokeai/models/sdxl/main/single.safetensors")), None);
+    }
+    
+    #[test]
+    fn test_store_policy_only_removes_model_files() {
+        let config = ClearModelConfig {
+            image_stores: vec![ImageStoreConfig {
+                name: "comfy".to_string(),
+                path: PathBuf::from("/opt/ComfyUI"),
+                kind: Some(ImageStoreKind::Comfyui),
+                max_age_days: Some(60),
+            }],
+            ..ClearModelConfig::default()
+        };
+        let store = &stores(&config)[0];
+        assert_eq!(store.models_dir(), PathBuf::from("/opt/ComfyUI/models"));
+        assert_eq!(store.policy.max_age, std::time::Duration::from_secs(60 * 24 * 3600));
+        
+        let old = |extension: &str| FileFacts {
+            extension: Some(extension.to_string()),
+            age: Some(std::time::Duration::from_secs(90 * 24 * 3600)),
+            ..FileFacts::default()
+        };
+        assert_eq!(store.policy.decide(&old("safetensors"), &[]), Decision::Expired);
+        assert_eq!(store.policy.decide(&old("CKPT"), &[]), Decision::Expired);
+        assert_eq!(store.policy.decide(&old("png"), &[]), Decision::Fresh);
+        assert_eq!(store.policy.decide(&old("yaml"), &[]), Decision::Fresh);
+    }
+}
\ No newline at end of file
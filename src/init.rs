@@ -0,0 +1,194 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::cache_target;
+use crate::config::ClearModelConfig;
+
+/// A cache found on this machine when `clearmodel config init` runs
+#[derive(Debug, Clone)]
+pub struct DetectedCache {
+    /// Target name, as given to `--only`
+    pub target: String,
+    pub roots: Vec<PathBuf>,
+    pub bytes: u64,
+}
+
+/// What the wizard asked
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitAnswers {
+    pub max_cache_age_days: u32,
+    pub quarantine: bool,
+    pub max_total_cache_gb: Option<f64>,
+}
+
+impl Default for InitAnswers {
+    fn default() -> Self {
+        Self {
+            max_cache_age_days: ClearModelConfig::default().max_cache_age_days,
+            quarantine: true,
+            max_total_cache_gb: None,
+        }
+    }
+}
+
+/// Targets with something on disk, largest first
+pub fn detect(config: &ClearModelConfig) -> Vec<DetectedCache> {
+    let mut detected: Vec<DetectedCache> = cache_target::builtin_targets(config)
+        .into_iter()
+        .filter(|target| target.detect())
+        .filter_map(|target| {
+            let roots: Vec<PathBuf> = target.roots().into_iter().filter(|root| root.is_dir()).collect();
+            if roots.is_empty() {
+                return None;
+            }
+            let bytes = roots.iter()
+                .flat_map(|root| walkdir::WalkDir::new(root).into_iter().flatten())
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|metadata| metadata.len())
+                .sum();
+            Some(DetectedCache { target: target.name().to_string(), roots, bytes })
+        })
+        .collect();
+    detected.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.target.cmp(&b.target)));
+    detected
+}
+
+/// Ask the wizard's questions, re-asking on invalid answers; the end of input takes the defaults
+pub fn ask(input: &mut impl BufRead, output: &mut impl Write, detected: &[DetectedCache]) -> InitAnswers {
+    let defaults = InitAnswers::default();
+    let total: u64 = detected.iter().map(|cache| cache.bytes).sum();
+    
+    let max_cache_age_days = question(
+        input, output,
+        &format!("Remove cache files older than how many days? [{}]", defaults.max_cache_age_days),
+        defaults.max_cache_age_days,
+        |answer| answer.parse().ok(),
+    );
+    let quarantine = question(
+        input, output,
+        &format!(
+            "Move removed files to quarantine for {} days instead of deleting them right away? [Y/n]",
+            ClearModelConfig::default().quarantine_ttl_days
+        ),
+        defaults.quarantine,
+        |answer| match answer {
+            "y" | "Y" | "yes" => Some(true),
+            "n" | "N" | "no" => Some(false),
+            _ => None,
+        },
+    );
+    let max_total_cache_gb = question(
+        input, output,
+        &format!("Cap the model caches at how many GB, evicting the least recently used? ({:.1} GB now; empty for no cap)", gb(total)),
+        defaults.max_total_cache_gb,
+        |answer| answer.parse::<f64>().ok().filter(|budget| *budget > 0.0).map(Some),
+    );
+    
+    InitAnswers { max_cache_age_days, quarantine, max_total_cache_gb }
+}
+
+fn question<T>(input: &mut impl BufRead, output: &mut impl Write, prompt: &str, default: T, parse: impl Fn(&str) -> Option<T>) -> T {
+    loop {
+        let _ = write!(output, "{} ", prompt);
+        let _ = output.flush();
+        
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) | Err(_) => return default,
+            Ok(_) if line.trim().is_empty() => return default,
+            Ok(_) => {
+                if let Some(answer) = parse(line.trim()) {
+                    return answer;
+                }
+                let _ = writeln!(output, "Invalid answer: {}", line.trim());
+            }
+        }
+    }
+}
+
+/// The configuration file, with comments, for what was detected and answered
+///
+/// Only the answered settings and the cache directories that exist are written; everything
+/// else keeps its default.
+pub fn render(config: &ClearModelConfig, detected: &[DetectedCache], answers: &InitAnswers) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# ClearModel configuration written by `clearmodel config init`");
+    let _ = writeln!(out, "# Settings left out keep their defaults; see clearmodel.example.toml or `clearmodel config schema`.");
+    let _ = writeln!(out, "# Check changes with `clearmodel config validate`.");
+    let _ = writeln!(out);
+    
+    if detected.is_empty() {
+        let _ = writeln!(out, "# No caches were found when this file was written.");
+    } else {
+        let _ = writeln!(out, "# Caches found when this file was written:");
+        for cache in detected {
+            let roots: Vec<String> = cache.roots.iter().map(|root| root.display().to_string()).collect();
+            let _ = writeln!(out, "#   {:<18} {:>9.2} GB  {}", cache.target, gb(cache.bytes), roots.join(", "));
+        }
+    }
+    let _ = writeln!(out);
+    
+    let existing: Vec<&PathBuf> = config.cache_paths.iter().filter(|path| path.is_dir()).collect();
+    if !existing.is_empty() {
+        let _ = writeln!(out, "# ML cache directories cleaned under max_cache_age_days (the defaults that exist here)");
+        let _ = writeln!(out, "cache_paths = [");
+        for path in existing {
+            let _ = writeln!(out, "    {},", toml_edit::Value::from(path.to_string_lossy().as_ref()));
+        }
+        let _ = writeln!(out, "]");
+        let _ = writeln!(out);
+    }
+    
+    let _ = writeln!(out, "# Maximum age of cache files in days");
+    let _ = writeln!(out, "max_cache_age_days = {}", answers.max_cache_age_days);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "# Move removed files into quarantine for quarantine_ttl_days instead of deleting them");
+    let _ = writeln!(out, "quarantine = {}", answers.quarantine);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "# Least recently used models are evicted until the model caches together fit");
+    match answers.max_total_cache_gb {
+        Some(budget) => {
+            let _ = writeln!(out, "max_total_cache_gb = {}", toml_edit::Value::from(budget));
+        }
+        None => {
+            let _ = writeln!(out, "# max_total_cache_gb = 100");
+        }
+    }
+    out
+}
+
+fn gb(bytes: u64) -> f64 {
+    bytes as f64 / 1_073_741_824.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_answers_are_written_as_a_loadable_config() {
+        let detected = vec![DetectedCache { target: "huggingface".to_string(), roots: vec![PathBuf::from("/hf")], bytes: 3 << 30 }];
+        let mut input = "abc\n14\nn\n250\n".as_bytes();
+        let mut output = Vec::new();
+        let answers = ask(&mut input, &mut output, &detected);
+        assert_eq!(answers, InitAnswers { max_cache_age_days: 14, quarantine: false, max_total_cache_gb: Some(250.0) });
+        assert!(String::from_utf8(output).unwrap().contains("Invalid answer: abc"));
+        assert_eq!(ask(&mut "".as_bytes(), &mut Vec::new(), &detected), InitAnswers::default());
+        
+        let temp_dir = TempDir::new().unwrap();
+        let config = ClearModelConfig {
+            cache_paths: vec![temp_dir.path().to_path_buf(), temp_dir.path().join("missing")],
+            ..ClearModelConfig::default()
+        };
+        let content = render(&config, &detected, &answers);
+        assert!(content.contains("#   huggingface             3.00 GB  /hf"));
+        let written: ClearModelConfig = toml::from_str(&content).unwrap();
+        assert_eq!(written.cache_paths, vec![temp_dir.path().to_path_buf()]);
+        assert_eq!(written.max_cache_age_days, 14);
+        assert!(!written.quarantine);
+        assert_eq!(written.max_total_cache_gb, Some(250.0));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+use crate::config::JournalConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Extension of journals for runs that can still be undone
+const JOURNAL_EXTENSION: &str = "jsonl";
+
+/// Extension journals are renamed to once their run has been undone
+const UNDONE_EXTENSION: &str = "undone";
+
+/// One removed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Original location of the file
+    pub path: PathBuf,
+    
+    /// Size in bytes at the time of removal
+    pub size: u64,
+    
+    /// BLAKE3 hash of the contents, taken only for files that can be restored or are audited
+    /// (and omitted above the hashing limit)
+    pub hash: Option<String>,
+    
+    /// Seconds since the Unix epoch when the file was removed
+    pub timestamp: u64,
+    
+    /// Where the file was moved to, if it was not deleted permanently
+    pub destination: Option<PathBuf>,
+}
+
+/// Append-only record of every file removed during one run
+///
+/// The journal file is only created once the first entry is recorded, so runs
+/// that remove nothing leave no trace.
+#[derive(Debug)]
+pub struct Journal {
+    run_id: String,
+    path: PathBuf,
+    hash_limit: u64,
+    writer: Mutex<Option<BufWriter<File>>>,
+}
+
+impl Journal {
+    /// Start a journal for a run in the default state directory
+    pub fn create(run_id: &str, config: &JournalConfig) -> Result<Self> {
+        let dir = journal_dir().ok_or_else(|| ClearModelError::environment(
+            "Cannot determine the state directory for the deletion journal".to_string()
+        ))?;
+        
+        Self::in_dir(&dir, run_id, config)
+    }
+    
+    /// Start a journal for a run in `dir`
+    pub fn in_dir(dir: &Path, run_id: &str, config: &JournalConfig) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to create journal directory: {}", e),
+                Some(dir.to_path_buf())
+            ))?;
+        
+        let path = dir.join(format!("{}.{}", run_id, JOURNAL_EXTENSION));
+        debug!("Journaling deletions for run {} to {:?}", run_id, path);
+        
+        Ok(Self {
+            run_id: run_id.to_string(),
+            path,
+            hash_limit: config.hash_max_mb.saturating_mul(1024 * 1024),
+            writer: Mutex::new(None),
+        })
+    }
+    
+    /// Identifier of the run being journaled
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+    
+    /// Build an entry for a file that is about to be removed, hashing it when `hashed` and small enough
+    ///
+    /// The hash is what `undo` checks a quarantined file against before putting it back, so
+    /// files deleted for good are only hashed when the audit log wants the hash too.
+    pub fn entry_for(&self, path: &Path, size: u64, hashed: bool) -> JournalEntry {
+        let hash = if hashed && size <= self.hash_limit {
+            hash_file(path).ok()
+        } else {
+            None
+        };
+        
+        JournalEntry {
+            path: path.to_path_buf(),
+            size,
+            hash,
+            timestamp: unix_now(),
+            destination: None,
+        }
+    }
+    
+    /// Append an entry, flushing it to disk immediately
+    pub fn record(&self, entry: &JournalEntry) -> Result<()> {
+        let mut writer = self.writer.lock()
+            .map_err(|_| ClearModelError::cache("Journal lock poisoned".to_string()))?;
+        
+        if writer.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to open journal: {}", e),
+                    Some(self.path.clone())
+                ))?;
+            info!("Recording deletions to {:?}", self.path);
+            *writer = Some(BufWriter::new(file));
+        }
+        
+        if let Some(writer) = writer.as_mut() {
+            serde_json::to_writer(&mut *writer, entry)?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+        
+        Ok(())
+    }
+}
+
+/// Directory holding deletion journals (`journal` in the state directory)
+pub fn journal_dir() -> Option<PathBuf> {
+    state::path(state::JOURNAL)
+}
+
+/// Most recent run that has not been undone yet, as (run id, journal path)
+pub fn latest_run(dir: &Path) -> Result<Option<(String, PathBuf)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(ClearModelError::file_operation(
+                format!("Failed to read journal directory: {}", e),
+                Some(dir.to_path_buf())
+            ));
+        }
+    };
+    
+    // Run ids start with a fixed-width timestamp, so the largest name is the newest run
+    Ok(entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(JOURNAL_EXTENSION))
+        .filter_map(|path| {
+            let run_id = path.file_stem()?.to_str()?.to_string();
+            Some((run_id, path))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0)))
+}
+
+/// Read every entry of a journal
+pub fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+    let file = File::open(path)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to open journal: {}", e),
+            Some(path.to_path_buf())
+        ))?;
+    
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    
+    Ok(entries)
+}
+
+/// Mark a run's journal as undone so the next `undo` goes further back
+pub fn mark_undone(path: &Path) -> Result<()> {
+    let undone = path.with_extension(UNDONE_EXTENSION);
+    std::fs::rename(path, &undone)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to mark journal as undone: {}", e),
+            Some(path.to_path_buf())
+        ))
+}
+
+/// BLAKE3 hash of a file's contents
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Identifier for a new run, starting with a fixed-width timestamp so ids sort by age
+pub fn new_run_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or(0);
+    format!("{:012}-{:09}-{}", unix_now(), nanos, std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_record_and_read_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("model.bin");
+        std::fs::write(&file, b"weights").unwrap();
+        
+        let journal_dir = temp_dir.path().join("journal");
+        let journal = Journal::in_dir(&journal_dir, &new_run_id(), &JournalConfig::default()).unwrap();
+        assert!(latest_run(&journal_dir).unwrap().is_none());
+        
+        assert_eq!(journal.entry_for(&file, 7, false).hash, None);
+        let entry = journal.entry_for(&file, 7, true);
+        assert_eq!(entry.hash, Some(blake3::hash(b"weights").to_hex().to_string()));
+        journal.record(&entry).unwrap();
+        
+        let (run_id, path) = latest_run(&journal_dir).unwrap().unwrap();
+        assert_eq!(run_id, journal.run_id());
+        assert_eq!(read_entries(&path).unwrap(), vec![entry]);
+        
+        mark_undone(&path).unwrap();
+        assert!(latest_run(&journal_dir).unwrap().is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,171 @@
+use secrecy::{ExposeSecret, Secret};
+use std::process::Output;
+use tracing::debug;
+use zeroize::Zeroize;
+
+use crate::command_runner::CommandRunner;
+use crate::errors::{ClearModelError, Result};
+
+/// Service name credentials are filed under
+pub const SERVICE: &str = "clearmodel";
+
+/// Account holding the sudo password
+pub const SUDO_ACCOUNT: &str = "sudo";
+
+/// The OS credential store: the macOS Keychain (`security`), the Secret Service on Linux
+/// (`secret-tool`) or the Windows Credential Manager (through PowerShell)
+///
+/// Secrets are passed to the tools on stdin, never on their command line.
+pub fn backend() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "macOS Keychain"
+    } else if cfg!(windows) {
+        "Windows Credential Manager"
+    } else {
+        "Secret Service"
+    }
+}
+
+/// Save a secret, replacing any stored under the same account
+pub fn store(commands: &CommandRunner, account: &str, secret: &Secret<String>) -> Result<()> {
+    let output = store_command(commands, account, secret.expose_secret())
+        .map_err(|e| unavailable(&e))?;
+    if !output.status.success() {
+        return Err(ClearModelError::security(format!(
+            "Could not save the credential to the {}: {}",
+            backend(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    debug!("Stored {} credential in the {}", account, backend());
+    Ok(())
+}
+
+/// Look up a secret; `None` when nothing is stored or no credential store is available
+pub fn load(commands: &CommandRunner, account: &str) -> Option<Secret<String>> {
+    let mut output = match load_command(commands, account) {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("{} is not available: {}", backend(), e);
+            return None;
+        }
+    };
+    
+    let secret = String::from_utf8_lossy(&output.stdout).trim_end_matches(['\r', '\n']).to_string();
+    output.stdout.zeroize();
+    if !output.status.success() || secret.is_empty() {
+        debug!("No {} credential in the {}", account, backend());
+        return None;
+    }
+    Some(Secret::new(secret))
+}
+
+/// Remove a stored secret; succeeds when there was none
+pub fn delete(commands: &CommandRunner, account: &str) -> Result<()> {
+    let output = delete_command(commands, account).map_err(|e| unavailable(&e))?;
+    if !output.status.success() {
+        debug!("Nothing to remove for {}: {}", account, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+fn unavailable(e: &ClearModelError) -> ClearModelError {
+    ClearModelError::environment(format!("The {} is not available: {}", backend(), e))
+}
+
+/// Run a credential tool through `commands`, feeding it `input` on stdin
+fn run(commands: &CommandRunner, program: &str, args: &[&str], input: Option<&str>) -> Result<Output> {
+    commands.output(program, args, input.map(str::as_bytes))
+}
+
+#[cfg(target_os = "macos")]
+fn store_command(commands: &CommandRunner, account: &str, secret: &str) -> Result<Output> {
+    // `security -i` reads commands from stdin, which keeps the password out of `ps`
+    let quoted = secret.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut script = format!("add-generic-password -U -a \"{}\" -s \"{}\" -w \"{}\"\n", account, SERVICE, quoted);
+    let output = run(commands, "/usr/bin/security", &["-i"], Some(&script));
+    script.zeroize();
+    output
+}
+
+#[cfg(target_os = "macos")]
+fn load_command(commands: &CommandRunner, account: &str) -> Result<Output> {
+    run(commands, "/usr/bin/security", &["find-generic-password", "-a", account, "-s", SERVICE, "-w"], None)
+}
+
+#[cfg(target_os = "macos")]
+fn delete_command(commands: &CommandRunner, account: &str) -> Result<Output> {
+    run(commands, "/usr/bin/security", &["delete-generic-password", "-a", account, "-s", SERVICE], None)
+}
+
+#[cfg(windows)]
+const POWERSHELL_VAULT: &str = "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,ContentType=WindowsRuntime]; $vault = New-Object Windows.Security.Credentials.PasswordVault;";
+
+#[cfg(windows)]
+fn powershell(commands: &CommandRunner, script: String, input: Option<&str>) -> Result<Output> {
+    run(commands, "powershell.exe", &["-NoProfile", "-NonInteractive", "-Command", &format!("{} {}", POWERSHELL_VAULT, script)], input)
+}
+
+#[cfg(windows)]
+fn store_command(commands: &CommandRunner, account: &str, secret: &str) -> Result<Output> {
+    powershell(
+        commands,
+        format!(
+            "$secret = [Console]::In.ReadToEnd(); try {{ $vault.Remove($vault.Retrieve('{service}', '{account}')) }} catch {{ }}; \
+             $vault.Add((New-Object Windows.Security.Credentials.PasswordCredential('{service}', '{account}', $secret)))",
+            service = SERVICE,
+            account = account
+        ),
+        Some(secret)
+    )
+}
+
+#[cfg(windows)]
+fn load_command(commands: &CommandRunner, account: &str) -> Result<Output> {
+    powershell(
+        commands,
+        format!(
+            "$credential = $vault.Retrieve('{}', '{}'); $credential.RetrievePassword(); [Console]::Out.Write($credential.Password)",
+            SERVICE, account
+        ),
+        None
+    )
+}
+
+#[cfg(windows)]
+fn delete_command(commands: &CommandRunner, account: &str) -> Result<Output> {
+    powershell(commands, format!("$vault.Remove($vault.Retrieve('{}', '{}'))", SERVICE, account), None)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn store_command(commands: &CommandRunner, account: &str, secret: &str) -> Result<Output> {
+    let label = format!("{} {} credential", SERVICE, account);
+    run(commands, "secret-tool", &["store", "--label", &label, "service", SERVICE, "account", account], Some(secret))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn load_command(commands: &CommandRunner, account: &str) -> Result<Output> {
+    run(commands, "secret-tool", &["lookup", "service", SERVICE, "account", account], None)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn delete_command(commands: &CommandRunner, account: &str) -> Result<Output> {
+    run(commands, "secret-tool", &["clear", "service", SERVICE, "account", account], None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_credential_tools_are_allowed_and_others_refused() {
+        let commands = CommandRunner::builtin();
+        assert!(run(&commands, "cat", &[], Some("hunter2")).unwrap_err().to_string().contains("not in commands.allowed"));
+        
+        // A missing credential store is not an error when looking up
+        if run(&commands, "secret-tool", &["--version"], None).is_err() {
+            assert!(load(&commands, "clearmodel-test-account").is_none());
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,97 @@
+//! Secure ML model cache cleaner with path traversal protection
+
+pub mod config;
+pub mod environment;
+pub mod cache_cleaner;
+pub mod cache_target;
+pub mod resource_manager;
+pub mod retry;
+pub mod command_runner;
+pub mod batching;
+pub mod walk;
+pub mod workers;
+pub mod uring;
+pub mod bench;
+pub mod plan;
+pub mod checkpoint;
+pub mod budget;
+pub mod exclude;
+pub mod expand;
+pub mod security;
+pub mod sandbox;
+pub mod anchored;
+pub mod shred;
+pub mod netfs;
+pub mod rate_limit;
+pub mod privilege;
+pub mod errors;
+pub mod events;
+pub mod stats;
+pub mod disk_space;
+pub mod allocation;
+pub mod categories;
+#[cfg(feature = "tui")]
+pub mod progress;
+#[cfg(not(feature = "tui"))]
+#[path = "progress_silent.rs"]
+pub mod progress;
+#[cfg(feature = "daemon")]
+pub mod watch;
+#[cfg(feature = "daemon")]
+pub mod daemon;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "keyring")]
+pub mod keyring;
+#[cfg(feature = "remote")]
+pub mod remote_config;
+#[cfg(feature = "remote")]
+pub mod remote_store;
+#[cfg(feature = "usage")]
+pub mod usage;
+pub mod capabilities;
+pub mod open_files;
+pub mod policy;
+pub mod units;
+pub mod targets;
+pub mod log_dedup;
+pub mod log_file;
+pub mod log_format;
+pub mod models;
+pub mod trace;
+pub mod journal;
+pub mod audit;
+pub mod run_lock;
+pub mod shared_cache;
+pub mod state;
+pub mod cancel;
+pub mod restore;
+pub mod quarantine;
+pub mod support;
+pub mod prompt;
+pub mod scheduler;
+pub mod size_index;
+pub mod prescan;
+pub mod analysis;
+pub mod top;
+pub mod export;
+pub mod notifications;
+pub mod report;
+pub mod hf_repair;
+pub mod hf_revisions;
+pub mod hf_caches;
+pub mod hf_datasets;
+pub mod torch_caches;
+pub mod image_models;
+pub mod gguf;
+pub mod data_caches;
+pub mod engine_caches;
+pub mod containers;
+pub mod experiments;
+pub mod notebooks;
+pub mod health;
+pub mod dedup;
+pub mod lifecycle;
+pub mod init;
\ No newline at end of file
@@ -0,0 +1,332 @@
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::config::{ClearModelConfig, LifecycleTiers};
+use crate::errors::{ClearModelError, Result};
+use crate::netfs;
+use crate::policy::CleanupPolicy;
+use crate::quarantine;
+use crate::resource_manager::ResourceManager;
+use crate::targets;
+
+/// Where a cache file is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Old enough to be reported
+    Warn,
+    /// Old enough to be moved into the archive
+    Archive,
+    /// Old enough to be deleted
+    Delete,
+}
+
+impl Stage {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Stage::Warn => "warn",
+            Stage::Archive => "archive",
+            Stage::Delete => "delete",
+        }
+    }
+}
+
+/// Lifecycle tiers as ages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tiers {
+    pub warn: Option<Duration>,
+    pub archive: Option<Duration>,
+    pub delete: Duration,
+}
+
+impl Tiers {
+    pub fn from_config(tiers: &LifecycleTiers) -> Self {
+        let days = |days: u32| Duration::from_secs(days as u64 * 24 * 3600);
+        Self {
+            warn: tiers.warn_days.map(days),
+            archive: tiers.archive_days.map(days),
+            delete: days(tiers.delete_days),
+        }
+    }
+    
+    /// The latest stage a file of this age has reached, if any
+    pub fn stage_for(&self, age: Duration) -> Option<Stage> {
+        if age >= self.delete {
+            Some(Stage::Delete)
+        } else if self.archive.map(|archive| age >= archive).unwrap_or(false) {
+            Some(Stage::Archive)
+        } else if self.warn.map(|warn| age >= warn).unwrap_or(false) {
+            Some(Stage::Warn)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Tiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = |age: Duration| age.as_secs() / 86400;
+        if let Some(warn) = self.warn {
+            write!(f, "warn {}d -> ", days(warn))?;
+        }
+        if let Some(archive) = self.archive {
+            write!(f, "archive {}d -> ", days(archive))?;
+        }
+        write!(f, "delete {}d", days(self.delete))
+    }
+}
+
+/// Caches of one kind that share a set of tiers
+#[derive(Debug, Clone)]
+pub struct TierGroup {
+    /// `ml` or `packages`; archived files of the group live under this name
+    pub name: &'static str,
+    pub tiers: Tiers,
+    pub roots: Vec<PathBuf>,
+    pub policy: CleanupPolicy,
+    
+    /// Whether walks stay on each root's filesystem
+    pub stay_on_filesystem: bool,
+}
+
+/// Files and bytes that reached a stage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTotals {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl StageTotals {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// What a lifecycle pass did (or would do in a dry run)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifecycleSummary {
+    pub warned: StageTotals,
+    pub archived: StageTotals,
+    pub deleted: StageTotals,
+    pub errors: u64,
+}
+
+/// Root of the archive tier (`archive_dir`, or `$XDG_CACHE_HOME/clearmodel/archive`)
+pub fn archive_dir(config: &ClearModelConfig) -> Option<PathBuf> {
+    config.lifecycle.archive_dir.clone().or_else(|| {
+        quarantine::quarantine_dir()
+            .and_then(|quarantine| quarantine.parent().map(|parent| parent.join("archive")))
+    })
+}
+
+/// The ML and package cache groups with their tiers and existing roots
+pub fn groups(config: &ClearModelConfig) -> Vec<TierGroup> {
+    let mut groups = vec![TierGroup {
+        name: "ml",
+        tiers: Tiers::from_config(&config.lifecycle.ml),
+        roots: config.existing_cache_paths().into_iter().filter(|path| netfs::admitted(path, config)).cloned().collect(),
+        policy: CleanupPolicy::from_config(config).with_lifecycle(&config.lifecycle.ml),
+        stay_on_filesystem: config.stay_on_filesystem,
+    }];
+    
+    if config.package_caches.enabled {
+        groups.push(TierGroup {
+            name: "packages",
+            tiers: Tiers::from_config(&config.lifecycle.packages),
+            roots: targets::package_cache_locations()
+                .into_iter()
+                .map(|(_, path)| path)
+                .filter(|path| path.is_dir() && netfs::admitted(path, config))
+                .collect(),
+            policy: CleanupPolicy::for_package_caches(config).with_lifecycle(&config.lifecycle.packages),
+            stay_on_filesystem: config.stay_on_filesystem,
+        });
+    }
+    
+    groups
+}
+
+/// Walk every group once: report files in the warn tier, archive files in the archive
+/// tier, then delete files past the final tier from the caches and the archive
+pub async fn run_pass(config: &ClearModelConfig, resource_manager: &ResourceManager, dry_run: bool) -> Result<LifecycleSummary> {
+    let archive_root = archive_dir(config).ok_or_else(|| ClearModelError::environment(
+        "Cannot determine the lifecycle archive directory".to_string()
+    ))?;
+    
+    let mut summary = LifecycleSummary::default();
+    for group in groups(config) {
+        let archive = archive_root.join(group.name);
+        for root in &group.roots {
+            let (task_root, task_group, task_archive) = (root.clone(), group.clone(), archive.clone());
+            let (warned, archived, errors) = tokio::task::spawn_blocking(move || {
+                advance_root(&task_root, &task_group, &task_archive, dry_run)
+            })
+            .await
+            .map_err(|e| ClearModelError::cache(format!("Lifecycle task failed: {}", e)))?;
+            
+            if warned.files > 0 {
+                info!(
+                    cache = %root.display(),
+                    files = warned.files,
+                    mb = warned.bytes as f64 / 1_048_576.0,
+                    "Files approaching the archive tier"
+                );
+            }
+            summary.warned.files += warned.files;
+            summary.warned.bytes += warned.bytes;
+            summary.archived.files += archived.files;
+            summary.archived.bytes += archived.bytes;
+            summary.errors += errors;
+        }
+        
+        // The delete tier goes through the regular cleaner, so pins, the journal and quarantine apply
+        let mut delete_roots = group.roots.clone();
+        if archive.is_dir() {
+            delete_roots.push(archive);
+        }
+        for result in resource_manager.clean_paths(&delete_roots, &group.policy, dry_run).await? {
+            summary.deleted.files += result.files_removed;
+            summary.deleted.bytes += result.bytes_freed;
+            summary.errors += result.errors.len() as u64;
+        }
+    }
+    
+    info!(
+        warned = summary.warned.files,
+        archived = summary.archived.files,
+        deleted = summary.deleted.files,
+        errors = summary.errors,
+        dry_run,
+        "Lifecycle pass finished"
+    );
+    Ok(summary)
+}
+
+/// Report and archive the files under one root, returning (warned, archived, errors)
+fn advance_root(root: &Path, group: &TierGroup, archive: &Path, dry_run: bool) -> (StageTotals, StageTotals, u64) {
+    let mut warned = StageTotals::default();
+    let mut archived = StageTotals::default();
+    let mut errors = 0;
+    let quarantine_root = quarantine::quarantine_dir();
+    let now = SystemTime::now();
+    
+    let walker = WalkDir::new(root)
+        .same_file_system(group.stay_on_filesystem)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != archive && quarantine_root.as_deref() != Some(entry.path()));
+    
+    for entry in walker.flatten() {
+        if !entry.file_type().is_file() || group.policy.pinned.protects(entry.path()) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.len() < group.policy.min_size {
+            continue;
+        }
+        let age = group.policy.age_of(entry.path(), &metadata, now).unwrap_or_default();
+        
+        match group.tiers.stage_for(age) {
+            Some(Stage::Warn) => {
+                debug!("Lifecycle warn: {:?}", entry.path());
+                warned.add(metadata.len());
+            }
+            Some(Stage::Archive) if dry_run => {
+                info!("Would archive {:?}", entry.path());
+                archived.add(metadata.len());
+            }
+            Some(Stage::Archive) => match move_to_archive(entry.path(), archive) {
+                Ok(destination) => {
+                    debug!("Archived {:?} -> {:?}", entry.path(), destination);
+                    archived.add(metadata.len());
+                }
+                Err(e) => {
+                    warn!("Failed to archive {:?}: {}", entry.path(), e);
+                    errors += 1;
+                }
+            },
+            // Deleted afterwards by the regular cleaner
+            Some(Stage::Delete) | None => {}
+        }
+    }
+    
+    (warned, archived, errors)
+}
+
+/// Move a file under `archive`, keeping its absolute path and modification time
+fn move_to_archive(path: &Path, archive: &Path) -> Result<PathBuf> {
+    let relative: PathBuf = path.components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect();
+    let destination = archive.join(relative);
+    
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to create archive directory: {}", e),
+                Some(parent.to_path_buf())
+            ))?;
+    }
+    
+    if std::fs::rename(path, &destination).is_err() {
+        // Different filesystem: copy, keep the mtime the delete tier is measured from, then remove
+        let mtime = filetime::FileTime::from_last_modification_time(&std::fs::metadata(path)?);
+        std::fs::copy(path, &destination)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to copy file into the archive: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        filetime::set_file_mtime(&destination, mtime)?;
+        std::fs::remove_file(path)?;
+    }
+    
+    Ok(destination)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filetime::FileTime;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_files_advance_through_tiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        let archive = temp_dir.path().join("archive");
+        fs::create_dir_all(&cache).unwrap();
+        
+        let now = SystemTime::now();
+        let days = |days: u64| Duration::from_secs(days * 24 * 3600);
+        for (name, age) in [("new.bin", 1), ("stale.bin", 40), ("old.bin", 90), ("ancient.bin", 200)] {
+            let path = cache.join(name);
+            fs::write(&path, vec![0u8; 10]).unwrap();
+            filetime::set_file_mtime(&path, FileTime::from_system_time(now - days(age))).unwrap();
+        }
+        
+        let config = ClearModelConfig::default();
+        let group = TierGroup {
+            name: "ml",
+            tiers: Tiers::from_config(&config.lifecycle.ml),
+            roots: vec![cache.clone()],
+            policy: CleanupPolicy::from_config(&config),
+            stay_on_filesystem: false,
+        };
+        assert_eq!(group.tiers.to_string(), "warn 30d -> archive 60d -> delete 120d");
+        
+        let (warned, archived, errors) = advance_root(&cache, &group, &archive, false);
+        assert_eq!((warned.files, archived.files, errors), (1, 1, 0));
+        assert!(!cache.join("old.bin").exists());
+        
+        // Archived files keep their age so the delete tier still applies to them
+        let moved = archive.join(cache.strip_prefix("/").unwrap()).join("old.bin");
+        let mtime = fs::metadata(&moved).unwrap().modified().unwrap();
+        assert_eq!(group.tiers.stage_for(now.duration_since(mtime).unwrap()), Some(Stage::Archive));
+        assert!(cache.join("ancient.bin").exists());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{warn, Event, Level, Metadata};
+use tracing_subscriber::layer::{Context, Filter};
+
+/// Placeholder substituted for path-like tokens when grouping messages
+const PATH_PLACEHOLDER: &str = "<path>";
+
+/// A message that was repeated, with the first and last paths it mentioned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepeatedMessage {
+    pub message: String,
+    pub count: usize,
+    pub first_path: Option<String>,
+    pub last_path: Option<String>,
+}
+
+impl fmt::Display for RepeatedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ×{}", self.message, self.count)?;
+        match (&self.first_path, &self.last_path) {
+            (Some(first), Some(last)) if first != last => write!(f, " (first: {}, last: {})", first, last),
+            (Some(first), _) => write!(f, " (path: {})", first),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Groups messages that only differ in the paths they mention
+///
+/// Up to `threshold` occurrences of each message are let through; the rest are
+/// counted and reported as a single summary line. A threshold of 0 disables
+/// deduplication.
+#[derive(Debug, Default)]
+pub struct ErrorDeduplicator {
+    threshold: usize,
+    groups: HashMap<String, RepeatedMessage>,
+    order: Vec<String>,
+}
+
+impl ErrorDeduplicator {
+    /// Create a deduplicator that collapses messages seen more than `threshold` times
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            ..Self::default()
+        }
+    }
+    
+    /// Record a message, returning whether it should still be shown on its own
+    pub fn record(&mut self, message: &str) -> bool {
+        if self.threshold == 0 {
+            return true;
+        }
+        
+        let (key, path) = Self::normalize(message);
+        let group = self.groups.entry(key.clone()).or_insert_with(|| {
+            self.order.push(key.clone());
+            RepeatedMessage {
+                message: key,
+                count: 0,
+                first_path: path.clone(),
+                last_path: None,
+            }
+        });
+        
+        group.count += 1;
+        if path.is_some() {
+            group.last_path = path;
+        }
+        
+        group.count <= self.threshold
+    }
+    
+    /// Messages that went over the threshold, in first-seen order
+    pub fn suppressed(&self) -> Vec<RepeatedMessage> {
+        self.order.iter()
+            .filter_map(|key| self.groups.get(key))
+            .filter(|group| self.threshold > 0 && group.count > self.threshold)
+            .cloned()
+            .collect()
+    }
+    
+    /// Collapse a list of messages for a report, summarising groups over the threshold
+    pub fn collapse(messages: &[String], threshold: usize) -> Vec<String> {
+        let mut dedup = Self::new(threshold);
+        let mut shown: Vec<String> = messages.iter()
+            .filter(|message| dedup.record(message))
+            .cloned()
+            .collect();
+        
+        // Replace the individually shown occurrences of large groups with one summary line
+        let suppressed = dedup.suppressed();
+        shown.retain(|message| {
+            let (key, _) = Self::normalize(message);
+            !suppressed.iter().any(|group| group.message == key)
+        });
+        shown.extend(suppressed.iter().map(|group| group.to_string()));
+        
+        shown
+    }
+    
+    /// Replace path-like tokens so messages about different files group together
+    fn normalize(message: &str) -> (String, Option<String>) {
+        let mut first_path = None;
+        let tokens: Vec<String> = message.split(' ')
+            .map(|token| {
+                let trimmed = token.trim_matches(|c: char| "\"'()[]{},:;".contains(c));
+                if trimmed.contains('/') || trimmed.contains('\\') {
+                    if first_path.is_none() {
+                        first_path = Some(trimmed.to_string());
+                    }
+                    token.replace(trimmed, PATH_PLACEHOLDER)
+                } else {
+                    token.to_string()
+                }
+            })
+            .collect();
+        
+        (tokens.join(" "), first_path)
+    }
+}
+
+/// Tracing filter that applies [`ErrorDeduplicator`] to console warnings and errors
+#[derive(Clone)]
+pub struct LogDeduplicator {
+    inner: Arc<Mutex<ErrorDeduplicator>>,
+}
+
+impl LogDeduplicator {
+    /// Create a filter collapsing messages seen more than `threshold` times
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ErrorDeduplicator::new(threshold))),
+        }
+    }
+    
+    /// Change the threshold once the configuration has been loaded
+    pub fn set_threshold(&self, threshold: usize) {
+        if let Ok(mut dedup) = self.inner.lock() {
+            dedup.threshold = threshold;
+        }
+    }
+    
+    /// Log one summary line per suppressed message group
+    pub fn flush(&self) {
+        let suppressed = match self.inner.lock() {
+            Ok(mut dedup) => {
+                let suppressed = dedup.suppressed();
+                *dedup = ErrorDeduplicator::new(dedup.threshold);
+                suppressed
+            }
+            Err(_) => return,
+        };
+        
+        for group in suppressed {
+            warn!("{}", group);
+        }
+    }
+}
+
+impl<S> Filter<S> for LogDeduplicator {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+    
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        // Only warnings and errors are deduplicated
+        if *event.metadata().level() > Level::WARN {
+            return true;
+        }
+        
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        
+        match self.inner.lock() {
+            Ok(mut dedup) => dedup.record(&visitor.message),
+            Err(_) => true,
+        }
+    }
+}
+
+/// Extracts the formatted `message` field of an event
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_messages_differing_by_path_are_grouped() {
+        let mut dedup = ErrorDeduplicator::new(2);
+        
+        for i in 0..5 {
+            let shown = dedup.record(&format!("Failed to delete file: Permission denied (path: \"/cache/blob{}\")", i));
+            assert_eq!(shown, i < 2);
+        }
+        
+        let suppressed = dedup.suppressed();
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].count, 5);
+        assert_eq!(suppressed[0].first_path.as_deref(), Some("/cache/blob0"));
+        assert_eq!(suppressed[0].last_path.as_deref(), Some("/cache/blob4"));
+        assert_eq!(
+            suppressed[0].to_string(),
+            "Failed to delete file: Permission denied (path: \"<path>\") ×5 (first: /cache/blob0, last: /cache/blob4)"
+        );
+    }
+    
+    #[test]
+    fn test_collapse_keeps_rare_messages() {
+        let mut messages: Vec<String> = (0..10)
+            .map(|i| format!("Permission denied: /cache/{}", i))
+            .collect();
+        messages.push("Failed to process directory: timeout".to_string());
+        
+        let collapsed = ErrorDeduplicator::collapse(&messages, 3);
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(collapsed[0], "Failed to process directory: timeout");
+        assert!(collapsed[1].starts_with("Permission denied: <path> ×10"));
+    }
+    
+    #[test]
+    fn test_zero_threshold_disables_deduplication() {
+        let mut dedup = ErrorDeduplicator::new(0);
+        assert!((0..100).all(|_| dedup.record("same message")));
+        assert!(dedup.suppressed().is_empty());
+    }
+}
\ No newline at end of file
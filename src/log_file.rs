@@ -0,0 +1,224 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::level_filters::LevelFilter;
+use tracing::Metadata;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Filter};
+
+use crate::config::LoggingConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Name of the file being written; rotated files get `.1`, `.2`, ... appended, newest first
+const LOG_NAME: &str = "clearmodel.log";
+
+/// When log files are started afresh, besides reaching `rotation.max_size_mb`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationPeriod {
+    Hourly,
+    #[default]
+    Daily,
+    
+    /// Only by size
+    Never,
+}
+
+impl RotationPeriod {
+    /// Index of the period `time` falls in (UTC), or 0 when files are never rotated by age
+    fn index(self, time: SystemTime) -> u64 {
+        let secs = time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+        match self {
+            RotationPeriod::Hourly => secs / 3600,
+            RotationPeriod::Daily => secs / 86_400,
+            RotationPeriod::Never => 0,
+        }
+    }
+}
+
+/// Log file output, switched on once the configuration has been loaded
+///
+/// Logging starts before the configuration is read, so the layer writing files is
+/// installed from the start with nothing to write to; [`LogFile::open`] points it at
+/// `logging.directory` later. Only clearmodel's own events are written, down to
+/// `logging.file_level`, whatever the console shows.
+#[derive(Clone, Default)]
+pub struct LogFile {
+    file: Arc<Mutex<Option<RotatingFile>>>,
+    
+    /// Most verbose level written, as `level_rank` numbers it; 0 while no file is open
+    level: Arc<AtomicUsize>,
+}
+
+impl LogFile {
+    /// A log file output that writes nothing until opened
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Start writing to the log directory when `logging.file` is set
+    pub fn open(&self, logging: &LoggingConfig) -> Result<()> {
+        if !logging.file {
+            return Ok(());
+        }
+        let level: LevelFilter = logging.file_level.parse()
+            .map_err(|_| ClearModelError::configuration(format!("Invalid logging.file_level {:?}", logging.file_level)))?;
+        let dir = logging.directory.clone().or_else(log_dir).ok_or_else(|| ClearModelError::environment(
+            "Cannot determine the state directory for log files; set logging.directory".to_string()
+        ))?;
+        
+        let file = RotatingFile::open(&dir, logging.rotation.max_size_mb * 1_048_576, logging.rotation.period, logging.rotation.keep)
+            .map_err(|e| ClearModelError::file_operation(format!("Failed to open the log file: {}", e), Some(dir.join(LOG_NAME))))?;
+        *self.file.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+        self.level.store(filter_rank(level), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Orders levels from 1 (error) to 5 (trace), so a level is written when its rank is at most the filter's
+fn level_rank(level: &tracing::Level) -> usize {
+    match *level {
+        tracing::Level::ERROR => 1,
+        tracing::Level::WARN => 2,
+        tracing::Level::INFO => 3,
+        tracing::Level::DEBUG => 4,
+        tracing::Level::TRACE => 5,
+    }
+}
+
+fn filter_rank(filter: LevelFilter) -> usize {
+    filter.into_level().map(|level| level_rank(&level)).unwrap_or(0)
+}
+
+impl<S> Filter<S> for LogFile {
+    fn enabled(&self, meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        level_rank(meta.level()) <= self.level.load(Ordering::Relaxed) && meta.target().starts_with("clearmodel")
+    }
+    
+    /// The level changes once the configuration is loaded, so no callsite is settled for good
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> tracing::subscriber::Interest {
+        tracing::subscriber::Interest::sometimes()
+    }
+    
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::TRACE)
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFile {
+    type Writer = LogFileWriter<'a>;
+    
+    fn make_writer(&'a self) -> Self::Writer {
+        LogFileWriter(self)
+    }
+}
+
+/// Writes formatted events to the current log file, rotating it first when it is due
+pub struct LogFileWriter<'a>(&'a LogFile);
+
+impl Write for LogFileWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.0.file.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            Some(file) => file.write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+    
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0.file.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            Some(file) => file.file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `clearmodel.log` in a directory, moved to `clearmodel.log.1` (and older files one
+/// number up) once it grows past `max_bytes` or its period ends
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    period: RotationPeriod,
+    
+    /// Period the current file was started in
+    started: u64,
+    
+    /// Rotated files kept
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path, max_bytes: u64, period: RotationPeriod, keep: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        // A file left by an earlier run belongs to the period it was last written in
+        let started = period.index(metadata.modified().unwrap_or_else(|_| SystemTime::now()));
+        Ok(Self { dir: dir.to_path_buf(), file, size: metadata.len(), max_bytes, period, started, keep })
+    }
+    
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = self.period.index(SystemTime::now());
+        let full = self.max_bytes > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_bytes;
+        if full || now != self.started {
+            self.rotate(now)?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+    
+    fn rotate(&mut self, now: u64) -> io::Result<()> {
+        let numbered = |n: usize| self.dir.join(format!("{}.{}", LOG_NAME, n));
+        if self.keep == 0 {
+            std::fs::remove_file(self.dir.join(LOG_NAME))?;
+        } else {
+            // Another process may have rotated already, so missing files are fine
+            let _ = std::fs::remove_file(numbered(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = std::fs::rename(numbered(n), numbered(n + 1));
+            }
+            std::fs::rename(self.dir.join(LOG_NAME), numbered(1))?;
+        }
+        
+        self.file = OpenOptions::new().create(true).append(true).open(self.dir.join(LOG_NAME))?;
+        self.size = 0;
+        self.started = now;
+        Ok(())
+    }
+}
+
+/// Default location of log files (`logs` in the state directory)
+pub fn log_dir() -> Option<PathBuf> {
+    state::path(state::LOGS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_files_rotate_by_size_keeping_the_newest() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut file = RotatingFile::open(temp_dir.path(), 10, RotationPeriod::Never, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write(line.as_bytes()).unwrap();
+        }
+        
+        let read = |name: &str| std::fs::read_to_string(temp_dir.path().join(name)).unwrap();
+        assert_eq!(read("clearmodel.log"), "fourth\n");
+        assert_eq!(read("clearmodel.log.1"), "third\n");
+        assert_eq!(read("clearmodel.log.2"), "second\n");
+        // Only `keep` rotated files are kept
+        assert!(!temp_dir.path().join("clearmodel.log.3").exists());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,124 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::Metadata;
+use tracing_subscriber::layer::{Context, Filter};
+
+/// How log lines are written, on the console and to log files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Timestamp, level and message, for people
+    #[default]
+    Text,
+    
+    /// One JSON object per line (`timestamp`, `level`, `target`, `fields`), for journald,
+    /// fluentd or Loki
+    Json,
+}
+
+/// The log format in use, switched once the configuration has been loaded
+///
+/// The fmt layer's format is fixed when logging starts, before the configuration is read,
+/// so a text and a JSON layer are both installed and [`FormatSwitch::only`] lets events
+/// through to the one matching the current format.
+#[derive(Clone, Default)]
+pub struct FormatSwitch {
+    json: Arc<AtomicBool>,
+}
+
+impl FormatSwitch {
+    /// A switch starting out on text
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn set(&self, format: LogFormat) {
+        self.json.store(format == LogFormat::Json, Ordering::Relaxed);
+    }
+    
+    pub fn current(&self) -> LogFormat {
+        if self.json.load(Ordering::Relaxed) {
+            LogFormat::Json
+        } else {
+            LogFormat::Text
+        }
+    }
+    
+    /// A filter for the layer writing `format`
+    pub fn only(&self, format: LogFormat) -> FormatFilter {
+        FormatFilter { switch: self.clone(), format }
+    }
+}
+
+/// Lets events through while the switch is on its format
+#[derive(Clone)]
+pub struct FormatFilter {
+    switch: FormatSwitch,
+    format: LogFormat,
+}
+
+impl<S> Filter<S> for FormatFilter {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        self.switch.current() == self.format
+    }
+    
+    /// The format changes once the configuration is loaded, so no callsite is settled for good
+    fn callsite_enabled(&self, _meta: &'static Metadata<'static>) -> tracing::subscriber::Interest {
+        tracing::subscriber::Interest::sometimes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tracing_subscriber::fmt::MakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::Layer;
+    
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+    
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+        
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+    
+    #[test]
+    fn test_switching_to_json_writes_json_lines() {
+        let switch = FormatSwitch::new();
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(buffer.clone()).with_filter(switch.only(LogFormat::Text)))
+            .with(tracing_subscriber::fmt::layer().json().with_writer(buffer.clone()).with_filter(switch.only(LogFormat::Json)));
+        
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("as text");
+            switch.set(LogFormat::Json);
+            tracing::info!(bytes = 42, "as json");
+        });
+        
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("as text") && !lines[0].starts_with('{'));
+        let json: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!((json["level"].as_str(), json["fields"]["message"].as_str()), (Some("INFO"), Some("as json")));
+        assert_eq!(json["fields"]["bytes"].as_u64(), Some(42));
+    }
+}
\ No newline at end of file
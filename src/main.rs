@@ -1,18 +1,72 @@
 use anyhow::Result;
-use clap::Parser;
-use tracing::{info, error};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, error, warn};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
-mod config;
-mod environment;
-mod cache_cleaner;
-mod resource_manager;
-mod security;
-mod errors;
-
-use config::ClearModelConfig;
-use environment::EnvironmentManager;
-use cache_cleaner::CacheCleaner;
+use clearmodel::allocation::SizeBasis;
+use clearmodel::analysis::{self, VolumeReport};
+use clearmodel::audit;
+use clearmodel::cancel;
+use clearmodel::capabilities::{self, Capabilities};
+use clearmodel::checkpoint::{self, Checkpoint};
+use clearmodel::command_runner::CommandRunner;
+use clearmodel::config::{ClearModelConfig, LoggingConfig};
+use clearmodel::errors::{ClearModelError, EXIT_FAILURE};
+#[cfg(feature = "daemon")]
+use clearmodel::daemon::{self, ControlRequest, ControlResponse};
+use clearmodel::data_caches::DataCacheKind;
+use clearmodel::environment::EnvironmentManager;
+use clearmodel::events::{EventFormat, EventSink};
+use clearmodel::export::{self, ExportRow, OutputFormat, RemovalLog};
+use clearmodel::experiments;
+use clearmodel::dedup::{self, LinkMode};
+use clearmodel::health::{self, CacheHealth};
+#[cfg(feature = "history")]
+use clearmodel::history::{self, History};
+use clearmodel::hf_caches;
+use clearmodel::hf_datasets;
+use clearmodel::hf_revisions;
+use clearmodel::image_models;
+use clearmodel::init;
+use clearmodel::gguf;
+use clearmodel::hf_repair::{self, RepairAction};
+use clearmodel::journal;
+#[cfg(feature = "keyring")]
+use clearmodel::keyring;
+use clearmodel::lifecycle;
+use clearmodel::log_dedup::LogDeduplicator;
+use clearmodel::log_file::LogFile;
+use clearmodel::log_format::{FormatSwitch, LogFormat};
+use clearmodel::models::{self, ModelSource, PinnedModels};
+use clearmodel::netfs::NetworkFsMode;
+use clearmodel::notifications;
+use clearmodel::bench::{self, BenchReport};
+use clearmodel::cache_cleaner::CacheCleaner;
+use clearmodel::policy::{AgeBasis, CleanupPolicy, ConflictMode, EvictionPolicy};
+use clearmodel::prescan;
+use clearmodel::top::{self, TopOrder};
+use clearmodel::prompt;
+use clearmodel::progress::ProgressReporter;
+use clearmodel::quarantine;
+use clearmodel::rate_limit;
+#[cfg(feature = "remote")]
+use clearmodel::remote_config;
+use clearmodel::report::{ResultTarget, RunReport};
+use clearmodel::resource_manager::CleanupResult;
+use clearmodel::security::SecurityManager;
+use clearmodel::restore;
+use clearmodel::run_lock::{PassLock, RunLock};
+use clearmodel::scheduler::{self, Frequency, Schedule};
+use clearmodel::support::{self, SupportBundle};
+use clearmodel::state;
+use clearmodel::targets::{self, TargetFilter};
+use clearmodel::trace;
+use clearmodel::units;
+#[cfg(feature = "usage")]
+use clearmodel::usage;
 
 #[derive(Parser)]
 #[command(name = "clearmodel")]
@@ -20,68 +74,2352 @@ use cache_cleaner::CacheCleaner;
 #[command(version = "0.1.0")]
 struct Cli {
     /// Enable debug logging
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     debug: bool,
     
-    /// Configuration file path
-    #[arg(short, long)]
+    /// Configuration file path, or an https:// URL to download it from on each run
+    #[arg(short, long, global = true)]
     config: Option<String>,
     
+    /// Digest a remote --config must have (`blake3:<hex>`)
+    #[cfg(feature = "remote")]
+    #[arg(long, global = true, env = "CLEARMODEL_CONFIG_CHECKSUM", value_name = "DIGEST")]
+    config_checksum: Option<String>,
+    
+    /// OpenSSH allowed signers file that the signature of a remote --config (`<URL>.sig`) must verify against
+    #[cfg(feature = "remote")]
+    #[arg(long, global = true, env = "CLEARMODEL_CONFIG_SIGNERS", value_name = "FILE")]
+    config_signers: Option<PathBuf>,
+    
+    /// Apply the `[profiles.<NAME>]` table of the configuration file over its base settings
+    #[arg(long, global = true, env = "CLEARMODEL_PROFILE", value_name = "NAME")]
+    profile: Option<String>,
+    
     /// Dry run - show what would be cleaned without actually cleaning
-    #[arg(short = 'n', long)]
+    #[arg(short = 'n', long, global = true)]
     dry_run: bool,
     
     /// Verbose output
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
+    
+    /// Only clean files at least this large (e.g. `100M`, `2G`), overriding `min_file_size_mb`
+    #[arg(long, global = true, visible_alias = "min-size", value_name = "SIZE", value_parser = units::parse_size)]
+    larger_than: Option<u64>,
+    
+    /// Remove files older than this (e.g. `30d`, `12h`), overriding every configured age limit
+    #[arg(long, global = true, value_name = "DURATION", value_parser = units::parse_duration)]
+    older_than: Option<std::time::Duration>,
+    
+    /// Leave files at least this old alone (e.g. `7d`), to clean only recent downloads
+    #[arg(long, global = true, value_name = "DURATION", value_parser = units::parse_duration)]
+    newer_than: Option<std::time::Duration>,
+    
+    /// Clean cache roots on network filesystems (NFS, SMB, FUSE), which are skipped by default
+    #[arg(long, global = true)]
+    allow_network_fs: bool,
+    
+    /// Report logical file sizes instead of the space files take up on disk (`size_basis = "apparent"`)
+    #[arg(long, global = true)]
+    apparent_size: bool,
+    
+    /// Also remove files owned by other users (normally left alone), e.g. when run as root
+    #[arg(long, global = true)]
+    include_other_users: bool,
+    
+    /// Remove the selected files that need administrator rights through the privilege backend
+    #[arg(long, global = true)]
+    privileged: bool,
+    
+    /// Cooperate with other users' instances on shared caches: lock each model directory and leave their files alone
+    #[arg(long, global = true)]
+    shared: bool,
+    
+    /// With --shared, remove every user's files from the shared caches (root only)
+    #[arg(long, global = true)]
+    all_users: bool,
+    
+    /// When another instance is cleaning, wait for it to finish instead of failing
+    #[arg(long, global = true)]
+    wait: bool,
+    
+    /// Exit with status 2 when any file or directory could not be cleaned, instead of 0
+    #[arg(long, global = true)]
+    fail_on_error: bool,
+    
+    /// Quiet mode - only report errors and hide progress bars
+    #[arg(short, long, global = true, conflicts_with_all = ["debug", "verbose"])]
+    quiet: bool,
+    
+    /// List the features and backends compiled into this binary and exit
+    #[arg(long)]
+    capabilities: bool,
+    
+    /// Enable debug tracing for specific subsystems only (e.g. `security,scanner`)
+    #[arg(long, global = true, value_delimiter = ',', value_name = "SUBSYSTEMS")]
+    trace: Vec<String>,
+    
+    /// Emit one JSON object per lifecycle event (logs move to stderr when events go to stdout)
+    #[arg(long, global = true, value_name = "FORMAT")]
+    events: Option<EventFormat>,
+    
+    /// Write events to this file or named pipe instead of stdout
+    #[arg(long, global = true, value_name = "PATH", requires = "events")]
+    events_to: Option<PathBuf>,
+    
+    /// Write the structured result as JSON at exit, even after a failure (a path, or `fd:N`)
+    #[arg(long, global = true, value_name = "PATH")]
+    result_file: Option<ResultTarget>,
+    
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Clean all configured caches (default when no command is given)
+    Clean {
+        /// Only clean these targets or groups (e.g. `huggingface,torch`, `ml`, `python`, `pip,uv`, `package_caches`, `compile-caches`, `engine-caches`, `gpu-caches`, `data-caches`, `gguf`, `image-models`, `experiments`, `notebooks`, `containers`)
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+        
+        /// Skip these targets or groups, even when `--only` selects them
+        #[arg(long = "exclude-target", value_delimiter = ',')]
+        exclude_targets: Vec<String>,
+        
+        /// Finish an interrupted run from its checkpoint instead of scanning again
+        #[arg(long)]
+        resume: bool,
+        
+        /// Print every file removed as `csv`, with its target, size, age and the action taken
+        #[arg(long, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
+    },
+    
+    /// Watch cache directories and re-apply the cleanup policy when downloads complete
+    #[cfg(feature = "daemon")]
+    Watch,
+    
+    /// Stay resident and clean when free space drops below `min_free_space_gb`
+    #[cfg(feature = "daemon")]
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonCommand>,
+    },
+    
+    /// Explain what is filling the filesystem that holds PATH (read-only)
+    WhyFull {
+        /// Mount point or any directory on the filesystem to analyse
+        path: PathBuf,
+        
+        /// Group directories this many levels below PATH
+        #[arg(long, default_value_t = 2)]
+        depth: usize,
+        
+        /// Number of non-cache directories to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    
+    /// Refresh the size index at background priority and exit (for login hooks)
+    Prescan,
+    
+    /// Report cache sizes from the size index, re-listing only directories that changed
+    Scan {
+        /// Largest subdirectories to show under each cache root
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Show the snapshot revisions of each HuggingFace repository with their sizes instead
+        #[arg(long)]
+        revisions: bool,
+        
+        /// Show each cached HuggingFace dataset with the size of every config and version instead
+        #[arg(long, conflicts_with = "revisions")]
+        datasets: bool,
+        
+        /// Print the cache roots and their largest subdirectories as `csv`
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["json", "revisions", "datasets"])]
+        output: Option<OutputFormat>,
+    },
+    
+    /// Show the largest models and directories across every cache, to pick what to delete by hand
+    Top {
+        /// Number of entries to show (`-n` is taken by `--dry-run`)
+        #[arg(short = 'N', long, default_value_t = 20)]
+        count: usize,
+        
+        /// Order the entries by `size` (largest first) or `age` (longest unused first)
+        #[arg(long, default_value_t = TopOrder::Size)]
+        sort: TopOrder,
+        
+        /// Group the entries under their cache roots, like `du`
+        #[arg(long, conflicts_with = "json")]
+        tree: bool,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Print the entries as `csv`
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["json", "tree"])]
+        output: Option<OutputFormat>,
+    },
+    
+    /// List the managed cache directories and their sizes
+    List {
+        /// Score each cache by stale data, interrupted downloads, duplicates and growth
+        #[arg(long)]
+        health: bool,
+        
+        /// Print the health report as JSON
+        #[arg(long, requires = "health")]
+        json: bool,
+    },
+    
+    /// Report identical large files across the cache roots, and optionally replace copies with links
+    Dedup {
+        /// Only consider files at least this large
+        #[arg(long, default_value_t = 64, value_name = "MB")]
+        min_size_mb: u64,
+        
+        /// Replace duplicates with links to one copy: `hardlink` or `reflink` (nothing is deleted)
+        #[arg(long, value_name = "MODE")]
+        link: Option<LinkMode>,
+    },
+    
+    /// Measure scan and delete throughput of the current settings on a generated cache
+    Bench {
+        /// Number of files to generate (e.g. `100K`, `1M`)
+        #[arg(long, value_name = "COUNT", value_parser = units::parse_count, default_value = "100K")]
+        synthetic: u64,
+        
+        /// Directory to generate the cache in, on the storage to measure (defaults to the temp directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// List past cleanup runs recorded in the history database
+    #[cfg(feature = "history")]
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryCommand>,
+        
+        /// Number of runs to list
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    
+    /// Find HuggingFace snapshots whose links point at missing blobs, and optionally repair them
+    Repair {
+        /// Remove the broken snapshots and refs pointing at them (blobs are left alone)
+        #[arg(long, conflicts_with = "refetch")]
+        remove: bool,
+        
+        /// Download the missing blobs again with `huggingface-cli`
+        #[arg(long)]
+        refetch: bool,
+    },
+    
+    /// Pin a model so it is never deleted, or list pinned models when no pattern is given
+    Pin {
+        /// Model name or glob pattern (e.g. `meta-llama/Llama-3-8B`, `llama3:*`)
+        pattern: Option<String>,
+    },
+    
+    /// Remove a model pattern from the keep-list
+    Unpin {
+        /// Pattern exactly as it was pinned
+        pattern: String,
+    },
+    
+    /// List cached models, or remove one model's files without touching the rest of the cache
+    Models {
+        #[command(subcommand)]
+        action: ModelsCommand,
+    },
+    
+    /// Restore the files removed by the last cleanup run
+    Undo,
+    
+    /// Permanently delete everything in quarantine
+    Purge,
+    
+    /// Show or wipe clearmodel's own data (history, size index, journals, logs)
+    State {
+        #[command(subcommand)]
+        action: StateCommand,
+    },
+    
+    /// Check the audit log of deletions for tampering
+    Audit {
+        #[command(subcommand)]
+        action: AuditCommand,
+    },
+    
+    /// Capture a sanitized support bundle, or replay one to reproduce its cleanup decisions
+    SupportBundle {
+        /// Where to write the bundle (defaults to ./clearmodel-support-<timestamp>.tar.gz)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        
+        /// Replay a bundle instead of capturing one (uses the bundled config unless --config is given)
+        #[arg(long, value_name = "BUNDLE", conflicts_with = "output")]
+        replay: Option<PathBuf>,
+    },
+    
+    /// Print the configuration schema or check a configuration file against it
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    
+    /// Inspect the cleanup policy
+    Policy {
+        #[command(subcommand)]
+        action: PolicyCommand,
+    },
+    
+    /// Run cleanups periodically with the OS scheduler (systemd, launchd or Task Scheduler)
+    Schedule {
+        #[command(subcommand)]
+        action: ScheduleCommand,
+    },
+    
+    /// Keep the sudo password in the OS keyring so privileged steps don't prompt
+    #[cfg(feature = "keyring")]
+    Auth {
+        #[command(subcommand)]
+        action: AuthCommand,
+    },
+    
+    /// Record which cache files are opened, and show the most and least used models
+    #[cfg(feature = "usage")]
+    Usage {
+        #[command(subcommand)]
+        action: UsageCommand,
+    },
+    
+    /// Report which platform capabilities are available in this environment
+    Doctor,
+    
+    /// Print version information
+    Version {
+        /// Emit JSON including the detected capability set
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// Show each cached model (HuggingFace repositories, Ollama models, torch hub entries) with its size
+    List {
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Remove every file of the models matching a name or glob (e.g. `org/model`, `llama3:*`)
+    Remove {
+        pattern: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Check every entry's MAC and its link to the previous entry
+    Verify {
+        /// Log to check (defaults to `audit.path`)
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// List what clearmodel keeps in its state directory, with sizes
+    Show,
+    
+    /// Delete clearmodel's history, size index, journals, checkpoint and logs
+    Clean {
+        /// Delete the audit log and its key too
+        #[arg(long)]
+        include_audit: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum PolicyCommand {
+    /// Show the effective policy, including the order in which conflicting rules win
+    Show,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Detect the caches on this machine, ask a few questions and write a commented configuration file
+    Init {
+        /// Replace an existing file
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Print the JSON Schema of the configuration file, for editors and CI
+    Schema,
+    
+    /// Check a configuration file (the one in use by default), reporting where each problem is
+    Validate {
+        path: Option<PathBuf>,
+    },
+}
+
+#[cfg(feature = "history")]
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// Show what a run removed, per directory, and the cache sizes after it
+    Show {
+        /// Run id, as listed by `clearmodel history`
+        id: i64,
+    },
+    
+    /// Compare cache sizes after two runs (the oldest and newest by default)
+    Diff {
+        from: Option<i64>,
+        to: Option<i64>,
+    },
+}
+
+#[cfg(feature = "usage")]
+#[derive(Subcommand)]
+enum UsageCommand {
+    /// Show models by how often their files were opened since tracking started
+    Top {
+        /// Least used first, starting with models never opened
+        #[arg(long)]
+        least: bool,
+        
+        /// Number of models to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Record file opens in the foreground until interrupted (the daemon does this with `usage.track`)
+    Track,
+}
+
+#[cfg(feature = "keyring")]
+#[derive(Subcommand)]
+enum AuthCommand {
+    /// Ask for the sudo password and save it to the keyring
+    Store,
+    
+    /// Remove the saved sudo password
+    Forget,
+}
+
+#[cfg(feature = "daemon")]
+#[derive(Subcommand)]
+enum DaemonCommand {
+    /// Ask the running daemon for its status
+    Status,
+    
+    /// Ask the running daemon to clean now
+    TriggerNow,
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommand {
+    /// Install (or replace) the scheduled cleanup
+    Install {
+        /// How often to clean: hourly, daily, weekly or monthly
+        #[arg(long, default_value = "weekly")]
+        every: Frequency,
+    },
+    
+    /// Show whether a scheduled cleanup is installed and when it runs next
+    Status,
+    
+    /// Remove the scheduled cleanup
+    Remove,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+async fn main() {
+    if let Err(e) = run().await {
+        match e.downcast_ref::<ClearModelError>() {
+            Some(e) => eprintln!("{}", e.render()),
+            None => eprintln!("Error: {:?}", e),
+        }
+        let code = e.downcast_ref::<ClearModelError>().map(ClearModelError::exit_code).unwrap_or(EXIT_FAILURE);
+        std::process::exit(code);
+    }
+}
+
+async fn run() -> Result<()> {
+    let mut cli = Cli::parse();
     
-    // Initialize logging
-    init_logging(cli.debug, cli.verbose)?;
+    // Initialize logging; events and CSV on stdout push the logs to stderr
+    let events_on_stdout = cli.events.is_some() && cli.events_to.is_none();
+    let csv_on_stdout = matches!(
+        &cli.command,
+        Some(Command::Clean { output: Some(_), .. } | Command::Scan { output: Some(_), .. } | Command::Top { output: Some(_), .. })
+    );
+    let (log_dedup, log_file, log_format) = init_logging(cli.debug, cli.verbose, cli.quiet, &cli.trace, events_on_stdout || csv_on_stdout)?;
     
     info!("Starting clearmodel - ML cache cleaner");
     
-    // Load environment and configuration
-    let env_manager = EnvironmentManager::new().await?;
-    let config = ClearModelConfig::load(cli.config.as_deref()).await?;
+    if cli.capabilities {
+        print_compiled_backends();
+        return Ok(());
+    }
+    
+    // A remote configuration is downloaded and verified once, then used as a local file
+    let remote_config = cli.config.clone().filter(|config| config.starts_with("https://") || config.starts_with("http://"));
+    if let Some(url) = &remote_config {
+        cli.config = Some(fetch_remote_config(url, &cli)?);
+    }
     
-    // Initialize cache cleaner
-    let cache_cleaner = CacheCleaner::new(config, env_manager).await?;
+    let capabilities = Capabilities::detect();
+    let command = cli.command.take().unwrap_or(Command::Clean { only: Vec::new(), exclude_targets: Vec::new(), resume: false, output: None });
+    // Usage tracking skips opens while a pass is marked; long-running commands mark each pass
+    let _pass = marks_pass(&command).then(PassLock::acquire).flatten();
     
-    // Perform cache cleaning
-    match cache_cleaner.clean_all_caches(cli.dry_run).await {
-        Ok(_) => {
-            info!("Model cache cleaning completed successfully!");
+    // Informational commands don't need the environment or a cleaner
+    match command {
+        Command::Doctor => {
+            print_capability_report(&capabilities);
+            return Ok(());
+        }
+        Command::Version { json } => {
+            print_version(&capabilities, json)?;
+            return Ok(());
+        }
+        Command::Pin { pattern: Some(_) } | Command::Unpin { .. } | Command::Config { action: ConfigCommand::Init { .. } } if remote_config.is_some() => {
+            anyhow::bail!("The configuration is managed remotely ({}); change it at the source", remote_config.unwrap_or_default());
+        }
+        Command::Pin { pattern } => {
+            return update_pins(cli.config.as_deref(), pattern.as_deref(), true).await;
+        }
+        Command::Unpin { pattern } => {
+            return update_pins(cli.config.as_deref(), Some(&pattern), false).await;
+        }
+        Command::Models { action: ModelsCommand::List { json } } => {
+            let config = load_config(&cli).await?;
+            return list_models(&config, json);
+        }
+        Command::Undo => {
+            let _lock = run_lock(&cli)?;
+            return undo_last_run(cli.dry_run);
+        }
+        Command::Purge => {
+            let _lock = run_lock(&cli)?;
+            return purge_quarantine(cli.dry_run);
+        }
+        Command::State { action: StateCommand::Show } => {
+            return show_state();
         }
+        Command::State { action: StateCommand::Clean { include_audit } } => {
+            let _lock = run_lock(&cli)?;
+            return clean_state(include_audit, cli.dry_run);
+        }
+        Command::Config { action: ConfigCommand::Init { force } } => {
+            return init_config(cli.config.as_deref(), force);
+        }
+        Command::Config { action: ConfigCommand::Schema } => {
+            println!("{}", serde_json::to_string_pretty(&ClearModelConfig::json_schema())?);
+            return Ok(());
+        }
+        Command::Config { action: ConfigCommand::Validate { path } } => {
+            let path = path.unwrap_or_else(|| ClearModelConfig::writable_config_path(cli.config.as_deref()));
+            return validate_config(&path).await;
+        }
+        Command::Audit { action: AuditCommand::Verify { path } } => {
+            let config = load_config(&cli).await?;
+            return verify_audit_log(&config, path);
+        }
+        Command::Policy { action: PolicyCommand::Show } => {
+            let config = load_config(&cli).await?;
+            print_policy(&config);
+            return Ok(());
+        }
+        #[cfg(feature = "daemon")]
+        Command::Daemon { action: Some(action) } => {
+            let request = match action {
+                DaemonCommand::Status => ControlRequest::Status,
+                DaemonCommand::TriggerNow => ControlRequest::TriggerNow,
+            };
+            return control_daemon(request, cli.config.as_deref(), cli.profile.as_deref()).await;
+        }
+        Command::WhyFull { path, depth, top } => {
+            let config = load_config(&cli).await?;
+            let report = analysis::why_full(&path, &config, depth, top).await?;
+            print_volume_report(&report);
+            return Ok(());
+        }
+        Command::Prescan => {
+            let config = load_config(&cli).await?;
+            let summary = prescan::run(&config)?;
+            println!(
+                "Indexed {} cache roots ({:.2} MB): {} directories listed, {} unchanged, {:.2?}",
+                summary.roots,
+                summary.bytes as f64 / 1_048_576.0,
+                summary.stats.dirs_scanned,
+                summary.stats.dirs_reused,
+                summary.elapsed
+            );
+            return Ok(());
+        }
+        Command::Scan { top, json, revisions, datasets, output } => {
+            let config = load_config(&cli).await?;
+            if revisions {
+                return show_revisions(&config, json);
+            }
+            if datasets {
+                return show_datasets(&config, json);
+            }
+            let report = prescan::scan(&config, top)?;
+            if let Some(OutputFormat::Csv) = output {
+                export::write_csv(&scan_rows(&report), std::io::stdout().lock())?;
+            } else if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_scan_report(&report);
+            }
+            return Ok(());
+        }
+        Command::Top { count, sort, tree, json, output } => {
+            let config = load_config(&cli).await?;
+            let report = top::largest(&prescan::cache_roots(&config), models::ollama_models_dir().as_deref(), config.size_basis, count, sort);
+            if let Some(OutputFormat::Csv) = output {
+                export::write_csv(&top_rows(&report), std::io::stdout().lock())?;
+            } else if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_top_report(&report, tree);
+            }
+            return Ok(());
+        }
+        Command::List { health, json } => {
+            let config = load_config(&cli).await?;
+            return list_caches(&config, health, json);
+        }
+        Command::Dedup { min_size_mb, link } => {
+            let config = load_config(&cli).await?;
+            let _lock = if link.is_some() { run_lock(&cli)? } else { None };
+            return dedup_caches(&config, min_size_mb * 1_048_576, link, cli.dry_run);
+        }
+        Command::Bench { synthetic, dir, json } => {
+            let config = load_config(&cli).await?;
+            let dir = dir.unwrap_or_else(std::env::temp_dir);
+            let report = bench::run(&config, synthetic, &dir).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_bench_report(&report);
+            }
+            return Ok(());
+        }
+        #[cfg(feature = "history")]
+        Command::History { action, limit } => {
+            return show_history(action, limit);
+        }
+        #[cfg(feature = "keyring")]
+        Command::Auth { action } => {
+            let config = load_config(&cli).await?;
+            return manage_credentials(action, &CommandRunner::new(&config.commands));
+        }
+        #[cfg(feature = "usage")]
+        Command::Usage { action } => {
+            let config = load_config(&cli).await?;
+            return manage_usage(&config, action).await;
+        }
+        Command::Repair { remove, refetch } => {
+            let action = match (remove, refetch) {
+                (true, _) => Some(RepairAction::RemoveSnapshot),
+                (_, true) => Some(RepairAction::Refetch),
+                _ => None,
+            };
+            let _lock = if action.is_some() { run_lock(&cli)? } else { None };
+            return repair_snapshots(action, cli.config.as_deref(), cli.profile.as_deref(), cli.dry_run).await;
+        }
+        Command::Schedule { action } => {
+            let config_path = remote_config.as_deref().or(cli.config.as_deref());
+            let config = load_config(&cli).await?;
+            return manage_schedule(action, config_path, &cli, &CommandRunner::new(&config.commands), cli.dry_run);
+        }
+        Command::SupportBundle { output, replay } => {
+            return match replay {
+                Some(bundle) => replay_support_bundle(&bundle, cli.config.as_deref()).await,
+                None => write_support_bundle(output, cli.config.as_deref(), cli.profile.as_deref(), &capabilities).await,
+            };
+        }
+        _ => {}
+    }
+    
+    capabilities.log_degraded();
+    
+    let mut report = RunReport::start(command_name(&command), cli.dry_run);
+    // Watch and daemon mode lock each pass instead, so they don't hold out every other run
+    let removes = matches!(command, Command::Clean { .. } | Command::Models { action: ModelsCommand::Remove { .. } });
+    let lock = if removes { run_lock(&cli) } else { Ok(None) };
+    // Watch and daemon mode stop between passes on their own signal handling
+    let cancel = CancellationToken::new();
+    if removes {
+        cancel::cancel_on_signal(cancel.clone());
+    }
+    let outcome = match lock {
         Err(e) => {
-            error!("Error during cache cleaning: {}", e);
-            std::process::exit(1);
+            log_failure("", &e);
+            Err(e)
+        }
+        Ok(_lock) => match build_cleaner(&cli, capabilities, &log_dedup, &log_file, &log_format).await {
+            Err(e) => {
+                log_failure("Failed to start: ", &e);
+                Err(e)
+            }
+            Ok(cache_cleaner) => {
+                let cache_cleaner = cache_cleaner.with_cancellation(cancel.clone());
+                run_command(command, cache_cleaner, cli.dry_run, &mut report).await
+            }
+        },
+    };
+    let outcome = match outcome {
+        Ok(()) if cancel.is_cancelled() => {
+            print_partial_summary(&report, cli.dry_run);
+            Err(ClearModelError::Cancelled)
+        }
+        outcome => outcome,
+    };
+    
+    // Summarise any warnings that were collapsed during the run
+    log_dedup.flush();
+    print_privileged_summary(&report);
+    print_failure_summary(&report);
+    
+    report.finish(outcome.as_ref().err());
+    if let Some(target) = &cli.result_file {
+        if let Err(e) = report.write(target) {
+            error!("Failed to write the result file: {}", e);
         }
     }
     
-    Ok(())
+    if matches!(report.command.as_str(), "clean" | "models") {
+        notify_webhook(&report, &cli).await;
+    }
+    
+    #[cfg(feature = "history")]
+    if report.command == "clean" {
+        if let Err(e) = record_history(&report, cli.config.as_deref(), cli.profile.as_deref()).await {
+            tracing::warn!("Failed to record the run in the history database: {}", e);
+        }
+    }
+    
+    // The error was logged already
+    match outcome {
+        Err(e) => std::process::exit(e.exit_code()),
+        Ok(()) => match report.exit_code(cli.fail_on_error) {
+            Some(code) => std::process::exit(code),
+            None => Ok(()),
+        },
+    }
+}
+
+/// Log why the run failed, with the error's code and hint as fields for JSON logs
+fn log_failure(context: &str, e: &ClearModelError) {
+    error!(code = e.code(), hint = e.hint(), "{}{}", context, e);
+}
+
+/// Post the run summary to `notifications.webhook_url`; a failure to notify only warns
+async fn notify_webhook(report: &RunReport, cli: &Cli) {
+    let config = match load_config(cli).await {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::debug!("Cannot load the configuration to send the run notification: {}", e);
+            return;
+        }
+    };
+    let commands = CommandRunner::new(&config.commands);
+    let notifications = config.notifications;
+    let report = report.clone();
+    let posted = tokio::task::spawn_blocking(move || notifications::notify(&notifications, &commands, &report)).await;
+    match posted {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("{}", e),
+        Err(e) => tracing::warn!("Failed to send the run notification: {}", e),
+    }
+}
+
+/// List the selected files left in place because removing them needs administrator rights
+fn print_privileged_summary(report: &RunReport) {
+    const LISTED: usize = 20;
+    
+    let paths: Vec<&PathBuf> = report.results.iter().flat_map(|result| &result.needs_privileges).collect();
+    if paths.is_empty() {
+        return;
+    }
+    
+    eprintln!("{} files need administrator rights; run with --privileged to clean these:", paths.len());
+    for path in paths.iter().take(LISTED) {
+        eprintln!("  {}", path.display());
+    }
+    if paths.len() > LISTED {
+        eprintln!("  ... and {} more (see --result-file for all of them)", paths.len() - LISTED);
+    }
+}
+
+/// List the directories and files a run could not clean, with the reasons
+fn print_failure_summary(report: &RunReport) {
+    for line in report.failure_summary(20) {
+        eprintln!("{}", line);
+    }
+}
+
+/// Report what an interrupted run got through before it stopped
+fn print_partial_summary(report: &RunReport, dry_run: bool) {
+    let totals = &report.totals;
+    eprintln!(
+        "Interrupted: {} {} files ({:.2} MB) in {} directories before stopping",
+        if dry_run { "would have removed" } else { "removed" },
+        totals.files_removed,
+        totals.bytes_freed as f64 / 1_048_576.0,
+        report.results.len()
+    );
+    if report.command == "clean" && !dry_run && checkpoint::checkpoint_path().is_ok_and(|path| path.exists()) {
+        eprintln!("Run `clearmodel clean --resume` to finish without scanning again");
+    }
+}
+
+/// Name of a command as given on the command line
+fn command_name(command: &Command) -> &'static str {
+    match command {
+        Command::Clean { .. } => "clean",
+        Command::Models { .. } => "models",
+        #[cfg(feature = "daemon")]
+        Command::Watch => "watch",
+        #[cfg(feature = "daemon")]
+        Command::Daemon { .. } => "daemon",
+        _ => "other",
+    }
+}
+
+/// Whether the command runs as one pass over the caches, rather than for as long as it is left
+fn marks_pass(command: &Command) -> bool {
+    match command {
+        #[cfg(feature = "daemon")]
+        Command::Watch | Command::Daemon { action: None } => false,
+        #[cfg(feature = "usage")]
+        Command::Usage { action: UsageCommand::Track } => false,
+        _ => true,
+    }
+}
+
+/// Take the run lock for a command that removes files (dry runs need none)
+fn run_lock(cli: &Cli) -> clearmodel::errors::Result<Option<RunLock>> {
+    if cli.dry_run {
+        return Ok(None);
+    }
+    RunLock::acquire(cli.wait).map(Some)
+}
+
+/// Load the configuration with the overrides every command honours
+async fn load_config(cli: &Cli) -> clearmodel::errors::Result<ClearModelConfig> {
+    let mut config = ClearModelConfig::load_profile(cli.config.as_deref(), cli.profile.as_deref()).await?;
+    if cli.apparent_size {
+        config.size_basis = SizeBasis::Apparent;
+    }
+    Ok(config)
+}
+
+/// Load the environment and configuration and set up the cleaner for this run
+async fn build_cleaner(
+    cli: &Cli,
+    capabilities: Capabilities,
+    log_dedup: &LogDeduplicator,
+    log_file: &LogFile,
+    log_format: &FormatSwitch,
+) -> clearmodel::errors::Result<CacheCleaner> {
+    let env_manager = EnvironmentManager::new().await?;
+    let mut config = load_config(cli).await?;
+    log_dedup.set_threshold(config.logging.dedup_threshold);
+    log_format.set(config.logging.format);
+    if let Err(e) = log_file.open(&config.logging) {
+        warn!("Not writing log files: {}", e);
+    }
+    rate_limit::set_priority(config.io_rate_limit.priority);
+    if let Some(bytes) = cli.larger_than {
+        config.min_file_size_mb = Some(bytes as f64 / 1_048_576.0);
+    }
+    config.older_than = cli.older_than;
+    config.newer_than = cli.newer_than;
+    if cli.allow_network_fs {
+        config.network_filesystems = NetworkFsMode::Allow;
+    }
+    if cli.include_other_users {
+        config.security.include_other_users = true;
+    }
+    if cli.privileged {
+        config.security.privileged = true;
+        config.check_privileged()?;
+    }
+    if cli.shared {
+        config.shared.enabled = true;
+    }
+    if cli.all_users {
+        config.shared.all_users = true;
+    }
+    if config.shared.all_users {
+        if !config.shared.enabled {
+            return Err(ClearModelError::configuration("--all-users only applies to shared caches; add --shared".to_string()));
+        }
+        // Root's removals would otherwise be held back as other users' files
+        config.security.include_other_users = true;
+    }
+    
+    let mut cache_cleaner = CacheCleaner::new(config, env_manager).await?
+        .with_progress(ProgressReporter::new(!cli.quiet))
+        .with_capabilities(capabilities);
+    
+    if let Some(EventFormat::Ndjson) = cli.events {
+        let events = match &cli.events_to {
+            Some(path) => EventSink::open(path)?,
+            None => EventSink::stdout(),
+        };
+        cache_cleaner = cache_cleaner.with_events(events);
+    }
+    
+    // Nothing is removed in a dry run, so there is nothing to journal or quarantine
+    if !cli.dry_run {
+        cache_cleaner = cache_cleaner.for_run(&journal::new_run_id())?;
+    } else {
+        cache_cleaner = cache_cleaner.previewing()?;
+    }
+    
+    Ok(cache_cleaner)
+}
+
+/// Run a command that needs the cleaner, recording what it removed in `report`
+async fn run_command(
+    command: Command,
+    cache_cleaner: CacheCleaner,
+    dry_run: bool,
+    report: &mut RunReport,
+) -> clearmodel::errors::Result<()> {
+    match command {
+        Command::Clean { only, exclude_targets, resume, output } => {
+            // Perform cache cleaning
+            let mut cache_cleaner = cache_cleaner.with_target_filter(TargetFilter::only(only).excluding(exclude_targets));
+            let removals = output.map(|_| Arc::new(RemovalLog::new()));
+            if let Some(removals) = &removals {
+                cache_cleaner = cache_cleaner.with_removal_log(removals.clone());
+            }
+            if resume {
+                let saved = Checkpoint::load(&checkpoint::checkpoint_path()?)?.ok_or_else(|| {
+                    ClearModelError::cache("No interrupted run to resume").with_hint("Run `clearmodel clean` instead")
+                }).inspect_err(|e| log_failure("", e))?;
+                cache_cleaner = cache_cleaner.resuming(saved);
+            }
+            let mut result = cache_cleaner.clean_all_caches(dry_run).await;
+            if let Ok(results) = &mut result {
+                if let Err(e) = cache_cleaner.remove_privileged(results, dry_run).await {
+                    log_failure("Failed to remove the files that need administrator rights: ", &e);
+                }
+            }
+            report.add_stats(&cache_cleaner.stats().await);
+            report.add_disk_space(cache_cleaner.disk_space());
+            report.add_commands(cache_cleaner.commands());
+            match &result {
+                Ok(_) => info!("Model cache cleaning completed successfully!"),
+                Err(e) => log_failure("Error during cache cleaning: ", e),
+            }
+            // Written after a failure too, so the files already removed are listed
+            if let Some(removals) = &removals {
+                export::write_csv(&removals.rows(), std::io::stdout().lock())
+                    .map_err(|e| ClearModelError::file_operation(format!("Failed to write the CSV output: {}", e), None))?;
+            }
+            result.map(|results| report.add_results(&results))
+        }
+        #[cfg(feature = "daemon")]
+        Command::Watch => {
+            let result = cache_cleaner.watch_and_enforce(dry_run).await;
+            if let Err(e) = &result {
+                log_failure("Error in watch mode: ", e);
+            }
+            result
+        }
+        #[cfg(feature = "daemon")]
+        Command::Daemon { action: None } => {
+            let result = cache_cleaner.run_daemon(dry_run).await;
+            if let Err(e) = &result {
+                log_failure("Error in daemon mode: ", e);
+            }
+            result
+        }
+        Command::Models { action: ModelsCommand::Remove { pattern } } => {
+            let removed = cache_cleaner.remove_models(&pattern, dry_run).await
+                .inspect_err(|e| log_failure("Error removing models: ", e))?;
+            for (cached, result) in &removed {
+                println!(
+                    "{} {}: {} files, {:.2} GB",
+                    if dry_run { "Would remove" } else { "Removed" },
+                    cached.model,
+                    result.files_removed,
+                    result.bytes_freed as f64 / 1_073_741_824.0
+                );
+            }
+            let results: Vec<CleanupResult> = removed.into_iter().map(|(_, result)| result).collect();
+            report.add_results(&results);
+            Ok(())
+        }
+        #[cfg(feature = "daemon")]
+        Command::Daemon { action: Some(_) } => unreachable!("handled above"),
+        #[cfg(feature = "history")]
+        Command::History { .. } => unreachable!("handled above"),
+        #[cfg(feature = "keyring")]
+        Command::Auth { .. } => unreachable!("handled above"),
+        #[cfg(feature = "usage")]
+        Command::Usage { .. } => unreachable!("handled above"),
+        Command::Doctor
+        | Command::Version { .. }
+        | Command::Pin { .. }
+        | Command::Unpin { .. }
+        | Command::Models { action: ModelsCommand::List { .. } }
+        | Command::Undo
+        | Command::Purge
+        | Command::State { .. }
+        | Command::Audit { .. }
+        | Command::SupportBundle { .. }
+        | Command::Config { .. }
+        | Command::Policy { .. }
+        | Command::Schedule { .. }
+        | Command::Prescan
+        | Command::Scan { .. }
+        | Command::Top { .. }
+        | Command::List { .. }
+        | Command::Dedup { .. }
+        | Command::Bench { .. }
+        | Command::Repair { .. }
+        | Command::WhyFull { .. } => unreachable!("handled above"),
+    }
 }
 
-fn init_logging(debug: bool, verbose: bool) -> Result<()> {
+fn init_logging(debug: bool, verbose: bool, quiet: bool, trace: &[String], log_to_stderr: bool) -> Result<(LogDeduplicator, LogFile, FormatSwitch)> {
     let log_level = if debug {
         "debug"
     } else if verbose {
         "info"
+    } else if quiet {
+        "error"
     } else {
         "warn"
     };
     
+    // Scoped tracing takes precedence over RUST_LOG so only the named subsystems get noisy
+    let env_filter = if trace.is_empty() {
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| format!("clearmodel={}", log_level).into())
+    } else {
+        tracing_subscriber::EnvFilter::try_new(trace::filter_directives(log_level, trace)?)?
+    };
+    
+    // Repeated warnings are collapsed; the threshold is updated once the config is loaded
+    let log_dedup = LogDeduplicator::new(LoggingConfig::default().dedup_threshold);
+    // Log files are opened once the config is loaded, with their own level
+    let log_file = LogFile::new();
+    // Text and JSON layers are both installed; the config picks which one writes
+    let format = FormatSwitch::new();
+    
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| format!("clearmodel={}", log_level).into()),
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_writer(console_writer(log_to_stderr))
+                .with_filter(log_dedup.clone())
+                .with_filter(format.only(LogFormat::Text))
+                .with_filter(env_filter.clone())
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(console_writer(log_to_stderr))
+                .with_filter(log_dedup.clone())
+                .with_filter(format.only(LogFormat::Json))
+                .with_filter(env_filter)
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(log_file.clone())
+                .with_filter(format.only(LogFormat::Text))
+                .with_filter(log_file.clone())
+        )
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_writer(log_file.clone())
+                .with_filter(format.only(LogFormat::Json))
+                .with_filter(log_file.clone())
         )
-        .with(tracing_subscriber::fmt::layer().with_target(false))
         .init();
     
+    Ok((log_dedup, log_file, format))
+}
+
+fn console_writer(log_to_stderr: bool) -> BoxMakeWriter {
+    BoxMakeWriter::new(move || -> Box<dyn std::io::Write> {
+        if log_to_stderr {
+            Box::new(std::io::stderr())
+        } else {
+            Box::new(std::io::stdout())
+        }
+    })
+}
+
+async fn update_pins(config_path: Option<&str>, pattern: Option<&str>, pin: bool) -> Result<()> {
+    let Some(pattern) = pattern else {
+        let config = ClearModelConfig::load(config_path).await?;
+        if config.pinned_models.is_empty() {
+            println!("No models are pinned.");
+        }
+        for pattern in &config.pinned_models {
+            println!("{}", pattern);
+        }
+        return Ok(());
+    };
+    
+    let path = ClearModelConfig::writable_config_path(config_path);
+    let changed = ClearModelConfig::update_pinned_models(&path, pattern, pin).await?;
+    
+    match (pin, changed) {
+        (true, true) => println!("Pinned {} in {}", pattern, path.display()),
+        (true, false) => println!("{} is already pinned", pattern),
+        (false, true) => println!("Unpinned {} in {}", pattern, path.display()),
+        (false, false) => println!("{} was not pinned", pattern),
+    }
+    
+    Ok(())
+}
+
+#[cfg(feature = "remote")]
+fn fetch_remote_config(url: &str, cli: &Cli) -> Result<String> {
+    let verification = remote_config::Verification {
+        checksum: cli.config_checksum.clone(),
+        signers: cli.config_signers.clone(),
+    };
+    let copy = remote_config::fetch(url, &verification)?;
+    copy.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("{} is not valid UTF-8", copy.display()))
+}
+
+#[cfg(not(feature = "remote"))]
+fn fetch_remote_config(url: &str, _cli: &Cli) -> Result<String> {
+    anyhow::bail!("Cannot load {}: this build does not include the `remote` feature", url);
+}
+
+fn init_config(config_path: Option<&str>, force: bool) -> Result<()> {
+    let Some(path) = config_path.map(PathBuf::from).or_else(|| ClearModelConfig::user_config_dir().map(|dir| dir.join("config.toml"))) else {
+        anyhow::bail!("Cannot determine the configuration directory; pass --config");
+    };
+    if path.exists() && !force {
+        anyhow::bail!("{} already exists; pass --force to replace it", path.display());
+    }
+    
+    let defaults = ClearModelConfig::default();
+    eprintln!("Looking for caches...");
+    let detected = init::detect(&defaults);
+    if detected.is_empty() {
+        eprintln!("No caches found yet.");
+    }
+    for cache in &detected {
+        eprintln!("  {:<18} {:>9.2} GB", cache.target, cache.bytes as f64 / 1_073_741_824.0);
+    }
+    let answers = if prompt::interactive() {
+        init::ask(&mut std::io::stdin().lock(), &mut std::io::stderr(), &detected)
+    } else {
+        eprintln!("stdin is not a terminal; using the default answers.");
+        init::InitAnswers::default()
+    };
+    
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, init::render(&defaults, &detected, &answers))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+async fn validate_config(path: &Path) -> Result<()> {
+    let issues = ClearModelConfig::validate_file(path).await?;
+    if issues.is_empty() {
+        println!("{} is valid", path.display());
+        return Ok(());
+    }
+    
+    for issue in &issues {
+        let location = match issue.line {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        if issue.pointer.is_empty() {
+            eprintln!("{}: {}", location, issue.message);
+        } else {
+            eprintln!("{}: {}: {}", location, issue.pointer, issue.message);
+        }
+    }
+    anyhow::bail!("{} has {} problem(s)", path.display(), issues.len());
+}
+
+fn undo_last_run(dry_run: bool) -> Result<()> {
+    let Some(journal_dir) = journal::journal_dir() else {
+        anyhow::bail!("Cannot determine the state directory for the deletion journal");
+    };
+    
+    let Some(summary) = restore::undo_last_run(&journal_dir, dry_run)? else {
+        println!("Nothing to undo.");
+        return Ok(());
+    };
+    
+    let verb = if dry_run { "Would restore" } else { "Restored" };
+    println!(
+        "{} {} files ({:.2} MB) from run {}",
+        verb,
+        summary.restored,
+        summary.bytes_restored as f64 / 1_048_576.0,
+        summary.run_id
+    );
+    if summary.unrecoverable > 0 {
+        println!("{} files were deleted permanently and cannot be restored", summary.unrecoverable);
+    }
+    if !summary.errors.is_empty() {
+        anyhow::bail!("{} files could not be restored", summary.errors.len());
+    }
+    
+    Ok(())
+}
+
+fn purge_quarantine(dry_run: bool) -> Result<()> {
+    let Some(root) = quarantine::quarantine_dir() else {
+        anyhow::bail!("Cannot determine the quarantine directory");
+    };
+    
+    let summary = quarantine::purge(&root, None, dry_run)?;
+    let verb = if dry_run { "Would purge" } else { "Purged" };
+    println!(
+        "{} {} files ({:.2} MB) from {} quarantined runs",
+        verb,
+        summary.files,
+        summary.bytes as f64 / 1_048_576.0,
+        summary.runs
+    );
+    
+    Ok(())
+}
+
+fn show_state() -> Result<()> {
+    let Some(dir) = state::state_dir() else {
+        anyhow::bail!("Cannot determine the state directory");
+    };
+    
+    println!("State directory: {}", dir.display());
+    let entries = state::entries(&dir);
+    if entries.is_empty() {
+        println!("  (empty)");
+    }
+    for entry in &entries {
+        println!("  {:>10.2} MB  {:<16} {}", entry.bytes as f64 / 1_048_576.0, entry.name, entry.description);
+    }
+    if let Some(quarantine) = quarantine::quarantine_dir() {
+        println!("Quarantine: {} (emptied by `clearmodel purge`)", quarantine.display());
+    }
+    
+    Ok(())
+}
+
+fn clean_state(include_audit: bool, dry_run: bool) -> Result<()> {
+    let Some(dir) = state::state_dir() else {
+        anyhow::bail!("Cannot determine the state directory");
+    };
+    
+    let removed = state::clean(&dir, include_audit, dry_run)?;
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for entry in &removed {
+        println!("{} the {} ({:.2} MB)", verb, entry.description, entry.bytes as f64 / 1_048_576.0);
+    }
+    println!(
+        "{} {} item(s), {:.2} MB, from {}",
+        verb,
+        removed.len(),
+        removed.iter().map(|entry| entry.bytes).sum::<u64>() as f64 / 1_048_576.0,
+        dir.display()
+    );
+    
+    Ok(())
+}
+
+/// Check the audit log's MAC chain with the configured key
+fn verify_audit_log(config: &ClearModelConfig, path: Option<PathBuf>) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => audit::log_path(&config.audit)?,
+    };
+    let key = audit::load_key(&config.audit, &path, false)?;
+    let entries = audit::verify(&path, &key)?;
+    println!("{:?}: {} entries verified", path, entries);
+    
+    Ok(())
+}
+
+#[cfg(feature = "daemon")]
+async fn control_daemon(request: ControlRequest, config_path: Option<&str>, profile: Option<&str>) -> Result<()> {
+    let config = ClearModelConfig::load_profile(config_path, profile).await?;
+    let Some(socket) = config.daemon.socket_path.clone().or_else(daemon::default_socket_path) else {
+        anyhow::bail!("Cannot determine the daemon control socket path");
+    };
+    
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    match daemon::send_request(&socket, request).await? {
+        ControlResponse::Status(status) => {
+            println!(
+                "Daemon running (pid {}{}), {} cleanups so far{}",
+                status.pid,
+                if status.dry_run { ", dry run" } else { "" },
+                status.cleanups,
+                if status.cleaning { ", cleaning now" } else { "" }
+            );
+            println!("  threshold: {:.2} GB free", gb(status.threshold_bytes));
+            for disk in &status.disks {
+                println!("  {}: {:.2} GB free", disk.mount_point.display(), gb(disk.available_bytes));
+            }
+            if status.under_pressure {
+                println!("  free space is below the threshold");
+            }
+        }
+        ControlResponse::Triggered { queued: true } => println!("Cleanup requested."),
+        ControlResponse::Triggered { queued: false } => println!("A cleanup is already pending."),
+        ControlResponse::Error { message } => anyhow::bail!("Daemon rejected the request: {}", message),
+    }
+    
+    Ok(())
+}
+
+fn manage_schedule(action: ScheduleCommand, config_path: Option<&str>, cli: &Cli, commands: &CommandRunner, dry_run: bool) -> Result<()> {
+    let backend = scheduler::native_backend(commands)?;
+    
+    match action {
+        ScheduleCommand::Install { every } => {
+            // Scheduled jobs run from another working directory, so pin down the config file now
+            let config = ClearModelConfig::writable_config_path(config_path);
+            let config = if config_path.is_some_and(|path| path.starts_with("https://")) {
+                Some(config)
+            } else if config.exists() {
+                Some(std::fs::canonicalize(&config)?)
+            } else if config_path.is_some() {
+                anyhow::bail!("Configuration file {} does not exist", config.display());
+            } else {
+                None
+            };
+            
+            let schedule = Schedule {
+                every,
+                executable: std::env::current_exe()?,
+                config,
+                #[cfg(feature = "remote")]
+                config_checksum: cli.config_checksum.clone(),
+                #[cfg(feature = "remote")]
+                config_signers: cli.config_signers.clone(),
+                #[cfg(not(feature = "remote"))]
+                config_checksum: None,
+                #[cfg(not(feature = "remote"))]
+                config_signers: None,
+                profile: cli.profile.clone(),
+            };
+            let files = backend.install(&schedule, dry_run)?;
+            
+            let verb = if dry_run { "Would install" } else { "Installed" };
+            println!("{} {} cleanup as a {}", verb, every, backend.name());
+            for file in files {
+                println!("  {}", file.display());
+            }
+        }
+        ScheduleCommand::Status => {
+            let status = backend.status()?;
+            if !status.installed {
+                println!("No scheduled cleanup is installed ({}).", backend.name());
+                return Ok(());
+            }
+            
+            let every = status.every.map(|every| every.to_string()).unwrap_or_else(|| "custom".to_string());
+            println!(
+                "Scheduled {} cleanup via {} ({})",
+                every,
+                backend.name(),
+                if status.active { "active" } else { "inactive" }
+            );
+            if let Some(next_run) = status.next_run {
+                println!("  next run: {}", next_run);
+            }
+            for file in status.files {
+                println!("  {}", file.display());
+            }
+        }
+        ScheduleCommand::Remove => {
+            let removed = backend.remove(dry_run)?;
+            match (removed, dry_run) {
+                (false, _) => println!("No scheduled cleanup is installed."),
+                (true, true) => println!("Would remove the scheduled cleanup ({}).", backend.name()),
+                (true, false) => println!("Removed the scheduled cleanup ({}).", backend.name()),
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn write_support_bundle(
+    output: Option<PathBuf>,
+    config_path: Option<&str>,
+    profile: Option<&str>,
+    capabilities: &Capabilities,
+) -> Result<()> {
+    let config = ClearModelConfig::load_profile(config_path, profile).await?;
+    let output = output.unwrap_or_else(support::default_bundle_path);
+    
+    let bundle = SupportBundle::capture(&config, capabilities)?;
+    bundle.write(&output)?;
+    
+    println!(
+        "Support bundle written to {} ({} cache roots, {} files)",
+        output.display(),
+        bundle.discovery.len(),
+        bundle.candidates.len()
+    );
+    println!("Paths under your home directory are shown as ~ and pinned model patterns are redacted.");
+    
+    Ok(())
+}
+
+async fn replay_support_bundle(path: &Path, config_path: Option<&str>) -> Result<()> {
+    let bundle = SupportBundle::read(path)?;
+    let manifest = &bundle.manifest;
+    println!(
+        "Replaying bundle from clearmodel {} ({}), captured at {} (unix time)",
+        manifest.clearmodel_version, manifest.target, manifest.created
+    );
+    
+    // A local config lets maintainers try policy changes against the captured files
+    let config = match config_path {
+        Some(config_path) => {
+            println!("Policy: {}", config_path);
+            ClearModelConfig::load(Some(config_path)).await?
+        }
+        None => {
+            println!("Policy: bundled configuration");
+            bundle.config()?
+        }
+    };
+    
+    let mut remove_files = 0;
+    let mut remove_bytes = 0;
+    for root in bundle.replay(&config) {
+        println!("{}", root.path);
+        for (decision, (files, bytes)) in &root.decisions {
+            let action = if decision.removes() { "remove" } else { "keep" };
+            println!(
+                "  {:<6} {:<13} {:>8} files {:>10.2} MB",
+                action,
+                decision.label(),
+                files,
+                *bytes as f64 / 1_048_576.0
+            );
+            if decision.removes() {
+                remove_files += files;
+                remove_bytes += bytes;
+            }
+        }
+    }
+    
+    println!(
+        "Would remove {} files ({:.2} MB)",
+        remove_files,
+        remove_bytes as f64 / 1_048_576.0
+    );
+    if let Some(last_run) = &bundle.last_run {
+        println!(
+            "Last run {}: {} files ({:.2} MB) removed, {} restorable",
+            last_run.run_id,
+            last_run.files,
+            last_run.bytes as f64 / 1_048_576.0,
+            last_run.restorable
+        );
+    }
+    
+    Ok(())
+}
+
+fn list_caches(config: &ClearModelConfig, with_health: bool, json: bool) -> Result<()> {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    
+    if !with_health {
+        for root in prescan::cache_roots(config) {
+            let bytes = walkdir::WalkDir::new(&root)
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|metadata| metadata.len())
+                .sum();
+            println!("  {:>10.2} GB  {}", gb(bytes), root.display());
+        }
+        print_hf_caches(config);
+        return Ok(());
+    }
+    
+    let caches = health::assess_all(config);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&caches)?);
+        return Ok(());
+    }
+    
+    println!("Cache health, worst first (100 = nothing to do):");
+    for cache in &caches {
+        print_cache_health(cache);
+    }
+    Ok(())
+}
+
+/// Store a finished run, with the cache sizes it left behind, in the history database
+#[cfg(feature = "history")]
+async fn record_history(report: &RunReport, config_path: Option<&str>, profile: Option<&str>) -> Result<()> {
+    let Some(path) = history::history_path() else {
+        anyhow::bail!("Cannot determine the state directory for the history database");
+    };
+    
+    let config = ClearModelConfig::load_profile(config_path, profile).await?;
+    let usage: Vec<(PathBuf, u64)> = prescan::scan(&config, 0)?
+        .roots
+        .into_iter()
+        .map(|root| (root.path, root.summary.size(config.size_basis)))
+        .collect();
+    
+    let id = History::open(&path)?.record(report, &usage)?;
+    info!("Recorded run {} in {:?}", id, path);
+    Ok(())
+}
+
+#[cfg(feature = "keyring")]
+fn manage_credentials(action: AuthCommand, commands: &CommandRunner) -> Result<()> {
+    match action {
+        AuthCommand::Store => {
+            let password = EnvironmentManager::read_sudo_password()?;
+            keyring::store(commands, keyring::SUDO_ACCOUNT, &password)?;
+            println!("Saved the sudo password to the {}.", keyring::backend());
+        }
+        AuthCommand::Forget => {
+            keyring::delete(commands, keyring::SUDO_ACCOUNT)?;
+            println!("Removed the sudo password from the {}.", keyring::backend());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "usage")]
+async fn manage_usage(config: &ClearModelConfig, action: UsageCommand) -> Result<()> {
+    let (least, limit, json) = match action {
+        UsageCommand::Track => {
+            let tracker = usage::UsageTracker::new(prescan::cache_roots(config), &config.usage)?;
+            println!("Recording file opens; press Ctrl-C to stop.");
+            tracker.run(cancel::shutdown_signal()).await?;
+            return Ok(());
+        }
+        UsageCommand::Top { least, limit, json } => (least, limit, json),
+    };
+    
+    let opens = usage::recorded_opens()?;
+    let cached = models::inventory(&prescan::cache_roots(config), models::ollama_models_dir().as_deref(), config.size_basis);
+    let mut ranked = usage::by_model(cached, &opens);
+    if least {
+        ranked.reverse();
+    }
+    ranked.truncate(limit);
+    
+    let last_open = |model: &usage::ModelUsage| model.last_open
+        .and_then(|at| at.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_secs());
+    if json {
+        let report: Vec<_> = ranked.iter()
+            .map(|model| serde_json::json!({
+                "name": model.model.name,
+                "source": model.model.source.to_string(),
+                "bytes": model.bytes,
+                "opens": model.opens,
+                "last_open": last_open(model),
+            }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
+    if opens.is_empty() {
+        println!("No file opens recorded yet; set usage.track = true for the daemon or run `clearmodel usage track`.");
+    }
+    for model in &ranked {
+        let idle = model.last_open
+            .map(|at| format!("{} days ago", at.elapsed().map(|idle| idle.as_secs() / 86_400).unwrap_or(0)))
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "  {:>8} opens  {:>14}  {:>10.2} GB  {:<10} {}",
+            model.opens,
+            idle,
+            model.bytes as f64 / 1_073_741_824.0,
+            model.model.source,
+            model.model.name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "history")]
+fn show_history(action: Option<HistoryCommand>, limit: usize) -> Result<()> {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let Some(path) = history::history_path() else {
+        anyhow::bail!("Cannot determine the state directory for the history database");
+    };
+    let history = History::open(&path)?;
+    
+    match action {
+        None => {
+            let runs = history.runs(limit)?;
+            if runs.is_empty() {
+                println!("No runs recorded yet.");
+            }
+            for run in runs {
+                let status = match (run.succeeded, run.dry_run) {
+                    (false, _) => "failed",
+                    (true, true) => "dry run",
+                    (true, false) => "ok",
+                };
+                println!(
+                    "  {:>5}  {:>8}  {:<7}  {:>8} files  {:>10.2} GB freed  {} errors",
+                    run.id,
+                    ago(run.started_at),
+                    status,
+                    run.files_removed,
+                    gb(run.bytes_freed),
+                    run.errors
+                );
+            }
+        }
+        Some(HistoryCommand::Show { id }) => {
+            let Some(details) = history.show(id)? else {
+                anyhow::bail!("No run with id {} in the history", id);
+            };
+            let run = &details.run;
+            println!(
+                "Run {}: {}, {}{}, took {} ms",
+                run.id,
+                run.command,
+                ago(run.started_at),
+                if run.dry_run { " (dry run)" } else { "" },
+                run.duration_ms
+            );
+            if let Some(error) = &run.error {
+                println!("Failed: {}", error);
+            }
+            println!("{} files, {:.2} GB freed, {} errors", run.files_removed, gb(run.bytes_freed), run.errors);
+            for directory in &details.directories {
+                println!(
+                    "  {:>10.2} GB  {:>8} files  {}",
+                    gb(directory.bytes_freed),
+                    directory.files_removed,
+                    directory.path.display()
+                );
+            }
+            if !details.usage.is_empty() {
+                println!("Cache sizes after the run:");
+                for (path, bytes) in &details.usage {
+                    println!("  {:>10.2} GB  {}", gb(*bytes), path.display());
+                }
+            }
+        }
+        Some(HistoryCommand::Diff { from, to }) => {
+            let Some((from, to, changes)) = history.diff(from, to)? else {
+                println!("No runs with recorded cache sizes yet.");
+                return Ok(());
+            };
+            println!("Cache sizes from run {} to run {}:", from, to);
+            for change in changes {
+                let size = |bytes: Option<u64>| bytes.map(|bytes| format!("{:.2} GB", gb(bytes))).unwrap_or_else(|| "-".to_string());
+                println!(
+                    "  {:>+10.2} GB  {:>10} -> {:<10}  {}",
+                    change.delta() as f64 / 1_073_741_824.0,
+                    size(change.before),
+                    size(change.after),
+                    change.path.display()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How long ago a Unix timestamp was, e.g. `3d ago`
+#[cfg(feature = "history")]
+fn ago(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let elapsed = now.saturating_sub(timestamp);
+    
+    match elapsed {
+        0..=59 => format!("{}s ago", elapsed),
+        60..=3_599 => format!("{}m ago", elapsed / 60),
+        3_600..=86_399 => format!("{}h ago", elapsed / 3_600),
+        _ => format!("{}d ago", elapsed / 86_400),
+    }
+}
+
+fn print_scan_report(report: &prescan::ScanReport) {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    
+    for root in &report.roots {
+        println!("  {:>10.2} GB  {:>9} files  {}", gb(root.summary.size(report.size_basis)), root.summary.files, root.path.display());
+        for (child, bytes) in &root.largest {
+            let name = child.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            println!("  {:>10.2} GB                   {}", gb(*bytes), name);
+        }
+    }
+    for cache in report.engines.iter().filter(|cache| !cache.archs.is_empty()) {
+        println!("Engines in {} ({}) by GPU architecture:", cache.path.display(), cache.name);
+        for usage in &cache.archs {
+            println!("  {:>10.2} GB  {:>9} files  {}", gb(usage.bytes), usage.files, usage.arch);
+        }
+    }
+    println!(
+        "{} directories listed, {} unchanged, {:.2?}",
+        report.dirs_listed,
+        report.dirs_unchanged,
+        report.elapsed
+    );
+}
+
+/// Cache roots and their largest subdirectories, each subdirectory under its root's target
+fn scan_rows(report: &prescan::ScanReport) -> Vec<ExportRow> {
+    report.roots.iter()
+        .flat_map(|root| {
+            let target = targets::cache_dir_target(&root.path);
+            let subdirs = root.largest.iter().map(|(path, bytes)| (path.clone(), *bytes));
+            std::iter::once((root.path.clone(), root.summary.size(report.size_basis)))
+                .chain(subdirs)
+                .map(move |(path, bytes)| ExportRow { path, target: target.clone(), bytes, age_days: None, last_access: None, action: None })
+        })
+        .collect()
+}
+
+/// Consumers listed by `top`; models are under their source, directories under their root's target
+fn top_rows(report: &top::TopReport) -> Vec<ExportRow> {
+    report.consumers.iter()
+        .map(|consumer| ExportRow {
+            path: consumer.path.clone(),
+            target: match consumer.kind.as_str() {
+                "directory" | "file" => targets::cache_dir_target(&consumer.root),
+                source => source.to_string(),
+            },
+            bytes: consumer.bytes,
+            age_days: None,
+            last_access: consumer.last_used,
+            action: None,
+        })
+        .collect()
+}
+
+fn print_top_report(report: &top::TopReport, tree: bool) {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let idle = |consumer: &top::Consumer| consumer.idle_days
+        .map(|days| format!("{} days ago", days))
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    if report.consumers.is_empty() {
+        println!("No cached files found");
+        return;
+    }
+    if !tree {
+        for consumer in &report.consumers {
+            println!("  {:>10.2} GB  {:<12} {:>14}  {}", gb(consumer.bytes), consumer.kind, idle(consumer), consumer.path.display());
+        }
+        return;
+    }
+    
+    // Roots keep the order their first entry has
+    let mut roots: Vec<&Path> = Vec::new();
+    for consumer in &report.consumers {
+        if !roots.contains(&consumer.root.as_path()) {
+            roots.push(&consumer.root);
+        }
+    }
+    for root in roots {
+        let total = report.roots.get(root).copied().unwrap_or(0);
+        println!("  {:>10.2} GB  {:<12} {:>14}  {}", gb(total), "", "", root.display());
+        let children: Vec<_> = report.consumers.iter().filter(|consumer| consumer.root == root).collect();
+        for (i, consumer) in children.iter().enumerate() {
+            let branch = if i + 1 == children.len() { "└─" } else { "├─" };
+            println!("  {:>10.2} GB  {:<12} {:>14}  {} {}", gb(consumer.bytes), consumer.kind, idle(consumer), branch, consumer.name);
+        }
+    }
+}
+
+/// Snapshot revisions per repository, marking those `keep_revisions` would prune
+fn show_revisions(config: &ClearModelConfig, json: bool) -> Result<()> {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let hubs: Vec<(PathBuf, Option<usize>, Vec<hf_revisions::RepoRevisions>)> = hf_revisions::hubs(config)
+        .into_iter()
+        .map(|(hub, keep)| {
+            let repos = hf_revisions::hub_repos(&hub);
+            (hub, keep, repos)
+        })
+        .collect();
+    
+    if json {
+        let report: Vec<_> = hubs.iter()
+            .map(|(hub, keep, repos)| serde_json::json!({ "hub": hub, "keep_revisions": keep, "repos": repos }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
+    if hubs.is_empty() {
+        println!("No HuggingFace hub caches found");
+        return Ok(());
+    }
+    for (hub, keep, repos) in &hubs {
+        match keep {
+            Some(keep) => println!("{} (keeping {} revisions per repository)", hub.display(), keep),
+            None => println!("{}", hub.display()),
+        }
+        for repo in repos {
+            println!("  {}", repo.name);
+            for (index, revision) in repo.revisions.iter().enumerate() {
+                let refs = if revision.refs.is_empty() { String::new() } else { format!(" [{}]", revision.refs.join(", ")) };
+                let pruned = if keep.is_some_and(|keep| index >= repo.kept(keep)) { "  (pruned)" } else { "" };
+                println!(
+                    "    {:.12}{:<14} {:>9.2} GB  {:>9.2} GB own  {:>5} days{}",
+                    revision.commit,
+                    refs,
+                    gb(revision.bytes),
+                    gb(revision.exclusive_bytes),
+                    revision.age_days,
+                    pruned
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn show_datasets(config: &ClearModelConfig, json: bool) -> Result<()> {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let caches: Vec<(PathBuf, Vec<hf_datasets::CachedDataset>)> = hf_datasets::caches(config)
+        .into_iter()
+        .map(|cache| {
+            let datasets = hf_datasets::datasets(&cache);
+            (cache, datasets)
+        })
+        .collect();
+    
+    if json {
+        let report: Vec<_> = caches.iter()
+            .map(|(cache, datasets)| serde_json::json!({ "cache": cache, "datasets": datasets }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
+    if caches.is_empty() {
+        println!("No HuggingFace datasets caches found");
+        return Ok(());
+    }
+    for (cache, datasets) in &caches {
+        println!("{}", cache.display());
+        for dataset in datasets {
+            println!("  {:<40} {:>9.2} GB", dataset.name, gb(dataset.bytes));
+            for build in &dataset.builds {
+                println!(
+                    "    {:<20} {:<10} {:.8}  {:>9.2} GB  {:>4} shards",
+                    build.config,
+                    build.version,
+                    build.fingerprint,
+                    gb(build.bytes),
+                    build.shards
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Cached models, largest first
+fn list_models(config: &ClearModelConfig, json: bool) -> Result<()> {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let mut cached = models::inventory(&prescan::cache_roots(config), models::ollama_models_dir().as_deref(), config.size_basis);
+    cached.sort_by_key(|cached| std::cmp::Reverse(cached.bytes));
+    
+    // GGUF headers say which model and quantization a file holds, which its name may not
+    let header = |cached: &models::CachedModel| {
+        (cached.model.source == ModelSource::Gguf)
+            .then(|| cached.files.iter().find_map(|file| gguf::read_info(file)))
+            .flatten()
+    };
+    
+    if json {
+        let report: Vec<_> = cached.iter()
+            .map(|cached| serde_json::json!({
+                "name": cached.model.name,
+                "source": cached.model.source.to_string(),
+                "bytes": cached.bytes,
+                "idle_days": cached.idle_days(),
+                "gguf": header(cached),
+            }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    
+    if cached.is_empty() {
+        println!("No cached models found");
+        return Ok(());
+    }
+    let pinned = PinnedModels::new(&config.pinned_models);
+    for cached in &cached {
+        let idle = cached.idle_days()
+            .map(|days| format!("{} days ago", days))
+            .unwrap_or_else(|| "unknown".to_string());
+        let pin = if pinned.matches(&cached.model.name) { "  (pinned)" } else { "" };
+        let details = header(cached)
+            .map(|info| [info.name, info.architecture, info.quantization].into_iter().flatten().collect::<Vec<_>>())
+            .filter(|parts| !parts.is_empty())
+            .map(|parts| format!("  [{}]", parts.join(", ")))
+            .unwrap_or_default();
+        println!("  {:>10.2} GB  {:<10} {:>14}  {}{}{}", gb(cached.bytes), cached.model.source, idle, cached.model.name, details, pin);
+    }
+    Ok(())
+}
+
+fn print_hf_caches(config: &ClearModelConfig) {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let instances = hf_caches::instances(config);
+    if instances.is_empty() {
+        return;
+    }
+    
+    println!();
+    println!("HuggingFace caches:");
+    for instance in &instances {
+        let bytes: u64 = instance.repos().iter().map(|repo| repo.bytes).sum();
+        let budget = instance.budget
+            .map(|budget| format!(" of {:.2} GB", gb(budget)))
+            .unwrap_or_default();
+        println!("  {:<12} {:>10.2} GB{}  {}", instance.name, gb(bytes), budget, instance.path.display());
+    }
+    
+    let shared = hf_caches::shared_models(&instances);
+    if !shared.is_empty() {
+        println!();
+        println!("Models cached in more than one of them:");
+        for model in &shared {
+            let copies: Vec<&str> = model.copies.iter().map(|(name, _)| name.as_str()).collect();
+            println!("  {} in {} ({:.2} GB redundant)", model.name, copies.join(", "), gb(model.redundant_bytes()));
+        }
+    }
+}
+
+fn print_cache_health(cache: &CacheHealth) {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    
+    println!("  {:>3}  {:>10.2} GB  {}", cache.score, gb(cache.bytes), cache.path.display());
+    let growth = match cache.growth_bytes_per_day {
+        Some(growth) => format!("{:+.2} GB/day", growth / 1_073_741_824.0),
+        None => "unknown (run `clearmodel prescan` to track)".to_string(),
+    };
+    println!(
+        "                      stale {:.0}%, incomplete downloads {}, duplicates {:.2} GB, growth {}",
+        cache.stale_ratio() * 100.0,
+        cache.incomplete_files,
+        gb(cache.duplicate_bytes),
+        growth
+    );
+}
+
+fn dedup_caches(config: &ClearModelConfig, min_size: u64, link: Option<LinkMode>, dry_run: bool) -> Result<()> {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let groups = dedup::find_duplicates(&prescan::cache_roots(config), min_size);
+    if groups.is_empty() {
+        println!("No duplicate files found");
+        return Ok(());
+    }
+    
+    let reclaimable: u64 = groups.iter().map(|group| group.reclaimable()).sum();
+    println!("{} set(s) of duplicate files, {:.2} GB reclaimable:", groups.len(), gb(reclaimable));
+    for group in &groups {
+        println!("  {:.2} GB x{}  blake3:{}", gb(group.size), group.paths.len(), &group.hash[..16]);
+        for path in &group.paths {
+            println!("      {}", path.display());
+        }
+    }
+    
+    let Some(mode) = link else {
+        println!("Run `clearmodel dedup --link hardlink` (or `--link reflink`) to keep one copy of each");
+        return Ok(());
+    };
+    
+    let mut reclaimed = 0;
+    let mut failed = 0;
+    for group in &groups {
+        match group.consolidate(mode, dry_run) {
+            Ok(bytes) => reclaimed += bytes,
+            Err(e) => {
+                error!("Failed to consolidate {}: {}", group.paths[0].display(), e);
+                failed += 1;
+            }
+        }
+    }
+    
+    println!(
+        "{} {:.2} GB using {}s",
+        if dry_run { "Would reclaim" } else { "Reclaimed" },
+        gb(reclaimed),
+        mode
+    );
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} duplicate sets could not be consolidated", failed, groups.len()));
+    }
+    Ok(())
+}
+
+async fn repair_snapshots(action: Option<RepairAction>, config_path: Option<&str>, profile: Option<&str>, dry_run: bool) -> Result<()> {
+    let config = ClearModelConfig::load_profile(config_path, profile).await?;
+    let hubs = hf_repair::hub_dirs(&config);
+    if hubs.is_empty() {
+        println!("No HuggingFace cache found");
+        return Ok(());
+    }
+    
+    let mut broken = Vec::new();
+    for hub in &hubs {
+        broken.extend(hf_repair::find_broken_snapshots(hub)?);
+    }
+    if broken.is_empty() {
+        println!("No broken snapshots in {} HuggingFace cache(s)", hubs.len());
+        return Ok(());
+    }
+    
+    println!("{} broken snapshot(s):", broken.len());
+    for snapshot in &broken {
+        println!(
+            "  {} {} @ {}: {} of {} files missing",
+            snapshot.repo_type,
+            snapshot.repo_id,
+            snapshot.commit,
+            snapshot.missing.len(),
+            snapshot.links
+        );
+        for file in &snapshot.missing {
+            println!("      {}", file.display());
+        }
+    }
+    
+    let Some(action) = action else {
+        println!("Run `clearmodel repair --remove` to drop them, or `clearmodel repair --refetch` to download the missing files");
+        return Ok(());
+    };
+    
+    let commands = CommandRunner::new(&config.commands);
+    let mut failed = 0;
+    for snapshot in &broken {
+        let result = match action {
+            RepairAction::RemoveSnapshot => SecurityManager::check_allowed_roots(&snapshot.snapshot_dir, &config.security.allowed_roots),
+            RepairAction::Refetch => Ok(()),
+        };
+        let result = match result {
+            Ok(()) => snapshot.repair(action, &commands, dry_run).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("Failed to repair {} @ {}: {}", snapshot.repo_id, snapshot.commit, e);
+            failed += 1;
+        }
+    }
+    
+    let verb = match (action, dry_run) {
+        (RepairAction::RemoveSnapshot, true) => "Would remove",
+        (RepairAction::RemoveSnapshot, false) => "Removed",
+        (RepairAction::Refetch, true) => "Would re-fetch",
+        (RepairAction::Refetch, false) => "Re-fetched",
+    };
+    println!("{} {} snapshot(s)", verb, broken.len() - failed);
+    if failed > 0 {
+        anyhow::bail!("{} snapshot(s) could not be repaired", failed);
+    }
+    
+    Ok(())
+}
+
+fn print_volume_report(report: &VolumeReport) {
+    let gb = |bytes: u64| bytes as f64 / 1_073_741_824.0;
+    let percent = |part: u64, whole: u64| if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 };
+    let used = report.used_bytes();
+    
+    println!("Filesystem {}", report.mount_point.display());
+    println!(
+        "  size {:.2} GB, used {:.2} GB ({:.0}%), free {:.2} GB",
+        gb(report.total_bytes),
+        gb(used),
+        percent(used, report.total_bytes),
+        gb(report.available_bytes)
+    );
+    
+    println!(
+        "Managed caches: {:.2} GB ({:.0}% of used space)",
+        gb(report.cache_bytes()),
+        percent(report.cache_bytes(), used)
+    );
+    for cache in &report.caches {
+        println!(
+            "  {:>10.2} GB  {}  ({:.2} GB reclaimable)",
+            gb(cache.bytes),
+            cache.path.display(),
+            gb(cache.reclaimable)
+        );
+    }
+    
+    println!(
+        "Everything else under {}: {:.2} GB ({:.0}% of used space)",
+        report.path.display(),
+        gb(report.other_bytes),
+        percent(report.other_bytes, used)
+    );
+    for (dir, bytes) in &report.top_dirs {
+        println!("  {:>10.2} GB  {}", gb(*bytes), dir.display());
+    }
+    if report.unreadable > 0 {
+        println!("  ({} entries could not be read and are not counted)", report.unreadable);
+    }
+    
+    println!(
+        "Reclaimable under the current policy: {:.2} GB ({:.0}% of used space)",
+        gb(report.reclaimable_bytes()),
+        percent(report.reclaimable_bytes(), used)
+    );
+}
+
+fn print_bench_report(report: &BenchReport) {
+    let settings = &report.settings;
+    println!(
+        "Synthetic cache: {} files in {} directories ({:.2} MB), generated in {:.2}s",
+        report.cache.files,
+        report.cache.directories,
+        report.cache.bytes as f64 / 1_048_576.0,
+        report.generate.seconds
+    );
+    println!(
+        "Settings: {} scan workers, {} delete workers, max_parallel_operations = {}, {}",
+        settings.scan_workers,
+        settings.delete_workers,
+        settings.max_parallel_operations,
+        if settings.batched_unlinks { "batched unlinks (io_uring)" } else { "one unlink per file" }
+    );
+    for (name, phase) in [("Scan", &report.scan), ("Delete", &report.delete)] {
+        println!("  {:<7} {:>9} files in {:>7.2}s  {:>10.0} files/s", name, phase.files, phase.seconds, phase.files_per_sec);
+    }
+    println!("Compare runs with different [concurrency] scan_workers and delete_workers to tune them for this storage");
+}
+
+fn print_policy(config: &ClearModelConfig) {
+    let days = |duration: std::time::Duration| duration.as_secs() / 86400;
+    let policy = CleanupPolicy::from_config(config);
+    
+    println!("ML model caches");
+    let basis = match policy.age_basis {
+        AgeBasis::Mtime => "since last modified",
+        AgeBasis::Atime => "since last accessed",
+        AgeBasis::Btime => "since created",
+    };
+    let basis = match config.policy {
+        EvictionPolicy::Age => basis.to_string(),
+        EvictionPolicy::Lru => format!("{} or last recorded open", basis),
+    };
+    println!("  max age:          {} days {}", days(policy.max_age), basis);
+    if config.pinned_models.is_empty() {
+        println!("  pinned models:    none");
+    } else {
+        println!("  pinned models:    {}", config.pinned_models.join(", "));
+    }
+    println!("  python caches:    {}", config.python_cache_extensions.join(", "));
+    match policy.incomplete_grace {
+        Some(grace) => println!("  incomplete:       partial downloads removed after {} minutes untouched", grace.as_secs() / 60),
+        None => println!("  incomplete:       partial downloads kept until they expire"),
+    }
+    if let Some(mb) = config.min_file_size_mb {
+        println!("  min file size:    {} MB, smaller files are kept", mb);
+    }
+    if let Some(gb) = config.max_total_cache_gb {
+        println!("  total budget:     {} GB, least recently used models evicted beyond it", gb);
+    }
+    for (target, gb) in &config.target_budgets_gb {
+        println!("  {:<18}{} GB", format!("budget ({}):", target), gb);
+    }
+    
+    let on_conflict = match policy.on_conflict {
+        ConflictMode::Precedence => "first matching rule below wins",
+        ConflictMode::Prompt => "ask (first matching rule below is the default)",
+    };
+    println!("  on conflict:      {}", on_conflict);
+    println!("  rule precedence:");
+    for (rank, rule) in policy.precedence.iter().enumerate() {
+        let action = if rule.removes() { "remove" } else { "keep" };
+        println!("    {}. {:<13} {}", rank + 1, rule.label(), action);
+    }
+    println!("  files matching no rule are kept");
+    
+    let packages = CleanupPolicy::for_package_caches(config);
+    println!("Package caches (pip, uv, poetry){}", if config.package_caches.enabled { "" } else { " - disabled" });
+    println!("  max age:          {} days", days(packages.max_age));
+    for (extension, max_age) in &packages.extension_max_age {
+        println!("  *.{:<15} {} days", extension, days(*max_age));
+    }
+    
+    let compiled = CleanupPolicy::for_compile_caches(config);
+    println!("Compile caches (jax, triton, torchinductor){}", if config.compile_caches.enabled { "" } else { " - disabled" });
+    println!("  max age:          {} days", days(compiled.max_age));
+    
+    let gguf_stores = CleanupPolicy::for_gguf_stores(config);
+    let engines = CleanupPolicy::for_engine_caches(config);
+    println!("Engine caches (onnxruntime, tensorrt){}", if config.engine_caches.enabled { "" } else { " - disabled" });
+    println!("  max age:          {} days", days(engines.max_age));
+    
+    let gpu = CleanupPolicy::for_gpu_caches(config);
+    println!("GPU caches (cuda-compute, cudnn, shader-cache){}", if config.gpu_caches.enabled { "" } else { " - disabled" });
+    println!("  max age:          {} days", days(gpu.max_age));
+    
+    println!("GGUF model stores (llama.cpp, lm-studio, gpt4all){}", if config.gguf_stores.enabled { "" } else { " - disabled" });
+    println!("  max age:          {} days", days(gguf_stores.max_age));
+    println!("  file types:       {}", gguf_stores.only_extensions.join(", "));
+    
+    println!("Data caches (each download is removed as a whole)");
+    for kind in DataCacheKind::ALL {
+        let settings = kind.settings(config);
+        let policy = CleanupPolicy::for_data_cache(config, settings);
+        let state = if settings.enabled { String::new() } else { " - disabled".to_string() };
+        println!("  {:<17} {} days{}", format!("{}:", kind.name()), days(policy.max_age), state);
+    }
+    
+    for store in image_models::stores(config) {
+        let kind = store.kind.map_or_else(|| "not detected".to_string(), |kind| kind.to_string());
+        println!("Image model store {} ({})", store.name, kind);
+        println!("  max age:          {} days", days(store.policy.max_age));
+        println!("  file types:       {}", store.policy.only_extensions.join(", "));
+    }
+    
+    let dvc = CleanupPolicy::for_dvc_cache(config);
+    println!("DVC caches (unreferenced objects only){}", if config.dvc.enabled { "" } else { " - disabled" });
+    println!("  max age:          {} days", days(dvc.max_age));
+    println!("  workspaces:       {}", config.dvc.workspaces.len());
+    
+    let retention = config.older_than.unwrap_or(std::time::Duration::from_secs(config.mlflow.retention_days as u64 * 24 * 3600));
+    println!("MLflow artifacts (runs that ended earlier){}", if config.mlflow.enabled { "" } else { " - disabled" });
+    println!("  retention:        {} days", days(retention));
+    
+    println!("Weights & Biases (cache, staging and run media){}", if config.wandb.enabled { "" } else { " - disabled" });
+    let cache_age = config.older_than.unwrap_or(std::time::Duration::from_secs(config.wandb.max_age_days as u64 * 24 * 3600));
+    println!("  cache max age:    {} days", days(cache_age));
+    for project in experiments::wandb_projects(config) {
+        println!("  {}: {} days", project.runs.display(), days(project.retention));
+    }
+    
+    let notebooks = &config.notebooks;
+    let notebook_age = |days: u32| config.older_than.unwrap_or(std::time::Duration::from_secs(days as u64 * 24 * 3600));
+    println!("Notebooks (jupyter-runtime, kernelspecs, ipython, nbconvert){}", if notebooks.enabled { "" } else { " - disabled" });
+    println!("  runtime max age:  {} days", days(notebook_age(notebooks.runtime_max_age_days)));
+    println!("  ipython history:  over {} MB", notebooks.ipython_history_max_mb);
+    println!("  execution caches: {} days", days(notebook_age(notebooks.execution_cache_max_age_days)));
+    
+    let images = &config.containers;
+    println!("Container images (docker, podman){}", if images.enabled { "" } else { " - disabled" });
+    let image_age = config.older_than.unwrap_or(std::time::Duration::from_secs(images.max_age_days as u64 * 24 * 3600));
+    println!("  max age:          {} days", days(image_age));
+    println!("  repositories:     {}", images.patterns.join(", "));
+    println!("  dangling images:  {}", if images.prune_dangling { "pruned" } else { "kept" });
+    
+    println!("Lifecycle (applied by the daemon){}", if config.lifecycle.enabled { "" } else { " - disabled" });
+    for group in lifecycle::groups(config) {
+        println!("  {:<17} {}", format!("{}:", group.name), group.tiers);
+    }
+    if let Some(archive) = lifecycle::archive_dir(config) {
+        println!("  archive:          {}", archive.display());
+    }
+}
+
+fn print_compiled_backends() {
+    println!(
+        "clearmodel {} ({}-{})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    );
+    
+    let features = capabilities::compiled_features();
+    if features.is_empty() {
+        println!("features: none");
+    } else {
+        println!("features: {}", features.join(", "));
+    }
+    
+    println!("backends:");
+    for backend in capabilities::compiled_backends() {
+        println!("  {:<14} {}", backend.subsystem, backend.implementation);
+    }
+}
+
+fn print_capability_report(capabilities: &Capabilities) {
+    println!("clearmodel {} capability report", env!("CARGO_PKG_VERSION"));
+    for status in capabilities.report() {
+        if status.available {
+            println!("  [ok]   {}", status.name);
+        } else {
+            println!("  [--]   {} ({})", status.name, status.impact);
+        }
+    }
+    
+    if capabilities.is_degraded() {
+        println!("Some features are disabled in this environment.");
+    } else {
+        println!("All features are available.");
+    }
+}
+
+fn print_version(capabilities: &Capabilities, json: bool) -> Result<()> {
+    if json {
+        let version = serde_json::json!({
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "target": format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+            "features": capabilities::compiled_features(),
+            "backends": capabilities::compiled_backends(),
+            "capabilities": capabilities,
+        });
+        println!("{}", serde_json::to_string_pretty(&version)?);
+    } else {
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    }
+    
     Ok(())
-} 
\ No newline at end of file
+}
\ No newline at end of file
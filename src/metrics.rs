@@ -0,0 +1,225 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, warn};
+
+use crate::errors::{ClearModelError, Result};
+use crate::resource_manager::CleanupResult;
+
+/// Largest request head read before giving up on a client
+const MAX_REQUEST_BYTES: usize = 8192;
+
+#[derive(Debug, Default)]
+struct MetricValues {
+    runs_succeeded: u64,
+    runs_failed: u64,
+    bytes_freed: u64,
+    files_removed: u64,
+    errors: u64,
+    last_run_timestamp: Option<u64>,
+    last_run_duration: Duration,
+    available_bytes: Vec<(PathBuf, u64)>,
+}
+
+/// Cleanup counters and gauges in the Prometheus text format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    values: Mutex<MetricValues>,
+}
+
+impl Metrics {
+    /// Record a finished cleanup; `results` is empty when the run failed outright
+    pub fn record_run(&self, results: &[CleanupResult], duration: Duration, succeeded: bool) {
+        let Ok(mut values) = self.values.lock() else {
+            return;
+        };
+        
+        if succeeded {
+            values.runs_succeeded += 1;
+        } else {
+            values.runs_failed += 1;
+            values.errors += 1;
+        }
+        for result in results {
+            values.bytes_freed += result.bytes_freed;
+            values.files_removed += result.files_removed;
+            values.errors += result.errors.len() as u64;
+        }
+        values.last_run_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|elapsed| elapsed.as_secs());
+        values.last_run_duration = duration;
+    }
+    
+    /// Record free space on the filesystems holding the caches
+    pub fn record_available(&self, filesystems: Vec<(PathBuf, u64)>) {
+        if let Ok(mut values) = self.values.lock() {
+            values.available_bytes = filesystems;
+        }
+    }
+    
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let Ok(values) = self.values.lock() else {
+            return String::new();
+        };
+        
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+        
+        metric("clearmodel_runs_total", "counter", "Cleanup runs by outcome", &[
+            ("{result=\"success\"}".to_string(), values.runs_succeeded.to_string()),
+            ("{result=\"failure\"}".to_string(), values.runs_failed.to_string()),
+        ]);
+        metric("clearmodel_bytes_freed_total", "counter", "Bytes removed from caches", &[
+            (String::new(), values.bytes_freed.to_string()),
+        ]);
+        metric("clearmodel_files_removed_total", "counter", "Files removed from caches", &[
+            (String::new(), values.files_removed.to_string()),
+        ]);
+        metric("clearmodel_errors_total", "counter", "Files that could not be removed, plus failed runs", &[
+            (String::new(), values.errors.to_string()),
+        ]);
+        if let Some(timestamp) = values.last_run_timestamp {
+            metric("clearmodel_last_run_timestamp_seconds", "gauge", "When the last cleanup finished", &[
+                (String::new(), timestamp.to_string()),
+            ]);
+            metric("clearmodel_last_run_duration_seconds", "gauge", "How long the last cleanup took", &[
+                (String::new(), format!("{:.3}", values.last_run_duration.as_secs_f64())),
+            ]);
+        }
+        let available: Vec<_> = values.available_bytes.iter()
+            .map(|(mount_point, bytes)| {
+                (format!("{{mount_point=\"{}\"}}", escape_label(&mount_point.display().to_string())), bytes.to_string())
+            })
+            .collect();
+        metric("clearmodel_filesystem_available_bytes", "gauge", "Free space on filesystems holding caches", &available);
+        
+        out
+    }
+}
+
+/// Minimal HTTP listener answering `GET /metrics`
+pub struct MetricsServer {
+    listener: TcpListener,
+}
+
+impl MetricsServer {
+    pub async fn bind(address: SocketAddr) -> Result<Self> {
+        let listener = TcpListener::bind(address).await
+            .map_err(|e| ClearModelError::environment(format!(
+                "Failed to bind metrics endpoint on {}: {}",
+                address, e
+            )))?;
+        Ok(Self { listener })
+    }
+    
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+    
+    /// Serve scrapes until the daemon exits
+    pub async fn serve(self, metrics: Arc<Metrics>) {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Metrics endpoint accept failed: {}", e);
+                    continue;
+                }
+            };
+            
+            let metrics = metrics.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(Duration::from_secs(10), respond(stream, &metrics)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => debug!("Metrics request failed: {}", e),
+                    Err(_) => debug!("Metrics request timed out"),
+                }
+            });
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics.render()),
+        (Some("GET"), Some(_)) => ("404 Not Found", "Not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "Method not allowed\n".to_string()),
+    };
+    
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_metrics_endpoint_serves_counters() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.record_run(&[CleanupResult {
+            path: PathBuf::from("/cache"),
+            files_removed: 3,
+            bytes_freed: 4096,
+            errors: vec!["Permission denied".to_string()],
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: Default::default(),
+            needs_privileges: Vec::new(),
+            failed: Vec::new(),
+            duration: Duration::from_millis(5),
+        }], Duration::from_millis(1500), true);
+        metrics.record_available(vec![(PathBuf::from("/"), 1024)]);
+        
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let address = server.local_addr().unwrap();
+        tokio::spawn(server.serve(metrics));
+        
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("clearmodel_runs_total{result=\"success\"} 1\n"));
+        assert!(response.contains("clearmodel_bytes_freed_total 4096\n"));
+        assert!(response.contains("clearmodel_errors_total 1\n"));
+        assert!(response.contains("clearmodel_last_run_duration_seconds 1.500\n"));
+        assert!(response.contains("clearmodel_filesystem_available_bytes{mount_point=\"/\"} 1024\n"));
+    }
+}
\ No newline at end of file
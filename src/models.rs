@@ -0,0 +1,547 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, warn};
+use walkdir::WalkDir;
+
+use crate::allocation::{self, SizeBasis};
+use crate::gguf;
+use crate::hf_datasets;
+use crate::image_models;
+use crate::torch_caches;
+
+/// Registry host and namespace Ollama uses for official models
+const OLLAMA_DEFAULT_REGISTRY: &str = "registry.ollama.ai";
+const OLLAMA_DEFAULT_NAMESPACE: &str = "library";
+
+/// Where a cached model comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelSource {
+    HuggingFace,
+    Ollama,
+    TorchHub,
+    HfDataset,
+    ImageModel,
+    Gguf,
+}
+
+impl fmt::Display for ModelSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelSource::HuggingFace => write!(f, "huggingface"),
+            ModelSource::Ollama => write!(f, "ollama"),
+            ModelSource::TorchHub => write!(f, "torch-hub"),
+            ModelSource::HfDataset => write!(f, "hf-datasets"),
+            ModelSource::ImageModel => write!(f, "image-model"),
+            ModelSource::Gguf => write!(f, "gguf"),
+        }
+    }
+}
+
+/// A model identified from its location inside a cache layout
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModelRef {
+    pub source: ModelSource,
+    pub name: String,
+}
+
+impl ModelRef {
+    /// Identify the model a cached file or directory belongs to
+    ///
+    /// Recognised layouts:
+    /// - HuggingFace hub: `models--org--name/...` (`datasets--` and `spaces--` are prefixed)
+    /// - Ollama manifests: `manifests/<registry>/<namespace>/<model>/<tag>`
+    /// - Torch hub: `hub/<owner>_<repo>_<ref>/...` and `hub/checkpoints/<name>-<hash>.<ext>`
+    /// - HuggingFace datasets: `datasets/<name>/<config>/<version>/<fingerprint>/...`
+    /// - Image-generation stores: `models/checkpoints/<file>`, `models/Stable-diffusion/<file>`, ...
+    /// - Any other `.gguf` file (llama.cpp, LM Studio, GPT4All), by file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::layout(path).or_else(|| Self::rooted(path))
+    }
+    
+    /// Identify a model from the layouts that can be matched below the cache root
+    fn layout(path: &Path) -> Option<Self> {
+        let components = normal_components(path);
+        Self::huggingface(&components)
+            .or_else(|| Self::ollama_manifest(&components))
+            .or_else(|| Self::torch_hub(&components))
+    }
+    
+    /// Identify a model from the layouts that are matched on the full path, because their
+    /// caches can be roots of their own
+    fn rooted(path: &Path) -> Option<Self> {
+        Self::hf_dataset(path)
+            .or_else(|| Self::image_model(&normal_components(path)))
+            .or_else(|| Self::gguf_file(path))
+    }
+    
+    fn huggingface(components: &[&str]) -> Option<Self> {
+        components.iter().find_map(|component| {
+            let (prefix, repo) = component.split_once("--")?;
+            let repo = repo.replace("--", "/");
+            let name = match prefix {
+                "models" => repo,
+                "datasets" | "spaces" => format!("{}/{}", prefix, repo),
+                _ => return None,
+            };
+            
+            Some(Self {
+                source: ModelSource::HuggingFace,
+                name,
+            })
+        })
+    }
+    
+    fn ollama_manifest(components: &[&str]) -> Option<Self> {
+        let index = components.iter().position(|component| *component == "manifests")?;
+        let [registry, namespace, model, tag] = components.get(index + 1..index + 5)? else {
+            return None;
+        };
+        
+        let name = if *registry == OLLAMA_DEFAULT_REGISTRY && *namespace == OLLAMA_DEFAULT_NAMESPACE {
+            format!("{}:{}", model, tag)
+        } else if *registry == OLLAMA_DEFAULT_REGISTRY {
+            format!("{}/{}:{}", namespace, model, tag)
+        } else {
+            format!("{}/{}/{}:{}", registry, namespace, model, tag)
+        };
+        
+        Some(Self {
+            source: ModelSource::Ollama,
+            name,
+        })
+    }
+    
+    fn torch_hub(components: &[&str]) -> Option<Self> {
+        let index = components.iter().rposition(|component| *component == "hub")?;
+        let entry = components.get(index + 1)?;
+        
+        let name = if *entry == "checkpoints" {
+            torch_caches::checkpoint_name(components.get(index + 2)?)
+        } else {
+            // Repositories are checked out as <owner>_<repo>_<ref>
+            let (owner, rest) = entry.split_once('_')?;
+            let (repo, _) = rest.rsplit_once('_')?;
+            format!("{}/{}", owner, repo)
+        };
+        
+        Some(Self {
+            source: ModelSource::TorchHub,
+            name,
+        })
+    }
+    
+    fn image_model(components: &[&str]) -> Option<Self> {
+        Some(Self {
+            source: ModelSource::ImageModel,
+            name: image_models::model_name(components)?,
+        })
+    }
+    
+    fn gguf_file(path: &Path) -> Option<Self> {
+        let name = gguf::model_name(path.file_name()?.to_str()?)?;
+        Some(Self {
+            source: ModelSource::Gguf,
+            name: name.to_string(),
+        })
+    }
+    
+    fn hf_dataset(path: &Path) -> Option<Self> {
+        let (name, _) = hf_datasets::locate(path)?;
+        Some(Self {
+            source: ModelSource::HfDataset,
+            name,
+        })
+    }
+}
+
+impl fmt::Display for ModelRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.source)
+    }
+}
+
+/// Models that must never be deleted, matched by glob pattern against model names
+#[derive(Debug, Clone, Default)]
+pub struct PinnedModels {
+    patterns: Vec<String>,
+    matcher: GlobSet,
+    ollama_blobs: HashSet<String>,
+}
+
+impl PinnedModels {
+    /// Build the keep-list, resolving pinned Ollama models in the default models directory
+    pub fn new(patterns: &[String]) -> Self {
+        Self::with_ollama_root(patterns, ollama_models_dir().as_deref())
+    }
+    
+    /// Build the keep-list, resolving pinned Ollama models under `ollama_root`
+    pub fn with_ollama_root(patterns: &[String], ollama_root: Option<&Path>) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        let mut valid = Vec::new();
+        
+        for pattern in patterns {
+            match GlobBuilder::new(pattern).case_insensitive(true).build() {
+                Ok(glob) => {
+                    builder.add(glob);
+                    valid.push(pattern.clone());
+                }
+                Err(e) => warn!("Ignoring invalid pinned model pattern {:?}: {}", pattern, e),
+            }
+        }
+        
+        let matcher = builder.build().unwrap_or_default();
+        let mut pinned = Self {
+            patterns: valid,
+            matcher,
+            ollama_blobs: HashSet::new(),
+        };
+        
+        if let Some(root) = ollama_root.filter(|_| !pinned.is_empty()) {
+            pinned.ollama_blobs = pinned.pinned_ollama_blobs(root);
+        }
+        
+        pinned
+    }
+    
+    /// Validate a pin pattern
+    pub fn validate_pattern(pattern: &str) -> std::result::Result<(), String> {
+        GlobBuilder::new(pattern)
+            .build()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+    
+    /// Whether no models are pinned
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+    
+    /// Whether a model name matches a pinned pattern
+    pub fn matches(&self, name: &str) -> bool {
+        !self.is_empty() && self.matcher.is_match(name)
+    }
+    
+    /// Whether a cached file belongs to a pinned model
+    pub fn protects(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        
+        // Ollama blobs are shared and named by digest, so they are resolved through manifests
+        if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+            if self.ollama_blobs.contains(name) {
+                return true;
+            }
+        }
+        
+        ModelRef::from_path(path)
+            .map(|model| self.matches(&model.name))
+            .unwrap_or(false)
+    }
+    
+    /// Collect the blob file names referenced by pinned Ollama manifests
+    fn pinned_ollama_blobs(&self, root: &Path) -> HashSet<String> {
+        let mut blobs = HashSet::new();
+        
+        for (model, manifest) in ollama_manifests(root) {
+            if !self.matches(&model.name) {
+                continue;
+            }
+            blobs.extend(manifest_blobs(&manifest));
+            debug!("Pinned Ollama model {} resolved", model.name);
+        }
+        
+        blobs
+    }
+}
+
+/// Ollama manifests under a models directory, with the model each one describes
+fn ollama_manifests(root: &Path) -> Vec<(ModelRef, PathBuf)> {
+    WalkDir::new(root.join("manifests"))
+        .max_depth(4)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| Some((ModelRef::from_path(entry.path())?, entry.into_path())))
+        .collect()
+}
+
+/// Blob file names an Ollama manifest references; empty when it cannot be read
+fn manifest_blobs(manifest: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(manifest) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return Vec::new();
+    };
+    
+    let config = manifest.get("config").into_iter();
+    let layers = manifest.get("layers")
+        .and_then(|layers| layers.as_array())
+        .into_iter()
+        .flatten();
+    
+    config.chain(layers)
+        .filter_map(|layer| layer.get("digest").and_then(|digest| digest.as_str()))
+        // Blobs are stored as sha256-<hex> for a digest of sha256:<hex>
+        .map(|digest| digest.replacen(':', "-", 1))
+        .collect()
+}
+
+/// A model found in the caches
+#[derive(Debug, Clone)]
+pub struct CachedModel {
+    pub model: ModelRef,
+    
+    /// Bytes of every file the model uses, including blobs shared with other models
+    pub bytes: u64,
+    
+    /// When any of its files was last read (or written, where access times are not kept)
+    pub last_access: Option<SystemTime>,
+    
+    /// Files removing the model deletes; blobs other models still use are left out
+    pub files: Vec<PathBuf>,
+    
+    /// Directories holding nothing but the model (`models--org--name`), removed with it
+    pub dirs: Vec<PathBuf>,
+}
+
+impl CachedModel {
+    /// Days since the model was last used
+    pub fn idle_days(&self) -> Option<u64> {
+        let last_access = self.last_access?;
+        Some(SystemTime::now().duration_since(last_access).map(|idle| idle.as_secs() / 86_400).unwrap_or(0))
+    }
+    
+    fn record(&mut self, metadata: &std::fs::Metadata, basis: SizeBasis) {
+        self.bytes += allocation::size_of(metadata, basis);
+        let used = metadata.accessed().or_else(|_| metadata.modified()).ok();
+        self.last_access = self.last_access.max(used);
+    }
+}
+
+/// Every model in the cache roots and the Ollama store, by source and name
+///
+/// HuggingFace repositories and torch hub entries are found by their directory layout,
+/// Ollama models through their manifests. Sizes are counted under `basis`, each file on
+/// its own.
+pub fn inventory(roots: &[PathBuf], ollama_root: Option<&Path>, basis: SizeBasis) -> Vec<CachedModel> {
+    let mut models: BTreeMap<(String, String), CachedModel> = BTreeMap::new();
+    
+    for root in roots {
+        for entry in WalkDir::new(root).into_iter().flatten().filter(|entry| entry.file_type().is_file()) {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            // Datasets caches and image stores are roots of their own, so their layouts are
+            // matched on the full path
+            let model = ModelRef::layout(relative).or_else(|| ModelRef::rooted(entry.path()));
+            let Some(model) = model.filter(|model| model.source != ModelSource::Ollama) else {
+                continue;
+            };
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            
+            let dir = model_dir(root, relative, &model);
+            let cached = models.entry((model.source.to_string(), model.name.clone()))
+                .or_insert_with(|| CachedModel { model, bytes: 0, last_access: None, files: Vec::new(), dirs: Vec::new() });
+            cached.record(&metadata, basis);
+            cached.files.push(entry.into_path());
+            if let Some(dir) = dir.filter(|dir| !cached.dirs.contains(dir)) {
+                cached.dirs.push(dir);
+            }
+        }
+    }
+    
+    if let Some(root) = ollama_root {
+        let manifests: Vec<(ModelRef, PathBuf, Vec<String>)> = ollama_manifests(root)
+            .into_iter()
+            .map(|(model, manifest)| {
+                let blobs = manifest_blobs(&manifest);
+                (model, manifest, blobs)
+            })
+            .collect();
+        let mut users: BTreeMap<&str, usize> = BTreeMap::new();
+        for blob in manifests.iter().flat_map(|(_, _, blobs)| blobs) {
+            *users.entry(blob.as_str()).or_default() += 1;
+        }
+        
+        for (model, manifest, blobs) in &manifests {
+            let mut cached = CachedModel { model: model.clone(), bytes: 0, last_access: None, files: vec![manifest.clone()], dirs: Vec::new() };
+            for blob in blobs {
+                let path = root.join("blobs").join(blob);
+                let Ok(metadata) = std::fs::metadata(&path) else {
+                    continue;
+                };
+                cached.record(&metadata, basis);
+                if users.get(blob.as_str()) == Some(&1) {
+                    cached.files.push(path);
+                }
+            }
+            models.insert((model.source.to_string(), model.name.clone()), cached);
+        }
+    }
+    
+    models.into_values().collect()
+}
+
+/// The outermost directory below `root` that belongs to the model alone, if any
+fn model_dir(root: &Path, relative: &Path, model: &ModelRef) -> Option<PathBuf> {
+    // Every config and version of a dataset sits in its own directory
+    if model.source == ModelSource::HfDataset {
+        return hf_datasets::dataset_dir(&root.join(relative));
+    }
+    // Image models are single files, apart from InvokeAI's diffusers directories
+    if model.source == ModelSource::ImageModel {
+        return image_models::model_dir(&root.join(relative));
+    }
+    relative.ancestors()
+        .skip(1)
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .filter(|ancestor| ModelRef::from_path(ancestor).as_ref() == Some(model))
+        .last()
+        .map(|ancestor| root.join(ancestor))
+}
+
+/// The named components of a path, skipping roots and `.`/`..`
+fn normal_components(path: &Path) -> Vec<&str> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Matches model names against a name or glob pattern, ignoring case
+pub fn model_matcher(pattern: &str) -> std::result::Result<GlobSet, String> {
+    let glob = GlobBuilder::new(pattern)
+        .case_insensitive(true)
+        .literal_separator(false)
+        .build()
+        .map_err(|e| e.to_string())?;
+    GlobSetBuilder::new().add(glob).build().map_err(|e| e.to_string())
+}
+
+/// Location of the Ollama model store
+pub fn ollama_models_dir() -> Option<PathBuf> {
+    std::env::var_os("OLLAMA_MODELS")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".ollama").join("models")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_model_ref_from_cache_layouts() {
+        let hf = ModelRef::from_path(Path::new(
+            "/home/u/.cache/huggingface/hub/models--meta-llama--Llama-3-8B/blobs/abc123"
+        )).unwrap();
+        assert_eq!(hf.source, ModelSource::HuggingFace);
+        assert_eq!(hf.name, "meta-llama/Llama-3-8B");
+        
+        let dataset = ModelRef::from_path(Path::new("/hf/hub/datasets--squad/snapshots/x/data.arrow")).unwrap();
+        assert_eq!(dataset.name, "datasets/squad");
+        
+        let ollama = ModelRef::from_path(Path::new(
+            "/home/u/.ollama/models/manifests/registry.ollama.ai/library/llama3/8b"
+        )).unwrap();
+        assert_eq!(ollama.source, ModelSource::Ollama);
+        assert_eq!(ollama.name, "llama3:8b");
+        
+        let torch = ModelRef::from_path(Path::new(
+            "/home/u/.cache/torch/hub/pytorch_vision_v0.10.0/hubconf.py"
+        )).unwrap();
+        assert_eq!(torch.source, ModelSource::TorchHub);
+        assert_eq!(torch.name, "pytorch/vision");
+        
+        let checkpoint = ModelRef::from_path(Path::new(
+            "/home/u/.cache/torch/hub/checkpoints/resnet50-0676ba61.pth"
+        )).unwrap();
+        assert_eq!(checkpoint.name, "resnet50");
+        
+        let dataset = ModelRef::from_path(Path::new(
+            "/home/u/.cache/huggingface/datasets/glue/mrpc/1.0.0/dacbe312/glue-train.arrow"
+        )).unwrap();
+        assert_eq!((dataset.source, dataset.name.as_str()), (ModelSource::HfDataset, "glue"));
+        
+        assert!(ModelRef::from_path(Path::new("/home/u/.cache/misc/file.bin")).is_none());
+    }
+    
+    #[test]
+    fn test_pinned_patterns() {
+        let pinned = PinnedModels::with_ollama_root(&["meta-llama/*".to_string()], None);
+        
+        assert!(pinned.protects(Path::new("/hf/hub/models--meta-llama--Llama-3-8B/blobs/abc")));
+        assert!(pinned.protects(Path::new("/hf/hub/models--META-LLAMA--Llama-3-8B/blobs/abc")));
+        assert!(!pinned.protects(Path::new("/hf/hub/models--mistralai--Mistral-7B/blobs/abc")));
+        assert!(!PinnedModels::default().protects(Path::new("/hf/hub/models--meta-llama--x/blobs/a")));
+    }
+    
+    #[test]
+    fn test_pinned_ollama_blobs_are_protected() {
+        let temp_dir = TempDir::new().unwrap();
+        let manifest_dir = temp_dir.path().join("manifests/registry.ollama.ai/library/llama3");
+        fs::create_dir_all(&manifest_dir).unwrap();
+        fs::write(
+            manifest_dir.join("8b"),
+            r#"{"config":{"digest":"sha256:aaa"},"layers":[{"digest":"sha256:bbb"}]}"#,
+        ).unwrap();
+        
+        let pinned = PinnedModels::with_ollama_root(&["llama3:*".to_string()], Some(temp_dir.path()));
+        let blobs = temp_dir.path().join("blobs");
+        
+        assert!(pinned.protects(&blobs.join("sha256-aaa")));
+        assert!(pinned.protects(&blobs.join("sha256-bbb")));
+        assert!(!pinned.protects(&blobs.join("sha256-ccc")));
+    }
+    
+    #[test]
+    fn test_inventory_groups_files_by_model() {
+        let temp_dir = TempDir::new().unwrap();
+        let hf = temp_dir.path().join("huggingface");
+        fs::create_dir_all(hf.join("hub/models--org--model/blobs")).unwrap();
+        fs::create_dir_all(hf.join("hub/models--org--model/refs")).unwrap();
+        fs::write(hf.join("hub/models--org--model/blobs/abc"), vec![0u8; 100]).unwrap();
+        fs::write(hf.join("hub/models--org--model/refs/main"), "abc").unwrap();
+        fs::create_dir_all(hf.join("hub/models--org--other/blobs")).unwrap();
+        fs::write(hf.join("hub/models--org--other/blobs/def"), vec![0u8; 10]).unwrap();
+        
+        let ollama = temp_dir.path().join("ollama");
+        for tag in ["8b", "70b"] {
+            let manifest_dir = ollama.join("manifests/registry.ollama.ai/library/llama3");
+            fs::create_dir_all(&manifest_dir).unwrap();
+            fs::write(
+                manifest_dir.join(tag),
+                format!(r#"{{"config":{{"digest":"sha256:shared"}},"layers":[{{"digest":"sha256:{}"}}]}}"#, tag),
+            ).unwrap();
+        }
+        fs::create_dir_all(ollama.join("blobs")).unwrap();
+        for (blob, size) in [("sha256-shared", 5), ("sha256-8b", 800), ("sha256-70b", 7000)] {
+            fs::write(ollama.join("blobs").join(blob), vec![0u8; size]).unwrap();
+        }
+        
+        let models = inventory(std::slice::from_ref(&hf), Some(&ollama), SizeBasis::Apparent);
+        let names: Vec<String> = models.iter().map(|cached| cached.model.to_string()).collect();
+        assert_eq!(names, ["org/model (huggingface)", "org/other (huggingface)", "llama3:70b (ollama)", "llama3:8b (ollama)"]);
+        
+        assert_eq!(models[0].bytes, 103);
+        assert_eq!(models[0].dirs, [hf.join("hub/models--org--model")]);
+        assert!(models[0].last_access.is_some());
+        
+        // The shared config blob counts toward both sizes but is removed with neither
+        assert_eq!(models[3].bytes, 805);
+        assert!(models[3].files.contains(&ollama.join("blobs/sha256-8b")));
+        assert!(!models[3].files.contains(&ollama.join("blobs/sha256-shared")));
+        
+        let matcher = model_matcher("ORG/*").unwrap();
+        assert!(matcher.is_match("org/model") && !matcher.is_match("llama3:8b"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,153 @@
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::config::ClearModelConfig;
+
+/// One worker, shared by every throttled root, so they are processed a file at a time
+static SERIAL_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .thread_name(|_| "clearmodel-netfs".to_string())
+        .build()
+        .expect("failed to start the network filesystem worker")
+});
+
+/// What happens to cache roots on network filesystems (NFS, SMB, FUSE, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkFsMode {
+    /// Leave them alone; shared team caches are not one user's to clean
+    #[default]
+    Skip,
+    
+    /// Clean them like local caches
+    Allow,
+    
+    /// Clean them one file at a time, to go easy on the server
+    Throttle,
+}
+
+/// The network (or FUSE) filesystem `path` is on, by name; `None` for local filesystems
+#[cfg(target_os = "linux")]
+pub fn network_filesystem(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid buffer for the call to fill
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // Every magic number fits in 32 bits, whatever the width of `f_type`
+    network_magic(stat.f_type as u32)
+}
+
+/// The network (or FUSE) filesystem `path` is on, by name; `None` for local filesystems
+#[cfg(target_os = "macos")]
+pub fn network_filesystem(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid buffer for the call to fill
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // SAFETY: the kernel NUL-terminates the filesystem type name
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    match name.to_bytes() {
+        b"nfs" => Some("nfs"),
+        b"smbfs" => Some("smb"),
+        b"afpfs" => Some("afp"),
+        b"webdav" => Some("webdav"),
+        name if name.starts_with(b"macfuse") || name.starts_with(b"osxfuse") => Some("fuse"),
+        _ => None,
+    }
+}
+
+/// The network (or FUSE) filesystem `path` is on, by name; `None` for local filesystems
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn network_filesystem(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Name of a network or FUSE filesystem from its `statfs` magic number
+#[cfg(any(target_os = "linux", test))]
+fn network_magic(magic: u32) -> Option<&'static str> {
+    match magic {
+        0x6969 => Some("nfs"),
+        0x517B => Some("smb"),
+        0xFF53_4D42 => Some("cifs"),
+        0xFE53_4D42 => Some("smb2"),
+        0x6573_5546 => Some("fuse"),
+        0x00C3_6400 => Some("ceph"),
+        0x5346_414F | 0x6B41_4653 => Some("afs"),
+        0x7375_7245 => Some("coda"),
+        0x0102_1997 => Some("9p"),
+        0x0116_1970 => Some("gfs2"),
+        0x0BD0_0BD0 => Some("lustre"),
+        0x7461_636F => Some("ocfs2"),
+        _ => None,
+    }
+}
+
+/// Whether the cache root at `root` may be cleaned under `network_filesystems`
+pub fn admitted(root: &Path, config: &ClearModelConfig) -> bool {
+    let Some(kind) = network_filesystem(root) else {
+        return true;
+    };
+    match config.network_filesystems {
+        NetworkFsMode::Skip => {
+            warn!(
+                "Skipping {:?}: it is on a network filesystem ({}); pass --allow-network-fs or set network_filesystems = \"allow\" to clean it",
+                root, kind
+            );
+            false
+        }
+        NetworkFsMode::Allow => true,
+        NetworkFsMode::Throttle => {
+            info!("{:?} is on a network filesystem ({}), cleaning it one file at a time", root, kind);
+            true
+        }
+    }
+}
+
+/// Whether files under `root` are processed one at a time
+pub fn throttled(root: &Path, config: &ClearModelConfig) -> bool {
+    config.network_filesystems == NetworkFsMode::Throttle && network_filesystem(root).is_some()
+}
+
+/// Run parallel (rayon) work, on the single shared worker when `serial` is set
+pub fn install<R: Send>(serial: bool, work: impl FnOnce() -> R + Send) -> R {
+    if serial {
+        SERIAL_POOL.install(work)
+    } else {
+        work()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_network_filesystems_are_recognised() {
+        assert_eq!(network_magic(0x6969), Some("nfs"));
+        assert_eq!(network_magic(0xFF53_4D42), Some("cifs"));
+        assert_eq!(network_magic(0x6573_5546), Some("fuse"));
+        // ext4, btrfs and tmpfs are local
+        assert_eq!(network_magic(0xEF53), None);
+        assert_eq!(network_magic(0x9123_683E), None);
+        assert_eq!(network_magic(0x0102_1994), None);
+        
+        let temp_dir = TempDir::new().unwrap();
+        if network_filesystem(temp_dir.path()).is_none() {
+            assert!(admitted(temp_dir.path(), &ClearModelConfig::default()));
+            assert!(!throttled(temp_dir.path(), &ClearModelConfig::default()));
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,225 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::ClearModelConfig;
+use crate::data_caches;
+use crate::policy::CleanupPolicy;
+
+/// Jupyter and IPython data the `notebooks` group cleans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotebookKind {
+    /// Connection files of kernels and servers in the Jupyter runtime directory
+    Runtime,
+    /// User kernel specs whose interpreter no longer exists
+    Kernelspecs,
+    /// IPython history databases grown past `ipython_history_max_mb`
+    Ipython,
+    /// jupyter-cache execution caches filled through nbclient/nbconvert (jupyter-book, MyST-NB)
+    Nbconvert,
+}
+
+impl NotebookKind {
+    pub const ALL: [NotebookKind; 4] = [NotebookKind::Runtime, NotebookKind::Kernelspecs, NotebookKind::Ipython, NotebookKind::Nbconvert];
+    
+    /// Target name
+    pub fn name(self) -> &'static str {
+        match self {
+            NotebookKind::Runtime => "jupyter-runtime",
+            NotebookKind::Kernelspecs => "kernelspecs",
+            NotebookKind::Ipython => "ipython",
+            NotebookKind::Nbconvert => "nbconvert",
+        }
+    }
+    
+    /// Directories the target looks in
+    pub fn locations(self, config: &ClearModelConfig) -> Vec<PathBuf> {
+        match self {
+            NotebookKind::Runtime => env_dir("JUPYTER_RUNTIME_DIR")
+                .or_else(|| jupyter_data_dir().map(|data| data.join("runtime")))
+                .into_iter()
+                .collect(),
+            NotebookKind::Kernelspecs => jupyter_data_dir().map(|data| data.join("kernels")).into_iter().collect(),
+            NotebookKind::Ipython => env_dir("IPYTHONDIR")
+                .or_else(|| home::home_dir().map(|home| home.join(".ipython")))
+                .into_iter()
+                .collect(),
+            NotebookKind::Nbconvert => {
+                let mut caches = config.notebooks.execution_caches.clone();
+                caches.extend(env_dir("JUPYTERCACHE"));
+                caches.sort();
+                caches.dedup();
+                caches
+            }
+        }
+    }
+    
+    /// Paths to remove under the target's directories, and the policy that removes them
+    pub fn candidates(self, config: &ClearModelConfig, roots: &[PathBuf]) -> (Vec<PathBuf>, CleanupPolicy) {
+        let settings = &config.notebooks;
+        let days = |days: u32| config.older_than.unwrap_or(Duration::from_secs(days as u64 * 24 * 3600));
+        match self {
+            NotebookKind::Runtime => {
+                let files = roots.iter().flat_map(|root| runtime_files(root)).collect();
+                (files, CleanupPolicy::for_notebooks(config, days(settings.runtime_max_age_days)))
+            }
+            // Both are removed as a whole once picked, whatever the age of their files
+            NotebookKind::Kernelspecs => {
+                let specs = roots.iter().flat_map(|root| orphaned_kernelspecs(root)).collect();
+                (specs, data_caches::eviction(&CleanupPolicy::for_notebooks(config, Duration::ZERO)))
+            }
+            NotebookKind::Ipython => {
+                let max_bytes = settings.ipython_history_max_mb * 1024 * 1024;
+                let histories = roots.iter().flat_map(|root| oversized_histories(root, max_bytes)).collect();
+                (histories, data_caches::eviction(&CleanupPolicy::for_notebooks(config, Duration::ZERO)))
+            }
+            NotebookKind::Nbconvert => {
+                let caches = roots.iter().filter(|root| root.is_dir()).cloned().collect();
+                (caches, CleanupPolicy::for_notebooks(config, days(settings.execution_cache_max_age_days)))
+            }
+        }
+    }
+}
+
+/// Jupyter's data directory: `$JUPYTER_DATA_DIR`, else `~/Library/Jupyter` on macOS,
+/// `%APPDATA%\jupyter` on Windows and `$XDG_DATA_HOME/jupyter` (`~/.local/share/jupyter`) elsewhere
+pub fn jupyter_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env_dir("JUPYTER_DATA_DIR") {
+        return Some(dir);
+    }
+    let home = home::home_dir();
+    if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library").join("Jupyter"))
+    } else if cfg!(windows) {
+        env_dir("APPDATA").map(|data| data.join("jupyter"))
+    } else {
+        env_dir("XDG_DATA_HOME")
+            .or_else(|| home.map(|home| home.join(".local").join("share")))
+            .map(|data| data.join("jupyter"))
+    }
+}
+
+/// Files in the runtime directory, except those of servers that are still running
+///
+/// Servers name their files after their process (`jpserver-<pid>.json`, `jpserver-<pid>-open.html`,
+/// `nbserver-<pid>.json`); kernel connection files carry no process and are left to the age limit.
+pub fn runtime_files(runtime: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(runtime) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| !path.file_name().and_then(|name| name.to_str()).and_then(server_pid).is_some_and(process_alive))
+        .collect();
+    files.sort();
+    files
+}
+
+/// The process of a server runtime file
+fn server_pid(file_name: &str) -> Option<u32> {
+    let rest = file_name.strip_prefix("jpserver-").or_else(|| file_name.strip_prefix("nbserver-"))?;
+    let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return true;
+    };
+    // Signal 0 only checks the process exists; EPERM means it does, under another user
+    let signalled = unsafe { libc::kill(pid, 0) } == 0;
+    signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Kernel spec directories whose `kernel.json` starts an interpreter that no longer exists,
+/// typically left by a deleted virtualenv or conda env
+pub fn orphaned_kernelspecs(kernels: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(kernels) else {
+        return Vec::new();
+    };
+    let mut specs: Vec<PathBuf> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|spec| {
+            let Some(spec_json) = std::fs::read(spec.join("kernel.json")).ok()
+                .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok()) else {
+                return false;
+            };
+            // A bare `python` is looked up on PATH, so only absolute interpreters can be checked
+            let interpreter = spec_json.get("argv").and_then(|argv| argv.get(0)).and_then(|argv0| argv0.as_str()).map(Path::new);
+            interpreter.is_some_and(|interpreter| interpreter.is_absolute() && !interpreter.exists())
+        })
+        .collect();
+    specs.sort();
+    specs
+}
+
+/// `profile_*/history.sqlite` databases larger than `max_bytes`, with their journal files
+pub fn oversized_histories(ipython: &Path, max_bytes: u64) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(ipython) else {
+        return Vec::new();
+    };
+    let mut histories = Vec::new();
+    for profile in entries.flatten().map(|entry| entry.path()) {
+        if !profile.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("profile_")) {
+            continue;
+        }
+        let history = profile.join("history.sqlite");
+        if std::fs::metadata(&history).is_ok_and(|metadata| metadata.len() > max_bytes) {
+            histories.extend(
+                ["-journal", "-wal", "-shm"].iter()
+                    .map(|suffix| profile.join(format!("history.sqlite{}", suffix)))
+                    .filter(|path| path.exists())
+            );
+            histories.push(history);
+        }
+    }
+    histories.sort();
+    histories
+}
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_only_stale_notebook_state_is_picked() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = temp_dir.path().join("runtime");
+        fs::create_dir_all(&runtime).unwrap();
+        let live = format!("jpserver-{}.json", std::process::id());
+        for name in [live.as_str(), "jpserver-999999999.json", "kernel-3f1c.json"] {
+            fs::write(runtime.join(name), "{}").unwrap();
+        }
+        assert_eq!(runtime_files(&runtime), vec![runtime.join("jpserver-999999999.json"), runtime.join("kernel-3f1c.json")]);
+        
+        let kernels = temp_dir.path().join("kernels");
+        for (name, argv0) in [("gone-env", "/nonexistent/env/bin/python"), ("system", "python3")] {
+            fs::create_dir_all(kernels.join(name)).unwrap();
+            fs::write(kernels.join(name).join("kernel.json"), format!(r#"{{"argv": ["{}", "-m", "ipykernel_launcher"], "language": "python"}}"#, argv0)).unwrap();
+        }
+        assert_eq!(orphaned_kernelspecs(&kernels), vec![kernels.join("gone-env")]);
+        
+        let ipython = temp_dir.path().join(".ipython");
+        fs::create_dir_all(ipython.join("profile_default")).unwrap();
+        fs::create_dir_all(ipython.join("profile_small")).unwrap();
+        fs::write(ipython.join("profile_default/history.sqlite"), vec![0u8; 2048]).unwrap();
+        fs::write(ipython.join("profile_default/history.sqlite-journal"), b"journal").unwrap();
+        fs::write(ipython.join("profile_small/history.sqlite"), vec![0u8; 16]).unwrap();
+        assert_eq!(oversized_histories(&ipython, 1024), vec![
+            ipython.join("profile_default/history.sqlite"),
+            ipython.join("profile_default/history.sqlite-journal"),
+        ]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,166 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::command_runner::CommandRunner;
+use crate::config::NotificationConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::report::RunReport;
+
+/// Seconds the webhook may take to answer before the notification is abandoned
+const POST_TIMEOUT_SECS: &str = "30";
+
+/// Errors listed in a notification; the rest are only counted
+const MAX_ERRORS: usize = 20;
+
+/// Discord rejects messages longer than this
+const DISCORD_MAX_CHARS: usize = 2000;
+
+/// Payload layout the webhook expects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The run summary as a JSON object, for custom receivers
+    #[default]
+    Json,
+    
+    /// A Slack incoming webhook message (`{"text": ...}`)
+    Slack,
+    
+    /// A Discord webhook message (`{"content": ...}`)
+    Discord,
+}
+
+/// Body posted for a finished run
+pub fn payload(report: &RunReport, format: WebhookFormat, host: &str) -> serde_json::Value {
+    let errors = errors(report);
+    match format {
+        WebhookFormat::Json => serde_json::json!({
+            "host": host,
+            "command": report.command,
+            "dry_run": report.dry_run,
+            "succeeded": report.succeeded,
+            "error": report.error,
+            "error_code": report.error_code,
+            "started_at": report.started_at,
+            "duration_ms": report.duration_ms,
+            "files_removed": report.totals.files_removed,
+            "bytes_freed": report.totals.bytes_freed,
+            "error_count": errors.len(),
+            "errors": errors.iter().take(MAX_ERRORS).collect::<Vec<_>>(),
+        }),
+        WebhookFormat::Slack => serde_json::json!({ "text": message(report, &errors, host) }),
+        WebhookFormat::Discord => {
+            let mut content = message(report, &errors, host);
+            if content.chars().count() > DISCORD_MAX_CHARS {
+                content = content.chars().take(DISCORD_MAX_CHARS - 1).collect::<String>() + "…";
+            }
+            serde_json::json!({ "content": content })
+        }
+    }
+}
+
+/// Post the summary of a finished run to `notifications.webhook_url`, if one is set
+///
+/// The request goes through `curl` (which `commands.allowed` must list), like remote
+/// configuration downloads. Failing to notify does not fail the run; the caller logs it.
+pub fn notify(config: &NotificationConfig, commands: &CommandRunner, report: &RunReport) -> Result<()> {
+    let Some(url) = &config.webhook_url else {
+        return Ok(());
+    };
+    if config.on_failure_only && report.succeeded {
+        debug!("Run succeeded; not notifying {}", redact(url));
+        return Ok(());
+    }
+    
+    let host = sysinfo::System::host_name().unwrap_or_else(|| "unknown host".to_string());
+    let body = serde_json::to_vec(&payload(report, config.format, &host))?;
+    let args = [
+        "--fail", "--silent", "--show-error", "--proto", "=https", "--max-time", POST_TIMEOUT_SECS,
+        "--header", "Content-Type: application/json", "--data-binary", "@-", url,
+    ];
+    let output = commands.output("curl", &args, Some(&body))?;
+    if !output.status.success() {
+        return Err(ClearModelError::environment(format!(
+            "Failed to post the run summary to {}: {}",
+            redact(url),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    info!("Posted the run summary to {}", redact(url));
+    Ok(())
+}
+
+/// Why the run failed, every error a directory reported and every file left in place, in that order
+fn errors(report: &RunReport) -> Vec<String> {
+    report.error.iter().cloned()
+        .chain(report.results.iter().flat_map(|result| result.errors.iter().cloned()))
+        .chain(report.results.iter().flat_map(|result| {
+            result.failed.iter().map(|file| format!("{}: {}", file.path.display(), file.error))
+        }))
+        .collect()
+}
+
+/// The chat message for Slack and Discord
+fn message(report: &RunReport, errors: &[String], host: &str) -> String {
+    let seconds = report.duration_ms as f64 / 1000.0;
+    let mut message = match &report.error {
+        Some(error) => format!("clearmodel {} failed on {} after {:.0}s: {}", report.command, host, seconds, error),
+        None => format!(
+            "clearmodel {} on {} {} {} files ({:.2} GB) in {:.0}s",
+            report.command,
+            host,
+            if report.dry_run { "would remove" } else { "removed" },
+            report.totals.files_removed,
+            report.totals.bytes_freed as f64 / 1_073_741_824.0,
+            seconds
+        ),
+    };
+    
+    // The run's own error is already in the first line
+    let listed: Vec<&String> = errors.iter().skip(usize::from(report.error.is_some())).collect();
+    if !listed.is_empty() {
+        message.push_str(&format!("\n{} errors:", listed.len()));
+        for error in listed.iter().take(MAX_ERRORS) {
+            message.push_str(&format!("\n• {}", error));
+        }
+        if listed.len() > MAX_ERRORS {
+            message.push_str(&format!("\n… and {} more", listed.len() - MAX_ERRORS));
+        }
+    }
+    message
+}
+
+/// Webhook URLs carry their credentials in the path, so only the host is logged
+fn redact(url: &str) -> &str {
+    let after_scheme = url.find("://").map(|at| at + 3).unwrap_or(0);
+    match url[after_scheme..].find('/') {
+        Some(slash) => &url[..after_scheme + slash],
+        None => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_payloads_summarize_the_run_and_its_errors() {
+        let mut report = RunReport::start("clean", false);
+        report.totals.files_removed = 12;
+        report.totals.bytes_freed = 3 * 1_073_741_824;
+        report.finish(None);
+        
+        let json = payload(&report, WebhookFormat::Json, "gpu-01");
+        assert_eq!((json["host"].as_str(), json["succeeded"].as_bool(), json["error_count"].as_u64()), (Some("gpu-01"), Some(true), Some(0)));
+        let slack = payload(&report, WebhookFormat::Slack, "gpu-01");
+        assert!(slack["text"].as_str().unwrap().starts_with("clearmodel clean on gpu-01 removed 12 files (3.00 GB)"));
+        
+        report.finish(Some(&ClearModelError::configuration("No cache paths configured")));
+        let discord = payload(&report, WebhookFormat::Discord, "gpu-01");
+        assert!(discord["content"].as_str().unwrap().contains("failed on gpu-01"));
+        assert_eq!(payload(&report, WebhookFormat::Json, "gpu-01")["errors"].as_array().unwrap().len(), 1);
+        
+        assert_eq!(redact("https://hooks.slack.com/services/T0/B0/secret"), "https://hooks.slack.com");
+    }
+}
\ No newline at end of file
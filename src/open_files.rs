@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::capabilities::Capabilities;
+
+/// Files that running processes hold open or have mapped into memory
+///
+/// Removing such a file frees nothing until the process lets go of it, and a model a job
+/// has mapped is one it is still using, so cleanups keep them. Processes are found through
+/// /proc, which only Linux has and containers may hide; without it nothing is detected.
+/// Only processes this user may inspect are seen.
+#[derive(Debug, Default)]
+pub struct OpenFiles {
+    /// Device and inode of every open file
+    files: HashSet<(u64, u64)>,
+}
+
+impl OpenFiles {
+    /// Files open in the processes visible now, or `None` when processes cannot be inspected
+    pub fn scan(capabilities: &Capabilities) -> Option<Self> {
+        if !capabilities.proc_fs || !cfg!(target_os = "linux") {
+            return None;
+        }
+        let open = Self::scan_proc(Path::new("/proc"));
+        debug!("{} files are open in running processes", open.files.len());
+        Some(open)
+    }
+    
+    /// Whether the file `metadata` was read from is open in a process
+    pub fn contains(&self, metadata: &std::fs::Metadata) -> bool {
+        identity(metadata).is_some_and(|identity| self.files.contains(&identity))
+    }
+    
+    /// Open and mapped files of every process under `proc`
+    fn scan_proc(proc: &Path) -> Self {
+        let mut files = HashSet::new();
+        let Ok(processes) = std::fs::read_dir(proc) else {
+            return Self { files };
+        };
+        // Libraries are mapped by most processes, so each path is stat'ed once
+        let mut mapped = HashSet::new();
+        
+        let pids = processes.flatten()
+            .filter(|entry| entry.file_name().to_str().is_some_and(|name| name.bytes().all(|byte| byte.is_ascii_digit())));
+        for process in pids.map(|entry| entry.path()) {
+            // Descriptors are stat'ed through their links, which reach files whatever their path
+            if let Ok(descriptors) = std::fs::read_dir(process.join("fd")) {
+                files.extend(descriptors.flatten()
+                    .filter_map(|descriptor| std::fs::metadata(descriptor.path()).ok())
+                    .filter(|metadata| metadata.is_file())
+                    .filter_map(|metadata| identity(&metadata)));
+            }
+            if let Ok(maps) = std::fs::read_to_string(process.join("maps")) {
+                mapped.extend(maps.lines().filter_map(mapped_path).map(PathBuf::from));
+            }
+        }
+        files.extend(mapped.iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .filter_map(|metadata| identity(&metadata)));
+        Self { files }
+    }
+}
+
+/// The file a line of `/proc/<pid>/maps` maps, unless it maps none or one since deleted
+fn mapped_path(line: &str) -> Option<&str> {
+    // address perms offset dev inode, then the path after padding
+    let path = line.splitn(6, ' ').nth(5)?.trim_start();
+    (path.starts_with('/') && !path.ends_with(" (deleted)")).then_some(path)
+}
+
+#[cfg(unix)]
+fn identity(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_mapped_paths_are_read_from_maps_lines() {
+        assert_eq!(
+            mapped_path("7f2c1a000000-7f2c1b000000 r--s 00000000 fd:01 1234567                    /home/me/.cache/model.safetensors"),
+            Some("/home/me/.cache/model.safetensors")
+        );
+        assert_eq!(mapped_path("7ffd5e3f1000-7ffd5e412000 rw-p 00000000 00:00 0                          [stack]"), None);
+        assert_eq!(mapped_path("7f2c1c000000-7f2c1c001000 rw-p 00000000 00:00 0 "), None);
+        assert_eq!(mapped_path("7f2c1d000000-7f2c1d001000 r--p 00000000 fd:01 42 /tmp/gone (deleted)"), None);
+    }
+}
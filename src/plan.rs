@@ -0,0 +1,102 @@
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::categories::CategoryBreakdown;
+
+/// What a cleanup would remove, found by a single walk of the caches
+///
+/// Built by `ResourceManager::plan` and carried out by `ResourceManager::apply`, so a
+/// caller can show the total, ask for confirmation and clean without walking twice.
+#[derive(Debug, Clone, Default)]
+pub struct CleanupPlan {
+    pub directories: Vec<PlannedDirectory>,
+}
+
+/// Candidates found under one cache root
+#[derive(Debug, Clone)]
+pub struct PlannedDirectory {
+    pub root: PathBuf,
+    pub files: Vec<PlannedFile>,
+    
+    /// Everything scanned under the root, by kind of file
+    pub categories: CategoryBreakdown,
+    
+    /// Files or directories that could not be inspected
+    pub errors: Vec<String>,
+    
+    /// Files the policy selected that this user cannot remove without sudo
+    pub needs_privileges: Vec<PathBuf>,
+}
+
+/// A file the policy would remove, as it was when the plan was made
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    
+    /// Policy rule that selected the file, as recorded in the audit log
+    #[serde(deserialize_with = "rule_named")]
+    pub rule: Rule,
+}
+
+/// Name of the policy rule that selected a file
+///
+/// An alias so serde does not tie deserialized plans to borrowed input; names are
+/// matched back to [`RULES`] instead.
+pub type Rule = &'static str;
+
+/// Every rule that selects files for removal, by the name recorded for it
+const RULES: &[Rule] = &["python_cache", "incomplete", "expired", "unreferenced_revision", "budget", "requested"];
+
+fn rule_named<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rule, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    RULES.iter()
+        .find(|rule| **rule == name)
+        .copied()
+        .ok_or_else(|| de::Error::unknown_variant(&name, RULES))
+}
+
+impl PlannedFile {
+    /// Whether the file on disk is still the one that was planned
+    ///
+    /// A file rewritten since (a fresh download under the same name) is not removed.
+    pub fn unchanged(&self, metadata: &std::fs::Metadata) -> bool {
+        metadata.len() == self.size && metadata.modified().ok() == self.modified
+    }
+}
+
+impl Default for PlannedDirectory {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::new(),
+            files: Vec::new(),
+            categories: CategoryBreakdown::new(),
+            errors: Vec::new(),
+            needs_privileges: Vec::new(),
+        }
+    }
+}
+
+impl PlannedDirectory {
+    pub fn bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+impl CleanupPlan {
+    /// Files the plan would remove
+    pub fn file_count(&self) -> u64 {
+        self.directories.iter().map(|directory| directory.files.len() as u64).sum()
+    }
+    
+    /// Bytes the plan would free
+    pub fn total_bytes(&self) -> u64 {
+        self.directories.iter().map(PlannedDirectory::bytes).sum()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.directories.iter().all(|directory| directory.files.is_empty())
+    }
+}
\ No newline at end of file
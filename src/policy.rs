@@ -0,0 +1,661 @@
+use dashmap::DashMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{ClearModelConfig, DataCacheEntry, LifecycleTiers};
+use crate::gguf;
+use crate::hf_datasets;
+use crate::models::PinnedModels;
+
+/// Which timestamp a file's age is measured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeBasis {
+    /// Last modification; weights are written once, so this is really the download time
+    #[default]
+    Mtime,
+    
+    /// Last access (or modification, if later); on `noatime` mounts this is never updated,
+    /// and `relatime` updates it at most daily
+    Atime,
+    
+    /// Creation (birth) time, from `statx` on Linux; modification time where it is unavailable
+    Btime,
+}
+
+impl AgeBasis {
+    /// The file's timestamp for this basis
+    pub fn timestamp(&self, metadata: &std::fs::Metadata) -> Option<SystemTime> {
+        let modified = metadata.modified().ok();
+        match self {
+            AgeBasis::Mtime => modified,
+            AgeBasis::Atime => metadata.accessed().ok().max(modified),
+            AgeBasis::Btime => metadata.created().ok().or(modified),
+        }
+    }
+}
+
+/// What counts as a file's last use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EvictionPolicy {
+    /// The `age_basis` timestamp alone
+    #[default]
+    Age,
+    
+    /// The latest open recorded by the usage tracker, or the `age_basis` timestamp if later;
+    /// works on `noatime` mounts
+    Lru,
+}
+
+/// Outcome of evaluating the policy for one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// Kept because it belongs to a pinned model
+    Pinned,
+    /// Removed because it is a Python bytecode cache file
+    PythonCache,
+    /// Removed because it is left over from an interrupted download
+    Incomplete,
+    /// Removed because it is older than its age limit
+    Expired,
+    /// Kept because it is younger than its age limit
+    Fresh,
+}
+
+/// Rules in the order they win when no precedence is configured
+pub const DEFAULT_PRECEDENCE: [Decision; 4] = [Decision::Pinned, Decision::PythonCache, Decision::Incomplete, Decision::Expired];
+
+/// Extensions download tools give files until they are complete
+const INCOMPLETE_EXTENSIONS: [&str; 5] = ["incomplete", "lock", "tmp", "part", "partial"];
+
+/// How a file matched by rules that disagree is settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictMode {
+    /// The rule listed first in `rule_precedence` wins
+    #[default]
+    Precedence,
+    /// Ask on the terminal, falling back to precedence when there is none
+    Prompt,
+}
+
+impl Decision {
+    /// Parse a rule name as used in `rule_precedence`
+    pub fn from_label(label: &str) -> Option<Self> {
+        DEFAULT_PRECEDENCE.into_iter().find(|rule| rule.label() == label)
+    }
+    
+    /// Short name used in reports
+    pub fn label(&self) -> &'static str {
+        match self {
+            Decision::Pinned => "pinned",
+            Decision::PythonCache => "python_cache",
+            Decision::Incomplete => "incomplete",
+            Decision::Expired => "expired",
+            Decision::Fresh => "fresh",
+        }
+    }
+    
+    /// Whether the file is removed
+    pub fn removes(&self) -> bool {
+        matches!(self, Decision::PythonCache | Decision::Incomplete | Decision::Expired)
+    }
+}
+
+/// Every rule that matched a file, and the one that decided its fate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evaluation {
+    pub matched: Vec<Decision>,
+    pub decision: Decision,
+}
+
+impl Evaluation {
+    /// Whether matching rules disagree on keeping the file
+    pub fn is_conflict(&self) -> bool {
+        self.matched.iter().any(Decision::removes) && self.matched.iter().any(|rule| !rule.removes())
+    }
+    
+    /// The highest-ranked matching rule with the given outcome
+    pub fn strongest(&self, removes: bool) -> Option<Decision> {
+        self.matched.iter().copied().find(|rule| rule.removes() == removes)
+    }
+}
+
+/// What the policy needs to know about a file, independent of where it lives
+#[derive(Debug, Clone, Default)]
+pub struct FileFacts {
+    /// Extension without the leading dot
+    pub extension: Option<String>,
+    
+    /// Whether the file sits directly inside a `__pycache__` directory
+    pub in_pycache: bool,
+    
+    /// Whether the file belongs to a pinned model
+    pub pinned: bool,
+    
+    /// Whether the file looks like a leftover from an interrupted download
+    pub incomplete: bool,
+    
+    /// Time since the file was last modified, if known
+    pub age: Option<Duration>,
+    
+    /// Size in bytes
+    pub size: u64,
+}
+
+impl FileFacts {
+    /// Gather the facts for a file on disk
+    pub fn from_path(path: &Path, policy: &CleanupPolicy, age: Option<Duration>, size: u64) -> Self {
+        let parent = path.parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|name| name.to_str());
+        
+        Self {
+            extension: path.extension().and_then(|ext| ext.to_str()).map(str::to_string),
+            in_pycache: parent == Some("__pycache__"),
+            pinned: policy.pinned.protects(path),
+            incomplete: is_incomplete_download(path, parent, size),
+            age,
+            size,
+        }
+    }
+}
+
+/// Partial-download markers, plus zero-byte files where HuggingFace keeps blobs and
+/// torch keeps checkpoints (a download that died before writing anything)
+fn is_incomplete_download(path: &Path, parent: Option<&str>, size: u64) -> bool {
+    let marked = path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| INCOMPLETE_EXTENSIONS.contains(&ext))
+        .unwrap_or(false);
+    
+    marked || (size == 0 && matches!(parent, Some("blobs") | Some("checkpoints")))
+}
+
+/// Age-based rules deciding which files in a cache directory are removed
+#[derive(Debug, Clone)]
+pub struct CleanupPolicy {
+    /// Files older than this are removed
+    pub max_age: Duration,
+    
+    /// Age limits for specific file extensions (without the leading dot)
+    pub extension_max_age: HashMap<String, Duration>,
+    
+    /// Models that are never deleted regardless of age
+    pub pinned: Arc<PinnedModels>,
+    
+    /// Order in which matching rules win, highest first
+    pub precedence: Vec<Decision>,
+    
+    /// How disagreeing rules are settled
+    pub on_conflict: ConflictMode,
+    
+    /// Interrupted downloads untouched for this long are removed regardless of `max_age`
+    /// (disabled when unset)
+    pub incomplete_grace: Option<Duration>,
+    
+    /// Snapshot revisions kept per HuggingFace repository (all when unset)
+    pub keep_revisions: Option<usize>,
+    
+    /// Timestamp ages are measured from
+    pub age_basis: AgeBasis,
+    
+    /// Last recorded open of each tracked file, under `policy = "lru"`
+    pub last_used: Option<Arc<HashMap<PathBuf, SystemTime>>>,
+    
+    /// Files smaller than this many bytes are always kept
+    pub min_size: u64,
+    
+    /// Files at least this old are always kept (`--newer-than`)
+    pub newer_than: Option<Duration>,
+    
+    /// Last use of each HuggingFace dataset build seen so far; the Arrow shards of a build
+    /// are only usable together, so they all take the age of the most recently used one
+    pub dataset_builds: Arc<DashMap<PathBuf, Option<SystemTime>>>,
+    
+    /// When not empty, files with any other extension are always kept
+    pub only_extensions: Vec<String>,
+}
+
+impl CleanupPolicy {
+    /// Policy for the configured ML cache directories
+    pub fn from_config(config: &ClearModelConfig) -> Self {
+        let policy = Self {
+            max_age: days(config.max_cache_age_days),
+            extension_max_age: HashMap::new(),
+            pinned: Arc::new(PinnedModels::new(&config.pinned_models)),
+            precedence: precedence_from(&config.rule_precedence),
+            on_conflict: config.on_conflict,
+            incomplete_grace: config.clean_incomplete_downloads
+                .then(|| Duration::from_secs(config.incomplete_grace_minutes * 60)),
+            keep_revisions: config.keep_revisions,
+            age_basis: config.age_basis,
+            last_used: last_used(config),
+            min_size: min_size(config),
+            newer_than: config.newer_than,
+            dataset_builds: Arc::default(),
+            only_extensions: Vec::new(),
+        };
+        
+        let policy = if config.lifecycle.enabled {
+            policy.with_lifecycle(&config.lifecycle.ml)
+        } else {
+            policy
+        };
+        policy.with_age_override(config)
+    }
+    
+    /// Policy for pip/uv/poetry caches, where built wheels get their own retention
+    pub fn for_package_caches(config: &ClearModelConfig) -> Self {
+        let packages = &config.package_caches;
+        let mut extension_max_age = HashMap::new();
+        extension_max_age.insert("whl".to_string(), days(packages.wheel_max_age_days));
+        
+        let policy = Self {
+            max_age: days(packages.max_age_days),
+            extension_max_age,
+            pinned: Arc::new(PinnedModels::default()),
+            precedence: DEFAULT_PRECEDENCE.to_vec(),
+            on_conflict: ConflictMode::Precedence,
+            incomplete_grace: None,
+            keep_revisions: None,
+            age_basis: config.age_basis,
+            last_used: last_used(config),
+            min_size: min_size(config),
+            newer_than: config.newer_than,
+            dataset_builds: Arc::default(),
+            only_extensions: Vec::new(),
+        };
+        
+        let policy = if config.lifecycle.enabled {
+            policy.with_lifecycle(&config.lifecycle.packages)
+        } else {
+            policy
+        };
+        policy.with_age_override(config)
+    }
+    
+    /// Policy for JAX, Triton and TorchInductor caches: one short age limit, nothing pinned
+    pub fn for_compile_caches(config: &ClearModelConfig) -> Self {
+        let policy = Self {
+            max_age: days(config.compile_caches.max_age_days),
+            extension_max_age: HashMap::new(),
+            pinned: Arc::new(PinnedModels::default()),
+            precedence: DEFAULT_PRECEDENCE.to_vec(),
+            on_conflict: ConflictMode::Precedence,
+            incomplete_grace: None,
+            keep_revisions: None,
+            age_basis: config.age_basis,
+            last_used: last_used(config),
+            min_size: min_size(config),
+            newer_than: config.newer_than,
+            dataset_builds: Arc::default(),
+            only_extensions: Vec::new(),
+        };
+        policy.with_age_override(config)
+    }
+    
+    /// Policy for ONNX Runtime and TensorRT engine caches
+    pub fn for_engine_caches(config: &ClearModelConfig) -> Self {
+        Self {
+            max_age: days(config.engine_caches.max_age_days),
+            ..Self::for_compile_caches(config)
+        }.with_age_override(config)
+    }
+    
+    /// Policy for the CUDA compute, cuDNN autotune and GL shader caches
+    pub fn for_gpu_caches(config: &ClearModelConfig) -> Self {
+        Self {
+            max_age: days(config.gpu_caches.max_age_days),
+            ..Self::for_compile_caches(config)
+        }.with_age_override(config)
+    }
+    
+    /// Policy for unreferenced DVC cache objects
+    pub fn for_dvc_cache(config: &ClearModelConfig) -> Self {
+        Self {
+            max_age: days(config.dvc.max_age_days),
+            ..Self::for_compile_caches(config)
+        }.with_age_override(config)
+    }
+    
+    /// Policy for the W&B artifact cache or one project's run staging, with its age limit
+    pub fn for_wandb(config: &ClearModelConfig, max_age: Duration) -> Self {
+        Self {
+            max_age,
+            ..Self::for_compile_caches(config)
+        }
+    }
+    
+    /// Policy for Jupyter and IPython data, with the age limit of the target
+    pub fn for_notebooks(config: &ClearModelConfig, max_age: Duration) -> Self {
+        Self {
+            max_age,
+            ..Self::for_compile_caches(config)
+        }
+    }
+    
+    /// Policy for the llama.cpp, LM Studio and GPT4All stores, where only model files are removed
+    pub fn for_gguf_stores(config: &ClearModelConfig) -> Self {
+        let policy = Self {
+            max_age: days(config.gguf_stores.max_age_days),
+            extension_max_age: HashMap::new(),
+            pinned: Arc::new(PinnedModels::new(&config.pinned_models)),
+            precedence: precedence_from(&config.rule_precedence),
+            on_conflict: config.on_conflict,
+            incomplete_grace: None,
+            keep_revisions: None,
+            age_basis: config.age_basis,
+            last_used: last_used(config),
+            min_size: min_size(config),
+            newer_than: config.newer_than,
+            dataset_builds: Arc::default(),
+            only_extensions: gguf::MODEL_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+        };
+        policy.with_age_override(config)
+    }
+    
+    /// Policy for a Whisper, spaCy, NLTK or gensim data cache
+    pub fn for_data_cache(config: &ClearModelConfig, settings: &DataCacheEntry) -> Self {
+        let policy = Self {
+            max_age: days(settings.max_age_days),
+            extension_max_age: HashMap::new(),
+            pinned: Arc::new(PinnedModels::new(&config.pinned_models)),
+            precedence: precedence_from(&config.rule_precedence),
+            on_conflict: config.on_conflict,
+            incomplete_grace: None,
+            keep_revisions: None,
+            age_basis: config.age_basis,
+            last_used: last_used(config),
+            min_size: min_size(config),
+            newer_than: config.newer_than,
+            dataset_builds: Arc::default(),
+            only_extensions: Vec::new(),
+        };
+        policy.with_age_override(config)
+    }
+    
+    /// Replace the age limits with `--older-than`, when given
+    pub fn with_age_override(mut self, config: &ClearModelConfig) -> Self {
+        if let Some(older_than) = config.older_than {
+            self.max_age = older_than;
+            self.extension_max_age.clear();
+        }
+        self
+    }
+    
+    /// Delete at the lifecycle's final tier instead of the plain age limits
+    pub fn with_lifecycle(mut self, tiers: &LifecycleTiers) -> Self {
+        self.max_age = days(tiers.delete_days);
+        self.extension_max_age.clear();
+        self
+    }
+    
+    /// Time since the file was last used: its `age_basis` timestamp, or its last recorded
+    /// open when that is later
+    pub fn age_of(&self, path: &Path, metadata: &std::fs::Metadata, now: SystemTime) -> Option<Duration> {
+        let mut used = self.last_use(path, metadata);
+        if let Some((_, build)) = hf_datasets::locate(path) {
+            let build_used = *self.dataset_builds.entry(build.clone())
+                .or_insert_with(|| hf_datasets::build_last_used(&build, |path, metadata| self.last_use(path, metadata)));
+            used = used.max(build_used);
+        }
+        used.map(|timestamp| now.duration_since(timestamp).unwrap_or_default())
+    }
+    
+    fn last_use(&self, path: &Path, metadata: &std::fs::Metadata) -> Option<SystemTime> {
+        let opened = self.last_used.as_ref().and_then(|opens| opens.get(path)).copied();
+        self.age_basis.timestamp(metadata).max(opened)
+    }
+    
+    /// Maximum age allowed for a given file
+    pub fn max_age_for(&self, path: &Path) -> Duration {
+        self.max_age_for_extension(path.extension().and_then(|ext| ext.to_str()))
+    }
+    
+    /// Maximum age allowed for files with the given extension
+    pub fn max_age_for_extension(&self, extension: Option<&str>) -> Duration {
+        extension
+            .and_then(|ext| self.extension_max_age.get(ext))
+            .copied()
+            .unwrap_or(self.max_age)
+    }
+    
+    /// Decide whether a file is kept or removed
+    pub fn decide(&self, facts: &FileFacts, python_cache_extensions: &[String]) -> Decision {
+        self.evaluate(facts, python_cache_extensions).decision
+    }
+    
+    /// Find every matching rule and settle them by precedence
+    ///
+    /// Files below `min_size`, not newer than `newer_than`, or outside `only_extensions` match
+    /// no rule, so they are kept.
+    pub fn evaluate(&self, facts: &FileFacts, python_cache_extensions: &[String]) -> Evaluation {
+        let too_old = self.newer_than.is_some_and(|limit| facts.age.is_none_or(|age| age >= limit));
+        let other_type = !self.only_extensions.is_empty() && !facts.extension.as_ref()
+            .is_some_and(|ext| self.only_extensions.iter().any(|only| only.eq_ignore_ascii_case(ext)));
+        if facts.size < self.min_size || too_old || other_type {
+            return Evaluation { matched: Vec::new(), decision: Decision::Fresh };
+        }
+        
+        let is_python_cache = facts.extension.as_ref()
+            .map(|ext| python_cache_extensions.contains(&format!(".{}", ext)))
+            .unwrap_or(false);
+        let expired = facts.age
+            .map(|age| age > self.max_age_for_extension(facts.extension.as_deref()))
+            .unwrap_or(false);
+        // The grace period keeps downloads that are still running
+        let abandoned = facts.incomplete && match (self.incomplete_grace, facts.age) {
+            (Some(grace), Some(age)) => age >= grace,
+            _ => false,
+        };
+        
+        let matched: Vec<Decision> = self.precedence.iter()
+            .copied()
+            .filter(|rule| match rule {
+                Decision::Pinned => facts.pinned,
+                Decision::PythonCache => is_python_cache || facts.in_pycache,
+                Decision::Incomplete => abandoned,
+                Decision::Expired => expired,
+                Decision::Fresh => false,
+            })
+            .collect();
+        
+        Evaluation {
+            decision: matched.first().copied().unwrap_or(Decision::Fresh),
+            matched,
+        }
+    }
+}
+
+/// Parse configured rule names, appending any left out in their default order
+fn precedence_from(labels: &[String]) -> Vec<Decision> {
+    let configured = labels.iter().filter_map(|label| Decision::from_label(label));
+    
+    let mut precedence = Vec::new();
+    for rule in configured.chain(DEFAULT_PRECEDENCE) {
+        if !precedence.contains(&rule) {
+            precedence.push(rule);
+        }
+    }
+    precedence
+}
+
+/// Recorded opens when the configuration asks for LRU
+#[cfg(feature = "usage")]
+fn last_used(config: &ClearModelConfig) -> Option<Arc<HashMap<PathBuf, SystemTime>>> {
+    (config.policy == EvictionPolicy::Lru).then(crate::usage::last_used)
+}
+
+#[cfg(not(feature = "usage"))]
+fn last_used(_config: &ClearModelConfig) -> Option<Arc<HashMap<PathBuf, SystemTime>>> {
+    None
+}
+
+fn min_size(config: &ClearModelConfig) -> u64 {
+    config.min_file_size_mb.map(|mb| (mb * 1_048_576.0) as u64).unwrap_or(0)
+}
+
+fn days(days: u32) -> Duration {
+    Duration::from_secs(days as u64 * 24 * 3600)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_wheels_use_their_own_age_limit() {
+        let mut config = ClearModelConfig::default();
+        config.package_caches.wheel_max_age_days = 30;
+        config.package_caches.max_age_days = 7;
+        let policy = CleanupPolicy::for_package_caches(&config);
+        
+        assert_eq!(policy.max_age_for(Path::new("wheels/torch-2.3.0-cp311.whl")), days(30));
+        assert_eq!(policy.max_age_for(Path::new("http/a/b/c/response")), days(7));
+        
+        let compiled = CleanupPolicy::for_compile_caches(&config);
+        assert_eq!(compiled.max_age_for(Path::new("triton/cache/abc/kernel.cubin")), days(3));
+        let engines = CleanupPolicy::for_engine_caches(&config);
+        assert_eq!(engines.max_age_for(Path::new("onnxruntime/TensorrtExecutionProvider_sm86.engine")), days(14));
+        
+        let gguf = CleanupPolicy::for_gguf_stores(&config);
+        assert_eq!(gguf.max_age_for(Path::new("lmstudio-community/Llama-3-GGUF/llama-3-Q4_K_M.gguf")), days(90));
+        assert_eq!(gguf.only_extensions, vec!["gguf".to_string(), "bin".to_string()]);
+    }
+    
+    #[test]
+    fn test_decide_checks_pins_before_age() {
+        let policy = CleanupPolicy::from_config(&ClearModelConfig::default());
+        let python = vec![".pyc".to_string()];
+        let old = FileFacts {
+            extension: Some("bin".to_string()),
+            age: Some(days(30)),
+            ..FileFacts::default()
+        };
+        
+        assert_eq!(policy.decide(&old, &python), Decision::Expired);
+        assert_eq!(policy.decide(&FileFacts { pinned: true, ..old.clone() }, &python), Decision::Pinned);
+        assert_eq!(policy.decide(&FileFacts { age: Some(days(1)), ..old.clone() }, &python), Decision::Fresh);
+        
+        let pyc = FileFacts { extension: Some("pyc".to_string()), ..FileFacts::default() };
+        assert_eq!(policy.decide(&pyc, &python), Decision::PythonCache);
+        
+        // Only files at least `min_file_size_mb` large are considered
+        let config = ClearModelConfig { min_file_size_mb: Some(100.0), ..ClearModelConfig::default() };
+        let policy = CleanupPolicy::from_config(&config);
+        assert_eq!(policy.decide(&FileFacts { size: 1024, ..old.clone() }, &python), Decision::Fresh);
+        assert_eq!(policy.decide(&FileFacts { size: 200 * 1_048_576, ..old.clone() }, &python), Decision::Expired);
+        
+        // `--older-than 12h --newer-than 7d` removes what is between half a day and a week old
+        let config = ClearModelConfig {
+            older_than: Some(Duration::from_secs(12 * 3600)),
+            newer_than: Some(days(7)),
+            ..ClearModelConfig::default()
+        };
+        let policy = CleanupPolicy::from_config(&config);
+        assert_eq!(policy.decide(&FileFacts { age: Some(days(1)), ..old.clone() }, &python), Decision::Expired);
+        assert_eq!(policy.decide(&FileFacts { age: Some(Duration::from_secs(3600)), ..old.clone() }, &python), Decision::Fresh);
+        assert_eq!(policy.decide(&old, &python), Decision::Fresh);
+    }
+    
+    #[test]
+    fn test_abandoned_downloads_are_removed_regardless_of_age() {
+        let config = ClearModelConfig {
+            clean_incomplete_downloads: true,
+            ..ClearModelConfig::default()
+        };
+        let policy = CleanupPolicy::from_config(&config);
+        let facts = |path: &str, size: u64, minutes: u64| {
+            FileFacts::from_path(Path::new(path), &policy, Some(Duration::from_secs(minutes * 60)), size)
+        };
+        
+        let partial = facts("hub/models--a--b/blobs/abc.incomplete", 1024, 120);
+        assert_eq!(policy.decide(&partial, &[]), Decision::Incomplete);
+        assert_eq!(policy.decide(&facts("hub/models--a--b/blobs/abc", 0, 120), &[]), Decision::Incomplete);
+        assert_eq!(policy.decide(&facts("torch/hub/checkpoints/resnet.pth.1f2e.partial", 10, 120), &[]), Decision::Incomplete);
+        
+        // Still downloading, a real blob, or the feature turned off
+        assert_eq!(policy.decide(&facts("hub/models--a--b/blobs/abc.incomplete", 1024, 5), &[]), Decision::Fresh);
+        assert_eq!(policy.decide(&facts("hub/models--a--b/blobs/abc", 1024, 120), &[]), Decision::Fresh);
+        let disabled = CleanupPolicy::from_config(&ClearModelConfig::default());
+        assert_eq!(disabled.decide(&partial, &[]), Decision::Fresh);
+    }
+    
+    #[test]
+    fn test_age_basis_picks_the_timestamp() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("model.safetensors");
+        std::fs::write(&path, b"weights").unwrap();
+        filetime::set_file_times(
+            &path,
+            filetime::FileTime::from_unix_time(2_000_000_000, 0),
+            filetime::FileTime::from_unix_time(1_000_000_000, 0),
+        ).unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000_000) + days(1);
+        
+        let mut policy = CleanupPolicy::from_config(&ClearModelConfig::default());
+        assert_eq!(policy.age_of(&path, &metadata, now), Some(Duration::from_secs(1_000_000_000) + days(1)));
+        policy.age_basis = AgeBasis::Atime;
+        assert_eq!(policy.age_of(&path, &metadata, now), Some(days(1)));
+        
+        // Read long before the last write still counts as used at the write
+        filetime::set_file_atime(&path, filetime::FileTime::from_unix_time(500_000_000, 0)).unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert_eq!(policy.age_of(&path, &metadata, now), Some(Duration::from_secs(1_000_000_000) + days(1)));
+        
+        policy.age_basis = AgeBasis::Btime;
+        assert!(policy.age_of(&path, &metadata, now).is_some());
+        
+        // An open recorded by the usage tracker counts even where atime is not kept
+        policy.age_basis = AgeBasis::Mtime;
+        policy.last_used = Some(Arc::new(HashMap::from([(path.clone(), now - days(2))])));
+        assert_eq!(policy.age_of(&path, &metadata, now), Some(days(2)));
+    }
+    
+    #[test]
+    fn test_dataset_shards_age_together() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let build = temp_dir.path().join("datasets/glue/mrpc/1.0.0/dacbe312");
+        std::fs::create_dir_all(&build).unwrap();
+        let (train, test) = (build.join("glue-train.arrow"), build.join("glue-test.arrow"));
+        std::fs::write(&train, b"shard").unwrap();
+        std::fs::write(&test, b"shard").unwrap();
+        filetime::set_file_mtime(&train, filetime::FileTime::from_unix_time(1_000_000_000, 0)).unwrap();
+        filetime::set_file_mtime(&test, filetime::FileTime::from_unix_time(1_500_000_000, 0)).unwrap();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_500_000_000) + days(1);
+        
+        let policy = CleanupPolicy::from_config(&ClearModelConfig::default());
+        assert_eq!(policy.age_of(&train, &std::fs::metadata(&train).unwrap(), now), Some(days(1)));
+    }
+    
+    #[test]
+    fn test_precedence_settles_conflicts() {
+        let facts = FileFacts {
+            pinned: true,
+            age: Some(days(30)),
+            ..FileFacts::default()
+        };
+        
+        let policy = CleanupPolicy::from_config(&ClearModelConfig::default());
+        let evaluation = policy.evaluate(&facts, &[]);
+        assert!(evaluation.is_conflict());
+        assert_eq!(evaluation.matched, vec![Decision::Pinned, Decision::Expired]);
+        assert_eq!(evaluation.decision, Decision::Pinned);
+        
+        let config = ClearModelConfig {
+            rule_precedence: vec!["expired".to_string()],
+            ..ClearModelConfig::default()
+        };
+        let policy = CleanupPolicy::from_config(&config);
+        assert_eq!(policy.precedence, vec![Decision::Expired, Decision::Pinned, Decision::PythonCache, Decision::Incomplete]);
+        assert_eq!(policy.decide(&facts, &[]), Decision::Expired);
+    }
+}
\ No newline at end of file
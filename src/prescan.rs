@@ -0,0 +1,183 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::allocation::SizeBasis;
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::engine_caches::{self, EngineCache};
+use crate::experiments;
+use crate::hf_caches;
+use crate::image_models;
+use crate::rate_limit::{self, IoPriority};
+use crate::size_index::{self, RefreshStats, RootSummary, SizeIndex, Throttle};
+use crate::targets;
+
+/// Outcome of a prescan
+#[derive(Debug, Default)]
+pub struct PrescanSummary {
+    pub roots: u64,
+    pub bytes: u64,
+    pub stats: RefreshStats,
+    pub elapsed: Duration,
+}
+
+/// Refresh the size index for every cache root at background priority, then return
+///
+/// Meant for login hooks: unchanged directories are not listed again, so a
+/// prescan on a quiet cache costs one `stat` per directory.
+pub fn run(config: &ClearModelConfig) -> Result<PrescanSummary> {
+    let path = size_index::index_path().ok_or_else(|| ClearModelError::environment(
+        "Cannot determine the state directory for the size index".to_string()
+    ))?;
+    
+    // Drop to the lowest CPU and IO priority so the scan does not compete with the login session
+    rate_limit::set_priority(IoPriority::Idle);
+    
+    let throttle = Throttle {
+        batch_dirs: config.prescan.batch_dirs,
+        pause: Duration::from_millis(config.prescan.pause_ms),
+    };
+    let started = Instant::now();
+    let mut index = SizeIndex::load(&path).sized_for(config);
+    let mut summary = PrescanSummary::default();
+    
+    for root in cache_roots(config) {
+        let stats = index.refresh(&root, Some(throttle));
+        summary.roots += 1;
+        summary.bytes += index.summary(&root).map(|root| root.size(config.size_basis)).unwrap_or(0);
+        summary.stats.dirs_scanned += stats.dirs_scanned;
+        summary.stats.dirs_reused += stats.dirs_reused;
+    }
+    
+    index.save(&path)?;
+    summary.elapsed = started.elapsed();
+    info!(
+        "Prescan indexed {} roots ({:.2} MB) in {:.2?}",
+        summary.roots,
+        summary.bytes as f64 / 1_048_576.0,
+        summary.elapsed
+    );
+    
+    Ok(summary)
+}
+
+/// Sizes of the cache roots as reported by `clearmodel scan`
+#[derive(Debug, Serialize)]
+pub struct ScanReport {
+    pub roots: Vec<ScannedRoot>,
+    
+    /// Engine caches broken down by the GPU architecture their engines were built for
+    pub engines: Vec<EngineCache>,
+    pub dirs_listed: u64,
+    pub dirs_unchanged: u64,
+    
+    /// Whether sizes are allocated or apparent ones
+    pub size_basis: SizeBasis,
+    
+    #[serde(skip)]
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScannedRoot {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub summary: RootSummary,
+    
+    /// Largest subdirectories, with their sizes in bytes
+    pub largest: Vec<(PathBuf, u64)>,
+}
+
+/// Bring the size index up to date at normal priority and report every cache root
+///
+/// Only directories whose mtime changed are listed, so unchanged subtrees cost a
+/// `stat` each and the sizes below them come straight from the index.
+pub fn scan(config: &ClearModelConfig, top: usize) -> Result<ScanReport> {
+    let path = size_index::index_path().ok_or_else(|| ClearModelError::environment(
+        "Cannot determine the state directory for the size index".to_string()
+    ))?;
+    
+    let started = Instant::now();
+    let mut index = SizeIndex::load(&path).sized_for(config);
+    let mut report = ScanReport {
+        roots: Vec::new(),
+        engines: engine_caches::caches(config),
+        dirs_listed: 0,
+        dirs_unchanged: 0,
+        size_basis: config.size_basis,
+        elapsed: Duration::ZERO,
+    };
+    
+    for root in cache_roots(config) {
+        let stats = index.refresh(&root, None);
+        report.dirs_listed += stats.dirs_scanned;
+        report.dirs_unchanged += stats.dirs_reused;
+        report.roots.push(ScannedRoot {
+            summary: index.summary(&root).cloned().unwrap_or_default(),
+            largest: index.largest_children(&root, top),
+            path: root,
+        });
+    }
+    
+    index.save(&path)?;
+    report.roots.sort_by_key(|root| std::cmp::Reverse(root.summary.size(config.size_basis)));
+    report.elapsed = started.elapsed();
+    Ok(report)
+}
+
+/// Every existing directory the cleaner manages
+pub fn cache_roots(config: &ClearModelConfig) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = config.existing_cache_paths().into_iter().cloned().collect();
+    if config.package_caches.enabled {
+        roots.extend(
+            targets::package_cache_locations()
+                .into_iter()
+                .map(|(_, path)| path)
+                .filter(|path| path.is_dir())
+        );
+    }
+    if config.gguf_stores.enabled {
+        roots.extend(
+            targets::gguf_store_locations()
+                .into_iter()
+                .map(|(_, path)| path)
+                .filter(|path| path.is_dir())
+        );
+    }
+    if config.engine_caches.enabled {
+        roots.extend(
+            engine_caches::locations()
+                .into_iter()
+                .flat_map(|(_, paths)| paths)
+                .filter(|path| path.is_dir())
+        );
+    }
+    if config.gpu_caches.enabled {
+        roots.extend(
+            targets::gpu_cache_locations(config)
+                .into_iter()
+                .flat_map(|(_, paths)| paths)
+                .filter(|path| path.is_dir())
+        );
+    }
+    if config.dvc.enabled {
+        roots.extend(experiments::dvc_caches(config).into_iter().filter(|path| path.is_dir()));
+    }
+    roots.extend(
+        hf_caches::instances(config)
+            .into_iter()
+            .map(|instance| instance.path)
+            .filter(|path| path.is_dir())
+    );
+    roots.extend(
+        image_models::stores(config)
+            .iter()
+            .map(|store| store.models_dir())
+            .filter(|path| path.is_dir())
+    );
+    roots.sort();
+    roots.dedup();
+    roots
+}
\ No newline at end of file
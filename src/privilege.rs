@@ -0,0 +1,443 @@
+use schemars::JsonSchema;
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use tracing::debug;
+use zeroize::Zeroizing;
+
+use crate::anchored::AnchoredRoot;
+use crate::command_runner::CommandRunner;
+use crate::errors::{ClearModelError, Result};
+
+/// `sudo`, run by absolute path so a program earlier in `PATH` cannot read the password
+const SUDO: &str = "/usr/bin/sudo";
+
+/// macOS's AppleScript runner, for Authorization Services
+const OSASCRIPT: &str = "/usr/bin/osascript";
+
+/// polkit's `pkexec`, run by absolute path so `PATH` cannot substitute another
+const PKEXEC: &str = "/usr/bin/pkexec";
+
+/// Removal helper the bundled polkit policy authorizes, in place of `rm`
+pub const REMOVE_HELPER: &str = "/usr/libexec/clearmodel-remove";
+
+/// Root-owned list of the directories [`REMOVE_HELPER`] removes files below, one per line
+pub const HELPER_ROOTS: &str = "/etc/clearmodel/privileged-roots";
+
+/// How commands that need administrator rights are run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivilegeBackend {
+    /// `sudo -S`, fed the password from `SUDO_PASSWORD`, the keyring or a prompt
+    #[default]
+    Password,
+    
+    /// `sudo --askpass` with `security.askpass_helper` (a Touch ID-capable helper, say),
+    /// or on macOS a built-in password dialog; clearmodel never sees the password
+    Askpass,
+    
+    /// macOS Authorization Services through `osascript ... with administrator privileges`,
+    /// which offers Touch ID where the Mac supports it
+    Authorization,
+    
+    /// polkit's `pkexec` on Linux desktops, which asks through the session's authentication
+    /// agent; clearmodel never sees the password
+    Pkexec,
+}
+
+/// Runs commands that need administrator rights, one implementation per [`PrivilegeBackend`]
+pub trait PrivilegeEscalator: Send + Sync {
+    /// Human-readable name of the mechanism
+    fn name(&self) -> &'static str;
+    
+    /// Whether `run` must be handed the sudo password
+    fn needs_password(&self) -> bool {
+        false
+    }
+    
+    /// Run `program args` with administrator rights, failing with its error output if it fails
+    fn run(&self, program: &str, args: &[&str], password: Option<&Secret<String>>) -> Result<()>;
+    
+    /// Program and arguments removing `paths` with administrator rights
+    fn removal<'a>(&self, paths: &[&'a str]) -> (&'static str, Vec<&'a str>) {
+        let mut args = vec!["-f", "--"];
+        args.extend_from_slice(paths);
+        ("rm", args)
+    }
+}
+
+/// Escalator for the configured backend, running `sudo`, `osascript` or `pkexec` through `commands`
+pub fn escalator(backend: PrivilegeBackend, askpass_helper: Option<&Path>, commands: &CommandRunner) -> Result<Box<dyn PrivilegeEscalator>> {
+    let commands = commands.clone();
+    match backend {
+        PrivilegeBackend::Password => Ok(Box::new(SudoPassword { commands })),
+        PrivilegeBackend::Askpass => Ok(Box::new(SudoAskpass { helper: askpass_helper.map(Path::to_path_buf), commands })),
+        PrivilegeBackend::Authorization => {
+            if !cfg!(target_os = "macos") {
+                return Err(ClearModelError::configuration(
+                    "privilege_backend = \"authorization\" is only available on macOS".to_string()
+                ));
+            }
+            Ok(Box::new(Authorization { commands }))
+        }
+        PrivilegeBackend::Pkexec => {
+            if !cfg!(target_os = "linux") {
+                return Err(ClearModelError::configuration(
+                    "privilege_backend = \"pkexec\" is only available on Linux".to_string()
+                ));
+            }
+            Ok(Box::new(Pkexec { commands }))
+        }
+    }
+}
+
+/// `sudo -S`, with the password written to its stdin
+pub struct SudoPassword {
+    commands: CommandRunner,
+}
+
+impl PrivilegeEscalator for SudoPassword {
+    fn name(&self) -> &'static str {
+        "sudo"
+    }
+    
+    fn needs_password(&self) -> bool {
+        true
+    }
+    
+    fn run(&self, program: &str, args: &[&str], password: Option<&Secret<String>>) -> Result<()> {
+        let password = password.ok_or_else(|| ClearModelError::environment(
+            "The sudo password is needed to run privileged commands".to_string()
+        ))?;
+        
+        let password_with_newline = Zeroizing::new(format!("{}\n", password.expose_secret()));
+        let output = self.commands.output(SUDO, &[&["-S", program], args].concat(), Some(password_with_newline.as_bytes()))?;
+        check(self.name(), &output)
+    }
+}
+
+/// `sudo --askpass` with a helper program (a built-in dialog on macOS when none is configured)
+pub struct SudoAskpass {
+    helper: Option<PathBuf>,
+    commands: CommandRunner,
+}
+
+impl PrivilegeEscalator for SudoAskpass {
+    fn name(&self) -> &'static str {
+        "sudo --askpass"
+    }
+    
+    fn run(&self, program: &str, args: &[&str], _password: Option<&Secret<String>>) -> Result<()> {
+        let builtin = match &self.helper {
+            Some(_) => None,
+            None => Some(BuiltinHelper(write_builtin_helper()?)),
+        };
+        let askpass = self.helper.as_deref()
+            .or(builtin.as_ref().map(|helper| helper.0.as_path()))
+            .unwrap_or(Path::new(""));
+        debug!("Using askpass helper {:?}", askpass);
+        
+        let output = self.commands.output_with_env(
+            SUDO,
+            &[&["--askpass", program], args].concat(),
+            &[("SUDO_ASKPASS", askpass.as_os_str())],
+            None
+        )?;
+        check(self.name(), &output)
+    }
+}
+
+/// macOS Authorization Services through `osascript`
+pub struct Authorization {
+    commands: CommandRunner,
+}
+
+impl PrivilegeEscalator for Authorization {
+    fn name(&self) -> &'static str {
+        "macOS authorization"
+    }
+    
+    fn run(&self, program: &str, args: &[&str], _password: Option<&Secret<String>>) -> Result<()> {
+        let output = self.commands.output(OSASCRIPT, &["-e", &authorization_script(program, args)], None)?;
+        check(self.name(), &output)
+    }
+}
+
+/// polkit's `pkexec`, which asks through the desktop's authentication agent
+///
+/// Removals go through [`REMOVE_HELPER`], the only program the policy in `packaging/polkit`
+/// authorizes; with it installed the prompt names clearmodel and one authorization is kept
+/// for a few minutes instead of asked for every batch.
+pub struct Pkexec {
+    commands: CommandRunner,
+}
+
+impl PrivilegeEscalator for Pkexec {
+    fn name(&self) -> &'static str {
+        "pkexec"
+    }
+    
+    fn run(&self, program: &str, args: &[&str], _password: Option<&Secret<String>>) -> Result<()> {
+        let output = self.commands.output(PKEXEC, &[&[program], args].concat(), None)?;
+        
+        match output.status.code() {
+            Some(0) => Ok(()),
+            // pkexec's own exit codes, as opposed to the program's
+            Some(126) => Err(ClearModelError::security(
+                "pkexec: authorization was dismissed or refused".to_string()
+            )),
+            Some(127) => Err(ClearModelError::security(format!(
+                "pkexec: not authorized, or no polkit authentication agent is running: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))),
+            _ => Err(failed(self.name(), &output.stderr)),
+        }
+    }
+    
+    fn removal<'a>(&self, paths: &[&'a str]) -> (&'static str, Vec<&'a str>) {
+        let mut args = vec!["--"];
+        args.extend_from_slice(paths);
+        (REMOVE_HELPER, args)
+    }
+}
+
+
+/// Directories listed in the helper's roots file; relative and missing ones are dropped
+pub fn helper_roots(contents: &str) -> Vec<PathBuf> {
+    contents.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(Path::new)
+        .filter(|root| root.is_absolute() && root.parent().is_some())
+        .filter_map(|root| root.canonicalize().ok())
+        .collect()
+}
+
+/// Read the helper's roots file, refusing one that anyone but root could have written
+pub fn read_helper_roots(path: &Path) -> std::result::Result<Vec<PathBuf>, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        
+        if metadata.uid() != 0 || metadata.mode() & 0o022 != 0 {
+            return Err(format!("{} must be owned by root and writable only by root", path.display()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    
+    std::fs::read_to_string(path)
+        .map(|contents| helper_roots(&contents))
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))
+}
+
+/// Remove `paths` as the privileged helper, returning those refused or not removed, with why
+///
+/// The helper runs as root for an unprivileged caller, so it trusts none of the caller's
+/// checks: a path must be absolute, its directory must resolve below one of `roots` and it
+/// must not be a directory. It is then removed through the root's [`AnchoredRoot`], so a
+/// directory swapped for a symlink meanwhile is refused rather than followed. Symlinks are
+/// removed, never followed, and files already gone count as removed.
+pub fn helper_remove(paths: &[PathBuf], roots: &[PathBuf]) -> Vec<(PathBuf, String)> {
+    paths.iter()
+        .filter_map(|path| helper_remove_one(path, roots).err().map(|reason| (path.clone(), reason)))
+        .collect()
+}
+
+fn helper_remove_one(path: &Path, roots: &[PathBuf]) -> std::result::Result<(), String> {
+    if !path.is_absolute() {
+        return Err("not an absolute path".to_string());
+    }
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err("not a file path".to_string());
+    };
+    // Resolving only picks the root; the removal walks down from it following no symlink,
+    // so a directory swapped after this makes it fail
+    let target = parent.canonicalize().map_err(|e| format!("cannot resolve its directory: {}", e))?.join(name);
+    let Some(root) = roots.iter().find(|root| target.starts_with(root)) else {
+        return Err("not below an allowed root".to_string());
+    };
+    let anchor = AnchoredRoot::open(root).map_err(|e| format!("cannot open {}: {}", root.display(), e))?;
+    
+    let expected = match target.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => return Err("is a directory".to_string()),
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.to_string()),
+    };
+    anchor.remove_file(&target, &expected).map_err(|e| e.to_string())
+}
+
+/// Fail with the command's error output unless it succeeded
+fn check(name: &str, output: &Output) -> Result<()> {
+    if !output.status.success() {
+        return Err(failed(name, &output.stderr));
+    }
+    Ok(())
+}
+
+fn failed(name: &str, stderr: &[u8]) -> ClearModelError {
+    ClearModelError::file_operation(
+        format!("Privileged command failed ({}): {}", name, String::from_utf8_lossy(stderr).trim()),
+        None
+    )
+}
+
+/// Built-in askpass helper, removed once the command has finished
+struct BuiltinHelper(PathBuf);
+
+impl Drop for BuiltinHelper {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// AppleScript running a shell command with administrator privileges
+fn authorization_script(program: &str, args: &[&str]) -> String {
+    let shell_command: Vec<String> = std::iter::once(program).chain(args.iter().copied()).map(shell_quote).collect();
+    let escaped = shell_command.join(" ").replace('\\', "\\\\").replace('"', "\\\"");
+    format!("do shell script \"{}\" with administrator privileges", escaped)
+}
+
+/// Quote a word for `sh` so it reaches the program unchanged
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+/// Write the built-in askpass helper, only executable by the current user
+#[cfg(unix)]
+fn write_builtin_helper() -> Result<PathBuf> {
+    use std::os::unix::fs::OpenOptionsExt;
+    
+    if !cfg!(target_os = "macos") {
+        return Err(ClearModelError::configuration(
+            "privilege_backend = \"askpass\" needs security.askpass_helper outside macOS".to_string()
+        ));
+    }
+    
+    let path = std::env::temp_dir().join(format!("clearmodel-askpass-{}", std::process::id()));
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o700)
+        .open(&path)
+        .map_err(|e| ClearModelError::file_operation(format!("Failed to write the askpass helper: {}", e), Some(path.clone())))?;
+    file.write_all(ASKPASS_DIALOG.as_bytes())?;
+    Ok(path)
+}
+
+#[cfg(not(unix))]
+fn write_builtin_helper() -> Result<PathBuf> {
+    Err(ClearModelError::configuration(
+        "privilege_backend = \"askpass\" is not available on this platform".to_string()
+    ))
+}
+
+/// Askpass helper showing a password dialog
+const ASKPASS_DIALOG: &str = r#"#!/bin/sh
+exec /usr/bin/osascript -e 'text returned of (display dialog "clearmodel needs administrator access to clean caches." default answer "" with hidden answer with title "clearmodel" with icon caution)'
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_authorization_script_quotes_arguments() {
+        let script = authorization_script("rm", &["-rf", "/Library/Caches/it's \"here\""]);
+        assert_eq!(
+            script,
+            r#"do shell script "'rm' '-rf' '/Library/Caches/it'\\''s \"here\"'" with administrator privileges"#
+        );
+        
+        let commands = CommandRunner::builtin();
+        let password = escalator(PrivilegeBackend::Password, None, &commands).unwrap();
+        assert!(password.needs_password());
+        let askpass = escalator(PrivilegeBackend::Askpass, Some(Path::new("/usr/local/bin/touchid-askpass")), &commands).unwrap();
+        assert!(!askpass.needs_password());
+        assert_eq!(escalator(PrivilegeBackend::Pkexec, None, &commands).is_ok(), cfg!(target_os = "linux"));
+    }
+    
+    #[test]
+    fn test_pkexec_removes_through_the_helper_by_absolute_path() {
+        let commands = CommandRunner::builtin();
+        let paths = ["/var/cache/models/a.bin", "/var/cache/models/-b.bin"];
+        assert_eq!(SudoPassword { commands: commands.clone() }.removal(&paths), ("rm", vec!["-f", "--", paths[0], paths[1]]));
+        
+        let (program, args) = Pkexec { commands: commands.clone() }.removal(&paths);
+        assert_eq!((program, args.as_slice()), (REMOVE_HELPER, ["--", paths[0], paths[1]].as_slice()));
+        
+        // The backends' programs are allowed only by their absolute paths
+        for program in [SUDO, OSASCRIPT, PKEXEC] {
+            assert!(commands.check(program).is_ok(), "{} is refused", program);
+        }
+        let path_only = CommandRunner::new(&crate::config::CommandsConfig { allowed: vec!["sudo".to_string()], ..Default::default() });
+        assert!(path_only.check(SUDO).is_err());
+        
+        let policy = include_str!("../packaging/polkit/io.github.griffincancode.clearmodel.policy");
+        assert!(policy.contains(&format!(r#"<annotate key="org.freedesktop.policykit.exec.path">{}</annotate>"#, REMOVE_HELPER)));
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_helper_only_removes_files_below_its_roots() {
+        use std::os::unix::fs::symlink;
+        
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("allowed");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(allowed.join("models")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(allowed.join("models/weights.bin"), b"w").unwrap();
+        std::fs::write(outside.join("secret"), b"s").unwrap();
+        symlink(&outside, allowed.join("escape")).unwrap();
+        symlink(outside.join("secret"), allowed.join("link")).unwrap();
+        
+        let roots = helper_roots(&format!("# cache roots\n{}\nrelative/dir\n\n", allowed.display()));
+        assert_eq!(roots, [allowed.canonicalize().unwrap()]);
+        
+        let refused = helper_remove(&[
+            allowed.join("models/weights.bin"),
+            allowed.join("models/gone.bin"),
+            allowed.join("link"),
+            allowed.join("escape/secret"),
+            allowed.join("models/../../outside/secret"),
+            outside.join("secret"),
+            allowed.join("models"),
+            PathBuf::from("relative/file"),
+        ], &roots);
+        let refused: Vec<PathBuf> = refused.into_iter().map(|(path, _)| path).collect();
+        assert_eq!(refused, [
+            allowed.join("escape/secret"),
+            allowed.join("models/../../outside/secret"),
+            outside.join("secret"),
+            allowed.join("models"),
+            PathBuf::from("relative/file"),
+        ]);
+        assert!(!allowed.join("models/weights.bin").exists());
+        assert!(allowed.join("link").symlink_metadata().is_err());
+        assert!(outside.join("secret").exists());
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_helper_refuses_a_parent_symlinked_out_of_its_root() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("allowed");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(allowed.join("models")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("weights.bin"), b"s").unwrap();
+        
+        // A cache directory the caller owns, swapped for a link out of the root
+        std::os::unix::fs::symlink(&outside, allowed.join("models/snapshot")).unwrap();
+        let roots = helper_roots(&allowed.display().to_string());
+        
+        let refused = helper_remove(&[allowed.join("models/snapshot/weights.bin")], &roots);
+        assert_eq!(refused.len(), 1);
+        assert!(outside.join("weights.bin").exists());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,150 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::time::Duration;
+
+/// Progress reporting for the scan and deletion phases
+///
+/// All bars hang off a single `MultiProgress`. When progress output is disabled
+/// (`--quiet`, or stderr is not a terminal) the draw target is hidden, so callers
+/// can drive the bars unconditionally without checking whether anything is shown.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    multi: MultiProgress,
+    scan: ProgressBar,
+    total: ProgressBar,
+}
+
+impl ProgressReporter {
+    /// Create a reporter that draws to stderr when enabled and attached to a terminal
+    pub fn new(enabled: bool) -> Self {
+        let target = if enabled && std::io::stderr().is_terminal() {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        
+        Self::with_draw_target(target)
+    }
+    
+    /// Create a reporter that never draws anything
+    pub fn hidden() -> Self {
+        Self::with_draw_target(ProgressDrawTarget::hidden())
+    }
+    
+    fn with_draw_target(target: ProgressDrawTarget) -> Self {
+        let multi = MultiProgress::with_draw_target(target);
+        
+        let scan = multi.add(ProgressBar::new_spinner());
+        scan.set_style(
+            ProgressStyle::with_template("{spinner:.green} Scanning caches: {pos} files found ({elapsed})")
+                .expect("valid scan template")
+        );
+        
+        let total = multi.add(ProgressBar::new(0));
+        total.set_style(
+            ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len} files ({percent}%) ETA {eta}")
+                .expect("valid total template")
+                .progress_chars("=>-")
+        );
+        
+        Self { multi, scan, total }
+    }
+    
+    /// Whether anything is actually being drawn
+    pub fn is_visible(&self) -> bool {
+        !self.multi.is_hidden()
+    }
+    
+    /// Start the scanning spinner
+    pub fn start_scan(&self) {
+        self.scan.enable_steady_tick(Duration::from_millis(120));
+    }
+    
+    /// Record files discovered while walking a cache directory
+    pub fn inc_scanned(&self, files: u64) {
+        self.scan.inc(files);
+    }
+    
+    /// Add a directory's pre-scan file count to the overall deletion bar
+    pub fn add_to_total(&self, files: u64) {
+        self.total.inc_length(files);
+    }
+    
+    /// Create a per-directory sub-bar sized by that directory's pre-scan total
+    pub fn directory_bar(&self, path: &Path, files: u64) -> DirectoryProgress {
+        let bar = self.multi.add(ProgressBar::new(files));
+        bar.set_style(
+            ProgressStyle::with_template("  {bar:30} {pos}/{len} {wide_msg}")
+                .expect("valid directory template")
+                .progress_chars("=>-")
+        );
+        bar.set_message(path.display().to_string());
+        
+        DirectoryProgress {
+            bar,
+            total: self.total.clone(),
+        }
+    }
+    
+    /// Hide the bars while `f` writes to the terminal (e.g. to ask a question)
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.multi.suspend(f)
+    }
+    
+    /// Stop all bars and clear them from the terminal
+    pub fn finish(&self) {
+        self.scan.finish_and_clear();
+        self.total.finish_and_clear();
+    }
+}
+
+/// Sub-bar for a single cache directory that also advances the overall bar
+pub struct DirectoryProgress {
+    bar: ProgressBar,
+    total: ProgressBar,
+}
+
+impl DirectoryProgress {
+    /// Grow both bars by files found after the bar was created
+    pub fn add_found(&self, files: u64) {
+        self.bar.inc_length(files);
+        self.total.inc_length(files);
+    }
+    
+    /// Record processed files
+    pub fn inc(&self, files: u64) {
+        self.bar.inc(files);
+        self.total.inc(files);
+    }
+}
+
+impl Drop for DirectoryProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_hidden_reporter_tracks_totals() {
+        let progress = ProgressReporter::hidden();
+        assert!(!progress.is_visible());
+        
+        progress.start_scan();
+        progress.inc_scanned(5);
+        progress.add_to_total(5);
+        
+        let dir = progress.directory_bar(Path::new("/tmp/cache"), 5);
+        dir.inc(3);
+        dir.add_found(2);
+        drop(dir);
+        
+        assert_eq!(progress.total.length(), Some(7));
+        assert_eq!(progress.total.position(), 3);
+        progress.finish();
+    }
+}
\ No newline at end of file
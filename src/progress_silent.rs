@@ -0,0 +1,58 @@
+use std::path::Path;
+
+/// Progress reporting for builds without the `tui` feature
+///
+/// Mirrors the API of the terminal reporter but never draws anything, so callers
+/// don't need to care which build they are part of.
+#[derive(Clone, Default)]
+pub struct ProgressReporter;
+
+impl ProgressReporter {
+    /// Create a reporter; progress bars are not compiled into this build
+    pub fn new(_enabled: bool) -> Self {
+        Self
+    }
+    
+    /// Create a reporter that never draws anything
+    pub fn hidden() -> Self {
+        Self
+    }
+    
+    /// Whether anything is actually being drawn
+    pub fn is_visible(&self) -> bool {
+        false
+    }
+    
+    /// Start the scanning spinner
+    pub fn start_scan(&self) {}
+    
+    /// Record files discovered while walking a cache directory
+    pub fn inc_scanned(&self, _files: u64) {}
+    
+    /// Add a directory's pre-scan file count to the overall deletion bar
+    pub fn add_to_total(&self, _files: u64) {}
+    
+    /// Create a per-directory sub-bar sized by that directory's pre-scan total
+    pub fn directory_bar(&self, _path: &Path, _files: u64) -> DirectoryProgress {
+        DirectoryProgress
+    }
+    
+    /// Hide the bars while `f` writes to the terminal (e.g. to ask a question)
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+    
+    /// Stop all bars and clear them from the terminal
+    pub fn finish(&self) {}
+}
+
+/// Sub-bar for a single cache directory
+pub struct DirectoryProgress;
+
+impl DirectoryProgress {
+    /// Grow both bars by files found after the bar was created
+    pub fn add_found(&self, _files: u64) {}
+    
+    /// Record processed files
+    pub fn inc(&self, _files: u64) {}
+}
\ No newline at end of file
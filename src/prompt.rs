@@ -0,0 +1,171 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::warn;
+
+use crate::policy::{Decision, Evaluation};
+use crate::progress::ProgressReporter;
+
+/// Answer to a conflict prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Keep,
+    Remove,
+    KeepAll,
+    RemoveAll,
+    Default,
+}
+
+impl Answer {
+    fn parse(input: &str) -> Option<Self> {
+        match input.trim() {
+            "k" | "keep" => Some(Answer::Keep),
+            "r" | "remove" => Some(Answer::Remove),
+            "K" => Some(Answer::KeepAll),
+            "R" => Some(Answer::RemoveAll),
+            "" => Some(Answer::Default),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PromptState {
+    /// Set by "keep all" / "remove all"
+    remembered: Option<bool>,
+    /// Set once it is clear there is no terminal to ask on
+    unavailable: bool,
+}
+
+/// Asks on the terminal how to settle files matched by disagreeing rules
+///
+/// Cleanup runs in parallel, so questions are serialized behind a lock and the
+/// progress bars are hidden while one is on screen.
+pub struct ConflictPrompter {
+    progress: ProgressReporter,
+    state: Mutex<PromptState>,
+}
+
+impl ConflictPrompter {
+    pub fn new(progress: ProgressReporter) -> Self {
+        Self {
+            progress,
+            state: Mutex::new(PromptState::default()),
+        }
+    }
+    
+    /// Settle a conflict, falling back to the precedence decision when nobody can be asked
+    pub fn resolve(&self, path: &Path, evaluation: &Evaluation) -> Decision {
+        let Ok(mut state) = self.state.lock() else {
+            return evaluation.decision;
+        };
+        
+        if let Some(remove) = state.remembered {
+            return evaluation.strongest(remove).unwrap_or(evaluation.decision);
+        }
+        
+        if state.unavailable {
+            return evaluation.decision;
+        }
+        
+        if !std::io::stdin().is_terminal() {
+            warn!("on_conflict = \"prompt\" but stdin is not a terminal; using rule precedence");
+            state.unavailable = true;
+            return evaluation.decision;
+        }
+        
+        let answer = self.progress.suspend(|| {
+            let stdin = std::io::stdin();
+            ask(&mut stdin.lock(), &mut std::io::stderr(), path, evaluation)
+        });
+        
+        match answer {
+            Answer::KeepAll | Answer::RemoveAll => {
+                let remove = answer == Answer::RemoveAll;
+                state.remembered = Some(remove);
+                evaluation.strongest(remove).unwrap_or(evaluation.decision)
+            }
+            Answer::Keep => evaluation.strongest(false).unwrap_or(evaluation.decision),
+            Answer::Remove => evaluation.strongest(true).unwrap_or(evaluation.decision),
+            Answer::Default => evaluation.decision,
+        }
+    }
+}
+
+/// Whether there is a terminal to ask questions on
+pub fn interactive() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+/// Ask a yes/no question on the terminal; without one the answer is no
+pub fn confirm(question: &str) -> bool {
+    if !interactive() {
+        return false;
+    }
+    
+    let stdin = std::io::stdin();
+    read_yes(&mut stdin.lock(), &mut std::io::stderr(), question)
+}
+
+fn read_yes(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> bool {
+    let _ = write!(output, "{} [y/N]: ", question);
+    let _ = output.flush();
+    
+    let mut line = String::new();
+    input.read_line(&mut line).is_ok() && matches!(line.trim(), "y" | "Y" | "yes")
+}
+
+/// Describe the conflict and read answers until one is valid
+fn ask(input: &mut impl BufRead, output: &mut impl Write, path: &Path, evaluation: &Evaluation) -> Answer {
+    let rules: Vec<String> = evaluation.matched.iter()
+        .map(|rule| format!("{} ({})", rule.label(), if rule.removes() { "remove" } else { "keep" }))
+        .collect();
+    let default = if evaluation.decision.removes() { "remove" } else { "keep" };
+    
+    let _ = writeln!(output, "Conflicting rules for {}: {}", path.display(), rules.join(", "));
+    loop {
+        let _ = write!(output, "[k]eep, [r]emove, [K]eep all, [R]emove all (default: {}): ", default);
+        let _ = output.flush();
+        
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            // End of input: nobody is there to answer
+            Ok(0) | Err(_) => return Answer::Default,
+            Ok(_) => {
+                if let Some(answer) = Answer::parse(&line) {
+                    return answer;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_ask_retries_until_valid_answer() {
+        let evaluation = Evaluation {
+            matched: vec![Decision::Pinned, Decision::Expired],
+            decision: Decision::Pinned,
+        };
+        let mut input = "maybe\nR\n".as_bytes();
+        let mut output = Vec::new();
+        
+        let answer = ask(&mut input, &mut output, Path::new("/cache/blob"), &evaluation);
+        assert_eq!(answer, Answer::RemoveAll);
+        
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("pinned (keep), expired (remove)"));
+        assert!(output.contains("default: keep"));
+        assert_eq!(ask(&mut "".as_bytes(), &mut Vec::new(), Path::new("/x"), &evaluation), Answer::Default);
+    }
+    
+    #[test]
+    fn test_confirmation_defaults_to_no() {
+        assert!(read_yes(&mut "y\n".as_bytes(), &mut Vec::new(), "Clean?"));
+        assert!(!read_yes(&mut "\n".as_bytes(), &mut Vec::new(), "Clean?"));
+        assert!(!read_yes(&mut "".as_bytes(), &mut Vec::new(), "Clean?"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,199 @@
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Holding area that removed files are renamed into instead of being deleted
+///
+/// Each run gets its own directory named after its run id, and files keep their
+/// original absolute path underneath it so they can be put back by `undo`.
+#[derive(Debug, Clone)]
+pub struct Quarantine {
+    run_dir: PathBuf,
+}
+
+/// What a purge removed (or would remove in a dry run)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PurgeSummary {
+    pub runs: u64,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl Quarantine {
+    /// Quarantine for a run in the default location
+    pub fn for_run(run_id: &str) -> Result<Self> {
+        let root = quarantine_dir().ok_or_else(|| ClearModelError::environment(
+            "Cannot determine the quarantine directory".to_string()
+        ))?;
+        
+        Ok(Self::in_dir(&root, run_id))
+    }
+    
+    /// Quarantine for a run under `root`
+    pub fn in_dir(root: &Path, run_id: &str) -> Self {
+        Self {
+            run_dir: root.join(run_id),
+        }
+    }
+    
+    /// Directory this run's files are moved into
+    pub fn run_dir(&self) -> &Path {
+        &self.run_dir
+    }
+    
+    /// Atomically move a file into quarantine with `rename`, returning where it now lives
+    ///
+    /// `rename` moves `path` to the destination it is given; the cleaner passes its anchored
+    /// rename, which refuses a file replaced since it was checked.
+    pub fn move_in(&self, path: &Path, rename: impl FnOnce(&Path) -> std::io::Result<()>) -> Result<PathBuf> {
+        let destination = self.destination(path)?;
+        
+        // A rename keeps this atomic; copying across filesystems would not be
+        rename(&destination)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to move file into quarantine (it must be on the same filesystem): {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        
+        debug!("Quarantined: {:?} -> {:?}", path, destination);
+        Ok(destination)
+    }
+    
+    /// Where `path` is kept in quarantine, creating its parent directories
+    fn destination(&self, path: &Path) -> Result<PathBuf> {
+        let relative: PathBuf = path.components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        let destination = self.run_dir.join(relative);
+        
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create quarantine directory: {}", e),
+                    Some(parent.to_path_buf())
+                ))?;
+        }
+        Ok(destination)
+    }
+}
+
+/// Root of the quarantine (`quarantine` in clearmodel's cache directory)
+pub fn quarantine_dir() -> Option<PathBuf> {
+    state::cache_dir().map(|cache| cache.join("quarantine"))
+}
+
+/// Permanently delete quarantined runs, optionally only those older than `older_than`
+pub fn purge(root: &Path, older_than: Option<Duration>, dry_run: bool) -> Result<PurgeSummary> {
+    let mut summary = PurgeSummary::default();
+    
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(summary),
+        Err(e) => {
+            return Err(ClearModelError::file_operation(
+                format!("Failed to read quarantine directory: {}", e),
+                Some(root.to_path_buf())
+            ));
+        }
+    };
+    
+    for entry in entries.flatten() {
+        let run_dir = entry.path();
+        if !entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false) {
+            continue;
+        }
+        
+        if let Some(ttl) = older_than {
+            let age = run_dir.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(run_age);
+            if age.map(|age| age < ttl).unwrap_or(true) {
+                continue;
+            }
+        }
+        
+        let (files, bytes) = WalkDir::new(&run_dir)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .fold((0u64, 0u64), |(files, bytes), entry| {
+                (files + 1, bytes + entry.metadata().map(|m| m.len()).unwrap_or(0))
+            });
+        
+        if !dry_run {
+            if let Err(e) = std::fs::remove_dir_all(&run_dir) {
+                warn!("Failed to purge {:?}: {}", run_dir, e);
+                continue;
+            }
+        }
+        
+        info!(
+            "{} quarantined run {:?}: {} files, {:.2} MB",
+            if dry_run { "Would purge" } else { "Purged" },
+            run_dir,
+            files,
+            bytes as f64 / 1_048_576.0
+        );
+        summary.runs += 1;
+        summary.files += files;
+        summary.bytes += bytes;
+    }
+    
+    Ok(summary)
+}
+
+/// Age of a run, read from the timestamp its id starts with
+fn run_age(run_id: &str) -> Option<Duration> {
+    let secs: u64 = run_id.split('-').next()?.parse().ok()?;
+    let started = UNIX_EPOCH + Duration::from_secs(secs);
+    Some(SystemTime::now().duration_since(started).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_move_in_keeps_original_layout() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("cache/blob");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, b"weights").unwrap();
+        
+        let quarantine = Quarantine::in_dir(&temp_dir.path().join("quarantine"), "000000000001-run");
+        let destination = quarantine.move_in(&file, |destination| fs::rename(&file, destination)).unwrap();
+        
+        assert!(!file.exists());
+        assert!(destination.starts_with(quarantine.run_dir()));
+        assert!(destination.ends_with("cache/blob"));
+        assert_eq!(fs::read(&destination).unwrap(), b"weights");
+    }
+    
+    #[test]
+    fn test_purge_respects_ttl() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        
+        for (run_id, age_days) in [("old", 10u64), ("new", 1)] {
+            let dir = root.join(format!("{:012}-{}", now - age_days * 86400, run_id));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("blob"), b"1234").unwrap();
+        }
+        
+        let ttl = Duration::from_secs(7 * 86400);
+        let summary = purge(root, Some(ttl), false).unwrap();
+        assert_eq!(summary, PurgeSummary { runs: 1, files: 1, bytes: 4 });
+        assert_eq!(fs::read_dir(root).unwrap().count(), 1);
+        
+        let summary = purge(root, None, false).unwrap();
+        assert_eq!(summary.runs, 1);
+        assert_eq!(fs::read_dir(root).unwrap().count(), 0);
+    }
+}
\ No newline at end of file
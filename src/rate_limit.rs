@@ -0,0 +1,173 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+use crate::config::IoRateLimitConfig;
+
+/// Unused rate that carries over, so short runs are not slowed down
+const BURST: Duration = Duration::from_secs(1);
+
+/// CPU and IO priority a cleanup runs at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IoPriority {
+    /// Leave the priority alone
+    #[default]
+    Normal,
+    
+    /// `nice 10` and the lowest best-effort IO priority (`ionice -c2 -n7`)
+    Low,
+    
+    /// `nice 19` and the idle IO class (`ionice -c3`): disk time only when nothing else wants it
+    Idle,
+}
+
+/// Paces removals and the bytes read for hashing or written for shredding
+///
+/// Each limit is a rate with a burst of one second's worth, so short runs are not slowed
+/// down. Bytes are reserved a whole file at a time, which keeps the average rate at the
+/// limit even though a single large file is read or written at full speed.
+#[derive(Debug)]
+pub struct RateLimiter {
+    files: Option<Pacer>,
+    bytes: Option<Pacer>,
+}
+
+impl RateLimiter {
+    /// A limiter for `io_rate_limit` (already validated), or `None` when no limit is set
+    pub fn from_config(config: &IoRateLimitConfig) -> Option<Self> {
+        let files = config.files_per_sec.map(|rate| Pacer::new(f64::from(rate)));
+        let bytes = config.bandwidth_mb_per_sec.map(|rate| Pacer::new(rate * 1_048_576.0));
+        if files.is_none() && bytes.is_none() {
+            return None;
+        }
+        info!(
+            "Limiting IO to {} files/s and {} of hashing or shredding",
+            config.files_per_sec.map_or("unlimited".to_string(), |rate| rate.to_string()),
+            config.bandwidth_mb_per_sec.map_or("unlimited".to_string(), |rate| format!("{:.1} MB/s", rate))
+        );
+        Some(Self { files, bytes })
+    }
+    
+    /// Wait for the turn of the next removal
+    pub fn file(&self) {
+        if let Some(files) = &self.files {
+            files.take(1.0);
+        }
+    }
+    
+    /// Wait until `bytes` more may be read or written
+    pub fn bytes(&self, bytes: u64) {
+        if let Some(pacer) = &self.bytes {
+            pacer.take(bytes as f64);
+        }
+    }
+}
+
+/// A token bucket kept as the time at which it will next be full
+#[derive(Debug)]
+struct Pacer {
+    /// Units per second
+    rate: f64,
+    full_at: Mutex<Instant>,
+}
+
+impl Pacer {
+    fn new(rate: f64) -> Self {
+        Self { rate, full_at: Mutex::new(Instant::now()) }
+    }
+    
+    /// Reserve `units` and sleep until the reservation comes due
+    fn take(&self, units: f64) {
+        let wait = {
+            let mut full_at = self.full_at.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            *full_at = (*full_at).max(now) + Duration::from_secs_f64(units / self.rate);
+            // The bucket holds a second's worth, so only what is owed beyond that waits
+            full_at.saturating_duration_since(now + BURST)
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+/// Drop the whole process to `priority`; its threads and children inherit it
+pub fn set_priority(priority: IoPriority) {
+    let (nice, io_class, io_level) = match priority {
+        IoPriority::Normal => return,
+        IoPriority::Low => (10, IOPRIO_CLASS_BE, 7),
+        IoPriority::Idle => (19, IOPRIO_CLASS_IDLE, 0),
+    };
+    debug!("Lowering priority to {:?}", priority);
+    lower_priority(nice, io_class, io_level);
+}
+
+const IOPRIO_CLASS_BE: i64 = 2;
+const IOPRIO_CLASS_IDLE: i64 = 3;
+
+#[cfg(target_os = "linux")]
+fn lower_priority(nice: i32, io_class: i64, io_level: i64) {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    
+    // SAFETY: plain syscalls on the current process with constant arguments
+    unsafe {
+        if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+            debug!("Failed to lower CPU priority");
+        }
+        if libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ((io_class as libc::c_long) << IOPRIO_CLASS_SHIFT) | io_level as libc::c_long) != 0 {
+            debug!("Failed to lower IO priority");
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn lower_priority(_nice: i32, _io_class: i64, _io_level: i64) {
+    // SAFETY: plain syscall on the current process with constant arguments
+    // PRIO_DARWIN_BG also throttles disk IO
+    unsafe {
+        if libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG) != 0 {
+            debug!("Failed to switch to background priority");
+        }
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn lower_priority(nice: i32, _io_class: i64, _io_level: i64) {
+    // SAFETY: plain syscall on the current process with constant arguments
+    unsafe {
+        if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+            debug!("Failed to lower CPU priority");
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn lower_priority(_nice: i32, _io_class: i64, _io_level: i64) {
+    debug!("Lowering priority is not supported on this platform; relying on rate limits");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_removals_are_paced_after_the_burst() {
+        let config = IoRateLimitConfig { files_per_sec: Some(20), ..IoRateLimitConfig::default() };
+        let limiter = RateLimiter::from_config(&config).unwrap();
+        
+        let started = Instant::now();
+        // One second of burst, then a quarter of a second at 20 files/s
+        for _ in 0..25 {
+            limiter.file();
+        }
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+        
+        assert!(RateLimiter::from_config(&IoRateLimitConfig::default()).is_none());
+    }
+}
\ No newline at end of file
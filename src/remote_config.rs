@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::command_runner::CommandRunner;
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Seconds a download may take before it is abandoned
+const FETCH_TIMEOUT_SECS: &str = "60";
+
+/// Principal and namespace a configuration signature is made for
+/// (`ssh-keygen -Y sign -f key -n clearmodel policy.toml`)
+pub const SIGNATURE_NAMESPACE: &str = "clearmodel";
+
+/// How a downloaded configuration is checked before it is used
+#[derive(Debug, Clone, Default)]
+pub struct Verification {
+    /// `blake3:<hex>` digest the file must have
+    pub checksum: Option<String>,
+    
+    /// OpenSSH allowed signers file that `<url>.sig` is verified against
+    pub signers: Option<PathBuf>,
+}
+
+/// Download, verify and keep a copy of a remote configuration, returning the path of the copy
+///
+/// When the download fails, the copy kept by the last successful run is used instead, after
+/// verifying it again, so a workstation that is offline keeps cleaning with the last policy.
+pub fn fetch(url: &str, verification: &Verification) -> Result<PathBuf> {
+    if !url.starts_with("https://") {
+        return Err(ClearModelError::security(format!("Remote configuration must use https: {}", url)));
+    }
+    if verification.checksum.is_none() && verification.signers.is_none() {
+        return Err(ClearModelError::security(format!(
+            "Remote configuration {} needs --config-checksum or --config-signers",
+            url
+        )));
+    }
+    let dir = cache_dir().ok_or_else(|| ClearModelError::environment(
+        "Cannot determine the state directory for remote configuration"
+    ))?;
+    std::fs::create_dir_all(&dir)?;
+    
+    let name = format!("{}.{}", &blake3::hash(url.as_bytes()).to_hex()[..16], extension(url));
+    let copy = dir.join(&name);
+    let download = dir.join(format!("{}.download", name));
+    let signature = verification.signers.as_ref().map(|_| dir.join(format!("{}.sig", name)));
+    let signature_download = signature.as_ref().map(|_| dir.join(format!("{}.sig.download", name)));
+    
+    let downloaded = download_to(url, &download).and_then(|()| match &signature_download {
+        Some(signature_download) => download_to(&format!("{}.sig", url), signature_download),
+        None => Ok(()),
+    });
+    match downloaded {
+        Ok(()) => {
+            verify(&download, signature_download.as_deref(), verification)?;
+            std::fs::rename(&download, &copy)?;
+            if let (Some(from), Some(to)) = (&signature_download, &signature) {
+                std::fs::rename(from, to)?;
+            }
+            info!("Using remote configuration {} ({:?})", url, copy);
+        }
+        Err(e) if copy.exists() => {
+            warn!("Failed to download {}, using the copy from the last run: {}", url, e);
+            verify(&copy, signature.as_deref(), verification)?;
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(copy)
+}
+
+/// Check a file against the expected checksum and signature
+pub fn verify(file: &Path, signature: Option<&Path>, verification: &Verification) -> Result<()> {
+    if let Some(expected) = &verification.checksum {
+        let Some(expected) = expected.strip_prefix("blake3:") else {
+            return Err(ClearModelError::configuration(format!(
+                "Unsupported configuration checksum {:?} (expected blake3:<hex>)",
+                expected
+            )));
+        };
+        let actual = blake3::hash(&std::fs::read(file)?).to_hex();
+        if !actual.as_str().eq_ignore_ascii_case(expected.trim()) {
+            return Err(ClearModelError::security(format!(
+                "Remote configuration checksum mismatch: expected blake3:{}, got blake3:{}",
+                expected, actual
+            )));
+        }
+    }
+    
+    if let (Some(signers), Some(signature)) = (&verification.signers, signature) {
+        let (signers, signature) = (utf8(signers)?, utf8(signature)?);
+        let args = ["-Y", "verify", "-f", signers, "-I", SIGNATURE_NAMESPACE, "-n", SIGNATURE_NAMESPACE, "-s", signature];
+        let output = CommandRunner::builtin().output("ssh-keygen", &args, Some(&std::fs::read(file)?))?;
+        if !output.status.success() {
+            return Err(ClearModelError::security(format!(
+                "Remote configuration signature is not valid: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A path as a command argument; the tools are run through [`CommandRunner`], which takes text
+fn utf8(path: &Path) -> Result<&str> {
+    path.to_str().ok_or_else(|| ClearModelError::configuration(format!("{:?} is not valid UTF-8", path)))
+}
+
+/// Download with `curl`, checked against the default `commands.allowed`, since the
+/// configuration that could change it is the one being downloaded
+fn download_to(url: &str, target: &Path) -> Result<()> {
+    let args = [
+        "--fail", "--silent", "--show-error", "--location",
+        "--proto", "=https", "--proto-redir", "=https", "--max-time", FETCH_TIMEOUT_SECS,
+        "--output", utf8(target)?, url,
+    ];
+    let output = CommandRunner::builtin().output("curl", &args, None)?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(target);
+        return Err(ClearModelError::environment(format!(
+            "Failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Format of the remote file, from the extension of its URL path
+fn extension(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    match path.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).as_deref() {
+        Some("yaml") | Some("yml") => "yaml",
+        Some("json") => "json",
+        _ => "toml",
+    }
+}
+
+/// Directory holding the last verified copies (`remote-config` in the state directory)
+fn cache_dir() -> Option<PathBuf> {
+    state::path(state::REMOTE_CONFIG)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_checksum_is_required_and_checked() {
+        assert!(fetch("https://config.example.com/policy.toml", &Verification::default()).unwrap_err().to_string().contains("--config-checksum"));
+        assert!(fetch("http://config.example.com/policy.toml", &Verification { checksum: Some("blake3:00".to_string()), signers: None }).is_err());
+        assert_eq!(extension("https://config.example.com/policy.YML?rev=3"), "yaml");
+        assert_eq!(extension("https://config.example.com/policy"), "toml");
+        
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("policy.toml");
+        std::fs::write(&file, "max_cache_age_days = 3\n").unwrap();
+        let digest = blake3::hash(b"max_cache_age_days = 3\n").to_hex().to_string();
+        let checked = |checksum: &str| verify(&file, None, &Verification { checksum: Some(checksum.to_string()), signers: None });
+        assert!(checked(&format!("blake3:{}", digest)).is_ok());
+        assert!(checked(&format!("blake3:{}", digest.to_uppercase())).is_ok());
+        assert!(checked(&format!("blake3:{}", "0".repeat(64))).unwrap_err().to_string().contains("mismatch"));
+        assert!(checked(&format!("sha256:{}", digest)).is_err());
+    }
+}
\ No newline at end of file
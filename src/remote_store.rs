@@ -0,0 +1,644 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use crate::cache_target::{CacheTarget, TargetContext, TargetFuture};
+use crate::categories;
+use crate::command_runner::{CommandRecord, CommandRunner};
+use crate::config::{ClearModelConfig, RemoteBucketConfig};
+use crate::errors::{ClearModelError, Result};
+use crate::plan::{CleanupPlan, PlannedDirectory, PlannedFile};
+use crate::policy::{CleanupPolicy, FileFacts};
+use crate::resource_manager::{CleanupResult, FailedFile};
+use crate::shared_cache;
+use crate::targets::REMOTE_GROUP;
+
+/// Objects removed per command, keeping each command line well under the argument size limit
+const DELETE_BATCH: usize = 100;
+
+/// Storage classes lifecycle rules move objects to; they bill a minimum storage duration,
+/// so they are left for the bucket's own rules to expire
+const ARCHIVE_CLASSES: [&str; 8] = [
+    "STANDARD_IA", "ONEZONE_IA", "GLACIER_IR", "GLACIER", "DEEP_ARCHIVE", "NEARLINE", "COLDLINE", "ARCHIVE",
+];
+
+/// Object store a bucket is in, and the CLI that reaches it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// Amazon S3 and S3-compatible stores such as MinIO, through `aws`
+    S3,
+    
+    /// Google Cloud Storage, through `gcloud`
+    Gcs,
+}
+
+impl Provider {
+    fn scheme(&self) -> &'static str {
+        match self {
+            Provider::S3 => "s3",
+            Provider::Gcs => "gs",
+        }
+    }
+    
+    fn program(&self) -> &'static str {
+        match self {
+            Provider::S3 => "aws",
+            Provider::Gcs => "gcloud",
+        }
+    }
+}
+
+/// A bucket and the prefix under it that is cleaned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub provider: Provider,
+    pub bucket: String,
+    pub prefix: String,
+}
+
+impl Location {
+    /// Parse `s3://bucket/prefix` or `gs://bucket/prefix`
+    pub fn parse(url: &str) -> Result<Self> {
+        let (provider, rest) = if let Some(rest) = url.strip_prefix("s3://") {
+            (Provider::S3, rest)
+        } else if let Some(rest) = url.strip_prefix("gs://") {
+            (Provider::Gcs, rest)
+        } else {
+            return Err(ClearModelError::configuration(
+                format!("Invalid remote_buckets url {:?}: expected s3://bucket/prefix or gs://bucket/prefix", url)
+            ));
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(ClearModelError::configuration(format!("Invalid remote_buckets url {:?}: no bucket", url)));
+        }
+        // `hub` would also match `hubble/...`; only keys inside the directory are cleaned
+        let mut prefix = prefix.to_string();
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        Ok(Self { provider, bucket: bucket.to_string(), prefix })
+    }
+    
+    /// Whether `key` is under the prefix
+    fn contains(&self, key: &str) -> bool {
+        key.starts_with(&self.prefix)
+    }
+    
+    /// URL of an object in the bucket
+    fn url(&self, key: &str) -> String {
+        format!("{}://{}/{}", self.provider.scheme(), self.bucket, key)
+    }
+    
+    /// Key of an object from its URL
+    fn key<'a>(&self, url: &'a str) -> Option<&'a str> {
+        url.strip_prefix(&self.url(""))
+    }
+}
+
+/// An object under the prefix, as listed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteObject {
+    pub key: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub storage_class: Option<String>,
+}
+
+impl RemoteObject {
+    fn archived(&self) -> bool {
+        self.storage_class.as_deref().is_some_and(|class| ARCHIVE_CLASSES.contains(&class))
+    }
+}
+
+/// `aws s3api list-objects-v2` output; the CLI follows the pages itself
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Listing {
+    #[serde(default)]
+    contents: Option<Vec<S3Object>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Object {
+    key: String,
+    size: u64,
+    last_modified: String,
+    #[serde(default)]
+    storage_class: Option<String>,
+}
+
+/// `aws s3api delete-objects` output; with `Quiet` only the failures are listed
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3Deleted {
+    #[serde(default)]
+    errors: Vec<S3DeleteError>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct S3DeleteError {
+    key: String,
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+/// An object in `gcloud storage objects list --format=json` output, which names fields
+/// differently across gcloud releases
+#[derive(Debug, Deserialize)]
+struct GcsObject {
+    name: String,
+    size: GcsSize,
+    #[serde(alias = "updated")]
+    update_time: String,
+    #[serde(default, alias = "storageClass")]
+    storage_class: Option<String>,
+}
+
+/// Object size, a number or (as the JSON API has it) a string of one
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum GcsSize {
+    Number(u64),
+    Text(String),
+}
+
+/// Parse a listing printed by the provider's CLI
+pub fn parse_listing(provider: Provider, output: &str) -> Result<Vec<RemoteObject>> {
+    let unexpected = |e: serde_json::Error| ClearModelError::environment(
+        format!("Unexpected object listing from {}: {}", provider.program(), e)
+    );
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    match provider {
+        Provider::S3 => {
+            let listing: S3Listing = serde_json::from_str(output).map_err(unexpected)?;
+            Ok(listing.contents.unwrap_or_default().into_iter().map(|object| RemoteObject {
+                modified: timestamp(&object.last_modified),
+                key: object.key,
+                size: object.size,
+                storage_class: object.storage_class,
+            }).collect())
+        }
+        Provider::Gcs => {
+            let listing: Vec<GcsObject> = serde_json::from_str(output).map_err(unexpected)?;
+            Ok(listing.into_iter().map(|object| RemoteObject {
+                modified: timestamp(&object.update_time),
+                size: match object.size {
+                    GcsSize::Number(size) => size,
+                    GcsSize::Text(size) => size.parse().unwrap_or(0),
+                },
+                key: object.name,
+                storage_class: object.storage_class,
+            }).collect())
+        }
+    }
+}
+
+/// An RFC 3339 timestamp as the cloud CLIs print them (`2024-05-01T12:34:56.000Z`,
+/// `2024-05-01T12:34:56+00:00`)
+fn timestamp(text: &str) -> Option<SystemTime> {
+    let (date, rest) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let (time, zone) = rest.split_at(rest.find(['Z', 'z', '+', '-'])?);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    
+    let offset = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let (hours, minutes) = zone[1..].split_once(':')?;
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            if zone.starts_with('-') { -offset } else { offset }
+        }
+    };
+    let nanos = match fraction {
+        "" => 0,
+        _ => format!("{:0<9}", &fraction[..fraction.len().min(9)]).parse().ok()?,
+    };
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(secs).ok().map(|secs| UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Days from 1970-01-01 to a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// A bucket prefix from `remote_buckets`, cleaned under the main policy and its own
+/// age limit and budget
+pub struct RemoteBucketTarget {
+    name: String,
+    location: Location,
+    endpoint_url: Option<String>,
+    profile: Option<String>,
+    policy: CleanupPolicy,
+    
+    /// Bytes the prefix may hold; the least recently written models go first beyond it
+    budget: Option<u64>,
+}
+
+/// The configured bucket prefixes; ones with an invalid URL are skipped (`validate` rejects them)
+pub fn targets(config: &ClearModelConfig) -> Vec<RemoteBucketTarget> {
+    config.remote_buckets.iter()
+        .filter_map(|bucket| match RemoteBucketTarget::new(config, bucket) {
+            Ok(target) => Some(target),
+            Err(e) => {
+                warn!("Skipping remote bucket {}: {}", bucket.name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+impl RemoteBucketTarget {
+    pub fn new(config: &ClearModelConfig, bucket: &RemoteBucketConfig) -> Result<Self> {
+        let mut policy = CleanupPolicy::from_config(config);
+        if let Some(days) = bucket.max_age_days.filter(|_| config.older_than.is_none()) {
+            policy.max_age = Duration::from_secs(days as u64 * 24 * 3600);
+            policy.extension_max_age.clear();
+        }
+        
+        Ok(Self {
+            name: bucket.name.clone(),
+            location: Location::parse(&bucket.url)?,
+            endpoint_url: bucket.endpoint_url.clone(),
+            profile: bucket.profile.clone(),
+            policy,
+            budget: bucket.budget_gb.map(crate::budget::bytes),
+        })
+    }
+    
+    fn root(&self) -> PathBuf {
+        PathBuf::from(self.location.url(&self.location.prefix))
+    }
+    
+    /// Arguments selecting the endpoint and credentials
+    fn connection_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        match self.location.provider {
+            Provider::S3 => {
+                if let Some(endpoint) = &self.endpoint_url {
+                    args.extend(["--endpoint-url".to_string(), endpoint.clone()]);
+                }
+                if let Some(profile) = &self.profile {
+                    args.extend(["--profile".to_string(), profile.clone()]);
+                }
+            }
+            Provider::Gcs => {
+                if let Some(profile) = &self.profile {
+                    args.push(format!("--configuration={}", profile));
+                }
+            }
+        }
+        args
+    }
+    
+    /// Run the provider's CLI, failing unless it succeeds
+    async fn run(&self, commands: &CommandRunner, mut args: Vec<String>) -> Result<CommandRecord> {
+        let program = self.location.provider.program();
+        args.extend(self.connection_args());
+        let record = commands.run(program, &args).await?;
+        if !record.success() {
+            return Err(ClearModelError::environment(format!(
+                "{} failed for {}: {}",
+                program,
+                self.root().display(),
+                record.stderr.trim()
+            )));
+        }
+        Ok(record)
+    }
+    
+    /// Every object under the prefix
+    async fn list(&self, commands: &CommandRunner) -> Result<Vec<RemoteObject>> {
+        let location = &self.location;
+        let args: Vec<String> = match location.provider {
+            Provider::S3 => {
+                let mut args = ["s3api", "list-objects-v2", "--bucket", location.bucket.as_str(), "--output", "json"].map(String::from).to_vec();
+                if !location.prefix.is_empty() {
+                    args.extend(["--prefix".to_string(), location.prefix.clone()]);
+                }
+                args
+            }
+            Provider::Gcs => vec![
+                "storage".to_string(),
+                "objects".to_string(),
+                "list".to_string(),
+                format!("{}**", location.url(&location.prefix)),
+                "--format=json".to_string(),
+            ],
+        };
+        let record = self.run(commands, args).await?;
+        let mut objects = parse_listing(location.provider, &record.stdout)?;
+        objects.retain(|object| location.contains(&object.key));
+        debug!("{}: {} objects under {}", self.name, objects.len(), self.root().display());
+        Ok(objects)
+    }
+    
+    /// What the policy and the budget would remove from `objects`
+    ///
+    /// Objects in archive storage classes are kept; they still count against the budget.
+    /// Over budget, whole models go least recently written first, as local budgets evict
+    /// them; a model with a pinned object is never chosen.
+    pub fn plan(&self, objects: &[RemoteObject], python_cache_extensions: &[String], now: SystemTime) -> PlannedDirectory {
+        let mut planned = PlannedDirectory { root: self.root(), ..PlannedDirectory::default() };
+        let mut kept = Vec::new();
+        let mut pinned_units = Vec::new();
+        let prefix = Path::new(&self.location.prefix);
+        
+        for object in objects {
+            let key = Path::new(&object.key);
+            categories::record(&mut planned.categories, key, object.size, false);
+            let age = object.modified.and_then(|modified| now.duration_since(modified).ok());
+            let facts = FileFacts::from_path(key, &self.policy, age, object.size);
+            let decision = self.policy.decide(&facts, python_cache_extensions);
+            let unit = shared_cache::model_dir(prefix, key).or_else(|| shared_cache::model_dir(Path::new(""), key));
+            if facts.pinned {
+                pinned_units.push(unit);
+            } else if decision.removes() && !object.archived() {
+                planned.files.push(self.planned(object, decision.label()));
+            } else if !object.archived() {
+                kept.push((unit, object));
+            }
+        }
+        
+        let Some(budget) = self.budget else {
+            return planned;
+        };
+        let total: u64 = objects.iter().map(|object| object.size).sum();
+        let mut used = total - planned.bytes();
+        if used <= budget {
+            return planned;
+        }
+        
+        let mut units: HashMap<Option<PathBuf>, (Option<SystemTime>, Vec<&RemoteObject>)> = HashMap::new();
+        for (unit, object) in kept.into_iter().filter(|(unit, _)| !pinned_units.contains(unit)) {
+            let (newest, members) = units.entry(unit).or_default();
+            *newest = (*newest).max(object.modified);
+            members.push(object);
+        }
+        let mut units: Vec<_> = units.into_values().collect();
+        units.sort_by_key(|(newest, _)| *newest);
+        for (_, members) in units {
+            if used <= budget {
+                break;
+            }
+            for object in members {
+                used -= object.size;
+                planned.files.push(self.planned(object, "budget"));
+            }
+        }
+        if used > budget {
+            warn!("{}: still over its budget after evicting everything unpinned", self.name);
+        }
+        planned
+    }
+    
+    fn planned(&self, object: &RemoteObject, rule: &'static str) -> PlannedFile {
+        PlannedFile {
+            path: PathBuf::from(self.location.url(&object.key)),
+            size: object.size,
+            modified: object.modified,
+            rule,
+        }
+    }
+    
+    /// Delete the planned objects that are still as they were planned
+    ///
+    /// The prefix is listed again first: objects rewritten since (a fresh upload under the
+    /// same key) or already gone are left alone. Objects are deleted without a version,
+    /// so in versioned buckets this only adds delete markers and the bucket's lifecycle
+    /// rules expire the old versions.
+    async fn apply(&self, cx: &TargetContext<'_>, planned: PlannedDirectory) -> Result<CleanupResult> {
+        let started = Instant::now();
+        let mut result = CleanupResult {
+            path: planned.root.clone(),
+            files_removed: 0,
+            bytes_freed: 0,
+            errors: planned.errors,
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: planned.categories,
+            needs_privileges: Vec::new(),
+            failed: Vec::new(),
+            duration: Duration::ZERO,
+        };
+        
+        let current: HashMap<String, RemoteObject> = self.list(cx.commands).await?
+            .into_iter()
+            .map(|object| (object.key.clone(), object))
+            .collect();
+        let doomed: Vec<(&str, &PlannedFile)> = planned.files.iter()
+            .filter_map(|file| {
+                let key = self.location.key(file.path.to_str()?)?;
+                let unchanged = current.get(key)
+                    .is_some_and(|object| object.size == file.size && object.modified == file.modified);
+                if !unchanged {
+                    debug!("Keeping {}: changed or gone since it was planned", file.path.display());
+                }
+                unchanged.then_some((key, file))
+            })
+            .collect();
+        
+        for batch in doomed.chunks(DELETE_BATCH) {
+            if cx.resource_manager.is_cancelled() {
+                break;
+            }
+            let failed = match self.delete(cx.commands, batch).await {
+                Ok(failed) => failed,
+                Err(e) => {
+                    warn!("{}", e);
+                    result.errors.push(e.to_string());
+                    batch.iter().map(|(key, _)| (key.to_string(), e.to_string())).collect()
+                }
+            };
+            for (key, file) in batch {
+                if let Some(error) = failed.get(*key) {
+                    result.failed.push(FailedFile { path: file.path.clone(), error: error.clone() });
+                    continue;
+                }
+                cx.resource_manager.audit_removal(&file.path, file.size, file.rule)?;
+                categories::record_deleted(&mut result.categories, Path::new(key), file.size);
+                result.files_removed += 1;
+                result.bytes_freed += file.size;
+            }
+        }
+        
+        info!(
+            "{}: deleted {} objects ({:.2} GB) from {}",
+            self.name,
+            result.files_removed,
+            result.bytes_freed as f64 / 1_073_741_824.0,
+            planned.root.display()
+        );
+        result.duration = started.elapsed();
+        Ok(result)
+    }
+    
+    /// Delete one batch of objects, returning the ones that could not be deleted and why
+    async fn delete(&self, commands: &CommandRunner, batch: &[(&str, &PlannedFile)]) -> Result<HashMap<String, String>> {
+        match self.location.provider {
+            Provider::S3 => {
+                let objects: Vec<serde_json::Value> = batch.iter()
+                    .map(|(key, _)| serde_json::json!({ "Key": key }))
+                    .collect();
+                let request = serde_json::json!({ "Objects": objects, "Quiet": true });
+                let args = ["s3api", "delete-objects", "--bucket", self.location.bucket.as_str(), "--output", "json", "--delete"]
+                    .map(String::from)
+                    .into_iter()
+                    .chain([request.to_string()])
+                    .collect();
+                let record = self.run(commands, args).await?;
+                let deleted: S3Deleted = match record.stdout.trim() {
+                    "" => S3Deleted::default(),
+                    output => serde_json::from_str(output).map_err(|e| ClearModelError::environment(
+                        format!("Unexpected delete response from aws: {}", e)
+                    ))?,
+                };
+                Ok(deleted.errors.into_iter()
+                    .map(|error| (error.key, format!("{}: {}", error.code, error.message)))
+                    .collect())
+            }
+            Provider::Gcs => {
+                let args = ["storage".to_string(), "rm".to_string()].into_iter()
+                    .chain(batch.iter().map(|(_, file)| file.path.display().to_string()))
+                    .collect();
+                self.run(commands, args).await?;
+                Ok(HashMap::new())
+            }
+        }
+    }
+}
+
+impl CacheTarget for RemoteBucketTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn group(&self) -> &str {
+        REMOTE_GROUP
+    }
+    
+    /// Remote buckets are shared and deletes there cannot be undone, so they are only
+    /// cleaned when named with `--only`
+    fn on_by_default(&self) -> bool {
+        false
+    }
+    
+    fn detect(&self) -> bool {
+        true
+    }
+    
+    fn scan<'a>(&'a self, cx: &'a TargetContext<'a>) -> TargetFuture<'a, CleanupPlan> {
+        Box::pin(async move {
+            let objects = self.list(cx.commands).await?;
+            let planned = self.plan(&objects, &cx.config.python_cache_extensions, SystemTime::now());
+            Ok(CleanupPlan { directories: vec![planned] })
+        })
+    }
+    
+    fn clean<'a>(&'a self, cx: &'a TargetContext<'a>, plan: Option<CleanupPlan>) -> TargetFuture<'a, Vec<CleanupResult>> {
+        Box::pin(async move {
+            let started = Instant::now();
+            let plan = match plan {
+                Some(plan) => plan,
+                None => self.scan(cx).await?,
+            };
+            let planned = plan.directories.into_iter().next()
+                .unwrap_or_else(|| PlannedDirectory { root: self.root(), ..PlannedDirectory::default() });
+            
+            if cx.dry_run {
+                for file in &planned.files {
+                    info!("Would delete {} ({:.2} MB, {})", file.path.display(), file.size as f64 / 1_048_576.0, file.rule);
+                }
+                return Ok(vec![CleanupResult::preview(planned, started.elapsed())]);
+            }
+            Ok(vec![self.apply(cx, planned).await?])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const S3_LISTING: &str = r#"{
+        "Contents": [
+            {"Key": "hub/models--org--old/blobs/a", "LastModified": "2024-01-01T00:00:00+00:00", "Size": 400, "StorageClass": "STANDARD"},
+            {"Key": "hub/models--org--old/blobs/b", "LastModified": "2024-01-01T00:00:00.000Z", "Size": 100, "StorageClass": "DEEP_ARCHIVE"},
+            {"Key": "hub/models--org--idle/blobs/c", "LastModified": "2024-04-01T00:00:00Z", "Size": 300, "StorageClass": "STANDARD"},
+            {"Key": "hub/models--org--recent/blobs/d", "LastModified": "2024-04-20T00:00:00Z", "Size": 300, "StorageClass": "STANDARD"}
+        ]
+    }"#;
+    
+    #[test]
+    fn test_listed_objects_are_planned_by_age_then_budget() {
+        let objects = parse_listing(Provider::S3, S3_LISTING).unwrap();
+        assert_eq!(objects[0].modified, Some(UNIX_EPOCH + Duration::from_secs(1_704_067_200)));
+        assert_eq!(objects[0].modified, objects[1].modified);
+        
+        let gcs = r#"[{"name": "hub/x", "size": "42", "updated": "2024-01-01T01:00:00.5+01:00", "storageClass": "COLDLINE"}]"#;
+        let gcs = parse_listing(Provider::Gcs, gcs).unwrap();
+        assert_eq!((gcs[0].size, gcs[0].archived()), (42, true));
+        assert_eq!(gcs[0].modified, Some(UNIX_EPOCH + Duration::new(1_704_067_200, 500_000_000)));
+        
+        let config = ClearModelConfig { max_cache_age_days: 60, ..ClearModelConfig::default() };
+        let bucket = RemoteBucketConfig {
+            name: "team".to_string(),
+            url: "s3://models/hub".to_string(),
+            endpoint_url: Some("http://minio:9000".to_string()),
+            profile: None,
+            max_age_days: None,
+            budget_gb: Some(500.0 / 1_073_741_824.0),
+        };
+        let target = RemoteBucketTarget::new(&config, &bucket).unwrap();
+        assert_eq!(target.connection_args(), ["--endpoint-url", "http://minio:9000"]);
+        
+        // The archived blob stays; the idle model goes to get the rest under 500 bytes
+        let now = UNIX_EPOCH + Duration::from_secs(1_714_521_600); // 2024-05-01
+        let planned = target.plan(&objects, &[], now);
+        let chosen: Vec<(&str, &str)> = planned.files.iter()
+            .map(|file| (file.path.to_str().unwrap(), file.rule))
+            .collect();
+        assert_eq!(chosen, [
+            ("s3://models/hub/models--org--old/blobs/a", "expired"),
+            ("s3://models/hub/models--org--idle/blobs/c", "budget"),
+        ]);
+        assert_eq!(target.location.key(chosen[1].0), Some("hub/models--org--idle/blobs/c"));
+    }
+    
+    #[test]
+    fn test_prefix_leaves_sibling_prefixes_alone() {
+        let location = Location::parse("s3://models/hub").unwrap();
+        assert_eq!(location.prefix, "hub/");
+        assert_eq!(Location::parse("gs://models/hub/").unwrap().prefix, "hub/");
+        assert_eq!(Location::parse("s3://models").unwrap().prefix, "");
+        
+        let listing = r#"{"Contents": [
+            {"Key": "hub/models--org--a/blobs/a", "LastModified": "2024-01-01T00:00:00Z", "Size": 1},
+            {"Key": "hubble/models--org--b/blobs/b", "LastModified": "2024-01-01T00:00:00Z", "Size": 1},
+            {"Key": "hub-backup/models--org--c/blobs/c", "LastModified": "2024-01-01T00:00:00Z", "Size": 1}
+        ]}"#;
+        let kept: Vec<String> = parse_listing(Provider::S3, listing).unwrap().into_iter()
+            .filter(|object| location.contains(&object.key))
+            .map(|object| object.key)
+            .collect();
+        assert_eq!(kept, ["hub/models--org--a/blobs/a"]);
+    }
+}
\ No newline at end of file
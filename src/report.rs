@@ -0,0 +1,366 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::categories::{self, CategoryBreakdown};
+use crate::command_runner::CommandRecord;
+use crate::disk_space::SpaceChange;
+use crate::errors::{ClearModelError, Result, EXIT_PARTIAL};
+use crate::events;
+use crate::resource_manager::{CleanupResult, FailedFile};
+use crate::stats::{OperationStats, StatsSnapshot};
+
+/// Bumped whenever a field changes meaning or is removed
+const REPORT_VERSION: u32 = 1;
+
+/// Where `--result-file` writes the run report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultTarget {
+    /// Written to a temporary file and renamed into place
+    Path(PathBuf),
+    /// An inherited file descriptor (`fd:3`), written as-is
+    Fd(i32),
+}
+
+impl FromStr for ResultTarget {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.strip_prefix("fd:") {
+            Some(fd) => fd.parse()
+                .map(ResultTarget::Fd)
+                .map_err(|_| format!("invalid file descriptor {:?}", fd)),
+            None if value.is_empty() => Err("result file path is empty".to_string()),
+            None => Ok(ResultTarget::Path(PathBuf::from(value))),
+        }
+    }
+}
+
+/// Outcome for one cleaned directory
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectoryReport {
+    pub path: PathBuf,
+    pub files_removed: u64,
+    pub bytes_freed: u64,
+    pub dirs_pruned: u64,
+    pub symlinks_pruned: u64,
+    pub errors: Vec<String>,
+    pub duration_ms: u64,
+    pub categories: CategoryBreakdown,
+    
+    /// Selected files left in place because removing them needs sudo
+    pub needs_privileges: Vec<PathBuf>,
+    
+    /// Files that could not be removed, even after retrying those that were busy
+    pub failed: Vec<FailedFile>,
+}
+
+impl From<&CleanupResult> for DirectoryReport {
+    fn from(result: &CleanupResult) -> Self {
+        Self {
+            path: result.path.clone(),
+            files_removed: result.files_removed,
+            bytes_freed: result.bytes_freed,
+            dirs_pruned: result.dirs_pruned,
+            symlinks_pruned: result.symlinks_pruned,
+            errors: result.errors.clone(),
+            duration_ms: events::millis(result.duration),
+            categories: result.categories.clone(),
+            needs_privileges: result.needs_privileges.clone(),
+            failed: result.failed.clone(),
+        }
+    }
+}
+
+/// Totals across every directory
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportTotals {
+    pub files_removed: u64,
+    pub bytes_freed: u64,
+    pub dirs_pruned: u64,
+    pub symlinks_pruned: u64,
+    pub errors: u64,
+    pub needs_privileges: u64,
+    pub files_failed: u64,
+    
+    /// Scanned and deleted bytes by kind of file (safetensors, bin, pyc, ...)
+    pub categories: CategoryBreakdown,
+}
+
+/// Structured result of a run, written once at exit for wrapper scripts
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub version: u32,
+    pub command: String,
+    pub dry_run: bool,
+    pub succeeded: bool,
+    
+    /// Why the run failed, when it did
+    pub error: Option<String>,
+    
+    /// Stable code of the failure class (`CM-CFG-001`, ...) and what to do about it
+    pub error_code: Option<String>,
+    pub error_hint: Option<String>,
+    
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub totals: ReportTotals,
+    pub results: Vec<DirectoryReport>,
+    
+    /// Files processed and cleaned, bytes and errors for each target that ran
+    pub targets: BTreeMap<String, OperationStats>,
+    
+    /// Free space on each filesystem holding a cache, before and after the run
+    pub filesystems: Vec<SpaceChange>,
+    
+    /// External commands run (or refused) and what they printed
+    pub commands: Vec<CommandRecord>,
+    
+    #[serde(skip)]
+    started: Instant,
+}
+
+impl RunReport {
+    pub fn start(command: &str, dry_run: bool) -> Self {
+        Self {
+            version: REPORT_VERSION,
+            command: command.to_string(),
+            dry_run,
+            succeeded: false,
+            error: None,
+            error_code: None,
+            error_hint: None,
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            duration_ms: 0,
+            totals: ReportTotals::default(),
+            results: Vec::new(),
+            targets: BTreeMap::new(),
+            filesystems: Vec::new(),
+            commands: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+    
+    pub fn add_results(&mut self, results: &[CleanupResult]) {
+        for result in results {
+            self.totals.files_removed += result.files_removed;
+            self.totals.bytes_freed += result.bytes_freed;
+            self.totals.dirs_pruned += result.dirs_pruned;
+            self.totals.symlinks_pruned += result.symlinks_pruned;
+            self.totals.errors += result.errors.len() as u64;
+            self.totals.needs_privileges += result.needs_privileges.len() as u64;
+            self.totals.files_failed += result.failed.len() as u64;
+            categories::merge(&mut self.totals.categories, &result.categories);
+            self.results.push(DirectoryReport::from(result));
+        }
+    }
+    
+    /// Record the per-target rollups of the run's statistics
+    pub fn add_stats(&mut self, stats: &StatsSnapshot) {
+        for (name, target) in &stats.targets {
+            self.targets.insert(name.clone(), target.clone());
+        }
+    }
+    
+    /// Record the free space the run's filesystems had before and after it
+    pub fn add_disk_space(&mut self, changes: Vec<SpaceChange>) {
+        self.filesystems.extend(changes);
+    }
+    
+    /// Record the external commands the run ran
+    pub fn add_commands(&mut self, commands: Vec<CommandRecord>) {
+        self.commands.extend(commands);
+    }
+    
+    /// Record how the run ended
+    pub fn finish(&mut self, error: Option<&ClearModelError>) {
+        self.succeeded = error.is_none();
+        self.error = error.map(ToString::to_string);
+        self.error_code = error.map(|e| e.code().to_string());
+        self.error_hint = error.and_then(|e| e.hint()).map(str::to_string);
+        self.duration_ms = events::millis(self.started.elapsed());
+    }
+    
+    /// Directories and files the run could not clean, with the reasons
+    pub fn failures(&self) -> Vec<(&Path, &str)> {
+        self.results.iter()
+            .flat_map(|result| {
+                let directory = result.errors.iter().map(|error| (result.path.as_path(), error.as_str()));
+                let files = result.failed.iter().map(|file| (file.path.as_path(), file.error.as_str()));
+                directory.chain(files)
+            })
+            .collect()
+    }
+    
+    /// Lines listing the first `listed` failures, empty when nothing failed
+    pub fn failure_summary(&self, listed: usize) -> Vec<String> {
+        let failures = self.failures();
+        if failures.is_empty() {
+            return Vec::new();
+        }
+        
+        let mut lines = vec![format!("{} paths failed:", failures.len())];
+        lines.extend(failures.iter().take(listed).map(|(path, reason)| format!("  {}: {}", path.display(), reason)));
+        if failures.len() > listed {
+            lines.push(format!("  ... and {} more (see --result-file for all of them)", failures.len() - listed));
+        }
+        lines
+    }
+    
+    /// Exit code of a run that itself succeeded: partial when `fail_on_error` is set and
+    /// anything failed, otherwise none (zero)
+    pub fn exit_code(&self, fail_on_error: bool) -> Option<i32> {
+        (fail_on_error && self.totals.errors + self.totals.files_failed > 0).then_some(EXIT_PARTIAL)
+    }
+    
+    /// Write the report as one JSON document; readers never see a partial file
+    pub fn write(&self, target: &ResultTarget) -> Result<()> {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        
+        match target {
+            ResultTarget::Path(path) => write_atomically(path, &json),
+            ResultTarget::Fd(fd) => write_fd(*fd, &json),
+        }
+    }
+}
+
+fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let file_name = path.file_name().ok_or_else(|| ClearModelError::configuration(
+        format!("Result file {:?} has no file name", path)
+    ))?;
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+    
+    let mut file = std::fs::File::create(&temp)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to create result file: {}", e),
+            Some(temp.clone())
+        ))?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to write result file: {}", e),
+            Some(path.to_path_buf())
+        ))
+}
+
+#[cfg(unix)]
+fn write_fd(fd: i32, content: &[u8]) -> Result<()> {
+    use std::os::unix::io::FromRawFd;
+    
+    if fd <= 2 {
+        return Err(ClearModelError::configuration(format!(
+            "Refusing to write the result to standard stream fd {}",
+            fd
+        )));
+    }
+    
+    // SAFETY: the caller handed this descriptor to us for the result; it is only
+    // written once, at exit, and closed when the file is dropped
+    let mut file = unsafe { std::fs::File::from_raw_fd(fd) };
+    file.write_all(content)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_fd(_fd: i32, _content: &[u8]) -> Result<()> {
+    Err(ClearModelError::configuration(
+        "Writing the result to a file descriptor is only supported on Unix".to_string()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::categories::{CategoryStats, FileCategory};
+    use std::time::Duration;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_report_is_written_atomically_on_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("result.json");
+        let target: ResultTarget = path.to_str().unwrap().parse().unwrap();
+        assert_eq!("fd:3".parse::<ResultTarget>().unwrap(), ResultTarget::Fd(3));
+        
+        let mut report = RunReport::start("clean", false);
+        report.add_results(&[CleanupResult {
+            path: PathBuf::from("/cache"),
+            files_removed: 2,
+            bytes_freed: 2048,
+            errors: vec!["Permission denied".to_string()],
+            dirs_pruned: 1,
+            symlinks_pruned: 0,
+            categories: [(FileCategory::Pyc, CategoryStats {
+                scanned_files: 3,
+                scanned_bytes: 3072,
+                deleted_files: 2,
+                deleted_bytes: 2048,
+            })].into(),
+            needs_privileges: vec![PathBuf::from("/cache/root-owned.pyc")],
+            failed: vec![FailedFile { path: PathBuf::from("/cache/busy.bin"), error: "Resource busy".to_string() }],
+            duration: Duration::from_millis(3),
+        }]);
+        report.finish(Some(&ClearModelError::cache("disk vanished".to_string())));
+        report.write(&target).unwrap();
+        
+        let written: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written["succeeded"], false);
+        assert_eq!(written["error"], "Cache operation error: disk vanished");
+        assert_eq!(written["totals"]["bytes_freed"], 2048);
+        assert_eq!(written["totals"]["errors"], 1);
+        assert_eq!(written["totals"]["needs_privileges"], 1);
+        assert_eq!(written["totals"]["files_failed"], 1);
+        assert_eq!(written["results"][0]["failed"][0]["path"], "/cache/busy.bin");
+        assert_eq!(written["totals"]["categories"]["pyc"]["scanned_bytes"], 3072);
+        assert_eq!(written["results"][0]["path"], "/cache");
+        assert!(!temp_dir.path().join("result.json.tmp").exists());
+    }
+    
+    #[test]
+    fn test_failed_removals_are_listed_and_fail_the_run_only_when_asked() {
+        let result = |path: &str, failed: &[&str]| CleanupResult {
+            path: PathBuf::from(path),
+            files_removed: 1,
+            bytes_freed: 1024,
+            errors: Vec::new(),
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: CategoryBreakdown::new(),
+            needs_privileges: Vec::new(),
+            failed: failed.iter()
+                .map(|file| FailedFile { path: PathBuf::from(file), error: "Resource busy".to_string() })
+                .collect(),
+            duration: Duration::from_millis(1),
+        };
+        
+        let mut clean = RunReport::start("clean", false);
+        clean.add_results(&[result("/cache/hf", &[])]);
+        clean.finish(None);
+        assert_eq!((clean.exit_code(true), clean.exit_code(false)), (None, None));
+        assert!(clean.failure_summary(20).is_empty());
+        
+        let mut partial = RunReport::start("clean", false);
+        partial.add_results(&[result("/cache/hf", &["/cache/hf/a.bin", "/cache/hf/b.bin"]), result("/cache/torch", &["/cache/torch/c.pt"])]);
+        partial.finish(None);
+        assert!(partial.succeeded);
+        assert_eq!(partial.exit_code(true), Some(EXIT_PARTIAL));
+        assert_eq!(partial.exit_code(false), None);
+        assert_eq!(partial.failure_summary(2), [
+            "3 paths failed:",
+            "  /cache/hf/a.bin: Resource busy",
+            "  /cache/hf/b.bin: Resource busy",
+            "  ... and 1 more (see --result-file for all of them)",
+        ]);
+    }
+}
@@ -1,44 +1,250 @@
-use dashmap::DashMap;
 use rayon::prelude::*;
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use sysinfo::System;
-use tokio::sync::Semaphore;
+use tokio::sync::{mpsc, Semaphore};
+use tokio_util::sync::CancellationToken;
 
 use tracing::{debug, info, warn, error};
 
+use crate::batching::BatchSizer;
+use crate::capabilities::Capabilities;
+use crate::categories::{self, CategoryBreakdown};
 use crate::config::ClearModelConfig;
+use crate::disk_space::{self, FilesystemSpace};
 use crate::errors::{ClearModelError, Result};
+use crate::events::{self, Event, EventSink};
+use crate::exclude::{self, Excludes};
+use crate::export::RemovalLog;
+use crate::audit::AuditLog;
+use crate::journal::{Journal, JournalEntry};
+use crate::netfs;
+use crate::open_files::OpenFiles;
+use crate::checkpoint::CheckpointWriter;
+use crate::rate_limit::RateLimiter;
+use crate::retry::{self, RetryPolicy};
+use crate::stats::{StatsCollector, StatsEvent, StatsSnapshot};
+use crate::plan::{CleanupPlan, PlannedDirectory, PlannedFile};
+use crate::policy::{CleanupPolicy, ConflictMode, Decision, FileFacts};
+use crate::prompt::ConflictPrompter;
+use crate::quarantine::{self, Quarantine};
+use crate::shared_cache::{SharedCache, SHARED_DIR};
+use crate::progress::ProgressReporter;
+use crate::sandbox::DeletionSandbox;
+use crate::anchored::AnchoredRoot;
 use crate::security::SecurityManager;
+use crate::shred::{self, DeletionMode};
+use crate::torch_caches;
+use crate::uring::BatchUnlinker;
+use crate::walk::{ParallelWalker, WalkOptions};
+use crate::workers::DeleteWorkers;
+
+/// Files the directory walker may run ahead of deletion, bounding memory on huge caches
+const WALK_QUEUE_CAPACITY: usize = 4_096;
+
+/// Aborts the tasks of a pass when it is dropped unfinished, as when the watchdog abandons a target
+#[derive(Default)]
+struct AbortOnDrop(Vec<tokio::task::AbortHandle>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
 
 /// Resource manager for handling cache operations with proper resource management
 pub struct ResourceManager {
     config: Arc<ClearModelConfig>,
     semaphore: Arc<Semaphore>,
     system_info: Arc<tokio::sync::Mutex<System>>,
-    operation_stats: Arc<DashMap<String, OperationStats>>,
+    run: RunContext,
+    capabilities: Capabilities,
 }
 
-/// Statistics for tracking operations
-#[derive(Debug, Clone)]
-pub struct OperationStats {
-    pub files_processed: u64,
-    pub bytes_cleaned: u64,
-    pub errors_encountered: u64,
-    pub start_time: SystemTime,
-    pub last_update: SystemTime,
+/// Per-run observers handed down to every cleanup task
+#[derive(Clone)]
+struct RunContext {
+    progress: ProgressReporter,
+    journal: Option<Arc<Journal>>,
+    audit: Option<Arc<AuditLog>>,
+    quarantine: Option<Arc<Quarantine>>,
+    prompter: Option<Arc<ConflictPrompter>>,
+    events: Option<EventSink>,
+    
+    /// Every file removed, for `clean --output csv`
+    removals: Option<Arc<RemovalLog>>,
+    
+    /// `security.allowed_roots`, checked again for every file removed
+    allowed_roots: Arc<[PathBuf]>,
+    
+    /// Landlock-confined workers that removals go through when `security.sandbox` is set
+    sandbox: Option<Arc<DeletionSandbox>>,
+    
+    /// `deletion_mode = "shred"`: overwrite files before unlinking them
+    shred: bool,
+    
+    /// `io_rate_limit`: paces removals and the bytes hashed or shredded
+    rate_limit: Option<Arc<RateLimiter>>,
+    
+    /// Stops new removals once cancelled; removals already under way finish
+    cancel: CancellationToken,
+    
+    /// Progress through a saved plan, for `clean --resume`
+    checkpoint: Option<Arc<CheckpointWriter>>,
+    
+    /// Per-directory, per-target and run-wide counts of what was cleaned
+    stats: StatsCollector,
+    
+    /// Lists cache directories, shared by every root walked at once
+    walker: Arc<ParallelWalker>,
+    
+    /// Inspect and remove the files of each batch, on the current target's pool
+    deleters: Arc<DeleteWorkers>,
+    
+    /// Tries removals that fail on a busy or locked file again
+    retry: RetryPolicy,
+    
+    /// Model directory locks and ownership rules for caches shared with other users
+    shared: Option<Arc<SharedCache>>,
+    
+    /// Files running processes hold open, which are kept; `None` when /proc is unavailable
+    open_files: Option<Arc<OpenFiles>>,
+    
+    /// Read `user.clearmodel.keep` markers, unless the environment denies extended attributes
+    keep_markers: bool,
+    
+    /// Batch unlinks through io_uring, unless a seccomp filter is likely to deny it
+    batch_unlinks: bool,
 }
 
-impl Default for OperationStats {
-    fn default() -> Self {
-        let now = SystemTime::now();
-        Self {
-            files_processed: 0,
-            bytes_cleaned: 0,
-            errors_encountered: 0,
-            start_time: now,
-            last_update: now,
+impl RunContext {
+    fn emit(&self, event: Event) {
+        if let Some(events) = &self.events {
+            events.emit(&event);
+        }
+    }
+    
+    /// Remover for the files of one cache root
+    ///
+    /// Removals go through a handle on the root unless symlinks are followed, since files
+    /// reached through a followed link are not physically below it.
+    fn remover(&self, root: &Path, follow_links: bool) -> Result<Remover> {
+        if self.shred {
+            if let Some(filesystem) = shred::copy_on_write_filesystem(root) {
+                warn!("{:?} is on {}, a copy-on-write filesystem: shredded files' old blocks are not overwritten", root, filesystem);
+            }
+        }
+        
+        let anchor = if follow_links {
+            None
+        } else {
+            let anchor = AnchoredRoot::open(root)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to open cache directory: {}", e),
+                    Some(root.to_path_buf())
+                ))?;
+            Some(Arc::new(anchor))
+        };
+        
+        // Only plain deletions through a handle on the root are batched
+        let batch = anchor.as_ref()
+            .filter(|_| self.batch_unlinks && self.sandbox.is_none() && self.quarantine.is_none() && !self.shred)
+            .and_then(|_| BatchUnlinker::detect());
+        
+        Ok(Remover { anchor, sandbox: self.sandbox.clone(), batch, retry: self.retry })
+    }
+}
+
+/// Removes files below one cache root, through its anchored handle and the sandbox when there are ones
+#[derive(Default)]
+struct Remover {
+    anchor: Option<Arc<AnchoredRoot>>,
+    sandbox: Option<Arc<DeletionSandbox>>,
+    
+    /// io_uring backend that checks and unlinks a whole batch of files at once
+    batch: Option<BatchUnlinker>,
+    retry: RetryPolicy,
+}
+
+impl Remover {
+    /// Remove a file if it is still the one `expected` was read from
+    fn remove_file(&self, path: &Path, expected: &std::fs::Metadata) -> std::io::Result<()> {
+        let (path, expected) = (path.to_path_buf(), expected.clone());
+        self.run(move |anchor| match anchor {
+            Some(anchor) => anchor.remove_file(&path, &expected),
+            None => std::fs::remove_file(&path),
+        })
+    }
+    
+    /// Whether plain deletions are checked and unlinked a batch at a time
+    fn batches(&self) -> bool {
+        self.batch.is_some()
+    }
+    
+    /// Remove files that are still the ones their metadata was read from, a batch at a time when possible
+    fn unlink_all(&self, files: &[(&Path, &std::fs::Metadata)]) -> Vec<std::io::Result<()>> {
+        match (&self.batch, &self.anchor) {
+            // Files that were busy are tried again one at a time
+            (Some(batch), Some(anchor)) => batch.unlink(anchor, files).into_iter()
+                .zip(files)
+                .map(|(unlinked, (path, expected))| match unlinked {
+                    Err(e) if retry::is_transient(&e) => self.remove_file(path, expected),
+                    unlinked => unlinked,
+                })
+                .collect(),
+            _ => files.iter().map(|(path, expected)| self.remove_file(path, expected)).collect(),
+        }
+    }
+    
+    /// Overwrite a file with random data, then remove it, if it is still the one `expected` was read from
+    fn shred_file(&self, path: &Path, expected: &std::fs::Metadata) -> std::io::Result<()> {
+        let (path, expected) = (path.to_path_buf(), expected.clone());
+        self.run(move |anchor| {
+            let file = match anchor {
+                Some(anchor) => anchor.open_for_overwrite(&path, &expected)?,
+                None => std::fs::OpenOptions::new().write(true).open(&path)?,
+            };
+            shred::overwrite(&file, expected.len())?;
+            drop(file);
+            match anchor {
+                Some(anchor) => anchor.remove_file(&path, &expected),
+                None => std::fs::remove_file(&path),
+            }
+        })
+    }
+    
+    /// Remove an empty directory
+    fn remove_dir(&self, path: &Path) -> std::io::Result<()> {
+        let path = path.to_path_buf();
+        self.run(move |anchor| match anchor {
+            Some(anchor) => anchor.remove_dir(&path),
+            None => std::fs::remove_dir(&path),
+        })
+    }
+    
+    /// Rename a file out of the cache if it is still the one `expected` was read from
+    fn rename_out(&self, path: &Path, expected: &std::fs::Metadata, destination: &Path) -> std::io::Result<()> {
+        let (path, expected, destination) = (path.to_path_buf(), expected.clone(), destination.to_path_buf());
+        self.run(move |anchor| match anchor {
+            Some(anchor) => anchor.rename_out(&path, &expected, &destination),
+            None => std::fs::rename(&path, &destination),
+        })
+    }
+    
+    /// Run a file operation, in the sandbox when there is one, retrying it while the file is busy
+    fn run<F>(&self, operation: F) -> std::io::Result<()>
+    where
+        F: Fn(Option<&AnchoredRoot>) -> std::io::Result<()> + Send + 'static,
+    {
+        let (anchor, retry) = (self.anchor.clone(), self.retry);
+        match &self.sandbox {
+            Some(sandbox) => sandbox.run(move || retry.run(|| operation(anchor.as_deref())))
+                .unwrap_or_else(|e| Err(std::io::Error::other(e.to_string()))),
+            None => retry.run(|| operation(anchor.as_deref())),
         }
     }
 }
@@ -50,30 +256,262 @@ pub struct CleanupResult {
     pub files_removed: u64,
     pub bytes_freed: u64,
     pub errors: Vec<String>,
+    
+    /// Directories removed because cleaning left them empty
+    pub dirs_pruned: u64,
+    
+    /// Symlinks removed because their target no longer exists
+    pub symlinks_pruned: u64,
+    
+    /// Bytes scanned and removed, by kind of file
+    pub categories: CategoryBreakdown,
+    
+    /// Files the policy selected but this user cannot remove without sudo, left in place
+    pub needs_privileges: Vec<PathBuf>,
+    
+    /// Files that could not be inspected or removed, retries included; the run went on without them
+    pub failed: Vec<FailedFile>,
+    
     pub duration: Duration,
 }
 
+/// A file a cleanup gave up on, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// A file the policy selected, with the metadata it was selected on
+struct Removal<'a> {
+    path: &'a Path,
+    metadata: &'a std::fs::Metadata,
+    rule: &'static str,
+}
+
+/// What is recorded about a file once it is removed, read before removing it
+struct PreparedRemoval {
+    entry: Option<JournalEntry>,
+    audit_hash: Option<String>,
+}
+
+/// A file about to be removed with administrator rights, outside this process
+pub struct PrivilegedRemoval {
+    pub path: PathBuf,
+    metadata: std::fs::Metadata,
+    prepared: PreparedRemoval,
+}
+
+impl PrivilegedRemoval {
+    pub fn size(&self) -> u64 {
+        self.metadata.len()
+    }
+}
+
+/// What the policy and the ownership preflight made of a scanned file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Keep,
+    
+    /// Removed under the named policy rule
+    Remove(&'static str),
+    
+    /// Selected, but another user's or in a directory this user cannot write to
+    NeedsPrivileges,
+}
+
+impl CleanupResult {
+    /// A directory that could not be cleaned at all, and why
+    pub fn aborted(path: PathBuf, error: String) -> Self {
+        Self {
+            path,
+            files_removed: 0,
+            bytes_freed: 0,
+            errors: vec![error],
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: CategoryBreakdown::new(),
+            needs_privileges: Vec::new(),
+            failed: Vec::new(),
+            duration: Duration::from_secs(0),
+        }
+    }
+    
+    /// What applying a planned directory would free, for dry runs
+    pub fn preview(planned: PlannedDirectory, duration: Duration) -> Self {
+        let mut categories = planned.categories;
+        for file in &planned.files {
+            categories::record_deleted(&mut categories, &file.path, file.size);
+        }
+        
+        Self {
+            files_removed: planned.files.len() as u64,
+            bytes_freed: planned.files.iter().map(|file| file.size).sum(),
+            path: planned.root,
+            errors: planned.errors,
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories,
+            needs_privileges: planned.needs_privileges,
+            failed: Vec::new(),
+            duration,
+        }
+    }
+}
+
+/// What the pruning post-pass removed (or would remove in a dry run)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PruneCounts {
+    dirs: u64,
+    symlinks: u64,
+}
+
 impl ResourceManager {
     /// Create a new resource manager
     pub async fn new(config: ClearModelConfig) -> Result<Self> {
         let max_concurrent = config.max_parallel_operations;
+        let scan_workers = config.concurrency.scan_workers.unwrap_or(max_concurrent);
+        let deleters = Arc::new(DeleteWorkers::new(&config.concurrency)?);
+        let allowed_roots: Arc<[PathBuf]> = config.security.allowed_roots.clone().into();
+        let shred = config.deletion_mode == DeletionMode::Shred;
+        let rate_limit = RateLimiter::from_config(&config.io_rate_limit).map(Arc::new);
+        let retry = RetryPolicy::from_config(&config.retry);
         
         Ok(Self {
             config: Arc::new(config),
             semaphore: Arc::new(Semaphore::new(max_concurrent)),
-            system_info: Arc::new(tokio::sync::Mutex::new(System::new_all())),
-            operation_stats: Arc::new(DashMap::new()),
+            system_info: Arc::new(tokio::sync::Mutex::new(System::new())),
+            run: RunContext {
+                progress: ProgressReporter::hidden(),
+                journal: None,
+                audit: None,
+                quarantine: None,
+                shared: None,
+                prompter: None,
+                events: None,
+                removals: None,
+                allowed_roots,
+                sandbox: None,
+                shred,
+                rate_limit,
+                cancel: CancellationToken::new(),
+                checkpoint: None,
+                stats: StatsCollector::spawn(),
+                walker: Arc::new(ParallelWalker::new(scan_workers)?),
+                deleters,
+                retry,
+                open_files: None,
+                keep_markers: Capabilities::full().xattrs,
+                batch_unlinks: true,
+            },
+            capabilities: Capabilities::full(),
         })
     }
     
+    /// Attach a progress reporter for scan and deletion progress bars
+    pub fn with_progress(mut self, progress: ProgressReporter) -> Self {
+        self.run.progress = progress;
+        self
+    }
+    
+    /// Record every removed file to a deletion journal
+    pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+        self.run.journal = Some(journal);
+        self
+    }
+    
+    /// Append every removed file to the tamper-evident audit log
+    pub fn with_audit(mut self, audit: Arc<AuditLog>) -> Self {
+        self.run.audit = Some(audit);
+        self
+    }
+    
+    /// Record a removal made outside the file system, such as of a remote object, in the audit log
+    pub fn audit_removal(&self, path: &Path, size: u64, rule: &str) -> Result<()> {
+        match &self.run.audit {
+            Some(audit) => audit.record(path, size, None, rule, "deleted"),
+            None => Ok(()),
+        }
+    }
+    
+    /// Whether the run was cancelled, so work still planned was left undone
+    pub fn is_cancelled(&self) -> bool {
+        self.run.cancel.is_cancelled()
+    }
+    
+    /// Save progress through planned removals so an interrupted run can be resumed
+    pub fn with_checkpoint(mut self, checkpoint: Arc<CheckpointWriter>) -> Self {
+        self.run.checkpoint = Some(checkpoint);
+        self
+    }
+    
+    /// Coordinate with other users' instances on shared caches
+    pub fn with_shared(mut self, shared: Arc<SharedCache>) -> Self {
+        self.run.shared = Some(shared);
+        self
+    }
+    
+    /// Move removed files into quarantine instead of deleting them
+    pub fn with_quarantine(mut self, quarantine: Arc<Quarantine>) -> Self {
+        self.run.quarantine = Some(quarantine);
+        self
+    }
+    
+    /// Stream lifecycle events (scans, deletions, errors) to an integration
+    pub fn with_events(mut self, events: EventSink) -> Self {
+        self.run.events = Some(events);
+        self
+    }
+    
+    /// Record every file removed (or that would be, in a dry run) in `removals`
+    pub fn with_removal_log(mut self, removals: Arc<RemovalLog>) -> Self {
+        self.run.removals = Some(removals);
+        self
+    }
+    
+    /// Stop starting new removals (and directories) once `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.run.cancel = cancel;
+        self
+    }
+    
+    /// Restrict operations to the capabilities available in this environment
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.run.keep_markers = capabilities.xattrs;
+        self.run.batch_unlinks = !capabilities.seccomp_filtered;
+        self.capabilities = capabilities;
+        self
+    }
+    
     /// Clean all configured cache directories
     pub async fn clean_all_caches(&self, dry_run: bool) -> Result<Vec<CleanupResult>> {
+        let cache_paths = self.configured_cache_paths();
+        self.clean_cache_paths(&cache_paths, dry_run).await
+    }
+    
+    /// Clean some of the configured cache directories under the configured policy
+    pub async fn clean_cache_paths(&self, cache_paths: &[PathBuf], dry_run: bool) -> Result<Vec<CleanupResult>> {
         info!("Starting cache cleanup (dry_run: {})", dry_run);
         
         // Check system resources before starting
-        self.check_system_resources().await?;
+        self.check_system_resources(cache_paths).await?;
+        
+        if cache_paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let policy = CleanupPolicy::from_config(&self.config);
+        self.clean_paths(cache_paths, &policy, dry_run).await
+    }
+    
+    /// Existing configured cache directories, without the separately cleaned HuggingFace instances
+    fn configured_cache_paths(&self) -> Vec<PathBuf> {
+        let cache_paths: Vec<PathBuf> = self.config.existing_cache_paths()
+            .into_iter()
+            .filter(|path| !self.config.is_hf_cache_instance(path))
+            .cloned()
+            .collect();
         
-        let cache_paths = self.config.existing_cache_paths();
         if cache_paths.is_empty() {
             info!("No existing cache directories found to clean");
             info!("Configured cache paths:");
@@ -81,44 +519,166 @@ impl ResourceManager {
                 info!("  - {:?} (does not exist)", path);
             }
             info!("This is normal if you haven't used ML frameworks yet that create these cache directories");
-            return Ok(Vec::new());
+        } else {
+            info!("Found {} cache directories to clean", cache_paths.len());
+        }
+        cache_paths
+    }
+    
+    /// Walk the configured cache directories once and list what a cleanup would remove
+    pub async fn plan(&self) -> Result<CleanupPlan> {
+        let policy = CleanupPolicy::from_config(&self.config);
+        self.plan_paths(&self.configured_cache_paths(), &policy).await
+    }
+    
+    /// List what cleaning the given directories under a policy would remove, without removing it
+    ///
+    /// Rule conflicts are settled (and prompted for) here, so `apply` asks nothing.
+    pub async fn plan_paths(&self, cache_paths: &[PathBuf], policy: &CleanupPolicy) -> Result<CleanupPlan> {
+        self.run.progress.start_scan();
+        let run = self.run_for(policy, false);
+        let policy = Arc::new(policy.clone());
+        let mut tasks = Vec::new();
+        let mut guard = AbortOnDrop::default();
+        
+        for cache_path in cache_paths.iter().filter(|path| netfs::admitted(path, &self.config)) {
+            let path = cache_path.clone();
+            let config = Arc::clone(&self.config);
+            let policy = Arc::clone(&policy);
+            let semaphore = Arc::clone(&self.semaphore);
+            let run = run.clone();
+            
+            let task = tokio::spawn(async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                Self::plan_directory(&path, &config, &policy, &run).await
+            });
+            guard.0.push(task.abort_handle());
+            tasks.push((cache_path, task));
+        }
+        
+        let mut plan = CleanupPlan::default();
+        for (path, task) in tasks {
+            match task.await {
+                Ok(Ok(directory)) => plan.directories.push(directory),
+                Ok(Err(e)) => {
+                    error!("Planning cleanup of {:?} failed: {}", path, e);
+                    run.emit(Event::error(Some(path), &e));
+                }
+                Err(e) => error!("Task join error: {}", e),
+            }
         }
+        self.run.progress.finish();
         
-        info!("Found {} cache directories to clean", cache_paths.len());
+        info!(
+            "Planned cleanup: {} files, {:.2} MB",
+            plan.file_count(),
+            plan.total_bytes() as f64 / 1_048_576.0
+        );
+        Ok(plan)
+    }
+    
+    /// Check a file that needs administrator rights against the allowed roots and read what
+    /// the journal and audit log record about it, before it is removed
+    pub fn prepare_privileged(&self, path: &Path, metadata: std::fs::Metadata) -> Result<PrivilegedRemoval> {
+        let prepared = Self::prepare_removal(path, metadata.len(), &self.run)?;
+        Ok(PrivilegedRemoval { path: path.to_path_buf(), metadata, prepared })
+    }
+    
+    /// Audit and journal a file removed with administrator rights
+    pub fn finish_privileged(&self, removal: PrivilegedRemoval) -> Result<()> {
+        Self::finish_removal(&removal.path, &removal.metadata, "privileged", "deleted", removal.prepared, &self.run)
+    }
+    
+    /// Remove the files of a plan, skipping any that changed since it was made
+    pub async fn apply(&self, plan: CleanupPlan) -> Result<Vec<CleanupResult>> {
+        let roots: Vec<PathBuf> = plan.directories.iter().map(|directory| directory.root.clone()).collect();
+        let mut run = self.run.clone();
+        run.open_files = OpenFiles::scan(&self.capabilities).map(Arc::new);
+        let run = self.sandboxed(run, &roots, false)?;
+        let mut tasks = Vec::new();
+        let mut guard = AbortOnDrop::default();
+        
+        for directory in plan.directories {
+            let config = Arc::clone(&self.config);
+            let semaphore = Arc::clone(&self.semaphore);
+            let run = run.clone();
+            
+            let task = tokio::spawn(async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                Self::apply_directory(directory, &config, &run).await
+            });
+            guard.0.push(task.abort_handle());
+            tasks.push(task);
+        }
+        
+        let mut results = Vec::new();
+        for task in tasks {
+            match task.await {
+                Ok(result) => results.push(result),
+                Err(e) => error!("Task join error: {}", e),
+            }
+        }
+        self.run.progress.finish();
         
+        info!(
+            "Cache cleanup completed: {} files, {:.2} MB freed",
+            results.iter().map(|r| r.files_removed).sum::<u64>(),
+            results.iter().map(|r| r.bytes_freed).sum::<u64>() as f64 / 1_048_576.0
+        );
+        Ok(results)
+    }
+    
+    /// Clean the given cache directories concurrently under a cleanup policy
+    pub async fn clean_paths(
+        &self,
+        cache_paths: &[PathBuf],
+        policy: &CleanupPolicy,
+        dry_run: bool,
+    ) -> Result<Vec<CleanupResult>> {
         // Process cache directories concurrently
+        let run = self.sandboxed(self.run_for(policy, dry_run), cache_paths, dry_run)?;
+        self.run.progress.start_scan();
+        let policy = Arc::new(policy.clone());
         let mut tasks = Vec::new();
+        let mut guard = AbortOnDrop::default();
         
-        for path in cache_paths {
-            let path = path.clone();
+        for cache_path in cache_paths.iter().filter(|path| netfs::admitted(path, &self.config)) {
+            let path = cache_path.clone();
             let config = Arc::clone(&self.config);
+            let policy = Arc::clone(&policy);
             let semaphore = Arc::clone(&self.semaphore);
-            let stats = Arc::clone(&self.operation_stats);
+            let run = run.clone();
             
             let task = tokio::spawn(async move {
                 let _permit = semaphore.acquire().await.unwrap();
-                Self::clean_cache_directory(&path, &config, &stats, dry_run).await
+                Self::clean_cache_directory(&path, &config, &policy, &run, dry_run).await
             });
             
-            tasks.push(task);
+            guard.0.push(task.abort_handle());
+            tasks.push((cache_path, task));
         }
         
         // Wait for all tasks to complete
         let mut results = Vec::new();
-        for task in tasks {
+        for (path, task) in tasks {
             match task.await {
                 Ok(Ok(result)) => results.push(result),
+                Ok(Err(ClearModelError::Cancelled)) => debug!("Not cleaning {:?}: the run was cancelled", path),
+                // Recorded with the results, so the summary and the result file list it
                 Ok(Err(e)) => {
                     error!("Cache cleaning task failed: {}", e);
-                    // Continue with other tasks
+                    run.emit(Event::error(Some(path), &e));
+                    results.push(CleanupResult::aborted(path.clone(), e.to_string()));
                 }
                 Err(e) => {
                     error!("Task join error: {}", e);
-                    // Continue with other tasks
+                    results.push(CleanupResult::aborted(path.clone(), format!("Cleaning task failed: {}", e)));
                 }
             }
         }
         
+        self.run.progress.finish();
+        
         // Log summary
         let total_files: u64 = results.iter().map(|r| r.files_removed).sum();
         let total_bytes: u64 = results.iter().map(|r| r.bytes_freed).sum();
@@ -132,18 +692,50 @@ impl ResourceManager {
         Ok(results)
     }
     
+    /// Observers for one cleanup pass, with a conflict prompt when the policy asks for one
+    fn run_for(&self, policy: &CleanupPolicy, dry_run: bool) -> RunContext {
+        let mut run = self.run.clone();
+        run.open_files = OpenFiles::scan(&self.capabilities).map(Arc::new);
+        if policy.on_conflict == ConflictMode::Prompt && !dry_run {
+            run.prompter = Some(Arc::new(ConflictPrompter::new(run.progress.clone())));
+        }
+        run
+    }
+    
+    /// Confine a pass's removals to `roots` (and this run's quarantine) when `security.sandbox` is set
+    fn sandboxed(&self, mut run: RunContext, roots: &[PathBuf], dry_run: bool) -> Result<RunContext> {
+        if !self.config.security.sandbox || dry_run {
+            return Ok(run);
+        }
+        
+        let mut roots = roots.to_vec();
+        if let Some(quarantine) = &run.quarantine {
+            // Landlock rules need the directory to exist already
+            std::fs::create_dir_all(quarantine.run_dir())
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create quarantine directory: {}", e),
+                    Some(quarantine.run_dir().to_path_buf())
+                ))?;
+            roots.push(quarantine.run_dir().to_path_buf());
+        }
+        
+        run.sandbox = Some(Arc::new(DeletionSandbox::spawn(&roots, self.config.max_parallel_operations)?));
+        Ok(run)
+    }
+    
     /// Clean a specific cache directory
     async fn clean_cache_directory(
         path: &Path,
         config: &ClearModelConfig,
-        stats: &DashMap<String, OperationStats>,
+        policy: &CleanupPolicy,
+        run: &RunContext,
         dry_run: bool,
     ) -> Result<CleanupResult> {
+        if run.cancel.is_cancelled() {
+            return Err(ClearModelError::Cancelled);
+        }
         let start_time = SystemTime::now();
-        let path_key = path.to_string_lossy().to_string();
-        
-        // Initialize stats for this operation
-        stats.insert(path_key.clone(), OperationStats::default());
+        run.stats.send(StatsEvent::DirectoryStarted { root: path.to_path_buf() });
         
         info!("Cleaning cache directory: {:?}", path);
         
@@ -153,28 +745,47 @@ impl ResourceManager {
         }
         
         // Check if path is safe for deletion
-        SecurityManager::validate_deletion_safety(path)?;
+        SecurityManager::validate_deletion_safety(path, &config.security)?;
         
+        run.emit(Event::ScanStarted { path: path.to_path_buf() });
         let mut result = CleanupResult {
             path: path.to_path_buf(),
             files_removed: 0,
             bytes_freed: 0,
             errors: Vec::new(),
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: CategoryBreakdown::new(),
+            needs_privileges: Vec::new(),
+            failed: Vec::new(),
             duration: Duration::from_secs(0),
         };
         
         // Process directory contents
-        match Self::process_directory_contents(path, config, stats, &path_key, dry_run).await {
-            Ok((files, bytes)) => {
+        match Self::process_directory_contents(path, config, policy, run, dry_run).await {
+            Ok((files, bytes, categories, pruned, needs_privileges, failed)) => {
                 result.files_removed = files;
                 result.bytes_freed = bytes;
+                result.categories = categories;
+                result.needs_privileges = needs_privileges;
+                result.failed = failed;
+                result.dirs_pruned = pruned.dirs;
+                result.symlinks_pruned = pruned.symlinks;
             }
             Err(e) => {
+                run.emit(Event::error(Some(path), &e));
                 result.errors.push(format!("Failed to process directory: {}", e));
             }
         }
         
         result.duration = start_time.elapsed().unwrap_or(Duration::from_secs(0));
+        run.emit(Event::DirCompleted {
+            path: path.to_path_buf(),
+            files_removed: result.files_removed,
+            bytes_freed: result.bytes_freed,
+            errors: (result.errors.len() + result.failed.len()) as u64,
+            duration_ms: events::millis(result.duration),
+        });
         
         info!(
             "Completed cleaning {:?}: {} files, {:.2} MB, took {:?}",
@@ -183,176 +794,703 @@ impl ResourceManager {
             result.bytes_freed as f64 / 1_048_576.0,
             result.duration
         );
+        if result.dirs_pruned + result.symlinks_pruned > 0 {
+            info!(
+                "Pruned {} empty directories and {} dangling symlinks under {:?}",
+                result.dirs_pruned,
+                result.symlinks_pruned,
+                path
+            );
+        }
         
         Ok(result)
     }
     
     /// Process directory contents recursively
+    ///
+    /// A blocking walker streams file paths through a bounded queue while batches are
+    /// processed, so memory stays flat however many files the cache holds. When the
+    /// queue is full the walker waits for the workers to catch up.
     async fn process_directory_contents(
         path: &Path,
         config: &ClearModelConfig,
-        stats: &DashMap<String, OperationStats>,
-        stats_key: &str,
+        policy: &CleanupPolicy,
+        run: &RunContext,
         dry_run: bool,
-    ) -> Result<(u64, u64)> {
+    ) -> Result<(u64, u64, CategoryBreakdown, PruneCounts, Vec<PathBuf>, Vec<FailedFile>)> {
         let mut total_files = 0u64;
         let mut total_bytes = 0u64;
+        let mut categories = CategoryBreakdown::new();
+        let mut needs_privileges = Vec::new();
+        let mut failed = Vec::new();
+        let remover = run.remover(path, config.follow_symlinks)?;
         
-        // Use walkdir for safe directory traversal
-        let walker = walkdir::WalkDir::new(path)
-            .max_depth(config.security.max_path_depth)
-            .follow_links(config.follow_symlinks)
-            .into_iter()
-            .filter_entry(|e| {
-                // Skip directories that should be ignored
-                if let Some(name) = e.file_name().to_str() {
-                    !config.skip_directories.contains(&name.to_string())
-                } else {
-                    true
-                }
-            });
+        let (walker, mut receiver) = Self::spawn_walker(path, config, run);
+        
+        // Files are counted into the bars as they arrive
+        let dir_progress = run.progress.directory_bar(path, 0);
         
-        // Collect entries to process
-        let mut entries_to_process = Vec::new();
+        // Process files in parallel batches, sized from how the previous batch went
+        let mut sizer = BatchSizer::new();
+        let mut batch = Vec::with_capacity(sizer.size());
+        let serial = netfs::throttled(path, config);
         
-        for entry in walker {
-            match entry {
-                Ok(entry) => {
-                    if entry.file_type().is_file() {
-                        entries_to_process.push(entry.path().to_path_buf());
+        while receiver.recv_many(&mut batch, sizer.size()).await > 0 {
+            // Hanging up stops the walker too
+            if run.cancel.is_cancelled() {
+                break;
+            }
+            dir_progress.add_found(batch.len() as u64);
+            let batch_start = Instant::now();
+            let mut batch_bytes = 0u64;
+            
+            let batch_results = run.deleters.install(serial, || Self::process_batch(&batch, config, policy, &remover, run, dry_run));
+            
+            // Aggregate results
+            for (file_path, result) in batch.iter().zip(batch_results) {
+                match result {
+                    Ok((verdict, size)) => {
+                        categories::record(&mut categories, file_path, size, matches!(verdict, Verdict::Remove(_)));
+                        match verdict {
+                            Verdict::Remove(_) => {
+                                total_files += 1;
+                                total_bytes += size;
+                                batch_bytes += size;
+                                run.stats.send(StatsEvent::FileCleaned { root: path.to_path_buf(), path: file_path.clone(), bytes: size });
+                            }
+                            Verdict::NeedsPrivileges => needs_privileges.push(file_path.clone()),
+                            Verdict::Keep => {}
+                        }
+                    }
+                    Err(e) => {
+                        debug!("Error processing file: {}", e);
+                        run.emit(Event::error(Some(file_path), &e));
+                        run.stats.send(StatsEvent::FileFailed { root: path.to_path_buf(), path: file_path.clone() });
+                        failed.push(FailedFile { path: file_path.clone(), error: e.to_string() });
                     }
-                }
-                Err(e) => {
-                    warn!("Error walking directory: {}", e);
-                    continue;
                 }
             }
+            
+            run.stats.send(StatsEvent::BatchProcessed { root: path.to_path_buf(), files: batch.len() });
+            
+            sizer.observe(batch.len(), batch_bytes, batch_start.elapsed());
+            
+            dir_progress.inc(batch.len() as u64);
+            batch.clear();
+            
+            // Yield control to allow other tasks to run
+            tokio::task::yield_now().await;
         }
         
-        // Process files in parallel batches
-        let batch_size = 100;
-        let batches: Vec<_> = entries_to_process.chunks(batch_size).collect();
+        drop(receiver);
+        Self::join_walker(walker, path).await?;
+        
+        let pruned = if config.prune_empty_dirs && !run.cancel.is_cancelled() {
+            Self::prune_directory(path, config, &remover, dry_run)
+        } else {
+            PruneCounts::default()
+        };
         
-        for batch in batches {
-            let batch_results: Vec<_> = batch
+        Ok((total_files, total_bytes, categories, pruned, needs_privileges, failed))
+    }
+    
+    /// Walk `path` on a blocking thread, streaming file paths through a bounded queue
+    fn spawn_walker(
+        path: &Path,
+        config: &ClearModelConfig,
+        run: &RunContext,
+    ) -> (tokio::task::JoinHandle<()>, mpsc::Receiver<PathBuf>) {
+        let (sender, receiver) = mpsc::channel(WALK_QUEUE_CAPACITY);
+        let walker = tokio::task::spawn_blocking({
+            let root = path.to_path_buf();
+            let options = WalkOptions {
+                max_depth: config.security.max_path_depth,
+                follow_links: config.follow_symlinks,
+                root_device: root_device(path, config),
+            };
+            // A throttled network filesystem is listed one directory at a time as well
+            let serial = netfs::throttled(path, config);
+            let skip_directories = config.skip_directories.clone();
+            let excludes = Excludes::for_walk(path, config);
+            let run = run.clone();
+            move || Self::walk_files(&run, &root, options, serial, &skip_directories, &excludes, sender)
+        });
+        (walker, receiver)
+    }
+    
+    async fn join_walker(walker: tokio::task::JoinHandle<()>, path: &Path) -> Result<()> {
+        walker.await.map_err(|e| ClearModelError::file_operation(
+            format!("Directory walker failed: {}", e),
+            Some(path.to_path_buf())
+        ))
+    }
+    
+    /// Find the files under one cache root that the policy would remove
+    async fn plan_directory(
+        path: &Path,
+        config: &ClearModelConfig,
+        policy: &CleanupPolicy,
+        run: &RunContext,
+    ) -> Result<PlannedDirectory> {
+        if config.security.validate_cache_paths {
+            SecurityManager::validate_cache_path(path)?;
+        }
+        SecurityManager::validate_deletion_safety(path, &config.security)?;
+        
+        run.emit(Event::ScanStarted { path: path.to_path_buf() });
+        let mut planned = PlannedDirectory {
+            root: path.to_path_buf(),
+            files: Vec::new(),
+            categories: CategoryBreakdown::new(),
+            errors: Vec::new(),
+            needs_privileges: Vec::new(),
+        };
+        
+        let (walker, mut receiver) = Self::spawn_walker(path, config, run);
+        let mut batch = Vec::with_capacity(WALK_QUEUE_CAPACITY);
+        let serial = netfs::throttled(path, config);
+        
+        while receiver.recv_many(&mut batch, WALK_QUEUE_CAPACITY).await > 0 {
+            let inspected: Vec<_> = run.deleters.install(serial, || batch
                 .par_iter()
-                .map(|file_path| {
-                    Self::process_single_file(file_path, config, dry_run)
-                })
-                .collect();
+                .map(|file_path| Self::inspect_file(file_path, config, policy, run))
+                .collect());
             
-            // Aggregate results
-            for result in batch_results {
+            for (file_path, result) in batch.drain(..).zip(inspected) {
                 match result {
-                    Ok((files, bytes)) => {
-                        total_files += files;
-                        total_bytes += bytes;
+                    Ok((metadata, verdict)) => {
+                        categories::record(&mut planned.categories, &file_path, metadata.len(), false);
+                        match verdict {
+                            Verdict::Remove(rule) => planned.files.push(PlannedFile {
+                                path: file_path,
+                                size: metadata.len(),
+                                modified: metadata.modified().ok(),
+                                rule,
+                            }),
+                            Verdict::NeedsPrivileges => planned.needs_privileges.push(file_path),
+                            Verdict::Keep => {}
+                        }
                     }
                     Err(e) => {
-                        debug!("Error processing file: {}", e);
-                        // Update error count in stats
-                        if let Some(mut stat) = stats.get_mut(stats_key) {
-                            stat.errors_encountered += 1;
-                        }
+                        debug!("Error inspecting file: {}", e);
+                        run.emit(Event::error(Some(&file_path), &e));
+                        planned.errors.push(e.to_string());
+                    }
+                }
+            }
+            
+            tokio::task::yield_now().await;
+        }
+        
+        Self::join_walker(walker, path).await?;
+        Ok(planned)
+    }
+    
+    /// Remove the planned files of one cache root
+    async fn apply_directory(
+        planned: PlannedDirectory,
+        config: &ClearModelConfig,
+        run: &RunContext,
+    ) -> CleanupResult {
+        let start_time = Instant::now();
+        run.stats.send(StatsEvent::DirectoryStarted { root: planned.root.clone() });
+        
+        let mut result = CleanupResult {
+            path: planned.root.clone(),
+            files_removed: 0,
+            bytes_freed: 0,
+            errors: planned.errors,
+            dirs_pruned: 0,
+            symlinks_pruned: 0,
+            categories: planned.categories,
+            needs_privileges: planned.needs_privileges,
+            failed: Vec::new(),
+            duration: Duration::from_secs(0),
+        };
+        
+        let remover = match run.remover(&planned.root, config.follow_symlinks) {
+            Ok(remover) => remover,
+            Err(e) => {
+                result.errors.push(e.to_string());
+                return result;
+            }
+        };
+        
+        run.progress.add_to_total(planned.files.len() as u64);
+        let dir_progress = run.progress.directory_bar(&planned.root, planned.files.len() as u64);
+        let mut sizer = BatchSizer::new();
+        let mut remaining = planned.files.as_slice();
+        let serial = netfs::throttled(&planned.root, config);
+        
+        while !remaining.is_empty() && !run.cancel.is_cancelled() {
+            let (batch, rest) = remaining.split_at(sizer.size().min(remaining.len()));
+            remaining = rest;
+            let batch_start = Instant::now();
+            let mut batch_bytes = 0u64;
+            
+            let removed = run.deleters.install(serial, || Self::remove_planned(batch, &remover, run));
+            for (file, removed) in batch.iter().zip(removed) {
+                match removed {
+                    Ok(true) => {
+                        categories::record_deleted(&mut result.categories, &file.path, file.size);
+                        result.files_removed += 1;
+                        result.bytes_freed += file.size;
+                        batch_bytes += file.size;
+                        run.stats.send(StatsEvent::FileCleaned { root: planned.root.clone(), path: file.path.clone(), bytes: file.size });
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        debug!("Error removing file: {}", e);
+                        run.emit(Event::error(Some(&file.path), &e));
+                        run.stats.send(StatsEvent::FileFailed { root: planned.root.clone(), path: file.path.clone() });
+                        result.failed.push(FailedFile { path: file.path.clone(), error: e.to_string() });
                     }
                 }
             }
             
-            // Update stats
-            if let Some(mut stat) = stats.get_mut(stats_key) {
-                stat.files_processed += batch.len() as u64;
-                stat.bytes_cleaned += total_bytes;
-                stat.last_update = SystemTime::now();
+            run.stats.send(StatsEvent::BatchProcessed { root: planned.root.clone(), files: batch.len() });
+            sizer.observe(batch.len(), batch_bytes, batch_start.elapsed());
+            dir_progress.inc(batch.len() as u64);
+            // Files of a cancelled batch may not have been tried, so they stay in the checkpoint
+            if let Some(checkpoint) = run.checkpoint.as_ref().filter(|_| !run.cancel.is_cancelled()) {
+                checkpoint.advance(&planned.root, batch);
             }
             
-            // Yield control to allow other tasks to run
             tokio::task::yield_now().await;
         }
         
-        Ok((total_files, total_bytes))
+        if config.prune_empty_dirs && !run.cancel.is_cancelled() {
+            let pruned = Self::prune_directory(&planned.root, config, &remover, false);
+            result.dirs_pruned = pruned.dirs;
+            result.symlinks_pruned = pruned.symlinks;
+        }
+        
+        result.duration = start_time.elapsed();
+        run.emit(Event::DirCompleted {
+            path: result.path.clone(),
+            files_removed: result.files_removed,
+            bytes_freed: result.bytes_freed,
+            errors: (result.errors.len() + result.failed.len()) as u64,
+            duration_ms: events::millis(result.duration),
+        });
+        result
+    }
+    
+    /// Remove the planned files of a batch, skipping those that are gone or were rewritten after planning
+    fn remove_planned(batch: &[PlannedFile], remover: &Remover, run: &RunContext) -> Vec<Result<bool>> {
+        let current: Vec<_> = batch.par_iter().map(|file| Self::still_planned(file, run)).collect();
+        let removals: Vec<_> = batch.iter()
+            .zip(&current)
+            .filter_map(|(file, metadata)| Some(Removal { path: &file.path, metadata: metadata.as_ref()?, rule: file.rule }))
+            .collect();
+        
+        let mut removed = Self::remove_files(&removals, remover, run, false).into_iter();
+        current.iter()
+            .map(|metadata| match metadata {
+                Some(_) => removed.next().unwrap_or(Ok(())).map(|()| true),
+                None => Ok(false),
+            })
+            .collect()
+    }
+    
+    /// Metadata of a planned file that is still there and unchanged since planning
+    fn still_planned(file: &PlannedFile, run: &RunContext) -> Option<std::fs::Metadata> {
+        if run.cancel.is_cancelled() {
+            return None;
+        }
+        let Ok(metadata) = std::fs::metadata(&file.path) else {
+            debug!("Planned file is already gone: {:?}", file.path);
+            return None;
+        };
+        if !file.unchanged(&metadata) {
+            info!("Keeping {:?}: it changed after the cleanup was planned", file.path);
+            return None;
+        }
+        if Self::held(&file.path, &metadata, run) {
+            return None;
+        }
+        Some(metadata)
+    }
+    
+    /// Send every file under `root` to the cleanup workers, stopping early if they hang up
+    ///
+    /// Subdirectories are listed concurrently on the walker pool, so the workers are fed
+    /// while the rest of the tree is still being read.
+    fn walk_files(
+        run: &RunContext,
+        root: &Path,
+        options: WalkOptions,
+        serial: bool,
+        skip_directories: &[String],
+        excludes: &Excludes,
+        sender: mpsc::Sender<PathBuf>,
+    ) {
+        // Never walk back into files that are already quarantined, or into a shared cache's locks
+        let quarantine_root = quarantine::quarantine_dir();
+        let shared_dir = root.join(SHARED_DIR);
+        
+        let admit = |path: &Path, is_dir: bool| {
+            if quarantine_root.as_deref() == Some(path) || path == shared_dir {
+                return false;
+            }
+            
+            // Excluded directories are never entered, excluded files never sent
+            if excludes.excludes(path, is_dir) {
+                return false;
+            }
+            
+            // Skip directories that should be ignored
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                !skip_directories.iter().any(|skip| skip == name)
+            } else {
+                true
+            }
+        };
+        
+        run.walker.walk(root, options, serial, admit, |path| {
+            run.progress.inc_scanned(1);
+            sender.blocking_send(path).is_ok()
+        });
+    }
+    
+    /// Remove dangling symlinks and directories left empty under a cache root
+    ///
+    /// Children are visited before their parents so whole emptied subtrees collapse.
+    /// Symlinks are never followed and the root itself is kept. In a dry run only what
+    /// is already empty or dangling is counted.
+    fn prune_directory(root: &Path, config: &ClearModelConfig, remover: &Remover, dry_run: bool) -> PruneCounts {
+        let mut pruned = PruneCounts::default();
+        let quarantine_root = quarantine::quarantine_dir();
+        let shared_dir = root.join(SHARED_DIR);
+        let excludes = Excludes::for_walk(root, config);
+        
+        let walker = cache_walker(root, config)
+            .min_depth(1)
+            .follow_links(false)
+            .contents_first(true)
+            .into_iter()
+            .filter_entry(|e| {
+                quarantine_root.as_deref() != Some(e.path())
+                    && e.path() != shared_dir
+                    && !excludes.excludes(e.path(), e.file_type().is_dir())
+                    && e.file_name().to_str().map(|name| !config.skip_directories.iter().any(|skip| skip == name)).unwrap_or(true)
+            });
+        
+        for entry in walker.flatten() {
+            let path = entry.path();
+            if entry.path_is_symlink() {
+                // metadata() follows the link, so it only fails when the target is gone
+                if std::fs::metadata(path).is_ok() {
+                    continue;
+                }
+                if dry_run {
+                    debug!("Would remove dangling symlink: {:?}", path);
+                    pruned.symlinks += 1;
+                } else if let Err(e) = entry.metadata().map_err(std::io::Error::from).and_then(|link| remover.remove_file(path, &link)) {
+                    debug!("Failed to remove dangling symlink {:?}: {}", path, e);
+                } else {
+                    debug!("Removed dangling symlink: {:?}", path);
+                    pruned.symlinks += 1;
+                }
+            } else if entry.file_type().is_dir() {
+                if dry_run {
+                    let empty = std::fs::read_dir(path).map(|mut entries| entries.next().is_none()).unwrap_or(false);
+                    if empty {
+                        debug!("Would remove empty directory: {:?}", path);
+                        pruned.dirs += 1;
+                    }
+                } else if remover.remove_dir(path).is_ok() {
+                    // remove_dir refuses non-empty directories, so nothing else can be lost here
+                    debug!("Removed empty directory: {:?}", path);
+                    pruned.dirs += 1;
+                }
+            }
+        }
+        
+        pruned
+    }
+    
+    /// Inspect a batch of files in parallel and remove the ones the policy selects,
+    /// returning what was decided for each file and its size
+    fn process_batch(
+        batch: &[PathBuf],
+        config: &ClearModelConfig,
+        policy: &CleanupPolicy,
+        remover: &Remover,
+        run: &RunContext,
+        dry_run: bool,
+    ) -> Vec<Result<(Verdict, u64)>> {
+        let inspected: Vec<_> = batch.par_iter()
+            .map(|file_path| Self::process_single_file(file_path, config, policy, run))
+            .collect();
+        let removals: Vec<_> = batch.iter()
+            .zip(&inspected)
+            .filter_map(|(path, inspected)| match inspected {
+                Ok((metadata, Verdict::Remove(rule))) => Some(Removal { path, metadata, rule }),
+                _ => None,
+            })
+            .collect();
+        let mut removed = Self::remove_files(&removals, remover, run, dry_run).into_iter();
+        
+        inspected.into_iter()
+            .map(|inspected| {
+                let (metadata, verdict) = inspected?;
+                if let Verdict::Remove(_) = verdict {
+                    removed.next().unwrap_or(Ok(()))?;
+                }
+                Ok((verdict, metadata.len()))
+            })
+            .collect()
     }
     
-    /// Process a single file
+    /// Inspect a single file, deciding what happens to it
     fn process_single_file(
         file_path: &Path,
         config: &ClearModelConfig,
-        dry_run: bool,
-    ) -> Result<(u64, u64)> {
-        // Check if file should be cleaned based on age and type
-        if !Self::should_clean_file(file_path, config)? {
-            return Ok((0, 0));
+        policy: &CleanupPolicy,
+        run: &RunContext,
+    ) -> Result<(std::fs::Metadata, Verdict)> {
+        let (metadata, verdict) = Self::inspect_file(file_path, config, policy, run)?;
+        // Files of a batch already under way are left alone once the run is cancelled
+        if run.cancel.is_cancelled() {
+            return Ok((metadata, Verdict::Keep));
+        }
+        Ok((metadata, verdict))
+    }
+    
+    /// Remove the files a batch selected, checking and unlinking them together through
+    /// io_uring when the remover batches
+    fn remove_files(removals: &[Removal], remover: &Remover, run: &RunContext, dry_run: bool) -> Vec<Result<()>> {
+        if dry_run || !remover.batches() {
+            return removals.par_iter()
+                .map(|removal| Self::remove_file(removal.path, removal.metadata, removal.rule, remover, run, dry_run))
+                .collect();
         }
         
-        // Get file size before deletion
-        let metadata = std::fs::metadata(file_path)
-            .map_err(|e| ClearModelError::file_operation(
-                format!("Failed to get file metadata: {}", e),
-                Some(file_path.to_path_buf())
-            ))?;
+        // Files are still hashed for the journal and audit log (and paced) one by one
+        let prepared: Vec<_> = removals.par_iter()
+            .map(|removal| Self::prepare_removal(removal.path, removal.metadata.len(), run))
+            .collect();
+        let files: Vec<_> = removals.iter()
+            .zip(&prepared)
+            .filter(|(_, prepared)| prepared.is_ok())
+            .map(|(removal, _)| (removal.path, removal.metadata))
+            .collect();
         
-        let file_size = metadata.len();
+        let mut unlinked = remover.unlink_all(&files).into_iter();
+        removals.iter()
+            .zip(prepared)
+            .map(|(removal, prepared)| {
+                let prepared = prepared?;
+                unlinked.next().unwrap_or(Ok(()))
+                    .map_err(|e| ClearModelError::file_operation(
+                        format!("Failed to delete file: {}", e),
+                        Some(removal.path.to_path_buf())
+                    ))?;
+                debug!("Deleted: {:?} ({} bytes)", removal.path, removal.metadata.len());
+                Self::finish_removal(removal.path, removal.metadata, removal.rule, "deleted", prepared, run)
+            })
+            .collect()
+    }
+    
+    /// Stat a file and decide whether the policy removes it and this user can
+    fn inspect_file(
+        file_path: &Path,
+        config: &ClearModelConfig,
+        policy: &CleanupPolicy,
+        run: &RunContext,
+    ) -> Result<(std::fs::Metadata, Verdict)> {
+        let metadata = Self::file_metadata(file_path)?;
         
+        // Check if file should be cleaned based on age and type
+        let decision = Self::decide_file(file_path, &metadata, config, policy, run.prompter.as_deref());
+        // A shared cache's lock is only taken for directories something is removed from
+        let verdict = if !decision.removes()
+            || Self::held(file_path, &metadata, run)
+            || run.shared.as_ref().is_some_and(|shared| !shared.admits(file_path, &metadata))
+        {
+            Verdict::Keep
+        } else if SecurityManager::removable_without_privileges(file_path, &metadata, &config.security) {
+            Verdict::Remove(decision.label())
+        } else {
+            Verdict::NeedsPrivileges
+        };
+        Ok((metadata, verdict))
+    }
+    
+    /// Whether a file the policy selected is kept for being open in a process or carrying a keep marker
+    fn held(file_path: &Path, metadata: &std::fs::Metadata, run: &RunContext) -> bool {
+        if run.open_files.as_ref().is_some_and(|open| open.contains(metadata)) {
+            info!("Keeping {:?}: a running process has it open", file_path);
+            return true;
+        }
+        if run.keep_markers && exclude::keep_marker(file_path) {
+            debug!("Keeping {:?}: it carries {}", file_path, exclude::KEEP_XATTR);
+            return true;
+        }
+        false
+    }
+    
+    /// Delete (or quarantine) a file `rule` selected, journaling and auditing the removal
+    fn remove_file(
+        file_path: &Path,
+        metadata: &std::fs::Metadata,
+        rule: &str,
+        remover: &Remover,
+        run: &RunContext,
+        dry_run: bool,
+    ) -> Result<()> {
+        let file_size = metadata.len();
         if dry_run {
+            SecurityManager::check_allowed_roots(file_path, &run.allowed_roots)?;
             debug!("Would delete: {:?} ({} bytes)", file_path, file_size);
-            return Ok((1, file_size));
+            run.emit(Event::FileDeleted { path: file_path.to_path_buf(), bytes: file_size, dry_run });
+            if let Some(removals) = &run.removals {
+                removals.record(file_path, metadata, "would delete");
+            }
+            return Ok(());
         }
         
-        // Actually delete the file
-        match std::fs::remove_file(file_path) {
-            Ok(_) => {
-                debug!("Deleted: {:?} ({} bytes)", file_path, file_size);
-                Ok((1, file_size))
+        let mut prepared = Self::prepare_removal(file_path, file_size, run)?;
+        let action = if let Some(quarantine) = &run.quarantine {
+            let destination = quarantine.move_in(file_path, |destination| remover.rename_out(file_path, metadata, destination))?;
+            if let Some(entry) = prepared.entry.as_mut() {
+                entry.destination = Some(destination);
             }
-            Err(e) => {
-                Err(ClearModelError::file_operation(
-                    format!("Failed to delete file: {}", e),
+            "quarantined"
+        } else if run.shred && !shred::has_other_links(metadata) {
+            if let Some(rate_limit) = &run.rate_limit {
+                rate_limit.bytes(file_size);
+            }
+            remover.shred_file(file_path, metadata)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to shred file: {}", e),
                     Some(file_path.to_path_buf())
-                ))
+                ))?;
+            debug!("Shredded: {:?} ({} bytes)", file_path, file_size);
+            "shredded"
+        } else {
+            if run.shred {
+                warn!("Deleting {:?} without shredding it: other hard links still use its contents", file_path);
             }
-        }
+            // Actually delete the file
+            remover.remove_file(file_path, metadata)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to delete file: {}", e),
+                    Some(file_path.to_path_buf())
+                ))?;
+            debug!("Deleted: {:?} ({} bytes)", file_path, file_size);
+            "deleted"
+        };
+        
+        Self::finish_removal(file_path, metadata, rule, action, prepared, run)
     }
     
-    /// Determine if a file should be cleaned
-    fn should_clean_file(file_path: &Path, config: &ClearModelConfig) -> Result<bool> {
-        // Check file extension for Python cache files
-        if let Some(extension) = file_path.extension().and_then(|s| s.to_str()) {
-            let ext_with_dot = format!(".{}", extension);
-            if config.python_cache_extensions.contains(&ext_with_dot) {
-                return Ok(true);
-            }
+    /// Check that a file may be removed, and hash it while its contents are still there
+    fn prepare_removal(file_path: &Path, file_size: u64, run: &RunContext) -> Result<PreparedRemoval> {
+        SecurityManager::check_allowed_roots(file_path, &run.allowed_roots)?;
+        if let Some(rate_limit) = &run.rate_limit {
+            rate_limit.file();
         }
         
-        // Check if file is in __pycache__ directory
-        if let Some(parent) = file_path.parent() {
-            if parent.file_name().and_then(|s| s.to_str()) == Some("__pycache__") {
-                return Ok(true);
+        // Hash before the contents are gone, when undo or the audit log can use the hash
+        let hashed = run.quarantine.is_some() || run.audit.is_some();
+        let entry = run.journal.as_ref().map(|journal| journal.entry_for(file_path, file_size, hashed));
+        let audit_hash = match (&run.audit, &entry) {
+            (Some(_), Some(entry)) => entry.hash.clone(),
+            (Some(audit), None) => audit.hash(file_path, file_size),
+            (None, _) => None,
+        };
+        if let Some(rate_limit) = run.rate_limit.as_ref().filter(|_| audit_hash.is_some() || entry.as_ref().is_some_and(|entry| entry.hash.is_some())) {
+            rate_limit.bytes(file_size);
+        }
+        Ok(PreparedRemoval { entry, audit_hash })
+    }
+    
+    /// Audit and journal a file that was just removed by `action`
+    fn finish_removal(
+        file_path: &Path,
+        metadata: &std::fs::Metadata,
+        rule: &str,
+        action: &str,
+        prepared: PreparedRemoval,
+        run: &RunContext,
+    ) -> Result<()> {
+        let file_size = metadata.len();
+        if let Some(audit) = &run.audit {
+            // The file is already gone; failing the removal at least reports the gap
+            audit.record(file_path, file_size, prepared.audit_hash, rule, action)?;
+        }
+        if let (Some(journal), Some(entry)) = (&run.journal, prepared.entry) {
+            if let Err(e) = journal.record(&entry) {
+                warn!("Failed to journal deletion of {:?}: {}", file_path, e);
             }
         }
         
-        // Check file age
-        let metadata = std::fs::metadata(file_path)
+        run.emit(Event::FileDeleted { path: file_path.to_path_buf(), bytes: file_size, dry_run: false });
+        if let Some(removals) = &run.removals {
+            removals.record(file_path, metadata, action);
+        }
+        Ok(())
+    }
+    
+    fn file_metadata(file_path: &Path) -> Result<std::fs::Metadata> {
+        std::fs::metadata(file_path)
             .map_err(|e| ClearModelError::file_operation(
                 format!("Failed to get file metadata: {}", e),
                 Some(file_path.to_path_buf())
-            ))?;
+            ))
+    }
+    
+    /// Decide whether a file is cleaned, and by which rule
+    fn decide_file(
+        file_path: &Path,
+        metadata: &std::fs::Metadata,
+        config: &ClearModelConfig,
+        policy: &CleanupPolicy,
+        prompter: Option<&ConflictPrompter>,
+    ) -> Decision {
+        if torch_caches::build_in_progress(file_path) {
+            debug!("Keeping file of a torch extension being built: {:?}", file_path);
+            return Decision::Fresh;
+        }
         
-        if let Ok(modified) = metadata.modified() {
-            let age = SystemTime::now()
-                .duration_since(modified)
-                .unwrap_or(Duration::from_secs(0));
-            
-            let max_age = Duration::from_secs(config.max_cache_age_days as u64 * 24 * 3600);
-            
-            if age > max_age {
-                return Ok(true);
+        let age = policy.age_of(file_path, metadata, SystemTime::now());
+        
+        let facts = FileFacts::from_path(file_path, policy, age, metadata.len());
+        let evaluation = policy.evaluate(&facts, &config.python_cache_extensions);
+        
+        let mut decision = evaluation.decision;
+        if evaluation.is_conflict() {
+            if let Some(prompter) = prompter {
+                decision = prompter.resolve(file_path, &evaluation);
             }
+            
+            let matched: Vec<_> = evaluation.matched.iter().map(|rule| rule.label()).collect();
+            info!(
+                "Rule conflict for {:?}: {} matched, resolved as {} ({})",
+                file_path,
+                matched.join(" + "),
+                decision.label(),
+                if decision.removes() { "removed" } else { "kept" }
+            );
+        }
+        
+        if decision == Decision::Pinned {
+            debug!("Keeping pinned model file: {:?}", file_path);
         }
         
-        Ok(false)
+        decision
     }
     
     /// Check system resources before starting operations
-    async fn check_system_resources(&self) -> Result<()> {
+    /// Check memory pressure and measure free space on the filesystems holding `roots`
+    pub async fn check_system_resources(&self, roots: &[PathBuf]) -> Result<Vec<FilesystemSpace>> {
+        if !self.capabilities.system_info {
+            debug!("System information unavailable, skipping resource check");
+            return Ok(Vec::new());
+        }
+        
         let mut system = self.system_info.lock().await;
         system.refresh_all();
         
@@ -370,18 +1508,40 @@ impl ResourceManager {
             memory_usage_percent
         );
         
-        // Note: Disk space checking simplified due to API compatibility
+        drop(system);
+        
+        let spaces = disk_space::measure(roots);
+        for space in &spaces {
+            debug!(
+                "{:?}: {:.2} GB free of {:.2} GB",
+                space.mount_point,
+                space.available_bytes as f64 / 1_073_741_824.0,
+                space.total_bytes as f64 / 1_073_741_824.0
+            );
+        }
         info!("System resource check completed");
         
-        Ok(())
+        Ok(spaces)
     }
     
-    /// Get current operation statistics
-    pub fn get_operation_stats(&self) -> Vec<(String, OperationStats)> {
-        self.operation_stats
-            .iter()
-            .map(|entry| (entry.key().clone(), entry.value().clone()))
-            .collect()
+    /// Refuse to remove a path outside `security.allowed_roots`, for removals done outside the manager
+    pub fn check_deletable(&self, path: &Path) -> Result<()> {
+        SecurityManager::check_allowed_roots(path, &self.run.allowed_roots)
+    }
+    
+    /// Attribute what is cleaned from now on to `target` in the statistics, and clean
+    /// it with its own deletion workers when `concurrency.targets` lists it
+    pub fn start_target(&self, target: &str) {
+        self.run.stats.send(StatsEvent::TargetStarted { name: target.to_string() });
+        self.run.deleters.start_target(target);
+        if let Some(removals) = &self.run.removals {
+            removals.start_target(target);
+        }
+    }
+    
+    /// Per-directory, per-target and run-wide statistics, including everything cleaned so far
+    pub async fn stats(&self) -> StatsSnapshot {
+        self.run.stats.snapshot().await
     }
     
     /// Clean up Python cache files specifically
@@ -394,11 +1554,44 @@ impl ResourceManager {
                 None
             ))?;
         
-        let stats = Arc::clone(&self.operation_stats);
         let config = Arc::clone(&self.config);
+        let policy = CleanupPolicy::from_config(&config);
+        
+        let run = self.sandboxed(self.run_for(&policy, dry_run), std::slice::from_ref(&current_dir), dry_run)?;
+        self.run.progress.start_scan();
+        let result = Self::clean_cache_directory(&current_dir, &config, &policy, &run, dry_run).await;
+        self.run.progress.finish();
         
-        Self::clean_cache_directory(&current_dir, &config, &stats, dry_run).await
+        result
+    }
+}
+
+/// Walker over a cache root with the configured depth and link limits, which stays on the
+/// root's filesystem under `stay_on_filesystem`
+pub(crate) fn cache_walker(root: &Path, config: &ClearModelConfig) -> walkdir::WalkDir {
+    walkdir::WalkDir::new(root)
+        .max_depth(config.security.max_path_depth)
+        .follow_links(config.follow_symlinks)
+        .same_file_system(config.stay_on_filesystem)
+}
+
+/// Device holding the cache root, when walks must not leave it
+fn root_device(root: &Path, config: &ClearModelConfig) -> Option<u64> {
+    if !config.stay_on_filesystem {
+        return None;
     }
+    std::fs::metadata(root).ok().and_then(|metadata| device(&metadata))
+}
+
+#[cfg(unix)]
+fn device(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
 }
 
 #[cfg(test)]
@@ -411,25 +1604,229 @@ mod tests {
     async fn test_resource_manager_creation() {
         let config = ClearModelConfig::default();
         let manager = ResourceManager::new(config).await.unwrap();
-        assert!(manager.operation_stats.is_empty());
+        assert!(manager.stats().await.directories.is_empty());
     }
     
     #[tokio::test]
     async fn test_should_clean_file() {
         let temp_dir = TempDir::new().unwrap();
         let config = ClearModelConfig::default();
+        let policy = CleanupPolicy::from_config(&config);
         
         // Create a .pyc file
         let pyc_file = temp_dir.path().join("test.pyc");
         fs::write(&pyc_file, b"test").unwrap();
         
-        assert!(ResourceManager::should_clean_file(&pyc_file, &config).unwrap());
+        assert!(ResourceManager::decide_file(&pyc_file, &fs::metadata(&pyc_file).unwrap(), &config, &policy, None).removes());
         
         // Create a regular file
         let regular_file = temp_dir.path().join("test.txt");
         fs::write(&regular_file, b"test").unwrap();
         
         // Should not clean regular files unless they're old
-        assert!(!ResourceManager::should_clean_file(&regular_file, &config).unwrap());
+        assert!(!ResourceManager::decide_file(&regular_file, &fs::metadata(&regular_file).unwrap(), &config, &policy, None).removes());
+    }
+    
+    #[tokio::test]
+    async fn test_streams_more_files_than_the_walk_queue_holds() {
+        let temp_dir = TempDir::new().unwrap();
+        let count = WALK_QUEUE_CAPACITY + 500;
+        for i in 0..count {
+            fs::write(temp_dir.path().join(format!("mod{}.pyc", i)), b"x").unwrap();
+        }
+        fs::write(temp_dir.path().join("keep.txt"), b"x").unwrap();
+        
+        let config = ClearModelConfig::default();
+        let policy = CleanupPolicy::from_config(&config);
+        let manager = ResourceManager::new(config.clone()).await.unwrap();
+        let result = ResourceManager::clean_cache_directory(temp_dir.path(), &config, &policy, &manager.run, false).await.unwrap();
+        let stats = manager.stats().await;
+        
+        assert_eq!(result.files_removed, count as u64);
+        let directory = &stats.directories[temp_dir.path()];
+        assert_eq!(directory.files_processed, count as u64 + 1);
+        assert_eq!(directory.files_cleaned, count as u64);
+        assert_eq!(directory.bytes_cleaned, count as u64);
+        assert_eq!(stats.global.bytes_cleaned, count as u64);
+        assert!(temp_dir.path().join("keep.txt").exists());
+    }
+    
+    #[tokio::test]
+    async fn test_apply_removes_planned_files_that_did_not_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("a.pyc"), b"aaaa").unwrap();
+        fs::write(cache.join("b.pyc"), b"bbbb").unwrap();
+        fs::write(cache.join("keep.txt"), b"keep").unwrap();
+        
+        let config = ClearModelConfig { cache_paths: vec![cache.clone()], ..ClearModelConfig::default() };
+        let manager = ResourceManager::new(config).await.unwrap();
+        let plan = manager.plan().await.unwrap();
+        assert_eq!(plan.file_count(), 2);
+        assert_eq!(plan.total_bytes(), 8);
+        
+        // Rewritten after planning, so it is no longer the file that was planned
+        fs::write(cache.join("b.pyc"), b"a fresh download").unwrap();
+        
+        let results = manager.apply(plan).await.unwrap();
+        assert_eq!(results[0].files_removed, 1);
+        assert_eq!(results[0].bytes_freed, 4);
+        assert!(!cache.join("a.pyc").exists());
+        assert!(cache.join("b.pyc").exists());
+        assert!(cache.join("keep.txt").exists());
+    }
+    
+    #[tokio::test]
+    async fn test_cancelled_run_removes_nothing_more() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("a.pyc"), b"aaaa").unwrap();
+        
+        let config = ClearModelConfig { cache_paths: vec![cache.clone()], ..ClearModelConfig::default() };
+        let cancel = CancellationToken::new();
+        let manager = ResourceManager::new(config).await.unwrap().with_cancellation(cancel.clone());
+        let plan = manager.plan().await.unwrap();
+        assert_eq!(plan.file_count(), 1);
+        
+        cancel.cancel();
+        let results = manager.apply(plan).await.unwrap();
+        assert_eq!(results.iter().map(|result| result.files_removed).sum::<u64>(), 0);
+        assert!(cache.join("a.pyc").exists());
+        assert!(manager.clean_all_caches(false).await.unwrap().is_empty());
+        assert!(cache.join("a.pyc").exists());
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_open_files_are_kept_unless_processes_cannot_be_inspected() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("loaded.pyc"), b"loaded").unwrap();
+        let _open = fs::File::open(cache.join("loaded.pyc")).unwrap();
+        
+        let config = ClearModelConfig { cache_paths: vec![cache.clone()], ..ClearModelConfig::default() };
+        let manager = ResourceManager::new(config.clone()).await.unwrap();
+        let results = manager.clean_all_caches(false).await.unwrap();
+        assert_eq!(results[0].files_removed, 0);
+        assert!(cache.join("loaded.pyc").exists());
+        
+        // Without /proc the open file cannot be seen, so the policy alone decides
+        let hidden = Capabilities { proc_fs: false, ..Capabilities::full() };
+        let manager = ResourceManager::new(config).await.unwrap().with_capabilities(hidden);
+        let results = manager.clean_all_caches(false).await.unwrap();
+        assert_eq!(results[0].files_removed, 1);
+        assert!(!cache.join("loaded.pyc").exists());
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_keep_markers_are_only_read_with_extended_attributes() {
+        use std::os::unix::ffi::OsStrExt;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        let marked = cache.join("marked.pyc");
+        fs::write(&marked, b"marked").unwrap();
+        let path = std::ffi::CString::new(marked.as_os_str().as_bytes()).unwrap();
+        let name = std::ffi::CString::new(crate::exclude::KEEP_XATTR).unwrap();
+        // SAFETY: both strings are NUL-terminated and the empty value is not read
+        if unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), std::ptr::null(), 0, 0) } != 0 {
+            // The temporary directory's filesystem has no user attributes
+            return;
+        }
+        
+        let config = ClearModelConfig { cache_paths: vec![cache.clone()], ..ClearModelConfig::default() };
+        let manager = ResourceManager::new(config.clone()).await.unwrap();
+        assert_eq!(manager.plan().await.unwrap().file_count(), 0);
+        
+        let denied = Capabilities { xattrs: false, ..Capabilities::full() };
+        let manager = ResourceManager::new(config).await.unwrap().with_capabilities(denied);
+        assert_eq!(manager.plan().await.unwrap().file_count(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_seccomp_filter_turns_off_batched_unlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let filtered = Capabilities { seccomp_filtered: true, ..Capabilities::full() };
+        let manager = ResourceManager::new(ClearModelConfig::default()).await.unwrap().with_capabilities(filtered);
+        let remover = manager.run.remover(temp_dir.path(), false).unwrap();
+        assert!(remover.anchor.is_some());
+        assert!(!remover.batches());
+    }
+    
+    #[tokio::test]
+    async fn test_excluded_files_are_never_planned() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(cache.join("experiments")).unwrap();
+        fs::write(cache.join("a.pyc"), b"aaaa").unwrap();
+        fs::write(cache.join("pinned.pyc"), b"pinned").unwrap();
+        fs::write(cache.join("experiments/run.pyc"), b"run").unwrap();
+        fs::write(cache.join(crate::exclude::IGNORE_FILE), b"experiments/\n").unwrap();
+        
+        let config = ClearModelConfig {
+            cache_paths: vec![cache.clone()],
+            exclude_patterns: vec!["pinned.*".to_string()],
+            ..ClearModelConfig::default()
+        };
+        let manager = ResourceManager::new(config).await.unwrap();
+        let plan = manager.plan().await.unwrap();
+        let planned: Vec<&PathBuf> = plan.directories.iter().flat_map(|directory| &directory.files).map(|file| &file.path).collect();
+        assert_eq!(planned, vec![&cache.join("a.pyc")]);
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_walks_stay_on_the_cache_filesystem() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("local.pyc"), b"local").unwrap();
+        // procfs is always a different filesystem from the temporary directory
+        std::os::unix::fs::symlink("/proc/version", cache.join("elsewhere.pyc")).unwrap();
+        
+        let planned = |stay_on_filesystem: bool| {
+            let config = ClearModelConfig {
+                cache_paths: vec![cache.clone()],
+                follow_symlinks: true,
+                stay_on_filesystem,
+                ..ClearModelConfig::default()
+            };
+            async move {
+                let plan = ResourceManager::new(config).await.unwrap().plan().await.unwrap();
+                let mut files: Vec<PathBuf> = plan.directories.into_iter().flat_map(|directory| directory.files).map(|file| file.path).collect();
+                files.sort();
+                files
+            }
+        };
+        assert_eq!(planned(false).await, vec![cache.join("elsewhere.pyc"), cache.join("local.pyc")]);
+        assert_eq!(planned(true).await, vec![cache.join("local.pyc")]);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_prune_removes_empty_dirs_and_dangling_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("models--a/snapshots/abc")).unwrap();
+        fs::create_dir_all(root.join("models--b/blobs")).unwrap();
+        fs::write(root.join("models--b/blobs/1"), b"kept").unwrap();
+        std::os::unix::fs::symlink("../../blobs/gone", root.join("models--a/snapshots/abc/config.json")).unwrap();
+        std::os::unix::fs::symlink("blobs/1", root.join("models--b/live")).unwrap();
+        
+        let config = ClearModelConfig::default();
+        let dry = ResourceManager::prune_directory(root, &config, &Remover::default(), true);
+        assert_eq!(dry, PruneCounts { dirs: 0, symlinks: 1 });
+        
+        let pruned = ResourceManager::prune_directory(root, &config, &Remover::default(), false);
+        assert_eq!(pruned, PruneCounts { dirs: 3, symlinks: 1 });
+        assert!(!root.join("models--a").exists());
+        assert!(root.join("models--b/blobs/1").exists());
+        assert!(root.join("models--b/live").exists());
+        assert!(root.exists());
     }
 } 
\ No newline at end of file
@@ -0,0 +1,183 @@
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+use crate::errors::{ClearModelError, Result};
+use crate::journal::{self, JournalEntry};
+
+/// Outcome of undoing a run
+#[derive(Debug, Default)]
+pub struct RestoreSummary {
+    /// Run that was undone
+    pub run_id: String,
+    
+    /// Files moved back to their original location
+    pub restored: u64,
+    
+    /// Bytes moved back
+    pub bytes_restored: u64,
+    
+    /// Files that were deleted permanently and cannot be brought back
+    pub unrecoverable: u64,
+    
+    /// Files that could have been restored but were not
+    pub errors: Vec<String>,
+}
+
+/// Restore the files removed by the most recent run that has not been undone yet
+///
+/// Returns `None` when there is no run to undo, and an error when the run deleted every
+/// file permanently, leaving nothing to restore.
+pub fn undo_last_run(journal_dir: &Path, dry_run: bool) -> Result<Option<RestoreSummary>> {
+    let Some((run_id, journal_path)) = journal::latest_run(journal_dir)? else {
+        return Ok(None);
+    };
+    
+    let entries = journal::read_entries(&journal_path)?;
+    if !entries.is_empty() && entries.iter().all(|entry| entry.destination.is_none()) {
+        return Err(ClearModelError::cache(format!(
+            "Run {} deleted its {} files permanently, so there is nothing to restore; set `quarantine = true` to make runs undoable",
+            run_id,
+            entries.len()
+        )));
+    }
+    
+    info!("Undoing run {} (dry_run: {})", run_id, dry_run);
+    
+    let mut summary = RestoreSummary {
+        run_id,
+        ..RestoreSummary::default()
+    };
+    
+    // Restore in reverse order so the newest state of a path wins
+    for entry in entries.iter().rev() {
+        if entry.destination.is_none() {
+            summary.unrecoverable += 1;
+            continue;
+        }
+        
+        match restore_entry(entry, dry_run) {
+            Ok(()) => {
+                summary.restored += 1;
+                summary.bytes_restored += entry.size;
+            }
+            Err(e) => {
+                warn!("Failed to restore {:?}: {}", entry.path, e);
+                summary.errors.push(e.to_string());
+            }
+        }
+    }
+    
+    // Keep the journal around for another attempt if anything could not be restored
+    if !dry_run && summary.errors.is_empty() {
+        journal::mark_undone(&journal_path)?;
+    }
+    
+    Ok(Some(summary))
+}
+
+/// Move a single file back to where it was removed from
+fn restore_entry(entry: &JournalEntry, dry_run: bool) -> Result<()> {
+    let Some(destination) = &entry.destination else {
+        return Err(ClearModelError::file_operation(
+            "File was deleted permanently".to_string(),
+            Some(entry.path.clone())
+        ));
+    };
+    
+    if !destination.exists() {
+        return Err(ClearModelError::file_operation(
+            "Removed file is no longer available".to_string(),
+            Some(destination.clone())
+        ));
+    }
+    
+    if entry.path.exists() {
+        return Err(ClearModelError::file_operation(
+            "A file already exists at the original location".to_string(),
+            Some(entry.path.clone())
+        ));
+    }
+    
+    // Refuse to put back contents that changed since they were removed
+    if let Some(expected) = &entry.hash {
+        let actual = journal::hash_file(destination)?;
+        if &actual != expected {
+            return Err(ClearModelError::security(format!(
+                "Contents of {:?} changed since removal (hash mismatch)",
+                destination
+            )));
+        }
+    }
+    
+    if dry_run {
+        debug!("Would restore: {:?} -> {:?}", destination, entry.path);
+        return Ok(());
+    }
+    
+    if let Some(parent) = entry.path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to recreate directory: {}", e),
+                Some(parent.to_path_buf())
+            ))?;
+    }
+    
+    std::fs::rename(destination, &entry.path)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to restore file: {}", e),
+            Some(entry.path.clone())
+        ))?;
+    
+    debug!("Restored: {:?}", entry.path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JournalConfig;
+    use crate::journal::{new_run_id, Journal};
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_undo_restores_moved_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_dir = temp_dir.path().join("journal");
+        let original = temp_dir.path().join("cache/models--org--name/blob");
+        let moved = temp_dir.path().join("held/blob");
+        let deleted = temp_dir.path().join("cache/other");
+        
+        fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        fs::write(&moved, b"weights").unwrap();
+        
+        let journal = Journal::in_dir(&journal_dir, &new_run_id(), &JournalConfig::default()).unwrap();
+        let mut entry = journal.entry_for(&moved, 7, true);
+        entry.path = original.clone();
+        entry.destination = Some(moved.clone());
+        journal.record(&entry).unwrap();
+        journal.record(&journal.entry_for(&deleted, 0, false)).unwrap();
+        
+        let summary = undo_last_run(&journal_dir, false).unwrap().unwrap();
+        assert_eq!(summary.restored, 1);
+        assert_eq!(summary.unrecoverable, 1);
+        assert!(summary.errors.is_empty());
+        assert_eq!(fs::read(&original).unwrap(), b"weights");
+        
+        // The run is marked as undone
+        assert!(undo_last_run(&journal_dir, false).unwrap().is_none());
+    }
+    
+    #[test]
+    fn test_undo_refuses_a_run_that_deleted_everything_permanently() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_dir = temp_dir.path().join("journal");
+        let journal = Journal::in_dir(&journal_dir, &new_run_id(), &JournalConfig::default()).unwrap();
+        journal.record(&journal.entry_for(&temp_dir.path().join("cache/blob"), 7, false)).unwrap();
+        
+        let error = undo_last_run(&journal_dir, false).unwrap_err();
+        assert!(error.to_string().contains("nothing to restore"));
+        // The run is still the last one, so undo keeps saying so
+        assert!(journal::latest_run(&journal_dir).unwrap().is_some());
+    }
+}
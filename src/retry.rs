@@ -0,0 +1,99 @@
+use std::io;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::config::RetryConfig;
+
+/// Windows' `ERROR_SHARING_VIOLATION` and `ERROR_LOCK_VIOLATION`: another process has the file open
+#[cfg(windows)]
+const SHARING_VIOLATIONS: [i32; 2] = [32, 33];
+
+/// How often a file operation that failed for a passing reason is tried again
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+        }
+    }
+    
+    /// Run `operation`, trying again after a doubling pause while it fails with a transient error
+    ///
+    /// Runs on the deletion workers, so the pause blocks the calling thread.
+    pub fn run<T>(&self, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Err(e) if attempt < self.max_attempts && is_transient(&e) => {
+                    debug!("Attempt {} of {} failed ({}); retrying in {:?}", attempt, self.max_attempts, e, backoff);
+                    std::thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_config(&RetryConfig::default())
+    }
+}
+
+/// Whether an error may go away on its own: the file is busy or locked (EBUSY, a Windows
+/// sharing violation, common while NFS or an antivirus scanner holds it), or the call was
+/// interrupted (EINTR, EAGAIN)
+pub fn is_transient(error: &io::Error) -> bool {
+    if matches!(error.kind(), io::ErrorKind::ResourceBusy | io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock) {
+        return true;
+    }
+    #[cfg(windows)]
+    if error.raw_os_error().is_some_and(|code| SHARING_VIOLATIONS.contains(&code)) {
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_transient_errors_are_retried_until_attempts_run_out() {
+        let policy = RetryPolicy::from_config(&RetryConfig { max_attempts: 3, initial_backoff_ms: 1, max_backoff_ms: 2 });
+        
+        let mut calls = 0;
+        let result = policy.run(|| {
+            calls += 1;
+            if calls < 3 { Err(io::Error::from(io::ErrorKind::ResourceBusy)) } else { Ok(calls) }
+        });
+        assert_eq!(result.unwrap(), 3);
+        
+        let mut calls = 0;
+        let result: io::Result<()> = policy.run(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::Interrupted))
+        });
+        assert_eq!((result.unwrap_err().kind(), calls), (io::ErrorKind::Interrupted, 3));
+        
+        // Permanent errors fail at once
+        let mut calls = 0;
+        let result: io::Result<()> = policy.run(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,176 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Exclusive lock held while a run removes files, so a scheduled run and a manual one
+/// never clean the same caches at once
+///
+/// The lock is an `flock` on `run.pid` in the state directory, which also records the
+/// holder's pid for the error message. It is released when the lock is dropped or the
+/// process dies; the file itself stays, since removing it would let two runs lock
+/// different files.
+#[derive(Debug)]
+pub struct RunLock {
+    _file: File,
+}
+
+impl RunLock {
+    /// Take the lock in the default state directory, waiting for the holder with `wait`
+    /// and failing with [`ClearModelError::AlreadyRunning`] otherwise
+    pub fn acquire(wait: bool) -> Result<Self> {
+        Self::acquire_at(&lock_path()?, wait)
+    }
+    
+    /// Take the lock at `path`
+    pub fn acquire_at(path: &Path, wait: bool) -> Result<Self> {
+        let mut file = open(path, "run lock")?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if wait => {
+                info!("Waiting for another clearmodel instance{} to finish", holder_suffix(&mut file));
+                file.lock()?;
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(ClearModelError::AlreadyRunning { pid: holder(&mut file) });
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        
+        Self::locked(file, path)
+    }
+    
+    /// Take the lock at `path` if it is free, for runs that can just skip a turn
+    pub fn try_acquire_at(path: &Path) -> Result<Option<Self>> {
+        let file = open(path, "run lock")?;
+        match file.try_lock() {
+            Ok(()) => Self::locked(file, path).map(Some),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+    
+    fn locked(mut file: File, path: &Path) -> Result<Self> {
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        debug!("Holding the run lock {:?}", path);
+        Ok(Self { _file: file })
+    }
+}
+
+/// Shared lock every clearmodel pass holds while it reads cache files
+///
+/// Usage tracking learns of opens from inotify, which does not say who opened a file, so
+/// it skips opens while any instance holds this lock; clearmodel's own scans and hashing
+/// are then not counted as use. Any number of passes hold it at once.
+#[derive(Debug)]
+pub struct PassLock {
+    _file: File,
+}
+
+impl PassLock {
+    /// Mark a pass in the default state directory; without one the pass goes unmarked
+    pub fn acquire() -> Option<Self> {
+        match pass_lock_path().and_then(|path| Self::acquire_at(&path)) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                debug!("Not marking this pass for usage tracking: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Mark a pass with the lock at `path`
+    pub fn acquire_at(path: &Path) -> Result<Self> {
+        let file = open(path, "pass lock")?;
+        file.lock_shared()?;
+        Ok(Self { _file: file })
+    }
+    
+    /// Whether any pass holds the lock at `path`
+    pub fn held_at(path: &Path) -> bool {
+        open(path, "pass lock").is_ok_and(|file| matches!(file.try_lock(), Err(TryLockError::WouldBlock)))
+    }
+}
+
+fn open(path: &Path, what: &str) -> Result<File> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to create state directory: {}", e),
+                Some(dir.to_path_buf())
+            ))?;
+    }
+    
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to open {}: {}", what, e),
+            Some(path.to_path_buf())
+        ))
+}
+
+/// Pid the current holder wrote to the lock file
+fn holder(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+fn holder_suffix(file: &mut File) -> String {
+    holder(file).map(|pid| format!(" (pid {})", pid)).unwrap_or_default()
+}
+
+/// Location of the lock (`run.pid` in the state directory)
+pub fn lock_path() -> Result<PathBuf> {
+    state::require(state::RUN_LOCK, "the run lock")
+}
+
+/// Location of the pass lock (`pass.lock` in the state directory)
+pub fn pass_lock_path() -> Result<PathBuf> {
+    state::require(state::PASS_LOCK, "the pass lock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_second_run_fails_fast_until_the_first_releases() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state").join("run.pid");
+        
+        let first = RunLock::acquire_at(&path, false).unwrap();
+        let busy = RunLock::acquire_at(&path, false).unwrap_err();
+        assert!(matches!(busy, ClearModelError::AlreadyRunning { pid: Some(pid) } if pid == std::process::id()));
+        assert!(RunLock::try_acquire_at(&path).unwrap().is_none());
+        
+        drop(first);
+        assert!(RunLock::try_acquire_at(&path).unwrap().is_some());
+    }
+    
+    #[test]
+    fn test_passes_share_the_pass_lock_and_are_seen_while_they_hold_it() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state").join("pass.lock");
+        assert!(!PassLock::held_at(&path));
+        
+        let scan = PassLock::acquire_at(&path).unwrap();
+        let clean = PassLock::acquire_at(&path).unwrap();
+        assert!(PassLock::held_at(&path));
+        
+        drop(scan);
+        assert!(PassLock::held_at(&path));
+        drop(clean);
+        assert!(!PassLock::held_at(&path));
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use crate::errors::{ClearModelError, Result};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Worker threads that perform deletions under a Landlock ruleset (`security.sandbox`)
+///
+/// Each worker restricts itself before taking any work, so it can only write, remove
+/// or rename below the roots it was started with. A path handling bug then fails with
+/// `EACCES` instead of deleting something elsewhere. The rest of the process is not
+/// restricted, so scanning, journaling and logging work as usual.
+pub struct DeletionSandbox {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl DeletionSandbox {
+    /// Start `workers` sandboxed threads that may only modify files below `roots`
+    ///
+    /// Roots that do not exist are left out. Fails if Landlock is unavailable, since
+    /// running unsandboxed when a sandbox was asked for would be silently weaker.
+    pub fn spawn(roots: &[PathBuf], workers: usize) -> Result<Self> {
+        let roots: Vec<PathBuf> = roots.iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect();
+        
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (ready, started) = mpsc::channel();
+        let workers = workers.max(1);
+        
+        for index in 0..workers {
+            let receiver = Arc::clone(&receiver);
+            let roots = roots.clone();
+            let ready = ready.clone();
+            std::thread::Builder::new()
+                .name(format!("clearmodel-sandbox-{}", index))
+                .spawn(move || {
+                    let restricted = restrict(&roots);
+                    let ok = restricted.is_ok();
+                    let _ = ready.send(restricted);
+                    drop(ready);
+                    if !ok {
+                        return;
+                    }
+                    
+                    loop {
+                        // The guard is dropped before the job runs, so workers delete in parallel
+                        let job = receiver.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => return,
+                        }
+                    }
+                })
+                .map_err(|e| ClearModelError::environment(format!("Failed to start sandbox worker: {}", e)))?;
+        }
+        drop(ready);
+        
+        for restricted in started.iter().take(workers) {
+            restricted?;
+        }
+        
+        debug!("Deleting from {} sandboxed worker(s) confined to {:?}", workers, roots);
+        Ok(Self { jobs })
+    }
+    
+    /// Run a job on a sandboxed worker and wait for its result
+    pub fn run<T, F>(&self, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (reply, result) = mpsc::sync_channel(1);
+        self.jobs
+            .send(Box::new(move || {
+                let _ = reply.send(job());
+            }))
+            .map_err(|_| ClearModelError::environment("Sandbox workers have exited".to_string()))?;
+        
+        result.recv()
+            .map_err(|_| ClearModelError::environment("Sandbox worker stopped before finishing a job".to_string()))
+    }
+}
+
+/// Restrict the calling thread to writing below `roots`
+#[cfg(target_os = "linux")]
+fn restrict(roots: &[PathBuf]) -> Result<()> {
+    use landlock::{
+        path_beneath_rules, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, RulesetStatus, ABI,
+    };
+    
+    let failed = |e: landlock::RulesetError| ClearModelError::security(format!("Failed to set up the Landlock sandbox: {}", e));
+    let access = AccessFs::from_write(ABI::V3);
+    let status = Ruleset::default()
+        .handle_access(access)
+        .map_err(failed)?
+        .create()
+        .map_err(failed)?
+        .add_rules(path_beneath_rules(roots, access))
+        .map_err(failed)?
+        .restrict_self()
+        .map_err(failed)?;
+    
+    match status.ruleset {
+        RulesetStatus::NotEnforced => Err(ClearModelError::security(
+            "security.sandbox is set but this kernel does not support Landlock (Linux 5.13 or newer with the landlock LSM enabled)".to_string()
+        )),
+        RulesetStatus::PartiallyEnforced => {
+            debug!("Landlock is only partially enforced by this kernel");
+            Ok(())
+        }
+        RulesetStatus::FullyEnforced => Ok(()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn restrict(_roots: &[PathBuf]) -> Result<()> {
+    Err(ClearModelError::security(
+        "security.sandbox uses Landlock and is only available on Linux".to_string()
+    ))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_workers_cannot_delete_outside_their_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let inside = temp_dir.path().join("cache");
+        let outside = temp_dir.path().join("home");
+        fs::create_dir_all(&inside).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(inside.join("model.bin"), b"x").unwrap();
+        fs::write(outside.join("notes.txt"), b"x").unwrap();
+        
+        let sandbox = match DeletionSandbox::spawn(std::slice::from_ref(&inside), 2) {
+            Ok(sandbox) => sandbox,
+            // Kernels without Landlock refuse to start a sandbox at all
+            Err(e) => {
+                assert!(e.to_string().contains("Landlock"));
+                return;
+            }
+        };
+        
+        let remove = |path: PathBuf| sandbox.run(move || fs::remove_file(path)).unwrap();
+        remove(inside.join("model.bin")).unwrap();
+        assert!(!inside.join("model.bin").exists());
+        
+        let denied = remove(outside.join("notes.txt")).unwrap_err();
+        assert_eq!(denied.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(outside.join("notes.txt").exists());
+        
+        // The calling thread is not restricted
+        fs::remove_file(outside.join("notes.txt")).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,586 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::str::FromStr;
+use tracing::{debug, info};
+
+use crate::command_runner::CommandRunner;
+use crate::errors::{ClearModelError, Result};
+
+/// Name used for the unit, agent label and scheduled task
+const JOB_NAME: &str = "clearmodel";
+
+/// Label of the launchd agent
+const LAUNCHD_LABEL: &str = "com.clearmodel.clean";
+
+/// How often a scheduled cleanup runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    pub fn label(self) -> &'static str {
+        match self {
+            Frequency::Hourly => "hourly",
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+            Frequency::Monthly => "monthly",
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "hourly" => Ok(Frequency::Hourly),
+            "daily" => Ok(Frequency::Daily),
+            "weekly" => Ok(Frequency::Weekly),
+            "monthly" => Ok(Frequency::Monthly),
+            other => Err(format!("unknown frequency {:?} (expected hourly, daily, weekly or monthly)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// What a scheduled job runs and how often
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub every: Frequency,
+    
+    /// Absolute path of the clearmodel binary
+    pub executable: PathBuf,
+    
+    /// Absolute path of the configuration file (or its `https://` URL), if one should be passed explicitly
+    pub config: Option<PathBuf>,
+    
+    /// `--config-checksum` of a remote configuration
+    pub config_checksum: Option<String>,
+    
+    /// `--config-signers` of a remote configuration
+    pub config_signers: Option<PathBuf>,
+    
+    /// Profile the job cleans with
+    pub profile: Option<String>,
+}
+
+impl Schedule {
+    /// Arguments the job passes to clearmodel
+    pub fn arguments(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(config) = &self.config {
+            args.push("--config".to_string());
+            args.push(config.display().to_string());
+        }
+        if let Some(checksum) = &self.config_checksum {
+            args.push("--config-checksum".to_string());
+            args.push(checksum.clone());
+        }
+        if let Some(signers) = &self.config_signers {
+            args.push("--config-signers".to_string());
+            args.push(signers.display().to_string());
+        }
+        if let Some(profile) = &self.profile {
+            args.push("--profile".to_string());
+            args.push(profile.clone());
+        }
+        args.push("--verbose".to_string());
+        args.push("clean".to_string());
+        args
+    }
+}
+
+/// State of the installed job as reported by the OS scheduler
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleStatus {
+    pub installed: bool,
+    
+    /// The OS scheduler has the job loaded and enabled
+    pub active: bool,
+    
+    pub every: Option<Frequency>,
+    
+    /// Next run time, when the scheduler reports one
+    pub next_run: Option<String>,
+    
+    /// Files that make up the job
+    pub files: Vec<PathBuf>,
+}
+
+/// An OS-native job scheduler
+pub trait SchedulerBackend {
+    /// Human-readable name of the scheduler
+    fn name(&self) -> &'static str;
+    
+    /// Install (or replace) the job, returning the files written
+    fn install(&self, schedule: &Schedule, dry_run: bool) -> Result<Vec<PathBuf>>;
+    
+    fn status(&self) -> Result<ScheduleStatus>;
+    
+    /// Remove the job, returning whether one was installed
+    fn remove(&self, dry_run: bool) -> Result<bool>;
+}
+
+/// Scheduler backend for the current platform, running the service manager's tool through `commands`
+pub fn native_backend(commands: &CommandRunner) -> Result<Box<dyn SchedulerBackend>> {
+    if cfg!(target_os = "linux") {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home::home_dir().map(|home| home.join(".config")))
+            .ok_or_else(|| ClearModelError::environment(
+                "Cannot determine the systemd user unit directory".to_string()
+            ))?;
+        Ok(Box::new(SystemdTimer::new(config_dir.join("systemd").join("user"), commands.clone())))
+    } else if cfg!(target_os = "macos") {
+        let home = home::home_dir().ok_or_else(|| ClearModelError::environment(
+            "Cannot determine the home directory".to_string()
+        ))?;
+        Ok(Box::new(Launchd::new(home.join("Library"), commands.clone())))
+    } else if cfg!(windows) {
+        Ok(Box::new(TaskScheduler::new(commands.clone())))
+    } else {
+        Err(ClearModelError::environment(
+            "No supported scheduler on this platform; run `clearmodel clean` from cron instead".to_string()
+        ))
+    }
+}
+
+/// systemd user timer and service on Linux
+pub struct SystemdTimer {
+    unit_dir: PathBuf,
+    commands: CommandRunner,
+}
+
+impl SystemdTimer {
+    pub fn new(unit_dir: PathBuf, commands: CommandRunner) -> Self {
+        Self { unit_dir, commands }
+    }
+    
+    fn service_path(&self) -> PathBuf {
+        self.unit_dir.join(format!("{}.service", JOB_NAME))
+    }
+    
+    fn timer_path(&self) -> PathBuf {
+        self.unit_dir.join(format!("{}.timer", JOB_NAME))
+    }
+    
+    /// Contents of the service and timer units
+    pub fn render(schedule: &Schedule) -> (String, String) {
+        let command: Vec<String> = std::iter::once(schedule.executable.display().to_string())
+            .chain(schedule.arguments())
+            .map(|arg| format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect();
+        
+        let service = format!(
+            "[Unit]\n\
+             Description=Clean ML model caches\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n\
+             ExecStart={}\n\
+             Nice=10\n\
+             IOSchedulingClass=idle\n",
+            command.join(" ")
+        );
+        
+        let timer = format!(
+            "[Unit]\n\
+             Description=Clean ML model caches {}\n\
+             \n\
+             [Timer]\n\
+             OnCalendar={}\n\
+             Persistent=true\n\
+             RandomizedDelaySec=15min\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n",
+            schedule.every,
+            schedule.every
+        );
+        
+        (service, timer)
+    }
+    
+    fn systemctl(&self, args: &[&str]) -> Result<Output> {
+        self.commands.output("systemctl", &[&["--user"], args].concat(), None)
+    }
+}
+
+impl SchedulerBackend for SystemdTimer {
+    fn name(&self) -> &'static str {
+        "systemd timer"
+    }
+    
+    fn install(&self, schedule: &Schedule, dry_run: bool) -> Result<Vec<PathBuf>> {
+        let files = vec![self.service_path(), self.timer_path()];
+        if dry_run {
+            return Ok(files);
+        }
+        
+        let (service, timer) = Self::render(schedule);
+        write_file(&self.service_path(), &service)?;
+        write_file(&self.timer_path(), &timer)?;
+        
+        check(self.systemctl(&["daemon-reload"])?, "systemctl --user daemon-reload")?;
+        let timer_unit = format!("{}.timer", JOB_NAME);
+        check(self.systemctl(&["enable", "--now", &timer_unit])?, "systemctl --user enable")?;
+        
+        info!("Installed systemd timer {} ({})", timer_unit, schedule.every);
+        Ok(files)
+    }
+    
+    fn status(&self) -> Result<ScheduleStatus> {
+        let Ok(timer) = std::fs::read_to_string(self.timer_path()) else {
+            return Ok(ScheduleStatus::default());
+        };
+        
+        let timer_unit = format!("{}.timer", JOB_NAME);
+        let active = self.systemctl(&["is-active", "--quiet", &timer_unit])
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+        let next_run = self.systemctl(&["show", &timer_unit, "--property=NextElapseUSecRealtime", "--value"])
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|next| !next.is_empty());
+        
+        Ok(ScheduleStatus {
+            installed: true,
+            active,
+            every: timer.lines()
+                .find_map(|line| line.strip_prefix("OnCalendar="))
+                .and_then(|value| value.trim().parse().ok()),
+            next_run,
+            files: vec![self.service_path(), self.timer_path()],
+        })
+    }
+    
+    fn remove(&self, dry_run: bool) -> Result<bool> {
+        if !self.timer_path().exists() && !self.service_path().exists() {
+            return Ok(false);
+        }
+        if dry_run {
+            return Ok(true);
+        }
+        
+        // The timer may already be disabled by hand, so a failure here is not fatal
+        let timer_unit = format!("{}.timer", JOB_NAME);
+        if let Err(e) = self.systemctl(&["disable", "--now", &timer_unit]) {
+            debug!("Failed to disable {}: {}", timer_unit, e);
+        }
+        remove_file(&self.timer_path())?;
+        remove_file(&self.service_path())?;
+        check(self.systemctl(&["daemon-reload"])?, "systemctl --user daemon-reload")?;
+        
+        info!("Removed systemd timer {}", timer_unit);
+        Ok(true)
+    }
+}
+
+/// launchd user agent on macOS
+pub struct Launchd {
+    library_dir: PathBuf,
+    commands: CommandRunner,
+}
+
+impl Launchd {
+    pub fn new(library_dir: PathBuf, commands: CommandRunner) -> Self {
+        Self { library_dir, commands }
+    }
+    
+    fn plist_path(&self) -> PathBuf {
+        self.library_dir.join("LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL))
+    }
+    
+    /// Contents of the agent's property list
+    pub fn render(schedule: &Schedule, log_path: &Path) -> String {
+        let arguments: String = std::iter::once(schedule.executable.display().to_string())
+            .chain(schedule.arguments())
+            .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+            .collect();
+        
+        let interval: &[(&str, u32)] = match schedule.every {
+            Frequency::Hourly => &[("Minute", 0)],
+            Frequency::Daily => &[("Hour", 3), ("Minute", 0)],
+            Frequency::Weekly => &[("Weekday", 0), ("Hour", 3), ("Minute", 0)],
+            Frequency::Monthly => &[("Day", 1), ("Hour", 3), ("Minute", 0)],
+        };
+        let interval: String = interval.iter()
+            .map(|(key, value)| format!("        <key>{}</key>\n        <integer>{}</integer>\n", key, value))
+            .collect();
+        let log_path = xml_escape(&log_path.display().to_string());
+        
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20   <key>Label</key>\n\
+             \x20   <string>{}</string>\n\
+             \x20   <key>ProgramArguments</key>\n\
+             \x20   <array>\n\
+             {}\
+             \x20   </array>\n\
+             \x20   <key>StartCalendarInterval</key>\n\
+             \x20   <dict>\n\
+             {}\
+             \x20   </dict>\n\
+             \x20   <key>ProcessType</key>\n\
+             \x20   <string>Background</string>\n\
+             \x20   <key>StandardOutPath</key>\n\
+             \x20   <string>{}</string>\n\
+             \x20   <key>StandardErrorPath</key>\n\
+             \x20   <string>{}</string>\n\
+             </dict>\n\
+             </plist>\n",
+            LAUNCHD_LABEL, arguments, interval, log_path, log_path
+        )
+    }
+}
+
+impl SchedulerBackend for Launchd {
+    fn name(&self) -> &'static str {
+        "launchd agent"
+    }
+    
+    fn install(&self, schedule: &Schedule, dry_run: bool) -> Result<Vec<PathBuf>> {
+        let plist = self.plist_path();
+        if dry_run {
+            return Ok(vec![plist]);
+        }
+        
+        // Reloading is the only way to pick up changes to an existing agent
+        if plist.exists() {
+            let _ = self.commands.output("launchctl", &["unload", "-w", &plist.display().to_string()], None);
+        }
+        
+        let log_path = self.library_dir.join("Logs").join("clearmodel.log");
+        write_file(&plist, &Self::render(schedule, &log_path))?;
+        check(self.commands.output("launchctl", &["load", "-w", &plist.display().to_string()], None)?, "launchctl load")?;
+        
+        info!("Installed launchd agent {} ({})", LAUNCHD_LABEL, schedule.every);
+        Ok(vec![plist])
+    }
+    
+    fn status(&self) -> Result<ScheduleStatus> {
+        let plist = self.plist_path();
+        let Ok(contents) = std::fs::read_to_string(&plist) else {
+            return Ok(ScheduleStatus::default());
+        };
+        
+        let active = self.commands.output("launchctl", &["list", LAUNCHD_LABEL], None)
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+        let every = if contents.contains("<key>Day</key>") {
+            Frequency::Monthly
+        } else if contents.contains("<key>Weekday</key>") {
+            Frequency::Weekly
+        } else if contents.contains("<key>Hour</key>") {
+            Frequency::Daily
+        } else {
+            Frequency::Hourly
+        };
+        
+        Ok(ScheduleStatus {
+            installed: true,
+            active,
+            every: Some(every),
+            next_run: None,
+            files: vec![plist],
+        })
+    }
+    
+    fn remove(&self, dry_run: bool) -> Result<bool> {
+        let plist = self.plist_path();
+        if !plist.exists() {
+            return Ok(false);
+        }
+        if dry_run {
+            return Ok(true);
+        }
+        
+        if let Err(e) = self.commands.output("launchctl", &["unload", "-w", &plist.display().to_string()], None) {
+            debug!("Failed to unload {}: {}", LAUNCHD_LABEL, e);
+        }
+        remove_file(&plist)?;
+        
+        info!("Removed launchd agent {}", LAUNCHD_LABEL);
+        Ok(true)
+    }
+}
+
+/// Task Scheduler entry on Windows
+pub struct TaskScheduler {
+    commands: CommandRunner,
+}
+
+impl TaskScheduler {
+    pub fn new(commands: CommandRunner) -> Self {
+        Self { commands }
+    }
+    
+    /// Arguments to `schtasks` that create the task
+    pub fn create_arguments(schedule: &Schedule) -> Vec<String> {
+        let command: Vec<String> = std::iter::once(schedule.executable.display().to_string())
+            .chain(schedule.arguments())
+            .map(|arg| if arg.contains(' ') { format!("\"{}\"", arg) } else { arg })
+            .collect();
+        
+        let mut args: Vec<String> = ["/Create", "/F", "/TN", JOB_NAME, "/TR"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        args.push(command.join(" "));
+        args.push("/SC".to_string());
+        args.push(schedule.every.label().to_ascii_uppercase());
+        if schedule.every != Frequency::Hourly {
+            args.extend(["/ST".to_string(), "03:00".to_string()]);
+        }
+        args
+    }
+}
+
+impl SchedulerBackend for TaskScheduler {
+    fn name(&self) -> &'static str {
+        "Task Scheduler"
+    }
+    
+    fn install(&self, schedule: &Schedule, dry_run: bool) -> Result<Vec<PathBuf>> {
+        if dry_run {
+            return Ok(Vec::new());
+        }
+        
+        let args = Self::create_arguments(schedule);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        check(self.commands.output("schtasks", &args, None)?, "schtasks /Create")?;
+        
+        info!("Installed scheduled task {} ({})", JOB_NAME, schedule.every);
+        Ok(Vec::new())
+    }
+    
+    fn status(&self) -> Result<ScheduleStatus> {
+        let output = self.commands.output("schtasks", &["/Query", "/TN", JOB_NAME, "/FO", "LIST", "/V"], None)?;
+        if !output.status.success() {
+            return Ok(ScheduleStatus::default());
+        }
+        
+        let report = String::from_utf8_lossy(&output.stdout);
+        let field = |name: &str| report.lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim_start_matches(':').trim().to_string());
+        
+        Ok(ScheduleStatus {
+            installed: true,
+            active: field("Scheduled Task State").map(|state| state == "Enabled").unwrap_or(true),
+            every: field("Schedule Type").and_then(|value| value.parse().ok()),
+            next_run: field("Next Run Time"),
+            files: Vec::new(),
+        })
+    }
+    
+    fn remove(&self, dry_run: bool) -> Result<bool> {
+        if !self.status()?.installed {
+            return Ok(false);
+        }
+        if dry_run {
+            return Ok(true);
+        }
+        
+        check(self.commands.output("schtasks", &["/Delete", "/TN", JOB_NAME, "/F"], None)?, "schtasks /Delete")?;
+        info!("Removed scheduled task {}", JOB_NAME);
+        Ok(true)
+    }
+}
+
+/// Turn a non-zero exit into an error carrying the command's stderr
+fn check(output: Output, what: &str) -> Result<()> {
+    if output.status.success() {
+        return Ok(());
+    }
+    
+    Err(ClearModelError::environment(format!(
+        "{} failed: {}",
+        what,
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to create directory: {}", e),
+                Some(parent.to_path_buf())
+            ))?;
+    }
+    
+    std::fs::write(path, contents)
+        .map_err(|e| ClearModelError::file_operation(
+            format!("Failed to write scheduler file: {}", e),
+            Some(path.to_path_buf())
+        ))
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(ClearModelError::file_operation(
+            format!("Failed to remove scheduler file: {}", e),
+            Some(path.to_path_buf())
+        )),
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn schedule(every: Frequency) -> Schedule {
+        Schedule {
+            every,
+            executable: PathBuf::from("/opt/clear model/clearmodel"),
+            config: Some(PathBuf::from("/home/user/.clearmodel.toml")),
+            config_checksum: None,
+            config_signers: None,
+            profile: None,
+        }
+    }
+    
+    #[test]
+    fn test_systemd_units() {
+        let (service, timer) = SystemdTimer::render(&schedule("weekly".parse().unwrap()));
+        assert!(service.contains(
+            "ExecStart=\"/opt/clear model/clearmodel\" \"--config\" \"/home/user/.clearmodel.toml\" \"--verbose\" \"clean\""
+        ));
+        assert!(timer.contains("OnCalendar=weekly"));
+        assert!(timer.contains("Persistent=true"));
+        assert!("fortnightly".parse::<Frequency>().is_err());
+    }
+    
+    #[test]
+    fn test_launchd_and_schtasks_schedules() {
+        let plist = Launchd::render(&schedule(Frequency::Monthly), Path::new("/tmp/clearmodel.log"));
+        assert!(plist.contains("<string>/opt/clear model/clearmodel</string>"));
+        assert!(plist.contains("<key>Day</key>\n        <integer>1</integer>"));
+        
+        let args = TaskScheduler::create_arguments(&schedule(Frequency::Daily));
+        assert!(args.contains(&"\"/opt/clear model/clearmodel\" --config /home/user/.clearmodel.toml --verbose clean".to_string()));
+        assert!(args.ends_with(&["/SC".to_string(), "DAILY".to_string(), "/ST".to_string(), "03:00".to_string()]));
+    }
+}
\ No newline at end of file
@@ -1,9 +1,12 @@
 use camino::{Utf8Path, Utf8PathBuf};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use path_clean::PathClean;
 use sanitize_filename::sanitize;
 use std::path::{Component, Path, PathBuf};
 use tracing::{debug, warn};
 
+use crate::config::SecurityConfig;
+use crate::environment::ContainerLayout;
 use crate::errors::{ClearModelError, Result};
 
 /// Security utilities for safe path operations and traversal protection
@@ -142,53 +145,214 @@ impl SecurityManager {
     }
     
     /// Check if a path is safe for deletion operations
-    pub fn validate_deletion_safety(path: &Path) -> Result<()> {
-        // Prevent deletion of critical system paths
-        let dangerous_paths = [
-            "/",
-            "/bin",
-            "/boot",
-            "/dev",
-            "/etc",
-            "/lib",
-            "/proc",
-            "/root",
-            "/sbin",
-            "/sys",
-            "/usr",
-            "/var/log",
-            "/var/lib",
-        ];
+    ///
+    /// The path must not match `denied_roots` or be a protected Windows location, and must be
+    /// inside one of `allowed_roots` when any are configured.
+    pub fn validate_deletion_safety(path: &Path, security: &SecurityConfig) -> Result<()> {
+        // Trailing separators would keep `/usr/` from matching `/usr`
+        let normalized: PathBuf = path.components().collect();
+        if Self::denied_roots(&security.denied_roots)?.is_match(&normalized) {
+            return Err(ClearModelError::security(
+                format!("Attempted to delete critical system path: {}", path.display())
+            ));
+        }
         
         let path_str = path.to_string_lossy();
-        for dangerous in &dangerous_paths {
-            if path_str.starts_with(dangerous) && path_str.len() <= dangerous.len() + 1 {
-                return Err(ClearModelError::security(
-                    format!("Attempted to delete critical system path: {}", path_str)
-                ));
-            }
+        if let Some(reason) = Self::windows_system_path(&path_str) {
+            return Err(ClearModelError::security(
+                format!("Attempted to delete critical Windows path ({}): {}", reason, path_str)
+            ));
         }
         
-        // Additional checks for macOS system paths
-        if cfg!(target_os = "macos") {
-            let macos_dangerous = [
-                "/System",
-                "/Library/System",
-                "/Applications/Utilities",
-            ];
-            
-            for dangerous in &macos_dangerous {
-                if path_str.starts_with(dangerous) {
-                    return Err(ClearModelError::security(
-                        format!("Attempted to delete critical macOS system path: {}", path_str)
-                    ));
-                }
-            }
+        Self::check_allowed_roots(path, &security.allowed_roots)
+    }
+    
+    /// Compile `security.denied_roots`
+    pub fn denied_roots(patterns: &[String]) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern).literal_separator(true).build()
+                .map_err(|e| ClearModelError::configuration(
+                    format!("Invalid security.denied_roots pattern {:?}: {}", pattern, e)
+                ))?;
+            builder.add(glob);
+        }
+        builder.build().map_err(|e| ClearModelError::configuration(
+            format!("Invalid security.denied_roots: {}", e)
+        ))
+    }
+    
+    /// Refuse a path outside `security.allowed_roots`, when any are configured
+    ///
+    /// Paths are compared as given first; a symlinked cache directory is then judged by where
+    /// it leads, and a file by the directory it is in.
+    pub fn check_allowed_roots(path: &Path, allowed_roots: &[PathBuf]) -> Result<()> {
+        if allowed_roots.is_empty() || allowed_roots.iter().any(|root| path.starts_with(root)) {
+            return Ok(());
+        }
+        
+        let is_dir = std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir());
+        let resolved = if is_dir {
+            path.canonicalize().ok()
+        } else {
+            path.parent()
+                .and_then(|parent| parent.canonicalize().ok())
+                .zip(path.file_name())
+                .map(|(parent, name)| parent.join(name))
+        };
+        let allowed = resolved.is_some_and(|resolved| allowed_roots.iter().any(|root| {
+            resolved.starts_with(root) || root.canonicalize().is_ok_and(|root| resolved.starts_with(root))
+        }));
+        if allowed {
+            return Ok(());
+        }
+        
+        Err(ClearModelError::security(format!(
+            "Refusing to delete {}: it is outside security.allowed_roots",
+            path.display()
+        )))
+    }
+    
+    /// Whether this user may remove a file without escalating privileges
+    ///
+    /// The file must be theirs (or owned by one of `security.allowed_uids`, or anyone's with
+    /// `security.include_other_users`) and its directory writable by them. In a sticky
+    /// directory such as `/tmp` they must also own the file or the directory.
+    #[cfg(unix)]
+    pub fn removable_without_privileges(path: &Path, metadata: &std::fs::Metadata, security: &SecurityConfig) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::fs::MetadataExt;
+        
+        // SAFETY: geteuid has no preconditions and cannot fail
+        let euid = unsafe { libc::geteuid() };
+        let owner = metadata.uid();
+        if owner != euid && !security.include_other_users && !security.allowed_uids.contains(&owner) {
+            debug!("{:?} belongs to uid {}, not to this user", path, owner);
+            return false;
         }
         
+        let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) else {
+            return true;
+        };
+        let Ok(parent_c) = std::ffi::CString::new(parent.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: `parent_c` is NUL-terminated; AT_EACCESS checks with the effective ids
+        if unsafe { libc::faccessat(libc::AT_FDCWD, parent_c.as_ptr(), libc::W_OK | libc::X_OK, libc::AT_EACCESS) } != 0 {
+            debug!("{:?} is in a directory this user cannot write to", path);
+            return false;
+        }
+        
+        // S_ISVTX: only owners may remove entries from the directory
+        let sticky = std::fs::metadata(parent).is_ok_and(|parent| {
+            parent.mode() & 0o1000 != 0 && parent.uid() != euid
+        });
+        euid == 0 || !sticky || owner == euid
+    }
+    
+    #[cfg(not(unix))]
+    pub fn removable_without_privileges(_path: &Path, _metadata: &std::fs::Metadata, _security: &SecurityConfig) -> bool {
+        true
+    }
+    
+    /// Refuse another user's file unless `security.include_other_users` or `allowed_uids` admits it,
+    /// even when it is removed with administrator rights
+    #[cfg(unix)]
+    pub fn check_owner(path: &Path, metadata: &std::fs::Metadata, security: &SecurityConfig) -> Result<()> {
+        use std::os::unix::fs::MetadataExt;
+        
+        // SAFETY: geteuid has no preconditions and cannot fail
+        let euid = unsafe { libc::geteuid() };
+        let owner = metadata.uid();
+        if owner == euid || security.include_other_users || security.allowed_uids.contains(&owner) {
+            return Ok(());
+        }
+        Err(ClearModelError::security(format!(
+            "Refusing to delete {}: it belongs to uid {}",
+            path.display(),
+            owner
+        )).with_hint("Pass --include-other-users to remove other users' files"))
+    }
+    
+    #[cfg(not(unix))]
+    pub fn check_owner(_path: &Path, _metadata: &std::fs::Metadata, _security: &SecurityConfig) -> Result<()> {
         Ok(())
     }
     
+    /// Why a Windows path must never be deleted, if it is a protected one
+    ///
+    /// Only paths with a drive letter or UNC prefix are considered (and, on Windows,
+    /// rooted paths like `\Windows`), so Unix paths are unaffected. Long-path
+    /// (`\\?\`) prefixes are stripped, and on Windows forward slashes are accepted.
+    fn windows_system_path(path: &str) -> Option<&'static str> {
+        let normalized = if cfg!(windows) { path.replace('/', "\\") } else { path.to_string() };
+        let normalized = normalized.to_lowercase();
+        let normalized = normalized.trim_end_matches('\\');
+        
+        let rest = if let Some(unc) = normalized.strip_prefix(r"\\?\unc\") {
+            Self::unc_volume_relative(unc)?
+        } else if let Some(local) = normalized.strip_prefix(r"\\?\") {
+            Self::drive_relative(local)?
+        } else if let Some(unc) = normalized.strip_prefix(r"\\") {
+            Self::unc_volume_relative(unc)?
+        } else {
+            Self::drive_relative(normalized)?
+        };
+        
+        if rest.is_empty() {
+            return Some("volume root");
+        }
+        
+        // These trees are protected along with everything below them
+        let protected_trees = [
+            ("windows", "Windows directory"),
+            ("program files", "Program Files"),
+            ("program files (x86)", "Program Files"),
+            ("programdata", "ProgramData"),
+            ("system volume information", "System Volume Information"),
+            ("$recycle.bin", "Recycle Bin"),
+            ("recovery", "recovery partition data"),
+            ("boot", "boot files"),
+        ];
+        for (tree, reason) in &protected_trees {
+            if rest == *tree || rest.starts_with(&format!("{}\\", tree)) {
+                return Some(reason);
+            }
+        }
+        
+        // C:\Users and each profile root are protected, but not what is inside a profile
+        let mut components = rest.split('\\');
+        if components.next() == Some("users") && components.nth(1).is_none() {
+            return Some("user profile");
+        }
+        
+        None
+    }
+    
+    /// `C:\rest` -> `rest`; on Windows also `\rest` (relative to the current drive)
+    fn drive_relative(path: &str) -> Option<&str> {
+        let bytes = path.as_bytes();
+        if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            Some(path[2..].trim_start_matches('\\'))
+        } else if cfg!(windows) && path.starts_with('\\') {
+            Some(path.trim_start_matches('\\'))
+        } else {
+            None
+        }
+    }
+    
+    /// `server\share` (a volume root) -> `""`, `server\c$\rest` (an administrative
+    /// share exposing a whole drive) -> `rest`; paths inside ordinary shares -> `None`
+    fn unc_volume_relative(path: &str) -> Option<&str> {
+        let mut parts = path.splitn(3, '\\');
+        let _server = parts.next();
+        match (parts.next(), parts.next()) {
+            (None, _) | (Some(_), None) => Some(""),
+            (Some(share), Some(rest)) if share.len() == 2 && share.ends_with('$') => Some(rest),
+            _ => None,
+        }
+    }
+    
     /// Validate that a path is within expected cache directories
     pub fn validate_cache_path(path: &Path) -> Result<()> {
         let path_str = path.to_string_lossy().to_lowercase();
@@ -225,6 +389,39 @@ impl SecurityManager {
         
         Ok(())
     }
+    
+    /// Refuse to clean caches that may be the host's, seen through a container bind mount
+    ///
+    /// A cache that looks disposable inside the container can be the only copy on the
+    /// host. Such paths are only cleaned when `allowed` is set or `confirm` agrees.
+    pub fn check_container_mounts(
+        layout: &ContainerLayout,
+        paths: &[PathBuf],
+        allowed: bool,
+        confirm: impl FnOnce(&[PathBuf]) -> bool,
+    ) -> Result<()> {
+        let on_host: Vec<PathBuf> = paths.iter()
+            .filter(|path| layout.host_mounts.iter().any(|mount| path.starts_with(mount)))
+            .cloned()
+            .collect();
+        if on_host.is_empty() {
+            return Ok(());
+        }
+        
+        warn!(
+            "Running in a container and these caches are on host-mounted directories ({:?}): {:?}",
+            layout.host_mounts,
+            on_host
+        );
+        if allowed || confirm(&on_host) {
+            return Ok(());
+        }
+        
+        Err(ClearModelError::security(format!(
+            "Refusing to clean {} cache(s) on directories mounted from the host; set security.allow_container_host_mounts = true if they are meant to be cleaned",
+            on_host.len()
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -275,4 +472,109 @@ mod tests {
             assert!(result.is_ok(), "Should accept valid path: {}", valid);
         }
     }
+    
+    #[test]
+    fn test_host_mounted_caches_need_confirmation() {
+        let layout = ContainerLayout {
+            root_fs_type: "overlay".to_string(),
+            host_mounts: vec![PathBuf::from("/home/me")],
+        };
+        let container_cache = vec![PathBuf::from("/root/.cache/torch")];
+        let host_cache = vec![PathBuf::from("/home/me/.cache/huggingface")];
+        
+        assert!(SecurityManager::check_container_mounts(&layout, &container_cache, false, |_| false).is_ok());
+        assert!(SecurityManager::check_container_mounts(&layout, &host_cache, false, |_| false).is_err());
+        assert!(SecurityManager::check_container_mounts(&layout, &host_cache, false, |paths| paths == host_cache.as_slice()).is_ok());
+        assert!(SecurityManager::check_container_mounts(&layout, &host_cache, true, |_| false).is_ok());
+    }
+    
+    #[test]
+    fn test_denied_and_allowed_roots_are_configurable() {
+        let defaults = SecurityConfig::default();
+        for path in ["/", "/usr", "/usr/", "/var/log"] {
+            assert!(SecurityManager::validate_deletion_safety(Path::new(path), &defaults).is_err(), "Should protect {}", path);
+        }
+        assert!(SecurityManager::validate_deletion_safety(Path::new("/usr/local/share/models"), &defaults).is_ok());
+        
+        let temp_dir = TempDir::new().unwrap();
+        let allowed = temp_dir.path().join("scratch");
+        fs::create_dir_all(allowed.join("hf")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("home")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(allowed.join("hf"), temp_dir.path().join("home/hf-link")).unwrap();
+        
+        let security = SecurityConfig {
+            denied_roots: vec!["/srv/shared/**".to_string()],
+            allowed_roots: vec![allowed.clone()],
+            ..SecurityConfig::default()
+        };
+        assert!(SecurityManager::validate_deletion_safety(Path::new("/srv/shared/team/cache"), &security).unwrap_err().to_string().contains("critical"));
+        assert!(SecurityManager::validate_deletion_safety(&allowed.join("hf"), &security).is_ok());
+        assert!(SecurityManager::validate_deletion_safety(&allowed.join("hf/blob"), &security).is_ok());
+        assert!(SecurityManager::validate_deletion_safety(&temp_dir.path().join("home"), &security).unwrap_err().to_string().contains("allowed_roots"));
+        #[cfg(unix)]
+        assert!(SecurityManager::check_allowed_roots(&temp_dir.path().join("home/hf-link/blob"), &security.allowed_roots).is_ok());
+        assert!(SecurityManager::denied_roots(&["/srv/[".to_string()]).is_err());
+    }
+    
+    #[test]
+    fn test_windows_system_paths_are_protected() {
+        let protected = [
+            r"C:\",
+            r"c:\Windows\System32",
+            r"C:\Program Files (x86)\Steam",
+            r"D:\ProgramData",
+            r"C:\Users",
+            r"C:\Users\alex\",
+            r"\\?\C:\Windows",
+            r"\\fileserver\models",
+            r"\\fileserver\c$\Windows\Temp",
+            r"\\?\UNC\fileserver\c$",
+        ];
+        for path in &protected {
+            assert!(SecurityManager::validate_deletion_safety(Path::new(path), &SecurityConfig::default()).is_err(), "Should protect {}", path);
+        }
+        
+        let allowed = [
+            r"C:\Users\alex\.cache\huggingface",
+            r"\\?\C:\Users\alex\AppData\Local\torch",
+            r"\\fileserver\models\hub",
+            r"C:\Windowsill",
+            "/home/alex/.cache/torch",
+        ];
+        for path in &allowed {
+            assert!(SecurityManager::validate_deletion_safety(Path::new(path), &SecurityConfig::default()).is_ok(), "Should allow {}", path);
+        }
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_removal_needs_ownership_and_a_writable_directory() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("model.bin");
+        fs::write(&file, b"x").unwrap();
+        let mut security = SecurityConfig::default();
+        let removable = |security: &SecurityConfig| {
+            SecurityManager::removable_without_privileges(&file, &fs::metadata(&file).unwrap(), security)
+        };
+        assert!(removable(&security));
+        
+        // SAFETY: geteuid has no preconditions
+        if unsafe { libc::geteuid() } == 0 {
+            // root can write anywhere, so only ownership is left to check
+            std::os::unix::fs::chown(&file, Some(4242), None).unwrap();
+            assert!(!removable(&security));
+            security.allowed_uids = vec![4242];
+            assert!(removable(&security));
+            security.allowed_uids.clear();
+            security.include_other_users = true;
+            assert!(removable(&security));
+        } else {
+            fs::set_permissions(temp_dir.path(), fs::Permissions::from_mode(0o555)).unwrap();
+            assert!(!removable(&security));
+            fs::set_permissions(temp_dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use crate::audit;
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::journal;
+
+/// Directory in each shared cache root holding the locks and the manifest; never cleaned
+pub const SHARED_DIR: &str = ".clearmodel";
+
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_LOCK: &str = "manifest.lock";
+const LOCKS: &str = "locks";
+
+/// Finished runs the manifest remembers
+const MAX_RECENT: usize = 20;
+
+/// Runs registered longer ago than this are assumed to have died without saying so
+const STALE_SECS: u64 = 7 * 86_400;
+
+/// HuggingFace hub directories holding one model, dataset or space each
+const REPO_PREFIXES: [&str; 3] = ["models--", "datasets--", "spaces--"];
+
+/// A clearmodel run cleaning a shared cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instance {
+    pub run_id: String,
+    pub user: String,
+    pub host: String,
+    pub pid: u32,
+    
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    
+    /// Model directories it locked and cleaned
+    #[serde(default)]
+    pub models_cleaned: usize,
+}
+
+impl Instance {
+    fn is(&self, other: &Instance) -> bool {
+        self.run_id == other.run_id
+    }
+}
+
+/// Who is cleaning a shared cache root and who cleaned it last
+/// (`<root>/.clearmodel/manifest.json`)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub active: Vec<Instance>,
+    
+    /// Newest first
+    pub recent: Vec<Instance>,
+}
+
+/// Cooperation with other users' clearmodel instances on shared caches (`shared.enabled`)
+///
+/// Before anything is removed from a model directory (a HuggingFace repository, or a
+/// top-level entry of the root) its advisory lock in `<root>/.clearmodel/locks` is
+/// taken and held until the run ends; a directory another instance holds is skipped.
+/// Files owned by other users are left alone unless `shared.all_users` is set by root.
+/// Runs register in the root's manifest so each can see who else is cleaning.
+pub struct SharedCache {
+    roots: Vec<PathBuf>,
+    all_users: bool,
+    
+    /// Only look at locks, without taking them or registering
+    dry_run: bool,
+    me: Instance,
+    
+    /// Roots this run registered in
+    registered: Vec<PathBuf>,
+    
+    /// Whether each model directory seen was free to clean; the locks of those that were are in `held`
+    units: Mutex<HashMap<PathBuf, bool>>,
+    held: Mutex<Vec<File>>,
+}
+
+impl SharedCache {
+    pub fn open(config: &ClearModelConfig, dry_run: bool) -> Result<Self> {
+        let shared = &config.shared;
+        if shared.all_users && !is_root() {
+            return Err(ClearModelError::security(
+                "--all-users removes other users' files from shared caches and must be run as root".to_string()
+            ));
+        }
+        
+        let roots = if shared.paths.is_empty() { &config.cache_paths } else { &shared.paths };
+        let mut cache = Self {
+            roots: roots.clone(),
+            all_users: shared.all_users,
+            dry_run,
+            me: Instance {
+                run_id: journal::new_run_id(),
+                user: audit::current_user().0,
+                host: sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string()),
+                pid: std::process::id(),
+                started_at: now(),
+                finished_at: None,
+                models_cleaned: 0,
+            },
+            registered: Vec::new(),
+            units: Mutex::new(HashMap::new()),
+            held: Mutex::new(Vec::new()),
+        };
+        if dry_run {
+            return Ok(cache);
+        }
+        
+        for root in cache.roots.iter().filter(|root| root.is_dir()) {
+            let manifest = update_manifest(root, |manifest| manifest.active.push(cache.me.clone()))
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to register in the shared cache manifest: {}", e),
+                    Some(root.join(SHARED_DIR).join(MANIFEST))
+                ))?;
+            for other in manifest.active.iter().filter(|other| !other.is(&cache.me)) {
+                info!("{} (pid {} on {}) is also cleaning {:?}", other.user, other.pid, other.host, root);
+            }
+            cache.registered.push(root.clone());
+        }
+        Ok(cache)
+    }
+    
+    /// Whether this run may remove `path`: it is not another user's (unless `all_users`)
+    /// and its model directory's lock is this run's
+    ///
+    /// Paths outside the shared roots are not this mode's concern.
+    pub fn admits(&self, path: &Path, metadata: &std::fs::Metadata) -> bool {
+        let Some(root) = self.root_of(path) else {
+            return true;
+        };
+        let Some(unit) = model_dir(root, path) else {
+            return true;
+        };
+        if unit.file_name().is_some_and(|name| name == SHARED_DIR) && unit.parent() == Some(root) {
+            return false;
+        }
+        if !self.all_users && !owned_by_me(metadata) {
+            debug!("{:?} belongs to another user of the shared cache", path);
+            return false;
+        }
+        
+        let mut units = self.units.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(free) = units.get(&unit) {
+            return *free;
+        }
+        let free = self.claim(root, &unit).unwrap_or_else(|e| {
+            warn!("Cannot lock {:?} in the shared cache ({}); leaving it alone", unit, e);
+            false
+        });
+        units.insert(unit, free);
+        free
+    }
+    
+    /// The innermost shared root `path` is in
+    fn root_of(&self, path: &Path) -> Option<&Path> {
+        self.roots.iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(PathBuf::as_path)
+    }
+    
+    /// Take the lock of a model directory, or in a dry run check nobody holds it
+    fn claim(&self, root: &Path, unit: &Path) -> io::Result<bool> {
+        let path = lock_path(root, unit);
+        if self.dry_run {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+                Err(e) => return Err(e),
+            };
+            return match file.try_lock_shared() {
+                Ok(()) => Ok(true),
+                Err(TryLockError::WouldBlock) => {
+                    info!("{:?} is being cleaned by another instance; skipping it", unit);
+                    Ok(false)
+                }
+                Err(TryLockError::Error(e)) => Err(e),
+            };
+        }
+        
+        let locks = root.join(SHARED_DIR).join(LOCKS);
+        if !locks.exists() {
+            std::fs::create_dir_all(&locks)?;
+            make_group_writable(&locks, 0o2775);
+        }
+        let mut file = open_shared(&path)?;
+        match file.try_lock() {
+            Ok(()) => {
+                file.set_len(0)?;
+                write!(file, "{} (pid {} on {})", self.me.user, self.me.pid, self.me.host)?;
+                self.held.lock().unwrap_or_else(|e| e.into_inner()).push(file);
+                Ok(true)
+            }
+            Err(TryLockError::WouldBlock) => {
+                let mut holder = String::new();
+                let _ = file.read_to_string(&mut holder);
+                info!("{:?} is being cleaned by {}; skipping it", unit, holder.trim());
+                Ok(false)
+            }
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+impl Drop for SharedCache {
+    /// Move this run from the active list to the recent one; the locks go with the files
+    fn drop(&mut self) {
+        let units = self.units.lock().unwrap_or_else(|e| e.into_inner());
+        for root in &self.registered {
+            let mut finished = self.me.clone();
+            finished.finished_at = Some(now());
+            finished.models_cleaned = units.iter()
+                .filter(|(unit, free)| **free && self.root_of(unit) == Some(root))
+                .count();
+            let result = update_manifest(root, |manifest| {
+                manifest.active.retain(|instance| !instance.is(&self.me));
+                manifest.recent.insert(0, finished);
+                manifest.recent.truncate(MAX_RECENT);
+            });
+            if let Err(e) = result {
+                warn!("Failed to update the shared cache manifest in {:?}: {}", root, e);
+            }
+        }
+    }
+}
+
+/// The model directory a path belongs to: the HuggingFace repository directory it is
+/// in, or else the top-level entry of the root
+pub(crate) fn model_dir(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut unit = root.to_path_buf();
+    let mut top = None;
+    for component in relative.components() {
+        unit.push(component);
+        if component.as_os_str().to_str().is_some_and(|name| REPO_PREFIXES.iter().any(|prefix| name.starts_with(prefix))) {
+            return Some(unit);
+        }
+        top.get_or_insert_with(|| unit.clone());
+    }
+    top
+}
+
+/// `<root>/.clearmodel/locks/<hash of the directory's relative path>.lock`
+fn lock_path(root: &Path, unit: &Path) -> PathBuf {
+    let relative = unit.strip_prefix(root).unwrap_or(unit);
+    let hash = blake3::hash(relative.to_string_lossy().as_bytes()).to_hex();
+    root.join(SHARED_DIR).join(LOCKS).join(format!("{}.lock", &hash[..16]))
+}
+
+/// Read, change and write back a root's manifest while holding its lock, dropping
+/// instances that are gone
+fn update_manifest(root: &Path, change: impl FnOnce(&mut Manifest)) -> io::Result<Manifest> {
+    let dir = root.join(SHARED_DIR);
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir)?;
+        // setgid, so everything in it stays in the cache's group
+        make_group_writable(&dir, 0o2775);
+    }
+    let lock = open_shared(&dir.join(MANIFEST_LOCK))?;
+    lock.lock()?;
+    
+    let path = dir.join(MANIFEST);
+    let mut manifest: Manifest = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            warn!("Starting {:?} afresh: {}", path, e);
+            Manifest::default()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Manifest::default(),
+        Err(e) => return Err(e),
+    };
+    let now = now();
+    manifest.active.retain(|instance| now.saturating_sub(instance.started_at) < STALE_SECS && !exited(instance));
+    change(&mut manifest);
+    
+    let temp = dir.join(format!("{}.{}.tmp", MANIFEST, std::process::id()));
+    let mut file = open_shared(&temp)?;
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    serde_json::to_writer_pretty(&mut file, &manifest)?;
+    file.write_all(b"\n")?;
+    file.sync_all()?;
+    std::fs::rename(&temp, &path)?;
+    Ok(manifest)
+}
+
+/// Open (creating) a file every member of the cache's group may lock and rewrite
+fn open_shared(path: &Path) -> io::Result<File> {
+    let created = !path.exists();
+    let file = OpenOptions::new().create(true).truncate(false).read(true).write(true).open(path)?;
+    if created {
+        make_group_writable(path, 0o664);
+    }
+    Ok(file)
+}
+
+/// Set `mode` regardless of the umask; only the creator can, and others need not
+#[cfg(unix)]
+fn make_group_writable(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    
+    if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)) {
+        debug!("Cannot make {:?} group-writable: {}", path, e);
+    }
+}
+
+#[cfg(not(unix))]
+fn make_group_writable(_path: &Path, _mode: u32) {}
+
+/// Whether an instance on this host has exited without deregistering
+fn exited(instance: &Instance) -> bool {
+    let here = sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string());
+    instance.host == here && !process_alive(instance.pid)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks the process exists; EPERM means it does but is not ours
+    let signalled = unsafe { libc::kill(pid as libc::pid_t, 0) == 0 };
+    signalled || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn owned_by_me(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    
+    // SAFETY: geteuid has no preconditions and cannot fail
+    metadata.uid() == unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+fn owned_by_me(_metadata: &std::fs::Metadata) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_instances_skip_model_directories_another_holds() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let blob = root.join("hub/models--org--a/blobs/abc");
+        let other = root.join("hub/models--org--b/blobs/def");
+        for file in [&blob, &other] {
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, b"weights").unwrap();
+        }
+        assert_eq!(model_dir(&root, &blob), Some(root.join("hub/models--org--a")));
+        assert_eq!(model_dir(&root, &root.join("stray.bin")), Some(root.join("stray.bin")));
+        
+        let mut config = ClearModelConfig { cache_paths: vec![root.clone()], ..Default::default() };
+        config.shared.enabled = true;
+        let metadata = std::fs::metadata(&blob).unwrap();
+        let first = SharedCache::open(&config, false).unwrap();
+        let second = SharedCache::open(&config, false).unwrap();
+        assert!(first.admits(&blob, &metadata));
+        assert!(!second.admits(&blob, &metadata));
+        assert!(second.admits(&other, &metadata));
+        assert!(!first.admits(&root.join(SHARED_DIR).join(MANIFEST), &metadata));
+        
+        drop(first);
+        let manifest: Manifest = serde_json::from_slice(&std::fs::read(root.join(SHARED_DIR).join(MANIFEST)).unwrap()).unwrap();
+        assert_eq!(manifest.active.len(), 1);
+        assert_eq!(manifest.recent[0].models_cleaned, 1);
+        // The lock went with the first instance
+        let third = SharedCache::open(&config, false).unwrap();
+        assert!(third.admits(&blob, &metadata));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,129 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, Metadata};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// How files the policy selects are removed (unless `quarantine` moves them aside)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionMode {
+    /// Unlink the file
+    #[default]
+    Delete,
+    
+    /// Overwrite the contents with random data and flush them before unlinking, for
+    /// confidential weights; see [`CAVEAT`] for where this does not reach
+    Shred,
+}
+
+/// Where overwriting in place does not destroy the old data, logged when shredding starts
+pub const CAVEAT: &str = "Shredding overwrites files in place, which does not guarantee the old data is unrecoverable on SSDs \
+    (wear levelling remaps writes), copy-on-write filesystems (btrfs, ZFS, APFS, bcachefs write new blocks), \
+    or in snapshots and backups; use full-disk encryption where that matters";
+
+/// Size of each chunk of random data written
+const CHUNK: usize = 1024 * 1024;
+
+/// Overwrite the first `len` bytes of `file` with random data and flush them to the device
+pub fn overwrite(mut file: &File, len: u64) -> io::Result<()> {
+    let mut key = [0u8; 32];
+    getrandom::getrandom(&mut key).map_err(|e| io::Error::other(e.to_string()))?;
+    // A keyed BLAKE3 stream is as unpredictable as the key and much faster than the OS source
+    let mut stream = blake3::Hasher::new_keyed(&key).finalize_xof();
+    
+    file.seek(SeekFrom::Start(0))?;
+    let mut buffer = vec![0u8; CHUNK];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(CHUNK as u64) as usize;
+        stream.fill(&mut buffer[..chunk]);
+        file.write_all(&buffer[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+}
+
+/// Whether other hard links still reach the file's contents, so overwriting them would
+/// corrupt those paths without making anything unrecoverable
+pub fn has_other_links(metadata: &Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink() > 1
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// The copy-on-write filesystem `path` is on, by name; `None` when overwriting reaches the old blocks
+#[cfg(target_os = "linux")]
+pub fn copy_on_write_filesystem(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid buffer for the call to fill
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // Every magic number fits in 32 bits, whatever the width of `f_type`
+    match stat.f_type as u32 {
+        0x9123_683E => Some("btrfs"),
+        0x2FC1_2FC1 => Some("zfs"),
+        0xCA45_1A4E => Some("bcachefs"),
+        _ => None,
+    }
+}
+
+/// The copy-on-write filesystem `path` is on, by name; `None` when overwriting reaches the old blocks
+#[cfg(target_os = "macos")]
+pub fn copy_on_write_filesystem(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid buffer for the call to fill
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // SAFETY: the kernel NUL-terminates the filesystem type name
+    let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    match name.to_bytes() {
+        b"apfs" => Some("apfs"),
+        b"zfs" => Some("zfs"),
+        _ => None,
+    }
+}
+
+/// The copy-on-write filesystem `path` is on, by name; `None` when overwriting reaches the old blocks
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn copy_on_write_filesystem(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_overwrite_replaces_every_byte() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("adapter.safetensors");
+        let secret = vec![0x5Au8; CHUNK + 17];
+        std::fs::write(&path, &secret).unwrap();
+        
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        overwrite(&file, secret.len() as u64).unwrap();
+        
+        let overwritten = std::fs::read(&path).unwrap();
+        assert_eq!(overwritten.len(), secret.len());
+        // Random data matches the old byte about once in 256
+        let unchanged = overwritten.iter().filter(|byte| **byte == 0x5A).count();
+        assert!(unchanged < secret.len() / 64);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,337 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+use crate::allocation::{SizeBasis, UsageCounter};
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::state;
+
+/// Bumped whenever the on-disk layout changes; older indexes are rebuilt
+const INDEX_VERSION: u32 = 2;
+
+/// What the index knows about a single directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DirRecord {
+    /// Directory mtime in nanoseconds; it changes whenever an entry is added, removed or renamed
+    mtime_ns: u64,
+    
+    /// Bytes in files directly inside the directory
+    bytes: u64,
+    
+    /// Bytes allocated to those files, counting links and clones within the directory once
+    allocated: u64,
+    
+    /// Files directly inside the directory
+    files: u64,
+    
+    /// Names of subdirectories
+    subdirs: Vec<String>,
+}
+
+/// Size of a cache root as of its last refresh
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RootSummary {
+    pub bytes: u64,
+    pub allocated_bytes: u64,
+    pub files: u64,
+    pub dirs: u64,
+    
+    /// Seconds since the Unix epoch
+    pub refreshed_at: u64,
+}
+
+impl RootSummary {
+    /// Size of the root under `basis`
+    pub fn size(&self, basis: SizeBasis) -> u64 {
+        match basis {
+            SizeBasis::Allocated => self.allocated_bytes,
+            SizeBasis::Apparent => self.bytes,
+        }
+    }
+}
+
+/// How much work a refresh did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefreshStats {
+    /// Directories whose contents were listed again
+    pub dirs_scanned: u64,
+    
+    /// Directories whose recorded contents were reused because their mtime was unchanged
+    pub dirs_reused: u64,
+}
+
+/// Pause between batches of directories so a refresh stays in the background
+#[derive(Debug, Clone, Copy)]
+pub struct Throttle {
+    pub batch_dirs: u64,
+    pub pause: Duration,
+}
+
+/// On-disk record of cache directory sizes
+///
+/// Directories are only listed again when their mtime changes. Cache files are
+/// written once and renamed into place, so an unchanged mtime means unchanged
+/// contents; a file rewritten in place is not noticed until its directory changes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SizeIndex {
+    roots: BTreeMap<PathBuf, RootSummary>,
+    dirs: HashMap<PathBuf, DirRecord>,
+    
+    /// Sizes `subtree` and `largest_children` report
+    #[serde(skip)]
+    basis: SizeBasis,
+    
+    /// Whether listings look for extents shared by clones (see `UsageCounter`)
+    #[serde(skip)]
+    clones: bool,
+}
+
+impl SizeIndex {
+    /// Load an index, starting empty if there is none yet (or it cannot be read)
+    pub fn load(path: &Path) -> Self {
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(_) => return Self::default(),
+        };
+        
+        match bincode::deserialize::<(u32, SizeIndex)>(&content) {
+            Ok((INDEX_VERSION, index)) => index,
+            Ok((version, _)) => {
+                debug!("Rebuilding size index {:?} written by format version {}", path, version);
+                Self::default()
+            }
+            Err(e) => {
+                debug!("Discarding unreadable size index {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+    
+    /// Report and record sizes as the configuration asks (`size_basis`, `usage.track`)
+    pub fn sized_for(mut self, config: &ClearModelConfig) -> Self {
+        self.basis = config.size_basis;
+        self.clones = !config.usage.track;
+        self
+    }
+    
+    /// Write the index atomically
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to create state directory: {}", e),
+                    Some(parent.to_path_buf())
+                ))?;
+        }
+        
+        let content = bincode::serialize(&(INDEX_VERSION, self))
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to encode size index: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, content)?;
+        std::fs::rename(&temp, path)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to save size index: {}", e),
+                Some(path.to_path_buf())
+            ))
+    }
+    
+    /// Last recorded size of a root
+    pub fn summary(&self, root: &Path) -> Option<&RootSummary> {
+        self.roots.get(root)
+    }
+    
+    /// Bytes and files under `dir`, from the records of the last refresh
+    pub fn subtree(&self, dir: &Path) -> (u64, u64) {
+        let Some(record) = self.dirs.get(dir) else {
+            return (0, 0);
+        };
+        
+        record.subdirs.iter()
+            .map(|name| self.subtree(&dir.join(name)))
+            .fold((record.size(self.basis), record.files), |(bytes, files), (sub_bytes, sub_files)| (bytes + sub_bytes, files + sub_files))
+    }
+    
+    /// The `limit` largest subdirectories of `dir` with their recorded sizes, largest first
+    pub fn largest_children(&self, dir: &Path, limit: usize) -> Vec<(PathBuf, u64)> {
+        let Some(record) = self.dirs.get(dir) else {
+            return Vec::new();
+        };
+        
+        let mut children: Vec<(PathBuf, u64)> = record.subdirs.iter()
+            .map(|name| {
+                let child = dir.join(name);
+                let (bytes, _) = self.subtree(&child);
+                (child, bytes)
+            })
+            .collect();
+        children.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+        children.truncate(limit);
+        children
+    }
+    
+    /// Bring a root up to date, re-listing only directories that changed
+    pub fn refresh(&mut self, root: &Path, throttle: Option<Throttle>) -> RefreshStats {
+        let mut stats = RefreshStats::default();
+        let mut summary = RootSummary::default();
+        let mut stack = vec![root.to_path_buf()];
+        let mut visited = HashSet::new();
+        
+        while let Some(dir) = stack.pop() {
+            let Some(mtime_ns) = dir_mtime(&dir) else {
+                continue;
+            };
+            
+            let record = match self.dirs.get(&dir) {
+                Some(record) if record.mtime_ns == mtime_ns => {
+                    stats.dirs_reused += 1;
+                    record.clone()
+                }
+                _ => {
+                    stats.dirs_scanned += 1;
+                    let record = list_dir(&dir, mtime_ns, self.clones);
+                    self.dirs.insert(dir.clone(), record.clone());
+                    record
+                }
+            };
+            
+            summary.bytes += record.bytes;
+            summary.allocated_bytes += record.allocated;
+            summary.files += record.files;
+            summary.dirs += 1;
+            stack.extend(record.subdirs.iter().map(|name| dir.join(name)));
+            visited.insert(dir);
+            
+            if let Some(throttle) = throttle {
+                if throttle.batch_dirs > 0 && stats.dirs_scanned > 0 && stats.dirs_scanned % throttle.batch_dirs == 0 {
+                    std::thread::sleep(throttle.pause);
+                }
+            }
+        }
+        
+        // Forget directories under this root that no longer exist
+        self.dirs.retain(|dir, _| !dir.starts_with(root) || visited.contains(dir));
+        
+        summary.refreshed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        info!(
+            "Indexed {:?}: {:.2} MB in {} files ({} directories listed, {} unchanged)",
+            root,
+            summary.size(self.basis) as f64 / 1_048_576.0,
+            summary.files,
+            stats.dirs_scanned,
+            stats.dirs_reused
+        );
+        self.roots.insert(root.to_path_buf(), summary);
+        
+        stats
+    }
+}
+
+/// Location of the size index (`size-index.bin` in the state directory)
+pub fn index_path() -> Option<PathBuf> {
+    state::path(state::SIZE_INDEX)
+}
+
+fn dir_mtime(dir: &Path) -> Option<u64> {
+    // Symlinked directories are not followed, matching the cleaner's default
+    let metadata = std::fs::symlink_metadata(dir).ok()?;
+    if !metadata.is_dir() {
+        return None;
+    }
+    
+    let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(u64::try_from(mtime.as_nanos()).unwrap_or(u64::MAX))
+}
+
+impl DirRecord {
+    fn size(&self, basis: SizeBasis) -> u64 {
+        match basis {
+            SizeBasis::Allocated => self.allocated,
+            SizeBasis::Apparent => self.bytes,
+        }
+    }
+}
+
+fn list_dir(dir: &Path, mtime_ns: u64, clones: bool) -> DirRecord {
+    let mut record = DirRecord {
+        mtime_ns,
+        bytes: 0,
+        allocated: 0,
+        files: 0,
+        subdirs: Vec::new(),
+    };
+    let mut allocated = UsageCounter::new(SizeBasis::Allocated, clones);
+    
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        debug!("Cannot list {:?}; indexing it as empty", dir);
+        return record;
+    };
+    
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        
+        if file_type.is_dir() {
+            if let Some(name) = entry.file_name().to_str() {
+                record.subdirs.push(name.to_string());
+            }
+        } else if file_type.is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                record.bytes += metadata.len();
+                allocated.add(&entry.path(), &metadata);
+            }
+            record.files += 1;
+        }
+    }
+    
+    record.allocated = allocated.total();
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_refresh_reuses_unchanged_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("hub");
+        fs::create_dir_all(root.join("models--a/blobs")).unwrap();
+        fs::create_dir_all(root.join("models--b/blobs")).unwrap();
+        fs::write(root.join("models--a/blobs/1"), vec![0u8; 100]).unwrap();
+        fs::write(root.join("models--b/blobs/2"), vec![0u8; 50]).unwrap();
+        
+        let mut index = SizeIndex::default();
+        let stats = index.refresh(&root, None);
+        assert_eq!(stats.dirs_scanned, 5);
+        assert_eq!(index.summary(&root).unwrap().bytes, 150);
+        
+        let path = temp_dir.path().join("index.bin");
+        index.save(&path).unwrap();
+        let apparent = ClearModelConfig { size_basis: SizeBasis::Apparent, ..ClearModelConfig::default() };
+        let mut index = SizeIndex::load(&path).sized_for(&apparent);
+        
+        fs::remove_dir_all(root.join("models--b")).unwrap();
+        let stats = index.refresh(&root, None);
+        assert_eq!(stats, RefreshStats { dirs_scanned: 1, dirs_reused: 2 });
+        assert_eq!(index.summary(&root).unwrap(), &RootSummary {
+            bytes: 100,
+            allocated_bytes: crate::allocation::size_of(&fs::metadata(root.join("models--a/blobs/1")).unwrap(), SizeBasis::Allocated),
+            files: 1,
+            dirs: 3,
+            refreshed_at: index.summary(&root).unwrap().refreshed_at,
+        });
+        assert_eq!(index.largest_children(&root, 5), vec![(root.join("models--a"), 100)]);
+    }
+}
\ No newline at end of file
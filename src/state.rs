@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::errors::{ClearModelError, Result};
+
+/// Database of past runs
+pub const HISTORY: &str = "history.sqlite3";
+
+/// Directory sizes reused between scans
+pub const SIZE_INDEX: &str = "size-index.bin";
+
+/// Deletion journals `clearmodel undo` restores from
+pub const JOURNAL: &str = "journal";
+
+/// Plans and progress of an interrupted cleanup
+pub const CHECKPOINT: &str = "checkpoint.json";
+
+/// Lock held while a run removes files
+pub const RUN_LOCK: &str = "run.pid";
+
+/// Shared lock marking that clearmodel is reading cache files
+pub const PASS_LOCK: &str = "pass.lock";
+
+/// Tamper-evident log of deletions, and the key its MACs are made with
+pub const AUDIT_LOG: &str = "audit.log";
+pub const AUDIT_KEY: &str = "audit.key";
+
+/// Rotated log files
+pub const LOGS: &str = "logs";
+
+/// Last verified copies of the remote configuration
+pub const REMOTE_CONFIG: &str = "remote-config";
+
+/// The daemon's control socket, where there is no runtime directory
+pub const DAEMON_SOCKET: &str = "daemon.sock";
+
+/// Everything clearmodel keeps in the state directory, with what it is for
+pub const ARTIFACTS: [(&str, &str); 10] = [
+    (HISTORY, "history of past runs"),
+    (SIZE_INDEX, "size index"),
+    (JOURNAL, "deletion journals"),
+    (CHECKPOINT, "checkpoint of an interrupted run"),
+    (LOGS, "log files"),
+    (REMOTE_CONFIG, "remote configuration copies"),
+    (AUDIT_LOG, "audit log"),
+    (AUDIT_KEY, "audit key"),
+    (RUN_LOCK, "run lock"),
+    (PASS_LOCK, "pass lock"),
+];
+
+/// Directory holding clearmodel's own data: `$XDG_STATE_HOME/clearmodel` (by default
+/// `~/.local/state/clearmodel`), `~/Library/Application Support/clearmodel` on macOS and
+/// `%LOCALAPPDATA%\clearmodel` on Windows
+///
+/// `XDG_STATE_HOME` is honoured on every platform when set.
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(state) = env_dir("XDG_STATE_HOME") {
+        return Some(state.join("clearmodel"));
+    }
+    if cfg!(windows) {
+        return env_dir("LOCALAPPDATA").map(|local| local.join("clearmodel"));
+    }
+    let home = home::home_dir()?;
+    if cfg!(target_os = "macos") {
+        return Some(home.join("Library").join("Application Support").join("clearmodel"));
+    }
+    Some(home.join(".local").join("state").join("clearmodel"))
+}
+
+/// Directory for bulky data that is not clearmodel's own: `$XDG_CACHE_HOME/clearmodel`
+/// (by default `~/.cache/clearmodel`), `~/Library/Caches/clearmodel` on macOS and
+/// `%LOCALAPPDATA%\clearmodel\cache` on Windows
+///
+/// The quarantine holds users' files and can grow as large as the caches themselves, so
+/// it lives here, where backups and `clearmodel state clean` leave it alone.
+pub fn cache_dir() -> Option<PathBuf> {
+    if let Some(cache) = env_dir("XDG_CACHE_HOME") {
+        return Some(cache.join("clearmodel"));
+    }
+    if cfg!(windows) {
+        return env_dir("LOCALAPPDATA").map(|local| local.join("clearmodel").join("cache"));
+    }
+    let home = home::home_dir()?;
+    if cfg!(target_os = "macos") {
+        return Some(home.join("Library").join("Caches").join("clearmodel"));
+    }
+    Some(home.join(".cache").join("clearmodel"))
+}
+
+/// Location of an artifact in the state directory
+pub fn path(name: &str) -> Option<PathBuf> {
+    state_dir().map(|dir| dir.join(name))
+}
+
+/// Location of an artifact, failing when there is no state directory; `what` names it in the error
+pub fn require(name: &str, what: &str) -> Result<PathBuf> {
+    path(name).ok_or_else(|| ClearModelError::environment(format!("Cannot determine the state directory for {}", what)))
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+/// One artifact found in the state directory
+#[derive(Debug, Clone)]
+pub struct StateEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// The artifacts present in `dir`, in the order of [`ARTIFACTS`]
+pub fn entries(dir: &Path) -> Vec<StateEntry> {
+    ARTIFACTS.iter()
+        .map(|(name, description)| (name, description, dir.join(name)))
+        .filter(|(_, _, path)| path.symlink_metadata().is_ok())
+        .map(|(name, description, path)| StateEntry { name, description, bytes: size(&path), path })
+        .collect()
+}
+
+fn size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Delete clearmodel's data from `dir`, returning what was (or would be) deleted
+///
+/// The run and pass locks stay, since the caller holds them, and so do the audit log and its key
+/// unless `include_audit` is set. Files clearmodel did not put there are never touched.
+pub fn clean(dir: &Path, include_audit: bool, dry_run: bool) -> Result<Vec<StateEntry>> {
+    let doomed: Vec<StateEntry> = entries(dir).into_iter()
+        .filter(|entry| ![RUN_LOCK, PASS_LOCK].contains(&entry.name))
+        .filter(|entry| include_audit || ![AUDIT_LOG, AUDIT_KEY].contains(&entry.name))
+        .collect();
+    if dry_run {
+        return Ok(doomed);
+    }
+    
+    for entry in &doomed {
+        let removed = if entry.path.symlink_metadata().is_ok_and(|metadata| metadata.is_dir()) {
+            std::fs::remove_dir_all(&entry.path)
+        } else {
+            std::fs::remove_file(&entry.path)
+        };
+        removed.map_err(|e| ClearModelError::file_operation(
+            format!("Failed to remove the {}: {}", entry.description, e),
+            Some(entry.path.clone())
+        ))?;
+    }
+    Ok(doomed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_clean_removes_only_clearmodel_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join(HISTORY), b"runs").unwrap();
+        fs::create_dir_all(dir.join(JOURNAL)).unwrap();
+        fs::write(dir.join(JOURNAL).join("run.jsonl"), b"{}\n").unwrap();
+        fs::write(dir.join(AUDIT_LOG), b"entry\n").unwrap();
+        fs::write(dir.join(RUN_LOCK), b"42").unwrap();
+        fs::write(dir.join("notes.txt"), b"mine").unwrap();
+        
+        let names = |entries: Vec<StateEntry>| entries.into_iter().map(|entry| entry.name).collect::<Vec<_>>();
+        assert_eq!(names(clean(dir, false, true).unwrap()), [HISTORY, JOURNAL]);
+        assert!(dir.join(HISTORY).exists());
+        
+        clean(dir, false, false).unwrap();
+        assert!(!dir.join(HISTORY).exists() && !dir.join(JOURNAL).exists());
+        assert!(dir.join(AUDIT_LOG).exists() && dir.join(RUN_LOCK).exists() && dir.join("notes.txt").exists());
+        
+        assert_eq!(names(clean(dir, true, false).unwrap()), [AUDIT_LOG]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tokio::sync::{mpsc, oneshot};
+
+/// Something that happened to a file or directory during a cleanup
+#[derive(Debug)]
+pub enum StatsEvent {
+    /// Work after this belongs to the named target, until the next one starts
+    TargetStarted { name: String },
+    
+    /// A cache root is about to be processed
+    DirectoryStarted { root: PathBuf },
+    
+    /// A batch of files under `root` was looked at, removed or not
+    BatchProcessed { root: PathBuf, files: usize },
+    
+    /// A file was removed (or, in a dry run, would be)
+    FileCleaned { root: PathBuf, path: PathBuf, bytes: u64 },
+    
+    /// A file could not be inspected or removed
+    FileFailed { root: PathBuf, path: PathBuf },
+    
+    /// Reply with the rollups once every earlier event has been counted
+    Snapshot(oneshot::Sender<StatsSnapshot>),
+}
+
+/// Counts for one directory, one target or the whole run
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationStats {
+    pub files_processed: u64,
+    pub files_cleaned: u64,
+    pub bytes_cleaned: u64,
+    pub errors_encountered: u64,
+    
+    /// Files per parallel batch, in the order the executor chose them
+    #[serde(skip)]
+    pub batch_sizes: Vec<usize>,
+    
+    #[serde(skip)]
+    pub start_time: SystemTime,
+    #[serde(skip)]
+    pub last_update: SystemTime,
+}
+
+impl Default for OperationStats {
+    fn default() -> Self {
+        let now = SystemTime::now();
+        Self {
+            files_processed: 0,
+            files_cleaned: 0,
+            bytes_cleaned: 0,
+            errors_encountered: 0,
+            batch_sizes: Vec::new(),
+            start_time: now,
+            last_update: now,
+        }
+    }
+}
+
+impl OperationStats {
+    fn add(&mut self, delta: &OperationStats) {
+        self.files_processed += delta.files_processed;
+        self.files_cleaned += delta.files_cleaned;
+        self.bytes_cleaned += delta.bytes_cleaned;
+        self.errors_encountered += delta.errors_encountered;
+        self.last_update = delta.last_update;
+    }
+}
+
+/// Per-directory, per-target and run-wide statistics at one point in time
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatsSnapshot {
+    pub global: OperationStats,
+    pub targets: BTreeMap<String, OperationStats>,
+    pub directories: BTreeMap<PathBuf, OperationStats>,
+}
+
+impl StatsSnapshot {
+    /// Count one event into the run, its target and its directory
+    fn apply(&mut self, target: Option<&str>, event: StatsEvent) {
+        let (root, delta) = match event {
+            StatsEvent::DirectoryStarted { root } => {
+                self.directories.entry(root).or_default();
+                return;
+            }
+            StatsEvent::BatchProcessed { root, files } => (root, OperationStats {
+                files_processed: files as u64,
+                batch_sizes: vec![files],
+                ..OperationStats::default()
+            }),
+            StatsEvent::FileCleaned { root, bytes, .. } => (root, OperationStats {
+                files_cleaned: 1,
+                bytes_cleaned: bytes,
+                ..OperationStats::default()
+            }),
+            StatsEvent::FileFailed { root, .. } => (root, OperationStats {
+                errors_encountered: 1,
+                ..OperationStats::default()
+            }),
+            StatsEvent::TargetStarted { .. } | StatsEvent::Snapshot(_) => return,
+        };
+        
+        self.global.add(&delta);
+        if let Some(name) = target {
+            self.targets.entry(name.to_string()).or_default().add(&delta);
+        }
+        let directory = self.directories.entry(root).or_default();
+        directory.add(&delta);
+        // Batch sizes only mean something for the directory the executor sized them for
+        directory.batch_sizes.extend(delta.batch_sizes);
+    }
+}
+
+/// Sends cleanup events to the task that aggregates them
+///
+/// Workers only send events, so counting never contends with removals, and every
+/// rollup is updated from the same event: totals cannot drift apart.
+#[derive(Debug, Clone)]
+pub struct StatsCollector {
+    events: mpsc::UnboundedSender<StatsEvent>,
+}
+
+impl StatsCollector {
+    /// Start the aggregating task; it stops once every collector is dropped
+    pub fn spawn() -> Self {
+        let (events, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut snapshot = StatsSnapshot::default();
+            let mut target: Option<String> = None;
+            while let Some(event) = receiver.recv().await {
+                match event {
+                    StatsEvent::TargetStarted { name } => {
+                        snapshot.targets.entry(name.clone()).or_default();
+                        target = Some(name);
+                    }
+                    StatsEvent::Snapshot(reply) => {
+                        let _ = reply.send(snapshot.clone());
+                    }
+                    event => snapshot.apply(target.as_deref(), event),
+                }
+            }
+        });
+        Self { events }
+    }
+    
+    /// Record an event; lost only if the aggregating task has already stopped
+    pub fn send(&self, event: StatsEvent) {
+        let _ = self.events.send(event);
+    }
+    
+    /// The rollups, including every event sent before this call
+    pub async fn snapshot(&self) -> StatsSnapshot {
+        let (reply, snapshot) = oneshot::channel();
+        self.send(StatsEvent::Snapshot(reply));
+        snapshot.await.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_every_rollup_counts_each_file_once() {
+        let stats = StatsCollector::spawn();
+        let root = PathBuf::from("/cache/hf");
+        stats.send(StatsEvent::TargetStarted { name: "huggingface".to_string() });
+        stats.send(StatsEvent::DirectoryStarted { root: root.clone() });
+        for (batch, bytes) in [(3, [10, 20]), (2, [30, 40])] {
+            for bytes in bytes {
+                stats.send(StatsEvent::FileCleaned { root: root.clone(), path: root.join("blob"), bytes });
+            }
+            stats.send(StatsEvent::BatchProcessed { root: root.clone(), files: batch });
+        }
+        stats.send(StatsEvent::FileFailed { root: root.clone(), path: root.join("locked") });
+        
+        let snapshot = stats.snapshot().await;
+        for rollup in [&snapshot.global, &snapshot.targets["huggingface"], &snapshot.directories[&root]] {
+            assert_eq!(rollup.files_processed, 5);
+            assert_eq!(rollup.files_cleaned, 4);
+            assert_eq!(rollup.bytes_cleaned, 100);
+            assert_eq!(rollup.errors_encountered, 1);
+        }
+    }
+}
\ No newline at end of file
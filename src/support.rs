@@ -0,0 +1,392 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+use crate::capabilities::{self, Capabilities};
+use crate::config::ClearModelConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::journal;
+use crate::models::ModelRef;
+use crate::policy::{CleanupPolicy, Decision, FileFacts};
+
+/// Bundle layout version, bumped when the archive contents change incompatibly
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Replaces pinned model patterns, which may name private models
+const REDACTED: &str = "<redacted>";
+
+/// Where and with what a bundle was captured
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub clearmodel_version: String,
+    pub created: u64,
+    pub target: String,
+    pub features: Vec<String>,
+    pub capabilities: serde_json::Value,
+}
+
+/// A configured cache directory as found on the user's machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredRoot {
+    pub path: String,
+    pub exists: bool,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Totals from the most recent journaled run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastRun {
+    pub run_id: String,
+    pub files: u64,
+    pub bytes: u64,
+    pub restorable: u64,
+}
+
+/// Anonymized metadata for one file the policy was evaluated against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    /// Index into the discovered roots
+    pub root: usize,
+    /// Stable hash of the full path, so files can be referred to without revealing it
+    pub id: String,
+    pub extension: Option<String>,
+    pub in_pycache: bool,
+    pub pinned: bool,
+    /// Absent from bundles written before the `incomplete` rule existed
+    #[serde(default)]
+    pub incomplete: bool,
+    pub model_source: Option<String>,
+    pub size: u64,
+    pub age_secs: Option<u64>,
+}
+
+/// Sanitized snapshot of everything needed to reproduce cleanup decisions
+#[derive(Debug, Clone)]
+pub struct SupportBundle {
+    pub manifest: BundleManifest,
+    pub config: serde_json::Value,
+    pub discovery: Vec<DiscoveredRoot>,
+    pub last_run: Option<LastRun>,
+    pub candidates: Vec<Candidate>,
+}
+
+/// Per-root outcome of replaying a bundle
+#[derive(Debug, Clone)]
+pub struct RootReplay {
+    pub path: String,
+    /// Files and bytes for each decision
+    pub decisions: BTreeMap<Decision, (u64, u64)>,
+}
+
+impl SupportBundle {
+    /// Capture a bundle from the current machine
+    pub fn capture(config: &ClearModelConfig, capabilities: &Capabilities) -> Result<Self> {
+        let home = home::home_dir();
+        let policy = CleanupPolicy::from_config(config);
+        let now = SystemTime::now();
+        
+        let mut discovery = Vec::new();
+        let mut candidates = Vec::new();
+        
+        for (root_index, root) in config.cache_paths.iter().enumerate() {
+            let mut discovered = DiscoveredRoot {
+                path: sanitize_path(root, home.as_deref()),
+                exists: root.exists(),
+                files: 0,
+                bytes: 0,
+            };
+            
+            if discovered.exists {
+                let walker = walkdir::WalkDir::new(root)
+                    .max_depth(config.security.max_path_depth)
+                    .follow_links(config.follow_symlinks)
+                    .into_iter()
+                    .filter_entry(|e| {
+                        e.file_name()
+                            .to_str()
+                            .map(|name| !config.skip_directories.iter().any(|skip| skip == name))
+                            .unwrap_or(true)
+                    });
+                
+                for entry in walker.flatten().filter(|entry| entry.file_type().is_file()) {
+                    let Ok(metadata) = entry.metadata() else {
+                        continue;
+                    };
+                    let age = policy.age_of(entry.path(), &metadata, now);
+                    let facts = FileFacts::from_path(entry.path(), &policy, age, metadata.len());
+                    
+                    discovered.files += 1;
+                    discovered.bytes += metadata.len();
+                    candidates.push(Candidate {
+                        root: root_index,
+                        id: path_id(entry.path()),
+                        extension: facts.extension,
+                        in_pycache: facts.in_pycache,
+                        pinned: facts.pinned,
+                        incomplete: facts.incomplete,
+                        model_source: ModelRef::from_path(entry.path()).map(|model| model.source.to_string()),
+                        size: metadata.len(),
+                        age_secs: age.map(|age| age.as_secs()),
+                    });
+                }
+            }
+            
+            discovery.push(discovered);
+        }
+        
+        info!("Captured {} candidate files from {} cache roots", candidates.len(), discovery.len());
+        
+        Ok(Self {
+            manifest: BundleManifest {
+                format_version: BUNDLE_FORMAT_VERSION,
+                clearmodel_version: env!("CARGO_PKG_VERSION").to_string(),
+                created: now.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0),
+                target: format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+                features: capabilities::compiled_features().into_iter().map(str::to_string).collect(),
+                capabilities: serde_json::to_value(capabilities)?,
+            },
+            config: sanitize_config(config, home.as_deref())?,
+            discovery,
+            last_run: last_run()?,
+            candidates,
+        })
+    }
+    
+    /// Write the bundle as a gzipped tar archive
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to create support bundle: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        append_json(&mut archive, "manifest.json", &self.manifest)?;
+        append_json(&mut archive, "config.json", &self.config)?;
+        append_json(&mut archive, "discovery.json", &self.discovery)?;
+        append_json(&mut archive, "last_run.json", &self.last_run)?;
+        append_json(&mut archive, "candidates.json", &self.candidates)?;
+        
+        archive.into_inner()?.finish()?;
+        info!("Support bundle written to {:?}", path);
+        Ok(())
+    }
+    
+    /// Load a bundle written by [`SupportBundle::write`]
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| ClearModelError::file_operation(
+                format!("Failed to open support bundle: {}", e),
+                Some(path.to_path_buf())
+            ))?;
+        
+        let mut contents = BTreeMap::new();
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().to_string();
+            let mut data = String::new();
+            entry.read_to_string(&mut data)?;
+            contents.insert(name, data);
+        }
+        
+        let manifest: BundleManifest = parse_entry(&contents, "manifest.json")?;
+        if manifest.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(ClearModelError::configuration(format!(
+                "Support bundle format {} is newer than this clearmodel supports ({})",
+                manifest.format_version, BUNDLE_FORMAT_VERSION
+            )));
+        }
+        
+        Ok(Self {
+            manifest,
+            config: parse_entry(&contents, "config.json")?,
+            discovery: parse_entry(&contents, "discovery.json")?,
+            last_run: parse_entry(&contents, "last_run.json")?,
+            candidates: parse_entry(&contents, "candidates.json")?,
+        })
+    }
+    
+    /// Configuration captured in the bundle
+    pub fn config(&self) -> Result<ClearModelConfig> {
+        serde_json::from_value(self.config.clone())
+            .map_err(|e| ClearModelError::configuration(
+                format!("Invalid configuration in support bundle: {}", e)
+            ))
+    }
+    
+    /// Re-run the policy over the captured candidates
+    ///
+    /// Pins are taken from the bundle, since the patterns themselves are redacted.
+    pub fn replay(&self, config: &ClearModelConfig) -> Vec<RootReplay> {
+        let policy = CleanupPolicy::from_config(config);
+        let mut roots: Vec<RootReplay> = self.discovery.iter()
+            .map(|root| RootReplay {
+                path: root.path.clone(),
+                decisions: BTreeMap::new(),
+            })
+            .collect();
+        
+        for candidate in &self.candidates {
+            let facts = FileFacts {
+                extension: candidate.extension.clone(),
+                in_pycache: candidate.in_pycache,
+                pinned: candidate.pinned,
+                incomplete: candidate.incomplete,
+                age: candidate.age_secs.map(Duration::from_secs),
+                size: candidate.size,
+            };
+            let decision = policy.decide(&facts, &config.python_cache_extensions);
+            
+            if let Some(root) = roots.get_mut(candidate.root) {
+                let totals = root.decisions.entry(decision).or_default();
+                totals.0 += 1;
+                totals.1 += candidate.size;
+            }
+        }
+        
+        debug!("Replayed {} candidates", self.candidates.len());
+        roots
+    }
+}
+
+/// Default bundle file name in the current directory
+pub fn default_bundle_path() -> PathBuf {
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    PathBuf::from(format!("clearmodel-support-{}.tar.gz", created))
+}
+
+/// Replace the home directory prefix with `~`
+fn sanitize_path(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(relative) => Path::new("~").join(relative).display().to_string(),
+        None => path.display().to_string(),
+    }
+}
+
+/// Serialize the configuration with home paths anonymized and pins and webhooks redacted
+fn sanitize_config(config: &ClearModelConfig, home: Option<&Path>) -> Result<serde_json::Value> {
+    let mut config = config.clone();
+    config.pinned_models = vec![REDACTED.to_string(); config.pinned_models.len()];
+    // The webhook URL is its own credential
+    if config.notifications.webhook_url.is_some() {
+        config.notifications.webhook_url = Some(REDACTED.to_string());
+    }
+    
+    let mut value = serde_json::to_value(&config)?;
+    if let Some(home) = home {
+        sanitize_value(&mut value, &home.display().to_string());
+    }
+    
+    Ok(value)
+}
+
+fn sanitize_value(value: &mut serde_json::Value, home: &str) {
+    match value {
+        serde_json::Value::String(text) => {
+            if let Some(rest) = text.strip_prefix(home) {
+                *text = format!("~{}", rest);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| sanitize_value(item, home)),
+        serde_json::Value::Object(fields) => fields.values_mut().for_each(|field| sanitize_value(field, home)),
+        _ => {}
+    }
+}
+
+fn path_id(path: &Path) -> String {
+    let hash = blake3::hash(path.to_string_lossy().as_bytes()).to_hex();
+    hash[..16].to_string()
+}
+
+fn last_run() -> Result<Option<LastRun>> {
+    let Some(dir) = journal::journal_dir() else {
+        return Ok(None);
+    };
+    let Some((run_id, path)) = journal::latest_run(&dir)? else {
+        return Ok(None);
+    };
+    
+    let entries = journal::read_entries(&path)?;
+    Ok(Some(LastRun {
+        run_id,
+        files: entries.len() as u64,
+        bytes: entries.iter().map(|entry| entry.size).sum(),
+        restorable: entries.iter().filter(|entry| entry.destination.is_some()).count() as u64,
+    }))
+}
+
+fn append_json<W: std::io::Write, T: Serialize>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    value: &T,
+) -> Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data.as_slice())?;
+    Ok(())
+}
+
+fn parse_entry<T: DeserializeOwned>(contents: &BTreeMap<String, String>, name: &str) -> Result<T> {
+    let data = contents.get(name).ok_or_else(|| ClearModelError::configuration(
+        format!("Support bundle is missing {}", name)
+    ))?;
+    Ok(serde_json::from_str(data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_sanitize_path_hides_home() {
+        let home = Path::new("/home/alice");
+        assert_eq!(sanitize_path(Path::new("/home/alice/.cache/torch"), Some(home)), "~/.cache/torch");
+        assert_eq!(sanitize_path(Path::new("/data/models"), Some(home)), "/data/models");
+    }
+    
+    #[test]
+    fn test_bundle_round_trip_and_replay() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("mod.cpython-311.pyc"), b"x").unwrap();
+        fs::write(cache.join("fresh.bin"), b"weights").unwrap();
+        
+        let config = ClearModelConfig {
+            cache_paths: vec![cache.clone()],
+            pinned_models: vec!["private-org/*".to_string()],
+            ..ClearModelConfig::default()
+        };
+        let bundle = SupportBundle::capture(&config, &Capabilities::full()).unwrap();
+        assert_eq!(bundle.candidates.len(), 2);
+        assert_eq!(bundle.config["pinned_models"][0], REDACTED);
+        
+        let path = temp_dir.path().join("bundle.tar.gz");
+        bundle.write(&path).unwrap();
+        let loaded = SupportBundle::read(&path).unwrap();
+        
+        let replay = loaded.replay(&loaded.config().unwrap());
+        assert_eq!(replay.len(), 1);
+        assert_eq!(replay[0].decisions.get(&Decision::PythonCache), Some(&(1, 1)));
+        assert_eq!(replay[0].decisions.get(&Decision::Fresh), Some(&(1, 7)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::ClearModelConfig;
+use crate::torch_caches;
+
+/// Group name for the configured ML model cache directories, each a target named after its framework
+pub const ML_TARGET: &str = "ml";
+
+/// Target name for Python bytecode caches in the current project
+pub const PYTHON_TARGET: &str = "python";
+
+/// Group name covering the pip, uv and poetry caches
+pub const PACKAGE_CACHE_GROUP: &str = "package_caches";
+
+/// Group name covering the JAX, Triton and TorchInductor compilation caches
+pub const COMPILE_CACHE_GROUP: &str = "compile-caches";
+
+/// Group name covering every HuggingFace cache instance in `hf_caches`
+pub const HF_GROUP: &str = "hf";
+
+/// Group name covering the llama.cpp, LM Studio and GPT4All model stores
+pub const GGUF_GROUP: &str = "gguf";
+
+/// Group name covering the CUDA compute, cuDNN autotune and GL shader caches
+pub const GPU_CACHE_GROUP: &str = "gpu-caches";
+
+/// Group name covering the ONNX Runtime and TensorRT engine caches
+pub const ENGINE_CACHE_GROUP: &str = "engine-caches";
+
+/// Group name covering the Whisper, spaCy, NLTK and gensim data caches
+pub const DATA_CACHE_GROUP: &str = "data-caches";
+
+/// Group name covering every ComfyUI, AUTOMATIC1111 and InvokeAI store in `image_stores`
+pub const IMAGE_MODEL_GROUP: &str = "image-models";
+
+/// Group name covering the DVC cache, MLflow artifacts and W&B staging
+pub const EXPERIMENT_GROUP: &str = "experiments";
+
+/// Group name covering Jupyter runtime files and kernel specs, IPython history and execution caches
+pub const NOTEBOOK_GROUP: &str = "notebooks";
+
+/// Target and group name of Docker/Podman image pruning
+pub const CONTAINER_TARGET: &str = "containers";
+
+/// Group name covering every bucket prefix in `remote_buckets`
+pub const REMOTE_GROUP: &str = "remote";
+
+/// Names `--only` already gives a meaning to, so cache instances cannot use them
+pub const RESERVED_TARGET_NAMES: [&str; 39] = [
+    ML_TARGET, PYTHON_TARGET, PACKAGE_CACHE_GROUP, COMPILE_CACHE_GROUP, HF_GROUP, IMAGE_MODEL_GROUP, GGUF_GROUP,
+    DATA_CACHE_GROUP, ENGINE_CACHE_GROUP, EXPERIMENT_GROUP, CONTAINER_TARGET, NOTEBOOK_GROUP, "dvc", "mlflow",
+    "wandb", "jupyter-runtime", "kernelspecs", "ipython", "nbconvert", GPU_CACHE_GROUP, "cuda-compute",
+    "cudnn", "shader-cache", "pip", "uv", "poetry", "jax", "triton", "torchinductor", "llama.cpp",
+    "lm-studio", "gpt4all", "whisper", "spacy", "nltk", "gensim", "onnxruntime", "tensorrt", REMOTE_GROUP,
+];
+
+/// Python package manager caches: (target name, override env var, default location under home)
+const PACKAGE_CACHES: [(&str, &str, &str); 3] = [
+    ("pip", "PIP_CACHE_DIR", if cfg!(target_os = "macos") { "Library/Caches/pip" } else { ".cache/pip" }),
+    ("uv", "UV_CACHE_DIR", ".cache/uv"),
+    ("poetry", "POETRY_CACHE_DIR", if cfg!(target_os = "macos") { "Library/Caches/pypoetry" } else { ".cache/pypoetry" }),
+];
+
+/// Restricts a run to the targets named on the command line
+#[derive(Debug, Clone, Default)]
+pub struct TargetFilter {
+    only: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl TargetFilter {
+    /// Only run the named targets or groups (an empty list means everything)
+    pub fn only(names: Vec<String>) -> Self {
+        Self { only: names, exclude: Vec::new() }
+    }
+    
+    /// Never run the named targets or groups, even when `only` selects them
+    pub fn excluding(mut self, names: Vec<String>) -> Self {
+        self.exclude = names;
+        self
+    }
+    
+    /// Whether a target should run
+    pub fn allows(&self, name: &str, group: &str) -> bool {
+        (self.only.is_empty() || self.names(name, group)) && !self.excludes(name, group)
+    }
+    
+    /// Whether a target or its group was explicitly requested (and not excluded)
+    pub fn names(&self, name: &str, group: &str) -> bool {
+        self.only.iter().any(|only| only == name || only == group) && !self.excludes(name, group)
+    }
+    
+    fn excludes(&self, name: &str, group: &str) -> bool {
+        self.exclude.iter().any(|exclude| exclude == name || exclude == group)
+    }
+    
+    /// Names on the command line that match no target or group in `known`
+    pub fn unknown<'a>(&'a self, known: &[(String, &str)]) -> Vec<&'a str> {
+        self.only.iter()
+            .chain(&self.exclude)
+            .filter(|wanted| !known.iter().any(|(name, group)| name == *wanted || group == wanted))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Target name of an ML cache directory: its framework, taken from the directory name
+/// (`~/.cache/huggingface` is `huggingface`, `~/.keras` is `keras`)
+pub fn cache_dir_target(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().trim_start_matches('.').to_lowercase())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| ML_TARGET.to_string())
+}
+
+/// Resolve the location of each package manager cache, honouring their env var overrides
+pub fn package_cache_locations() -> Vec<(&'static str, PathBuf)> {
+    let home = home::home_dir();
+    
+    PACKAGE_CACHES.iter()
+        .filter_map(|(name, env_var, default)| {
+            let path = std::env::var_os(env_var)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| home.as_ref().map(|home| home.join(default)))?;
+            Some((*name, path))
+        })
+        .collect()
+}
+
+/// Resolve the location of each compilation cache, honouring their env var overrides
+///
+/// JAX only keeps a persistent cache when `jax_compilation_cache_dir` is set; the
+/// directory it is usually pointed at is assumed otherwise.
+pub fn compile_cache_locations() -> Vec<(&'static str, PathBuf)> {
+    let home = home::home_dir();
+    let env_dir = |var: &str| std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from);
+    
+    let mut locations = Vec::new();
+    if let Some(jax) = env_dir("JAX_COMPILATION_CACHE_DIR").or_else(|| home.as_ref().map(|home| home.join(".cache").join("jax"))) {
+        locations.push(("jax", jax));
+    }
+    if let Some(triton) = env_dir("TRITON_CACHE_DIR").or_else(|| home.as_ref().map(|home| home.join(".triton").join("cache"))) {
+        locations.push(("triton", triton));
+    }
+    if let Some(inductor) = torch_caches::inductor_dir() {
+        locations.push(("torchinductor", inductor));
+    }
+    locations
+}
+
+/// Where llama.cpp, LM Studio and GPT4All keep downloaded models: (target name, directory)
+///
+/// llama.cpp downloads to `$LLAMA_CACHE`, else its platform cache directory. LM Studio moved
+/// from `~/.cache/lm-studio/models` to `~/.lmstudio/models`; the newer one wins when present.
+pub fn gguf_store_locations() -> Vec<(&'static str, PathBuf)> {
+    let Some(home) = home::home_dir() else {
+        return Vec::new();
+    };
+    let env_dir = |var: &str| std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let cache = if cfg!(target_os = "macos") {
+        home.join("Library").join("Caches")
+    } else if cfg!(windows) {
+        env_dir("LOCALAPPDATA").unwrap_or_else(|| home.join("AppData").join("Local"))
+    } else {
+        env_dir("XDG_CACHE_HOME").unwrap_or_else(|| home.join(".cache"))
+    };
+    let data = if cfg!(target_os = "macos") {
+        home.join("Library").join("Application Support")
+    } else if cfg!(windows) {
+        cache.clone()
+    } else {
+        env_dir("XDG_DATA_HOME").unwrap_or_else(|| home.join(".local").join("share"))
+    };
+    
+    let lm_studio = home.join(".lmstudio").join("models");
+    let lm_studio = if lm_studio.is_dir() { lm_studio } else { home.join(".cache").join("lm-studio").join("models") };
+    vec![
+        ("llama.cpp", env_dir("LLAMA_CACHE").unwrap_or_else(|| cache.join("llama.cpp"))),
+        ("lm-studio", lm_studio),
+        ("gpt4all", data.join("nomic.ai").join("GPT4All")),
+    ]
+}
+
+/// NVIDIA driver and GPU library caches: (target name, directories)
+///
+/// - cuda-compute: the driver's JIT cache of PTX compiled for the installed GPU
+///   (`$CUDA_CACHE_PATH`, else `~/.nv/ComputeCache`, `%APPDATA%\NVIDIA\ComputeCache` on Windows)
+/// - cudnn: autotune results, from XLA's `--xla_gpu_per_fusion_autotune_cache_dir` in `$XLA_FLAGS`
+///   and `gpu_caches.autotune_dirs`
+/// - shader-cache: OpenGL/Vulkan shader caches of the NVIDIA driver (`$__GL_SHADER_DISK_CACHE_PATH`,
+///   `~/.nv/GLCache`, `~/.cache/nvidia/GLCache`) and Mesa (`$MESA_SHADER_CACHE_DIR`, `~/.cache/mesa_shader_cache`)
+pub fn gpu_cache_locations(config: &ClearModelConfig) -> Vec<(&'static str, Vec<PathBuf>)> {
+    let home = home::home_dir();
+    let env_dir = |var: &str| std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let cache = env_dir("XDG_CACHE_HOME").or_else(|| home.as_ref().map(|home| home.join(".cache")));
+    
+    let compute = env_dir("CUDA_CACHE_PATH").or_else(|| {
+        if cfg!(windows) {
+            env_dir("APPDATA").map(|data| data.join("NVIDIA").join("ComputeCache"))
+        } else {
+            home.as_ref().map(|home| home.join(".nv").join("ComputeCache"))
+        }
+    });
+    
+    let mut autotune: Vec<PathBuf> = std::env::var("XLA_FLAGS").ok()
+        .into_iter()
+        .flat_map(|flags| {
+            flags.split_whitespace()
+                .filter_map(|flag| flag.strip_prefix("--xla_gpu_per_fusion_autotune_cache_dir="))
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    autotune.extend(config.gpu_caches.autotune_dirs.iter().cloned());
+    autotune.sort();
+    autotune.dedup();
+    
+    let mut shaders: Vec<PathBuf> = Vec::new();
+    shaders.extend(env_dir("__GL_SHADER_DISK_CACHE_PATH"));
+    shaders.extend(home.as_ref().map(|home| home.join(".nv").join("GLCache")));
+    shaders.extend(cache.as_ref().map(|cache| cache.join("nvidia").join("GLCache")));
+    shaders.extend(env_dir("MESA_SHADER_CACHE_DIR").or_else(|| cache.as_ref().map(|cache| cache.join("mesa_shader_cache"))));
+    if cfg!(windows) {
+        shaders.extend(env_dir("LOCALAPPDATA").map(|data| data.join("NVIDIA").join("GLCache")));
+    }
+    
+    vec![("cuda-compute", compute.into_iter().collect()), ("cudnn", autotune), ("shader-cache", shaders)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_filter_matches_names_and_groups() {
+        let everything = TargetFilter::default();
+        assert!(everything.allows("pip", PACKAGE_CACHE_GROUP));
+        assert!(!everything.names("pip", PACKAGE_CACHE_GROUP));
+        
+        let filter = TargetFilter::only(vec!["pip".to_string(), "uv".to_string()]);
+        assert!(filter.allows("pip", PACKAGE_CACHE_GROUP));
+        assert!(filter.allows("uv", PACKAGE_CACHE_GROUP));
+        assert!(!filter.allows("poetry", PACKAGE_CACHE_GROUP));
+        assert!(!filter.allows(ML_TARGET, ML_TARGET));
+        
+        let group = TargetFilter::only(vec![PACKAGE_CACHE_GROUP.to_string()]);
+        assert!(group.allows("poetry", PACKAGE_CACHE_GROUP));
+        
+        let excluded = TargetFilter::only(vec![ML_TARGET.to_string()]).excluding(vec!["torch".to_string()]);
+        assert!(excluded.allows("huggingface", ML_TARGET));
+        assert!(!excluded.allows("torch", ML_TARGET));
+        assert!(!TargetFilter::default().excluding(vec![PACKAGE_CACHE_GROUP.to_string()]).allows("pip", PACKAGE_CACHE_GROUP));
+        
+        let known = vec![("torch".to_string(), ML_TARGET), ("pip".to_string(), PACKAGE_CACHE_GROUP)];
+        let typo = TargetFilter::only(vec!["ml".to_string(), "troch".to_string()]).excluding(vec!["pip".to_string()]);
+        assert_eq!(typo.unknown(&known), vec!["troch"]);
+        
+        assert_eq!(cache_dir_target(Path::new("/home/user/.cache/huggingface")), "huggingface");
+        assert_eq!(cache_dir_target(Path::new("/home/user/.keras")), "keras");
+    }
+    
+    #[test]
+    fn test_package_cache_locations() {
+        let names: Vec<_> = package_cache_locations().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["pip", "uv", "poetry"]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,210 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+use crate::allocation::{self, SizeBasis};
+use crate::models::{self, CachedModel};
+
+/// Order `clearmodel top` lists the largest consumers in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopOrder {
+    /// Largest first
+    #[default]
+    Size,
+    
+    /// Longest unused first
+    Age,
+}
+
+impl FromStr for TopOrder {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "size" => Ok(TopOrder::Size),
+            "age" => Ok(TopOrder::Age),
+            other => Err(format!("unknown order {:?} (expected size or age)", other)),
+        }
+    }
+}
+
+impl fmt::Display for TopOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TopOrder::Size => "size",
+            TopOrder::Age => "age",
+        })
+    }
+}
+
+/// Something taking up space in a cache: a model, or a top-level entry of a cache root
+/// holding no models
+#[derive(Debug, Clone, Serialize)]
+pub struct Consumer {
+    /// Model name, or the entry's path relative to its cache root
+    pub name: String,
+    
+    /// Model source (`huggingface`, `ollama`, ...), or `directory` or `file`
+    pub kind: String,
+    pub path: PathBuf,
+    
+    /// Cache root the consumer is in
+    pub root: PathBuf,
+    pub bytes: u64,
+    
+    /// Days since any of its files was last read (or written, where access times are not kept)
+    pub idle_days: Option<u64>,
+    
+    #[serde(skip)]
+    pub last_used: Option<SystemTime>,
+}
+
+/// The largest consumers across the cache roots
+#[derive(Debug, Clone, Serialize)]
+pub struct TopReport {
+    pub consumers: Vec<Consumer>,
+    
+    /// Bytes of everything found under each cache root, listed or not
+    pub roots: BTreeMap<PathBuf, u64>,
+    pub size_basis: SizeBasis,
+}
+
+/// The `limit` largest models and directories under `roots` and the Ollama store, in `order`
+///
+/// Models are found as `models list` finds them. Whatever no model claims is grouped by
+/// the top-level entry of its cache root, so stray checkpoints and package caches show up
+/// next to the models. Sizes are counted under `basis`, each file on its own.
+pub fn largest(roots: &[PathBuf], ollama_root: Option<&Path>, basis: SizeBasis, limit: usize, order: TopOrder) -> TopReport {
+    let mut consumers = Vec::new();
+    let mut claimed_files: HashSet<PathBuf> = HashSet::new();
+    // Ollama blobs shared by several models are not among the files of any of them
+    let mut claimed_dirs: HashSet<PathBuf> = ollama_root.map(Path::to_path_buf).into_iter().collect();
+    
+    for cached in models::inventory(roots, ollama_root, basis) {
+        let Some(path) = model_path(&cached) else {
+            continue;
+        };
+        let root = root_of(&path, roots).or(ollama_root).unwrap_or(&path).to_path_buf();
+        claimed_files.extend(cached.files.iter().cloned());
+        claimed_dirs.extend(cached.dirs.iter().cloned());
+        consumers.push(Consumer {
+            name: cached.model.name.clone(),
+            kind: cached.model.source.to_string(),
+            idle_days: cached.idle_days(),
+            last_used: cached.last_access,
+            bytes: cached.bytes,
+            path,
+            root,
+        });
+    }
+    
+    for root in roots {
+        let mut entries: BTreeMap<PathBuf, (bool, u64, Option<SystemTime>)> = BTreeMap::new();
+        let walk = WalkDir::new(root)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| !claimed_dirs.contains(entry.path()))
+            .flatten()
+            .filter(|entry| entry.file_type().is_file() && !claimed_files.contains(entry.path()));
+        for entry in walk {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let Some(top) = entry.path().strip_prefix(root).ok().and_then(|relative| relative.components().next()) else {
+                continue;
+            };
+            let (is_dir, bytes, last_used) = entries.entry(root.join(top))
+                .or_insert((entry.depth() > 1, 0, None));
+            *is_dir |= entry.depth() > 1;
+            *bytes += allocation::size_of(&metadata, basis);
+            *last_used = (*last_used).max(metadata.accessed().or_else(|_| metadata.modified()).ok());
+        }
+        
+        consumers.extend(entries.into_iter().map(|(path, (is_dir, bytes, last_used))| Consumer {
+            name: path.strip_prefix(root).unwrap_or(&path).display().to_string(),
+            kind: if is_dir { "directory" } else { "file" }.to_string(),
+            idle_days: last_used.map(idle_days),
+            last_used,
+            root: root.clone(),
+            bytes,
+            path,
+        }));
+    }
+    
+    let mut totals: BTreeMap<PathBuf, u64> = BTreeMap::new();
+    for consumer in &consumers {
+        *totals.entry(consumer.root.clone()).or_default() += consumer.bytes;
+    }
+    consumers.sort_by_key(|consumer| std::cmp::Reverse(consumer.bytes));
+    consumers.truncate(limit);
+    if order == TopOrder::Age {
+        // Stable, so equally idle consumers stay largest first; unknown ages go last
+        consumers.sort_by_key(|consumer| std::cmp::Reverse(consumer.idle_days));
+    }
+    
+    TopReport { consumers, roots: totals, size_basis: basis }
+}
+
+/// The directory holding the model, or its file when it is a single one
+fn model_path(cached: &CachedModel) -> Option<PathBuf> {
+    if let Some(dir) = cached.dirs.first() {
+        return Some(dir.clone());
+    }
+    let (first, rest) = cached.files.split_first()?;
+    if rest.is_empty() {
+        return Some(first.clone());
+    }
+    first.ancestors()
+        .find(|ancestor| rest.iter().all(|file| file.starts_with(ancestor)))
+        .map(Path::to_path_buf)
+}
+
+/// The innermost cache root `path` is in
+fn root_of<'a>(path: &Path, roots: &'a [PathBuf]) -> Option<&'a Path> {
+    roots.iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .map(PathBuf::as_path)
+}
+
+fn idle_days(last_used: SystemTime) -> u64 {
+    SystemTime::now().duration_since(last_used).map(|idle| idle.as_secs() / 86_400).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_models_and_leftover_directories_rank_together() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("cache");
+        let model = root.join("hub/models--org--model");
+        fs::create_dir_all(model.join("blobs")).unwrap();
+        fs::write(model.join("blobs/abc"), vec![0u8; 5000]).unwrap();
+        fs::create_dir_all(root.join("checkpoints/run-1")).unwrap();
+        fs::write(root.join("checkpoints/run-1/model.pt"), vec![0u8; 3000]).unwrap();
+        fs::write(root.join("stray.bin"), vec![0u8; 1000]).unwrap();
+        let old = filetime::FileTime::from_system_time(SystemTime::now() - Duration::from_secs(40 * 86_400));
+        filetime::set_file_times(root.join("stray.bin"), old, old).unwrap();
+        
+        let roots = [root.clone()];
+        let report = largest(&roots, None, SizeBasis::Apparent, 2, TopOrder::Size);
+        let listed: Vec<(&str, &str, u64)> = report.consumers.iter()
+            .map(|consumer| (consumer.name.as_str(), consumer.kind.as_str(), consumer.bytes))
+            .collect();
+        assert_eq!(listed, [("org/model", "huggingface", 5000), ("checkpoints", "directory", 3000)]);
+        assert_eq!(report.roots[&root], 9000);
+        
+        // Sorting by age puts the long-unused stray file first
+        let report = largest(&roots, None, SizeBasis::Apparent, 3, TopOrder::Age);
+        assert_eq!((report.consumers[0].name.as_str(), report.consumers[0].idle_days), ("stray.bin", Some(40)));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,118 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Torch's own cache directory: `$TORCH_HOME`, else `$XDG_CACHE_HOME/torch`, else `~/.cache/torch`
+pub fn torch_home() -> Option<PathBuf> {
+    env_dir("TORCH_HOME")
+        .or_else(|| env_dir("XDG_CACHE_HOME").map(|cache| cache.join("torch")))
+        .or_else(|| home::home_dir().map(|home| home.join(".cache").join("torch")))
+}
+
+/// The CUDA kernel cache: `$PYTORCH_KERNEL_CACHE_PATH`, else `kernels` in the torch home
+pub fn kernels_dir() -> Option<PathBuf> {
+    env_dir("PYTORCH_KERNEL_CACHE_PATH").or_else(|| torch_home().map(|torch| torch.join("kernels")))
+}
+
+/// Where JIT-compiled C++/CUDA extensions are built: `$TORCH_EXTENSIONS_DIR`, else
+/// `torch_extensions` in the platform cache directory
+pub fn extensions_dir() -> Option<PathBuf> {
+    if let Some(dir) = env_dir("TORCH_EXTENSIONS_DIR") {
+        return Some(dir);
+    }
+    if cfg!(windows) {
+        return env_dir("LOCALAPPDATA").map(|local| local.join("torch_extensions").join("torch_extensions").join("Cache"));
+    }
+    let home = home::home_dir()?;
+    Some(if cfg!(target_os = "macos") {
+        home.join("Library").join("Caches").join("torch_extensions")
+    } else {
+        env_dir("XDG_CACHE_HOME").unwrap_or_else(|| home.join(".cache")).join("torch_extensions")
+    })
+}
+
+/// TorchInductor's compiled graph and kernel cache: `$TORCHINDUCTOR_CACHE_DIR`, else
+/// `torchinductor_<user>` in the temporary directory
+pub fn inductor_dir() -> Option<PathBuf> {
+    if let Some(dir) = env_dir("TORCHINDUCTOR_CACHE_DIR") {
+        return Some(dir);
+    }
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok()?;
+    // torch replaces anything that is not safe in a file name
+    let user: String = user.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect();
+    Some(std::env::temp_dir().join(format!("torchinductor_{}", user)))
+}
+
+/// Torch cache directories to clean that the default list does not already cover
+pub fn cache_paths(defaults: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for dir in [torch_home(), kernels_dir(), extensions_dir()].into_iter().flatten() {
+        let covered = defaults.iter().chain(&paths).any(|path| dir.starts_with(path));
+        if !covered {
+            paths.push(dir);
+        }
+    }
+    paths
+}
+
+/// Model name of a torch hub checkpoint file
+///
+/// `torch.hub.download_url_to_file` names checkpoints `<name>-<hash prefix>.<ext>`
+/// (`resnet50-0676ba61.pth`), so the hash and extension are dropped.
+pub fn checkpoint_name(file_name: &str) -> String {
+    let stem = Path::new(file_name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(file_name);
+    match stem.rsplit_once('-') {
+        Some((name, hash)) if !name.is_empty() && hash.len() >= 6 && hash.chars().all(|c| c.is_ascii_hexdigit()) => name.to_string(),
+        _ => stem.to_string(),
+    }
+}
+
+/// Whether a file belongs to a torch extension that is being compiled right now
+///
+/// Builds live in `torch_extensions/<python and CUDA tag>/<extension>`, and torch holds a
+/// `lock` file there until the build finishes; removing files mid-build breaks it.
+pub fn build_in_progress(path: &Path) -> bool {
+    let components: Vec<Component> = path.components().collect();
+    let Some(mut index) = components.iter().rposition(|component| *component == Component::Normal("torch_extensions".as_ref())) else {
+        return false;
+    };
+    // On Windows the builds sit one level further down, in `torch_extensions/Cache`
+    if components.get(index + 1) == Some(&Component::Normal("Cache".as_ref())) {
+        index += 1;
+    }
+    if components.len() <= index + 3 {
+        return false;
+    }
+    let build_dir: PathBuf = components[..index + 3].iter().collect();
+    build_dir.join("lock").exists()
+}
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|value| !value.is_empty()).map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_checkpoints_are_named_without_their_hash() {
+        assert_eq!(checkpoint_name("resnet50-0676ba61.pth"), "resnet50");
+        assert_eq!(checkpoint_name("efficientnet_b0_rwightman-7f5810bc.pth"), "efficientnet_b0_rwightman");
+        assert_eq!(checkpoint_name("yolov5s.pt"), "yolov5s");
+        assert_eq!(checkpoint_name("vit-base.pth"), "vit-base");
+    }
+    
+    #[test]
+    fn test_extension_builds_holding_a_lock_are_in_progress() {
+        let temp_dir = TempDir::new().unwrap();
+        let build = temp_dir.path().join("torch_extensions/py311_cu121/fused_adam");
+        fs::create_dir_all(&build).unwrap();
+        fs::write(build.join("fused_adam.so"), b"elf").unwrap();
+        assert!(!build_in_progress(&build.join("fused_adam.so")));
+        
+        fs::write(build.join("lock"), b"").unwrap();
+        assert!(build_in_progress(&build.join("fused_adam.so")));
+        assert!(!build_in_progress(&temp_dir.path().join("torch/kernels/abc")));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,48 @@
+use crate::errors::{ClearModelError, Result};
+
+/// Internal subsystems that can be traced individually: (name on the CLI, tracing target)
+pub const SUBSYSTEMS: [(&str, &str); 9] = [
+    ("security", "clearmodel::security"),
+    ("scanner", "clearmodel::resource_manager"),
+    ("cleaner", "clearmodel::cache_cleaner"),
+    ("config", "clearmodel::config"),
+    ("environment", "clearmodel::environment"),
+    ("watch", "clearmodel::watch"),
+    ("models", "clearmodel::models"),
+    ("policy", "clearmodel::policy"),
+    ("capabilities", "clearmodel::capabilities"),
+];
+
+/// Build an `EnvFilter` directive string that keeps `level` globally but enables
+/// debug output for the named subsystems only
+pub fn filter_directives(level: &str, subsystems: &[String]) -> Result<String> {
+    let mut directives = vec![format!("clearmodel={}", level)];
+    
+    for name in subsystems {
+        let target = SUBSYSTEMS.iter()
+            .find(|(subsystem, _)| subsystem.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, target)| *target)
+            .ok_or_else(|| ClearModelError::configuration(format!(
+                "Unknown trace subsystem '{}' (expected one of: {})",
+                name,
+                SUBSYSTEMS.iter().map(|(subsystem, _)| *subsystem).collect::<Vec<_>>().join(", ")
+            )))?;
+        directives.push(format!("{}=debug", target));
+    }
+    
+    Ok(directives.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_subsystems_map_to_targets() {
+        let directives = filter_directives("warn", &["security".to_string(), "Scanner".to_string()]).unwrap();
+        assert_eq!(directives, "clearmodel=warn,clearmodel::security=debug,clearmodel::resource_manager=debug");
+        
+        assert_eq!(filter_directives("info", &[]).unwrap(), "clearmodel=info");
+        assert!(filter_directives("warn", &["network".to_string()]).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+/// Parse a size like `100M`, `1.5G`, `512KiB` or `4096` (bytes) into bytes
+///
+/// Suffixes are binary multiples, matching the GB reported everywhere else:
+/// `K` is 1024 bytes, `M` 1024² and so on. `B`, `iB` and case are optional.
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse()
+        .map_err(|_| format!("{:?} is not a size (expected e.g. 100M or 2G)", text))?;
+    
+    let unit = unit.trim().to_ascii_lowercase();
+    let exponent = match unit.trim_end_matches('b').trim_end_matches('i') {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => return Err(format!("Unknown size unit in {:?} (use K, M, G or T)", text)),
+    };
+    Ok((number * 1024f64.powi(exponent)) as u64)
+}
+
+/// Parse a count like `1M`, `250K` or `5000`
+///
+/// Unlike sizes, counts use decimal multiples: `1M` is a million.
+pub fn parse_count(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse()
+        .map_err(|_| format!("{:?} is not a count (expected e.g. 100K or 1M)", text))?;
+    
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" => 1.0,
+        "k" => 1e3,
+        "m" => 1e6,
+        "g" => 1e9,
+        _ => return Err(format!("Unknown count unit in {:?} (use K, M or G)", text)),
+    };
+    Ok((number * multiplier) as u64)
+}
+
+/// Parse a duration like `30d`, `12h`, `1d12h` or `2w`
+///
+/// Units are `s`, `m` (minutes), `h`, `d` and `w`; every number needs one.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("{:?} is not a duration (expected e.g. 30d or 12h)", text);
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    
+    let mut secs: u64 = 0;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let number: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = rest[digits..].trim_start();
+        
+        let letters = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let unit = match &rest[..letters] {
+            "s" | "sec" | "secs" => 1,
+            "m" | "min" | "mins" => 60,
+            "h" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86_400,
+            "w" | "week" | "weeks" => 604_800,
+            _ => return Err(invalid()),
+        };
+        rest = rest[letters..].trim_start();
+        secs = number.checked_mul(unit)
+            .and_then(|part| secs.checked_add(part))
+            .ok_or_else(invalid)?;
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("100M"), Ok(100 * 1_048_576));
+        assert_eq!(parse_size("1.5g"), Ok(1_610_612_736));
+        assert_eq!(parse_size("512KiB"), Ok(512 * 1024));
+        assert_eq!(parse_size("2 GB"), Ok(2 * 1_073_741_824));
+        assert!(parse_size("M").is_err());
+        assert!(parse_size("10X").is_err());
+    }
+    
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("5000"), Ok(5000));
+        assert_eq!(parse_count("1M"), Ok(1_000_000));
+        assert_eq!(parse_count("2.5k"), Ok(2500));
+        assert!(parse_count("1MiB").is_err());
+    }
+    
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 86_400)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_duration("1d 12h"), Ok(Duration::from_secs(36 * 3600)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(14 * 86_400)));
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,257 @@
+/// Unlinks many files below an anchored root with a few io_uring submissions
+///
+/// Removing millions of small files is dominated by syscall overhead: every file costs
+/// a stat to check it is still the inspected one and an unlink. Here a whole batch is
+/// checked with one submission of `statx` operations and removed with one of `unlinkat`,
+/// through the same directory handles and with the same checks as a removal one file at
+/// a time. Chosen automatically on Linux when the kernel allows io_uring (5.11 or newer,
+/// and not disabled by `io_uring_disabled` or a seccomp profile); elsewhere files are
+/// removed one at a time.
+pub struct BatchUnlinker {
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    ring: std::sync::Mutex<Option<io_uring::IoUring>>,
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod imp {
+    use io_uring::{opcode, types, IoUring, Probe};
+    use std::collections::HashMap;
+    use std::ffi::CString;
+    use std::fs::Metadata;
+    use std::io;
+    use std::mem::MaybeUninit;
+    use std::os::fd::{AsRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use tracing::{debug, info, warn};
+    
+    use super::BatchUnlinker;
+    use crate::anchored::AnchoredRoot;
+    
+    /// Operations submitted at once
+    const ENTRIES: u32 = 256;
+    
+    impl BatchUnlinker {
+        /// An unlinker, when this kernel supports io_uring with `statx` and `unlinkat`
+        pub fn detect() -> Option<Self> {
+            static AVAILABLE: OnceLock<bool> = OnceLock::new();
+            
+            let ring = match IoUring::new(ENTRIES) {
+                Ok(ring) => ring,
+                Err(e) => {
+                    if AVAILABLE.set(false).is_ok() {
+                        debug!("io_uring is not available, removing files one at a time: {}", e);
+                    }
+                    return None;
+                }
+            };
+            let supported = *AVAILABLE.get_or_init(|| {
+                let mut probe = Probe::new();
+                let supported = ring.submitter().register_probe(&mut probe).is_ok()
+                    && probe.is_supported(opcode::Statx::CODE)
+                    && probe.is_supported(opcode::UnlinkAt::CODE);
+                if supported {
+                    info!("Removing files in batches through io_uring");
+                } else {
+                    debug!("io_uring lacks statx or unlinkat on this kernel, removing files one at a time");
+                }
+                supported
+            });
+            supported.then(|| Self { ring: std::sync::Mutex::new(Some(ring)) })
+        }
+        
+        /// Unlink every file that is still the one its metadata was read from
+        ///
+        /// Results are in the order of `files`. Should the ring fail, the rest of the
+        /// files are removed one at a time and the ring is not used again.
+        pub fn unlink(&self, anchor: &AnchoredRoot, files: &[(&Path, &Metadata)]) -> Vec<io::Result<()>> {
+            let mut ring = self.ring.lock().unwrap_or_else(|e| e.into_inner());
+            let mut results = Vec::with_capacity(files.len());
+            for chunk in files.chunks(ENTRIES as usize) {
+                let Some(active) = ring.as_mut() else {
+                    results.extend(chunk.iter().map(|(path, expected)| anchor.remove_file(path, expected)));
+                    continue;
+                };
+                match unlink_chunk(active, anchor, chunk) {
+                    Ok(removed) => results.extend(removed),
+                    Err(e) => {
+                        warn!("io_uring failed, removing the remaining files one at a time: {}", e);
+                        // Operations may still be in flight, so the ring and what it points at are never freed
+                        std::mem::forget(ring.take());
+                        results.extend(chunk.iter().map(|(path, expected)| anchor.remove_file(path, expected)));
+                    }
+                }
+            }
+            results
+        }
+    }
+    
+    /// Check and unlink up to `ENTRIES` files; `Err` means the ring itself failed
+    fn unlink_chunk(ring: &mut IoUring, anchor: &AnchoredRoot, files: &[(&Path, &Metadata)]) -> io::Result<Vec<io::Result<()>>> {
+        let mut directories: HashMap<&Path, OwnedFd> = HashMap::new();
+        let mut targets: Vec<io::Result<(i32, CString)>> = Vec::with_capacity(files.len());
+        for (path, _) in files {
+            let Some(parent) = path.parent() else {
+                targets.push(Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} has no parent", path))));
+                continue;
+            };
+            // Files of a directory share one handle on it
+            targets.push(match (directories.get(parent), path.file_name()) {
+                (Some(dir), Some(name)) => CString::new(name.as_bytes()).map(|name| (dir.as_raw_fd(), name)).map_err(io::Error::from),
+                _ => anchor.parent(path).map(|(dir, name)| {
+                    let fd = dir.as_raw_fd();
+                    directories.insert(parent, dir);
+                    (fd, name)
+                }),
+            });
+        }
+        
+        // Refuse entries that are no longer the inspected file (e.g. now a symlink)
+        let mut stats = vec![MaybeUninit::<libc::statx>::zeroed(); files.len()];
+        let mut pending = Vec::new();
+        for (index, (target, stat)) in targets.iter().zip(stats.iter_mut()).enumerate() {
+            if let Ok((dir, name)) = target {
+                let entry = opcode::Statx::new(types::Fd(*dir), name.as_ptr(), stat.as_mut_ptr().cast::<types::statx>())
+                    .flags(libc::AT_SYMLINK_NOFOLLOW)
+                    .mask(libc::STATX_INO)
+                    .build()
+                    .user_data(index as u64);
+                pending.push(entry);
+            }
+        }
+        let checked = match complete(ring, &pending, files.len()) {
+            Ok(checked) => checked,
+            Err(e) => {
+                // The kernel may still write the buffers or read the names
+                std::mem::forget((stats, targets, directories));
+                return Err(e);
+            }
+        };
+        
+        let mut results: Vec<io::Result<()>> = Vec::with_capacity(files.len());
+        let mut pending = Vec::new();
+        for (index, target) in targets.iter().enumerate() {
+            let result = match (target, checked[index]) {
+                (Err(e), _) => Err(io::Error::new(e.kind(), e.to_string())),
+                (Ok(_), Some(result)) if result < 0 => Err(io::Error::from_raw_os_error(-result)),
+                (Ok((dir, name)), _) => {
+                    // SAFETY: the statx completed successfully, so the kernel filled the buffer
+                    let stat = unsafe { stats[index].assume_init_ref() };
+                    let (path, expected) = files[index];
+                    if libc::makedev(stat.stx_dev_major, stat.stx_dev_minor) != expected.dev() || stat.stx_ino != expected.ino() {
+                        Err(io::Error::other(format!("{:?} was replaced after it was checked", path)))
+                    } else {
+                        pending.push(opcode::UnlinkAt::new(types::Fd(*dir), name.as_ptr()).build().user_data(index as u64));
+                        Ok(())
+                    }
+                }
+            };
+            results.push(result);
+        }
+        
+        let unlinked = match complete(ring, &pending, files.len()) {
+            Ok(unlinked) => unlinked,
+            Err(e) => {
+                std::mem::forget((targets, directories));
+                return Err(e);
+            }
+        };
+        for (result, unlinked) in results.iter_mut().zip(unlinked) {
+            if let Some(code) = unlinked.filter(|code| *code < 0) {
+                *result = Err(io::Error::from_raw_os_error(-code));
+            }
+        }
+        Ok(results)
+    }
+    
+    /// Submit `entries` and wait for all of them; the result of each is stored at its user data
+    fn complete(ring: &mut IoUring, entries: &[io_uring::squeue::Entry], count: usize) -> io::Result<Vec<Option<i32>>> {
+        let mut results = vec![None; count];
+        // SAFETY: every buffer and name the entries point at outlives their completion,
+        // and none is freed if the ring fails before that
+        unsafe {
+            ring.submission()
+                .push_multiple(entries)
+                .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+        }
+        
+        let mut done = 0;
+        while done < entries.len() {
+            match ring.submit_and_wait(entries.len() - done) {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted || e.raw_os_error() == Some(libc::EBUSY) => {}
+                Err(e) => return Err(e),
+            }
+            for completion in ring.completion() {
+                results[completion.user_data() as usize] = Some(completion.result());
+                done += 1;
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+mod imp {
+    use std::fs::Metadata;
+    use std::io;
+    use std::path::Path;
+    
+    use super::BatchUnlinker;
+    use crate::anchored::AnchoredRoot;
+    
+    impl BatchUnlinker {
+        /// Batched unlinks need io_uring, so files are removed one at a time here
+        pub fn detect() -> Option<Self> {
+            None
+        }
+        
+        /// Unlink every file that is still the one its metadata was read from
+        pub fn unlink(&self, anchor: &AnchoredRoot, files: &[(&Path, &Metadata)]) -> Vec<io::Result<()>> {
+            files.iter().map(|(path, expected)| anchor.remove_file(path, expected)).collect()
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux", feature = "io-uring"))]
+mod tests {
+    use super::*;
+    use crate::anchored::AnchoredRoot;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_batch_unlinks_only_unchanged_files() {
+        // Containers often forbid io_uring; the one-at-a-time path is tested elsewhere
+        let Some(unlinker) = BatchUnlinker::detect() else {
+            return;
+        };
+        let temp_dir = TempDir::new().unwrap();
+        let cache = temp_dir.path().join("cache");
+        fs::create_dir_all(cache.join("a/__pycache__")).unwrap();
+        let paths: Vec<_> = (0..300).map(|index| cache.join(format!("a/__pycache__/m{}.pyc", index))).collect();
+        for path in &paths {
+            fs::write(path, b"x").unwrap();
+        }
+        let checked: Vec<_> = paths.iter().map(|path| fs::metadata(path).unwrap()).collect();
+        
+        // Replaced between the check and the removal
+        fs::write(cache.join("a/other"), b"y").unwrap();
+        fs::rename(cache.join("a/other"), &paths[7]).unwrap();
+        fs::remove_file(&paths[9]).unwrap();
+        
+        let anchor = AnchoredRoot::open(&cache).unwrap();
+        let files: Vec<_> = paths.iter().map(PathBuf::as_path).zip(&checked).collect();
+        let results = unlinker.unlink(&anchor, &files);
+        
+        assert_eq!(results.len(), 300);
+        assert!(results[7].is_err() && paths[7].exists());
+        assert_eq!(results[9].as_ref().unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 298);
+        assert_eq!(fs::read_dir(cache.join("a/__pycache__")).unwrap().count(), 1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,240 @@
+use notify::event::AccessKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::config::UsageConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::history::{self, History};
+use crate::models::{CachedModel, ModelRef};
+use crate::run_lock::{self, PassLock};
+
+/// How long `last_used` keeps serving the opens it read from the database
+const SNAPSHOT_TTL: Duration = Duration::from_secs(60);
+
+/// Open count and last open of each recorded path
+pub type Opens = HashMap<PathBuf, (u64, SystemTime)>;
+
+/// Last open of each recorded path
+pub type LastUsed = Arc<HashMap<PathBuf, SystemTime>>;
+
+static SNAPSHOT: Lazy<Mutex<Option<(Instant, LastUsed)>>> = Lazy::new(|| Mutex::new(None));
+
+/// How often a cached model has been opened since tracking started
+#[derive(Debug, Clone)]
+pub struct ModelUsage {
+    pub model: ModelRef,
+    pub bytes: u64,
+    pub opens: u64,
+    pub last_open: Option<SystemTime>,
+}
+
+/// Records opens of files under the cache roots in the state database
+///
+/// Opens come from inotify, so tracking only works on Linux; unlike access times it is not
+/// affected by `noatime` or `relatime` mounts. inotify does not name the opening process,
+/// so opens are dropped while any clearmodel pass holds the [`PassLock`]; its own scans and
+/// hashing are not use.
+pub struct UsageTracker {
+    roots: Vec<PathBuf>,
+    flush: Duration,
+    db: PathBuf,
+    passes: Option<PathBuf>,
+}
+
+impl UsageTracker {
+    /// Create a tracker for the given cache roots
+    pub fn new(roots: Vec<PathBuf>, config: &UsageConfig) -> Result<Self> {
+        let db = history::history_path().ok_or_else(|| ClearModelError::environment(
+            "Cannot determine the state directory for the usage database".to_string()
+        ))?;
+        Ok(Self {
+            roots,
+            flush: Duration::from_secs(config.flush_secs),
+            db,
+            passes: run_lock::pass_lock_path().ok(),
+        })
+    }
+    
+    /// Record opens until `shutdown` resolves, then write out what is pending
+    pub async fn run(self, shutdown: impl Future<Output = ()>) -> Result<()> {
+        if !cfg!(target_os = "linux") {
+            return Err(ClearModelError::environment(
+                "File opens are only reported on Linux; usage cannot be tracked on this platform".to_string()
+            ));
+        }
+        
+        let mut history = History::open(&self.db)?;
+        match history.forget_missing_opens() {
+            Ok(0) => {}
+            Ok(dropped) => debug!("Forgot recorded opens of {} removed files", dropped),
+            Err(e) => warn!("Failed to prune recorded opens: {}", e),
+        }
+        
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
+        let mut watcher: RecommendedWatcher = notify::recommended_watcher(
+            move |res: notify::Result<Event>| {
+                if let Ok(event) = res {
+                    let _ = event_tx.send(event);
+                }
+            }
+        ).map_err(|e| ClearModelError::file_operation(
+            format!("Failed to create file watcher: {}", e),
+            None
+        ))?;
+        
+        for root in &self.roots {
+            watcher.watch(root, RecursiveMode::Recursive)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to watch directory: {}", e),
+                    Some(root.clone())
+                ))?;
+            info!("Tracking file opens under {:?}", root);
+        }
+        
+        let mut pending = Opens::new();
+        let mut ticker = tokio::time::interval(self.flush);
+        tokio::pin!(shutdown);
+        
+        loop {
+            tokio::select! {
+                Some(event) = event_rx.recv() => record(&mut pending, event, self.passes.as_deref()),
+                _ = ticker.tick() => flush(&mut history, &mut pending),
+                _ = &mut shutdown => break,
+            }
+        }
+        
+        flush(&mut history, &mut pending);
+        Ok(())
+    }
+}
+
+/// Count the files `event` opened, unless a clearmodel pass holds the lock at `passes`
+fn record(pending: &mut Opens, event: Event, passes: Option<&Path>) {
+    if !matches!(event.kind, EventKind::Access(AccessKind::Open(_))) || passes.is_some_and(PassLock::held_at) {
+        return;
+    }
+    let now = SystemTime::now();
+    for path in event.paths {
+        let entry = pending.entry(path).or_insert((0, now));
+        *entry = (entry.0 + 1, now);
+    }
+}
+
+/// Write pending opens of regular files (directory listings are reported as opens too)
+fn flush(history: &mut History, pending: &mut Opens) {
+    pending.retain(|path, _| path.is_file());
+    if pending.is_empty() {
+        return;
+    }
+    
+    match history.record_opens(pending) {
+        Ok(()) => {
+            debug!("Recorded opens of {} files", pending.len());
+            pending.clear();
+        }
+        Err(e) => warn!("Failed to record file opens, retrying later: {}", e),
+    }
+}
+
+/// Every recorded path with its open count and last open; empty before anything was tracked
+pub fn recorded_opens() -> Result<Opens> {
+    match history::history_path().filter(|path| path.exists()) {
+        Some(path) => History::open(&path)?.opens(),
+        None => Ok(HashMap::new()),
+    }
+}
+
+/// Last recorded open of each tracked file, re-read from the database at most once a minute
+pub fn last_used() -> LastUsed {
+    let mut snapshot = SNAPSHOT.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((loaded, opens)) = snapshot.as_ref() {
+        if loaded.elapsed() < SNAPSHOT_TTL {
+            return opens.clone();
+        }
+    }
+    
+    let opens = recorded_opens().unwrap_or_else(|e| {
+        warn!("Recorded file opens are unavailable, falling back to file timestamps: {}", e);
+        HashMap::new()
+    });
+    let opens = Arc::new(opens.into_iter().map(|(path, (_, last_open))| (path, last_open)).collect());
+    *snapshot = Some((Instant::now(), Arc::clone(&opens)));
+    opens
+}
+
+/// Total the opens of each model's files, most used first
+pub fn by_model(models: Vec<CachedModel>, opens: &Opens) -> Vec<ModelUsage> {
+    let mut usage: Vec<ModelUsage> = models.into_iter()
+        .map(|cached| {
+            let recorded: Vec<&(u64, SystemTime)> = cached.files.iter().filter_map(|file| opens.get(file)).collect();
+            ModelUsage {
+                model: cached.model,
+                bytes: cached.bytes,
+                opens: recorded.iter().map(|(count, _)| count).sum(),
+                last_open: recorded.iter().map(|(_, last_open)| *last_open).max(),
+            }
+        })
+        .collect();
+    usage.sort_by(|a, b| b.opens.cmp(&a.opens).then(b.last_open.cmp(&a.last_open)));
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModelSource;
+    use std::time::UNIX_EPOCH;
+    
+    #[test]
+    fn test_opens_are_totalled_per_model() {
+        let model = |name: &str, files: &[&str]| CachedModel {
+            model: ModelRef { source: ModelSource::HuggingFace, name: name.to_string() },
+            bytes: 10,
+            last_access: None,
+            files: files.iter().map(PathBuf::from).collect(),
+            dirs: Vec::new(),
+        };
+        let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(secs);
+        let opens = HashMap::from([
+            (PathBuf::from("/hf/a/config.json"), (3, at(100))),
+            (PathBuf::from("/hf/a/model.bin"), (2, at(300))),
+            (PathBuf::from("/hf/b/model.bin"), (1, at(200))),
+        ]);
+        
+        let usage = by_model(vec![
+            model("unused", &["/hf/c/model.bin"]),
+            model("b", &["/hf/b/model.bin"]),
+            model("a", &["/hf/a/config.json", "/hf/a/model.bin"]),
+        ], &opens);
+        let names: Vec<&str> = usage.iter().map(|usage| usage.model.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "unused"]);
+        assert_eq!((usage[0].opens, usage[0].last_open), (5, Some(at(300))));
+        assert_eq!((usage[2].opens, usage[2].last_open), (0, None));
+    }
+    
+    #[test]
+    fn test_opens_during_a_clearmodel_pass_are_not_recorded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let passes = temp_dir.path().join("pass.lock");
+        let open = |path: &str| Event::new(EventKind::Access(AccessKind::Open(notify::event::AccessMode::Read)))
+            .add_path(PathBuf::from(path));
+        let mut pending = Opens::new();
+        
+        record(&mut pending, open("/hf/a/model.bin"), Some(&passes));
+        let pass = PassLock::acquire_at(&passes).unwrap();
+        record(&mut pending, open("/hf/a/model.bin"), Some(&passes));
+        record(&mut pending, open("/hf/b/model.bin"), Some(&passes));
+        drop(pass);
+        record(&mut pending, open("/hf/a/model.bin"), Some(&passes));
+        
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[Path::new("/hf/a/model.bin")].0, 2);
+    }
+}
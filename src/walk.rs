@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::errors::{ClearModelError, Result};
+
+/// How far and where a walk goes
+#[derive(Debug, Clone, Copy)]
+pub struct WalkOptions {
+    /// Deepest level yielded; the root's own entries are at depth 1
+    pub max_depth: usize,
+    
+    /// Descend into symlinked directories and yield symlinked files
+    pub follow_links: bool,
+    
+    /// Device of the root, when the walk has to stay on its filesystem
+    pub root_device: Option<u64>,
+}
+
+/// Walks directory trees with every subdirectory as a separate task on its own pool
+///
+/// Listing directories is mostly waiting on the disk or the file server, so reading
+/// many at once is what makes scans of network and spinning storage fast. The pool is
+/// separate from the global rayon pool that inspects and removes files: walkers block
+/// while the bounded queue to those workers is full, and must not hold the threads the
+/// workers need to drain it.
+pub struct ParallelWalker {
+    pool: rayon::ThreadPool,
+}
+
+/// A directory still to be listed
+struct Pending {
+    path: PathBuf,
+    depth: usize,
+    
+    /// `(device, inode)` of the directories above, to catch symlink loops
+    ancestors: Arc<Vec<(u64, u64)>>,
+}
+
+impl ParallelWalker {
+    /// Start a pool of `threads` walkers, shared by every cache root walked at once
+    pub fn new(threads: usize) -> Result<Self> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|index| format!("clearmodel-walk-{}", index))
+            .build()
+            .map_err(|e| ClearModelError::environment(format!("Failed to start the directory walkers: {}", e)))?;
+        Ok(Self { pool })
+    }
+    
+    /// Walk `root`, passing every regular file to `visit` until it returns `false`
+    ///
+    /// `admit(path, is_dir)` decides whether an entry is looked at at all; directories it
+    /// turns down are not entered. With `serial` the walk runs on the calling thread, one
+    /// directory at a time. Files arrive in no particular order.
+    pub fn walk<A, V>(&self, root: &Path, options: WalkOptions, serial: bool, admit: A, visit: V)
+    where
+        A: Fn(&Path, bool) -> bool + Sync,
+        V: Fn(PathBuf) -> bool + Sync,
+    {
+        if options.max_depth == 0 || !admit(root, true) {
+            return;
+        }
+        let walk = Walk { options, admit, visit, stopped: AtomicBool::new(false) };
+        let root = Pending {
+            path: root.to_path_buf(),
+            depth: 0,
+            ancestors: Arc::new(identity(root).into_iter().collect()),
+        };
+        
+        if serial {
+            let mut stack = vec![root];
+            while let Some(dir) = stack.pop() {
+                stack.extend(walk.list(dir));
+            }
+        } else {
+            self.pool.scope(|scope| walk.spawn(scope, root));
+        }
+    }
+}
+
+struct Walk<A, V> {
+    options: WalkOptions,
+    admit: A,
+    visit: V,
+    
+    /// Set once `visit` hangs up, so queued directories are dropped unread
+    stopped: AtomicBool,
+}
+
+impl<A, V> Walk<A, V>
+where
+    A: Fn(&Path, bool) -> bool + Sync,
+    V: Fn(PathBuf) -> bool + Sync,
+{
+    fn spawn<'scope>(&'scope self, scope: &rayon::Scope<'scope>, dir: Pending) {
+        scope.spawn(move |scope| {
+            for subdir in self.list(dir) {
+                self.spawn(scope, subdir);
+            }
+        });
+    }
+    
+    /// Visit the files of one directory and return its subdirectories to walk
+    fn list(&self, dir: Pending) -> Vec<Pending> {
+        if self.stopped.load(Ordering::Relaxed) {
+            return Vec::new();
+        }
+        let entries = match std::fs::read_dir(&dir.path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Error walking directory: {}: {}", dir.path.display(), e);
+                return Vec::new();
+            }
+        };
+        
+        let depth = dir.depth + 1;
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("Error walking directory: {}: {}", dir.path.display(), e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            
+            let followed = file_type.is_symlink() && self.options.follow_links;
+            let metadata = if followed || (file_type.is_dir() && self.needs_identity()) {
+                match std::fs::metadata(&path) {
+                    Ok(metadata) => Some(metadata),
+                    Err(e) => {
+                        warn!("Error walking directory: {}: {}", path.display(), e);
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+            let (is_dir, is_file) = match &metadata {
+                Some(metadata) if followed => (metadata.is_dir(), metadata.is_file()),
+                _ => (file_type.is_dir(), file_type.is_file()),
+            };
+            
+            if !(self.admit)(&path, is_dir) {
+                continue;
+            }
+            // A followed link may lead off the root's filesystem
+            let off_filesystem = self.options.root_device.is_some()
+                && metadata.as_ref().and_then(device).is_some_and(|dev| Some(dev) != self.options.root_device);
+            
+            if is_file {
+                if followed && off_filesystem {
+                    debug!("Skipping {:?}, which links to another filesystem", path);
+                    continue;
+                }
+                if !(self.visit)(path) {
+                    self.stopped.store(true, Ordering::Relaxed);
+                    return Vec::new();
+                }
+            } else if is_dir && depth < self.options.max_depth && !off_filesystem {
+                let ancestors = match metadata.as_ref().and_then(identity_of) {
+                    Some(id) if self.options.follow_links => {
+                        if dir.ancestors.contains(&id) {
+                            warn!("Error walking directory: File system loop found: {} points to an ancestor", path.display());
+                            continue;
+                        }
+                        let mut ancestors = Vec::clone(&dir.ancestors);
+                        ancestors.push(id);
+                        Arc::new(ancestors)
+                    }
+                    _ => Arc::clone(&dir.ancestors),
+                };
+                subdirs.push(Pending { path, depth, ancestors });
+            }
+        }
+        subdirs
+    }
+    
+    /// Whether directories have to be stat'ed: for loops when following links, or their device
+    fn needs_identity(&self) -> bool {
+        self.options.follow_links || self.options.root_device.is_some()
+    }
+}
+
+fn identity(path: &Path) -> Option<(u64, u64)> {
+    std::fs::metadata(path).ok().as_ref().and_then(identity_of)
+}
+
+#[cfg(unix)]
+fn identity_of(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity_of(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn device(metadata: &std::fs::Metadata) -> Option<u64> {
+    identity_of(metadata).map(|(dev, _)| dev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_walks_every_admitted_file_within_the_depth_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for dir in ["a/b/c", "a/skip", "d"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["top.bin", "a/one.bin", "a/b/two.bin", "a/b/c/deep.bin", "a/skip/no.bin", "d/three.bin"] {
+            fs::write(root.join(file), b"x").unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("a"), root.join("a/b/loop")).unwrap();
+        
+        let walker = ParallelWalker::new(4).unwrap();
+        for (serial, follow_links) in [(false, false), (true, false), (false, true)] {
+            let found = Mutex::new(Vec::new());
+            let options = WalkOptions { max_depth: 3, follow_links, root_device: None };
+            walker.walk(
+                root,
+                options,
+                serial,
+                |path, is_dir| !(is_dir && path.ends_with("skip")),
+                |path| {
+                    found.lock().unwrap().push(path.strip_prefix(root).unwrap().to_path_buf());
+                    true
+                },
+            );
+            
+            let mut found = found.into_inner().unwrap();
+            found.sort();
+            let expected: Vec<PathBuf> = ["a/b/two.bin", "a/one.bin", "d/three.bin", "top.bin"].iter().map(PathBuf::from).collect();
+            assert_eq!(found, expected, "serial: {}, follow_links: {}", serial, follow_links);
+        }
+    }
+}
\ No newline at end of file
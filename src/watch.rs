@@ -0,0 +1,185 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use crate::config::WatchConfig;
+use crate::errors::{ClearModelError, Result};
+
+/// Extensions used by downloaders for files that are still being written
+const PARTIAL_DOWNLOAD_EXTENSIONS: [&str; 4] = ["incomplete", "part", "tmp", "lock"];
+
+/// A file that stopped receiving writes inside a watched cache root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedDownload {
+    pub root: PathBuf,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// Watches cache roots and reports large downloads once their writes have quiesced
+pub struct DownloadWatcher {
+    roots: Vec<PathBuf>,
+    quiesce: Duration,
+    min_size: u64,
+}
+
+impl DownloadWatcher {
+    /// Create a watcher for the given cache roots
+    pub fn new(roots: Vec<PathBuf>, config: &WatchConfig) -> Self {
+        Self {
+            roots,
+            quiesce: Duration::from_secs(config.quiesce_secs),
+            min_size: config.min_download_mb * 1_048_576,
+        }
+    }
+    
+    /// Watch the roots until the receiving side of `tx` is dropped
+    pub async fn run(self, tx: mpsc::Sender<CompletedDownload>) -> Result<()> {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
+        
+        let mut watcher: RecommendedWatcher = notify::recommended_watcher(
+            move |res: notify::Result<Event>| {
+                if let Ok(event) = res {
+                    let _ = event_tx.send(event);
+                }
+            }
+        ).map_err(|e| ClearModelError::file_operation(
+            format!("Failed to create file watcher: {}", e),
+            None
+        ))?;
+        
+        for root in &self.roots {
+            watcher.watch(root, RecursiveMode::Recursive)
+                .map_err(|e| ClearModelError::file_operation(
+                    format!("Failed to watch directory: {}", e),
+                    Some(root.clone())
+                ))?;
+            info!("Watching cache directory: {:?}", root);
+        }
+        
+        let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+        let mut ticker = tokio::time::interval((self.quiesce / 2).max(Duration::from_millis(100)));
+        
+        loop {
+            tokio::select! {
+                Some(event) = event_rx.recv() => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        let now = Instant::now();
+                        for path in event.paths {
+                            pending.insert(path, now);
+                        }
+                    }
+                }
+                _ = ticker.tick() => {
+                    for download in self.take_settled(&mut pending, Instant::now()) {
+                        if tx.send(download).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Remove paths that have been quiet for the quiesce period and return the completed downloads
+    fn take_settled(
+        &self,
+        pending: &mut HashMap<PathBuf, Instant>,
+        now: Instant,
+    ) -> Vec<CompletedDownload> {
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, last_write)| now.duration_since(**last_write) >= self.quiesce)
+            .map(|(path, _)| path.clone())
+            .collect();
+        
+        let mut completed = Vec::new();
+        for path in settled {
+            pending.remove(&path);
+            
+            if Self::is_partial_download(&path) {
+                continue;
+            }
+            
+            let size = match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_file() => metadata.len(),
+                _ => continue, // Renamed away, deleted, or a directory
+            };
+            
+            if size < self.min_size {
+                continue;
+            }
+            
+            if let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) {
+                debug!("Download settled: {:?} ({} bytes)", path, size);
+                completed.push(CompletedDownload {
+                    root: root.clone(),
+                    path,
+                    size,
+                });
+            }
+        }
+        
+        completed
+    }
+    
+    /// Check whether a path is a downloader's in-progress temporary file
+    fn is_partial_download(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| PARTIAL_DOWNLOAD_EXTENSIONS.contains(&ext))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    
+    fn watcher_for(root: &Path) -> DownloadWatcher {
+        DownloadWatcher::new(
+            vec![root.to_path_buf()],
+            &WatchConfig { quiesce_secs: 5, min_download_mb: 0 },
+        )
+    }
+    
+    #[test]
+    fn test_settled_download_is_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let blob = temp_dir.path().join("model.safetensors");
+        fs::write(&blob, b"weights").unwrap();
+        
+        let watcher = watcher_for(temp_dir.path());
+        let start = Instant::now();
+        let mut pending = HashMap::from([(blob.clone(), start)]);
+        
+        // Still being written
+        assert!(watcher.take_settled(&mut pending, start + Duration::from_secs(1)).is_empty());
+        assert_eq!(pending.len(), 1);
+        
+        let completed = watcher.take_settled(&mut pending, start + Duration::from_secs(6));
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].path, blob);
+        assert_eq!(completed[0].size, 7);
+        assert!(pending.is_empty());
+    }
+    
+    #[test]
+    fn test_partial_downloads_are_ignored() {
+        let temp_dir = TempDir::new().unwrap();
+        let partial = temp_dir.path().join("blob.incomplete");
+        fs::write(&partial, b"half").unwrap();
+        
+        let watcher = watcher_for(temp_dir.path());
+        let start = Instant::now();
+        let mut pending = HashMap::from([(partial, start)]);
+        
+        assert!(watcher.take_settled(&mut pending, start + Duration::from_secs(6)).is_empty());
+        assert!(pending.is_empty());
+    }
+}
\ No newline at end of file
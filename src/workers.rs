@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+use crate::config::ConcurrencyConfig;
+use crate::errors::{ClearModelError, Result};
+use crate::netfs;
+
+/// Thread pools that inspect and remove files, chosen by the target being cleaned
+///
+/// Batches run on `concurrency.delete_workers` threads (the global rayon pool, one per
+/// CPU, when unset), or on the target's own pool while a target listed under
+/// `concurrency.targets` is cleaned, so deletions on NFS can go one at a time while
+/// local NVMe caches use every core. Roots on throttled network filesystems always
+/// share the single network worker.
+pub struct DeleteWorkers {
+    default: Option<Arc<rayon::ThreadPool>>,
+    targets: BTreeMap<String, Arc<rayon::ThreadPool>>,
+    
+    /// Pool of the target being cleaned; `None` for the default
+    current: Mutex<Option<Arc<rayon::ThreadPool>>>,
+}
+
+impl DeleteWorkers {
+    /// Start the pools `concurrency` (already validated) asks for
+    pub fn new(concurrency: &ConcurrencyConfig) -> Result<Self> {
+        let default = concurrency.delete_workers
+            .map(|threads| pool("clearmodel-delete", threads))
+            .transpose()?;
+        let targets = concurrency.targets.iter()
+            .map(|(name, threads)| Ok((name.clone(), pool(&format!("clearmodel-{}", name), *threads)?)))
+            .collect::<Result<_>>()?;
+        
+        Ok(Self { default, targets, current: Mutex::new(None) })
+    }
+    
+    /// Run the batches from now on with the workers of `target`
+    pub fn start_target(&self, target: &str) {
+        let pool = self.targets.get(target).cloned();
+        if let Some(pool) = &pool {
+            debug!("Cleaning {} with {} deletion workers", target, pool.current_num_threads());
+        }
+        *self.current.lock().unwrap_or_else(|e| e.into_inner()) = pool;
+    }
+    
+    /// Run parallel (rayon) work on the current target's workers, or on the single
+    /// network worker when `serial` is set
+    pub fn install<R: Send>(&self, serial: bool, work: impl FnOnce() -> R + Send) -> R {
+        if serial {
+            return netfs::install(true, work);
+        }
+        let current = self.current.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        match current.as_ref().or(self.default.as_ref()) {
+            Some(pool) => pool.install(work),
+            None => work(),
+        }
+    }
+}
+
+fn pool(name: &str, threads: usize) -> Result<Arc<rayon::ThreadPool>> {
+    let prefix = name.to_string();
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(move |index| format!("{}-{}", prefix, index))
+        .build()
+        .map(Arc::new)
+        .map_err(|e| ClearModelError::environment(format!("Failed to start the deletion workers: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+    
+    #[test]
+    fn test_targets_run_on_their_own_workers() {
+        let concurrency = ConcurrencyConfig {
+            delete_workers: Some(3),
+            targets: BTreeMap::from([("nfs-models".to_string(), 1)]),
+            ..ConcurrencyConfig::default()
+        };
+        let workers = DeleteWorkers::new(&concurrency).unwrap();
+        let threads = || workers.install(false, rayon::current_num_threads);
+        
+        assert_eq!(threads(), 3);
+        workers.start_target("nfs-models");
+        assert_eq!(threads(), 1);
+        // Work in a batch stays on the target's pool
+        let names: Vec<_> = workers.install(false, || (0..8).into_par_iter().map(|_| std::thread::current().name().map(str::to_string)).collect());
+        assert!(names.iter().all(|name| name.as_deref() == Some("clearmodel-nfs-models-0")));
+        workers.start_target("pip");
+        assert_eq!(threads(), 3);
+    }
+}
\ No newline at end of file